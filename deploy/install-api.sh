@@ -58,5 +58,5 @@ echo ""
 echo "Next steps:"
 echo "1. Edit /etc/hyle/env and add your OPENROUTER_API_KEY"
 echo "2. Restart: sudo systemctl restart hyle-api"
-echo "3. Test: curl https://hyle.hyperstitious.org/health"
+echo "3. Test: curl https://hyle.hyperstitious.org/health  (503 + JSON report if degraded)"
 echo ""
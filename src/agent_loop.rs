@@ -0,0 +1,800 @@
+//! Agentic loop progression for the TUI
+//!
+//! The TUI learns about the loop through interleaved channel messages: a
+//! stream finishing (`Done`), background tool execution finishing
+//! (`ToolsComplete`) and the continuation it schedules (`ContinueLoop`).
+//! `AgentLoop` is the only place that counts iterations and decides whether
+//! a message should dispatch work, so a late or duplicated message can never
+//! start a second continuation or skip a count.
+//!
+//! `PlanGate` holds plan-first mode: mutating tool calls are refused until
+//! the user has approved a plan. `FocusGate` holds the `/focus` working set:
+//! edits to files outside it are refused until the user allows them.
+
+use crate::agent::{
+    format_answer, format_refusal, is_mutating, plan_steps, ParsedToolCall, ASK_USER_TOOL,
+    PLAN_TOOL,
+};
+
+/// Something the TUI loop observed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopEvent {
+    /// A fresh user prompt started generating
+    Prompt,
+    /// The response stream finished (also accepted while idle, for
+    /// generations started outside the loop)
+    Done { tool_calls: bool },
+    /// Background tool execution finished
+    ToolsComplete,
+    /// A scheduled continuation arrived
+    ContinueLoop { iteration: u8 },
+    /// Error, cancel or /clear: abandon the loop
+    Reset,
+}
+
+/// What the TUI should do in response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopStep {
+    /// Execute the tool calls in the response
+    RunTools,
+    /// Schedule the continuation for this iteration
+    ScheduleContinue(u8),
+    /// Send the continuation prompt for this iteration
+    Continue(u8),
+    /// Response had no tool calls; the loop is over
+    Finished,
+    /// Iteration limit hit; pause for input
+    MaxIterations,
+    /// Stale or duplicate message; do nothing
+    Ignore,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Idle,
+    Generating,
+    RunningTools,
+    Continuing(u8),
+}
+
+#[derive(Debug, Clone)]
+pub struct AgentLoop {
+    iteration: u8,
+    max_iterations: u8,
+    phase: Phase,
+}
+
+impl AgentLoop {
+    pub fn new(max_iterations: u8) -> Self {
+        Self {
+            iteration: 0,
+            max_iterations,
+            phase: Phase::Idle,
+        }
+    }
+
+    /// Continuations sent since the last prompt
+    pub fn iteration(&self) -> u8 {
+        self.iteration
+    }
+
+    pub fn max_iterations(&self) -> u8 {
+        self.max_iterations
+    }
+
+    /// Apply an event and return the single step it calls for
+    pub fn advance(&mut self, event: LoopEvent) -> LoopStep {
+        match (event, self.phase) {
+            (LoopEvent::Prompt, _) => {
+                self.iteration = 0;
+                self.phase = Phase::Generating;
+                LoopStep::Ignore
+            }
+            (LoopEvent::Reset, _) => {
+                self.iteration = 0;
+                self.phase = Phase::Idle;
+                LoopStep::Ignore
+            }
+            (LoopEvent::Done { tool_calls: true }, Phase::Idle | Phase::Generating) => {
+                self.phase = Phase::RunningTools;
+                LoopStep::RunTools
+            }
+            (LoopEvent::Done { tool_calls: false }, Phase::Idle | Phase::Generating) => {
+                self.iteration = 0;
+                self.phase = Phase::Idle;
+                LoopStep::Finished
+            }
+            (LoopEvent::ToolsComplete, Phase::RunningTools) => {
+                if self.iteration >= self.max_iterations {
+                    self.iteration = 0;
+                    self.phase = Phase::Idle;
+                    return LoopStep::MaxIterations;
+                }
+                self.iteration += 1;
+                self.phase = Phase::Continuing(self.iteration);
+                LoopStep::ScheduleContinue(self.iteration)
+            }
+            (LoopEvent::ContinueLoop { iteration }, Phase::Continuing(expected))
+                if iteration == expected =>
+            {
+                self.phase = Phase::Generating;
+                LoopStep::Continue(iteration)
+            }
+            _ => LoopStep::Ignore,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ASK USER
+// ═══════════════════════════════════════════════════════════════
+
+/// An `ask_user` question (or a plan awaiting approval) waiting on the
+/// user's answer
+///
+/// The other tool calls from the same response keep running in the
+/// background; the loop resumes once both their results and the answer are
+/// in, whichever arrives last.
+#[derive(Debug, Clone)]
+pub struct PendingQuestion {
+    pub question: String,
+    /// Tool the answer goes back to the model as
+    pub tool: &'static str,
+    feedback: Option<String>,
+    answer: Option<String>,
+}
+
+impl PendingQuestion {
+    pub fn new(question: &str) -> Self {
+        Self {
+            question: question.to_string(),
+            tool: ASK_USER_TOOL,
+            feedback: None,
+            answer: None,
+        }
+    }
+
+    /// Approval of the plan just proposed
+    pub fn plan_approval() -> Self {
+        Self {
+            tool: PLAN_TOOL,
+            ..Self::new("Approve this plan? (y/n, or say what to change)")
+        }
+    }
+
+    /// Approval of edits outside the focus set
+    pub fn focus_approval(paths: &[String]) -> Self {
+        Self {
+            tool: FOCUS_TOOL,
+            ..Self::new(&format!(
+                "Allow edits outside the focus set to {}? (y/n)",
+                paths.join(", ")
+            ))
+        }
+    }
+
+    pub fn is_answered(&self) -> bool {
+        self.answer.is_some()
+    }
+
+    /// Record the other tools' results; the combined feedback once answered
+    pub fn tools_done(&mut self, feedback: String) -> Option<String> {
+        self.feedback = Some(feedback);
+        self.resume()
+    }
+
+    /// Record the user's answer; the combined feedback once tools are done
+    pub fn answer(&mut self, answer: &str) -> Option<String> {
+        self.answer = Some(answer.to_string());
+        self.resume()
+    }
+
+    fn resume(&self) -> Option<String> {
+        match (&self.feedback, &self.answer) {
+            (Some(feedback), Some(answer)) => {
+                Some(format!("{}{}", feedback, format_answer(self.tool, answer)))
+            }
+            _ => None,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// PLAN FIRST
+// ═══════════════════════════════════════════════════════════════
+
+/// Calls from one response, sorted out by `PlanGate::admit`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanRound {
+    /// Calls that may run
+    pub calls: Vec<ParsedToolCall>,
+    /// Result blocks for the plan and for refused calls
+    pub feedback: String,
+    /// A plan was proposed and waits on the user
+    pub awaiting_approval: bool,
+}
+
+/// Plan-first mode: the model proposes steps with the `plan` tool, and no
+/// mutating call runs until the user approves them
+///
+/// Disabled, plans are recorded and approved as they arrive so the Plans
+/// view still follows them. Steps are ticked off one per tool round that
+/// changed something.
+#[derive(Debug, Clone, Default)]
+pub struct PlanGate {
+    pub enabled: bool,
+    steps: Vec<String>,
+    approved: bool,
+    done: usize,
+    // The round in flight ran a mutating call
+    mutated: bool,
+}
+
+impl PlanGate {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..Default::default()
+        }
+    }
+
+    pub fn steps(&self) -> &[String] {
+        &self.steps
+    }
+
+    pub fn is_approved(&self) -> bool {
+        self.approved
+    }
+
+    /// Steps completed so far
+    pub fn done(&self) -> usize {
+        self.done
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.approved && self.done >= self.steps.len()
+    }
+
+    /// Drop the plan (a new prompt is a new task)
+    pub fn clear(&mut self) {
+        *self = Self::new(self.enabled);
+    }
+
+    /// A new plan replaces the current one and needs approval again
+    pub fn propose(&mut self, steps: Vec<String>) {
+        self.steps = steps;
+        self.approved = false;
+        self.done = 0;
+    }
+
+    /// Approve or reject the proposed plan from the user's reply; the reply
+    /// to send back as the plan's result
+    pub fn decide(&mut self, reply: &str) -> String {
+        let reply = reply.trim();
+        if matches!(
+            reply.to_lowercase().as_str(),
+            "y" | "yes" | "ok" | "approve" | "approved"
+        ) {
+            self.approved = true;
+            return "approved: go ahead, starting with step 1".into();
+        }
+        self.steps.clear();
+        self.approved = false;
+        if reply.is_empty() || reply.eq_ignore_ascii_case("n") || reply.eq_ignore_ascii_case("no") {
+            "rejected: propose a different plan".into()
+        } else {
+            format!("rejected: {}; propose a revised plan", reply)
+        }
+    }
+
+    /// Why `call` may not run yet, if it may not
+    pub fn check(&self, call: &ParsedToolCall) -> Result<(), String> {
+        if !self.enabled || self.approved {
+            return Ok(());
+        }
+        if call.name == ASK_USER_TOOL && !self.steps.is_empty() {
+            return Err("a plan is waiting on the user; ask once it is settled".into());
+        }
+        if !is_mutating(call) {
+            return Ok(());
+        }
+        Err(if self.steps.is_empty() {
+            "plan-first mode: propose a plan with plan(steps=[...]) before changing anything".into()
+        } else {
+            "plan-first mode: the plan is not approved yet".into()
+        })
+    }
+
+    /// Record any plan in `calls` and refuse what may not run yet
+    ///
+    /// With `trust` (or the mode off) a plan is approved as it arrives, so
+    /// calls after it in the same response go ahead.
+    pub fn admit(&mut self, calls: Vec<ParsedToolCall>, trust: bool) -> PlanRound {
+        let mut round = PlanRound {
+            calls: vec![],
+            feedback: String::new(),
+            awaiting_approval: false,
+        };
+        for call in calls {
+            if call.name == PLAN_TOOL {
+                let steps = plan_steps(&call.args);
+                if steps.is_empty() {
+                    round.feedback.push_str(&format_refusal(
+                        &call,
+                        "a plan needs steps, e.g. plan(steps=[\"...\", \"...\"])",
+                    ));
+                    continue;
+                }
+                self.propose(steps);
+                if self.enabled && !trust {
+                    round.awaiting_approval = true;
+                } else {
+                    self.approved = true;
+                    round
+                        .feedback
+                        .push_str(&format_answer(PLAN_TOOL, "approved: go ahead"));
+                }
+                continue;
+            }
+            match self.check(&call) {
+                Ok(()) => round.calls.push(call),
+                Err(reason) => round.feedback.push_str(&format_refusal(&call, &reason)),
+            }
+        }
+        self.mutated = round.calls.iter().any(is_mutating);
+        round
+    }
+
+    /// A tool round finished: tick off the next step if it changed anything
+    pub fn round_complete(&mut self) {
+        if std::mem::take(&mut self.mutated) && self.approved && self.done < self.steps.len() {
+            self.done += 1;
+        }
+    }
+
+    /// Numbered steps, done ones checked
+    pub fn render(&self) -> Vec<String> {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let mark = if i < self.done { "✓" } else { "○" };
+                format!("{} {}. {}", mark, i + 1, step)
+            })
+            .collect()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// FOCUS
+// ═══════════════════════════════════════════════════════════════
+
+/// Tool name the user's answer about out-of-focus edits goes back as
+pub const FOCUS_TOOL: &str = "focus";
+
+/// Calls from one response, sorted out by `FocusGate::admit`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FocusRound {
+    /// Calls that may run
+    pub calls: Vec<ParsedToolCall>,
+    /// Error blocks for refused edits
+    pub feedback: String,
+    /// Paths outside the focus set the model tried to edit
+    pub outside: Vec<String>,
+}
+
+/// The `/focus` working set: edits stay within these files (or directories,
+/// kept with a trailing `/`) unless the user allows more
+#[derive(Debug, Clone, Default)]
+pub struct FocusGate {
+    files: Vec<String>,
+    // Refused paths waiting on the user's answer
+    pending: Vec<String>,
+}
+
+/// Paths a call would change; empty for calls that don't edit files
+fn edited_paths(call: &ParsedToolCall) -> Vec<String> {
+    let keys: &[&str] = match call.name.as_str() {
+        "write" | "patch" | "diff" | "delete" => &["path"],
+        "move" => &["from", "to"],
+        _ => &[],
+    };
+    keys.iter()
+        .filter_map(|k| call.args.get(*k).and_then(|v| v.as_str()))
+        .map(String::from)
+        .collect()
+}
+
+impl FocusGate {
+    /// Gate on `files`; an empty list turns the gate off
+    pub fn new(files: &[String]) -> Self {
+        Self {
+            files: files.to_vec(),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.files.is_empty()
+    }
+
+    /// Whether `path` is in the set (inside a focused directory counts)
+    pub fn contains(&self, path: &str) -> bool {
+        let path = path.trim_start_matches("./");
+        self.files.iter().any(|f| {
+            let f = f.trim_start_matches("./");
+            path == f || (f.ends_with('/') && path.starts_with(f))
+        })
+    }
+
+    /// Refuse edits outside the set; everything else goes ahead
+    pub fn admit(&mut self, calls: Vec<ParsedToolCall>) -> FocusRound {
+        let mut round = FocusRound {
+            calls: vec![],
+            feedback: String::new(),
+            outside: vec![],
+        };
+        for call in calls {
+            let outside: Vec<String> = edited_paths(&call)
+                .into_iter()
+                .filter(|p| self.is_active() && !self.contains(p))
+                .collect();
+            if outside.is_empty() {
+                round.calls.push(call);
+                continue;
+            }
+            round.feedback.push_str(&format_refusal(
+                &call,
+                &format!(
+                    "{} is outside the focus set ({}); the user has been asked to allow it",
+                    outside.join(", "),
+                    self.files.join(", ")
+                ),
+            ));
+            for path in outside {
+                if !round.outside.contains(&path) {
+                    round.outside.push(path);
+                }
+            }
+        }
+        self.pending = round.outside.clone();
+        round
+    }
+
+    /// Allow or refuse the pending paths from the user's reply; the reply to
+    /// send back as the focus result
+    pub fn decide(&mut self, reply: &str) -> String {
+        let pending = std::mem::take(&mut self.pending);
+        let reply = reply.trim();
+        if matches!(
+            reply.to_lowercase().as_str(),
+            "y" | "yes" | "ok" | "allow" | "allowed"
+        ) {
+            let added = pending.join(", ");
+            self.files.extend(pending);
+            return format!("allowed: {} added to the focus set; retry the edit", added);
+        }
+        if reply.is_empty() || reply.eq_ignore_ascii_case("n") || reply.eq_ignore_ascii_case("no") {
+            format!("refused: keep edits within {}", self.files.join(", "))
+        } else {
+            format!(
+                "refused: {}; keep edits within {}",
+                reply,
+                self.files.join(", ")
+            )
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TESTS
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One full tool round: Done with tools, tools finish, continuation arrives
+    fn round(l: &mut AgentLoop) -> Vec<LoopStep> {
+        let mut steps = vec![
+            l.advance(LoopEvent::Done { tool_calls: true }),
+            l.advance(LoopEvent::ToolsComplete),
+        ];
+        if let LoopStep::ScheduleContinue(i) = steps[1] {
+            steps.push(l.advance(LoopEvent::ContinueLoop { iteration: i }));
+        }
+        steps
+    }
+
+    #[test]
+    fn test_rounds_count_once_each() {
+        let mut l = AgentLoop::new(10);
+        l.advance(LoopEvent::Prompt);
+        for i in 1..=3 {
+            assert_eq!(
+                round(&mut l),
+                vec![
+                    LoopStep::RunTools,
+                    LoopStep::ScheduleContinue(i),
+                    LoopStep::Continue(i)
+                ]
+            );
+            assert_eq!(l.iteration(), i);
+        }
+        assert_eq!(
+            l.advance(LoopEvent::Done { tool_calls: false }),
+            LoopStep::Finished
+        );
+        assert_eq!(l.iteration(), 0);
+    }
+
+    #[test]
+    fn test_duplicate_messages_ignored() {
+        let mut l = AgentLoop::new(10);
+        l.advance(LoopEvent::Prompt);
+        assert_eq!(
+            l.advance(LoopEvent::Done { tool_calls: true }),
+            LoopStep::RunTools
+        );
+        // A second Done while tools run must not execute them again
+        assert_eq!(
+            l.advance(LoopEvent::Done { tool_calls: true }),
+            LoopStep::Ignore
+        );
+        assert_eq!(
+            l.advance(LoopEvent::ToolsComplete),
+            LoopStep::ScheduleContinue(1)
+        );
+        assert_eq!(l.advance(LoopEvent::ToolsComplete), LoopStep::Ignore);
+        assert_eq!(
+            l.advance(LoopEvent::ContinueLoop { iteration: 1 }),
+            LoopStep::Continue(1)
+        );
+        // Only one continuation per step
+        assert_eq!(
+            l.advance(LoopEvent::ContinueLoop { iteration: 1 }),
+            LoopStep::Ignore
+        );
+        assert_eq!(l.iteration(), 1);
+    }
+
+    #[test]
+    fn test_stale_continuation_after_new_prompt() {
+        let mut l = AgentLoop::new(10);
+        l.advance(LoopEvent::Prompt);
+        l.advance(LoopEvent::Done { tool_calls: true });
+        assert_eq!(
+            l.advance(LoopEvent::ToolsComplete),
+            LoopStep::ScheduleContinue(1)
+        );
+        // User sends a new prompt before the continuation is delivered
+        l.advance(LoopEvent::Prompt);
+        assert_eq!(
+            l.advance(LoopEvent::ContinueLoop { iteration: 1 }),
+            LoopStep::Ignore
+        );
+        assert_eq!(l.iteration(), 0);
+
+        l.advance(LoopEvent::Reset);
+        assert_eq!(l.advance(LoopEvent::ToolsComplete), LoopStep::Ignore);
+    }
+
+    #[test]
+    fn test_max_iterations() {
+        let mut l = AgentLoop::new(2);
+        l.advance(LoopEvent::Prompt);
+        round(&mut l);
+        round(&mut l);
+        assert_eq!(l.iteration(), 2);
+        assert_eq!(
+            round(&mut l),
+            vec![LoopStep::RunTools, LoopStep::MaxIterations]
+        );
+        assert_eq!(l.iteration(), 0);
+        assert_eq!(
+            l.advance(LoopEvent::ContinueLoop { iteration: 3 }),
+            LoopStep::Ignore
+        );
+    }
+
+    #[test]
+    fn test_question_resumes_after_tools_and_answer() {
+        let mut l = AgentLoop::new(10);
+        l.advance(LoopEvent::Prompt);
+        assert_eq!(
+            l.advance(LoopEvent::Done { tool_calls: true }),
+            LoopStep::RunTools
+        );
+
+        // Tools finish first: nothing to send until the user answers
+        let mut q = PendingQuestion::new("Which database?");
+        let read = "\n<tool_result tool=\"read\" status=\"ok\" bytes=\"2\">\nok\n</tool_result>\n";
+        assert_eq!(q.tools_done(read.into()), None);
+        assert!(!q.is_answered());
+        let feedback = q.answer("postgres").unwrap();
+        assert!(feedback.starts_with(read));
+        assert!(feedback.ends_with("status=\"ok\" bytes=\"8\">\npostgres\n</tool_result>\n"));
+
+        // The answer is what moves the loop on, exactly once
+        assert_eq!(
+            l.advance(LoopEvent::ToolsComplete),
+            LoopStep::ScheduleContinue(1)
+        );
+        assert_eq!(
+            l.advance(LoopEvent::ContinueLoop { iteration: 1 }),
+            LoopStep::Continue(1)
+        );
+    }
+
+    fn call(name: &str, args: serde_json::Value) -> ParsedToolCall {
+        ParsedToolCall {
+            name: name.into(),
+            args,
+        }
+    }
+
+    #[test]
+    fn test_plan_first_refuses_mutation_without_approved_plan() {
+        let write = || call("write", serde_json::json!({"path": "a.rs", "content": "x"}));
+        let read = || call("read", serde_json::json!({"path": "a.rs"}));
+        let plan = || {
+            call(
+                PLAN_TOOL,
+                serde_json::json!({"steps": ["Read a.rs", "Fix it"]}),
+            )
+        };
+        let mut gate = PlanGate::new(true);
+
+        // No plan: reads run, writes are refused with an error block
+        let round = gate.admit(
+            vec![read(), write(), call("bash", serde_json::json!({}))],
+            false,
+        );
+        assert_eq!(round.calls, vec![read()]);
+        assert_eq!(round.feedback.matches("status=\"error\"").count(), 2);
+        assert!(round.feedback.contains("propose a plan"));
+        assert!(!round.awaiting_approval);
+
+        // Proposed alongside a write: the write still waits for approval
+        let round = gate.admit(vec![plan(), write()], false);
+        assert!(round.awaiting_approval);
+        assert!(round.calls.is_empty());
+        assert!(round.feedback.contains("not approved yet"));
+        assert!(gate
+            .check(&call(ASK_USER_TOOL, serde_json::json!({})))
+            .is_err());
+
+        // Rejected: back to no plan at all
+        assert!(gate
+            .decide("no, test first")
+            .starts_with("rejected: no, test first"));
+        assert!(gate.steps().is_empty());
+        assert!(gate.check(&write()).is_err());
+
+        gate.admit(vec![plan()], false);
+        assert_eq!(
+            gate.decide(" y "),
+            "approved: go ahead, starting with step 1"
+        );
+        assert!(gate.check(&write()).is_ok());
+        assert_eq!(gate.admit(vec![write()], false).calls.len(), 1);
+
+        // A new task needs a new plan
+        gate.clear();
+        assert!(gate.enabled);
+        assert!(gate.check(&write()).is_err());
+
+        // Trust approves the plan as it arrives; calls after it go ahead
+        let round = gate.admit(vec![plan(), write()], true);
+        assert!(!round.awaiting_approval);
+        assert_eq!(round.calls, vec![write()]);
+        assert!(round.feedback.contains("approved: go ahead"));
+
+        // Off: nothing is refused, and an empty plan is an error
+        let mut off = PlanGate::new(false);
+        let round = off.admit(vec![write(), call(PLAN_TOOL, serde_json::json!({}))], false);
+        assert_eq!(round.calls, vec![write()]);
+        assert!(round.feedback.contains("a plan needs steps"));
+    }
+
+    #[test]
+    fn test_plan_steps_marked_done_as_loop_advances() {
+        let mut gate = PlanGate::new(true);
+        let plan = call(
+            PLAN_TOOL,
+            serde_json::json!({"steps": ["Read", "Patch", "Test"]}),
+        );
+        gate.admit(vec![plan], false);
+        gate.decide("yes");
+
+        // Read-only rounds don't count as progress
+        gate.admit(vec![call("read", serde_json::json!({}))], false);
+        gate.round_complete();
+        assert_eq!(gate.done(), 0);
+
+        for expected in 1..=3 {
+            gate.admit(vec![call("patch", serde_json::json!({}))], false);
+            gate.round_complete();
+            assert_eq!(gate.done(), expected);
+        }
+        // Past the last step it stays finished
+        gate.admit(vec![call("bash", serde_json::json!({}))], false);
+        gate.round_complete();
+        assert_eq!(gate.done(), 3);
+        assert!(gate.is_finished());
+        assert_eq!(gate.render()[2], "✓ 3. Test");
+    }
+
+    #[test]
+    fn test_out_of_focus_edits_need_confirmation() {
+        let write = |path: &str| call("write", serde_json::json!({"path": path, "content": "x"}));
+        let read = call("read", serde_json::json!({"path": "src/other.rs"}));
+        let mut gate = FocusGate::new(&["src/auth.rs".into(), "tests/".into()]);
+
+        // Inside the set, reads and shell go ahead; outside edits are refused
+        let round = gate.admit(vec![
+            write("src/auth.rs"),
+            write("./tests/login.rs"),
+            read.clone(),
+            call("bash", serde_json::json!({"command": "cargo test"})),
+            write("src/main.rs"),
+            call(
+                "move",
+                serde_json::json!({"from": "src/auth.rs", "to": "src/login.rs"}),
+            ),
+        ]);
+        assert_eq!(round.calls.len(), 4);
+        assert_eq!(round.outside, vec!["src/main.rs", "src/login.rs"]);
+        assert_eq!(round.feedback.matches("status=\"error\"").count(), 2);
+        assert!(round
+            .feedback
+            .contains("src/main.rs is outside the focus set"));
+
+        let mut q = PendingQuestion::focus_approval(&round.outside);
+        assert!(q.question.contains("src/main.rs, src/login.rs"));
+
+        // Refused: the set is unchanged
+        assert_eq!(
+            gate.decide("n"),
+            "refused: keep edits within src/auth.rs, tests/"
+        );
+        assert!(!gate.contains("src/main.rs"));
+
+        // Allowed: the paths join the set and the retry goes through
+        gate.admit(vec![write("src/main.rs")]);
+        let answer = gate.decide("yes");
+        assert!(answer.starts_with("allowed: src/main.rs"));
+        assert!(gate.admit(vec![write("src/main.rs")]).outside.is_empty());
+        q.tools_done(String::new());
+        let feedback = q.answer(&answer).unwrap();
+        assert!(feedback.contains("<tool_result tool=\"focus\" status=\"ok\""));
+
+        // No focus set: nothing is gated
+        let mut off = FocusGate::default();
+        assert!(!off.is_active());
+        assert_eq!(off.admit(vec![write("anything.rs")]).calls.len(), 1);
+    }
+
+    #[test]
+    fn test_plan_approval_resumes_as_plan_result() {
+        let mut q = PendingQuestion::plan_approval();
+        assert_eq!(q.tools_done(String::new()), None);
+        let feedback = q.answer("approved: go ahead").unwrap();
+        assert!(feedback.contains("<tool_result tool=\"plan\" status=\"ok\""));
+    }
+
+    #[test]
+    fn test_question_answered_before_tools_finish() {
+        let mut q = PendingQuestion::new("Which file?");
+        assert_eq!(q.answer("  src/lib.rs \n"), None);
+        assert!(q.is_answered());
+        assert_eq!(
+            q.tools_done(String::new()).unwrap(),
+            crate::agent::format_user_answer("src/lib.rs")
+        );
+    }
+}
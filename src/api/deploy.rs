@@ -0,0 +1,261 @@
+//! Deploy backends for built projects
+//!
+//! A finished build is a directory containing `index.html`. A deployer makes
+//! it reachable and returns the public URL.
+//!
+//!   static - directory is already served by the web server (default),
+//!            URL built from HYLE_DEPLOY_URL (default: http://{name}.hyperstitious.org)
+//!   local  - serve each project from 127.0.0.1 on its own port (development)
+
+use anyhow::{bail, Context, Result};
+use reqwest::Url;
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Default URL scheme for the static-site layout
+pub const DEFAULT_URL_TEMPLATE: &str = "http://{name}.hyperstitious.org";
+
+/// Makes a built project directory reachable
+pub trait Deployer: Send + Sync {
+    /// Short name for logs
+    fn name(&self) -> &'static str;
+
+    /// Deploy `project_dir` under `name`, returning its URL
+    fn deploy(&self, project_dir: &Path, name: &str) -> Result<Url>;
+}
+
+/// Select a deployer from HYLE_DEPLOYER / HYLE_DEPLOY_URL
+pub fn from_env() -> Result<Box<dyn Deployer>> {
+    let kind = std::env::var("HYLE_DEPLOYER").unwrap_or_else(|_| "static".into());
+    match kind.as_str() {
+        "static" => Ok(Box::new(StaticSiteDeployer::new(
+            &std::env::var("HYLE_DEPLOY_URL").unwrap_or_else(|_| DEFAULT_URL_TEMPLATE.into()),
+        ))),
+        "local" => Ok(Box::new(LocalPreviewDeployer::new())),
+        other => bail!(
+            "Unknown HYLE_DEPLOYER '{}' (expected static or local)",
+            other
+        ),
+    }
+}
+
+fn require_index(project_dir: &Path) -> Result<()> {
+    if !project_dir.join("index.html").is_file() {
+        bail!("No index.html in {}", project_dir.display());
+    }
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// STATIC SITE
+// ═══════════════════════════════════════════════════════════════
+
+/// Projects dir is served by nginx with one subdomain per project
+pub struct StaticSiteDeployer {
+    url_template: String,
+}
+
+impl StaticSiteDeployer {
+    pub fn new(url_template: &str) -> Self {
+        Self {
+            url_template: url_template.to_string(),
+        }
+    }
+}
+
+impl Deployer for StaticSiteDeployer {
+    fn name(&self) -> &'static str {
+        "static"
+    }
+
+    fn deploy(&self, project_dir: &Path, name: &str) -> Result<Url> {
+        require_index(project_dir)?;
+        let url = self.url_template.replace("{name}", name);
+        Url::parse(&url).with_context(|| format!("Invalid deploy URL: {}", url))
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// LOCAL PREVIEW
+// ═══════════════════════════════════════════════════════════════
+
+/// Serves each project from its own localhost port, like `python -m http.server`
+///
+/// Servers live for the rest of the process; redeploying a name reuses its port.
+pub struct LocalPreviewDeployer {
+    ports: Mutex<HashMap<String, u16>>,
+}
+
+impl LocalPreviewDeployer {
+    pub fn new() -> Self {
+        Self {
+            ports: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for LocalPreviewDeployer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deployer for LocalPreviewDeployer {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn deploy(&self, project_dir: &Path, name: &str) -> Result<Url> {
+        require_index(project_dir)?;
+        let mut ports = self.ports.lock().unwrap();
+        let port = match ports.get(name) {
+            Some(port) => *port,
+            None => {
+                let port = serve_dir(project_dir.to_path_buf())?;
+                ports.insert(name.to_string(), port);
+                port
+            }
+        };
+        Ok(Url::parse(&format!("http://127.0.0.1:{}/", port))?)
+    }
+}
+
+/// Start a static file server for `root` on a free port
+fn serve_dir(root: PathBuf) -> Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0").context("Failed to bind preview server")?;
+    let port = listener.local_addr()?.port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = serve_file(&root, stream);
+        }
+    });
+    Ok(port)
+}
+
+fn serve_file(root: &Path, mut stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Drain headers so closing the socket doesn't reset the connection
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+    let target = request.split_whitespace().nth(1).unwrap_or("/");
+    let target = target.split(['?', '#']).next().unwrap_or("/");
+
+    let (status, content_type, body) = match resolve(root, target) {
+        Some(path) => match std::fs::read(&path) {
+            Ok(body) => ("200 OK", content_type(&path), body),
+            Err(_) => ("404 Not Found", "text/plain", b"not found".to_vec()),
+        },
+        None => ("400 Bad Request", "text/plain", b"bad path".to_vec()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(&body)
+}
+
+/// Map a request path to a file under `root`, refusing anything that climbs out
+fn resolve(root: &Path, target: &str) -> Option<PathBuf> {
+    let relative = Path::new(target.trim_start_matches('/'));
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+    let path = root.join(relative);
+    if path.is_dir() {
+        Some(path.join("index.html"))
+    } else {
+        Some(path)
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("html") | Some("htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "application/javascript",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn project_dir(tag: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("hyle_test_deploy_{}_{}", tag, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_local_deployer_serves_index() {
+        let dir = project_dir("local");
+        std::fs::write(dir.join("index.html"), "<h1>hello drop</h1>").unwrap();
+
+        let deployer = LocalPreviewDeployer::new();
+        let url = deployer.deploy(&dir, "drop").unwrap();
+        assert_eq!(url.host_str(), Some("127.0.0.1"));
+        // Redeploying the same name keeps the URL stable
+        assert_eq!(deployer.deploy(&dir, "drop").unwrap(), url);
+
+        let mut stream = TcpStream::connect(("127.0.0.1", url.port().unwrap())).unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: x\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("<h1>hello drop</h1>"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_deployers_require_index() {
+        let dir = project_dir("empty");
+        assert!(LocalPreviewDeployer::new().deploy(&dir, "x").is_err());
+        assert!(StaticSiteDeployer::new(DEFAULT_URL_TEMPLATE)
+            .deploy(&dir, "x")
+            .is_err());
+
+        std::fs::write(dir.join("index.html"), "ok").unwrap();
+        let url = StaticSiteDeployer::new(DEFAULT_URL_TEMPLATE)
+            .deploy(&dir, "calc-app")
+            .unwrap();
+        assert_eq!(url.as_str(), "http://calc-app.hyperstitious.org/");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_resolve_refuses_traversal() {
+        let root = Path::new("/srv/drop");
+        assert!(resolve(root, "/../etc/passwd").is_none());
+        assert_eq!(
+            resolve(root, "/app.js"),
+            Some(PathBuf::from("/srv/drop/app.js"))
+        );
+    }
+}
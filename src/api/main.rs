@@ -6,7 +6,11 @@
 //! hyle-api: HTTP server for sketch submission and job orchestration
 //!
 //! Accepts sketch submissions, queues builds, returns live URLs.
-//! Supports multi-model round-robin with automatic fallback on rate limits.
+//! Spreads builds across models with a shared per-model rate limiter and
+//! falls back to another model on rate limits. Each distinct index.html a
+//! build produces is kept in the project's `.hyle/artifacts/`; see
+//! `GET /api/projects/{name}/versions`, `GET .../versions/{hash}` to read one
+//! and `POST .../versions/{hash}/restore` to make it current and redeploy.
 //!
 //! Environment variables:
 //!   PORT                 - HTTP port (default: 3000)
@@ -14,29 +18,39 @@
 //!   HYLE_MODELS          - Comma-separated list of models to use
 //!   HYLE_PROJECTS_DIR    - Where to create projects (default: /var/www/drops)
 //!   HYLE_BINARY          - Path to hyle binary (default: /usr/local/bin/hyle)
+//!   HYLE_DEPLOYER        - Deploy backend: static (default) or local
+//!   HYLE_DEPLOY_URL      - URL template for static deploys (default: http://{name}.hyperstitious.org)
+//!   HYLE_MAX_SKETCH_CHARS - Longest sketch accepted (default: 8000)
+//!   HYLE_MODEL_RATE      - Build starts per model per minute (default: 1)
+
+mod deploy;
+mod scheduler;
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{header, Method, StatusCode},
     routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     env,
+    future::Future,
     path::PathBuf,
     process::Stdio,
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::{process::Command, sync::RwLock, time::timeout};
 use tower_http::cors::{Any, CorsLayer};
 use uuid::Uuid;
 
+use hyle::artifacts::{self, ArtifactStore};
+
 /// Default free models sorted by context length and coding capability
 /// Verified against OpenRouter API 2025-12-29
 const DEFAULT_MODELS: &[&str] = &[
@@ -56,8 +70,32 @@ const MODEL_TIMEOUT_SECS: u64 = 300;
 /// Delay between model fallback attempts
 const FALLBACK_DELAY_MS: u64 = 2000;
 
+/// Shortest sketch worth a build slot
+const MIN_SKETCH_CHARS: usize = 20;
+
+/// Default cap on sketch length
+const DEFAULT_MAX_SKETCH_CHARS: usize = 8000;
+
+/// Phrases that mark a sketch as something we won't build, with the category reported
+const DISALLOWED_SKETCH_TERMS: &[(&str, &str)] = &[
+    ("phishing", "phishing"),
+    ("credential harvest", "credential harvesting"),
+    ("steal password", "credential harvesting"),
+    ("keylogger", "malware"),
+    ("ransomware", "malware"),
+    ("cryptominer", "cryptomining"),
+    ("crypto miner", "cryptomining"),
+    ("mine monero", "cryptomining"),
+];
+
+/// How long shutdown waits for running builds before giving up on them
+const DRAIN_TIMEOUT_SECS: u64 = 120;
+
+/// Job state saved on shutdown, relative to the projects dir
+const JOBS_FILE: &str = ".hyle-api-jobs.json";
+
 /// Job status
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 enum JobStatus {
     Queued,
@@ -67,8 +105,14 @@ enum JobStatus {
     Failed,
 }
 
+impl JobStatus {
+    fn is_active(&self) -> bool {
+        matches!(self, Self::Queued | Self::Building | Self::Deploying)
+    }
+}
+
 /// A build job
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Job {
     id: String,
     status: JobStatus,
@@ -79,6 +123,9 @@ struct Job {
     model_used: Option<String>,
     models_tried: Vec<String>,
     created_at: chrono::DateTime<chrono::Utc>,
+    /// Hash of the stored index.html this build produced
+    #[serde(default)]
+    artifact: Option<String>,
 }
 
 /// Application state
@@ -86,28 +133,13 @@ struct AppState {
     jobs: RwLock<HashMap<String, Job>>,
     projects_dir: PathBuf,
     hyle_binary: PathBuf,
+    deployer: Box<dyn deploy::Deployer>,
+    max_sketch_chars: usize,
     api_key: Option<String>,
-    models: Vec<String>,
-    /// Round-robin index for load balancing across models
-    model_index: AtomicUsize,
-}
-
-impl AppState {
-    /// Get next model in round-robin order
-    fn next_model(&self) -> &str {
-        let idx = self.model_index.fetch_add(1, Ordering::Relaxed) % self.models.len();
-        &self.models[idx]
-    }
-
-    /// Get all models starting from a random position for better distribution
-    fn get_model_rotation(&self) -> Vec<&str> {
-        let start = self.model_index.fetch_add(1, Ordering::Relaxed) % self.models.len();
-        let mut rotation = Vec::with_capacity(self.models.len());
-        for i in 0..self.models.len() {
-            rotation.push(self.models[(start + i) % self.models.len()].as_str());
-        }
-        rotation
-    }
+    /// Decides which model each build attempt uses, across all jobs
+    scheduler: scheduler::ModelScheduler,
+    /// Set once shutdown starts; new submissions are refused
+    draining: AtomicBool,
 }
 
 /// Request to submit a sketch
@@ -132,25 +164,107 @@ struct JobResponse {
     error: Option<String>,
     model_used: Option<String>,
     models_tried: Vec<String>,
+    artifact: Option<String>,
+}
+
+/// Stored versions of a project's generated files, oldest first
+#[derive(Debug, Serialize)]
+struct VersionsResponse {
+    project: String,
+    files: BTreeMap<String, Vec<artifacts::Version>>,
+}
+
+/// Which file `POST .../versions/{hash}/restore` puts back (default index.html)
+#[derive(Debug, Deserialize)]
+struct RestoreQuery {
+    file: Option<String>,
+}
+
+/// A restored version, live again
+#[derive(Debug, Serialize)]
+struct RestoreResponse {
+    project: String,
+    file: String,
+    hash: String,
+    url: String,
+}
+
+/// Health report; any failed check makes the service unhealthy
+#[derive(Debug, Serialize)]
+struct HealthResponse {
+    status: String,
+    api_key_present: bool,
+    hyle_binary_exists_and_executable: bool,
+    projects_dir_writable: bool,
+    model_count: usize,
+}
+
+impl HealthResponse {
+    fn check(state: &AppState) -> Self {
+        let mut report = Self {
+            status: String::new(),
+            api_key_present: state.api_key.as_deref().is_some_and(|k| !k.is_empty()),
+            hyle_binary_exists_and_executable: is_executable(&state.hyle_binary),
+            projects_dir_writable: is_writable_dir(&state.projects_dir),
+            model_count: state.scheduler.models().len(),
+        };
+        report.status = if report.healthy() { "ok" } else { "degraded" }.into();
+        report
+    }
+
+    fn healthy(&self) -> bool {
+        self.api_key_present
+            && self.hyle_binary_exists_and_executable
+            && self.projects_dir_writable
+            && self.model_count > 0
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &std::path::Path) -> bool {
+    path.is_file()
+}
+
+/// Probe by creating (and removing) a file, since permission bits don't tell the whole story
+fn is_writable_dir(dir: &std::path::Path) -> bool {
+    let probe = dir.join(format!(".hyle-health-{}", std::process::id()));
+    let ok = std::fs::write(&probe, b"").is_ok();
+    let _ = std::fs::remove_file(&probe);
+    ok
 }
 
 /// Models list response
 #[derive(Debug, Serialize)]
 struct ModelsResponse {
     models: Vec<String>,
-    active_index: usize,
+    /// Tokens, builds in flight and throttle window per model
+    load: Vec<scheduler::ModelLoad>,
 }
 
-/// Health check
-async fn health() -> &'static str {
-    "ok"
+/// Health check: 503 when a dependency needed for builds is missing
+async fn health(State(state): State<Arc<AppState>>) -> (StatusCode, Json<HealthResponse>) {
+    let report = HealthResponse::check(&state);
+    let code = if report.healthy() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(report))
 }
 
 /// List available models
 async fn list_models(State(state): State<Arc<AppState>>) -> Json<ModelsResponse> {
     Json(ModelsResponse {
-        models: state.models.clone(),
-        active_index: state.model_index.load(Ordering::Relaxed) % state.models.len(),
+        models: state.scheduler.models().to_vec(),
+        load: state.scheduler.load(),
     })
 }
 
@@ -159,14 +273,16 @@ async fn submit_sketch(
     State(state): State<Arc<AppState>>,
     Json(req): Json<SubmitRequest>,
 ) -> Result<Json<SubmitResponse>, (StatusCode, String)> {
-    let sketch = req.sketch.trim();
-    if sketch.len() < 20 {
+    if state.draining.load(Ordering::SeqCst) {
         return Err((
-            StatusCode::BAD_REQUEST,
-            "Sketch too short (min 20 chars)".into(),
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Server is shutting down".into(),
         ));
     }
 
+    let sketch = req.sketch.trim();
+    validate_sketch(sketch, state.max_sketch_chars).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
     if state.api_key.is_none() {
         return Err((
             StatusCode::SERVICE_UNAVAILABLE,
@@ -187,6 +303,7 @@ async fn submit_sketch(
         model_used: None,
         models_tried: Vec::new(),
         created_at: chrono::Utc::now(),
+        artifact: None,
     };
 
     {
@@ -228,6 +345,7 @@ async fn get_job(
             error: job.error.clone(),
             model_used: job.model_used.clone(),
             models_tried: job.models_tried.clone(),
+            artifact: job.artifact.clone(),
         })),
         None => {
             eprintln!(
@@ -245,36 +363,188 @@ async fn get_job(
                     )),
                     model_used: None,
                     models_tried: vec![],
+                    artifact: None,
                 }),
             ))
         }
     }
 }
 
+/// Directory of an existing project
+fn project_dir(state: &AppState, project: &str) -> Result<PathBuf, (StatusCode, String)> {
+    // Names come from generate_project_name; anything else is not a project
+    let valid = !project.is_empty()
+        && project
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-');
+    let project_dir = state.projects_dir.join(project);
+    if !valid || !project_dir.is_dir() {
+        return Err((StatusCode::NOT_FOUND, format!("No project {}", project)));
+    }
+    Ok(project_dir)
+}
+
+/// List the stored versions of a project's generated files
+async fn list_versions(
+    State(state): State<Arc<AppState>>,
+    Path(project): Path<String>,
+) -> Result<Json<VersionsResponse>, (StatusCode, String)> {
+    let project_dir = project_dir(&state, &project)?;
+    let files = ArtifactStore::open(&project_dir)
+        .list()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(VersionsResponse { project, files }))
+}
+
+/// Content of one stored version, as plain text so it is shown, not run
+async fn read_version(
+    State(state): State<Arc<AppState>>,
+    Path((project, hash)): Path<(String, String)>,
+) -> Result<([(header::HeaderName, &'static str); 1], Vec<u8>), (StatusCode, String)> {
+    let project_dir = project_dir(&state, &project)?;
+    let store = ArtifactStore::open(&project_dir);
+    let known = store
+        .list()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .values()
+        .flatten()
+        .any(|v| v.hash == hash);
+    if !known {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("{} has no version {}", project, hash),
+        ));
+    }
+    let content = store
+        .read(&hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        content,
+    ))
+}
+
+/// Make a stored version of a project's file current again and redeploy
+async fn restore_version(
+    State(state): State<Arc<AppState>>,
+    Path((project, hash)): Path<(String, String)>,
+    Query(query): Query<RestoreQuery>,
+) -> Result<Json<RestoreResponse>, (StatusCode, String)> {
+    let project_dir = project_dir(&state, &project)?;
+    let file = query.file.unwrap_or_else(|| "index.html".into());
+    let store = ArtifactStore::open(&project_dir);
+    let versions = store
+        .versions(&file)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !versions.iter().any(|v| v.hash == hash) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("{} has no version {} of {}", project, hash, file),
+        ));
+    }
+    let stored = store
+        .restore(&project_dir, &file, &hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let url = state.deployer.deploy(&project_dir, &project).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Deploy failed: {}", e),
+        )
+    })?;
+    Ok(Json(RestoreResponse {
+        project,
+        file,
+        hash: stored.hash,
+        url: url.to_string(),
+    }))
+}
+
+/// Check a (trimmed) sketch is worth building
+fn validate_sketch(sketch: &str, max_chars: usize) -> Result<(), String> {
+    if !sketch.chars().any(|c| c.is_alphanumeric()) {
+        return Err("Sketch is empty (only whitespace or punctuation)".into());
+    }
+
+    let chars = sketch.chars().count();
+    if chars < MIN_SKETCH_CHARS {
+        return Err(format!("Sketch too short (min {} chars)", MIN_SKETCH_CHARS));
+    }
+    if chars > max_chars {
+        return Err(format!(
+            "Sketch too long ({} chars, max {})",
+            chars, max_chars
+        ));
+    }
+
+    let lower = sketch.to_lowercase();
+    if let Some((_, category)) = DISALLOWED_SKETCH_TERMS
+        .iter()
+        .find(|(term, _)| lower.contains(term))
+    {
+        return Err(format!("Sketch requests disallowed content: {}", category));
+    }
+
+    Ok(())
+}
+
 /// Generate a project name from sketch
 fn generate_project_name(sketch: &str) -> String {
-    let words: Vec<&str> = sketch
-        .split_whitespace()
-        .filter(|w| w.len() > 3 && w.chars().all(|c| c.is_alphanumeric()))
-        .take(2)
-        .collect();
-
-    let base = words.first().copied().unwrap_or("project");
+    format!(
+        "{}-{}",
+        project_slug(sketch),
+        &Uuid::new_v4().to_string()[..4]
+    )
+}
 
-    let sanitized: String = base
-        .chars()
-        .filter(|c| c.is_alphanumeric())
-        .take(12)
-        .collect::<String>()
-        .to_lowercase();
+/// Words that say nothing about what a sketch is
+const SLUG_STOP_WORDS: &str = "about also and app application basic build can cool create each \
+     for from has have interactive into just let lets like little make more need new nice one \
+     page please should show shows simple site small some that the them then this tiny use user \
+     users using very want web website what when where which will with would you your";
+
+/// Deterministic slug from the sketch's most frequent content words
+///
+/// Falls back to a hash of the sketch when nothing salient is left.
+fn project_slug(sketch: &str) -> String {
+    let words: Vec<String> = sketch
+        .split(|c: char| !c.is_alphanumeric())
+        .map(|w| w.to_lowercase())
+        .filter(|w| {
+            w.len() >= 3
+                && !w.chars().all(|c| c.is_ascii_digit())
+                && !SLUG_STOP_WORDS
+                    .split_whitespace()
+                    .any(|stop| stop == w.as_str())
+        })
+        .collect();
 
-    let name = if sanitized.is_empty() {
-        "project".to_string()
+    // (word, count, first position)
+    let mut counts: Vec<(&str, usize, usize)> = Vec::new();
+    for (pos, word) in words.iter().enumerate() {
+        match counts.iter_mut().find(|(w, _, _)| *w == word.as_str()) {
+            Some(entry) => entry.1 += 1,
+            None => counts.push((word.as_str(), 1, pos)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.cmp(&b.2)));
+    let mut top: Vec<_> = counts.into_iter().take(2).collect();
+    top.sort_by_key(|(_, _, pos)| *pos);
+
+    let slug: String = top
+        .iter()
+        .map(|(w, _, _)| w.chars().take(12).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if slug.is_empty() {
+        // FNV-1a: stable across runs and toolchains
+        let hash = sketch.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x100000001b3)
+        });
+        format!("project-{:06x}", hash & 0xffffff)
     } else {
-        sanitized
-    };
-
-    format!("{}-{}", name, &Uuid::new_v4().to_string()[..4])
+        slug
+    }
 }
 
 /// Run build with multi-model fallback
@@ -312,11 +582,27 @@ async fn run_build_with_fallback(state: Arc<AppState>, job_id: String) {
         return;
     }
 
-    // Try each model in rotation
-    let models = state.get_model_rotation();
+    // Try models as the shared scheduler hands them out
+    let mut tried: Vec<String> = Vec::new();
+    let mut waited = Duration::ZERO;
     let mut last_error = String::new();
 
-    for model in &models {
+    loop {
+        let model = match state.scheduler.acquire(&tried) {
+            Some(model) => model,
+            None => match state.scheduler.next_ready(&tried) {
+                Some(wait) if waited + wait <= scheduler::MAX_WAIT => {
+                    eprintln!("[{}] All models busy, waiting {}s", job_id, wait.as_secs());
+                    let wait = wait.max(Duration::from_millis(100));
+                    tokio::time::sleep(wait).await;
+                    waited += wait;
+                    continue;
+                }
+                _ => break,
+            },
+        };
+        tried.push(model.clone());
+
         // Record that we tried this model
         {
             let mut jobs = state.jobs.write().await;
@@ -327,19 +613,44 @@ async fn run_build_with_fallback(state: Arc<AppState>, job_id: String) {
 
         eprintln!("[{}] Trying model: {}", job_id, model);
 
-        match try_build_with_model(&state, &project_dir, &sketch_file, model).await {
+        let result = try_build_with_model(&state, &project_dir, &sketch_file, &model).await;
+        state.scheduler.release(&model);
+        match result {
             Ok(()) => {
                 // Check if index.html was created
                 let index_path = project_dir.join("index.html");
                 if index_path.exists() {
-                    // Success! Use HTTP until wildcard SSL is set up
-                    let url = format!("http://{}.hyperstitious.org", project_name);
+                    // Keep every distinct build; a rebuild with the same output stores nothing
+                    let artifact = match ArtifactStore::open(&project_dir)
+                        .store_file(&project_dir, "index.html")
+                    {
+                        Ok(stored) => Some(stored.hash),
+                        Err(e) => {
+                            eprintln!("[{}] Failed to store artifact: {}", job_id, e);
+                            None
+                        }
+                    };
                     {
                         let mut jobs = state.jobs.write().await;
                         if let Some(job) = jobs.get_mut(&job_id) {
-                            job.status = JobStatus::Live;
-                            job.url = Some(url);
+                            job.status = JobStatus::Deploying;
                             job.model_used = Some(model.to_string());
+                            job.artifact = artifact;
+                        }
+                    }
+                    let url = match state.deployer.deploy(&project_dir, &project_name) {
+                        Ok(url) => url,
+                        Err(e) => {
+                            update_job_error(&state, &job_id, &format!("Deploy failed: {}", e))
+                                .await;
+                            return;
+                        }
+                    };
+                    {
+                        let mut jobs = state.jobs.write().await;
+                        if let Some(job) = jobs.get_mut(&job_id) {
+                            job.status = JobStatus::Live;
+                            job.url = Some(url.to_string());
                         }
                     }
                     eprintln!("[{}] Success with model: {}", job_id, model);
@@ -351,12 +662,9 @@ async fn run_build_with_fallback(state: Arc<AppState>, job_id: String) {
                 last_error = e;
                 eprintln!("[{}] Model {} failed: {}", job_id, model, last_error);
 
-                // Check if it's a rate limit error - if so, try next model
-                if last_error.contains("429")
-                    || last_error.contains("rate")
-                    || last_error.contains("throttl")
-                    || last_error.contains("limit")
-                {
+                // Rate limited: keep every job off this model for a while
+                if scheduler::is_rate_limited(&last_error) {
+                    state.scheduler.throttle(&model);
                     eprintln!("[{}] Rate limited, trying next model...", job_id);
                     tokio::time::sleep(Duration::from_millis(FALLBACK_DELAY_MS)).await;
                     continue;
@@ -374,7 +682,7 @@ async fn run_build_with_fallback(state: Arc<AppState>, job_id: String) {
         &job_id,
         &format!(
             "All {} models failed. Last error: {}",
-            models.len(),
+            tried.len(),
             last_error
         ),
     )
@@ -441,7 +749,7 @@ Make it something people want to share. Make it memorable."#,
             if output.status.success() {
                 Ok(())
             } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
+                let stderr = hyle::decode::decode_output(&output.stderr);
                 Err(format!("Exit {}: {}", output.status, stderr.trim()))
             }
         }
@@ -458,6 +766,119 @@ async fn update_job_error(state: &Arc<AppState>, job_id: &str, error: &str) {
     }
 }
 
+/// Resolves on SIGINT or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Wait for `signal`, then refuse new work and let running builds finish
+///
+/// The server keeps answering polls until this resolves, so clients can
+/// still collect results during the drain.
+async fn drain(state: Arc<AppState>, signal: impl Future<Output = ()>, limit: Duration) {
+    signal.await;
+    state.draining.store(true, Ordering::SeqCst);
+
+    let deadline = Instant::now() + limit;
+    loop {
+        let active = state
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|j| j.status.is_active())
+            .count();
+        if active == 0 {
+            break;
+        }
+        if Instant::now() >= deadline {
+            eprintln!("Drain timed out with {} build(s) still running", active);
+            break;
+        }
+        eprintln!("Draining: waiting on {} build(s)...", active);
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+
+    if let Err(e) = save_jobs(&state).await {
+        eprintln!("Failed to save job state: {}", e);
+    }
+}
+
+/// Persist jobs so results survive a restart
+async fn save_jobs(state: &AppState) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(&*state.jobs.read().await)?;
+    tokio::fs::write(state.projects_dir.join(JOBS_FILE), json).await?;
+    Ok(())
+}
+
+/// Load jobs saved by a previous run; builds it never finished are marked failed
+fn load_jobs(projects_dir: &std::path::Path) -> HashMap<String, Job> {
+    let mut jobs: HashMap<String, Job> = std::fs::read_to_string(projects_dir.join(JOBS_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    for job in jobs.values_mut() {
+        if job.status.is_active() {
+            job.status = JobStatus::Failed;
+            job.error = Some("Interrupted by server shutdown".into());
+        }
+    }
+    jobs
+}
+
+fn router(state: Arc<AppState>) -> Router {
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION]);
+
+    Router::new()
+        .route("/health", get(health))
+        .route("/api/models", get(list_models))
+        .route("/api/sketch", post(submit_sketch))
+        .route("/api/jobs/:job_id", get(get_job))
+        .route("/api/projects/:name/versions", get(list_versions))
+        .route("/api/projects/:name/versions/:hash", get(read_version))
+        .route(
+            "/api/projects/:name/versions/:hash/restore",
+            post(restore_version),
+        )
+        .layer(cors)
+        .with_state(state)
+}
+
+/// Serve until `signal` fires and the drain completes
+async fn serve(
+    listener: tokio::net::TcpListener,
+    state: Arc<AppState>,
+    signal: impl Future<Output = ()> + Send + 'static,
+    drain_limit: Duration,
+) -> anyhow::Result<()> {
+    axum::serve(listener, router(state.clone()))
+        .with_graceful_shutdown(drain(state, signal, drain_limit))
+        .await?;
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let port: u16 = env::var("PORT")
@@ -473,47 +894,66 @@ async fn main() -> anyhow::Result<()> {
 
     let api_key = env::var("OPENROUTER_API_KEY").ok();
 
+    let deployer = deploy::from_env()?;
+
+    let max_sketch_chars = env::var("HYLE_MAX_SKETCH_CHARS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(DEFAULT_MAX_SKETCH_CHARS);
+
     // Load models from env or use defaults
     let models: Vec<String> = env::var("HYLE_MODELS")
         .map(|s| s.split(',').map(|m| m.trim().to_string()).collect())
         .unwrap_or_else(|_| DEFAULT_MODELS.iter().map(|s| s.to_string()).collect());
+    let model_rate = env::var("HYLE_MODEL_RATE")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(scheduler::DEFAULT_BUILDS_PER_MINUTE);
 
     eprintln!("hyle-api starting...");
     eprintln!("  Port: {}", port);
     eprintln!("  Projects dir: {}", projects_dir.display());
     eprintln!("  Hyle binary: {}", hyle_binary.display());
+    eprintln!("  Deployer: {}", deployer.name());
     eprintln!(
         "  API key: {}",
         if api_key.is_some() { "set" } else { "NOT SET" }
     );
     eprintln!("  Models ({}): {:?}", models.len(), models);
+    eprintln!("  Model rate: {} build(s)/min each", model_rate);
+
+    let jobs = load_jobs(&projects_dir);
+    if !jobs.is_empty() {
+        eprintln!("  Restored {} job(s)", jobs.len());
+    }
 
     let state = Arc::new(AppState {
-        jobs: RwLock::new(HashMap::new()),
+        jobs: RwLock::new(jobs),
         projects_dir,
         hyle_binary,
+        deployer,
+        max_sketch_chars,
         api_key,
-        models,
-        model_index: AtomicUsize::new(0),
+        scheduler: scheduler::ModelScheduler::with_limits(
+            models,
+            scheduler::DEFAULT_BURST,
+            model_rate,
+            Duration::from_secs(scheduler::DEFAULT_THROTTLE_SECS),
+        ),
+        draining: AtomicBool::new(false),
     });
 
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
-        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION]);
-
-    let app = Router::new()
-        .route("/health", get(health))
-        .route("/api/models", get(list_models))
-        .route("/api/sketch", post(submit_sketch))
-        .route("/api/jobs/:job_id", get(get_job))
-        .layer(cors)
-        .with_state(state);
-
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
     eprintln!("hyle-api listening on http://0.0.0.0:{}", port);
 
-    axum::serve(listener, app).await?;
+    serve(
+        listener,
+        state,
+        shutdown_signal(),
+        Duration::from_secs(DRAIN_TIMEOUT_SECS),
+    )
+    .await?;
+    eprintln!("hyle-api stopped");
     Ok(())
 }
 
@@ -524,20 +964,302 @@ mod tests {
     #[test]
     fn test_generate_project_name() {
         let name = generate_project_name("build a simple calculator app");
-        assert!(name.starts_with("build-") || name.starts_with("simple-"));
+        assert!(name.starts_with("calculator-"), "{}", name);
         assert!(name.len() < 20);
     }
 
+    #[test]
+    fn test_project_slug_prefers_salient_words() {
+        assert_eq!(project_slug("build a simple calculator app"), "calculator");
+        assert_eq!(
+            project_slug("Create a drum machine. The drum pads glow; the machine loops."),
+            "drum-machine"
+        );
+        // Same sketch, same slug
+        assert_eq!(
+            project_slug("a starfield you can fly through"),
+            project_slug("a starfield you can fly through")
+        );
+    }
+
     #[test]
     fn test_generate_project_name_sanitizes() {
         let name = generate_project_name("foo/bar/../baz evil");
         assert!(!name.contains('/'));
         assert!(!name.contains('.'));
+        assert!(name.starts_with("foo-bar-"));
     }
 
     #[test]
     fn test_generate_project_name_handles_empty() {
         let name = generate_project_name("a b c");
         assert!(name.starts_with("project-"));
+        // Hashed fallback is stable for the same sketch
+        assert_eq!(
+            project_slug("build a simple app"),
+            project_slug("build a simple app")
+        );
+        assert_ne!(project_slug("a b c"), project_slug("x y z"));
+    }
+
+    #[test]
+    fn test_validate_sketch_too_short() {
+        let err = validate_sketch("a tiny toy", DEFAULT_MAX_SKETCH_CHARS).unwrap_err();
+        assert!(err.contains("too short"));
+    }
+
+    #[test]
+    fn test_validate_sketch_too_long() {
+        let sketch = "draw a spiral ".repeat(50);
+        assert!(validate_sketch(sketch.trim(), 1000).is_ok());
+        let err = validate_sketch(sketch.trim(), 100).unwrap_err();
+        assert!(err.contains("too long"));
+    }
+
+    #[test]
+    fn test_validate_sketch_whitespace_only() {
+        for sketch in ["", "   \n\t  ", "?!?!?! ... --- !!! ??? ,,,"] {
+            let err = validate_sketch(sketch.trim(), DEFAULT_MAX_SKETCH_CHARS).unwrap_err();
+            assert!(err.contains("empty"), "{:?} -> {}", sketch, err);
+        }
+    }
+
+    #[test]
+    fn test_validate_sketch_disallowed() {
+        let err = validate_sketch(
+            "a bank login page for Phishing customers",
+            DEFAULT_MAX_SKETCH_CHARS,
+        )
+        .unwrap_err();
+        assert!(err.contains("phishing"));
+        assert!(validate_sketch(
+            "a particle system that follows the mouse",
+            DEFAULT_MAX_SKETCH_CHARS
+        )
+        .is_ok());
+    }
+
+    fn health_state(
+        api_key: Option<&str>,
+        hyle_binary: PathBuf,
+        projects_dir: PathBuf,
+    ) -> AppState {
+        AppState {
+            jobs: RwLock::new(HashMap::new()),
+            projects_dir,
+            hyle_binary,
+            deployer: Box::new(deploy::LocalPreviewDeployer::new()),
+            max_sketch_chars: DEFAULT_MAX_SKETCH_CHARS,
+            api_key: api_key.map(String::from),
+            scheduler: scheduler::ModelScheduler::new(vec!["test/model".into()]),
+            draining: AtomicBool::new(false),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_ok() {
+        let state = health_state(
+            Some("sk-test"),
+            env::current_exe().unwrap(),
+            env::temp_dir(),
+        );
+        let (code, Json(report)) = health(State(Arc::new(state))).await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(report.status, "ok");
+        assert_eq!(report.model_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_health_degraded() {
+        let state = health_state(
+            None,
+            PathBuf::from("/nonexistent/hyle"),
+            env::temp_dir().join("hyle_test_health_missing/dir"),
+        );
+        let (code, Json(report)) = health(State(Arc::new(state))).await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(report.status, "degraded");
+        assert!(!report.api_key_present);
+        assert!(!report.hyle_binary_exists_and_executable);
+        assert!(!report.projects_dir_writable);
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown_drains_jobs() {
+        let dir = env::temp_dir().join(format!("hyle_test_api_drain_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state = Arc::new(health_state(
+            Some("sk-test"),
+            env::current_exe().unwrap(),
+            dir.clone(),
+        ));
+        state.jobs.write().await.insert(
+            "j1".into(),
+            Job {
+                id: "j1".into(),
+                status: JobStatus::Building,
+                sketch: "a spinning cube that follows the mouse".into(),
+                project_name: Some("spinning-cube".into()),
+                url: None,
+                error: None,
+                model_used: None,
+                models_tried: vec![],
+                created_at: chrono::Utc::now(),
+                artifact: None,
+            },
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let signal = async {
+            let _ = rx.await;
+        };
+        let server = tokio::spawn(serve(
+            listener,
+            state.clone(),
+            signal,
+            Duration::from_secs(10),
+        ));
+
+        tx.send(()).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(state.draining.load(Ordering::SeqCst));
+        assert!(!server.is_finished(), "must wait for the running build");
+
+        let req = SubmitRequest {
+            sketch: "another sketch that should be refused".into(),
+        };
+        let (code, _) = submit_sketch(State(state.clone()), Json(req))
+            .await
+            .unwrap_err();
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+
+        state.jobs.write().await.get_mut("j1").unwrap().status = JobStatus::Live;
+        timeout(Duration::from_secs(5), server)
+            .await
+            .expect("serve should resolve once drained")
+            .unwrap()
+            .unwrap();
+
+        let saved = load_jobs(&dir);
+        assert_eq!(saved["j1"].status, JobStatus::Live);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_list_versions() {
+        let dir = env::temp_dir().join(format!("hyle_test_api_versions_{}", std::process::id()));
+        let project = dir.join("drum-machine");
+        std::fs::create_dir_all(&project).unwrap();
+        let store = ArtifactStore::open(&project);
+        let v1 = store.store("index.html", b"<p>one</p>").unwrap();
+        let v2 = store.store("index.html", b"<p>two</p>").unwrap();
+        let state = Arc::new(health_state(
+            Some("sk-test"),
+            env::current_exe().unwrap(),
+            dir.clone(),
+        ));
+
+        let Json(listed) = list_versions(State(state.clone()), Path("drum-machine".into()))
+            .await
+            .unwrap();
+        let hashes: Vec<&str> = listed.files["index.html"]
+            .iter()
+            .map(|v| v.hash.as_str())
+            .collect();
+        assert_eq!(hashes, vec![v1.hash.as_str(), v2.hash.as_str()]);
+
+        for missing in ["no-such-project", "..", "drum-machine/.hyle"] {
+            let (code, _) = list_versions(State(state.clone()), Path(missing.into()))
+                .await
+                .unwrap_err();
+            assert_eq!(code, StatusCode::NOT_FOUND);
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_read_and_restore_version() {
+        let dir = env::temp_dir().join(format!("hyle_test_api_restore_{}", std::process::id()));
+        let project = dir.join("drum-machine");
+        std::fs::create_dir_all(&project).unwrap();
+        let store = ArtifactStore::open(&project);
+        let v1 = store.store("index.html", b"<p>one</p>").unwrap();
+        std::fs::write(project.join("index.html"), "<p>two</p>").unwrap();
+        store.store_file(&project, "index.html").unwrap();
+        let state = Arc::new(health_state(
+            Some("sk-test"),
+            env::current_exe().unwrap(),
+            dir.clone(),
+        ));
+        let path = |hash: &str| Path(("drum-machine".to_string(), hash.to_string()));
+
+        let (_, content) = read_version(State(state.clone()), path(&v1.hash))
+            .await
+            .unwrap();
+        assert_eq!(content, b"<p>one</p>");
+
+        let Json(restored) = restore_version(
+            State(state.clone()),
+            path(&v1.hash),
+            Query(RestoreQuery { file: None }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(restored.file, "index.html");
+        assert_eq!(restored.hash, v1.hash);
+        assert_eq!(
+            std::fs::read_to_string(project.join("index.html")).unwrap(),
+            "<p>one</p>"
+        );
+        assert_eq!(store.current("index.html").unwrap().unwrap().hash, v1.hash);
+
+        // Unknown or malformed hashes and other files' names are not found
+        for hash in ["0000000000000000", "../index.json"] {
+            let (code, _) = read_version(State(state.clone()), path(hash))
+                .await
+                .unwrap_err();
+            assert_eq!(code, StatusCode::NOT_FOUND);
+        }
+        let (code, _) = restore_version(
+            State(state.clone()),
+            path(&v1.hash),
+            Query(RestoreQuery {
+                file: Some("../escape.html".into()),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(code, StatusCode::NOT_FOUND);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_load_jobs_fails_interrupted_builds() {
+        let dir = env::temp_dir().join(format!("hyle_test_api_jobs_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let json = serde_json::json!({
+            "j2": {
+                "id": "j2",
+                "status": "building",
+                "sketch": "x",
+                "project_name": null,
+                "url": null,
+                "error": null,
+                "model_used": null,
+                "models_tried": [],
+                "created_at": "2026-01-01T00:00:00Z"
+            }
+        });
+        std::fs::write(dir.join(JOBS_FILE), json.to_string()).unwrap();
+
+        let jobs = load_jobs(&dir);
+        assert_eq!(jobs["j2"].status, JobStatus::Failed);
+        assert!(jobs["j2"].error.as_deref().unwrap().contains("shutdown"));
+
+        std::fs::remove_dir_all(&dir).ok();
     }
 }
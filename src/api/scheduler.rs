@@ -0,0 +1,320 @@
+//! Shared model scheduling for build jobs
+//!
+//! Each job used to walk its own rotation of the model list, so under load
+//! they all started on the same model and hit its rate limit together. The
+//! scheduler is shared by every job: each model has a token bucket that a
+//! build start draws from, and a throttle window set when the model answers
+//! with a rate-limit error. Jobs ask it which model to use next.
+
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Builds a model may start back to back before jobs spread to others
+pub const DEFAULT_BURST: f64 = 2.0;
+
+/// Sustained build starts per model per minute
+pub const DEFAULT_BUILDS_PER_MINUTE: f64 = 1.0;
+
+/// How long a model is skipped after a rate-limit error
+pub const DEFAULT_THROTTLE_SECS: u64 = 60;
+
+/// Longest a job waits for a model to free up before giving up
+pub const MAX_WAIT: Duration = Duration::from_secs(90);
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+    throttled_until: Option<Instant>,
+    in_flight: usize,
+}
+
+/// Per-model load, as reported by `/api/models`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ModelLoad {
+    pub model: String,
+    /// Build starts available right now
+    pub tokens: f64,
+    pub in_flight: usize,
+    /// Seconds left in the throttle window; 0 when usable
+    pub throttled_secs: u64,
+}
+
+/// Token bucket per model, shared by all jobs
+pub struct ModelScheduler {
+    models: Vec<String>,
+    burst: f64,
+    /// Tokens added per second
+    refill: f64,
+    throttle: Duration,
+    buckets: Mutex<HashMap<String, Bucket>>,
+    /// Where ties start, so equal models take turns
+    cursor: Mutex<usize>,
+}
+
+impl ModelScheduler {
+    pub fn new(models: Vec<String>) -> Self {
+        Self::with_limits(
+            models,
+            DEFAULT_BURST,
+            DEFAULT_BUILDS_PER_MINUTE,
+            Duration::from_secs(DEFAULT_THROTTLE_SECS),
+        )
+    }
+
+    pub fn with_limits(
+        models: Vec<String>,
+        burst: f64,
+        per_minute: f64,
+        throttle: Duration,
+    ) -> Self {
+        let now = Instant::now();
+        let buckets = models
+            .iter()
+            .map(|m| {
+                let bucket = Bucket {
+                    tokens: burst,
+                    refilled_at: now,
+                    throttled_until: None,
+                    in_flight: 0,
+                };
+                (m.clone(), bucket)
+            })
+            .collect();
+        Self {
+            models,
+            burst,
+            refill: per_minute / 60.0,
+            throttle,
+            buckets: Mutex::new(buckets),
+            cursor: Mutex::new(0),
+        }
+    }
+
+    pub fn models(&self) -> &[String] {
+        &self.models
+    }
+
+    /// Take a build slot on the best model not in `tried`, or None if every
+    /// remaining model is throttled or out of tokens
+    pub fn acquire(&self, tried: &[String]) -> Option<String> {
+        self.acquire_at(tried, Instant::now())
+    }
+
+    /// `acquire` at a given instant
+    ///
+    /// Prefers the model with the most tokens, then the fewest builds in
+    /// flight; remaining ties rotate.
+    pub fn acquire_at(&self, tried: &[String], now: Instant) -> Option<String> {
+        if self.models.is_empty() {
+            return None;
+        }
+        let mut buckets = self.buckets.lock().unwrap();
+        let mut cursor = self.cursor.lock().unwrap();
+        let n = self.models.len();
+
+        let mut best: Option<(&String, f64, usize)> = None;
+        for i in 0..n {
+            let model = &self.models[(*cursor + i) % n];
+            if tried.contains(model) {
+                continue;
+            }
+            let bucket = buckets.get_mut(model)?;
+            self.refill(bucket, now);
+            if bucket.throttled_until.is_some_and(|t| t > now) || bucket.tokens < 1.0 {
+                continue;
+            }
+            let better = match best {
+                None => true,
+                Some((_, tokens, in_flight)) => {
+                    bucket.tokens > tokens
+                        || (bucket.tokens == tokens && bucket.in_flight < in_flight)
+                }
+            };
+            if better {
+                best = Some((model, bucket.tokens, bucket.in_flight));
+            }
+        }
+
+        let model = best?.0.clone();
+        let bucket = buckets.get_mut(&model)?;
+        bucket.tokens -= 1.0;
+        bucket.in_flight += 1;
+        *cursor = (*cursor + 1) % n;
+        Some(model)
+    }
+
+    /// A build on `model` finished, however it went
+    pub fn release(&self, model: &str) {
+        if let Some(bucket) = self.buckets.lock().unwrap().get_mut(model) {
+            bucket.in_flight = bucket.in_flight.saturating_sub(1);
+        }
+    }
+
+    /// `model` answered with a rate limit: skip it for the throttle window
+    pub fn throttle(&self, model: &str) {
+        self.throttle_at(model, Instant::now());
+    }
+
+    pub fn throttle_at(&self, model: &str, now: Instant) {
+        if let Some(bucket) = self.buckets.lock().unwrap().get_mut(model) {
+            bucket.throttled_until = Some(now + self.throttle);
+            bucket.tokens = 0.0;
+            bucket.refilled_at = now;
+        }
+    }
+
+    /// How long until a model not in `tried` can be acquired; None when
+    /// every model has been tried
+    pub fn next_ready(&self, tried: &[String]) -> Option<Duration> {
+        self.next_ready_at(tried, Instant::now())
+    }
+
+    pub fn next_ready_at(&self, tried: &[String], now: Instant) -> Option<Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        self.models
+            .iter()
+            .filter(|m| !tried.contains(m))
+            .filter_map(|m| {
+                let bucket = buckets.get_mut(m)?;
+                self.refill(bucket, now);
+                let throttled = bucket
+                    .throttled_until
+                    .map(|t| t.saturating_duration_since(now))
+                    .unwrap_or_default();
+                let refilled = if bucket.tokens >= 1.0 {
+                    Duration::ZERO
+                } else if self.refill > 0.0 {
+                    Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill)
+                } else {
+                    return None;
+                };
+                Some(throttled.max(refilled))
+            })
+            .min()
+    }
+
+    /// Current load of every model, in configured order
+    pub fn load(&self) -> Vec<ModelLoad> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        self.models
+            .iter()
+            .filter_map(|m| {
+                let bucket = buckets.get_mut(m)?;
+                self.refill(bucket, now);
+                Some(ModelLoad {
+                    model: m.clone(),
+                    tokens: (bucket.tokens * 100.0).floor() / 100.0,
+                    in_flight: bucket.in_flight,
+                    throttled_secs: bucket
+                        .throttled_until
+                        .map(|t| t.saturating_duration_since(now).as_secs())
+                        .unwrap_or(0),
+                })
+            })
+            .collect()
+    }
+
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        if bucket.throttled_until.is_some_and(|t| t > now) {
+            return;
+        }
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.refill).min(self.burst);
+        bucket.refilled_at = now;
+    }
+}
+
+/// Whether a build error looks like the provider's rate limit
+pub fn is_rate_limited(error: &str) -> bool {
+    let error = error.to_lowercase();
+    ["429", "rate", "throttl", "limit"]
+        .iter()
+        .any(|s| error.contains(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(models: &[&str], burst: f64) -> ModelScheduler {
+        ModelScheduler::with_limits(
+            models.iter().map(|m| m.to_string()).collect(),
+            burst,
+            6.0,
+            Duration::from_secs(30),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_throttled_model_sends_concurrent_jobs_elsewhere() {
+        let scheduler = std::sync::Arc::new(scheduler(&["best", "other"], 2.0));
+
+        // First job takes the preferred model, which then answers 429
+        let first = scheduler.acquire(&[]).unwrap();
+        assert_eq!(first, "best");
+        scheduler.throttle(&first);
+        scheduler.release(&first);
+
+        // A job starting meanwhile, and the first job's retry, both avoid it
+        let second = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move { scheduler.acquire(&[]) })
+        };
+        let retry = scheduler.acquire(std::slice::from_ref(&first)).unwrap();
+        let second = second.await.unwrap().unwrap();
+        assert_eq!(retry, "other");
+        assert_eq!(second, "other");
+
+        let load = scheduler.load();
+        assert!(load[0].throttled_secs > 0);
+        assert_eq!(load[1].in_flight, 2);
+    }
+
+    #[test]
+    fn test_token_buckets_spread_and_refill() {
+        let scheduler = scheduler(&["a", "b"], 1.0);
+        let start = Instant::now();
+
+        // One token each: back-to-back jobs land on different models
+        assert_eq!(scheduler.acquire_at(&[], start).as_deref(), Some("a"));
+        assert_eq!(scheduler.acquire_at(&[], start).as_deref(), Some("b"));
+        assert_eq!(scheduler.acquire_at(&[], start), None);
+        // 6 per minute: a token every 10s
+        assert_eq!(
+            scheduler.next_ready_at(&[], start),
+            Some(Duration::from_secs(10))
+        );
+        let later = start + Duration::from_secs(10);
+        assert!(scheduler.acquire_at(&[], later).is_some());
+
+        // A throttle outlasts the refill
+        scheduler.throttle_at("a", later);
+        let ready = scheduler.next_ready_at(&["b".to_string()], later);
+        assert_eq!(ready, Some(Duration::from_secs(30)));
+        assert_eq!(scheduler.acquire_at(&["b".to_string()], later), None);
+        let after = later + Duration::from_secs(40);
+        assert_eq!(
+            scheduler.acquire_at(&["b".to_string()], after).as_deref(),
+            Some("a")
+        );
+
+        // Every model tried: nothing to wait for
+        let all = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(scheduler.next_ready_at(&all, after), None);
+    }
+
+    #[test]
+    fn test_is_rate_limited() {
+        assert!(is_rate_limited("Exit 1: HTTP 429 Too Many Requests"));
+        assert!(is_rate_limited("Rate limit exceeded"));
+        assert!(!is_rate_limited(
+            "Build completed but no index.html created"
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+//! Content-addressed storage for generated files
+//!
+//! Each version of a generated file (a build's `index.html`, say) is kept as
+//! `.hyle/artifacts/<hash>`, named by a fingerprint of its bytes, so a rebuild
+//! that produces the same output stores nothing new and earlier outputs can be
+//! recovered. The fingerprint is 64-bit FNV-1a, not a cryptographic hash:
+//! it dedupes, it doesn't authenticate, and a collision is refused by
+//! comparing bytes before a blob is reused. `.hyle/artifacts/index.json` lists each file's versions, oldest
+//! first; the last one is the current version.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where artifacts live, relative to the project
+pub const ARTIFACTS_DIR: &str = ".hyle/artifacts";
+
+/// Versions of every stored file
+const INDEX_FILE: &str = "index.json";
+
+/// Non-cryptographic fingerprint (FNV-1a) of the bytes: stable across runs
+/// and toolchains, but not collision resistant
+pub fn fingerprint(content: &[u8]) -> String {
+    let hash = content.iter().fold(0xcbf29ce484222325u64, |h, b| {
+        (h ^ *b as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// One stored version of a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Version {
+    pub hash: String,
+    pub bytes: u64,
+    pub stored_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Outcome of storing a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stored {
+    pub hash: String,
+    /// False when the content already was the current version
+    pub new_version: bool,
+}
+
+/// The artifacts of one project
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    dir: PathBuf,
+}
+
+impl ArtifactStore {
+    /// Store under `<root>/.hyle/artifacts`; nothing is created until the
+    /// first `store`
+    pub fn open(root: &Path) -> Self {
+        Self {
+            dir: root.join(ARTIFACTS_DIR),
+        }
+    }
+
+    /// Every stored file name with its versions, oldest first
+    pub fn list(&self) -> Result<BTreeMap<String, Vec<Version>>> {
+        match fs::read_to_string(self.dir.join(INDEX_FILE)) {
+            Ok(json) => serde_json::from_str(&json).context("Corrupt artifact index"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn versions(&self, name: &str) -> Result<Vec<Version>> {
+        Ok(self.list()?.remove(name).unwrap_or_default())
+    }
+
+    pub fn current(&self, name: &str) -> Result<Option<Version>> {
+        Ok(self.versions(name)?.pop())
+    }
+
+    /// Record `content` as the current version of `name`. Content already
+    /// stored (under any name) reuses its blob; content that already is
+    /// the current version changes nothing.
+    pub fn store(&self, name: &str, content: &[u8]) -> Result<Stored> {
+        let hash = fingerprint(content);
+        let blob = self.dir.join(&hash);
+        match fs::read(&blob) {
+            Ok(existing) if existing == content => {}
+            Ok(_) => bail!("Artifact hash collision: {} for {}", hash, name),
+            Err(_) => {
+                self.create_dir()?;
+                let tmp = self.dir.join(format!("{}.tmp", hash));
+                fs::write(&tmp, content)?;
+                fs::rename(&tmp, &blob)?;
+            }
+        }
+
+        let mut index = self.list()?;
+        let versions = index.entry(name.to_string()).or_default();
+        if versions.last().is_some_and(|v| v.hash == hash) {
+            return Ok(Stored {
+                hash,
+                new_version: false,
+            });
+        }
+        versions.push(Version {
+            hash: hash.clone(),
+            bytes: content.len() as u64,
+            stored_at: chrono::Utc::now(),
+        });
+        let tmp = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        fs::write(&tmp, serde_json::to_string_pretty(&index)?)?;
+        fs::rename(&tmp, self.dir.join(INDEX_FILE))?;
+        Ok(Stored {
+            hash,
+            new_version: true,
+        })
+    }
+
+    /// Store the file `name` under `root` as it is now
+    pub fn store_file(&self, root: &Path, name: &str) -> Result<Stored> {
+        let content =
+            fs::read(root.join(name)).with_context(|| format!("Failed to read {}", name))?;
+        self.store(name, &content)
+    }
+
+    /// Content of a stored version
+    pub fn read(&self, hash: &str) -> Result<Vec<u8>> {
+        if hash.len() != 16 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!("Not an artifact hash: {}", hash);
+        }
+        fs::read(self.dir.join(hash)).with_context(|| format!("No artifact {}", hash))
+    }
+
+    /// Write version `hash` of `name` back to `root/name`, making it current
+    pub fn restore(&self, root: &Path, name: &str, hash: &str) -> Result<Stored> {
+        if !self.versions(name)?.iter().any(|v| v.hash == hash) {
+            bail!("{} has no version {}", name, hash);
+        }
+        let content = self.read(hash)?;
+        fs::write(root.join(name), &content)?;
+        self.store(name, &content)
+    }
+
+    fn create_dir(&self) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        // Keeps stored versions out of commits
+        let ignore = self.dir.join(".gitignore");
+        if !ignore.exists() {
+            fs::write(&ignore, "*\n")?;
+        }
+        Ok(())
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TESTS
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("hyle_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_identical_content_reuses_hash() {
+        let root = temp_root("artifacts_same");
+        let store = ArtifactStore::open(&root);
+        assert!(store.list().unwrap().is_empty());
+
+        let first = store.store("index.html", b"<h1>hi</h1>").unwrap();
+        assert!(first.new_version);
+        assert_eq!(first.hash, fingerprint(b"<h1>hi</h1>"));
+
+        // A rebuild with the same output is a no-op
+        let again = store.store("index.html", b"<h1>hi</h1>").unwrap();
+        assert_eq!(again.hash, first.hash);
+        assert!(!again.new_version);
+        assert_eq!(store.versions("index.html").unwrap().len(), 1);
+
+        // Other names share the blob
+        let copy = store.store("copy.html", b"<h1>hi</h1>").unwrap();
+        assert_eq!(copy.hash, first.hash);
+        let blobs: Vec<String> = fs::read_dir(root.join(ARTIFACTS_DIR))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name != INDEX_FILE && name != ".gitignore")
+            .collect();
+        assert_eq!(blobs, vec![first.hash.clone()]);
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_distinct_content_adds_version() {
+        let root = temp_root("artifacts_versions");
+        let store = ArtifactStore::open(&root);
+        fs::write(root.join("index.html"), "v1").unwrap();
+        let v1 = store.store_file(&root, "index.html").unwrap();
+        fs::write(root.join("index.html"), "v2").unwrap();
+        let v2 = store.store_file(&root, "index.html").unwrap();
+        assert!(v2.new_version);
+        assert_ne!(v1.hash, v2.hash);
+
+        let versions = store.versions("index.html").unwrap();
+        assert_eq!(
+            versions.iter().map(|v| v.hash.as_str()).collect::<Vec<_>>(),
+            vec![v1.hash.as_str(), v2.hash.as_str()]
+        );
+        assert_eq!(versions[0].bytes, 2);
+        assert_eq!(store.current("index.html").unwrap().unwrap().hash, v2.hash);
+        assert_eq!(store.read(&v1.hash).unwrap(), b"v1");
+
+        // An older version comes back as the current one
+        store.restore(&root, "index.html", &v1.hash).unwrap();
+        assert_eq!(fs::read_to_string(root.join("index.html")).unwrap(), "v1");
+        assert_eq!(store.current("index.html").unwrap().unwrap().hash, v1.hash);
+        assert_eq!(store.versions("index.html").unwrap().len(), 3);
+
+        assert!(store
+            .restore(&root, "index.html", "0000000000000000")
+            .is_err());
+        assert!(store.read("../index.html").is_err());
+        assert_eq!(
+            fs::read_to_string(root.join(ARTIFACTS_DIR).join(".gitignore")).unwrap(),
+            "*\n"
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
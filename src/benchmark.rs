@@ -5,9 +5,16 @@
 // Evaluates LLMs on repository hygiene and maintenance tasks.
 // Scores models based on accuracy, efficiency, and code quality.
 
+use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
 use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+use crate::bootstrap::{CodebaseAnalysis, TodoPriority};
+use crate::models::Model;
 
 // ═══════════════════════════════════════════════════════════════
 // PROMPT CATEGORIES
@@ -121,6 +128,11 @@ impl PromptSet {
         }
     }
 
+    /// A set of prompts built elsewhere, e.g. from the project
+    pub fn from_prompts(prompts: Vec<BenchmarkPrompt>) -> Self {
+        Self { prompts }
+    }
+
     pub fn by_category(&self, category: TaskCategory) -> Vec<&BenchmarkPrompt> {
         self.prompts
             .iter()
@@ -407,6 +419,9 @@ pub struct ModelProfile {
     pub avg_latency_ms: u64,
     pub total_tokens: u32,
     pub cost_estimate: f64,
+    /// Prompts that failed or timed out
+    #[serde(default)]
+    pub errors: usize,
 }
 
 impl ModelProfile {
@@ -452,6 +467,7 @@ impl ModelProfile {
             avg_latency_ms,
             total_tokens,
             cost_estimate,
+            errors: 0,
         }
     }
 
@@ -828,153 +844,647 @@ commit pqr678: refactored some things
 commit stu901: final fix (hopefully)
 "#;
 
-// ═══════════════════════════════════════════════════════════════
-// TESTS
-// ═══════════════════════════════════════════════════════════════
-
 // ═══════════════════════════════════════════════════════════════
 // BENCHMARK RUNNER - Execute benchmarks against LLM APIs
 // ═══════════════════════════════════════════════════════════════
 
-use std::path::Path;
+/// Free models benchmarked when none are named
+pub const DEFAULT_FREE_MODELS: usize = 3;
 
-/// Runner for executing benchmarks against a model
-pub struct BenchmarkRunner<'a> {
-    api_key: &'a str,
-    model: &'a str,
-    work_dir: &'a Path,
+impl TaskCategory {
+    /// Parse a CLI category name such as `security`, `docs` or `git-hygiene`
+    pub fn parse(name: &str) -> Option<TaskCategory> {
+        let name = name.trim().to_lowercase().replace(['_', ' '], "-");
+        let category = match name.as_str() {
+            "cleanup" | "code-cleanup" => TaskCategory::CodeCleanup,
+            "docs" | "documentation" => TaskCategory::Documentation,
+            "deps" | "dependencies" => TaskCategory::Dependencies,
+            "test" | "tests" | "testing" => TaskCategory::Testing,
+            "security" => TaskCategory::Security,
+            "perf" | "performance" => TaskCategory::Performance,
+            "structure" => TaskCategory::Structure,
+            "git" | "git-hygiene" => TaskCategory::GitHygiene,
+            _ => return None,
+        };
+        Some(category)
+    }
+}
+
+/// Parse a comma-separated category list
+pub fn parse_categories(spec: &str) -> anyhow::Result<Vec<TaskCategory>> {
+    let mut categories = Vec::new();
+    for name in spec.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let category = TaskCategory::parse(name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown category '{}'", name))?;
+        if !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    if categories.is_empty() {
+        anyhow::bail!("No categories given");
+    }
+    Ok(categories)
+}
+
+/// Models to benchmark: the requested ones (only free ones with `free_only`),
+/// or the first few free models from the catalog when none were named
+pub fn select_models(requested: &[String], free_only: bool, catalog: &[Model]) -> Vec<String> {
+    if requested.is_empty() {
+        return crate::models::get_free_models(catalog)
+            .into_iter()
+            .take(DEFAULT_FREE_MODELS)
+            .map(|m| m.id.clone())
+            .collect();
+    }
+    requested
+        .iter()
+        .filter(|id| {
+            !free_only
+                || id.ends_with(":free")
+                || catalog.iter().any(|m| &m.id == *id && m.is_free())
+        })
+        .cloned()
+        .collect()
+}
+
+/// Prompt text sent to the model for one benchmark prompt
+fn housekeeping_prompt(prompt: &BenchmarkPrompt) -> String {
+    match &prompt.context {
+        Some(ctx) => format!(
+            "You are a code assistant helping with housekeeping tasks. \
+             Be concise and specific.\n\n{}\n\nContext:\n{}",
+            prompt.prompt, ctx
+        ),
+        None => format!(
+            "You are a code assistant helping with housekeeping tasks. \
+             Be concise and specific.\n\n{}",
+            prompt.prompt
+        ),
+    }
+}
+
+/// Runs the prompt suite against several models concurrently
+pub struct BenchmarkRunner<F> {
     config: BenchmarkConfig,
+    prompt_set: PromptSet,
+    complete: F,
 }
 
-impl<'a> BenchmarkRunner<'a> {
-    pub fn new(api_key: &'a str, model: &'a str, work_dir: &'a Path) -> Self {
+impl<F, Fut> BenchmarkRunner<F>
+where
+    F: Fn(String, String, u32) -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+{
+    /// `complete(model, prompt, max_tokens)` performs one request
+    pub fn new(config: BenchmarkConfig, complete: F) -> Self {
         Self {
-            api_key,
-            model,
-            work_dir,
-            config: BenchmarkConfig::default(),
+            config,
+            prompt_set: PromptSet::new(),
+            complete,
         }
     }
 
-    pub fn with_config(mut self, config: BenchmarkConfig) -> Self {
-        self.config = config;
+    /// Run `prompt_set` instead of the synthetic samples
+    pub fn with_prompts(mut self, prompt_set: PromptSet) -> Self {
+        self.prompt_set = prompt_set;
         self
     }
 
-    /// Run the full benchmark suite and return a profile
-    pub async fn run_full_suite(&mut self) -> anyhow::Result<ModelProfileWithMeta> {
-        let prompts = PromptSet::default();
-        let mut scores = Vec::new();
+    /// Prompts selected by the configured categories
+    pub fn prompts(&self) -> Vec<BenchmarkPrompt> {
+        self.prompt_set
+            .all()
+            .iter()
+            .filter(|p| self.config.categories.contains(&p.category))
+            .cloned()
+            .collect()
+    }
 
-        println!(
-            "Running {} prompts across {} categories...",
-            prompts.prompts.len(),
-            self.config.categories.len()
-        );
+    /// Run every selected prompt on every model, at most `max_concurrent`
+    /// requests at a time. Failed or timed-out prompts are counted as errors
+    /// on that model's profile and never stop the run.
+    pub async fn run(&self, models: &[String]) -> BenchmarkResult {
+        let prompts = self.prompts();
+        let semaphore = Semaphore::new(self.config.max_concurrent.max(1));
+        let (prompts, semaphore) = (&prompts, &semaphore);
+
+        let tasks = models.iter().flat_map(|model| {
+            prompts.iter().map(move |prompt| async move {
+                let _permit = semaphore.acquire().await.expect("semaphore closed");
+                let start = Instant::now();
+                let request = (self.complete)(
+                    model.clone(),
+                    housekeeping_prompt(prompt),
+                    prompt.max_tokens,
+                );
+                let score = match tokio::time::timeout(self.config.timeout, request).await {
+                    Ok(Ok(response)) => Some(ResponseScore::compute(
+                        prompt,
+                        model,
+                        &response,
+                        start.elapsed(),
+                        estimate_tokens(&response),
+                    )),
+                    _ => None,
+                };
+                (model, score)
+            })
+        });
+        let outcomes = join_all(tasks).await;
+
+        let profiles = models
+            .iter()
+            .map(|model| {
+                let mut scores = Vec::new();
+                let mut errors = 0;
+                for (m, score) in &outcomes {
+                    if *m != model {
+                        continue;
+                    }
+                    match score {
+                        Some(score) => scores.push(score.clone()),
+                        None => errors += 1,
+                    }
+                }
+                let mut profile = ModelProfile::from_scores(model, scores);
+                profile.errors = errors;
+                profile
+            })
+            .collect();
+        BenchmarkResult::new(profiles)
+    }
+}
 
-        for prompt in &prompts.prompts {
-            if !self.config.categories.contains(&prompt.category) {
-                continue;
-            }
+/// Estimate token count from text (rough approximation)
+fn estimate_tokens(text: &str) -> u32 {
+    // Rough estimate: ~4 chars per token for English
+    (text.len() as f64 / 4.0).ceil() as u32
+}
 
-            print!("  {} ({:?})... ", prompt.id, prompt.category);
-
-            let start = std::time::Instant::now();
-            match self.run_single_prompt(prompt).await {
-                Ok(response) => {
-                    let elapsed = start.elapsed();
-                    let tokens = estimate_tokens(&response);
-                    let score =
-                        ResponseScore::compute(prompt, self.model, &response, elapsed, tokens);
-                    println!("score: {:.2}", score.weighted_score);
-                    scores.push(score);
-                }
-                Err(e) => {
-                    println!("error: {}", e);
-                }
-            }
+// ═══════════════════════════════════════════════════════════════
+// PROJECT PROMPTS - `hyle benchmark self`
+// ═══════════════════════════════════════════════════════════════
+
+/// TODO/FIXME items turned into prompts
+const MAX_TODO_PROMPTS: usize = 4;
+/// Lint findings turned into prompts
+const MAX_LINT_PROMPTS: usize = 2;
+/// Lines of source shown either side of a TODO or lint
+const EXCERPT_LINES: usize = 15;
+/// Cap on the source sent with one prompt
+const MAX_CONTEXT_CHARS: usize = 6000;
+/// Modules shorter than this aren't worth splitting
+const REFACTOR_MIN_LINES: usize = 300;
+
+/// Generated prompts for one state of the source tree
+#[derive(Debug, Serialize, Deserialize)]
+struct ProjectPromptCache {
+    source_hash: String,
+    prompts: Vec<BenchmarkPrompt>,
+}
+
+/// Prompts for the project at `root`, regenerated (analysis included)
+/// only when its source changed since the last `hyle benchmark self`
+pub fn load_project_prompts(root: &Path) -> anyhow::Result<PromptSet> {
+    let project = crate::project::Project::detect(root)
+        .ok_or_else(|| anyhow::anyhow!("No project found at {}", root.display()))?;
+    let cache_path = crate::config::cache_dir()?.join("bench_self.json");
+    let hash = crate::bootstrap::source_hash(&project.root);
+    let prompts = cached_project_prompts(&cache_path, &hash, || {
+        let analyzer = crate::bootstrap::SelfAnalyzer::for_project(project.clone());
+        Ok(project_prompts(&analyzer.analyze()?, &project.root))
+    })?;
+    Ok(PromptSet::from_prompts(prompts))
+}
+
+/// Prompts for `hash` from `cache_path`, or from `generate` (then cached)
+pub fn cached_project_prompts<F>(
+    cache_path: &Path,
+    hash: &str,
+    generate: F,
+) -> anyhow::Result<Vec<BenchmarkPrompt>>
+where
+    F: FnOnce() -> anyhow::Result<Vec<BenchmarkPrompt>>,
+{
+    let cached: Option<ProjectPromptCache> = std::fs::read_to_string(cache_path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok());
+    if let Some(cache) = cached {
+        if cache.source_hash == hash {
+            return Ok(cache.prompts);
         }
+    }
 
-        let profile = ModelProfile::from_scores(self.model, scores);
-        Ok(ModelProfileWithMeta {
-            model: profile.model,
-            scores: profile.scores,
-            category_scores: profile.category_scores,
-            total_score: profile.total_score,
-            avg_latency_ms: profile.avg_latency_ms,
-            total_tokens: profile.total_tokens,
-            cost_estimate: profile.cost_estimate,
-            total_prompt_tokens: 0, // TODO: track separately
-            total_completion_tokens: 0,
-            total_time_secs: 0.0,
-        })
+    let cache = ProjectPromptCache {
+        source_hash: hash.to_string(),
+        prompts: generate()?,
+    };
+    if let Some(parent) = cache_path.parent() {
+        std::fs::create_dir_all(parent)?;
     }
+    std::fs::write(cache_path, serde_json::to_string(&cache)?)?;
+    Ok(cache.prompts)
+}
 
-    async fn run_single_prompt(&self, prompt: &BenchmarkPrompt) -> anyhow::Result<String> {
-        use crate::client;
+/// Prompts drawn from the project itself: its TODOs and lint findings to
+/// resolve, its largest module to split, its least documented module to
+/// document and an untested one to test. Expected elements are names from
+/// the code sent along, so a good answer has to engage with it.
+pub fn project_prompts(analysis: &CodebaseAnalysis, root: &Path) -> Vec<BenchmarkPrompt> {
+    let read = |path: &Path| std::fs::read_to_string(root.join(path)).ok();
+    let relative = |path: &Path| {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    let mut prompts = Vec::new();
+
+    let todos = analysis
+        .todos
+        .iter()
+        .filter(|t| t.priority != TodoPriority::Low);
+    for todo in todos.take(MAX_TODO_PROMPTS) {
+        let Some(source) = read(&todo.file) else {
+            continue;
+        };
+        let file = relative(&todo.file);
+        let context = excerpt(&source, todo.line);
+        let mut expected = fn_names(&context, 3);
+        if expected.is_empty() {
+            expected.push(module_name(&file));
+        }
+        prompts.push(BenchmarkPrompt {
+            id: format!("self-todo-{}:{}", file, todo.line),
+            category: todo_category(&todo.text),
+            prompt: format!(
+                "Resolve this note in {} (line {}): {}\nExplain the fix and show the changed code.",
+                file, todo.line, todo.text
+            ),
+            context: Some(context),
+            expected_elements: expected,
+            negative_elements: vec!["no changes".into()],
+            max_tokens: 800,
+            difficulty: match todo.priority {
+                TodoPriority::High => Difficulty::Hard,
+                _ => Difficulty::Medium,
+            },
+        });
+    }
 
-        let full_prompt = if let Some(ctx) = &prompt.context {
-            format!(
-                "You are a code assistant helping with housekeeping tasks. \
-                     Be concise and specific.\n\n{}\n\nContext:\n{}",
-                prompt.prompt, ctx
-            )
-        } else {
-            format!(
-                "You are a code assistant helping with housekeeping tasks. \
-                     Be concise and specific.\n\n{}",
-                prompt.prompt
-            )
+    for finding in analysis.lint_findings.iter().take(MAX_LINT_PROMPTS) {
+        let Some(source) = read(Path::new(&finding.file)) else {
+            continue;
         };
+        let lint = finding.lint.trim_start_matches("clippy::").to_string();
+        prompts.push(BenchmarkPrompt {
+            id: format!("self-lint-{}:{}", finding.file, finding.line),
+            category: TaskCategory::CodeCleanup,
+            prompt: format!(
+                "Fix this {} in {} (line {}): {} ({}). Show the corrected code.",
+                match finding.severity {
+                    crate::bootstrap::LintSeverity::Error => "error",
+                    crate::bootstrap::LintSeverity::Warning => "warning",
+                },
+                finding.file,
+                finding.line,
+                finding.message,
+                finding.lint
+            ),
+            context: Some(excerpt(&source, finding.line)),
+            expected_elements: vec![lint],
+            negative_elements: vec!["#[allow".into()],
+            max_tokens: 500,
+            difficulty: Difficulty::Easy,
+        });
+    }
+
+    // Modules come largest first
+    let refactor = analysis
+        .modules
+        .first()
+        .filter(|m| m.lines >= REFACTOR_MIN_LINES);
+    if let Some(module) = refactor {
+        if let Some(source) = read(&module.path) {
+            let file = relative(&module.path);
+            let context = truncate(&outline(&source));
+            let mut expected = fn_names(&context, 4);
+            expected.push("module".into());
+            prompts.push(BenchmarkPrompt {
+                id: format!("self-refactor-{}", file),
+                category: TaskCategory::Structure,
+                prompt: format!(
+                    "{} has {} lines. Suggest how to split it into smaller modules: name each new module and the items it would hold. The context is its outline.",
+                    file, module.lines
+                ),
+                context: Some(context),
+                expected_elements: expected,
+                negative_elements: vec!["no changes".into()],
+                max_tokens: 800,
+                difficulty: Difficulty::Hard,
+            });
+        }
+    }
 
-        let response = client::chat_completion_simple(
-            self.api_key,
-            self.model,
-            &full_prompt,
-            prompt.max_tokens,
-        )
-        .await?;
+    let least_documented = analysis
+        .modules
+        .iter()
+        .filter(|m| m.lines >= 50)
+        .min_by(|a, b| a.doc_coverage.total_cmp(&b.doc_coverage));
+    if let Some(module) = least_documented {
+        if let Some(source) = read(&module.path) {
+            let context = truncate(&source);
+            let mut expected = undocumented_items(&context, 4);
+            if !expected.is_empty() {
+                let file = relative(&module.path);
+                expected.push("///".into());
+                prompts.push(BenchmarkPrompt {
+                    id: format!("self-docs-{}", file),
+                    category: TaskCategory::Documentation,
+                    prompt: format!(
+                        "Write `///` doc comments for the undocumented public items in {}.",
+                        file
+                    ),
+                    context: Some(context),
+                    expected_elements: expected,
+                    negative_elements: vec!["well documented".into()],
+                    max_tokens: 800,
+                    difficulty: Difficulty::Easy,
+                });
+            }
+        }
+    }
 
-        Ok(response)
+    let untested = analysis
+        .modules
+        .iter()
+        .filter(|m| m.tests == 0 && m.functions >= 3)
+        .max_by_key(|m| m.functions);
+    if let Some(module) = untested {
+        if let Some(source) = read(&module.path) {
+            let file = relative(&module.path);
+            let context = truncate(&source);
+            let mut expected = fn_names(&context, 2);
+            expected.extend(["#[test]".to_string(), "assert".to_string()]);
+            prompts.push(BenchmarkPrompt {
+                id: format!("self-tests-{}", file),
+                category: TaskCategory::Testing,
+                prompt: format!(
+                    "{} has no tests. Write unit tests for its main functions, covering edge cases and error paths.",
+                    file
+                ),
+                context: Some(context),
+                expected_elements: expected,
+                negative_elements: vec!["complete coverage".into()],
+                max_tokens: 1000,
+                difficulty: Difficulty::Medium,
+            });
+        }
     }
+
+    prompts
 }
 
-/// Extended profile with metadata about the run
-pub struct ModelProfileWithMeta {
-    pub model: String,
-    pub scores: Vec<ResponseScore>,
-    pub category_scores: HashMap<TaskCategory, f64>,
-    pub total_score: f64,
-    pub avg_latency_ms: u64,
-    pub total_tokens: u32,
-    pub cost_estimate: f64,
-    pub total_prompt_tokens: u32,
-    pub total_completion_tokens: u32,
-    pub total_time_secs: f64,
+/// Category a TODO's wording suggests
+fn todo_category(text: &str) -> TaskCategory {
+    let lower = text.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|w| lower.contains(w));
+    if has(&["test", "coverage"]) {
+        TaskCategory::Testing
+    } else if has(&["doc", "comment", "explain"]) {
+        TaskCategory::Documentation
+    } else if has(&["unsafe", "secur", "sanitiz", "escape", "inject"]) {
+        TaskCategory::Security
+    } else if has(&["slow", "perf", "alloc", "clone", "cache"]) {
+        TaskCategory::Performance
+    } else if has(&["split", "refactor", "extract", "reorganiz", "module"]) {
+        TaskCategory::Structure
+    } else {
+        TaskCategory::CodeCleanup
+    }
 }
 
-impl ModelProfileWithMeta {
-    pub fn grade(&self) -> &'static str {
-        let avg = self.total_score / self.scores.len().max(1) as f64;
-        match avg {
-            x if x >= 2.5 => "A+",
-            x if x >= 2.0 => "A",
-            x if x >= 1.7 => "B+",
-            x if x >= 1.4 => "B",
-            x if x >= 1.1 => "C+",
-            x if x >= 0.8 => "C",
-            x if x >= 0.5 => "D",
-            _ => "F",
+/// Numbered lines around 1-based `line`
+fn excerpt(source: &str, line: usize) -> String {
+    let start = line.saturating_sub(EXCERPT_LINES + 1);
+    let text: String = source
+        .lines()
+        .enumerate()
+        .skip(start)
+        .take(EXCERPT_LINES * 2 + 1)
+        .map(|(i, l)| format!("{:>5} | {}\n", i + 1, l))
+        .collect();
+    truncate(&text)
+}
+
+/// At most MAX_CONTEXT_CHARS, cut at a line break
+fn truncate(text: &str) -> String {
+    if text.len() <= MAX_CONTEXT_CHARS {
+        return text.to_string();
+    }
+    let mut end = MAX_CONTEXT_CHARS;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let end = text[..end].rfind('\n').map_or(end, |i| i + 1);
+    text[..end].to_string()
+}
+
+/// Item declarations only: a long module's shape without its bodies
+fn outline(source: &str) -> String {
+    const ITEMS: &[&str] = &[
+        "fn ", "struct ", "enum ", "trait ", "impl", "mod ", "const ",
+    ];
+    source
+        .lines()
+        .filter(|l| {
+            let item = l.trim_start().trim_start_matches("pub(crate) ");
+            let item = item.trim_start_matches("pub ").trim_start_matches("async ");
+            ITEMS.iter().any(|i| item.starts_with(i))
+        })
+        .map(|l| format!("{}\n", l.trim_end().trim_end_matches('{').trim_end()))
+        .collect()
+}
+
+/// Identifier following `keyword` on a line, e.g. `fn parse(` -> `parse`
+fn item_name<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = &line[line.find(keyword)? + keyword.len()..];
+    let name = rest
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()?;
+    (!name.is_empty()).then_some(name)
+}
+
+/// Up to `limit` distinct function names declared in `source`, skipping
+/// tests and `main`
+fn fn_names(source: &str, limit: usize) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for line in source.lines() {
+        let Some(name) = item_name(line, "fn ") else {
+            continue;
+        };
+        if name == "main" || name.starts_with("test_") || names.iter().any(|n| n == name) {
+            continue;
+        }
+        names.push(name.to_string());
+        if names.len() == limit {
+            break;
         }
     }
+    names
 }
 
-/// Estimate token count from text (rough approximation)
-fn estimate_tokens(text: &str) -> u32 {
-    // Rough estimate: ~4 chars per token for English
-    (text.len() as f64 / 4.0).ceil() as u32
+/// Up to `limit` public fns, structs and enums with no `///` above them
+fn undocumented_items(source: &str, limit: usize) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut documented = false;
+    for line in source.lines().map(str::trim_start) {
+        if line.starts_with("///") {
+            documented = true;
+            continue;
+        }
+        if line.starts_with("#[") {
+            continue;
+        }
+        if !documented {
+            let name = ["pub fn ", "pub async fn ", "pub struct ", "pub enum "]
+                .iter()
+                .filter(|k| line.starts_with(*k))
+                .find_map(|k| item_name(line, k));
+            if let Some(name) = name {
+                names.push(name.to_string());
+                if names.len() == limit {
+                    break;
+                }
+            }
+        }
+        documented = false;
+    }
+    names
 }
 
+/// `src/ui.rs` -> `ui`
+fn module_name(file: &str) -> String {
+    Path::new(file)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| file.to_string())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// EXPORT
+// ═══════════════════════════════════════════════════════════════
+
+/// Output format for `hyle benchmark --format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn parse(name: &str) -> anyhow::Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "text" | "txt" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            other => anyhow::bail!(
+                "Unknown format '{}' (expected text, json or markdown)",
+                other
+            ),
+        }
+    }
+}
+
+impl BenchmarkResult {
+    pub fn render(&self, format: ReportFormat) -> anyhow::Result<String> {
+        Ok(match format {
+            ReportFormat::Text => self.render_full_report(),
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Markdown => self.render_markdown(),
+        })
+    }
+
+    fn render_markdown(&self) -> String {
+        let mut md = String::from("| Model | Grade | Score | Avg latency | Tokens | Errors |\n");
+        md.push_str("|---|---|---:|---:|---:|---:|\n");
+        for p in &self.profiles {
+            md.push_str(&format!(
+                "| {}{} | {} | {:.2} | {}ms | {} | {} |\n",
+                p.model,
+                if p.model == self.winner { " 👑" } else { "" },
+                p.grade(),
+                p.total_score,
+                p.avg_latency_ms,
+                p.total_tokens,
+                p.errors
+            ));
+        }
+        md
+    }
+
+    /// Load a result previously saved with `--format json`
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Keep this run as the one the model picker badges from
+    pub fn save_latest(&self) -> anyhow::Result<std::path::PathBuf> {
+        let path = latest_path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    /// Average weighted score per prompt, by model (models with no scores are left out)
+    pub fn average_scores(&self) -> HashMap<String, f64> {
+        self.profiles
+            .iter()
+            .filter(|p| !p.scores.is_empty())
+            .map(|p| (p.model.clone(), p.total_score / p.scores.len() as f64))
+            .collect()
+    }
+
+    /// Per-model score change against `baseline` (models missing from it are marked new)
+    pub fn compare_to(&self, baseline: &BenchmarkResult) -> String {
+        let mut out = String::from("Against baseline:\n");
+        for p in &self.profiles {
+            match baseline.profiles.iter().find(|b| b.model == p.model) {
+                Some(b) => out.push_str(&format!(
+                    "  {:30} {:6.2} ({:+.2}, was {} → now {})\n",
+                    p.model,
+                    p.total_score,
+                    p.total_score - b.total_score,
+                    b.grade(),
+                    p.grade()
+                )),
+                None => out.push_str(&format!("  {:30} {:6.2} (new)\n", p.model, p.total_score)),
+            }
+        }
+        out
+    }
+}
+
+fn latest_path() -> anyhow::Result<std::path::PathBuf> {
+    Ok(crate::config::cache_dir()?.join("benchmark.json"))
+}
+
+/// Per-model scores from the last `hyle benchmark` run, if any
+pub fn latest_scores() -> HashMap<String, f64> {
+    latest_path()
+        .and_then(|path| BenchmarkResult::load(&path))
+        .map(|result| result.average_scores())
+        .unwrap_or_default()
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TESTS
+// ═══════════════════════════════════════════════════════════════
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1117,4 +1627,303 @@ mod tests {
         let result = BenchmarkResult::new(profiles);
         assert_eq!(result.winner, "model-b");
     }
+
+    /// Stub client: "good" answers with the prompt's expected elements,
+    /// "bad" with filler, anything else fails
+    async fn run_stub(config: BenchmarkConfig, models: &[&str]) -> BenchmarkResult {
+        let runner =
+            BenchmarkRunner::new(config, |model: String, prompt: String, _max| async move {
+                match model.as_str() {
+                    "good" => Ok(PromptSet::new()
+                        .all()
+                        .iter()
+                        .find(|p| prompt.contains(&p.prompt))
+                        .map(|p| p.expected_elements.join("\n"))
+                        .unwrap_or_default()),
+                    "bad" => Ok("Looks fine to me.".to_string()),
+                    _ => anyhow::bail!("503 from provider"),
+                }
+            });
+        let models: Vec<String> = models.iter().map(|m| m.to_string()).collect();
+        runner.run(&models).await
+    }
+
+    #[tokio::test]
+    async fn test_runner_builds_profiles_per_model() {
+        let config = BenchmarkConfig {
+            categories: vec![TaskCategory::Security, TaskCategory::Testing],
+            ..Default::default()
+        };
+        let expected = PromptSet::new()
+            .all()
+            .iter()
+            .filter(|p| config.categories.contains(&p.category))
+            .count();
+        let result = run_stub(config, &["bad", "good", "down"]).await;
+
+        assert_eq!(result.profiles.len(), 3);
+        assert_eq!(result.winner, "good");
+        let good = &result.profiles[1];
+        assert_eq!(good.scores.len(), expected);
+        assert_eq!(good.errors, 0);
+        assert!(good.total_score > result.profiles[0].total_score);
+        assert!(good
+            .category_scores
+            .keys()
+            .all(|c| matches!(c, TaskCategory::Security | TaskCategory::Testing)));
+
+        let down = &result.profiles[2];
+        assert!(down.scores.is_empty());
+        assert_eq!(down.errors, expected);
+    }
+
+    #[tokio::test]
+    async fn test_runner_times_out_slow_models() {
+        let config = BenchmarkConfig {
+            categories: vec![TaskCategory::GitHygiene],
+            timeout: Duration::from_millis(20),
+            ..Default::default()
+        };
+        let runner = BenchmarkRunner::new(config, |_model: String, _prompt, _max| async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(String::new())
+        });
+        let result = runner.run(&["slow".to_string()]).await;
+        assert!(result.profiles[0].scores.is_empty());
+        assert_eq!(result.profiles[0].errors, runner.prompts().len());
+    }
+
+    #[test]
+    fn test_parse_categories() {
+        assert_eq!(
+            parse_categories("security, docs,git-hygiene,security").unwrap(),
+            vec![
+                TaskCategory::Security,
+                TaskCategory::Documentation,
+                TaskCategory::GitHygiene
+            ]
+        );
+        assert!(parse_categories("security,astrology").is_err());
+        assert!(parse_categories(" , ").is_err());
+    }
+
+    #[test]
+    fn test_select_models() {
+        let model = |id: &str, ctx: u32, price: f64| Model {
+            id: id.into(),
+            name: id.into(),
+            context_length: ctx,
+            pricing_prompt: price,
+            pricing_completion: price,
+            pricing_cache_read: None,
+            pricing_cache_write: None,
+            capabilities: vec![],
+        };
+        let catalog = vec![
+            model("paid/big", 200_000, 1.0),
+            model("free/a", 8_000, 0.0),
+            model("free/b", 128_000, 0.0),
+        ];
+
+        // No request: largest free models first
+        assert_eq!(select_models(&[], true, &catalog), vec!["free/b", "free/a"]);
+
+        let requested = vec!["paid/big".to_string(), "free/a".to_string()];
+        assert_eq!(select_models(&requested, true, &catalog), vec!["free/a"]);
+        assert_eq!(select_models(&requested, false, &catalog), requested);
+    }
+
+    #[tokio::test]
+    async fn test_render_and_baseline() {
+        let config = BenchmarkConfig {
+            categories: vec![TaskCategory::Security],
+            ..Default::default()
+        };
+        let result = run_stub(config, &["good", "bad"]).await;
+
+        let json = result.render(ReportFormat::Json).unwrap();
+        let dir = std::env::temp_dir().join(format!("hyle_test_bench_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("baseline.json");
+        std::fs::write(&path, &json).unwrap();
+        let baseline = BenchmarkResult::load(&path).unwrap();
+        assert_eq!(baseline.winner, "good");
+
+        let md = result.render(ReportFormat::Markdown).unwrap();
+        assert!(md.contains("| good 👑 |"));
+        assert_eq!(ReportFormat::parse("md").unwrap(), ReportFormat::Markdown);
+        assert!(ReportFormat::parse("yaml").is_err());
+
+        let averages = result.average_scores();
+        assert!(averages["good"] > averages["bad"]);
+
+        let mut rerun = result.clone();
+        rerun.profiles.retain(|p| p.model == "good");
+        rerun
+            .profiles
+            .push(ModelProfile::from_scores("fresh", vec![]));
+        let diff = rerun.compare_to(&baseline);
+        assert!(diff.contains("(+0.00"));
+        assert!(diff.contains("fresh"));
+        assert!(diff.contains("(new)"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    fn fixture_analysis(root: &Path) -> CodebaseAnalysis {
+        use crate::bootstrap::{LintFinding, LintSeverity, ModuleInfo, TodoItem};
+
+        let src = root.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        let big = format!(
+            "pub fn alpha() {{}}\nfn beta() {{}}\n{}",
+            "// filler\n".repeat(318)
+        );
+        std::fs::write(src.join("big.rs"), big).unwrap();
+        let small = format!(
+            "//! Small module\npub struct Widget;\n\npub fn render(w: &Widget) -> String {{\n    \
+             // TODO: handle empty widgets\n    String::new()\n}}\n\n/// Documented\n\
+             pub fn documented() {{}}\n\nfn helper() {{}}\n{}",
+            "// filler\n".repeat(50)
+        );
+        std::fs::write(src.join("small.rs"), small).unwrap();
+
+        let module = |name: &str, lines, functions, doc_coverage| ModuleInfo {
+            name: name.into(),
+            path: src.join(format!("{}.rs", name)),
+            lines,
+            functions,
+            tests: 0,
+            doc_coverage,
+            dependencies: vec![],
+        };
+        let todo = |line, text: &str, priority| TodoItem {
+            file: src.join("small.rs"),
+            line,
+            text: text.into(),
+            priority,
+        };
+        CodebaseAnalysis {
+            modules: vec![module("big", 320, 2, 0.5), module("small", 62, 4, 0.1)],
+            total_lines: 382,
+            test_count: 0,
+            dead_code_warnings: 0,
+            todos: vec![
+                todo(5, "// TODO: handle empty widgets", TodoPriority::Medium),
+                todo(1, "// NOTE: small", TodoPriority::Low),
+            ],
+            lint_findings: vec![LintFinding {
+                file: "src/big.rs".into(),
+                line: 2,
+                lint: "clippy::needless_return".into(),
+                severity: LintSeverity::Warning,
+                message: "unneeded `return` statement".into(),
+            }],
+            health_score: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_project_prompts_from_fixture_analysis() {
+        let root =
+            std::env::temp_dir().join(format!("hyle_test_bench_self_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let prompts = project_prompts(&fixture_analysis(&root), &root);
+
+        let ids: Vec<&str> = prompts.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "self-todo-src/small.rs:5",
+                "self-lint-src/big.rs:2",
+                "self-refactor-src/big.rs",
+                "self-docs-src/small.rs",
+                "self-tests-src/small.rs"
+            ]
+        );
+        for prompt in &prompts {
+            assert!(!prompt.prompt.is_empty(), "{}", prompt.id);
+            assert!(!prompt.expected_elements.is_empty(), "{}", prompt.id);
+            assert!(prompt.max_tokens > 0);
+            let context = prompt.context.as_deref().unwrap();
+            assert!(!context.is_empty() && context.len() <= MAX_CONTEXT_CHARS);
+        }
+        // Prompts carry the real code and expect its names back
+        let todo = &prompts[0];
+        assert_eq!(todo.category, TaskCategory::CodeCleanup);
+        assert!(todo.context.as_deref().unwrap().contains("    5 | "));
+        assert_eq!(
+            todo.expected_elements,
+            vec!["render", "documented", "helper"]
+        );
+        assert_eq!(prompts[1].expected_elements, vec!["needless_return"]);
+        assert_eq!(prompts[2].category, TaskCategory::Structure);
+        assert_eq!(
+            prompts[2].expected_elements,
+            vec!["alpha", "beta", "module"]
+        );
+        assert_eq!(
+            prompts[3].expected_elements,
+            vec!["Widget", "render", "///"]
+        );
+        assert!(prompts[4]
+            .expected_elements
+            .contains(&"#[test]".to_string()));
+
+        // The runner takes them in place of the samples
+        let config = BenchmarkConfig {
+            categories: vec![TaskCategory::Structure],
+            ..Default::default()
+        };
+        let runner = BenchmarkRunner::new(config, |_m: String, _p, _t| async { Ok(String::new()) })
+            .with_prompts(PromptSet::from_prompts(prompts));
+        assert_eq!(runner.prompts().len(), 1);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_todo_category() {
+        assert_eq!(
+            todo_category("// TODO: add tests for x"),
+            TaskCategory::Testing
+        );
+        assert_eq!(
+            todo_category("// FIXME: sanitize input"),
+            TaskCategory::Security
+        );
+        assert_eq!(
+            todo_category("// TODO: avoid this clone"),
+            TaskCategory::Performance
+        );
+        assert_eq!(
+            todo_category("// TODO: delete this"),
+            TaskCategory::CodeCleanup
+        );
+    }
+
+    #[test]
+    fn test_cached_project_prompts_regenerate_on_change() {
+        let dir =
+            std::env::temp_dir().join(format!("hyle_test_bench_cache_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache_path = dir.join("bench_self.json");
+        let generated = std::cell::Cell::new(0);
+        let generate = || {
+            generated.set(generated.get() + 1);
+            Ok(PromptSet::new().all()[..2].to_vec())
+        };
+
+        let first = cached_project_prompts(&cache_path, "aaa", generate).unwrap();
+        let again = cached_project_prompts(&cache_path, "aaa", generate).unwrap();
+        assert_eq!(generated.get(), 1);
+        assert_eq!(
+            first.iter().map(|p| &p.id).collect::<Vec<_>>(),
+            again.iter().map(|p| &p.id).collect::<Vec<_>>()
+        );
+        cached_project_prompts(&cache_path, "bbb", generate).unwrap();
+        assert_eq!(generated.get(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -12,6 +12,8 @@
 #![allow(dead_code)] // Forward-looking module for self-bootstrapping
 
 use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
@@ -326,9 +328,22 @@ pub struct CodebaseAnalysis {
     pub test_count: usize,
     pub dead_code_warnings: usize,
     pub todos: Vec<TodoItem>,
+    pub lint_findings: Vec<LintFinding>,
     pub health_score: f32, // 0.0 to 1.0
 }
 
+impl CodebaseAnalysis {
+    /// (warnings, errors) among the lint findings
+    pub fn lint_counts(&self) -> (usize, usize) {
+        let errors = self
+            .lint_findings
+            .iter()
+            .filter(|f| f.severity == LintSeverity::Error)
+            .count();
+        (self.lint_findings.len() - errors, errors)
+    }
+}
+
 /// Info about a module
 #[derive(Debug, Clone)]
 pub struct ModuleInfo {
@@ -357,6 +372,149 @@ pub enum TodoPriority {
     Low,    // NOTE, IDEA
 }
 
+/// Classify a source line as a TODO-style marker, if it is one
+pub fn classify_todo(line: &str) -> Option<TodoPriority> {
+    let upper = line.to_uppercase();
+    if upper.contains("FIXME") || upper.contains("XXX") || upper.contains("HACK") {
+        Some(TodoPriority::High)
+    } else if upper.contains("TODO") {
+        Some(TodoPriority::Medium)
+    } else if upper.contains("NOTE:") || upper.contains("IDEA:") {
+        Some(TodoPriority::Low)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Warning,
+    Error,
+}
+
+/// A clippy/rustc diagnostic pinned to a source location
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LintFinding {
+    pub file: String,
+    pub line: usize,
+    /// Lint or error code, e.g. "clippy::needless_return" or "E0308"
+    pub lint: String,
+    pub severity: LintSeverity,
+    pub message: String,
+}
+
+/// Parse `cargo clippy --message-format=json` output into findings.
+/// Non-diagnostic lines, notes and location-less summaries are skipped, and
+/// diagnostics reported once per target (lib + bin) are deduplicated.
+pub fn parse_clippy_json(stream: &str) -> Vec<LintFinding> {
+    let mut seen = std::collections::HashSet::new();
+    let mut findings = Vec::new();
+
+    for line in stream.lines() {
+        let value: serde_json::Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if value["reason"] != "compiler-message" {
+            continue;
+        }
+        let message = &value["message"];
+        let severity = match message["level"].as_str() {
+            Some("warning") => LintSeverity::Warning,
+            Some("error") => LintSeverity::Error,
+            _ => continue,
+        };
+        let spans = message["spans"].as_array().cloned().unwrap_or_default();
+        let span = match spans
+            .iter()
+            .find(|s| s["is_primary"].as_bool().unwrap_or(false))
+        {
+            Some(span) => span,
+            None => continue,
+        };
+
+        let finding = LintFinding {
+            file: span["file_name"].as_str().unwrap_or("").to_string(),
+            line: span["line_start"].as_u64().unwrap_or(0) as usize,
+            lint: message["code"]["code"]
+                .as_str()
+                .unwrap_or("rustc")
+                .to_string(),
+            severity,
+            message: message["message"].as_str().unwrap_or("").to_string(),
+        };
+        if seen.insert(finding.clone()) {
+            findings.push(finding);
+        }
+    }
+    findings
+}
+
+/// Clippy results for one state of the source tree
+#[derive(Debug, Serialize, Deserialize)]
+struct LintCache {
+    source_hash: String,
+    findings: Vec<LintFinding>,
+}
+
+/// Hash of every .rs file under `root/src` plus Cargo.toml, for cache keys
+pub fn source_hash(root: &Path) -> String {
+    fn collect(dir: &Path, files: &mut Vec<PathBuf>) {
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let path = entry.path();
+                if path.is_dir() {
+                    collect(&path, files);
+                } else if path.extension().map(|e| e == "rs").unwrap_or(false) {
+                    files.push(path);
+                }
+            }
+        }
+    }
+
+    let mut files = vec![root.join("Cargo.toml")];
+    collect(&root.join("src"), &mut files);
+    files.sort();
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for file in &files {
+        file.hash(&mut hasher);
+        std::fs::read(file).unwrap_or_default().hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// Findings for `hash` from `cache_path`, or from `run_clippy` (then cached)
+pub fn cached_lint_findings<F>(
+    cache_path: &Path,
+    hash: &str,
+    run_clippy: F,
+) -> Result<Vec<LintFinding>>
+where
+    F: FnOnce() -> Result<String>,
+{
+    let cached: Option<LintCache> = std::fs::read_to_string(cache_path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok());
+    if let Some(cache) = cached {
+        if cache.source_hash == hash {
+            return Ok(cache.findings);
+        }
+    }
+
+    let findings = parse_clippy_json(&run_clippy()?);
+    let cache = LintCache {
+        source_hash: hash.to_string(),
+        findings,
+    };
+    if let Some(parent) = cache_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(cache_path, serde_json::to_string(&cache)?)?;
+    Ok(cache.findings)
+}
+
 /// Self-analyzer for hyle codebase
 pub struct SelfAnalyzer {
     project: Project,
@@ -368,6 +526,11 @@ impl SelfAnalyzer {
         Ok(Self { project })
     }
 
+    /// Analyzer for another project, e.g. for `hyle summarize`
+    pub fn for_project(project: Project) -> Self {
+        Self { project }
+    }
+
     /// Full codebase analysis
     pub fn analyze(&self) -> Result<CodebaseAnalysis> {
         let modules = self.analyze_modules()?;
@@ -375,6 +538,8 @@ impl SelfAnalyzer {
         let test_count = self.count_tests()?;
         let dead_code_warnings = self.count_dead_code()?;
         let todos = self.find_todos()?;
+        // Clippy may not be installed; analysis still works without it
+        let lint_findings = self.lint_findings().unwrap_or_default();
 
         // Calculate health score
         let test_ratio = (test_count as f32 / modules.len() as f32).min(10.0) / 10.0;
@@ -385,9 +550,18 @@ impl SelfAnalyzer {
             .count() as f32
             / 10.0)
             .min(0.2);
+        let lint_penalty = lint_findings
+            .iter()
+            .map(|f| match f.severity {
+                LintSeverity::Error => 0.05,
+                LintSeverity::Warning => 0.01,
+            })
+            .sum::<f32>()
+            .min(0.2);
 
         let health_score =
-            (0.5 + test_ratio * 0.3 - dead_code_penalty - todo_penalty).clamp(0.0, 1.0);
+            (0.5 + test_ratio * 0.3 - dead_code_penalty - todo_penalty - lint_penalty)
+                .clamp(0.0, 1.0);
 
         Ok(CodebaseAnalysis {
             modules,
@@ -395,12 +569,46 @@ impl SelfAnalyzer {
             test_count,
             dead_code_warnings,
             todos,
+            lint_findings,
             health_score,
         })
     }
 
-    /// Analyze individual modules
-    fn analyze_modules(&self) -> Result<Vec<ModuleInfo>> {
+    /// Project root being analyzed
+    pub fn root(&self) -> &Path {
+        &self.project.root
+    }
+
+    /// Pick the next fix and build the prompt asking the model for its diff
+    pub fn improvement_task(&self) -> Result<Option<(ImprovementTarget, String)>> {
+        let analysis = self.analyze()?;
+        let target = match analysis.next_target() {
+            Some(target) => target,
+            None => return Ok(None),
+        };
+        let file = target.file(&self.project.root);
+        let source = std::fs::read_to_string(self.project.root.join(&file))
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let prompt = fix_prompt(&target, &file, &source);
+        Ok(Some((target, prompt)))
+    }
+
+    /// Clippy findings, re-running clippy only when the source changed
+    pub fn lint_findings(&self) -> Result<Vec<LintFinding>> {
+        let cache_path = crate::config::cache_dir()?.join("clippy.json");
+        let hash = source_hash(&self.project.root);
+        cached_lint_findings(&cache_path, &hash, || {
+            let output = Command::new("cargo")
+                .args(["clippy", "--all-targets", "--message-format=json"])
+                .current_dir(&self.project.root)
+                .output()
+                .context("Failed to run cargo clippy")?;
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+    }
+
+    /// Analyze individual modules, largest first
+    pub fn analyze_modules(&self) -> Result<Vec<ModuleInfo>> {
         let mut modules = Vec::new();
         let src_dir = self.project.root.join("src");
 
@@ -426,17 +634,8 @@ impl SelfAnalyzer {
                             .count();
                         let doc_coverage = (doc_lines as f32 / lines.max(1) as f32).min(1.0);
 
-                        // Extract dependencies (use statements)
-                        let dependencies: Vec<String> = content
-                            .lines()
-                            .filter(|l| l.starts_with("use crate::"))
-                            .filter_map(|l| {
-                                l.strip_prefix("use crate::")
-                                    .map(|s| s.split(':').next().unwrap_or(s))
-                                    .map(|s| s.split(';').next().unwrap_or(s))
-                                    .map(|s| s.to_string())
-                            })
-                            .collect();
+                        // Extract dependencies (use statements and inline paths)
+                        let dependencies = module_dependencies(&content);
 
                         modules.push(ModuleInfo {
                             name,
@@ -491,21 +690,7 @@ impl SelfAnalyzer {
                 if path.extension().map(|e| e == "rs").unwrap_or(false) {
                     if let Ok(content) = std::fs::read_to_string(&path) {
                         for (i, line) in content.lines().enumerate() {
-                            let upper = line.to_uppercase();
-                            let priority = if upper.contains("FIXME")
-                                || upper.contains("XXX")
-                                || upper.contains("HACK")
-                            {
-                                Some(TodoPriority::High)
-                            } else if upper.contains("TODO") {
-                                Some(TodoPriority::Medium)
-                            } else if upper.contains("NOTE:") || upper.contains("IDEA:") {
-                                Some(TodoPriority::Low)
-                            } else {
-                                None
-                            };
-
-                            if let Some(p) = priority {
+                            if let Some(p) = classify_todo(line) {
                                 todos.push(TodoItem {
                                     file: path.clone(),
                                     line: i + 1,
@@ -537,6 +722,11 @@ impl SelfAnalyzer {
         Ok(graph)
     }
 
+    /// Module import graph, for cycle and coupling checks
+    pub fn module_graph(&self) -> Result<ModuleGraph> {
+        Ok(ModuleGraph::from_modules(&self.analyze_modules()?))
+    }
+
     /// Generate LLM prompt for improvement suggestions
     pub fn improvement_prompt(&self) -> Result<String> {
         let analysis = self.analyze()?;
@@ -583,6 +773,24 @@ impl SelfAnalyzer {
             ));
         }
 
+        if !analysis.lint_findings.is_empty() {
+            prompt.push_str("\n## Lint Findings\n");
+            for finding in analysis.lint_findings.iter().take(10) {
+                prompt.push_str(&format!(
+                    "- {}:{}: [{}] {}\n",
+                    finding.file, finding.line, finding.lint, finding.message
+                ));
+            }
+        }
+
+        let cycles = ModuleGraph::from_modules(&analysis.modules).cycles();
+        if !cycles.is_empty() {
+            prompt.push_str("\n## Import Cycles\n");
+            for cycle in &cycles {
+                prompt.push_str(&format!("- {}\n", cycle.join(" <-> ")));
+            }
+        }
+
         prompt.push_str("\n## Suggestions Requested\n");
         prompt.push_str("1. Which modules need more tests?\n");
         prompt.push_str("2. Which modules have too many responsibilities?\n");
@@ -603,6 +811,13 @@ impl std::fmt::Display for CodebaseAnalysis {
         writeln!(f, "Total Lines: {}", self.total_lines)?;
         writeln!(f, "Tests: {}", self.test_count)?;
         writeln!(f, "Dead Code Warnings: {}", self.dead_code_warnings)?;
+        let (warnings, errors) = self.lint_counts();
+        writeln!(f, "Lint: {} warnings, {} errors", warnings, errors)?;
+        writeln!(
+            f,
+            "Import Cycles: {}",
+            ModuleGraph::from_modules(&self.modules).cycles().len()
+        )?;
         writeln!(
             f,
             "TODOs: {} ({} high priority)",
@@ -616,6 +831,463 @@ impl std::fmt::Display for CodebaseAnalysis {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════
+// DEPENDENCY GRAPH
+// ═══════════════════════════════════════════════════════════════
+
+/// Modules referenced as `crate::<name>` in `content`, deduplicated
+pub fn module_dependencies(content: &str) -> Vec<String> {
+    let mut deps: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("//") {
+            continue;
+        }
+        for (idx, _) in line.match_indices("crate::") {
+            let rest = &line[idx + "crate::".len()..];
+            let name: String = rest
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            if !name.is_empty() && !deps.contains(&name) {
+                deps.push(name);
+            }
+        }
+    }
+    deps.sort();
+    deps
+}
+
+/// A module whose fan-in or fan-out stands out from the rest of the crate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coupling {
+    pub module: String,
+    pub fan_in: usize,
+    pub fan_out: usize,
+}
+
+/// Module import graph as adjacency lists
+#[derive(Debug, Clone, Default)]
+pub struct ModuleGraph {
+    names: Vec<String>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl ModuleGraph {
+    /// Build from `(from, to)` pairs; duplicate edges are dropped
+    pub fn from_edges<'a>(edges: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut graph = Self::default();
+        for (from, to) in edges {
+            let from = graph.node(from);
+            let to = graph.node(to);
+            if !graph.edges[from].contains(&to) {
+                graph.edges[from].push(to);
+            }
+        }
+        graph
+    }
+
+    /// Build from analyzed modules, keeping only edges between known modules
+    pub fn from_modules(modules: &[ModuleInfo]) -> Self {
+        let known: std::collections::HashSet<&str> =
+            modules.iter().map(|m| m.name.as_str()).collect();
+        let mut graph = Self::from_edges(modules.iter().flat_map(|m| {
+            m.dependencies
+                .iter()
+                .filter(|d| known.contains(d.as_str()) && **d != m.name)
+                .map(move |d| (m.name.as_str(), d.as_str()))
+        }));
+        // Modules with no edges still count towards the averages
+        for module in modules {
+            graph.node(&module.name);
+        }
+        graph
+    }
+
+    fn node(&mut self, name: &str) -> usize {
+        match self.names.iter().position(|n| n == name) {
+            Some(i) => i,
+            None => {
+                self.names.push(name.to_string());
+                self.edges.push(Vec::new());
+                self.names.len() - 1
+            }
+        }
+    }
+
+    /// Import cycles: strongly connected components with more than one
+    /// module (or a module importing itself), via Tarjan's algorithm
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: vec![None; self.names.len()],
+            lowlink: vec![0; self.names.len()],
+            on_stack: vec![false; self.names.len()],
+            stack: Vec::new(),
+            next_index: 0,
+            components: Vec::new(),
+        };
+        for node in 0..self.names.len() {
+            if tarjan.index[node].is_none() {
+                tarjan.visit(node);
+            }
+        }
+
+        let mut cycles: Vec<Vec<String>> = tarjan
+            .components
+            .into_iter()
+            .filter(|c| c.len() > 1 || self.edges[c[0]].contains(&c[0]))
+            .map(|c| {
+                let mut names: Vec<String> = c.iter().map(|&i| self.names[i].clone()).collect();
+                names.sort();
+                names
+            })
+            .collect();
+        cycles.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        cycles
+    }
+
+    /// Modules whose fan-in or fan-out is more than two standard deviations
+    /// above the crate average, most coupled first
+    pub fn coupling_hotspots(&self) -> Vec<Coupling> {
+        let n = self.names.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        let fan_out: Vec<usize> = self.edges.iter().map(Vec::len).collect();
+        let mut fan_in = vec![0; n];
+        for targets in &self.edges {
+            for &to in targets {
+                fan_in[to] += 1;
+            }
+        }
+
+        let threshold = |values: &[usize]| {
+            let mean = values.iter().sum::<usize>() as f64 / n as f64;
+            let variance = values
+                .iter()
+                .map(|&v| (v as f64 - mean).powi(2))
+                .sum::<f64>()
+                / n as f64;
+            mean + 2.0 * variance.sqrt()
+        };
+        let (in_limit, out_limit) = (threshold(&fan_in), threshold(&fan_out));
+
+        let mut hotspots: Vec<Coupling> = (0..n)
+            .filter(|&i| fan_in[i] as f64 > in_limit || fan_out[i] as f64 > out_limit)
+            .map(|i| Coupling {
+                module: self.names[i].clone(),
+                fan_in: fan_in[i],
+                fan_out: fan_out[i],
+            })
+            .collect();
+        hotspots.sort_by(|a, b| {
+            (b.fan_in + b.fan_out)
+                .cmp(&(a.fan_in + a.fan_out))
+                .then_with(|| a.module.cmp(&b.module))
+        });
+        hotspots
+    }
+
+    /// Cycle and coupling report for `/analyze` and `/deps`
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let cycles = self.cycles();
+        if cycles.is_empty() {
+            out.push_str("Import cycles: none\n");
+        } else {
+            out.push_str(&format!("Import cycles ({}):\n", cycles.len()));
+            for cycle in &cycles {
+                out.push_str(&format!("  {}\n", cycle.join(" ⇄ ")));
+            }
+        }
+        let hotspots = self.coupling_hotspots();
+        if !hotspots.is_empty() {
+            out.push_str("High coupling:\n");
+            for c in &hotspots {
+                out.push_str(&format!(
+                    "  {} (fan-in {}, fan-out {})\n",
+                    c.module, c.fan_in, c.fan_out
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// State for one run of Tarjan's strongly-connected-components algorithm
+struct Tarjan<'a> {
+    graph: &'a ModuleGraph,
+    index: Vec<Option<usize>>,
+    lowlink: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, node: usize) {
+        self.index[node] = Some(self.next_index);
+        self.lowlink[node] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+
+        for &next in &self.graph.edges[node] {
+            match self.index[next] {
+                None => {
+                    self.visit(next);
+                    self.lowlink[node] = self.lowlink[node].min(self.lowlink[next]);
+                }
+                Some(index) if self.on_stack[next] => {
+                    self.lowlink[node] = self.lowlink[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.lowlink[node]) == self.index[node] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// SELF-IMPROVEMENT LOOP
+// ═══════════════════════════════════════════════════════════════
+
+/// Modules below this doc-comment density count as documentation gaps
+const DOC_GAP_THRESHOLD: f32 = 0.02;
+
+/// The single thing `/improve apply` will try to fix
+#[derive(Debug, Clone)]
+pub enum ImprovementTarget {
+    Lint(LintFinding),
+    Todo(TodoItem),
+    DocGap(ModuleInfo),
+}
+
+impl ImprovementTarget {
+    /// Source file to fix, relative to `root` when it lives under it
+    pub fn file(&self, root: &Path) -> PathBuf {
+        let path = match self {
+            ImprovementTarget::Lint(f) => root.join(&f.file),
+            ImprovementTarget::Todo(t) => t.file.clone(),
+            ImprovementTarget::DocGap(m) => m.path.clone(),
+        };
+        path.strip_prefix(root)
+            .map(Path::to_path_buf)
+            .unwrap_or(path)
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ImprovementTarget::Lint(f) => {
+                format!("{}:{}: [{}] {}", f.file, f.line, f.lint, f.message)
+            }
+            ImprovementTarget::Todo(t) => format!(
+                "{}:{}: {}",
+                t.file.file_name().unwrap_or_default().to_string_lossy(),
+                t.line,
+                t.text
+            ),
+            ImprovementTarget::DocGap(m) => format!(
+                "{}: {:.0}% doc coverage over {} lines",
+                m.name,
+                m.doc_coverage * 100.0,
+                m.lines
+            ),
+        }
+    }
+}
+
+impl CodebaseAnalysis {
+    /// Highest-priority fix: lint errors, then FIXMEs, lint warnings, TODOs,
+    /// and finally the largest under-documented module
+    pub fn next_target(&self) -> Option<ImprovementTarget> {
+        let lint = |severity| {
+            self.lint_findings
+                .iter()
+                .find(|f| f.severity == severity)
+                .cloned()
+                .map(ImprovementTarget::Lint)
+        };
+        let todo = |priority| {
+            self.todos
+                .iter()
+                .find(|t| t.priority == priority)
+                .cloned()
+                .map(ImprovementTarget::Todo)
+        };
+
+        lint(LintSeverity::Error)
+            .or_else(|| todo(TodoPriority::High))
+            .or_else(|| lint(LintSeverity::Warning))
+            .or_else(|| todo(TodoPriority::Medium))
+            .or_else(|| {
+                self.modules
+                    .iter()
+                    .filter(|m| m.doc_coverage < DOC_GAP_THRESHOLD)
+                    .max_by_key(|m| m.lines)
+                    .cloned()
+                    .map(ImprovementTarget::DocGap)
+            })
+    }
+}
+
+/// Prompt asking the model for a unified diff that fixes `target`
+pub fn fix_prompt(target: &ImprovementTarget, file: &Path, source: &str) -> String {
+    let task = match target {
+        ImprovementTarget::Lint(_) => "Fix this compiler/clippy diagnostic",
+        ImprovementTarget::Todo(_) => "Resolve this TODO/FIXME",
+        ImprovementTarget::DocGap(_) => "Add missing doc comments to this module",
+    };
+    format!(
+        "{}:\n\n  {}\n\nFile: {}\n```rust\n{}\n```\n\n\
+         Reply with ONLY a unified diff in a ```diff block, using paths \
+         `--- a/{path}` and `+++ b/{path}`. Keep the change minimal and make \
+         sure `cargo test` still passes.",
+        task,
+        target.describe(),
+        file.display(),
+        source,
+        path = file.display()
+    )
+}
+
+/// Pull the unified diff out of a model reply (fenced or bare)
+pub fn extract_diff(response: &str) -> Option<String> {
+    let fenced = response
+        .split("```diff")
+        .nth(1)
+        .and_then(|rest| rest.split("```").next());
+    let diff = fenced.unwrap_or(response).trim_matches('\n');
+    if diff.contains("@@") && diff.contains("+++ ") {
+        Some(format!("{}\n", diff))
+    } else {
+        None
+    }
+}
+
+/// A file's contents before a patch touched it (None: it didn't exist)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub original: Option<String>,
+}
+
+/// What one pass of the improvement loop ended with
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImproveOutcome {
+    /// Patch applied and verification passed
+    Applied { files: Vec<PathBuf> },
+    /// Patch applied but verification failed, so it was rolled back
+    Reverted { files: Vec<PathBuf>, reason: String },
+}
+
+/// Apply `diff`, verify, and roll back if verification fails.
+///
+/// `verify` returns Ok(()) when the tree is healthy or Err(reason).
+pub fn apply_and_verify<A, V, R>(
+    diff: &str,
+    apply: A,
+    verify: V,
+    revert: R,
+) -> Result<ImproveOutcome>
+where
+    A: FnOnce(&str) -> Result<Vec<Snapshot>>,
+    V: FnOnce() -> std::result::Result<(), String>,
+    R: FnOnce(&[Snapshot]) -> Result<()>,
+{
+    let snapshots = apply(diff)?;
+    let files = snapshots.iter().map(|s| s.path.clone()).collect();
+    match verify() {
+        Ok(()) => Ok(ImproveOutcome::Applied { files }),
+        Err(reason) => {
+            revert(&snapshots).context("Verification failed and revert failed")?;
+            Ok(ImproveOutcome::Reverted { files, reason })
+        }
+    }
+}
+
+/// Apply a (possibly multi-file) unified diff under `root`.
+/// Either every file is patched or none are.
+pub fn apply_diff(root: &Path, diff: &str) -> Result<Vec<Snapshot>> {
+    let mut snapshots: Vec<Snapshot> = Vec::new();
+    for file_diff in split_file_diffs(diff) {
+        let result = apply_file_diff(root, &file_diff);
+        match result {
+            Ok(snapshot) => snapshots.push(snapshot),
+            Err(e) => {
+                restore(&snapshots)?;
+                return Err(e);
+            }
+        }
+    }
+    if snapshots.is_empty() {
+        bail!("Diff does not touch any files");
+    }
+    Ok(snapshots)
+}
+
+fn apply_file_diff(root: &Path, file_diff: &str) -> Result<Snapshot> {
+    let target =
+        crate::tools::extract_diff_target(file_diff).context("Diff section has no +++ target")?;
+    let relative = Path::new(target.trim());
+    if relative
+        .components()
+        .any(|c| !matches!(c, std::path::Component::Normal(_)))
+    {
+        bail!("Refusing to patch path outside the project: {}", target);
+    }
+
+    let path = root.join(relative);
+    let original = std::fs::read_to_string(&path).ok();
+    let patched = crate::tools::apply_patch(original.as_deref().unwrap_or(""), file_diff)
+        .with_context(|| format!("Patch does not apply to {}", target))?;
+    std::fs::write(&path, patched)?;
+    Ok(Snapshot { path, original })
+}
+
+/// Split a unified diff into one chunk per file
+fn split_file_diffs(diff: &str) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    for line in diff.lines() {
+        if line.starts_with("--- ") || chunks.is_empty() {
+            chunks.push(String::new());
+        }
+        let chunk = chunks.last_mut().expect("pushed above");
+        chunk.push_str(line);
+        chunk.push('\n');
+    }
+    chunks.retain(|c| c.contains("@@"));
+    chunks
+}
+
+/// Put files back the way `apply_diff` found them
+pub fn restore(snapshots: &[Snapshot]) -> Result<()> {
+    for snapshot in snapshots.iter().rev() {
+        match &snapshot.original {
+            Some(content) => std::fs::write(&snapshot.path, content)?,
+            None => {
+                if snapshot.path.exists() {
+                    std::fs::remove_file(&snapshot.path)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 // ═══════════════════════════════════════════════════════════════
 // SELF-REPAIR
 // ═══════════════════════════════════════════════════════════════
@@ -756,6 +1428,325 @@ mod tests {
         assert_eq!(extract_test_count(output2), 118);
     }
 
+    /// Trimmed `cargo clippy --message-format=json` output: an artifact line,
+    /// a clippy warning reported for both lib and bin, an error, a note and
+    /// the trailing summary
+    const CLIPPY_FIXTURE: &str = r#"{"reason":"compiler-artifact","package_id":"hyle 0.1.0","target":{"name":"hyle"}}
+{"reason":"compiler-message","package_id":"hyle 0.1.0","message":{"message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"level":"warning","spans":[{"file_name":"src/tools.rs","line_start":42,"line_end":42,"is_primary":true}],"children":[],"rendered":"warning: unneeded `return` statement"}}
+{"reason":"compiler-message","package_id":"hyle 0.1.0","message":{"message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"level":"warning","spans":[{"file_name":"src/tools.rs","line_start":42,"line_end":42,"is_primary":true}],"children":[],"rendered":"warning: unneeded `return` statement"}}
+{"reason":"compiler-message","package_id":"hyle 0.1.0","message":{"message":"mismatched types","code":{"code":"E0308","explanation":"..."},"level":"error","spans":[{"file_name":"src/ui.rs","line_start":7,"line_end":7,"is_primary":false},{"file_name":"src/ui.rs","line_start":9,"line_end":9,"is_primary":true}],"children":[],"rendered":"error[E0308]: mismatched types"}}
+{"reason":"compiler-message","package_id":"hyle 0.1.0","message":{"message":"for more information, see the docs","code":null,"level":"note","spans":[],"children":[],"rendered":"note"}}
+{"reason":"compiler-message","package_id":"hyle 0.1.0","message":{"message":"2 warnings emitted","code":null,"level":"warning","spans":[],"children":[],"rendered":"warning: 2 warnings emitted"}}
+{"reason":"build-finished","success":false}
+not json at all"#;
+
+    #[test]
+    fn test_parse_clippy_json() {
+        let findings = parse_clippy_json(CLIPPY_FIXTURE);
+        assert_eq!(
+            findings,
+            vec![
+                LintFinding {
+                    file: "src/tools.rs".into(),
+                    line: 42,
+                    lint: "clippy::needless_return".into(),
+                    severity: LintSeverity::Warning,
+                    message: "unneeded `return` statement".into(),
+                },
+                LintFinding {
+                    file: "src/ui.rs".into(),
+                    line: 9,
+                    lint: "E0308".into(),
+                    severity: LintSeverity::Error,
+                    message: "mismatched types".into(),
+                },
+            ]
+        );
+        assert!(parse_clippy_json("").is_empty());
+    }
+
+    #[test]
+    fn test_lint_findings_cached_by_source_hash() {
+        let dir = std::env::temp_dir().join(format!("hyle_test_clippy_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "pub fn a() {}").unwrap();
+        let cache = dir.join("cache/clippy.json");
+
+        let hash = source_hash(&dir);
+        let mut runs = 0;
+        let first = cached_lint_findings(&cache, &hash, || {
+            runs += 1;
+            Ok(CLIPPY_FIXTURE.to_string())
+        })
+        .unwrap();
+        assert_eq!(first.len(), 2);
+
+        // Same source: served from cache without running clippy
+        let again = cached_lint_findings(&cache, &source_hash(&dir), || {
+            runs += 1;
+            Ok(String::new())
+        })
+        .unwrap();
+        assert_eq!(again, first);
+        assert_eq!(runs, 1);
+
+        // Editing a source file changes the key and re-runs
+        std::fs::write(dir.join("src/lib.rs"), "pub fn b() {}").unwrap();
+        let changed = source_hash(&dir);
+        assert_ne!(changed, hash);
+        let rerun = cached_lint_findings(&cache, &changed, || {
+            runs += 1;
+            Ok(String::new())
+        })
+        .unwrap();
+        assert!(rerun.is_empty());
+        assert_eq!(runs, 2);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    fn analysis_fixture() -> CodebaseAnalysis {
+        let module = |name: &str, lines, doc_coverage| ModuleInfo {
+            name: name.into(),
+            path: PathBuf::from(format!("/repo/src/{}.rs", name)),
+            lines,
+            functions: 10,
+            tests: 0,
+            doc_coverage,
+            dependencies: vec![],
+        };
+        CodebaseAnalysis {
+            modules: vec![module("ui", 900, 0.01), module("tools", 400, 0.0)],
+            total_lines: 1300,
+            test_count: 0,
+            dead_code_warnings: 0,
+            todos: vec![TodoItem {
+                file: PathBuf::from("/repo/src/tools.rs"),
+                line: 12,
+                text: "// TODO: handle symlinks".into(),
+                priority: TodoPriority::Medium,
+            }],
+            lint_findings: parse_clippy_json(CLIPPY_FIXTURE),
+            health_score: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_next_target_priority() {
+        let mut analysis = analysis_fixture();
+        let root = Path::new("/repo");
+
+        // Lint errors first
+        let target = analysis.next_target().unwrap();
+        assert!(matches!(&target, ImprovementTarget::Lint(f) if f.lint == "E0308"));
+        assert_eq!(target.file(root), PathBuf::from("src/ui.rs"));
+
+        // Then warnings, then TODOs
+        analysis
+            .lint_findings
+            .retain(|f| f.severity == LintSeverity::Warning);
+        assert!(matches!(
+            analysis.next_target(),
+            Some(ImprovementTarget::Lint(f)) if f.lint == "clippy::needless_return"
+        ));
+        analysis.lint_findings.clear();
+        let target = analysis.next_target().unwrap();
+        assert!(matches!(&target, ImprovementTarget::Todo(t) if t.line == 12));
+        assert_eq!(target.file(root), PathBuf::from("src/tools.rs"));
+
+        // Finally the largest under-documented module
+        analysis.todos.clear();
+        assert!(matches!(
+            analysis.next_target(),
+            Some(ImprovementTarget::DocGap(m)) if m.name == "ui"
+        ));
+        analysis
+            .modules
+            .iter_mut()
+            .for_each(|m| m.doc_coverage = 0.5);
+        assert!(analysis.next_target().is_none());
+    }
+
+    #[test]
+    fn test_extract_diff() {
+        let reply = "Here's the fix:\n```diff\n--- a/src/ui.rs\n+++ b/src/ui.rs\n@@ -1 +1 @@\n-old\n+new\n```\nDone.";
+        assert_eq!(
+            extract_diff(reply).unwrap(),
+            "--- a/src/ui.rs\n+++ b/src/ui.rs\n@@ -1 +1 @@\n-old\n+new\n"
+        );
+        assert!(extract_diff("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b").is_some());
+        assert!(extract_diff("I couldn't find a fix.").is_none());
+    }
+
+    /// select → prompt → (stub model) → patch → verify, with every effect stubbed
+    fn stub_improve(tests_pass: bool) -> (ImproveOutcome, Vec<&'static str>) {
+        let analysis = analysis_fixture();
+        let target = analysis.next_target().unwrap();
+        let prompt = fix_prompt(&target, &target.file(Path::new("/repo")), "fn main() {}");
+        assert!(prompt.contains("E0308"));
+        assert!(prompt.contains("+++ b/src/ui.rs"));
+
+        let reply = "```diff\n--- a/src/ui.rs\n+++ b/src/ui.rs\n@@ -1 +1 @@\n-a\n+b\n```";
+        let diff = extract_diff(reply).unwrap();
+
+        let calls = std::cell::RefCell::new(Vec::new());
+        let outcome = apply_and_verify(
+            &diff,
+            |_| {
+                calls.borrow_mut().push("apply");
+                Ok(vec![Snapshot {
+                    path: PathBuf::from("src/ui.rs"),
+                    original: Some("a\n".into()),
+                }])
+            },
+            || {
+                calls.borrow_mut().push("verify");
+                if tests_pass {
+                    Ok(())
+                } else {
+                    Err("1 test failed".into())
+                }
+            },
+            |snapshots| {
+                assert_eq!(snapshots.len(), 1);
+                calls.borrow_mut().push("revert");
+                Ok(())
+            },
+        )
+        .unwrap();
+        (outcome, calls.into_inner())
+    }
+
+    #[test]
+    fn test_improve_loop_keeps_passing_fix() {
+        let (outcome, calls) = stub_improve(true);
+        assert_eq!(
+            outcome,
+            ImproveOutcome::Applied {
+                files: vec![PathBuf::from("src/ui.rs")]
+            }
+        );
+        assert_eq!(calls, vec!["apply", "verify"]);
+    }
+
+    #[test]
+    fn test_improve_loop_reverts_on_test_failure() {
+        let (outcome, calls) = stub_improve(false);
+        assert_eq!(
+            outcome,
+            ImproveOutcome::Reverted {
+                files: vec![PathBuf::from("src/ui.rs")],
+                reason: "1 test failed".into()
+            }
+        );
+        assert_eq!(calls, vec!["apply", "verify", "revert"]);
+    }
+
+    #[test]
+    fn test_improve_loop_skips_verify_when_patch_fails() {
+        let verified = std::cell::Cell::new(false);
+        let result = apply_and_verify(
+            "bad diff",
+            |_| bail!("Patch does not apply"),
+            || {
+                verified.set(true);
+                Ok(())
+            },
+            |_| Ok(()),
+        );
+        assert!(result.is_err());
+        assert!(!verified.get());
+    }
+
+    #[test]
+    fn test_apply_diff_and_restore() {
+        let dir = std::env::temp_dir().join(format!("hyle_test_improve_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/a.rs"), "fn a() {}\nfn old() {}\n").unwrap();
+
+        let diff = "--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1,2 +1,2 @@\n fn a() {}\n-fn old() {}\n+fn new() {}\n\
+                    --- /dev/null\n+++ b/src/b.rs\n@@ -0,0 +1 @@\n+fn b() {}\n";
+        let snapshots = apply_diff(&dir, diff).unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("src/a.rs")).unwrap(),
+            "fn a() {}\nfn new() {}\n"
+        );
+        assert!(dir.join("src/b.rs").exists());
+
+        restore(&snapshots).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("src/a.rs")).unwrap(),
+            "fn a() {}\nfn old() {}\n"
+        );
+        assert!(!dir.join("src/b.rs").exists());
+
+        // Paths that escape the project are refused
+        let escape = "--- a/../x\n+++ b/../x\n@@ -0,0 +1 @@\n+x\n";
+        assert!(apply_diff(&dir, escape).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_module_dependencies() {
+        let content = "use crate::config::{self, Config};\n\
+                       use crate::tools;\n\
+                       // crate::ignored in a comment\n\
+                       fn f() { crate::session::Session::new(); crate::tools::run(); }\n";
+        assert_eq!(
+            module_dependencies(content),
+            vec!["config", "session", "tools"]
+        );
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        // ui -> skills -> bootstrap -> ui is a cycle; tools <-> git is another;
+        // config and client are leaves
+        let graph = ModuleGraph::from_edges([
+            ("ui", "skills"),
+            ("skills", "bootstrap"),
+            ("bootstrap", "ui"),
+            ("ui", "config"),
+            ("skills", "tools"),
+            ("tools", "git"),
+            ("git", "tools"),
+            ("client", "config"),
+            ("ui", "skills"),
+        ]);
+        assert_eq!(
+            graph.cycles(),
+            vec![vec!["bootstrap", "skills", "ui"], vec!["git", "tools"],]
+        );
+
+        let acyclic = ModuleGraph::from_edges([("a", "b"), ("b", "c"), ("a", "c")]);
+        assert!(acyclic.cycles().is_empty());
+        assert!(acyclic.report().contains("Import cycles: none"));
+
+        let self_loop = ModuleGraph::from_edges([("a", "a"), ("a", "b")]);
+        assert_eq!(self_loop.cycles(), vec![vec!["a"]]);
+    }
+
+    #[test]
+    fn test_coupling_hotspots() {
+        // Every module imports "config"; one module imports everything
+        let mut edges = Vec::new();
+        let names: Vec<String> = (0..12).map(|i| format!("m{}", i)).collect();
+        for name in &names {
+            edges.push((name.as_str(), "config"));
+        }
+        for name in &names[1..] {
+            edges.push(("m0", name.as_str()));
+        }
+        let graph = ModuleGraph::from_edges(edges);
+        let hotspots = graph.coupling_hotspots();
+        let modules: Vec<&str> = hotspots.iter().map(|c| c.module.as_str()).collect();
+        assert_eq!(modules, vec!["config", "m0"]);
+        assert_eq!(hotspots[0].fan_in, 12);
+        assert!(graph.report().contains("m0 (fan-in 0, fan-out 12)"));
+    }
+
     #[test]
     fn test_bootstrap_creation() {
         // This test will pass if we're in the hyle project
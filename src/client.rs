@@ -12,10 +12,12 @@ use serde::{Deserialize, Serialize};
 use std::sync::OnceLock;
 use tokio::sync::mpsc;
 
+use crate::config::{ProviderPrefs, SamplingParams, RESERVED_HEADERS};
 use crate::models::Model;
 
 use crate::project::Project;
-use crate::prompt::SystemPrompt;
+use crate::prompt::{CustomInstructions, SystemPrompt};
+use crate::response_cache::{self, ResponseCache};
 
 // ═══════════════════════════════════════════════════════════════
 // SHARED HTTP CLIENT
@@ -33,6 +35,102 @@ fn shared_client() -> &'static reqwest::Client {
     })
 }
 
+// ═══════════════════════════════════════════════════════════════
+// RESPONSE CACHE
+// ═══════════════════════════════════════════════════════════════
+
+static RESPONSE_CACHE: OnceLock<Option<ResponseCache>> = OnceLock::new();
+
+/// Turn on the response cache for this process when config enables it and
+/// `--no-cache` wasn't given (first call wins)
+pub fn init_response_cache(no_cache: bool) {
+    let cache = crate::config::Config::load()
+        .ok()
+        .filter(|cfg| cfg.response_cache && !no_cache)
+        .and_then(|cfg| {
+            let ttl = cfg
+                .response_cache_ttl_secs
+                .map(std::time::Duration::from_secs)
+                .unwrap_or(response_cache::DEFAULT_TTL);
+            ResponseCache::open(ttl).ok()
+        });
+    let _ = RESPONSE_CACHE.set(cache);
+}
+
+fn response_cache() -> Option<&'static ResponseCache> {
+    RESPONSE_CACHE.get().and_then(|c| c.as_ref())
+}
+
+/// Key covering everything sent: model, system prompt, messages, sampling
+fn request_cache_key(request: &ChatRequest) -> String {
+    response_cache::cache_key(&serde_json::to_string(request).unwrap_or_default())
+}
+
+/// Stream cached token chunks as if they came from the API
+fn replay(tokens: Vec<String>) -> mpsc::Receiver<StreamEvent> {
+    let (tx, rx) = mpsc::channel(256);
+    tokio::spawn(async move {
+        for token in tokens {
+            if tx.send(StreamEvent::Token(token)).await.is_err() {
+                return;
+            }
+        }
+        let _ = tx.send(StreamEvent::Done(TokenUsage::default())).await;
+    });
+    rx
+}
+
+// ═══════════════════════════════════════════════════════════════
+// REQUEST OPTIONS
+// ═══════════════════════════════════════════════════════════════
+
+/// Provider routing and extra headers added to every chat request
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    pub provider: ProviderPrefs,
+    pub headers: std::collections::BTreeMap<String, String>,
+}
+
+static REQUEST_OPTIONS: OnceLock<RequestOptions> = OnceLock::new();
+
+/// Take provider routing and request headers from config for this process
+/// (first call wins; without it requests go out as before)
+pub fn init_request_options() {
+    let options = crate::config::Config::load()
+        .map(|cfg| RequestOptions {
+            provider: cfg.provider_prefs,
+            headers: cfg.request_headers,
+        })
+        .unwrap_or_default();
+    let _ = REQUEST_OPTIONS.set(options);
+}
+
+fn request_options() -> &'static RequestOptions {
+    REQUEST_OPTIONS.get_or_init(RequestOptions::default)
+}
+
+/// Headers for a chat request: hyle's own, then configured ones, which
+/// replace a default of the same name except the reserved ones
+fn request_headers(api_key: &str, options: &RequestOptions) -> Vec<(String, String)> {
+    let mut headers = vec![
+        ("Authorization".to_string(), format!("Bearer {}", api_key)),
+        ("Content-Type".to_string(), "application/json".to_string()),
+        (
+            "HTTP-Referer".to_string(),
+            "https://github.com/uprootiny/hyle".to_string(),
+        ),
+        ("X-Title".to_string(), "hyle".to_string()),
+    ];
+    for (name, value) in &options.headers {
+        if RESERVED_HEADERS.contains(&name.to_lowercase().as_str()) {
+            continue;
+        }
+        headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+        headers.push((name.clone(), value.clone()));
+    }
+    headers
+}
+
 // ═══════════════════════════════════════════════════════════════
 // TYPED ERRORS
 // ═══════════════════════════════════════════════════════════════
@@ -144,6 +242,31 @@ fn build_system_prompt(project: Option<&Project>) -> String {
         builder = builder.with_project(p.clone());
     }
 
+    // Re-read on every request so /system edits apply immediately
+    let root = project
+        .map(|p| p.root.clone())
+        .or_else(|| std::env::current_dir().ok());
+    let global = crate::config::config_dir().ok();
+    let instructions = CustomInstructions::load(root.as_deref(), global.as_deref());
+    if let Some(section) = instructions.to_section() {
+        builder = builder.add_instruction(&section);
+    }
+    // Build/test/format commands for this kind of project
+    let hints = crate::config::Config::load()
+        .map(|c| c.project_hints())
+        .unwrap_or(true);
+    if let Some(section) = root
+        .as_deref()
+        .filter(|_| hints)
+        .and_then(crate::prompt::project_hints_for)
+    {
+        builder = builder.add_instruction(&section);
+    }
+    // Cached `hyle summarize` output, for orientation in a large repo
+    if let Some(section) = root.as_deref().and_then(crate::overview::context_section) {
+        builder = builder.add_instruction(&section);
+    }
+
     builder.build()
 }
 
@@ -151,11 +274,25 @@ const OPENROUTER_API_URL: &str = "https://openrouter.ai/api/v1/chat/completions"
 const OPENROUTER_MODELS_URL: &str = "https://openrouter.ai/api/v1/models";
 
 /// Token usage statistics
+///
+/// `prompt_tokens` counts every input token; the cache counts are the parts
+/// of it served from (or written to) the provider's prompt cache.
 #[derive(Debug, Clone, Default)]
 pub struct TokenUsage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    pub cache_read_tokens: u32,
+    pub cache_write_tokens: u32,
+}
+
+impl TokenUsage {
+    /// Prompt tokens billed at the full prompt rate
+    pub fn uncached_prompt_tokens(&self) -> u32 {
+        self.prompt_tokens
+            .saturating_sub(self.cache_read_tokens)
+            .saturating_sub(self.cache_write_tokens)
+    }
 }
 
 /// Stream events from the API
@@ -176,18 +313,49 @@ pub async fn chat_completion_simple(
     prompt: &str,
     max_tokens: u32,
 ) -> Result<String> {
-    let mut rx = stream_completion_configurable(api_key, model, prompt, None, &[], Some(max_tokens), None).await?;
+    let (response, _usage) = chat_completion_with_usage(api_key, model, prompt, max_tokens).await?;
+    Ok(response)
+}
+
+/// Non-streaming completion that also reports token usage
+pub async fn chat_completion_with_usage(
+    api_key: &str,
+    model: &str,
+    prompt: &str,
+    max_tokens: u32,
+) -> Result<(String, TokenUsage)> {
+    let params = SamplingParams {
+        max_tokens: Some(max_tokens),
+        ..Default::default()
+    };
+    complete_with_history(api_key, model, prompt, &[], params).await
+}
+
+/// Non-streaming completion with history and sampling parameters
+pub async fn complete_with_history(
+    api_key: &str,
+    model: &str,
+    prompt: &str,
+    history: &[serde_json::Value],
+    params: SamplingParams,
+) -> Result<(String, TokenUsage)> {
+    let mut rx =
+        stream_completion_configurable(api_key, model, prompt, None, history, params).await?;
     let mut response = String::new();
+    let mut usage = TokenUsage::default();
 
     while let Some(event) = rx.recv().await {
         match event {
             StreamEvent::Token(t) => response.push_str(&t),
-            StreamEvent::Done(_) => break,
+            StreamEvent::Done(u) => {
+                usage = u;
+                break;
+            }
             StreamEvent::Error(e) => anyhow::bail!("API error: {}", e),
         }
     }
 
-    Ok(response)
+    Ok((response, usage))
 }
 
 /// Check connectivity to OpenRouter
@@ -225,13 +393,29 @@ pub async fn fetch_models(api_key: &str) -> Result<Vec<Model>> {
         .data
         .into_iter()
         .map(|m| {
-            let (pricing_prompt, pricing_completion) = match m.pricing {
+            let (pricing_prompt, pricing_completion) = match &m.pricing {
                 Some(p) => (
                     p.prompt.parse().unwrap_or(0.0),
                     p.completion.parse().unwrap_or(0.0),
                 ),
                 None => (0.0, 0.0),
             };
+            let (pricing_cache_read, pricing_cache_write) = match &m.pricing {
+                Some(p) => (
+                    p.input_cache_read.as_ref().and_then(|v| v.parse().ok()),
+                    p.input_cache_write.as_ref().and_then(|v| v.parse().ok()),
+                ),
+                None => (None, None),
+            };
+
+            let mut capabilities = m.supported_parameters.unwrap_or_default();
+            let vision = m
+                .architecture
+                .map(|a| a.input_modalities.iter().any(|i| i == "image"))
+                .unwrap_or(false);
+            if vision {
+                capabilities.push("vision".to_string());
+            }
 
             Model {
                 id: m.id,
@@ -239,6 +423,9 @@ pub async fn fetch_models(api_key: &str) -> Result<Vec<Model>> {
                 context_length: m.context_length.unwrap_or(4096),
                 pricing_prompt,
                 pricing_completion,
+                pricing_cache_read,
+                pricing_cache_write,
+                capabilities,
             }
         })
         .collect();
@@ -273,7 +460,101 @@ pub async fn stream_completion_full(
     project: Option<&Project>,
     history: &[serde_json::Value],
 ) -> Result<mpsc::Receiver<StreamEvent>> {
-    stream_completion_configurable(api_key, model, prompt, project, history, None, None).await
+    stream_completion_configurable(
+        api_key,
+        model,
+        prompt,
+        project,
+        history,
+        SamplingParams::default(),
+    )
+    .await
+}
+
+/// Stream a chat completion with explicit sampling parameters
+pub async fn stream_completion_with_params(
+    api_key: &str,
+    model: &str,
+    prompt: &str,
+    project: Option<&Project>,
+    history: &[serde_json::Value],
+    params: SamplingParams,
+) -> Result<mpsc::Receiver<StreamEvent>> {
+    stream_completion_configurable(api_key, model, prompt, project, history, params).await
+}
+
+/// Default max_tokens when unset
+pub const DEFAULT_MAX_TOKENS: u32 = 4096;
+/// Default temperature when unset
+const DEFAULT_TEMPERATURE: f32 = 0.7;
+
+/// Build the request body: system + history + current user message
+fn build_chat_request(
+    model: &str,
+    prompt: &str,
+    project: Option<&Project>,
+    history: &[serde_json::Value],
+    params: &SamplingParams,
+) -> ChatRequest {
+    let system_prompt = build_system_prompt(project);
+
+    let mut messages = vec![ChatMessage {
+        role: "system".to_string(),
+        content: system_prompt,
+    }];
+
+    // Add conversation history
+    for msg in history {
+        if let (Some(role), Some(content)) = (
+            msg.get("role").and_then(|v| v.as_str()),
+            msg.get("content").and_then(|v| v.as_str()),
+        ) {
+            messages.push(ChatMessage {
+                role: role.to_string(),
+                content: content.to_string(),
+            });
+        }
+    }
+
+    // Add current user message
+    messages.push(ChatMessage {
+        role: "user".to_string(),
+        content: prompt.to_string(),
+    });
+
+    ChatRequest {
+        model: model.to_string(),
+        messages,
+        stream: true,
+        max_tokens: Some(params.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS)),
+        temperature: Some(params.temperature.unwrap_or(DEFAULT_TEMPERATURE)),
+        top_p: params.top_p,
+        stop: if params.stop.is_empty() {
+            None
+        } else {
+            Some(params.stop.clone())
+        },
+        seed: params.seed,
+        provider: None,
+    }
+}
+
+/// Rough per-message overhead (role, separators) on top of the content
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Estimate the prompt tokens of the request that would be sent for
+/// `prompt` + `history`, before sending it
+pub fn estimate_prompt_tokens(
+    prompt: &str,
+    project: Option<&Project>,
+    history: &[serde_json::Value],
+) -> u32 {
+    let request = build_chat_request("", prompt, project, history, &SamplingParams::default());
+    request
+        .messages
+        .iter()
+        .map(|m| crate::cognitive::estimate_tokens(&m.content) + MESSAGE_OVERHEAD_TOKENS)
+        .sum::<usize>() as u32
 }
 
 /// Stream a chat completion with all options configurable
@@ -283,59 +564,60 @@ fn stream_completion_configurable<'a>(
     prompt: &'a str,
     project: Option<&'a Project>,
     history: &'a [serde_json::Value],
-    max_tokens: Option<u32>,
-    temperature: Option<f32>,
-) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<mpsc::Receiver<StreamEvent>>> + Send + 'a>> {
-    let max_tokens = max_tokens.unwrap_or(4096);
-    let temperature = temperature.unwrap_or(0.7);
-
+    params: SamplingParams,
+) -> std::pin::Pin<
+    Box<dyn std::future::Future<Output = Result<mpsc::Receiver<StreamEvent>>> + Send + 'a>,
+> {
     Box::pin(async move {
         let (tx, rx) = mpsc::channel(256);
 
-        let system_prompt = build_system_prompt(project);
-
-        // Build messages: system + history + current user message
-        let mut messages = vec![ChatMessage {
-            role: "system".to_string(),
-            content: system_prompt,
-        }];
-
-        // Add conversation history
-        for msg in history {
-            if let (Some(role), Some(content)) = (
-                msg.get("role").and_then(|v| v.as_str()),
-                msg.get("content").and_then(|v| v.as_str()),
-            ) {
-                messages.push(ChatMessage {
-                    role: role.to_string(),
-                    content: content.to_string(),
-                });
+        let request = build_chat_request(model, prompt, project, history, &params)
+            .with_routing(request_options());
+
+        let cached = response_cache().map(|cache| (cache, request_cache_key(&request)));
+        if let Some((cache, key)) = &cached {
+            if let Some(tokens) = cache.get(key) {
+                return Ok(replay(tokens));
             }
         }
 
-        // Add current user message
-        messages.push(ChatMessage {
-            role: "user".to_string(),
-            content: prompt.to_string(),
-        });
-
-        let request = ChatRequest {
-            model: model.to_string(),
-            messages,
-            stream: true,
-            max_tokens: Some(max_tokens),
-            temperature: Some(temperature),
-        };
-
         let client = shared_client().clone();
         let api_key = api_key.to_string();
 
         tokio::spawn(async move {
-            match do_stream(&client, &api_key, &request, &tx).await {
-                Ok(usage) => {
-                    let _ = tx.send(StreamEvent::Done(usage)).await;
+            let span = crate::otel::start("chat");
+            let result = do_stream(&client, &api_key, &request, &tx).await;
+            if let Some(mut span) = span {
+                match &result {
+                    Ok(streamed) => {
+                        crate::otel::record_request(&mut span, &request.model, &streamed.usage);
+                        span.end(None);
+                    }
+                    Err(e) => {
+                        crate::otel::record_request(
+                            &mut span,
+                            &request.model,
+                            &TokenUsage::default(),
+                        );
+                        span.end(Some(&e.to_string()));
+                    }
+                }
+            }
+
+            match result {
+                Ok(streamed) => {
+                    if let Some((cache, key)) = cached {
+                        if !streamed.tokens.is_empty() {
+                            let _ = cache.put(&key, &streamed.tokens);
+                        }
+                    }
+                    let _ = tx.send(StreamEvent::Done(streamed.usage)).await;
                 }
                 Err(e) => {
+                    crate::logfile::LogEvent::error(module_path!(), "chat request failed")
+                        .field("model", &request.model)
+                        .field("error", e.to_string())
+                        .emit();
                     let _ = tx.send(StreamEvent::Error(e.to_string())).await;
                 }
             }
@@ -356,13 +638,19 @@ const MAX_RETRIES: u32 = 3;
 /// Base delay for exponential backoff (ms)
 const RETRY_BASE_DELAY_MS: u64 = 500;
 
+/// A completed stream: usage plus the token chunks that were sent
+struct Streamed {
+    usage: TokenUsage,
+    tokens: Vec<String>,
+}
+
 /// Perform the actual streaming request with retry
 async fn do_stream(
     client: &reqwest::Client,
     api_key: &str,
     request: &ChatRequest,
     tx: &mpsc::Sender<StreamEvent>,
-) -> Result<TokenUsage> {
+) -> Result<Streamed> {
     let mut last_error = None;
 
     for attempt in 0..MAX_RETRIES {
@@ -379,7 +667,7 @@ async fn do_stream(
         }
 
         match do_stream_attempt(client, api_key, request, tx).await {
-            Ok(usage) => return Ok(usage),
+            Ok(streamed) => return Ok(streamed),
             Err(e) => {
                 // Check if it's a typed ApiError
                 if let Some(api_err) = e.downcast_ref::<ApiError>() {
@@ -413,13 +701,12 @@ async fn do_stream_attempt(
     api_key: &str,
     request: &ChatRequest,
     tx: &mpsc::Sender<StreamEvent>,
-) -> Result<TokenUsage> {
-    let response = client
-        .post(OPENROUTER_API_URL)
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .header("HTTP-Referer", "https://github.com/uprootiny/hyle")
-        .header("X-Title", "hyle")
+) -> Result<Streamed> {
+    let mut builder = client.post(OPENROUTER_API_URL);
+    for (name, value) in request_headers(api_key, request_options()) {
+        builder = builder.header(name, value);
+    }
+    let response = builder
         .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
         .json(request)
         .send()
@@ -441,6 +728,7 @@ async fn do_stream_attempt(
     }
 
     let mut usage = TokenUsage::default();
+    let mut tokens = Vec::new();
     let mut bytes_stream = response.bytes_stream();
 
     // Buffer for incomplete SSE lines
@@ -471,6 +759,7 @@ async fn do_stream_attempt(
                         if let Some(delta) = &choice.delta {
                             if let Some(content) = &delta.content {
                                 if !content.is_empty() {
+                                    tokens.push(content.clone());
                                     let _ = tx.send(StreamEvent::Token(content.clone())).await;
                                 }
                             }
@@ -479,9 +768,7 @@ async fn do_stream_attempt(
 
                     // Extract usage if present
                     if let Some(u) = chunk.usage {
-                        usage.prompt_tokens = u.prompt_tokens;
-                        usage.completion_tokens = u.completion_tokens;
-                        usage.total_tokens = u.total_tokens;
+                        usage = u.into();
                     }
                 }
                 // Silently skip unparseable SSE data lines (common with some providers)
@@ -489,7 +776,7 @@ async fn do_stream_attempt(
         }
     }
 
-    Ok(usage)
+    Ok(Streamed { usage, tokens })
 }
 
 /// Parse SSE lines from a text buffer, extracting complete data payloads.
@@ -538,6 +825,22 @@ struct ChatRequest {
     max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provider: Option<ProviderPrefs>,
+}
+
+impl ChatRequest {
+    /// Add configured provider routing; empty prefs send no `provider` field
+    fn with_routing(mut self, options: &RequestOptions) -> Self {
+        self.provider = (!options.provider.is_empty()).then(|| options.provider.clone());
+        self
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -568,6 +871,47 @@ struct StreamUsage {
     prompt_tokens: u32,
     completion_tokens: u32,
     total_tokens: u32,
+    /// OpenRouter: `{"cached_tokens": .., "cache_write_tokens": ..}`
+    #[serde(default)]
+    prompt_tokens_details: Option<PromptTokensDetails>,
+    /// Anthropic-style cache counters, passed through by some providers
+    #[serde(default)]
+    cache_read_input_tokens: Option<u32>,
+    #[serde(default)]
+    cache_creation_input_tokens: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PromptTokensDetails {
+    #[serde(default)]
+    cached_tokens: Option<u32>,
+    #[serde(default)]
+    cache_write_tokens: Option<u32>,
+}
+
+impl From<StreamUsage> for TokenUsage {
+    fn from(u: StreamUsage) -> Self {
+        let details = u.prompt_tokens_details.unwrap_or_default();
+        let cache_read = details
+            .cached_tokens
+            .or(u.cache_read_input_tokens)
+            .unwrap_or(0);
+        let cache_write = details
+            .cache_write_tokens
+            .or(u.cache_creation_input_tokens)
+            .unwrap_or(0);
+        // Never let cache counts exceed the prompt they are part of
+        let cache_read_tokens = cache_read.min(u.prompt_tokens);
+        let cache_write_tokens = cache_write.min(u.prompt_tokens - cache_read_tokens);
+
+        TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+            cache_read_tokens,
+            cache_write_tokens,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -581,12 +925,25 @@ struct ApiModel {
     name: Option<String>,
     context_length: Option<u32>,
     pricing: Option<ApiPricing>,
+    supported_parameters: Option<Vec<String>>,
+    #[serde(default)]
+    architecture: Option<ApiArchitecture>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiArchitecture {
+    #[serde(default)]
+    input_modalities: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct ApiPricing {
     prompt: String,
     completion: String,
+    #[serde(default)]
+    input_cache_read: Option<String>,
+    #[serde(default)]
+    input_cache_write: Option<String>,
 }
 
 // ═══════════════════════════════════════════════════════════════
@@ -623,6 +980,27 @@ mod tests {
         assert_eq!(usage.prompt_tokens, 10);
         assert_eq!(usage.completion_tokens, 20);
         assert_eq!(usage.total_tokens, 30);
+        let usage: TokenUsage = usage.into();
+        assert_eq!(usage.cache_read_tokens, 0);
+        assert_eq!(usage.uncached_prompt_tokens(), 10);
+    }
+
+    #[test]
+    fn test_parse_usage_with_cache_tokens() {
+        let json = r#"{"choices":[],"usage":{"prompt_tokens":1000,"completion_tokens":20,"total_tokens":1020,"prompt_tokens_details":{"cached_tokens":800,"cache_write_tokens":150}}}"#;
+        let chunk: StreamChunk = serde_json::from_str(json).unwrap();
+        let usage: TokenUsage = chunk.usage.unwrap().into();
+        assert_eq!(usage.cache_read_tokens, 800);
+        assert_eq!(usage.cache_write_tokens, 150);
+        assert_eq!(usage.uncached_prompt_tokens(), 50);
+
+        // Anthropic-style counters, with a bogus read count clamped to the prompt
+        let json = r#"{"choices":[],"usage":{"prompt_tokens":100,"completion_tokens":5,"total_tokens":105,"cache_read_input_tokens":500,"cache_creation_input_tokens":10}}"#;
+        let chunk: StreamChunk = serde_json::from_str(json).unwrap();
+        let usage: TokenUsage = chunk.usage.unwrap().into();
+        assert_eq!(usage.cache_read_tokens, 100);
+        assert_eq!(usage.cache_write_tokens, 0);
+        assert_eq!(usage.uncached_prompt_tokens(), 0);
     }
 
     #[test]
@@ -712,6 +1090,20 @@ mod tests {
         assert!(resp.data[0].pricing.is_none());
     }
 
+    #[test]
+    fn test_parse_models_response_modalities() {
+        let json = r#"{"data":[{"id":"v/model","architecture":{"input_modalities":["text","image"]}},{"id":"t/model","architecture":{}}]}"#;
+        let resp: ModelsResponse = serde_json::from_str(json).unwrap();
+        let image = |i: usize| {
+            resp.data[i]
+                .architecture
+                .as_ref()
+                .map(|a| a.input_modalities.contains(&"image".to_string()))
+        };
+        assert_eq!(image(0), Some(true));
+        assert_eq!(image(1), Some(false));
+    }
+
     #[test]
     fn test_parse_models_response_empty() {
         let json = r#"{"data":[]}"#;
@@ -819,6 +1211,10 @@ mod tests {
             stream: true,
             max_tokens: Some(1024),
             temperature: Some(0.5),
+            top_p: None,
+            stop: None,
+            seed: Some(7),
+            provider: None,
         };
 
         let json = serde_json::to_value(&req).unwrap();
@@ -826,6 +1222,7 @@ mod tests {
         assert_eq!(json["stream"], true);
         assert_eq!(json["max_tokens"], 1024);
         assert_eq!(json["temperature"], 0.5);
+        assert_eq!(json["seed"], 7);
         assert_eq!(json["messages"][0]["role"], "user");
         assert_eq!(json["messages"][0]["content"], "Hello");
     }
@@ -838,11 +1235,174 @@ mod tests {
             stream: false,
             max_tokens: None,
             temperature: None,
+            top_p: None,
+            stop: None,
+            seed: None,
+            provider: None,
         };
 
         let json = serde_json::to_string(&req).unwrap();
         assert!(!json.contains("max_tokens"));
         assert!(!json.contains("temperature"));
+        assert!(!json.contains("top_p"));
+        assert!(!json.contains("stop"));
+        assert!(!json.contains("seed"));
+        assert!(!json.contains("provider"));
+    }
+
+    #[test]
+    fn test_build_chat_request_uses_sampling_params() {
+        let params = SamplingParams {
+            temperature: Some(0.2),
+            top_p: Some(0.9),
+            max_tokens: Some(256),
+            stop: vec!["\n\n".into()],
+            seed: None,
+        };
+        let req = build_chat_request("m", "hi", None, &[], &params);
+        let json = serde_json::to_value(&req).unwrap();
+
+        assert_eq!(json["max_tokens"], 256);
+        assert!((json["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+        assert!((json["top_p"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+        assert_eq!(json["stop"][0], "\n\n");
+        assert_eq!(json["messages"][0]["role"], "system");
+        assert_eq!(json["messages"][1]["content"], "hi");
+    }
+
+    #[test]
+    fn test_estimate_prompt_tokens_counts_assembled_request() {
+        let bare = estimate_prompt_tokens("hi", None, &[]);
+        // The system prompt alone is well over a few tokens
+        assert!(bare > 10);
+
+        let history = vec![
+            serde_json::json!({"role": "user", "content": "x".repeat(4000)}),
+            serde_json::json!({"role": "assistant", "content": "y".repeat(400)}),
+        ];
+        let with_history = estimate_prompt_tokens("hi", None, &history);
+        assert_eq!(with_history, bare + 1000 + 100 + 2 * 4);
+    }
+
+    #[test]
+    fn test_build_chat_request_defaults_when_unset() {
+        let req = build_chat_request("m", "hi", None, &[], &SamplingParams::default());
+        let json = serde_json::to_value(&req).unwrap();
+
+        assert_eq!(json["max_tokens"], DEFAULT_MAX_TOKENS);
+        assert!((json["temperature"].as_f64().unwrap() - 0.7).abs() < 1e-6);
+        assert!(json.get("top_p").is_none());
+        assert!(json.get("stop").is_none());
+    }
+
+    #[test]
+    fn test_build_chat_request_includes_provider_prefs() {
+        let params = SamplingParams::default();
+        let unrouted = build_chat_request("m", "hi", None, &[], &params)
+            .with_routing(&RequestOptions::default());
+        let json = serde_json::to_value(&unrouted).unwrap();
+        assert!(json.get("provider").is_none());
+
+        let options = RequestOptions {
+            provider: ProviderPrefs {
+                order: vec!["anthropic".into(), "together".into()],
+                allow_fallbacks: Some(false),
+                data_collection: Some(crate::config::DataCollection::Deny),
+                ignore: vec![],
+            },
+            ..Default::default()
+        };
+        let routed = build_chat_request("m", "hi", None, &[], &params).with_routing(&options);
+        let json = serde_json::to_value(&routed).unwrap();
+        assert_eq!(
+            json["provider"],
+            serde_json::json!({
+                "order": ["anthropic", "together"],
+                "allow_fallbacks": false,
+                "data_collection": "deny"
+            })
+        );
+        // Routed and unrouted replies are cached apart
+        assert_ne!(request_cache_key(&unrouted), request_cache_key(&routed));
+    }
+
+    #[test]
+    fn test_request_headers_merge_configured() {
+        let names = |headers: &[(String, String)]| {
+            headers.iter().map(|(n, _)| n.clone()).collect::<Vec<_>>()
+        };
+        let defaults = request_headers("sk", &RequestOptions::default());
+        assert_eq!(
+            names(&defaults),
+            vec!["Authorization", "Content-Type", "HTTP-Referer", "X-Title"]
+        );
+
+        let options = RequestOptions {
+            headers: [
+                ("x-title", "ci-bot"),
+                ("X-Team", "infra"),
+                ("authorization", "Bearer other"),
+            ]
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect(),
+            ..Default::default()
+        };
+        let headers = request_headers("sk", &options);
+        assert_eq!(
+            names(&headers),
+            [
+                "Authorization",
+                "Content-Type",
+                "HTTP-Referer",
+                "X-Team",
+                "x-title"
+            ]
+        );
+        assert_eq!(headers[0].1, "Bearer sk");
+        assert_eq!(headers[4].1, "ci-bot");
+    }
+
+    #[test]
+    fn test_cache_key_changes_with_sampling_params() {
+        let history = vec![serde_json::json!({"role": "user", "content": "earlier"})];
+        let key = |model: &str, prompt: &str, params: &SamplingParams| {
+            request_cache_key(&build_chat_request(model, prompt, None, &history, params))
+        };
+        let params = SamplingParams::default();
+        assert_eq!(key("m", "hi", &params), key("m", "hi", &params));
+        assert_ne!(key("m", "hi", &params), key("other", "hi", &params));
+        assert_ne!(key("m", "hi", &params), key("m", "hello", &params));
+
+        let warmer = SamplingParams {
+            temperature: Some(1.2),
+            ..Default::default()
+        };
+        let capped = SamplingParams {
+            max_tokens: Some(64),
+            ..Default::default()
+        };
+        assert_ne!(key("m", "hi", &params), key("m", "hi", &warmer));
+        assert_ne!(key("m", "hi", &params), key("m", "hi", &capped));
+    }
+
+    #[tokio::test]
+    async fn test_replay_streams_cached_tokens() {
+        let mut rx = replay(vec!["cached ".into(), "answer".into()]);
+        let mut text = String::new();
+        let mut done = false;
+        while let Some(event) = rx.recv().await {
+            match event {
+                StreamEvent::Token(t) => text.push_str(&t),
+                StreamEvent::Done(usage) => {
+                    assert_eq!(usage.total_tokens, 0);
+                    done = true;
+                }
+                StreamEvent::Error(e) => panic!("unexpected error: {}", e),
+            }
+        }
+        assert_eq!(text, "cached answer");
+        assert!(done);
     }
 
     // --- Shared client ---
@@ -862,5 +1422,7 @@ mod tests {
         assert_eq!(usage.prompt_tokens, 0);
         assert_eq!(usage.completion_tokens, 0);
         assert_eq!(usage.total_tokens, 0);
+        assert_eq!(usage.cache_read_tokens, 0);
+        assert_eq!(usage.cache_write_tokens, 0);
     }
 }
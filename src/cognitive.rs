@@ -177,6 +177,27 @@ impl Momentum {
 // STUCK DETECTION
 // ═══════════════════════════════════════════════════════════════
 
+/// Why the stuck detector tripped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StuckReason {
+    /// The same action 3+ times in the recent window
+    RepeatedAction,
+    /// The same error 3+ times
+    RepeatedError,
+    /// Nothing changed in 5 iterations
+    NoProgress,
+}
+
+impl StuckReason {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            StuckReason::RepeatedAction => "the same action keeps repeating",
+            StuckReason::RepeatedError => "the same error keeps coming back",
+            StuckReason::NoProgress => "nothing has changed in several iterations",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct StuckDetector {
     recent_actions: VecDeque<u64>, // Hashes of recent actions
@@ -213,19 +234,24 @@ impl StuckDetector {
     }
 
     pub fn is_stuck(&self) -> bool {
+        self.reason().is_some()
+    }
+
+    /// Why the loop is stuck, if it is
+    pub fn reason(&self) -> Option<StuckReason> {
         // Same action repeated 3+ times
         if self.has_repeated_action(3) {
-            return true;
+            return Some(StuckReason::RepeatedAction);
         }
         // Same error 3+ times
         if self.error_counts.values().any(|&c| c >= 3) {
-            return true;
+            return Some(StuckReason::RepeatedError);
         }
         // No changes in 5 iterations
         if self.no_change_count >= 5 {
-            return true;
+            return Some(StuckReason::NoProgress);
         }
-        false
+        None
     }
 
     fn has_repeated_action(&self, threshold: usize) -> bool {
@@ -248,6 +274,152 @@ impl StuckDetector {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════
+// STUCK RECOVERY
+// ═══════════════════════════════════════════════════════════════
+
+/// Recoveries tried per task before a stuck loop stops
+pub const MAX_STUCK_RECOVERIES: usize = 2;
+
+/// Something to try on a stuck loop before giving up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Add more of the project to the context
+    BroadenContext,
+    /// Continue on a stronger model
+    StrongerModel,
+    /// Split the task into smaller subtasks
+    Decompose,
+}
+
+impl Recovery {
+    /// Every recovery, in the order they're tried after the preferred one
+    pub const ALL: [Recovery; 3] = [
+        Recovery::BroadenContext,
+        Recovery::StrongerModel,
+        Recovery::Decompose,
+    ];
+
+    /// First thing to try for `reason`: repeating an action usually means
+    /// looking for something that isn't in view; an error the model keeps
+    /// hitting is beyond it; no progress at all means the step is too big
+    pub fn for_reason(reason: StuckReason) -> Self {
+        match reason {
+            StuckReason::RepeatedAction => Recovery::BroadenContext,
+            StuckReason::RepeatedError => Recovery::StrongerModel,
+            StuckReason::NoProgress => Recovery::Decompose,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Recovery::BroadenContext => "broaden context",
+            Recovery::StrongerModel => "stronger model",
+            Recovery::Decompose => "decompose",
+        }
+    }
+
+    /// Instruction added to the next continuation prompt
+    pub fn instruction(&self) -> &'static str {
+        match self {
+            Recovery::BroadenContext => {
+                "More project files are now in context. Use them to take a different approach \
+                 instead of repeating the last action."
+            }
+            Recovery::StrongerModel => {
+                "You are taking over from a model that kept hitting the same error. Read the \
+                 errors above and try a different fix."
+            }
+            Recovery::Decompose => {
+                "Break the task into 2-4 smaller subtasks. List them, then do only the first \
+                 one."
+            }
+        }
+    }
+}
+
+/// Escalation for a stuck loop: the recovery its reason calls for, then
+/// the others, at most `max_attempts` per task
+#[derive(Debug, Clone)]
+pub struct StuckRecovery {
+    max_attempts: usize,
+    tried: Vec<Recovery>,
+}
+
+impl Default for StuckRecovery {
+    fn default() -> Self {
+        Self::new(MAX_STUCK_RECOVERIES)
+    }
+}
+
+impl StuckRecovery {
+    pub fn new(max_attempts: usize) -> Self {
+        Self {
+            max_attempts,
+            tried: Vec::new(),
+        }
+    }
+
+    /// Next recovery for `reason` among those `available` now; None once
+    /// attempts are used up or nothing untried is left
+    pub fn next(
+        &mut self,
+        reason: StuckReason,
+        available: impl Fn(Recovery) -> bool,
+    ) -> Option<Recovery> {
+        if self.tried.len() >= self.max_attempts {
+            return None;
+        }
+        let recovery = std::iter::once(Recovery::for_reason(reason))
+            .chain(Recovery::ALL)
+            .find(|r| !self.tried.contains(r) && available(*r))?;
+        self.tried.push(recovery);
+        Some(recovery)
+    }
+
+    pub fn attempts(&self) -> usize {
+        self.tried.len()
+    }
+
+    /// Start over for a new task
+    pub fn reset(&mut self) {
+        self.tried.clear();
+    }
+}
+
+/// Project files whose paths mention the most `keywords`, skipping
+/// directories and `known` files; at most `limit`
+pub fn related_files(
+    files: &[String],
+    keywords: &[String],
+    known: &[String],
+    limit: usize,
+) -> Vec<String> {
+    let mut scored: Vec<(usize, &String)> = files
+        .iter()
+        .filter(|f| !f.ends_with('/') && !known.contains(f))
+        .map(|f| {
+            let lower = f.to_lowercase();
+            let hits = keywords
+                .iter()
+                .filter(|k| lower.contains(k.as_str()))
+                .count();
+            (hits, f)
+        })
+        .filter(|(hits, _)| *hits > 0)
+        .collect();
+    // Most matches first, then shallower paths
+    scored.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then(a.1.matches('/').count().cmp(&b.1.matches('/').count()))
+    });
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, f)| f.clone())
+        .collect()
+}
+
 // ═══════════════════════════════════════════════════════════════
 // SANITY CHECK
 // ═══════════════════════════════════════════════════════════════
@@ -336,8 +508,11 @@ pub enum ToolRisk {
 impl ToolRisk {
     pub fn from_tool_call(tool: &str, args: &str) -> Self {
         match tool {
-            "read" | "glob" | "grep" => ToolRisk::Safe,
+            "read" | "glob" | "grep" | "tree" | "stats" | "docs" | "environ" | "ask_user" => {
+                ToolRisk::Safe
+            }
             "write" | "edit" => ToolRisk::Cautious,
+            "move" | "delete" => ToolRisk::Confirm,
             "bash" | "shell" => {
                 // Analyze command for danger signals
                 let lower = args.to_lowercase();
@@ -568,7 +743,8 @@ impl ContextLayers {
     }
 }
 
-fn estimate_tokens(text: &str) -> usize {
+/// Estimate token count from text (~4 chars per token)
+pub fn estimate_tokens(text: &str) -> usize {
     // Rough estimate: ~4 chars per token
     text.len() / 4
 }
@@ -625,13 +801,14 @@ pub enum ContextCategory {
     Fact,              // Extracted key facts
     Intent,            // User's goals
     Constraint,        // Constraints/requirements
+    Pinned,            // User-pinned notes, never trimmed
 }
 
 impl ContextCategory {
     /// Base salience weight for this category
     pub fn base_weight(&self) -> f32 {
         match self {
-            ContextCategory::SystemPrompt => 1.0,
+            ContextCategory::SystemPrompt | ContextCategory::Pinned => 1.0,
             ContextCategory::UserMessage => 0.9,
             ContextCategory::Error => 0.95, // Errors are very salient
             ContextCategory::Intent => 0.85,
@@ -702,8 +879,12 @@ pub struct SalienceContext {
     token_budget: usize,
     current_keywords: Vec<String>,
     focus_files: Vec<String>,
+    locked_files: Vec<String>,
 }
 
+/// Score added to content that mentions a file in the `/focus` working set
+const LOCKED_FILE_BOOST: f32 = 0.3;
+
 impl SalienceContext {
     pub fn new(token_budget: usize) -> Self {
         Self {
@@ -711,6 +892,7 @@ impl SalienceContext {
             token_budget,
             current_keywords: Vec::new(),
             focus_files: Vec::new(),
+            locked_files: Vec::new(),
         }
     }
 
@@ -724,10 +906,23 @@ impl SalienceContext {
         self.focus_files = files;
     }
 
+    /// Set the working set pinned with `/focus`; content about these files
+    /// outranks the rest
+    pub fn set_locked_files(&mut self, files: Vec<String>) {
+        self.locked_files = files;
+    }
+
     /// Add a context item with automatic salience scoring
     pub fn add(&mut self, content: String, category: ContextCategory, age: u32) {
         let factors = self.calculate_factors(&content, age);
-        let score = factors.score(category);
+        let mut score = factors.score(category);
+        if self
+            .locked_files
+            .iter()
+            .any(|f| content.contains(f.trim_start_matches("./")))
+        {
+            score = (score + LOCKED_FILE_BOOST).min(1.0);
+        }
         let tier = self.score_to_tier(score);
         let tokens = estimate_tokens(&content);
 
@@ -764,6 +959,11 @@ impl SalienceContext {
         });
     }
 
+    /// Add a pinned note: Focus tier, always included even over budget
+    pub fn pin(&mut self, content: String) {
+        self.add_with_tier(content, ContextCategory::Pinned, SalienceTier::Focus);
+    }
+
     fn calculate_factors(&self, content: &str, age: u32) -> SalienceFactors {
         let content_lower = content.to_lowercase();
 
@@ -830,8 +1030,19 @@ impl SalienceContext {
         let mut output = String::new();
         let mut used_tokens = 0;
 
+        // Pinned notes go first, verbatim, whatever the budget
+        let (pinned, rest): (Vec<_>, Vec<_>) = self
+            .items
+            .iter()
+            .partition(|item| item.category == ContextCategory::Pinned);
+        for item in pinned {
+            output.push_str(&item.content);
+            output.push('\n');
+            used_tokens += item.tokens;
+        }
+
         // Sort by tier (highest first), then by score within tier
-        let mut sorted: Vec<_> = self.items.iter().collect();
+        let mut sorted = rest;
         sorted.sort_by(|a, b| match b.tier.cmp(&a.tier) {
             std::cmp::Ordering::Equal => b
                 .score
@@ -1041,6 +1252,103 @@ mod tests {
         s.record_action(12345);
         s.record_action(12345);
         assert!(s.is_stuck());
+        assert_eq!(s.reason(), Some(StuckReason::RepeatedAction));
+
+        let mut s = StuckDetector::default();
+        for _ in 0..3 {
+            s.record_error("bash");
+        }
+        assert_eq!(s.reason(), Some(StuckReason::RepeatedError));
+
+        let mut s = StuckDetector::default();
+        for _ in 0..5 {
+            s.record_no_change();
+        }
+        assert_eq!(s.reason(), Some(StuckReason::NoProgress));
+    }
+
+    #[test]
+    fn test_stuck_reason_picks_recovery() {
+        assert_eq!(
+            Recovery::for_reason(StuckReason::RepeatedAction),
+            Recovery::BroadenContext
+        );
+        assert_eq!(
+            Recovery::for_reason(StuckReason::RepeatedError),
+            Recovery::StrongerModel
+        );
+        assert_eq!(
+            Recovery::for_reason(StuckReason::NoProgress),
+            Recovery::Decompose
+        );
+
+        // Each reason gets its own recovery first
+        for reason in [
+            StuckReason::RepeatedAction,
+            StuckReason::RepeatedError,
+            StuckReason::NoProgress,
+        ] {
+            let mut ladder = StuckRecovery::default();
+            assert_eq!(
+                ladder.next(reason, |_| true),
+                Some(Recovery::for_reason(reason))
+            );
+        }
+    }
+
+    #[test]
+    fn test_stuck_recovery_escalates_then_gives_up() {
+        let mut ladder = StuckRecovery::default();
+        assert_eq!(
+            ladder.next(StuckReason::RepeatedError, |_| true),
+            Some(Recovery::StrongerModel)
+        );
+        // The same reason again moves on to something not yet tried
+        assert_eq!(
+            ladder.next(StuckReason::RepeatedError, |_| true),
+            Some(Recovery::BroadenContext)
+        );
+        assert_eq!(ladder.attempts(), MAX_STUCK_RECOVERIES);
+        assert_eq!(ladder.next(StuckReason::NoProgress, |_| true), None);
+
+        // Unavailable recoveries are skipped; a new task starts over
+        ladder.reset();
+        assert_eq!(
+            ladder.next(StuckReason::RepeatedError, |r| r != Recovery::StrongerModel),
+            Some(Recovery::BroadenContext)
+        );
+        let mut none = StuckRecovery::default();
+        assert_eq!(none.next(StuckReason::NoProgress, |_| false), None);
+        assert_eq!(none.attempts(), 0);
+    }
+
+    #[test]
+    fn test_related_files() {
+        let files: Vec<String> = [
+            "src/",
+            "src/session.rs",
+            "src/ui.rs",
+            "src/api/session_store.rs",
+            "tests/session_ui.rs",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let keywords = vec!["session".to_string(), "ui".to_string()];
+        assert_eq!(
+            related_files(&files, &keywords, &[], 10),
+            vec![
+                "tests/session_ui.rs",
+                "src/session.rs",
+                "src/ui.rs",
+                "src/api/session_store.rs"
+            ]
+        );
+        assert_eq!(
+            related_files(&files, &keywords, &["tests/session_ui.rs".to_string()], 1),
+            vec!["src/session.rs"]
+        );
+        assert!(related_files(&files, &["zzz".to_string()], &[], 10).is_empty());
     }
 
     #[test]
@@ -1121,6 +1429,51 @@ mod tests {
         assert!(stats.focus_items >= 1, "Error should be in focus tier");
     }
 
+    #[test]
+    fn test_locked_files_rank_higher() {
+        let build = |locked: Vec<String>| {
+            let mut ctx = SalienceContext::new(1000);
+            ctx.set_locked_files(locked);
+            ctx.add(
+                "Changed the retry loop in src/client.rs".into(),
+                ContextCategory::AssistantResponse,
+                4,
+            );
+            ctx.add(
+                "Renamed the token check in src/auth.rs".into(),
+                ContextCategory::AssistantResponse,
+                4,
+            );
+            let stats = ctx.stats();
+            (ctx.build(), stats.focus_items + stats.recent_items)
+        };
+
+        let (built, _) = build(vec![]);
+        assert!(built.find("src/client.rs").unwrap() < built.find("src/auth.rs").unwrap());
+
+        let (built, upper) = build(vec!["src/auth.rs".into()]);
+        assert!(built.find("src/auth.rs").unwrap() < built.find("src/client.rs").unwrap());
+        assert_eq!(upper, 1);
+    }
+
+    #[test]
+    fn test_pinned_survive_over_budget() {
+        let mut ctx = SalienceContext::new(10);
+        ctx.add("x ".repeat(200), ContextCategory::UserMessage, 0);
+        ctx.pin("The API base is https://api.example.com".into());
+        ctx.pin("Don't touch src/legacy/".into());
+
+        let built = ctx.build();
+        let pins = "The API base is https://api.example.com\nDon't touch src/legacy/\n";
+        assert!(built.starts_with(pins));
+        assert!(!built.contains(&"x ".repeat(200)));
+
+        // Even with no budget at all
+        let mut empty = SalienceContext::new(0);
+        empty.pin("keep me".into());
+        assert_eq!(empty.build(), "keep me\n");
+    }
+
     #[test]
     fn test_extract_keywords() {
         let text = "Please help me fix the authentication bug in the login module";
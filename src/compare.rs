@@ -0,0 +1,713 @@
+//! Side-by-side model comparison
+//!
+//! Sends one prompt to several models concurrently and collects
+//! latency, token, cost and quality stats for each.
+
+#![allow(dead_code)] // Forward-looking module
+
+use anyhow::Result;
+use futures::future::join_all;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+use crate::client::TokenUsage;
+use crate::eval::ResponseEvaluator;
+use crate::models::Model;
+
+// ═══════════════════════════════════════════════════════════════
+// CONFIG & RESULTS
+// ═══════════════════════════════════════════════════════════════
+
+/// Limits for a comparison run
+#[derive(Debug, Clone)]
+pub struct CompareConfig {
+    /// Max models queried at once
+    pub max_concurrent: usize,
+    /// Per-model deadline
+    pub timeout: Duration,
+}
+
+impl Default for CompareConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 4,
+            timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+/// Outcome for a single model
+#[derive(Debug, Clone)]
+pub struct CompareResult {
+    pub model: String,
+    pub response: Option<String>,
+    pub error: Option<String>,
+    pub latency_ms: u64,
+    pub usage: TokenUsage,
+    pub cost: f64,
+    pub quality: f32,
+}
+
+impl CompareResult {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// RUNNER
+// ═══════════════════════════════════════════════════════════════
+
+/// Parse a `m1,m2,...` list, dropping blanks and duplicates
+pub fn parse_model_list(spec: &str) -> Vec<String> {
+    let mut models: Vec<String> = Vec::new();
+    for m in spec.split(',').map(str::trim).filter(|m| !m.is_empty()) {
+        if !models.iter().any(|existing| existing == m) {
+            models.push(m.to_string());
+        }
+    }
+    models
+}
+
+/// Query every model with `prompt`, returning results in input order.
+///
+/// `complete` performs one request; a failure or timeout is recorded on
+/// that model's result and never aborts the others.
+pub async fn compare_models<F, Fut>(
+    models: &[String],
+    prompt: &str,
+    config: &CompareConfig,
+    complete: F,
+) -> Vec<CompareResult>
+where
+    F: Fn(String, String) -> Fut,
+    Fut: Future<Output = Result<(String, TokenUsage)>>,
+{
+    let semaphore = Arc::new(Semaphore::new(config.max_concurrent.max(1)));
+    let evaluator = ResponseEvaluator::new();
+
+    let tasks = models.iter().map(|model| {
+        let semaphore = semaphore.clone();
+        let fut = complete(model.clone(), prompt.to_string());
+        let evaluator = &evaluator;
+        async move {
+            let _permit = semaphore.acquire().await.expect("semaphore closed");
+            let start = Instant::now();
+            let outcome = tokio::time::timeout(config.timeout, fut).await;
+            let latency_ms = start.elapsed().as_millis() as u64;
+
+            let mut result = CompareResult {
+                model: model.clone(),
+                response: None,
+                error: None,
+                latency_ms,
+                usage: TokenUsage::default(),
+                cost: 0.0,
+                quality: 0.0,
+            };
+
+            match outcome {
+                Ok(Ok((response, usage))) => {
+                    result.quality = evaluator.evaluate(prompt, &response).overall;
+                    result.cost = crate::models::calculate_cost(model, &usage);
+                    result.usage = usage;
+                    result.response = Some(response);
+                }
+                Ok(Err(e)) => result.error = Some(e.to_string()),
+                Err(_) => {
+                    result.error = Some(format!("Timeout after {}s", config.timeout.as_secs()))
+                }
+            }
+            result
+        }
+    });
+
+    join_all(tasks).await
+}
+
+// ═══════════════════════════════════════════════════════════════
+// AUTO-SELECTION
+// ═══════════════════════════════════════════════════════════════
+
+/// Free models probed by `hyle --auto`
+pub const AUTO_PROBE_MODELS: usize = 4;
+
+/// Small task that separates models that follow instructions from ones that ramble
+pub const AUTO_PROBE_PROMPT: &str = "Write a Rust function `fn is_even(n: u32) -> bool`. \
+Reply with only a code block.";
+
+/// Free models to probe, largest context first
+pub fn probe_candidates(models: &[Model], limit: usize) -> Vec<String> {
+    crate::models::get_free_models(models)
+        .into_iter()
+        .take(limit)
+        .map(|m| m.id.clone())
+        .collect()
+}
+
+/// Highest-quality successful result; faster wins ties. None if nothing usable.
+pub fn pick_best(results: &[CompareResult]) -> Option<String> {
+    results
+        .iter()
+        .filter(|r| r.is_ok() && r.quality > 0.0)
+        .max_by(|a, b| {
+            a.quality
+                .partial_cmp(&b.quality)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.latency_ms.cmp(&a.latency_ms))
+        })
+        .map(|r| r.model.clone())
+}
+
+/// Probe `candidates` concurrently and return the best scoring model
+pub async fn auto_select<F, Fut>(
+    candidates: &[String],
+    config: &CompareConfig,
+    complete: F,
+) -> Option<String>
+where
+    F: Fn(String, String) -> Fut,
+    Fut: Future<Output = Result<(String, TokenUsage)>>,
+{
+    if candidates.is_empty() {
+        return None;
+    }
+    let results = compare_models(candidates, AUTO_PROBE_PROMPT, config, complete).await;
+    pick_best(&results)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// LIVE COMPARISON
+// ═══════════════════════════════════════════════════════════════
+
+/// Most models the TUI compares at once; more columns get too narrow
+pub const MAX_LIVE_MODELS: usize = 3;
+
+/// Worst-case spend (USD) allowed for one TUI comparison
+pub const COMPARE_COST_CEILING: f64 = 0.05;
+
+/// Split `models` into those that fit under `ceiling` and those skipped
+///
+/// `estimate` gives a model's worst-case cost for the request; models are
+/// admitted in order while the running total stays under the ceiling.
+pub fn within_cost_ceiling(
+    models: &[String],
+    ceiling: f64,
+    estimate: impl Fn(&str) -> f64,
+) -> (Vec<String>, Vec<(String, f64)>) {
+    let mut kept = Vec::new();
+    let mut skipped = Vec::new();
+    let mut total = 0.0;
+    for model in models {
+        let cost = estimate(model);
+        if total + cost <= ceiling {
+            total += cost;
+            kept.push(model.clone());
+        } else {
+            skipped.push((model.clone(), cost));
+        }
+    }
+    (kept, skipped)
+}
+
+/// One model's response as it streams in
+#[derive(Debug, Clone)]
+pub struct LiveColumn {
+    pub model: String,
+    pub text: String,
+    /// Chunks received, roughly one token each
+    pub chunks: usize,
+    pub elapsed_ms: u64,
+    /// Set once the stream finishes or fails
+    pub result: Option<CompareResult>,
+}
+
+impl LiveColumn {
+    pub fn tokens_per_sec(&self) -> f32 {
+        if self.elapsed_ms == 0 {
+            return 0.0;
+        }
+        self.chunks as f32 * 1000.0 / self.elapsed_ms as f32
+    }
+}
+
+/// Ready-to-draw contents of one column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnView {
+    pub title: String,
+    /// Token rate while streaming; score, cost and latency once done
+    pub status: String,
+    /// Response so far, wrapped to the column
+    pub lines: Vec<String>,
+}
+
+/// A comparison in progress: one prompt, one column per model
+#[derive(Debug, Clone)]
+pub struct LiveCompare {
+    pub prompt: String,
+    pub columns: Vec<LiveColumn>,
+}
+
+impl LiveCompare {
+    pub fn new(prompt: &str, models: &[String]) -> Self {
+        let columns = models
+            .iter()
+            .map(|model| LiveColumn {
+                model: model.clone(),
+                text: String::new(),
+                chunks: 0,
+                elapsed_ms: 0,
+                result: None,
+            })
+            .collect();
+        Self {
+            prompt: prompt.to_string(),
+            columns,
+        }
+    }
+
+    pub fn token(&mut self, slot: usize, chunk: &str, elapsed_ms: u64) {
+        if let Some(col) = self.columns.get_mut(slot).filter(|c| c.result.is_none()) {
+            col.text.push_str(chunk);
+            col.chunks += 1;
+            col.elapsed_ms = elapsed_ms;
+        }
+    }
+
+    /// Stream finished: score the response and price the usage
+    pub fn finish(&mut self, slot: usize, usage: TokenUsage, elapsed_ms: u64) {
+        let prompt = self.prompt.clone();
+        if let Some(col) = self.columns.get_mut(slot).filter(|c| c.result.is_none()) {
+            col.elapsed_ms = elapsed_ms;
+            col.result = Some(CompareResult {
+                model: col.model.clone(),
+                response: Some(col.text.clone()),
+                error: None,
+                latency_ms: elapsed_ms,
+                cost: crate::models::calculate_cost(&col.model, &usage),
+                usage,
+                quality: ResponseEvaluator::new()
+                    .evaluate(&prompt, &col.text)
+                    .overall,
+            });
+        }
+    }
+
+    pub fn fail(&mut self, slot: usize, error: &str, elapsed_ms: u64) {
+        if let Some(col) = self.columns.get_mut(slot).filter(|c| c.result.is_none()) {
+            col.elapsed_ms = elapsed_ms;
+            col.result = Some(CompareResult {
+                model: col.model.clone(),
+                response: None,
+                error: Some(error.to_string()),
+                latency_ms: elapsed_ms,
+                usage: TokenUsage::default(),
+                cost: 0.0,
+                quality: 0.0,
+            });
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.columns.iter().all(|c| c.result.is_some())
+    }
+
+    /// Finished results, in column order
+    pub fn results(&self) -> Vec<CompareResult> {
+        self.columns
+            .iter()
+            .filter_map(|c| c.result.clone())
+            .collect()
+    }
+
+    /// The successful result in `slot` (0-based), if it can be adopted
+    pub fn adoptable(&self, slot: usize) -> Option<&CompareResult> {
+        self.columns
+            .get(slot)
+            .and_then(|c| c.result.as_ref())
+            .filter(|r| r.is_ok())
+    }
+
+    /// Columns for a row `width` chars wide, each response wrapped to fit
+    /// inside a bordered block; once all are done the best scorer is starred
+    pub fn layout(&self, width: usize) -> Vec<ColumnView> {
+        if self.columns.is_empty() {
+            return vec![];
+        }
+        let inner = (width / self.columns.len()).saturating_sub(2).max(1);
+        let best = if self.is_done() {
+            pick_best(&self.results())
+        } else {
+            None
+        };
+
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, col)| {
+                let star = if best.as_deref() == Some(col.model.as_str()) {
+                    " ★"
+                } else {
+                    ""
+                };
+                let status = match &col.result {
+                    None => format!("⋯ {} tok  {:.1} tok/s", col.chunks, col.tokens_per_sec()),
+                    Some(r) => match &r.error {
+                        Some(err) => format!("✗ {}", err),
+                        None => format!(
+                            "✓ quality {:.0}%  {} tok  {:.1} tok/s  ${:.4}  {:.1}s",
+                            r.quality * 100.0,
+                            r.usage.completion_tokens.max(col.chunks as u32),
+                            col.tokens_per_sec(),
+                            r.cost,
+                            r.latency_ms as f32 / 1000.0
+                        ),
+                    },
+                };
+                ColumnView {
+                    title: format!("[{}] {}{}", i + 1, col.model, star),
+                    status,
+                    lines: wrap_text(&col.text, inner),
+                }
+            })
+            .collect()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// RENDERING
+// ═══════════════════════════════════════════════════════════════
+
+/// Narrowest column before falling back to stacked output
+const MIN_COLUMN_WIDTH: usize = 30;
+
+/// One-line stats per model
+pub fn format_stats(results: &[CompareResult]) -> String {
+    let mut out = String::new();
+    for r in results {
+        if let Some(err) = &r.error {
+            out.push_str(&format!(
+                "  ✗ {}  {}ms  error: {}\n",
+                r.model, r.latency_ms, err
+            ));
+        } else {
+            out.push_str(&format!(
+                "  ✓ {}  {}ms  {}+{} tok  ${:.6}  quality {:.0}%\n",
+                r.model,
+                r.latency_ms,
+                r.usage.prompt_tokens,
+                r.usage.completion_tokens,
+                r.cost,
+                r.quality * 100.0
+            ));
+        }
+    }
+    out
+}
+
+/// Render responses in columns when `width` allows, stacked otherwise
+pub fn format_side_by_side(results: &[CompareResult], width: usize) -> String {
+    if results.is_empty() {
+        return String::new();
+    }
+
+    let sep = " │ ";
+    let col_width = width.saturating_sub(sep.chars().count() * (results.len() - 1)) / results.len();
+
+    let bodies: Vec<String> = results
+        .iter()
+        .map(|r| match (&r.response, &r.error) {
+            (Some(resp), _) => resp.clone(),
+            (None, Some(err)) => format!("[error: {}]", err),
+            (None, None) => String::new(),
+        })
+        .collect();
+
+    if col_width < MIN_COLUMN_WIDTH {
+        let mut out = String::new();
+        for (r, body) in results.iter().zip(&bodies) {
+            out.push_str(&format!("─── {} ───\n{}\n\n", r.model, body.trim_end()));
+        }
+        return out;
+    }
+
+    let columns: Vec<Vec<String>> = results
+        .iter()
+        .zip(&bodies)
+        .map(|(r, body)| {
+            let mut lines = wrap_text(&r.model, col_width);
+            lines.push("─".repeat(col_width));
+            lines.extend(wrap_text(body, col_width));
+            lines
+        })
+        .collect();
+
+    let rows = columns.iter().map(|c| c.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for row in 0..rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|c| pad_to(c.get(row).map(|s| s.as_str()).unwrap_or(""), col_width))
+            .collect();
+        out.push_str(cells.join(sep).trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Hard-wrap text to `width` chars per line
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+            continue;
+        }
+        for chunk in chars.chunks(width.max(1)) {
+            lines.push(chunk.iter().collect());
+        }
+    }
+    lines
+}
+
+fn pad_to(s: &str, width: usize) -> String {
+    let len = s.chars().count();
+    format!("{}{}", s, " ".repeat(width.saturating_sub(len)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(completion: u32) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: 10,
+            completion_tokens: completion,
+            total_tokens: 10 + completion,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_model_list() {
+        let models = parse_model_list("a/one, b/two,,a/one ");
+        assert_eq!(models, vec!["a/one".to_string(), "b/two".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_compare_models_distinct_responses() {
+        let models = vec!["a/one".to_string(), "b/two".to_string()];
+        let results = compare_models(
+            &models,
+            "say hi",
+            &CompareConfig::default(),
+            |model, _prompt| async move { Ok((format!("hi from {}", model), usage(5))) },
+        )
+        .await;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].model, "a/one");
+        assert_eq!(results[0].response.as_deref(), Some("hi from a/one"));
+        assert_eq!(results[1].response.as_deref(), Some("hi from b/two"));
+        assert_eq!(results[1].usage.completion_tokens, 5);
+    }
+
+    #[tokio::test]
+    async fn test_compare_models_failure_does_not_abort_others() {
+        let models = vec!["bad/model".to_string(), "good/model".to_string()];
+        let results = compare_models(
+            &models,
+            "say hi",
+            &CompareConfig::default(),
+            |model, _prompt| async move {
+                if model.starts_with("bad") {
+                    anyhow::bail!("model not found");
+                }
+                Ok(("hello".to_string(), usage(1)))
+            },
+        )
+        .await;
+
+        assert!(!results[0].is_ok());
+        assert!(results[0].error.as_deref().unwrap().contains("not found"));
+        assert!(results[1].is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_compare_models_timeout() {
+        let models = vec!["slow/model".to_string()];
+        let config = CompareConfig {
+            max_concurrent: 1,
+            timeout: Duration::from_millis(20),
+        };
+        let results = compare_models(&models, "x", &config, |_model, _prompt| async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok((String::new(), TokenUsage::default()))
+        })
+        .await;
+
+        assert!(results[0].error.as_deref().unwrap().contains("Timeout"));
+    }
+
+    fn scored(model: &str, quality: f32, latency_ms: u64, error: Option<&str>) -> CompareResult {
+        CompareResult {
+            model: model.into(),
+            response: error.is_none().then(|| "ok".to_string()),
+            error: error.map(String::from),
+            latency_ms,
+            usage: TokenUsage::default(),
+            cost: 0.0,
+            quality,
+        }
+    }
+
+    #[test]
+    fn test_pick_best_prefers_higher_score() {
+        let results = vec![
+            scored("a/low", 0.4, 100, None),
+            scored("b/high", 0.9, 900, None),
+            scored("c/failed", 1.0, 50, Some("rate limited")),
+        ];
+        assert_eq!(pick_best(&results).as_deref(), Some("b/high"));
+
+        // Equal quality: the faster model wins
+        let tie = vec![
+            scored("slow", 0.8, 900, None),
+            scored("fast", 0.8, 100, None),
+        ];
+        assert_eq!(pick_best(&tie).as_deref(), Some("fast"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_select_falls_back_when_probes_fail() {
+        let candidates = vec!["a/one".to_string(), "b/two".to_string()];
+        let picked = auto_select(&candidates, &CompareConfig::default(), |_m, _p| async {
+            anyhow::bail!("network down")
+        })
+        .await;
+        assert_eq!(picked, None);
+
+        assert_eq!(
+            auto_select(&[], &CompareConfig::default(), |_m, _p| async {
+                Ok((String::new(), TokenUsage::default()))
+            })
+            .await,
+            None
+        );
+    }
+
+    #[test]
+    fn test_probe_candidates_free_only() {
+        let model = |id: &str, ctx: u32, price: f64| Model {
+            id: id.into(),
+            name: id.into(),
+            context_length: ctx,
+            pricing_prompt: price,
+            pricing_completion: price,
+            pricing_cache_read: None,
+            pricing_cache_write: None,
+            capabilities: vec![],
+        };
+        let models = vec![
+            model("paid/big", 200_000, 1.0),
+            model("free/small", 8_000, 0.0),
+            model("free/large", 128_000, 0.0),
+        ];
+        assert_eq!(probe_candidates(&models, 1), vec!["free/large".to_string()]);
+        assert_eq!(probe_candidates(&models, 5).len(), 2);
+    }
+
+    #[test]
+    fn test_cost_ceiling_skips_expensive_models() {
+        let models = parse_model_list("free/a,paid/big,paid/small");
+        let estimate = |m: &str| match m {
+            "paid/big" => 0.08,
+            "paid/small" => 0.01,
+            _ => 0.0,
+        };
+        let (kept, skipped) = within_cost_ceiling(&models, COMPARE_COST_CEILING, estimate);
+        assert_eq!(kept, vec!["free/a".to_string(), "paid/small".to_string()]);
+        assert_eq!(skipped, vec![("paid/big".to_string(), 0.08)]);
+
+        // The total counts, not just each model
+        let (kept, _) = within_cost_ceiling(&models, 0.05, |_| 0.03);
+        assert_eq!(kept, vec!["free/a".to_string()]);
+    }
+
+    #[test]
+    fn test_live_compare_layout() {
+        let models = parse_model_list("a/one,b/two,c/three");
+        let mut live = LiveCompare::new("write hello world in rust", &models);
+        live.token(0, "fn main() {", 500);
+        live.token(0, " println!(\"hello world\"); }", 1000);
+        live.token(1, "Sure", 250);
+
+        // 90 wide: three 30-char columns, 28 inside the borders
+        let cols = live.layout(90);
+        assert_eq!(cols.len(), 3);
+        assert_eq!(cols[0].title, "[1] a/one");
+        assert_eq!(cols[0].status, "⋯ 2 tok  2.0 tok/s");
+        assert_eq!(cols[1].status, "⋯ 1 tok  4.0 tok/s");
+        assert_eq!(cols[2].status, "⋯ 0 tok  0.0 tok/s");
+        assert!(cols
+            .iter()
+            .flat_map(|c| &c.lines)
+            .all(|l| l.chars().count() <= 28));
+        assert_eq!(
+            cols[0].lines.concat(),
+            "fn main() { println!(\"hello world\"); }"
+        );
+
+        live.finish(0, usage(12), 1000);
+        live.fail(1, "Timeout after 60s", 60_000);
+        // Late chunks for a finished column are ignored
+        live.token(1, "more", 61_000);
+        assert!(!live.is_done());
+        let cols = live.layout(90);
+        assert!(cols[0].status.starts_with("✓ quality"));
+        assert!(cols[0].status.contains("12 tok"));
+        assert_eq!(cols[1].status, "✗ Timeout after 60s");
+        assert!(!cols[0].title.contains('★'));
+
+        live.finish(2, usage(1), 2000);
+        assert!(live.is_done());
+        let cols = live.layout(90);
+        assert_eq!(cols.iter().filter(|c| c.title.ends_with(" ★")).count(), 1);
+    }
+
+    #[test]
+    fn test_live_compare_adoptable() {
+        let models = parse_model_list("a/one,b/two");
+        let mut live = LiveCompare::new("hi", &models);
+        live.token(0, "hello", 10);
+        assert!(live.adoptable(0).is_none()); // still streaming
+        live.finish(0, usage(1), 20);
+        live.fail(1, "model not found", 30);
+
+        let adopted = live.adoptable(0).unwrap();
+        assert_eq!(adopted.model, "a/one");
+        assert_eq!(adopted.response.as_deref(), Some("hello"));
+        assert!(live.adoptable(1).is_none());
+        assert!(live.adoptable(2).is_none());
+    }
+
+    #[test]
+    fn test_side_by_side_falls_back_to_stacked() {
+        let results = vec![CompareResult {
+            model: "a/one".into(),
+            response: Some("hello".into()),
+            error: None,
+            latency_ms: 1,
+            usage: TokenUsage::default(),
+            cost: 0.0,
+            quality: 1.0,
+        }];
+        assert!(format_side_by_side(&results, 20).starts_with("─── a/one ───"));
+        let wide = format_side_by_side(&results, 80);
+        assert!(wide.starts_with("a/one"));
+        assert!(wide.contains("hello"));
+    }
+}
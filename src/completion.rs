@@ -0,0 +1,228 @@
+//! Tab-completion of file paths in the chat input
+//!
+//! The word under the cursor is completed when it looks like a path (has a
+//! `/` or `.`, or starts with `@`) or is the argument of a command that takes
+//! one (`/view`, `/edit`, `/attach`, ...). Candidates are project files,
+//! fuzzy-ranked; pressing Tab again cycles through them.
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+
+/// Slash commands whose arguments are paths
+const PATH_COMMANDS: &[&str] = &[
+    "view", "cat", "read", "edit", "open", "explain", "attach", "detach", "focus", "ls", "files",
+    "cd", "apply",
+];
+
+/// Candidates offered per completion
+pub const MAX_CANDIDATES: usize = 20;
+
+/// The word being completed
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathToken<'a> {
+    /// Byte offset of the word in the input
+    pub start: usize,
+    /// "@" for a mention, kept in front of the completed path
+    pub prefix: &'static str,
+    pub query: &'a str,
+}
+
+/// The path-like word ending at `cursor`, if any
+pub fn path_token(input: &str, cursor: usize) -> Option<PathToken<'_>> {
+    let cursor = cursor.min(input.len());
+    let before = input.get(..cursor)?;
+    let start = before.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+    let word = &before[start..];
+
+    if let Some(query) = word.strip_prefix('@') {
+        return Some(PathToken {
+            start,
+            prefix: "@",
+            query,
+        });
+    }
+    // The command itself isn't a path
+    if start == 0 && word.starts_with('/') && !word[1..].contains('/') {
+        return None;
+    }
+    let command_arg = input
+        .strip_prefix('/')
+        .and_then(|rest| rest.split_whitespace().next())
+        .is_some_and(|cmd| PATH_COMMANDS.contains(&cmd));
+    let looks_like_path = word.contains('/') || word.contains('.');
+    if !(command_arg || looks_like_path) {
+        return None;
+    }
+    Some(PathToken {
+        start,
+        prefix: "",
+        query: word,
+    })
+}
+
+/// `files` that fuzzy-match `query`, best first (shorter paths win ties);
+/// an empty query lists the first files in order
+pub fn path_candidates(query: &str, files: &[String]) -> Vec<String> {
+    let query = query.trim_start_matches("./");
+    if query.is_empty() {
+        return files.iter().take(MAX_CANDIDATES).cloned().collect();
+    }
+    let matcher = SkimMatcherV2::default();
+    let mut scored: Vec<(i64, &String)> = files
+        .iter()
+        .filter_map(|f| matcher.fuzzy_match(f, query).map(|score| (score, f)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.len().cmp(&b.1.len())));
+    scored
+        .into_iter()
+        .take(MAX_CANDIDATES)
+        .map(|(_, f)| f.clone())
+        .collect()
+}
+
+/// A completion in progress: Tab again moves to the next candidate
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathCompletion {
+    start: usize,
+    prefix: &'static str,
+    candidates: Vec<String>,
+    index: usize,
+    /// Input as last completed, to tell a repeated Tab from new typing
+    applied: String,
+}
+
+impl PathCompletion {
+    /// Start completing the word at `cursor`; None when there's no path
+    /// word or nothing matches
+    pub fn start(input: &str, cursor: usize, files: &[String]) -> Option<Self> {
+        let token = path_token(input, cursor)?;
+        let candidates = path_candidates(token.query, files);
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(Self {
+            start: token.start,
+            prefix: token.prefix,
+            candidates,
+            index: 0,
+            applied: String::new(),
+        })
+    }
+
+    /// Whether `input` is still what this completion last produced
+    pub fn is_current(&self, input: &str) -> bool {
+        !self.applied.is_empty() && self.applied == input
+    }
+
+    pub fn candidates(&self) -> &[String] {
+        &self.candidates
+    }
+
+    /// Put the current candidate in place of the word, returning the new
+    /// input and cursor; the next call moves on to the next candidate
+    pub fn apply(&mut self, input: &str, cursor: usize) -> (String, usize) {
+        let candidate = &self.candidates[self.index % self.candidates.len()];
+        let end = input[cursor.min(input.len())..]
+            .find(char::is_whitespace)
+            .map_or(input.len(), |i| cursor + i);
+        let replacement = format!("{}{}", self.prefix, candidate);
+        let completed = format!("{}{}{}", &input[..self.start], replacement, &input[end..]);
+        self.index += 1;
+        self.applied = completed.clone();
+        (completed, self.start + replacement.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Vec<String> {
+        [
+            "Cargo.toml",
+            "README.md",
+            "src/",
+            "src/main.rs",
+            "src/session.rs",
+            "src/ui.rs",
+            "src/api/server.rs",
+            "tests/session_roundtrip.rs",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_path_token() {
+        fn token(input: &str) -> Option<PathToken<'_>> {
+            path_token(input, input.len())
+        }
+        assert_eq!(token("/view src/ma").unwrap().query, "src/ma");
+        assert_eq!(token("/edit ").unwrap().query, "");
+        assert_eq!(token("/attach sess").unwrap().query, "sess");
+        let mention = token("look at @sess").unwrap();
+        assert_eq!(
+            (mention.start, mention.prefix, mention.query),
+            (8, "@", "sess")
+        );
+        assert_eq!(token("check main.rs").unwrap().query, "main.rs");
+
+        // Plain words, commands and non-path commands are left alone
+        assert_eq!(token("explain this"), None);
+        assert_eq!(token("/vie"), None);
+        assert_eq!(token("/model gemma"), None);
+        assert_eq!(token(""), None);
+        assert_eq!(token("hello "), None);
+
+        // Completes the word at the cursor, not the end of the input
+        let input = "/view src/ma please";
+        assert_eq!(path_token(input, 12).unwrap().query, "src/ma");
+    }
+
+    #[test]
+    fn test_path_candidates_from_fixture() {
+        let files = fixture();
+        assert_eq!(path_candidates("src/ma", &files)[0], "src/main.rs");
+        let session = path_candidates("session", &files);
+        assert_eq!(session.len(), 2);
+        assert!(session.contains(&"src/session.rs".to_string()));
+        assert!(session.contains(&"tests/session_roundtrip.rs".to_string()));
+        // A contiguous match ranks above a scattered one
+        assert_eq!(path_candidates("ui.r", &files)[0], "src/ui.rs");
+        assert!(path_candidates("zzz", &files).is_empty());
+        assert_eq!(path_candidates("", &files).len(), files.len());
+        assert_eq!(path_candidates("./READ", &files)[0], "README.md");
+
+        let many: Vec<String> = (0..50).map(|i| format!("src/mod{}.rs", i)).collect();
+        assert_eq!(path_candidates("mod", &many).len(), MAX_CANDIDATES);
+    }
+
+    #[test]
+    fn test_completion_cycles_candidates() {
+        let files = fixture();
+        let input = "/view session";
+        let mut completion = PathCompletion::start(input, input.len(), &files).unwrap();
+        assert_eq!(completion.candidates().len(), 2);
+
+        let (first, cursor) = completion.apply(input, input.len());
+        assert_eq!(first, format!("/view {}", completion.candidates()[0]));
+        assert_eq!(cursor, first.len());
+        assert!(completion.is_current(&first));
+
+        let (second, cursor) = completion.apply(&first, cursor);
+        assert_eq!(second, format!("/view {}", completion.candidates()[1]));
+        assert!(!completion.is_current(&first));
+        let (third, _) = completion.apply(&second, cursor);
+        assert_eq!(third, first);
+
+        // Mentions keep the @, and text after the word survives
+        let input = "see @ui.r now";
+        let mut completion = PathCompletion::start(input, 9, &files).unwrap();
+        let (done, cursor) = completion.apply(input, 9);
+        assert_eq!(done, "see @src/ui.rs now");
+        assert_eq!(&done[..cursor], "see @src/ui.rs");
+
+        assert!(PathCompletion::start("hello world", 11, &files).is_none());
+    }
+}
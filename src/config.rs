@@ -6,10 +6,10 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // ═══════════════════════════════════════════════════════════════
 // PERMISSION SYSTEM
@@ -45,8 +45,9 @@ impl ToolCategory {
     /// Get category for a tool name
     pub fn from_tool(tool: &str) -> Self {
         match tool {
-            "read" | "glob" | "grep" | "find" => Self::Read,
-            "write" | "patch" | "edit" => Self::Write,
+            "read" | "glob" | "grep" | "find" | "tree" | "stats" | "docs" | "environ"
+            | "ask_user" | "plan" => Self::Read,
+            "write" | "patch" | "edit" | "move" | "delete" => Self::Write,
             "bash" | "shell" | "exec" => Self::Execute,
             "git" | "commit" | "push" | "checkout" => Self::Git,
             _ => Self::Execute, // Unknown tools are treated as execute
@@ -198,6 +199,165 @@ impl PermissionCheck {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════
+// SUBPROCESS ENVIRONMENT
+// ═══════════════════════════════════════════════════════════════
+
+/// Environment handed to shell tool subprocesses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvPolicy {
+    /// Variables scrubbed from the child (exact names or globs like `*_TOKEN`)
+    #[serde(default = "default_env_deny")]
+    pub deny: Vec<String>,
+
+    /// Variables passed through even if they match `deny`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+
+    /// Variables injected into every child (override inherited values)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub set: BTreeMap<String, String>,
+}
+
+fn default_env_deny() -> Vec<String> {
+    [
+        "OPENROUTER_API_KEY",
+        "GITHUB_TOKEN",
+        "GH_TOKEN",
+        "AWS_SECRET_ACCESS_KEY",
+        "AWS_SESSION_TOKEN",
+        "*_API_KEY",
+        "*_TOKEN",
+        "*_SECRET",
+        "*_PASSWORD",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+impl Default for EnvPolicy {
+    fn default() -> Self {
+        Self {
+            deny: default_env_deny(),
+            allow: vec![],
+            set: BTreeMap::new(),
+        }
+    }
+}
+
+impl EnvPolicy {
+    /// Whether an inherited variable is passed to the child
+    pub fn passes(&self, name: &str) -> bool {
+        let matches = |pattern: &String| {
+            glob::Pattern::new(pattern)
+                .map(|p| p.matches(name))
+                .unwrap_or(pattern == name)
+        };
+        self.allow.iter().any(matches) || !self.deny.iter().any(matches)
+    }
+
+    /// Build the child environment from the parent's variables
+    pub fn child_env(
+        &self,
+        parent: impl IntoIterator<Item = (String, String)>,
+    ) -> Vec<(String, String)> {
+        let mut env: BTreeMap<String, String> = parent
+            .into_iter()
+            .filter(|(name, _)| self.passes(name))
+            .collect();
+        env.extend(self.set.clone());
+        env.into_iter().collect()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// PROTECTED PATHS
+// ═══════════════════════════════════════════════════════════════
+
+/// Files the write and patch tools refuse to modify unless a call passes
+/// `"allow_protected": true`
+///
+/// Lockfiles and build output are generated; hand edits are almost
+/// always a mistake and get overwritten by the next build anyway.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtectedPaths {
+    /// Globs matched against the path and each of its trailing parts, so
+    /// `Cargo.lock` and `target/**` apply at any depth
+    #[serde(default = "default_protected_paths")]
+    pub deny: Vec<String>,
+
+    /// Paths editable even if they match `deny`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+}
+
+fn default_protected_paths() -> Vec<String> {
+    [
+        "Cargo.lock",
+        "package-lock.json",
+        "yarn.lock",
+        "pnpm-lock.yaml",
+        "poetry.lock",
+        "Pipfile.lock",
+        "Gemfile.lock",
+        "composer.lock",
+        "go.sum",
+        "target/**",
+        "dist/**",
+        "node_modules/**",
+        "__pycache__/**",
+        "*.min.js",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+impl Default for ProtectedPaths {
+    fn default() -> Self {
+        Self {
+            deny: default_protected_paths(),
+            allow: vec![],
+        }
+    }
+}
+
+impl ProtectedPaths {
+    /// The deny pattern protecting `path`, if any
+    pub fn matching(&self, path: &str) -> Option<&str> {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        self.matching_in(&cwd, path)
+    }
+
+    /// `matching`, with absolute paths taken relative to `root`
+    pub fn matching_in(&self, root: &Path, path: &str) -> Option<&str> {
+        let path = Path::new(path);
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let parts: Vec<String> = relative
+            .components()
+            .filter_map(|c| match c {
+                std::path::Component::Normal(s) => Some(s.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        let matches = |pattern: &String| {
+            let glob = glob::Pattern::new(pattern).ok();
+            (0..parts.len()).any(|i| {
+                let tail = parts[i..].join("/");
+                match &glob {
+                    Some(g) => g.matches(&tail),
+                    None => *pattern == tail,
+                }
+            })
+        };
+        if self.allow.iter().any(matches) {
+            return None;
+        }
+        self.deny.iter().find(|p| matches(p)).map(|p| p.as_str())
+    }
+}
+
 /// Check if a tool operation is permitted
 pub fn check_tool_permission(
     config: &Config,
@@ -214,20 +374,25 @@ pub fn check_tool_permission(
 
     // Check explicit path/command rules first
     match tool_name {
-        "read" | "write" | "patch" | "glob" | "grep" => {
-            if let Some(path) = args.get("path").and_then(|v| v.as_str()) {
-                if let Some(false) = perms.is_path_allowed(path) {
-                    return PermissionCheck::Denied {
-                        reason: format!("Path '{}' is in denied list", path),
-                    };
-                }
-                if let Some(true) = perms.is_path_allowed(path) {
-                    return PermissionCheck::Allowed;
-                }
+        "read" | "write" | "patch" | "glob" | "grep" | "tree" | "stats" | "move" | "delete" => {
+            let paths: Vec<&str> = ["path", "from", "to"]
+                .iter()
+                .filter_map(|key| args.get(*key).and_then(|v| v.as_str()))
+                .collect();
+            if let Some(path) = paths
+                .iter()
+                .find(|p| perms.is_path_allowed(p) == Some(false))
+            {
+                return PermissionCheck::Denied {
+                    reason: format!("Path '{}' is in denied list", path),
+                };
+            }
+            if !paths.is_empty() && paths.iter().all(|p| perms.is_path_allowed(p) == Some(true)) {
+                return PermissionCheck::Allowed;
             }
         }
         "bash" | "shell" | "exec" => {
-            if let Some(cmd) = args.get("command").and_then(|v| v.as_str()) {
+            if let Some(cmd) = command_line(args).as_deref() {
                 if let Some(false) = perms.is_command_allowed(cmd) {
                     return PermissionCheck::Denied {
                         reason: format!("Command '{}' is in denied list", cmd),
@@ -250,8 +415,8 @@ pub fn check_tool_permission(
         PermissionMode::Ask => {
             let desc = match tool_name {
                 "bash" | "shell" => {
-                    let cmd = args.get("command").and_then(|v| v.as_str()).unwrap_or("?");
-                    format!("Run: {}", truncate(cmd, 60))
+                    let cmd = command_line(args).unwrap_or_else(|| "?".into());
+                    format!("Run: {}", truncate(&cmd, 60))
                 }
                 "write" => {
                     let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("?");
@@ -261,6 +426,14 @@ pub fn check_tool_permission(
                     let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("?");
                     format!("Patch: {}", path)
                 }
+                "move" => {
+                    let path = |key| args.get(key).and_then(|v| v.as_str()).unwrap_or("?");
+                    format!("Move: {} -> {}", path("from"), path("to"))
+                }
+                "delete" => {
+                    let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("?");
+                    format!("Delete: {}", path)
+                }
                 "git" | "commit" => {
                     let msg = args.get("message").and_then(|v| v.as_str()).unwrap_or("?");
                     format!("Git commit: {}", truncate(msg, 40))
@@ -275,6 +448,16 @@ pub fn check_tool_permission(
     }
 }
 
+/// Command line of a shell tool call; argv-style calls (which take
+/// precedence over `command`) are joined with spaces
+fn command_line(args: &serde_json::Value) -> Option<String> {
+    if let Some(argv) = args.get("argv").and_then(|v| v.as_array()) {
+        let words: Vec<&str> = argv.iter().filter_map(|v| v.as_str()).collect();
+        return Some(words.join(" "));
+    }
+    args.get("command")?.as_str().map(String::from)
+}
+
 fn truncate(s: &str, max: usize) -> String {
     if s.len() <= max {
         s.to_string()
@@ -345,6 +528,32 @@ pub fn state_dir() -> Result<PathBuf> {
     Ok(base.join(APP_NAME))
 }
 
+/// Sessions root: `env` (HYLE_SESSIONS_DIR), then `configured`
+/// (sessions_dir), then the state directory; `~/` expands to home
+pub fn sessions_root(env: Option<&str>, configured: Option<&str>) -> Result<PathBuf> {
+    let chosen = env.or(configured).map(str::trim).filter(|p| !p.is_empty());
+    match chosen {
+        Some(path) => match path.strip_prefix("~/") {
+            Some(rest) => Ok(dirs::home_dir()
+                .context("Could not determine home directory")?
+                .join(rest)),
+            None => Ok(PathBuf::from(path)),
+        },
+        None => Ok(state_dir()?.join("sessions")),
+    }
+}
+
+/// Idle limit for hosted sessions: `env` (HYLE_IDLE_TIMEOUT) seconds, then
+/// `configured` (idle_timeout_secs); None never times out, and an env value
+/// of 0 turns a configured limit off
+pub fn idle_timeout(env: Option<&str>, configured: Option<u64>) -> Option<std::time::Duration> {
+    let secs = match env.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => v.parse::<u64>().ok().or(configured)?,
+        None => configured?,
+    };
+    (secs > 0).then_some(std::time::Duration::from_secs(secs))
+}
+
 /// Get config file path
 pub fn config_path() -> Result<PathBuf> {
     Ok(config_dir()?.join("config.json"))
@@ -358,6 +567,370 @@ pub fn ensure_dirs() -> Result<()> {
     Ok(())
 }
 
+// ═══════════════════════════════════════════════════════════════
+// SAMPLING
+// ═══════════════════════════════════════════════════════════════
+
+/// Max stop sequences OpenRouter accepts
+const MAX_STOP_SEQUENCES: usize = 4;
+
+/// Sampling controls sent with each request; unset fields use client defaults
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SamplingParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    /// Best-effort determinism on providers that support it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl SamplingParams {
+    /// Overlay `overrides` on top of these params (override wins when set)
+    pub fn merged(&self, overrides: &SamplingParams) -> SamplingParams {
+        SamplingParams {
+            temperature: overrides.temperature.or(self.temperature),
+            top_p: overrides.top_p.or(self.top_p),
+            max_tokens: overrides.max_tokens.or(self.max_tokens),
+            stop: if overrides.stop.is_empty() {
+                self.stop.clone()
+            } else {
+                overrides.stop.clone()
+            },
+            seed: overrides.seed.or(self.seed),
+        }
+    }
+
+    /// Set one parameter from user input; "reset" clears it
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        let reset = value == "reset" || value == "default";
+        match key {
+            "temperature" | "temp" => {
+                self.temperature = if reset {
+                    None
+                } else {
+                    let t: f32 = value
+                        .parse()
+                        .with_context(|| format!("Invalid temperature: {}", value))?;
+                    if !(0.0..=2.0).contains(&t) {
+                        anyhow::bail!("Temperature must be between 0 and 2");
+                    }
+                    Some(t)
+                };
+            }
+            "top_p" => {
+                self.top_p = if reset {
+                    None
+                } else {
+                    let p: f32 = value
+                        .parse()
+                        .with_context(|| format!("Invalid top_p: {}", value))?;
+                    if p.is_nan() || p <= 0.0 || p > 1.0 {
+                        anyhow::bail!("top_p must be in (0, 1]");
+                    }
+                    Some(p)
+                };
+            }
+            "max_tokens" => {
+                self.max_tokens = if reset {
+                    None
+                } else {
+                    let n: u32 = value
+                        .parse()
+                        .with_context(|| format!("Invalid max_tokens: {}", value))?;
+                    if n == 0 {
+                        anyhow::bail!("max_tokens must be positive");
+                    }
+                    Some(n)
+                };
+            }
+            "stop" => {
+                if reset || value == "clear" {
+                    self.stop.clear();
+                } else {
+                    if self.stop.len() >= MAX_STOP_SEQUENCES {
+                        anyhow::bail!("At most {} stop sequences", MAX_STOP_SEQUENCES);
+                    }
+                    // Allow escaped newlines, the most common stop sequence
+                    let seq = value.replace("\\n", "\n");
+                    if !self.stop.contains(&seq) {
+                        self.stop.push(seq);
+                    }
+                }
+            }
+            "seed" => {
+                self.seed = if reset {
+                    None
+                } else {
+                    Some(
+                        value
+                            .parse()
+                            .with_context(|| format!("Invalid seed: {}", value))?,
+                    )
+                };
+            }
+            _ => anyhow::bail!("Unknown sampling parameter: {}", key),
+        }
+        Ok(())
+    }
+
+    /// One-line summary for display
+    pub fn display(&self) -> String {
+        let fmt_opt = |v: Option<String>| v.unwrap_or_else(|| "default".into());
+        let mut line = format!(
+            "temperature={} top_p={} max_tokens={} stop={:?}",
+            fmt_opt(self.temperature.map(|t| t.to_string())),
+            fmt_opt(self.top_p.map(|p| p.to_string())),
+            fmt_opt(self.max_tokens.map(|n| n.to_string())),
+            self.stop
+        );
+        if let Some(seed) = self.seed {
+            line.push_str(&format!(" seed={}", seed));
+        }
+        line
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// CONTEXT ASSEMBLY
+// ═══════════════════════════════════════════════════════════════
+
+/// Default turns kept by `/context window`
+pub const DEFAULT_WINDOW_TURNS: usize = 6;
+/// Default token budget for `/context salience`
+pub const DEFAULT_SALIENCE_BUDGET: usize = 4000;
+/// Messages `Summarized` keeps verbatim at the end
+const SUMMARIZED_KEEP_RECENT: usize = 4;
+/// Longest line kept per message in a `Summarized` digest
+const DIGEST_LINE_CHARS: usize = 100;
+
+/// How conversation history is turned into the messages of a request,
+/// trading cost against fidelity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum ContextStrategy {
+    /// Send every message
+    #[default]
+    FullHistory,
+    /// Send only the last `turns` user turns (plus the system prompt)
+    SlidingWindow { turns: usize },
+    /// Fold earlier turns into one salience-ranked context of ~`budget` tokens
+    Salience { budget: usize },
+    /// Replace earlier turns with a one-line-per-message digest
+    Summarized,
+}
+
+impl ContextStrategy {
+    /// Parse `/context` arguments: full | window [turns] | salience [budget] | summarized
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut parts = spec.split_whitespace();
+        let name = parts.next().unwrap_or("");
+        let mut number = |default: usize| -> Result<usize> {
+            match parts.next() {
+                Some(n) => {
+                    let n: usize = n
+                        .parse()
+                        .with_context(|| format!("Invalid number: {}", n))?;
+                    if n == 0 {
+                        anyhow::bail!("{} needs a positive number", name);
+                    }
+                    Ok(n)
+                }
+                None => Ok(default),
+            }
+        };
+        match name {
+            "full" => Ok(Self::FullHistory),
+            "window" | "sliding" => Ok(Self::SlidingWindow {
+                turns: number(DEFAULT_WINDOW_TURNS)?,
+            }),
+            "salience" | "salient" => Ok(Self::Salience {
+                budget: number(DEFAULT_SALIENCE_BUDGET)?,
+            }),
+            "summarized" | "summary" => Ok(Self::Summarized),
+            other => anyhow::bail!(
+                "Unknown context strategy: {} (full, window [turns], salience [budget], summarized)",
+                other
+            ),
+        }
+    }
+
+    /// Short label for the status bar
+    pub fn label(&self) -> String {
+        match self {
+            Self::FullHistory => "full".into(),
+            Self::SlidingWindow { turns } => format!("window:{}", turns),
+            Self::Salience { budget } => format!("salience:{}", budget),
+            Self::Summarized => "summarized".into(),
+        }
+    }
+
+    /// Shape `history` (role/content messages, oldest first) for a request.
+    /// `salient` renders messages into a context of about `budget` tokens and
+    /// is only called for `Salience`.
+    pub fn assemble(
+        &self,
+        history: &[serde_json::Value],
+        salient: impl FnOnce(&[serde_json::Value], usize) -> String,
+    ) -> Vec<serde_json::Value> {
+        let role = |m: &serde_json::Value| m.get("role").and_then(|r| r.as_str()).map(String::from);
+        // The leading system prompt is always kept as-is
+        let start = match history.first() {
+            Some(m) if role(m).as_deref() == Some("system") => 1,
+            _ => 0,
+        };
+        let (prompt, rest) = history.split_at(start);
+        let user_turns: Vec<usize> = rest
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| role(m).as_deref() == Some("user"))
+            .map(|(i, _)| i)
+            .collect();
+
+        let (older, recent) = match self {
+            Self::FullHistory => return history.to_vec(),
+            Self::SlidingWindow { turns } => {
+                let cut = match user_turns.len().checked_sub(*turns) {
+                    Some(skip) if skip > 0 => user_turns[skip],
+                    _ => 0,
+                };
+                let mut out = prompt.to_vec();
+                out.extend_from_slice(&rest[cut..]);
+                return out;
+            }
+            // Keep the latest turn verbatim, fold everything before it
+            Self::Salience { .. } => rest.split_at(user_turns.last().copied().unwrap_or(0)),
+            Self::Summarized => rest.split_at(rest.len().saturating_sub(SUMMARIZED_KEEP_RECENT)),
+        };
+        if older.is_empty() {
+            return history.to_vec();
+        }
+
+        let folded = match self {
+            Self::Salience { budget } => salient(older, *budget),
+            _ => digest(older),
+        };
+        let mut out = prompt.to_vec();
+        out.push(serde_json::json!({"role": "system", "content": folded}));
+        out.extend_from_slice(recent);
+        out
+    }
+}
+
+/// One line per message: "- role: first line of content"
+fn digest(messages: &[serde_json::Value]) -> String {
+    let mut out = format!(
+        "[Earlier conversation, {} messages condensed]",
+        messages.len()
+    );
+    for m in messages {
+        let role = m.get("role").and_then(|r| r.as_str()).unwrap_or("unknown");
+        let content = m.get("content").and_then(|c| c.as_str()).unwrap_or("");
+        let line = content.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+        let mut short: String = line.trim().chars().take(DIGEST_LINE_CHARS).collect();
+        if line.trim().chars().count() > DIGEST_LINE_CHARS {
+            short.push_str("...");
+        }
+        out.push_str(&format!("\n- {}: {}", role, short));
+    }
+    out
+}
+
+/// What happens to tool output and ingested text that reads like
+/// instructions to the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InjectionPolicy {
+    /// Pass content through untouched
+    Off,
+    /// Flag suspicious phrases next to the content
+    #[default]
+    Warn,
+    /// Fence all content as data and redact suspicious phrases
+    Wrap,
+}
+
+impl InjectionPolicy {
+    pub fn parse(value: &str) -> Result<Self> {
+        serde_json::from_value(serde_json::Value::String(value.trim().to_lowercase())).map_err(
+            |_| anyhow::anyhow!("injection_guard must be off, warn or wrap, got: {}", value),
+        )
+    }
+}
+
+/// Lowest severity written to the log file, most severe first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    pub fn parse(value: &str) -> Result<Self> {
+        serde_json::from_value(serde_json::Value::String(value.trim().to_lowercase())).map_err(
+            |_| {
+                anyhow::anyhow!(
+                    "log_level must be error, warn, info or debug, got: {}",
+                    value
+                )
+            },
+        )
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// PROVIDER ROUTING
+// ═══════════════════════════════════════════════════════════════
+
+/// Whether providers may store or train on prompts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataCollection {
+    Allow,
+    Deny,
+}
+
+/// OpenRouter provider routing, sent as the request's `provider` field;
+/// unset fields leave OpenRouter's defaults
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProviderPrefs {
+    /// Providers to try first, in order (e.g. `["anthropic", "together"]`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+
+    /// Whether other providers may serve the request when those in `order`
+    /// fail (OpenRouter default: true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_fallbacks: Option<bool>,
+
+    /// `deny` skips providers that store or train on prompts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_collection: Option<DataCollection>,
+
+    /// Providers never to use
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+}
+
+impl ProviderPrefs {
+    /// Nothing set: requests go out without a `provider` field
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Headers hyle sets itself; `request_headers` can't replace these
+pub const RESERVED_HEADERS: &[&str] = &["authorization", "content-type"];
+
 /// Main configuration
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
@@ -388,8 +961,108 @@ pub struct Config {
     /// Trust mode: skip all permission checks (for automation)
     #[serde(default)]
     pub trust_mode: bool,
+
+    /// Model picked by `hyle --auto`, reused until it disappears
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_model: Option<String>,
+
+    /// Models to upgrade to on repeated low quality, best first
+    #[serde(default)]
+    pub upgrade_models: Vec<String>,
+
+    /// Perform quality upgrades automatically instead of only suggesting them
+    #[serde(default)]
+    pub auto_upgrade: bool,
+
+    /// Default sampling parameters for requests
+    #[serde(default)]
+    pub sampling: SamplingParams,
+
+    /// How history is assembled into each request
+    #[serde(default)]
+    pub context_strategy: ContextStrategy,
+
+    /// Environment scrubbing/injection for shell tools
+    #[serde(default)]
+    pub env: EnvPolicy,
+
+    /// Lockfiles and build output the write/patch tools won't touch
+    #[serde(default)]
+    pub protected_paths: ProtectedPaths,
+
+    /// Mirror live shell tool output into a tmux pane (no-op outside tmux)
+    #[serde(default)]
+    pub tmux_mirror: bool,
+
+    /// Replay identical requests from an on-disk cache (`--no-cache` skips it)
+    #[serde(default)]
+    pub response_cache: bool,
+
+    /// How long cached responses stay valid in seconds (default: 1 day)
+    #[serde(default)]
+    pub response_cache_ttl_secs: Option<u64>,
+
+    /// OTLP/HTTP collector for request and tool call spans, e.g.
+    /// "http://localhost:4318" (`OTEL_EXPORTER_OTLP_ENDPOINT` overrides)
+    #[serde(default)]
+    pub otel_endpoint: Option<String>,
+
+    /// Answer given to the agent's ask_user questions in --task and server
+    /// runs; unset stops the run with a needs-input status
+    #[serde(default)]
+    pub ask_user_default: Option<String>,
+
+    /// Require an approved plan before any write, shell or git tool call
+    #[serde(default)]
+    pub plan_first: bool,
+
+    /// Cap on any shell command's timeout in ms; per-call values are
+    /// clamped (defaults to DEFAULT_MAX_COMMAND_TIMEOUT_MS)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_command_timeout_ms: Option<u64>,
+
+    /// Where sessions are stored; `HYLE_SESSIONS_DIR` overrides (default:
+    /// ~/.local/state/hyle/sessions)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sessions_dir: Option<String>,
+
+    /// Save and close a TUI or `--serve` process after this many seconds
+    /// without activity; `HYLE_IDLE_TIMEOUT` overrides (default: never)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_secs: Option<u64>,
+
+    /// Offer to /compact once the next request would fill this percent of
+    /// the context window (default: off)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_compact_percent: Option<u32>,
+
+    /// Prompt-injection guard for tool output, ingested pages and docs
+    #[serde(default)]
+    pub injection_guard: InjectionPolicy,
+
+    /// Add build/test/format commands for the detected project type to the
+    /// system prompt (default: on)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_hints: Option<bool>,
+
+    /// Write JSON-lines logs at this level and above to
+    /// ~/.local/state/hyle/logs; `HYLE_LOG` overrides (default: off)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<LogLevel>,
+
+    /// OpenRouter provider routing for every chat request (default: none)
+    #[serde(default, skip_serializing_if = "ProviderPrefs::is_empty")]
+    pub provider_prefs: ProviderPrefs,
+
+    /// Extra headers sent with every chat request; they may replace
+    /// `HTTP-Referer` and `X-Title` but not the reserved ones
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub request_headers: BTreeMap<String, String>,
 }
 
+/// Default cap on shell command timeouts (10 minutes)
+pub const DEFAULT_MAX_COMMAND_TIMEOUT_MS: u64 = 600_000;
+
 fn default_sample_rate() -> u32 {
     4
 }
@@ -441,6 +1114,11 @@ impl Config {
 
         Ok(())
     }
+
+    /// Whether project hints go into the system prompt
+    pub fn project_hints(&self) -> bool {
+        self.project_hints.unwrap_or(true)
+    }
 }
 
 /// Get API key from config or environment
@@ -459,6 +1137,394 @@ pub fn get_api_key() -> Result<String> {
     )
 }
 
+// ═══════════════════════════════════════════════════════════════
+// SETTING AND VALIDATION
+// ═══════════════════════════════════════════════════════════════
+
+/// Keys config.json understands
+const KNOWN_KEYS: &[&str] = &[
+    "api_key",
+    "default_model",
+    "free_only",
+    "telemetry_hz",
+    "auto_throttle",
+    "permissions",
+    "trust_mode",
+    "auto_model",
+    "upgrade_models",
+    "auto_upgrade",
+    "sampling",
+    "context_strategy",
+    "env",
+    "protected_paths",
+    "tmux_mirror",
+    "response_cache",
+    "response_cache_ttl_secs",
+    "otel_endpoint",
+    "ask_user_default",
+    "plan_first",
+    "max_command_timeout_ms",
+    "sessions_dir",
+    "injection_guard",
+    "idle_timeout_secs",
+    "auto_compact_percent",
+    "log_level",
+    "project_hints",
+    "provider_prefs",
+    "request_headers",
+];
+
+/// Keys `hyle config set` accepts, for its usage message
+pub const SETTABLE_KEYS: &str = "key, model, free_only, telemetry_hz, auto_throttle, \
+    permissions[.read|.write|.execute|.git], trust_mode, upgrade_models, auto_upgrade, \
+    sampling.<temperature|top_p|max_tokens|stop|seed>, context_strategy, tmux_mirror, \
+    response_cache, response_cache_ttl_secs, otel_endpoint, ask_user_default, \
+    plan_first, max_command_timeout_ms, sessions_dir, injection_guard, idle_timeout_secs, \
+    auto_compact_percent, log_level, project_hints";
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "on" | "yes" | "1" => Ok(true),
+        "false" | "off" | "no" | "0" => Ok(false),
+        _ => anyhow::bail!("{} must be true or false, got: {}", key, value),
+    }
+}
+
+fn parse_positive(key: &str, value: &str) -> Result<u64> {
+    let n: u64 = value
+        .trim()
+        .parse()
+        .with_context(|| format!("{} must be a number, got: {}", key, value))?;
+    if n == 0 {
+        anyhow::bail!("{} must be positive", key);
+    }
+    Ok(n)
+}
+
+fn parse_mode(key: &str, value: &str) -> Result<PermissionMode> {
+    serde_json::from_value(serde_json::Value::String(value.trim().to_lowercase()))
+        .map_err(|_| anyhow::anyhow!("{} must be auto, ask or deny, got: {}", key, value))
+}
+
+fn check_endpoint(endpoint: &str) -> Result<()> {
+    if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+        anyhow::bail!("otel_endpoint must be an http(s) URL, got: {}", endpoint);
+    }
+    Ok(())
+}
+
+impl Config {
+    /// Set one key from `hyle config set`; "reset" clears optional values
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let reset = value == "reset" || value == "default";
+        match key {
+            "key" | "api_key" | "openrouter.key" => {
+                if let Some(Finding::Error(e)) = check_api_key(Some(value)) {
+                    anyhow::bail!(e);
+                }
+                self.api_key = Some(value.trim().to_string());
+            }
+            "model" | "default_model" => {
+                self.default_model = if reset { None } else { Some(value.to_string()) };
+            }
+            "free_only" => self.free_only = parse_bool(key, value)?,
+            "auto_throttle" => self.auto_throttle = parse_bool(key, value)?,
+            "trust_mode" => self.trust_mode = parse_bool(key, value)?,
+            "plan_first" => self.plan_first = parse_bool(key, value)?,
+            "auto_upgrade" => self.auto_upgrade = parse_bool(key, value)?,
+            "tmux_mirror" => self.tmux_mirror = parse_bool(key, value)?,
+            "response_cache" => self.response_cache = parse_bool(key, value)?,
+            "project_hints" => {
+                self.project_hints = if reset {
+                    None
+                } else {
+                    Some(parse_bool(key, value)?)
+                };
+            }
+            "telemetry_hz" => {
+                self.telemetry_hz = u32::try_from(parse_positive(key, value)?)
+                    .context("telemetry_hz is too large")?;
+            }
+            "upgrade_models" => {
+                self.upgrade_models = value
+                    .split(',')
+                    .map(|m| m.trim().to_string())
+                    .filter(|m| !m.is_empty())
+                    .collect();
+            }
+            "context_strategy" => self.context_strategy = ContextStrategy::parse(value)?,
+            "injection_guard" => self.injection_guard = InjectionPolicy::parse(value)?,
+            "log_level" => {
+                self.log_level = if reset || value.trim().eq_ignore_ascii_case("off") {
+                    None
+                } else {
+                    Some(LogLevel::parse(value)?)
+                };
+            }
+            "response_cache_ttl_secs" => {
+                self.response_cache_ttl_secs = if reset {
+                    None
+                } else {
+                    Some(parse_positive(key, value)?)
+                };
+            }
+            "idle_timeout_secs" => {
+                self.idle_timeout_secs = if reset {
+                    None
+                } else {
+                    Some(parse_positive(key, value)?)
+                };
+            }
+            "auto_compact_percent" => {
+                self.auto_compact_percent = if reset || value.trim().eq_ignore_ascii_case("off") {
+                    None
+                } else {
+                    match parse_positive(key, value)? {
+                        pct @ 1..=100 => Some(pct as u32),
+                        _ => anyhow::bail!("{} must be 1-100, got: {}", key, value),
+                    }
+                };
+            }
+            "max_command_timeout_ms" => {
+                self.max_command_timeout_ms = if reset {
+                    None
+                } else {
+                    Some(parse_positive(key, value)?)
+                };
+            }
+            "otel_endpoint" => {
+                self.otel_endpoint = if reset {
+                    None
+                } else {
+                    check_endpoint(value)?;
+                    Some(value.to_string())
+                };
+            }
+            "ask_user_default" => {
+                self.ask_user_default = if reset { None } else { Some(value.to_string()) };
+            }
+            "sessions_dir" => {
+                self.sessions_dir = if reset { None } else { Some(value.to_string()) };
+            }
+            "permissions" => {
+                self.permissions = match value {
+                    "permissive" => Permissions::permissive(),
+                    "restrictive" => Permissions::restrictive(),
+                    "default" | "reset" => Permissions::default(),
+                    _ => anyhow::bail!(
+                        "permissions must be permissive, restrictive or default, got: {}",
+                        value
+                    ),
+                };
+            }
+            "permissions.read" => self.permissions.read = parse_mode(key, value)?,
+            "permissions.write" => self.permissions.write = parse_mode(key, value)?,
+            "permissions.execute" => self.permissions.execute = parse_mode(key, value)?,
+            "permissions.git" => self.permissions.git = parse_mode(key, value)?,
+            _ => match key.strip_prefix("sampling.") {
+                Some(param) => self.sampling.set(param, value)?,
+                None => anyhow::bail!("Unknown config key: {}. Valid keys: {}", key, SETTABLE_KEYS),
+            },
+        }
+        Ok(())
+    }
+}
+
+/// A problem found by `hyle config validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// The config won't load or a value can't work
+    Error(String),
+    /// Works, but probably not what you meant
+    Warning(String),
+}
+
+impl Finding {
+    pub fn is_error(&self) -> bool {
+        matches!(self, Finding::Error(_))
+    }
+}
+
+/// Check the shape of an OpenRouter API key (`sk-or-...`)
+pub fn check_api_key(key: Option<&str>) -> Option<Finding> {
+    let key = match key.map(str::trim) {
+        Some(key) if !key.is_empty() => key,
+        _ => {
+            return Some(Finding::Error(
+                "No API key: set OPENROUTER_API_KEY or run `hyle config set key <key>`".into(),
+            ))
+        }
+    };
+    if key.chars().any(char::is_whitespace) {
+        return Some(Finding::Error("API key contains whitespace".into()));
+    }
+    if !key.starts_with("sk-or-") {
+        return Some(Finding::Warning(
+            "API key doesn't look like an OpenRouter key (expected sk-or-...)".into(),
+        ));
+    }
+    None
+}
+
+/// Check config.json contents without loading or writing anything:
+/// JSON syntax, unknown keys, value types and ranges, and the API key
+/// (`env_key` is OPENROUTER_API_KEY, which wins over the file)
+pub fn validate_config(content: &str, env_key: Option<&str>) -> Vec<Finding> {
+    let value: serde_json::Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(e) => return vec![Finding::Error(format!("Not valid JSON: {}", e))],
+    };
+    let obj = match value.as_object() {
+        Some(obj) => obj,
+        None => return vec![Finding::Error("Config must be a JSON object".into())],
+    };
+
+    let mut findings = Vec::new();
+    for (key, value) in obj {
+        if !KNOWN_KEYS.contains(&key.as_str()) {
+            findings.push(Finding::Warning(format!(
+                "Unknown key `{}` is ignored",
+                key
+            )));
+            continue;
+        }
+        // One key at a time so the error names the key at fault
+        let single = serde_json::json!({ key.as_str(): value });
+        if let Err(e) = serde_json::from_value::<Config>(single) {
+            findings.push(Finding::Error(format!("`{}`: {}", key, e)));
+        }
+    }
+    if findings.iter().any(Finding::is_error) {
+        return findings;
+    }
+
+    let cfg: Config = match serde_json::from_value(value) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            findings.push(Finding::Error(e.to_string()));
+            return findings;
+        }
+    };
+    if cfg.telemetry_hz == 0 {
+        findings.push(Finding::Error("`telemetry_hz` must be at least 1".into()));
+    }
+    if cfg.max_command_timeout_ms == Some(0) {
+        findings.push(Finding::Error(
+            "`max_command_timeout_ms` of 0 makes every shell command time out".into(),
+        ));
+    }
+    if cfg.response_cache_ttl_secs == Some(0) {
+        findings.push(Finding::Warning(
+            "`response_cache_ttl_secs` of 0 expires cached responses immediately".into(),
+        ));
+    }
+    if let Some(Err(e)) = cfg.otel_endpoint.as_deref().map(check_endpoint) {
+        findings.push(Finding::Error(format!("`otel_endpoint`: {}", e)));
+    }
+    // Re-apply sampling values through the same checks as /sampling
+    let mut sampling = SamplingParams::default();
+    let sampling_values = [
+        (
+            "temperature",
+            cfg.sampling.temperature.map(|t| t.to_string()),
+        ),
+        ("top_p", cfg.sampling.top_p.map(|p| p.to_string())),
+        ("max_tokens", cfg.sampling.max_tokens.map(|n| n.to_string())),
+    ];
+    for (param, value) in sampling_values {
+        if let Some(value) = value {
+            if let Err(e) = sampling.set(param, &value) {
+                findings.push(Finding::Error(format!("`sampling.{}`: {}", param, e)));
+            }
+        }
+    }
+    if cfg.sampling.stop.len() > MAX_STOP_SEQUENCES {
+        findings.push(Finding::Error(format!(
+            "`sampling.stop`: at most {} stop sequences",
+            MAX_STOP_SEQUENCES
+        )));
+    }
+    for name in cfg.request_headers.keys() {
+        if RESERVED_HEADERS.contains(&name.to_lowercase().as_str()) {
+            findings.push(Finding::Warning(format!(
+                "`request_headers`: {} is set by hyle and is ignored",
+                name
+            )));
+        } else if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            findings.push(Finding::Error(format!(
+                "`request_headers`: not a header name: {:?}",
+                name
+            )));
+        }
+    }
+    if cfg.trust_mode {
+        findings.push(Finding::Warning(
+            "`trust_mode` is on: every tool call is auto-approved".into(),
+        ));
+    }
+
+    let key = env_key.filter(|k| !k.is_empty()).or(cfg.api_key.as_deref());
+    findings.extend(check_api_key(key));
+    findings
+}
+
+// ═══════════════════════════════════════════════════════════════
+// PROJECT CONFIG
+// ═══════════════════════════════════════════════════════════════
+
+/// Per-project settings, relative to the project root
+pub const PROJECT_CONFIG_FILE: &str = ".hyle/config.json";
+
+/// Shell commands that replace the built-in ones for /build, /test and
+/// /check, for toolchains hyle doesn't guess (nextest, just, make targets)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProjectCommands {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test: Option<String>,
+    #[serde(default, alias = "lint", skip_serializing_if = "Option::is_none")]
+    pub check: Option<String>,
+}
+
+impl ProjectCommands {
+    /// Configured command by name ("build", "test", "check" or "lint")
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let cmd = match name {
+            "build" => &self.build,
+            "test" => &self.test,
+            "check" | "lint" => &self.check,
+            _ => return None,
+        };
+        cmd.as_deref().map(str::trim).filter(|c| !c.is_empty())
+    }
+}
+
+/// `.hyle/config.json` in a project
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub commands: ProjectCommands,
+}
+
+impl ProjectConfig {
+    /// Load from `root`; defaults when the file doesn't exist
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(PROJECT_CONFIG_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// `commands.<name>` if configured, else `default`
+    pub fn command(&self, name: &str, default: &str) -> String {
+        self.commands.get(name).unwrap_or(default).to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -488,6 +1554,264 @@ mod tests {
         assert!(json.contains("test/model"));
     }
 
+    #[test]
+    fn test_validate_valid_config() {
+        let content = r#"{
+            "api_key": "sk-or-v1-abc123",
+            "default_model": "openai/gpt-4o",
+            "telemetry_hz": 2,
+            "permissions": { "write": "ask", "execute": "deny" },
+            "sampling": { "temperature": 0.7 },
+            "context_strategy": { "strategy": "full_history" },
+            "otel_endpoint": "http://localhost:4318"
+        }"#;
+        assert_eq!(validate_config(content, None), vec![]);
+        assert_eq!(validate_config("{}", Some("sk-or-v1-env")), vec![]);
+    }
+
+    #[test]
+    fn test_validate_invalid_config() {
+        assert!(validate_config("{ nope", None)[0].is_error());
+        assert!(validate_config("[]", None)[0].is_error());
+
+        // Wrong types name the key
+        let findings = validate_config(
+            r#"{"telemetry_hz": "fast", "permissions": {"write": "maybe"}}"#,
+            Some("sk-or-v1-x"),
+        );
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().all(Finding::is_error));
+        assert!(findings
+            .iter()
+            .any(|f| format!("{:?}", f).contains("telemetry_hz")));
+
+        // Bad values, unknown keys and a missing key
+        let findings = validate_config(
+            r#"{"telemetry_hz": 0, "sampling": {"temperature": 3.5}, "modle": "x",
+                "otel_endpoint": "localhost:4318", "trust_mode": true}"#,
+            None,
+        );
+        let errors: Vec<_> = findings.iter().filter(|f| f.is_error()).collect();
+        assert_eq!(errors.len(), 4, "{:?}", findings);
+        assert!(findings.contains(&Finding::Warning("Unknown key `modle` is ignored".into())));
+        assert_eq!(findings.len(), 6);
+    }
+
+    #[test]
+    fn test_provider_prefs_and_headers() {
+        // Unset: nothing written, nothing sent
+        let json = serde_json::to_string(&Config::default()).unwrap();
+        assert!(!json.contains("provider_prefs"));
+        assert!(!json.contains("request_headers"));
+        assert!(Config::default().provider_prefs.is_empty());
+
+        let cfg: Config = serde_json::from_str(
+            r#"{"provider_prefs": {"order": ["anthropic"], "data_collection": "deny"}}"#,
+        )
+        .unwrap();
+        assert_eq!(cfg.provider_prefs.order, vec!["anthropic"]);
+        assert_eq!(
+            cfg.provider_prefs.data_collection,
+            Some(DataCollection::Deny)
+        );
+        assert_eq!(cfg.provider_prefs.allow_fallbacks, None);
+        assert!(!cfg.provider_prefs.is_empty());
+
+        let findings = validate_config(
+            r#"{"provider_prefs": {"data_collection": "never"}}"#,
+            Some("sk-or-v1-x"),
+        );
+        assert!(findings[0].is_error());
+
+        let findings = validate_config(
+            r#"{"request_headers": {"X-Title": "ci", "Authorization": "x", "bad header": "y"}}"#,
+            Some("sk-or-v1-x"),
+        );
+        assert_eq!(findings.len(), 2, "{:?}", findings);
+        assert!(findings.iter().any(Finding::is_error));
+        assert!(findings
+            .iter()
+            .any(|f| format!("{:?}", f).contains("Authorization")));
+    }
+
+    #[test]
+    fn test_check_api_key() {
+        assert_eq!(check_api_key(Some("sk-or-v1-abc")), None);
+        assert!(check_api_key(None).unwrap().is_error());
+        assert!(check_api_key(Some("  ")).unwrap().is_error());
+        assert!(check_api_key(Some("sk-or v1")).unwrap().is_error());
+        assert!(!check_api_key(Some("sk-ant-123")).unwrap().is_error());
+    }
+
+    #[test]
+    fn test_idle_timeout() {
+        use std::time::Duration;
+        assert_eq!(idle_timeout(None, None), None);
+        assert_eq!(
+            idle_timeout(None, Some(600)),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(
+            idle_timeout(Some("90"), Some(600)),
+            Some(Duration::from_secs(90))
+        );
+        // 0 in the environment switches a configured limit off
+        assert_eq!(idle_timeout(Some("0"), Some(600)), None);
+        // Garbage falls back to config
+        assert_eq!(
+            idle_timeout(Some("soon"), Some(600)),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(idle_timeout(Some(" "), None), None);
+    }
+
+    #[test]
+    fn test_project_command_overrides() {
+        let root =
+            std::env::temp_dir().join(format!("hyle_test_project_cfg_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join(".hyle")).unwrap();
+
+        // No file: built-in defaults
+        let cfg = ProjectConfig::load(&root).unwrap();
+        assert_eq!(cfg.command("test", "cargo test"), "cargo test");
+
+        fs::write(
+            root.join(PROJECT_CONFIG_FILE),
+            r#"{"commands": {"test": "cargo nextest run", "lint": "just lint", "build": " "}}"#,
+        )
+        .unwrap();
+        let cfg = ProjectConfig::load(&root).unwrap();
+        assert_eq!(cfg.command("test", "cargo test"), "cargo nextest run");
+        assert_eq!(cfg.command("check", "cargo clippy"), "just lint");
+        // Blank counts as unset
+        assert_eq!(cfg.command("build", "cargo build"), "cargo build");
+
+        fs::write(root.join(PROJECT_CONFIG_FILE), "{not json").unwrap();
+        assert!(ProjectConfig::load(&root).is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_sessions_root() {
+        let default = sessions_root(None, None).unwrap();
+        assert!(default.ends_with("hyle/sessions"));
+        assert_eq!(
+            sessions_root(Some("/env/s"), Some("/cfg/s")).unwrap(),
+            PathBuf::from("/env/s")
+        );
+        assert_eq!(
+            sessions_root(None, Some("/cfg/s")).unwrap(),
+            PathBuf::from("/cfg/s")
+        );
+        assert_eq!(sessions_root(Some(" "), None).unwrap(), default);
+        let home = sessions_root(None, Some("~/sync/hyle")).unwrap();
+        assert!(home.is_absolute() && home.ends_with("sync/hyle"));
+    }
+
+    #[test]
+    fn test_config_set() {
+        let mut cfg = Config {
+            telemetry_hz: default_sample_rate(),
+            ..Default::default()
+        };
+        cfg.set("model", "openai/gpt-4o").unwrap();
+        cfg.set("trust_mode", "on").unwrap();
+        cfg.set("plan_first", "true").unwrap();
+        cfg.set("upgrade_models", "a/b, c/d,").unwrap();
+        cfg.set("sampling.temperature", "0.2").unwrap();
+        cfg.set("permissions.execute", "Deny").unwrap();
+        cfg.set("context_strategy", "window 4").unwrap();
+        cfg.set("max_command_timeout_ms", "5000").unwrap();
+        cfg.set("injection_guard", "Wrap").unwrap();
+        assert_eq!(cfg.default_model.as_deref(), Some("openai/gpt-4o"));
+        assert!(cfg.trust_mode);
+        assert!(cfg.plan_first);
+        assert_eq!(cfg.upgrade_models, vec!["a/b", "c/d"]);
+        assert_eq!(cfg.sampling.temperature, Some(0.2));
+        assert_eq!(cfg.permissions.execute, PermissionMode::Deny);
+        assert_eq!(cfg.max_command_timeout_ms, Some(5000));
+        assert_eq!(cfg.injection_guard, InjectionPolicy::Wrap);
+
+        cfg.set("max_command_timeout_ms", "reset").unwrap();
+        assert_eq!(cfg.max_command_timeout_ms, None);
+        cfg.set("sessions_dir", "~/sync/hyle").unwrap();
+        cfg.set("idle_timeout_secs", "900").unwrap();
+        assert_eq!(cfg.idle_timeout_secs, Some(900));
+        cfg.set("auto_compact_percent", "85").unwrap();
+        assert_eq!(cfg.auto_compact_percent, Some(85));
+        assert!(cfg.set("auto_compact_percent", "150").is_err());
+        cfg.set("auto_compact_percent", "off").unwrap();
+        assert_eq!(cfg.auto_compact_percent, None);
+        assert_eq!(cfg.sessions_dir.as_deref(), Some("~/sync/hyle"));
+        cfg.set("log_level", "Info").unwrap();
+        assert_eq!(cfg.log_level, Some(LogLevel::Info));
+        cfg.set("log_level", "off").unwrap();
+        assert_eq!(cfg.log_level, None);
+
+        assert!(cfg.set("trust_mode", "sometimes").is_err());
+        assert!(cfg.set("telemetry_hz", "0").is_err());
+        assert!(cfg.set("otel_endpoint", "localhost").is_err());
+        assert!(cfg.set("permissions.git", "never").is_err());
+        assert!(cfg.set("injection_guard", "strict").is_err());
+        assert!(cfg.set("log_level", "trace").is_err());
+        assert!(cfg.set("key", "has space").is_err());
+        assert!(cfg.set("backend", "x").is_err());
+
+        // What set writes, validate accepts
+        cfg.set("key", "sk-or-v1-abc").unwrap();
+        let content = serde_json::to_string(&cfg).unwrap();
+        let findings = validate_config(&content, None);
+        assert!(!findings.iter().any(Finding::is_error), "{:?}", findings);
+    }
+
+    #[test]
+    fn test_sampling_set_and_validate() {
+        let mut p = SamplingParams::default();
+        p.set("temp", "0.2").unwrap();
+        p.set("top_p", "0.9").unwrap();
+        p.set("max_tokens", "512").unwrap();
+        p.set("stop", "\\n\\n").unwrap();
+        assert_eq!(p.temperature, Some(0.2));
+        assert_eq!(p.top_p, Some(0.9));
+        assert_eq!(p.max_tokens, Some(512));
+        assert_eq!(p.stop, vec!["\n\n".to_string()]);
+
+        assert!(p.set("temp", "3.5").is_err());
+        assert!(p.set("top_p", "0").is_err());
+        assert!(p.set("max_tokens", "-1").is_err());
+        assert!(p.set("bogus", "1").is_err());
+
+        p.set("temp", "reset").unwrap();
+        assert_eq!(p.temperature, None);
+
+        p.set("seed", "42").unwrap();
+        assert_eq!(p.seed, Some(42));
+        assert!(p.display().ends_with(" seed=42"));
+        assert!(p.set("seed", "-1").is_err());
+        p.set("seed", "reset").unwrap();
+        assert_eq!(p.seed, None);
+    }
+
+    #[test]
+    fn test_sampling_merged_prefers_overrides() {
+        let base = SamplingParams {
+            temperature: Some(0.7),
+            max_tokens: Some(1000),
+            stop: vec!["END".into()],
+            ..Default::default()
+        };
+        let overrides = SamplingParams {
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+        let merged = base.merged(&overrides);
+        assert_eq!(merged.temperature, Some(0.0));
+        assert_eq!(merged.max_tokens, Some(1000));
+        assert_eq!(merged.stop, vec!["END".to_string()]);
+    }
+
     #[test]
     fn test_tool_category_from_tool() {
         assert_eq!(ToolCategory::from_tool("read"), ToolCategory::Read);
@@ -589,6 +1913,66 @@ mod tests {
         assert!(matches!(check, PermissionCheck::Denied { .. }));
     }
 
+    #[test]
+    fn test_env_policy() {
+        let mut policy = EnvPolicy::default();
+        assert!(!policy.passes("OPENROUTER_API_KEY"));
+        assert!(!policy.passes("NPM_TOKEN"));
+        assert!(policy.passes("PATH"));
+
+        policy.allow.push("NPM_TOKEN".into());
+        policy.set.insert("HYLE_TASK".into(), "build".into());
+        let env = policy.child_env(vec![
+            ("PATH".to_string(), "/bin".to_string()),
+            ("GITHUB_TOKEN".to_string(), "secret".to_string()),
+            ("NPM_TOKEN".to_string(), "npm".to_string()),
+        ]);
+        let names: Vec<&str> = env.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(names, vec!["HYLE_TASK", "NPM_TOKEN", "PATH"]);
+
+        // Old configs without an `env` section still get the default denylist
+        let cfg: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(cfg.env, EnvPolicy::default());
+    }
+
+    #[test]
+    fn test_protected_paths() {
+        let mut protected = ProtectedPaths::default();
+        assert_eq!(protected.matching("Cargo.lock"), Some("Cargo.lock"));
+        assert_eq!(
+            protected.matching("./web/package-lock.json"),
+            Some("package-lock.json")
+        );
+        assert_eq!(
+            protected.matching("target/debug/build/out.rs"),
+            Some("target/**")
+        );
+        assert_eq!(protected.matching("web/dist/app.js"), Some("dist/**"));
+        assert_eq!(protected.matching("src/main.rs"), None);
+        assert_eq!(
+            protected.matching_in(Path::new("/work/target"), "/work/target/src/main.rs"),
+            None
+        );
+        assert_eq!(protected.matching("src/target.rs"), None);
+        assert_eq!(protected.matching("Cargo.toml"), None);
+
+        protected.allow.push("dist/**".into());
+        assert_eq!(protected.matching("web/dist/app.js"), None);
+
+        let cfg: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(cfg.protected_paths, ProtectedPaths::default());
+    }
+
+    #[test]
+    fn test_check_permission_denied_argv() {
+        let mut cfg = Config::default();
+        cfg.permissions.denied_commands.insert("rm -rf".to_string());
+
+        let args = serde_json::json!({"argv": ["rm", "-rf", "/"]});
+        let check = check_tool_permission(&cfg, "bash", &args);
+        assert!(matches!(check, PermissionCheck::Denied { .. }));
+    }
+
     #[test]
     fn test_check_permission_allowed_command() {
         let mut cfg = Config::default();
@@ -623,4 +2007,134 @@ mod tests {
         assert_eq!(truncate("short", 10), "short");
         assert_eq!(truncate("this is a longer string", 10), "this is...");
     }
+
+    /// system prompt + 4 user turns, the second with a tool result
+    fn fixture_history() -> Vec<serde_json::Value> {
+        let msg = |role: &str, content: &str| serde_json::json!({"role": role, "content": content});
+        vec![
+            msg("system", "You are a helpful coding assistant."),
+            msg("user", "u1"),
+            msg("assistant", "a1"),
+            msg("user", "u2"),
+            msg("assistant", "a2 calls a tool"),
+            msg("system", "tool output\nsecond line"),
+            msg("user", "u3"),
+            msg("assistant", "a3"),
+            msg("user", "u4"),
+        ]
+    }
+
+    fn contents(messages: &[serde_json::Value]) -> Vec<String> {
+        messages
+            .iter()
+            .map(|m| m["content"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    fn no_salience(_: &[serde_json::Value], _: usize) -> String {
+        panic!("salience should not be consulted")
+    }
+
+    #[test]
+    fn test_context_strategy_parse() {
+        assert_eq!(
+            ContextStrategy::parse("full").unwrap(),
+            ContextStrategy::FullHistory
+        );
+        assert_eq!(
+            ContextStrategy::parse("window").unwrap(),
+            ContextStrategy::SlidingWindow {
+                turns: DEFAULT_WINDOW_TURNS
+            }
+        );
+        assert_eq!(
+            ContextStrategy::parse("window 3").unwrap(),
+            ContextStrategy::SlidingWindow { turns: 3 }
+        );
+        assert_eq!(
+            ContextStrategy::parse("salience 2000").unwrap(),
+            ContextStrategy::Salience { budget: 2000 }
+        );
+        assert_eq!(
+            ContextStrategy::parse("summarized").unwrap(),
+            ContextStrategy::Summarized
+        );
+        assert!(ContextStrategy::parse("window 0").is_err());
+        assert!(ContextStrategy::parse("window lots").is_err());
+        assert!(ContextStrategy::parse("everything").is_err());
+        assert_eq!(
+            ContextStrategy::parse("window 3").unwrap().label(),
+            "window:3"
+        );
+
+        // Round-trips through the config file
+        let json = r#"{"context_strategy": {"strategy": "salience", "budget": 1500}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            config.context_strategy,
+            ContextStrategy::Salience { budget: 1500 }
+        );
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.context_strategy, ContextStrategy::FullHistory);
+    }
+
+    #[test]
+    fn test_context_full_history() {
+        let history = fixture_history();
+        let out = ContextStrategy::FullHistory.assemble(&history, no_salience);
+        assert_eq!(out, history);
+    }
+
+    #[test]
+    fn test_context_sliding_window() {
+        let history = fixture_history();
+        let out = ContextStrategy::SlidingWindow { turns: 2 }.assemble(&history, no_salience);
+        assert_eq!(
+            contents(&out),
+            vec!["You are a helpful coding assistant.", "u3", "a3", "u4"]
+        );
+
+        // A window wider than the conversation keeps everything
+        let out = ContextStrategy::SlidingWindow { turns: 10 }.assemble(&history, no_salience);
+        assert_eq!(out, history);
+    }
+
+    #[test]
+    fn test_context_salience() {
+        let history = fixture_history();
+        let mut seen = None;
+        let out = ContextStrategy::Salience { budget: 500 }.assemble(&history, |older, budget| {
+            seen = Some((older.len(), budget));
+            "SALIENT".to_string()
+        });
+        // Everything between the system prompt and the last turn is folded
+        assert_eq!(seen, Some((7, 500)));
+        assert_eq!(
+            contents(&out),
+            vec!["You are a helpful coding assistant.", "SALIENT", "u4"]
+        );
+        assert_eq!(out[1]["role"], "system");
+    }
+
+    #[test]
+    fn test_context_summarized() {
+        let history = fixture_history();
+        let out = ContextStrategy::Summarized.assemble(&history, no_salience);
+        assert_eq!(out.len(), 1 + 1 + 4);
+        assert_eq!(
+            contents(&out[2..]),
+            vec!["tool output\nsecond line", "u3", "a3", "u4"]
+        );
+        let digest = out[1]["content"].as_str().unwrap();
+        assert!(digest.starts_with("[Earlier conversation, 4 messages condensed]"));
+        assert!(digest.contains("- user: u1"));
+        assert!(digest.contains("- assistant: a2 calls a tool"));
+
+        // Too short to condense: unchanged
+        let short = &history[..3];
+        assert_eq!(
+            ContextStrategy::Summarized.assemble(short, no_salience),
+            short.to_vec()
+        );
+    }
 }
@@ -0,0 +1,122 @@
+//! Decoding subprocess output
+//!
+//! `String::from_utf8_lossy` swaps invalid bytes for U+FFFD without a word,
+//! which leaves the model guessing at what a command actually printed.
+//! These helpers decode the same way but say when bytes were replaced, and
+//! can carry the raw bytes along as base64 when asked.
+
+/// Environment variable that, set to `base64`, appends the raw bytes of
+/// non-UTF-8 output
+pub const RAW_OUTPUT_ENV: &str = "HYLE_RAW_OUTPUT";
+
+/// Whether `HYLE_RAW_OUTPUT=base64` is set
+pub fn raw_base64_requested() -> bool {
+    std::env::var(RAW_OUTPUT_ENV)
+        .map(|v| v.trim().eq_ignore_ascii_case("base64"))
+        .unwrap_or(false)
+}
+
+/// Number of bytes that aren't part of a valid UTF-8 sequence
+pub fn invalid_utf8_bytes(mut bytes: &[u8]) -> usize {
+    let mut invalid = 0;
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(_) => return invalid,
+            Err(e) => {
+                let valid = e.valid_up_to();
+                // None: truncated sequence at the very end
+                let bad = e.error_len().unwrap_or(bytes.len() - valid);
+                invalid += bad;
+                bytes = &bytes[valid + bad..];
+            }
+        }
+    }
+}
+
+/// Decode lossily, noting how many bytes were replaced
+///
+/// Valid UTF-8 comes back unchanged. Otherwise a
+/// `[output contained N invalid bytes, shown lossily]` line follows the
+/// text, plus the raw bytes as base64 when `raw_base64` is set.
+pub fn decode_lossy(bytes: &[u8], raw_base64: bool) -> String {
+    let invalid = invalid_utf8_bytes(bytes);
+    let mut text = String::from_utf8_lossy(bytes).into_owned();
+    if invalid == 0 {
+        return text;
+    }
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(&format!(
+        "[output contained {} invalid byte{}, shown lossily]",
+        invalid,
+        if invalid == 1 { "" } else { "s" }
+    ));
+    if raw_base64 {
+        text.push_str(&format!("\n[raw output, base64: {}]", base64(bytes)));
+    }
+    text
+}
+
+/// `decode_lossy`, with raw bytes included per `HYLE_RAW_OUTPUT`
+pub fn decode_output(bytes: &[u8]) -> String {
+    decode_lossy(bytes, raw_base64_requested())
+}
+
+/// Standard base64 with padding
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | ((b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_utf8_bytes() {
+        assert_eq!(invalid_utf8_bytes(b"plain ascii"), 0);
+        assert_eq!(invalid_utf8_bytes("héllo ✓".as_bytes()), 0);
+        assert_eq!(invalid_utf8_bytes(b"a\xffb\xfe"), 2);
+        // Truncated multi-byte sequence at the end
+        assert_eq!(invalid_utf8_bytes(b"ok \xe2\x9c"), 2);
+    }
+
+    #[test]
+    fn test_decode_lossy_annotates_invalid_output() {
+        assert_eq!(decode_lossy(b"fine\n", true), "fine\n");
+
+        let text = decode_lossy(b"bin\xff\xfeary", false);
+        assert!(text.starts_with("bin\u{fffd}\u{fffd}ary\n"));
+        assert!(text.ends_with("[output contained 2 invalid bytes, shown lossily]"));
+        assert!(!text.contains("base64"));
+
+        let text = decode_lossy(b"\xff", true);
+        assert!(text.contains("[output contained 1 invalid byte, shown lossily]"));
+        assert!(text.ends_with("[raw output, base64: /w==]"));
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(&[0xff, 0xfe, 0x00]), "//4A");
+    }
+}
@@ -2,13 +2,20 @@
 //!
 //! Uses a free LLM to watch for code changes and suggest doc updates.
 //! Designed to run as a background process alongside the main session.
+//!
+//! Also keeps a searchable index of the project's docs (README, docs/,
+//! rustdoc comments) so answers can cite the repo's own documentation.
 
 #![allow(dead_code)]
 
+use anyhow::Result;
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
+use crate::config::InjectionPolicy;
+
 /// A documentation file being watched
 #[derive(Debug, Clone)]
 pub struct DocFile {
@@ -365,6 +372,331 @@ pub fn changelog_prompt(changes: &[CodeChange], version: Option<&str>) -> String
     prompt
 }
 
+// ═══════════════════════════════════════════════════════════════
+// DOCS INDEX
+// ═══════════════════════════════════════════════════════════════
+
+/// Bump when the cached index format changes
+const DOCS_INDEX_VERSION: u32 = 1;
+
+/// Results returned when the caller doesn't ask for a count
+pub const DOCS_DEFAULT_LIMIT: usize = 5;
+
+/// A searchable chunk of documentation: a markdown section or a doc comment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Passage {
+    /// Path relative to the project root
+    pub path: String,
+    /// 1-based line of the passage's first line
+    pub line: usize,
+    pub heading: String,
+    pub lines: Vec<String>,
+}
+
+/// A search result, cited as `path:line`
+#[derive(Debug, Clone)]
+pub struct DocHit {
+    pub path: String,
+    pub line: usize,
+    pub heading: String,
+    pub snippet: String,
+    pub score: f64,
+}
+
+/// Index over README, docs/ and rustdoc comments, cached per project
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocsIndex {
+    #[serde(default)]
+    version: u32,
+    /// Indexed files with their (mtime, size), to detect changes
+    files: BTreeMap<String, (u64, u64)>,
+    pub passages: Vec<Passage>,
+}
+
+impl DocsIndex {
+    /// Index the docs under `root` from scratch
+    pub fn build(root: &Path) -> Self {
+        let files = doc_files(root);
+        let mut passages = Vec::new();
+        for rel in files.keys() {
+            let Ok(content) = std::fs::read_to_string(root.join(rel)) else {
+                continue;
+            };
+            if rel.ends_with(".rs") {
+                passages.extend(rustdoc_passages(rel, &content));
+            } else {
+                passages.extend(markdown_passages(rel, &content));
+            }
+        }
+        Self {
+            version: DOCS_INDEX_VERSION,
+            files,
+            passages,
+        }
+    }
+
+    /// Load the cached index, rebuilding it if any doc file changed
+    pub fn load_or_build(root: &Path) -> Self {
+        match docs_cache_path(root) {
+            Ok(cache) => Self::load_or_build_at(root, &cache),
+            Err(_) => Self::build(root),
+        }
+    }
+
+    pub fn load_or_build_at(root: &Path, cache: &Path) -> Self {
+        let cached: Option<Self> = std::fs::read_to_string(cache)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok());
+        if let Some(index) = cached {
+            if index.version == DOCS_INDEX_VERSION && index.files == doc_files(root) {
+                return index;
+            }
+        }
+
+        let index = Self::build(root);
+        if let Some(dir) = cache.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Ok(json) = serde_json::to_string(&index) {
+            let _ = std::fs::write(cache, json);
+        }
+        index
+    }
+
+    /// Rank passages against a free-text query (TF-IDF over words)
+    pub fn search(&self, query: &str, limit: usize) -> Vec<DocHit> {
+        let terms = query_terms(query);
+        if terms.is_empty() {
+            return vec![];
+        }
+
+        let lowered: Vec<String> = self
+            .passages
+            .iter()
+            .map(|p| format!("{}\n{}", p.heading, p.lines.join("\n")).to_lowercase())
+            .collect();
+        let n = lowered.len() as f64;
+        let idf: Vec<f64> = terms
+            .iter()
+            .map(|t| {
+                let df = lowered
+                    .iter()
+                    .filter(|text| text.contains(t.as_str()))
+                    .count();
+                (1.0 + n / (df.max(1) as f64)).ln()
+            })
+            .collect();
+
+        let mut hits: Vec<DocHit> = self
+            .passages
+            .iter()
+            .zip(&lowered)
+            .filter_map(|(passage, text)| {
+                let heading = passage.heading.to_lowercase();
+                let score: f64 = terms
+                    .iter()
+                    .zip(&idf)
+                    .map(|(t, idf)| {
+                        let tf = text.matches(t.as_str()).count();
+                        if tf == 0 {
+                            return 0.0;
+                        }
+                        let boost = if heading.contains(t.as_str()) {
+                            2.0
+                        } else {
+                            1.0
+                        };
+                        (1.0 + (tf as f64).ln()) * idf * boost
+                    })
+                    .sum();
+                (score > 0.0).then(|| passage_hit(passage, &terms, score))
+            })
+            .collect();
+
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(limit);
+        hits
+    }
+}
+
+/// Cite the line of the passage that matches the most query terms
+fn passage_hit(passage: &Passage, terms: &[String], score: f64) -> DocHit {
+    let (offset, _) = passage
+        .lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = line.to_lowercase();
+            (
+                i,
+                terms.iter().filter(|t| line.contains(t.as_str())).count(),
+            )
+        })
+        .fold((0, 0), |best, cur| if cur.1 > best.1 { cur } else { best });
+
+    let snippet = passage
+        .lines
+        .iter()
+        .skip(offset)
+        .filter(|l| !l.trim().is_empty())
+        .take(3)
+        .map(|l| l.trim())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    DocHit {
+        path: passage.path.clone(),
+        line: passage.line + offset,
+        heading: passage.heading.clone(),
+        snippet,
+        score,
+    }
+}
+
+fn query_terms(query: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    query
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .map(|w| w.to_lowercase())
+        .filter(|w| w.len() >= 2 && seen.insert(w.clone()))
+        .collect()
+}
+
+/// Format hits as `path:line` citations with a snippet each, snippets
+/// passed through the injection guard
+pub fn format_hits(query: &str, hits: &[DocHit], guard: InjectionPolicy) -> String {
+    if hits.is_empty() {
+        return format!("No docs match '{}'", query);
+    }
+    hits.iter()
+        .map(|h| {
+            let source = format!("{}:{}", h.path, h.line);
+            let snippet = crate::guard::guard_block(&source, &h.snippet, guard);
+            format!("{} ({})\n  {}", source, h.heading, snippet)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Markdown and Rust files that make up the docs, with their (mtime, size)
+fn doc_files(root: &Path) -> BTreeMap<String, (u64, u64)> {
+    let base = glob::Pattern::escape(&root.display().to_string());
+    let patterns = ["*.md", "docs/**/*.md", "src/**/*.rs"];
+
+    let mut files = BTreeMap::new();
+    for pattern in patterns {
+        let Ok(entries) = glob::glob(&format!("{}/{}", base, pattern)) else {
+            continue;
+        };
+        for path in entries.filter_map(|e| e.ok()) {
+            let Ok(meta) = path.metadata() else {
+                continue;
+            };
+            let mtime = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0);
+            if let Ok(rel) = path.strip_prefix(root) {
+                files.insert(rel.display().to_string(), (mtime, meta.len()));
+            }
+        }
+    }
+    files
+}
+
+/// One passage per markdown section (text before the first heading included)
+fn markdown_passages(path: &str, content: &str) -> Vec<Passage> {
+    let mut passages = Vec::new();
+    let mut current = Passage {
+        path: path.to_string(),
+        line: 1,
+        heading: path.to_string(),
+        lines: vec![],
+    };
+    let mut in_code = false;
+
+    for (i, line) in content.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        let trimmed = line.trim_start();
+        if !in_code && trimmed.starts_with('#') {
+            if current.lines.iter().any(|l| !l.trim().is_empty()) {
+                passages.push(current.clone());
+            }
+            current.line = i + 1;
+            current.heading = trimmed.trim_start_matches('#').trim().to_string();
+            current.lines = vec![line.to_string()];
+        } else {
+            current.lines.push(line.to_string());
+        }
+    }
+    if current.lines.iter().any(|l| !l.trim().is_empty()) {
+        passages.push(current);
+    }
+    passages
+}
+
+/// One passage per `///` or `//!` comment block, headed by the item it documents
+fn rustdoc_passages(path: &str, content: &str) -> Vec<Passage> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut passages = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let trimmed = lines[i].trim_start();
+        let inner = trimmed.starts_with("//!");
+        if !inner && !trimmed.starts_with("///") {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let prefix = if inner { "//!" } else { "///" };
+        let mut text = Vec::new();
+        while i < lines.len() && lines[i].trim_start().starts_with(prefix) {
+            let body = lines[i].trim_start().trim_start_matches(prefix);
+            text.push(body.strip_prefix(' ').unwrap_or(body).to_string());
+            i += 1;
+        }
+
+        let heading = if inner {
+            format!("module {}", path)
+        } else {
+            lines[i..]
+                .iter()
+                .map(|l| l.trim())
+                .find(|l| !l.is_empty() && !l.starts_with("#["))
+                .map(|l| l.trim_end_matches('{').trim().chars().take(80).collect())
+                .unwrap_or_else(|| path.to_string())
+        };
+
+        if text.iter().any(|l| !l.trim().is_empty()) {
+            passages.push(Passage {
+                path: path.to_string(),
+                line: start + 1,
+                heading,
+                lines: text,
+            });
+        }
+    }
+    passages
+}
+
+/// Per-project cache file under the hyle cache dir
+fn docs_cache_path(root: &Path) -> Result<PathBuf> {
+    use std::hash::{Hash, Hasher};
+
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    root.hash(&mut hasher);
+    Ok(crate::config::cache_dir()?
+        .join("docs")
+        .join(format!("{:016x}.json", hasher.finish())))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -386,4 +718,72 @@ mod tests {
 
         std::fs::remove_file(temp_file).ok();
     }
+
+    fn docs_fixture(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("hyle_test_docs_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("README.md"),
+            "# Demo\n\nA demo project.\n\n## Install\n\nRun cargo install demo.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("docs/config.md"),
+            "# Configuration\n\nSettings live in config.json.\n\n## Timeouts\n\nThe max_command_timeout_ms key caps shell timeouts.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "//! Demo crate root\n\n/// Retry a request with exponential backoff\npub fn retry() {}\n",
+        )
+        .unwrap();
+        root
+    }
+
+    #[test]
+    fn test_docs_search_cites_right_file() {
+        let root = docs_fixture("search");
+        let index = DocsIndex::build(&root);
+
+        let hits = index.search("shell timeouts", 3);
+        assert_eq!(hits[0].path, "docs/config.md");
+        assert_eq!(hits[0].line, 7);
+        assert_eq!(hits[0].heading, "Timeouts");
+
+        let hits = index.search("exponential backoff", 3);
+        assert_eq!(hits[0].path, "src/lib.rs");
+        assert_eq!(hits[0].line, 3);
+        assert_eq!(hits[0].heading, "pub fn retry() {}");
+
+        let hits = index.search("install", 3);
+        assert_eq!(hits[0].path, "README.md");
+        assert!(format_hits("install", &hits, InjectionPolicy::Warn)
+            .starts_with("README.md:5 (Install)"));
+
+        assert!(index.search("nonexistentword", 3).is_empty());
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_docs_index_cache_invalidation() {
+        let root = docs_fixture("cache");
+        let cache = root.join("cache/index.json");
+
+        let index = DocsIndex::load_or_build_at(&root, &cache);
+        assert!(cache.exists());
+        assert!(index.search("webhooks", 3).is_empty());
+
+        std::fs::write(
+            root.join("docs/hooks.md"),
+            "# Hooks\n\nWebhooks fire on push.\n",
+        )
+        .unwrap();
+        let index = DocsIndex::load_or_build_at(&root, &cache);
+        assert_eq!(index.search("webhooks", 3)[0].path, "docs/hooks.md");
+
+        std::fs::remove_dir_all(&root).ok();
+    }
 }
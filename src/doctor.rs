@@ -0,0 +1,341 @@
+//! Repairs for `hyle doctor --fix`
+//!
+//! Plain `hyle doctor` only reports. With `--fix`, each problem found here
+//! is offered in turn, repaired once confirmed, and the outcome summarized.
+
+use anyhow::{bail, Result};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use crate::models::{self, Model};
+
+/// Sessions kept when stale ones are cleaned, as `hyle sessions --clean`
+pub const KEEP_SESSIONS: usize = 10;
+
+/// The state repairs are planned from
+#[derive(Debug, Clone)]
+pub struct Findings {
+    pub config_dir: PathBuf,
+    /// In config or OPENROUTER_API_KEY
+    pub has_api_key: bool,
+    pub models_cache: PathBuf,
+    /// Cache exists, parses and is fresh
+    pub models_cache_ok: bool,
+    pub sessions_dir: PathBuf,
+    pub session_count: usize,
+}
+
+impl Findings {
+    pub fn gather() -> Result<Self> {
+        let has_api_key = crate::config::get_api_key().is_ok();
+        let models_cache = models::cache_path()?;
+        let sessions_dir = crate::session::sessions_dir()?;
+        Ok(Self {
+            config_dir: crate::config::config_dir()?,
+            has_api_key,
+            models_cache_ok: models::cache_usable_at(&models_cache),
+            models_cache,
+            session_count: crate::session::list_sessions_in(&sessions_dir).len(),
+            sessions_dir,
+        })
+    }
+
+    /// Repairs worth offering, in the order they should run
+    pub fn repairs(&self) -> Vec<Repair> {
+        let mut repairs = Vec::new();
+        // First: the key is saved into it
+        if !self.config_dir.is_dir() {
+            repairs.push(Repair::CreateConfigDir(self.config_dir.clone()));
+        }
+        if !self.has_api_key {
+            repairs.push(Repair::SaveApiKey);
+        }
+        if !self.models_cache_ok {
+            repairs.push(Repair::RefreshModels(self.models_cache.clone()));
+        }
+        if self.session_count > KEEP_SESSIONS {
+            repairs.push(Repair::CleanSessions {
+                dir: self.sessions_dir.clone(),
+                stale: self.session_count - KEEP_SESSIONS,
+            });
+        }
+        repairs
+    }
+}
+
+/// A problem `--fix` knows how to repair
+#[derive(Debug, Clone, PartialEq)]
+pub enum Repair {
+    CreateConfigDir(PathBuf),
+    /// Prompt for a key and save it to config
+    SaveApiKey,
+    /// Models cache missing, stale or corrupt
+    RefreshModels(PathBuf),
+    /// Sessions beyond the newest `KEEP_SESSIONS`
+    CleanSessions {
+        dir: PathBuf,
+        stale: usize,
+    },
+}
+
+impl Repair {
+    /// Question asked before repairing
+    pub fn question(&self) -> String {
+        match self {
+            Repair::CreateConfigDir(dir) => format!("Create config directory {}?", dir.display()),
+            Repair::SaveApiKey => "No API key found. Enter and save one now?".into(),
+            Repair::RefreshModels(path) => {
+                format!(
+                    "Models cache missing or unreadable. Fetch it into {}?",
+                    path.display()
+                )
+            }
+            Repair::CleanSessions { stale, .. } => format!(
+                "Remove {} stale session{} (keeping the newest {})?",
+                stale,
+                if *stale == 1 { "" } else { "s" },
+                KEEP_SESSIONS
+            ),
+        }
+    }
+}
+
+/// What `--fix` did, one line per repair
+#[derive(Debug, Default)]
+pub struct FixReport {
+    pub fixed: Vec<String>,
+    pub skipped: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl FixReport {
+    pub fn summary(&self) -> String {
+        let mut lines = vec![format!(
+            "Fixed {}, skipped {}, failed {}",
+            self.fixed.len(),
+            self.skipped.len(),
+            self.failed.len()
+        )];
+        let sections = [
+            ("✓", &self.fixed),
+            ("○", &self.skipped),
+            ("✗", &self.failed),
+        ];
+        for (mark, items) in sections {
+            lines.extend(items.iter().map(|item| format!("  [{}] {}", mark, item)));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Offer each repair, running the confirmed ones
+///
+/// `save_key` prompts for and saves an API key, returning where it went;
+/// `fetch` downloads the model list.
+pub async fn fix<C, K, F, Fut>(
+    repairs: &[Repair],
+    mut confirm: C,
+    mut save_key: K,
+    mut fetch: F,
+) -> FixReport
+where
+    C: FnMut(&str) -> bool,
+    K: FnMut() -> Result<PathBuf>,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Vec<Model>>>,
+{
+    let mut report = FixReport::default();
+    for repair in repairs {
+        let question = repair.question();
+        if !confirm(&question) {
+            report.skipped.push(question);
+            continue;
+        }
+        let outcome = match repair {
+            Repair::CreateConfigDir(dir) => create_dir(dir),
+            Repair::SaveApiKey => {
+                save_key().map(|path| format!("Saved API key to {}", path.display()))
+            }
+            Repair::RefreshModels(path) => models::refresh_cache_at(path, &mut fetch)
+                .await
+                .map(|n| format!("Cached {} models in {}", n, path.display())),
+            Repair::CleanSessions { dir, .. } => {
+                let removed = crate::session::cleanup_sessions_in(dir, KEEP_SESSIONS);
+                Ok(format!("Removed {} stale sessions", removed))
+            }
+        };
+        match outcome {
+            Ok(done) => report.fixed.push(done),
+            Err(e) => report.failed.push(format!("{} ({})", question, e)),
+        }
+    }
+    report
+}
+
+fn create_dir(dir: &Path) -> Result<String> {
+    if dir.exists() && !dir.is_dir() {
+        bail!("{} exists and is not a directory", dir.display());
+    }
+    std::fs::create_dir_all(dir)?;
+    Ok(format!("Created {}", dir.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("hyle_test_doctor_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn findings(root: &Path) -> Findings {
+        Findings {
+            config_dir: root.join("config"),
+            has_api_key: true,
+            models_cache: root.join("cache/models.json"),
+            models_cache_ok: true,
+            sessions_dir: root.join("sessions"),
+            session_count: 0,
+        }
+    }
+
+    fn sample_models() -> Vec<Model> {
+        vec![Model {
+            id: "fetched/model:free".into(),
+            name: "Fetched".into(),
+            context_length: 8192,
+            pricing_prompt: 0.0,
+            pricing_completion: 0.0,
+            pricing_cache_read: None,
+            pricing_cache_write: None,
+            capabilities: vec![],
+        }]
+    }
+
+    #[test]
+    fn test_repairs_planned_from_findings() {
+        let root = scratch("plan");
+        std::fs::create_dir_all(root.join("config")).unwrap();
+        assert!(findings(&root).repairs().is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+        let broken = Findings {
+            has_api_key: false,
+            models_cache_ok: false,
+            session_count: KEEP_SESSIONS + 3,
+            ..findings(&root)
+        };
+        assert_eq!(
+            broken.repairs(),
+            vec![
+                Repair::CreateConfigDir(root.join("config")),
+                Repair::SaveApiKey,
+                Repair::RefreshModels(root.join("cache/models.json")),
+                Repair::CleanSessions {
+                    dir: root.join("sessions"),
+                    stale: 3
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fix_runs_confirmed_repairs() {
+        let root = scratch("fix");
+        let cache = root.join("cache/models.json");
+        let repairs = vec![
+            Repair::CreateConfigDir(root.join("config")),
+            Repair::SaveApiKey,
+            Repair::RefreshModels(cache.clone()),
+        ];
+
+        // Missing cache: the fetch stub runs and the cache becomes usable
+        let mut fetches = 0;
+        let report = fix(
+            &repairs,
+            |q| !q.contains("API key"),
+            || panic!("declined repairs must not run"),
+            || {
+                fetches += 1;
+                async { Ok(sample_models()) }
+            },
+        )
+        .await;
+        assert_eq!(fetches, 1);
+        assert!(root.join("config").is_dir());
+        assert!(models::cache_usable_at(&cache));
+        assert_eq!(report.fixed.len(), 2);
+        assert!(report.fixed[1].starts_with("Cached 1 models"));
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.summary().starts_with("Fixed 2, skipped 1, failed 0"));
+
+        // Failures are reported, not fatal
+        std::fs::write(root.join("file"), "").unwrap();
+        let repairs = vec![
+            Repair::CreateConfigDir(root.join("file")),
+            Repair::SaveApiKey,
+        ];
+        let report = fix(
+            &repairs,
+            |_| true,
+            || Ok(root.join("config/config.json")),
+            || async { anyhow::bail!("offline") },
+        )
+        .await;
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].contains("not a directory"));
+        assert_eq!(
+            report.fixed,
+            vec![format!(
+                "Saved API key to {}",
+                root.join("config/config.json").display()
+            )]
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_fix_cleans_stale_sessions() {
+        let root = scratch("sessions");
+        let template = scratch("sessions_template");
+        let meta = crate::session::Session::new_in(&template, "test/model")
+            .unwrap()
+            .meta;
+        for i in 0..KEEP_SESSIONS + 2 {
+            let mut meta = meta.clone();
+            meta.id = format!("20260101-0000{:02}-0000", i);
+            meta.updated_at += chrono::Duration::minutes(i as i64);
+            let dir = root.join(&meta.id);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("meta.json"), serde_json::to_string(&meta).unwrap()).unwrap();
+        }
+        assert_eq!(
+            crate::session::list_sessions_in(&root).len(),
+            KEEP_SESSIONS + 2
+        );
+
+        let repairs = vec![Repair::CleanSessions {
+            dir: root.clone(),
+            stale: 2,
+        }];
+        let report = fix(
+            &repairs,
+            |_| true,
+            || unreachable!(),
+            || async { anyhow::bail!("no fetch") },
+        )
+        .await;
+        assert_eq!(report.fixed, vec!["Removed 2 stale sessions".to_string()]);
+        let kept = crate::session::list_sessions_in(&root);
+        assert_eq!(kept.len(), KEEP_SESSIONS);
+        // The oldest two went
+        assert!(kept.iter().all(|m| m.id.as_str() > "20260101-000001-0000"));
+
+        let _ = std::fs::remove_dir_all(&root);
+        let _ = std::fs::remove_dir_all(&template);
+    }
+}
@@ -5,10 +5,14 @@
 //! - Recent activity and projects
 //! - System constraints and resources
 //! - Remote access and connectivity
+//! - Execution snapshot (OS, shell, PATH tools) for the agent
 
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::OnceLock;
 
 // ═══════════════════════════════════════════════════════════════
 // ENVIRONMENT MAP
@@ -204,6 +208,140 @@ impl ToolInventory {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════
+// EXECUTION SNAPSHOT
+// ═══════════════════════════════════════════════════════════════
+
+/// Commands whose presence on PATH changes what the agent should run
+pub const SNAPSHOT_TOOLS: &[&str] = &[
+    "git", "cargo", "rustc", "rg", "fd", "make", "npm", "pnpm", "yarn", "node", "python3",
+    "pytest", "ruff", "black", "go", "docker", "gh", "jq", "curl", "tmux",
+];
+
+/// Where commands will run: platform, shell and available tools
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvSnapshot {
+    pub os: String,
+    pub arch: String,
+    /// Login shell name from $SHELL
+    pub shell: Option<String>,
+    /// Tool name -> found on PATH
+    pub tools: BTreeMap<String, bool>,
+    pub git_version: Option<String>,
+    /// Terminal size as (columns, rows), if attached to one
+    pub terminal: Option<(u16, u16)>,
+    pub in_tmux: bool,
+}
+
+impl EnvSnapshot {
+    /// Inspect the current process environment
+    pub fn capture() -> Self {
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let tools = detect_tools(&path, SNAPSHOT_TOOLS);
+        let git_version = if tools.get("git").copied().unwrap_or(false) {
+            Command::new("git")
+                .arg("--version")
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| {
+                    let out = String::from_utf8_lossy(&o.stdout);
+                    out.trim().trim_start_matches("git version ").to_string()
+                })
+        } else {
+            None
+        };
+
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            shell: std::env::var("SHELL").ok().and_then(|s| {
+                Path::new(&s)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+            }),
+            tools,
+            git_version,
+            terminal: crossterm::terminal::size().ok(),
+            in_tmux: std::env::var_os("TMUX").is_some(),
+        }
+    }
+
+    pub fn has(&self, tool: &str) -> bool {
+        self.tools.get(tool).copied().unwrap_or(false)
+    }
+
+    /// Short human-readable report (used by /doctor)
+    pub fn report(&self) -> Vec<String> {
+        let mark = |ok: bool| if ok { "✓" } else { "○" };
+        let (found, missing): (Vec<_>, Vec<_>) = self.tools.iter().partition(|(_, ok)| **ok);
+        let names = |list: Vec<(&String, &bool)>| {
+            list.into_iter()
+                .map(|(n, _)| n.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let mut lines = vec![
+            format!(
+                "[✓] Platform: {} {} (shell: {})",
+                self.os,
+                self.arch,
+                self.shell.as_deref().unwrap_or("unknown")
+            ),
+            format!(
+                "[{}] git: {}",
+                mark(self.git_version.is_some()),
+                self.git_version.as_deref().unwrap_or("not found")
+            ),
+            format!("[✓] Tools on PATH: {}", names(found)),
+        ];
+        if !missing.is_empty() {
+            lines.push(format!("[○] Not found: {}", names(missing)));
+        }
+        lines.push(format!(
+            "[{}] Terminal: {}{}",
+            mark(self.terminal.is_some()),
+            self.terminal
+                .map(|(w, h)| format!("{}x{}", w, h))
+                .unwrap_or_else(|| "none".into()),
+            if self.in_tmux { " (tmux)" } else { "" }
+        ));
+        lines
+    }
+}
+
+/// Snapshot taken once per run
+pub fn snapshot() -> &'static EnvSnapshot {
+    static SNAPSHOT: OnceLock<EnvSnapshot> = OnceLock::new();
+    SNAPSHOT.get_or_init(EnvSnapshot::capture)
+}
+
+/// Check which `names` are executables on the given PATH value
+pub fn detect_tools(path: &OsStr, names: &[&str]) -> BTreeMap<String, bool> {
+    let dirs: Vec<PathBuf> = std::env::split_paths(path).collect();
+    names
+        .iter()
+        .map(|name| {
+            let found = dirs.iter().any(|d| is_executable(&d.join(name)));
+            (name.to_string(), found)
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() || path.with_extension("exe").is_file()
+}
+
 // ═══════════════════════════════════════════════════════════════
 // PROJECT MAP
 // ═══════════════════════════════════════════════════════════════
@@ -503,6 +641,40 @@ mod tests {
         assert!(tools.tools.contains_key("git"));
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn test_detect_tools_uses_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("hyle_test_path_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let rg = dir.join("rg");
+        std::fs::write(&rg, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&rg, std::fs::Permissions::from_mode(0o755)).unwrap();
+        // Present but not executable
+        std::fs::write(dir.join("fd"), "").unwrap();
+
+        let path = std::env::join_paths([dir.join("missing"), dir.clone()]).unwrap();
+        let tools = detect_tools(&path, &["rg", "fd", "cargo"]);
+        assert!(tools["rg"]);
+        assert!(!tools["fd"]);
+        assert!(!tools["cargo"]);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_env_snapshot_serializes() {
+        let snap = EnvSnapshot::capture();
+        assert_eq!(snap.os, std::env::consts::OS);
+        assert!(SNAPSHOT_TOOLS.iter().all(|t| snap.tools.contains_key(*t)));
+
+        let json = serde_json::to_string(&snap).unwrap();
+        let back: EnvSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, snap);
+        assert!(snap.report()[0].contains(&snap.arch));
+    }
+
     #[test]
     fn test_system_resources() {
         let res = SystemResources::check();
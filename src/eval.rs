@@ -237,7 +237,10 @@ impl ResponseEvaluator {
         let mut valid_count = 0;
         for call in &calls {
             // Check if tool name is known
-            let known_tools = ["read", "write", "bash", "glob", "grep", "edit", "search"];
+            let known_tools = [
+                "read", "write", "bash", "glob", "grep", "tree", "stats", "docs", "environ",
+                "ask_user", "edit", "search", "move", "delete",
+            ];
             if known_tools.contains(&call.name.as_str()) {
                 valid_count += 1;
             }
@@ -338,6 +341,37 @@ impl ResponseEvaluator {
 // MODEL PERFORMANCE TRACKER
 // ═══════════════════════════════════════════════════════════════
 
+/// Consecutive low-quality responses before an upgrade is proposed
+pub const UPGRADE_AFTER_LOW_SCORES: usize = 3;
+
+/// Why the active model changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchReason {
+    /// Repeated low-quality responses
+    Quality,
+    /// Provider returned 429
+    RateLimit,
+    /// User picked a model
+    Manual,
+    /// User adopted this model's response in a comparison
+    Compare,
+    /// The agent loop got stuck on the previous model
+    Stuck,
+}
+
+impl SwitchReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SwitchReason::Quality => "quality",
+            SwitchReason::RateLimit => "rate limit",
+            SwitchReason::Manual => "manual",
+            SwitchReason::Compare => "compare",
+            SwitchReason::Stuck => "stuck",
+        }
+    }
+}
+
 /// Tracks performance of a single model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelStats {
@@ -350,6 +384,8 @@ pub struct ModelStats {
     pub recent_scores: Vec<f32>, // Last N scores
     pub last_used: Option<i64>,  // Unix timestamp
     pub consecutive_failures: usize,
+    #[serde(default)]
+    pub quality_sum: f32, // All scores, for the lifetime average
 }
 
 impl ModelStats {
@@ -364,6 +400,7 @@ impl ModelStats {
             recent_scores: Vec::new(),
             last_used: None,
             consecutive_failures: 0,
+            quality_sum: 0.0,
         }
     }
 
@@ -374,6 +411,7 @@ impl ModelStats {
         self.total_tokens += tokens;
         self.consecutive_failures = 0;
         self.last_used = Some(chrono::Utc::now().timestamp());
+        self.quality_sum += quality;
 
         // Update recent scores (keep last 20)
         self.recent_scores.push(quality);
@@ -491,6 +529,30 @@ impl ModelTracker {
             .and_then(|id| self.stats.get(id))
     }
 
+    /// Next preferred model to upgrade to after repeated low-quality responses.
+    ///
+    /// Returns None until the current model has `UPGRADE_AFTER_LOW_SCORES`
+    /// consecutive bad responses; any acceptable response resets that count.
+    /// Preferences already known to be struggling are skipped.
+    pub fn upgrade_target(&self, preferences: &[String]) -> Option<String> {
+        let current = self.current_model.as_ref()?;
+        let stats = self.stats.get(current)?;
+        if stats.consecutive_failures < UPGRADE_AFTER_LOW_SCORES {
+            return None;
+        }
+
+        preferences
+            .iter()
+            .filter(|m| *m != current)
+            .find(|m| {
+                self.stats
+                    .get(*m)
+                    .map(|s| !s.should_switch())
+                    .unwrap_or(true)
+            })
+            .cloned()
+    }
+
     /// Get best performing model from tracked models
     pub fn best_model(&self) -> Option<&str> {
         self.stats
@@ -517,6 +579,142 @@ impl ModelTracker {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════
+// LEADERBOARD
+// ═══════════════════════════════════════════════════════════════
+
+/// Lifetime quality of one model across sessions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelRecord {
+    pub avg_quality: f32,
+    pub samples: usize,
+    pub last_used: Option<i64>,
+}
+
+impl ModelRecord {
+    /// Fold in another record, weighting each average by its samples
+    pub fn merge(&mut self, other: &ModelRecord) {
+        let total = self.samples + other.samples;
+        if total == 0 {
+            return;
+        }
+        self.avg_quality = (self.avg_quality * self.samples as f32
+            + other.avg_quality * other.samples as f32)
+            / total as f32;
+        self.samples = total;
+        self.last_used = self.last_used.max(other.last_used);
+    }
+}
+
+/// Per-model quality observed across sessions, persisted to
+/// ~/.local/state/hyle/model_stats.json
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub models: HashMap<String, ModelRecord>,
+}
+
+impl Leaderboard {
+    pub fn path() -> anyhow::Result<std::path::PathBuf> {
+        Ok(crate::config::state_dir()?.join("model_stats.json"))
+    }
+
+    /// Load from the default path; empty when missing or unreadable
+    pub fn load() -> Self {
+        Self::path()
+            .map(|p| Self::load_from(&p))
+            .unwrap_or_default()
+    }
+
+    pub fn load_from(path: &std::path::Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save_to(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// This session's totals; models with no responses are left out
+    pub fn from_tracker(tracker: &ModelTracker) -> Self {
+        let models = tracker
+            .stats
+            .values()
+            .filter(|s| s.total_requests > 0)
+            .map(|s| {
+                let record = ModelRecord {
+                    avg_quality: s.quality_sum / s.total_requests as f32,
+                    samples: s.total_requests,
+                    last_used: s.last_used,
+                };
+                (s.model_id.clone(), record)
+            })
+            .collect();
+        Self { models }
+    }
+
+    pub fn merge(&mut self, other: &Leaderboard) {
+        for (id, record) in &other.models {
+            self.models.entry(id.clone()).or_default().merge(record);
+        }
+    }
+
+    /// Merge a finished session into the file at `path`
+    ///
+    /// Re-reads the file first so concurrent sessions don't drop each
+    /// other's samples.
+    pub fn persist_session(tracker: &ModelTracker, path: &std::path::Path) -> anyhow::Result<()> {
+        let session = Self::from_tracker(tracker);
+        if session.models.is_empty() {
+            return Ok(());
+        }
+        let mut board = Self::load_from(path);
+        board.merge(&session);
+        board.save_to(path)
+    }
+
+    /// Best first: by average quality, then by samples, then by id
+    pub fn ranking(&self) -> Vec<(&str, &ModelRecord)> {
+        let mut ranked: Vec<_> = self.models.iter().map(|(id, r)| (id.as_str(), r)).collect();
+        ranked.sort_by(|(a_id, a), (b_id, b)| {
+            b.avg_quality
+                .partial_cmp(&a.avg_quality)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.samples.cmp(&a.samples))
+                .then(a_id.cmp(b_id))
+        });
+        ranked
+    }
+
+    /// One line per model, best first
+    pub fn render(&self) -> Vec<String> {
+        self.ranking()
+            .iter()
+            .enumerate()
+            .map(|(i, (id, r))| {
+                let last = r
+                    .last_used
+                    .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+                    .map(|t| t.format("%Y-%m-%d").to_string())
+                    .unwrap_or_else(|| "-".into());
+                format!(
+                    "{:>2}. {:<45} {:>4.0}% {:>6}  {}",
+                    i + 1,
+                    id,
+                    r.avg_quality * 100.0,
+                    r.samples,
+                    last
+                )
+            })
+            .collect()
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════
 // MODEL SWITCHER
 // ═══════════════════════════════════════════════════════════════
@@ -805,6 +1003,59 @@ fn main() {
         assert!(tracker.should_switch());
     }
 
+    #[test]
+    fn test_upgrade_after_consecutive_low_scores() {
+        let prefs = vec![
+            "anthropic/claude-3.5-haiku".to_string(),
+            "openai/gpt-4o-mini".to_string(),
+        ];
+        let mut tracker = ModelTracker::new();
+        tracker.set_model("test/model:free");
+
+        for i in 0..UPGRADE_AFTER_LOW_SCORES {
+            assert_eq!(tracker.upgrade_target(&prefs), None, "too early at {}", i);
+            tracker.record_response("Explain the function", "", 0);
+        }
+
+        assert_eq!(
+            tracker.upgrade_target(&prefs).as_deref(),
+            Some("anthropic/claude-3.5-haiku")
+        );
+    }
+
+    #[test]
+    fn test_upgrade_counter_resets_on_good_response() {
+        let prefs = vec!["openai/gpt-4o-mini".to_string()];
+        let mut tracker = ModelTracker::new();
+        tracker.set_model("test/model:free");
+
+        for _ in 0..UPGRADE_AFTER_LOW_SCORES - 1 {
+            tracker.record_response("Explain the function", "", 0);
+        }
+        tracker.record_response(
+            "Explain the function",
+            "Here is the explanation: the function parses input and returns a result. Done.",
+            20,
+        );
+        tracker.record_response("Explain the function", "", 0);
+
+        assert_eq!(tracker.upgrade_target(&prefs), None);
+    }
+
+    #[test]
+    fn test_upgrade_skips_current_and_struggling_models() {
+        let prefs = vec!["a/current".to_string(), "b/next".to_string()];
+        let mut tracker = ModelTracker::new();
+        tracker.set_model("a/current");
+        for _ in 0..UPGRADE_AFTER_LOW_SCORES {
+            tracker.record_failure();
+        }
+        assert_eq!(tracker.upgrade_target(&prefs).as_deref(), Some("b/next"));
+
+        // No preferences configured: nothing to offer
+        assert_eq!(tracker.upgrade_target(&[]), None);
+    }
+
     #[test]
     fn test_model_switcher() {
         let models = vec![
@@ -842,6 +1093,85 @@ fn main() {
         assert_eq!(switcher.current_model(), Some("model-a"));
     }
 
+    #[test]
+    fn test_leaderboard_from_tracker_and_merge() {
+        let mut tracker = ModelTracker::new();
+        tracker.set_model("a/fast");
+        tracker
+            .stats
+            .get_mut("a/fast")
+            .unwrap()
+            .record_success(0.8, 10);
+        tracker.stats.get_mut("a/fast").unwrap().record_failure();
+        tracker.set_model("b/unused");
+
+        let session = Leaderboard::from_tracker(&tracker);
+        assert_eq!(session.models.len(), 1);
+        let fast = &session.models["a/fast"];
+        assert_eq!(fast.samples, 2);
+        assert!((fast.avg_quality - 0.4).abs() < 1e-6);
+
+        // Weighted by samples: (0.4*2 + 0.9*6) / 8
+        let mut board = Leaderboard::default();
+        board.models.insert(
+            "a/fast".into(),
+            ModelRecord {
+                avg_quality: 0.9,
+                samples: 6,
+                last_used: Some(1),
+            },
+        );
+        board.merge(&session);
+        let merged = &board.models["a/fast"];
+        assert_eq!(merged.samples, 8);
+        assert!((merged.avg_quality - 0.775).abs() < 1e-6);
+        assert_eq!(merged.last_used, fast.last_used);
+    }
+
+    #[test]
+    fn test_leaderboard_persists_across_sessions() {
+        let path =
+            std::env::temp_dir().join(format!("hyle_test_model_stats_{}.json", std::process::id()));
+        std::fs::remove_file(&path).ok();
+
+        for quality in [0.6, 1.0] {
+            let mut tracker = ModelTracker::new();
+            tracker.set_model("m");
+            tracker
+                .stats
+                .get_mut("m")
+                .unwrap()
+                .record_success(quality, 1);
+            Leaderboard::persist_session(&tracker, &path).unwrap();
+        }
+        let board = Leaderboard::load_from(&path);
+        assert_eq!(board.models["m"].samples, 2);
+        assert!((board.models["m"].avg_quality - 0.8).abs() < 1e-6);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_leaderboard_ranking() {
+        let record = |avg_quality, samples| ModelRecord {
+            avg_quality,
+            samples,
+            last_used: None,
+        };
+        let mut board = Leaderboard::default();
+        board.models.insert("low".into(), record(0.3, 50));
+        board.models.insert("tie-b".into(), record(0.7, 5));
+        board.models.insert("tie-a".into(), record(0.7, 5));
+        board.models.insert("proven".into(), record(0.7, 20));
+        board.models.insert("top".into(), record(0.9, 2));
+
+        let order: Vec<&str> = board.ranking().iter().map(|(id, _)| *id).collect();
+        assert_eq!(order, vec!["top", "proven", "tie-a", "tie-b", "low"]);
+        let lines = board.render();
+        assert!(lines[0].starts_with(" 1. top"));
+        assert!(lines[0].contains("90%"));
+    }
+
     #[test]
     fn test_quality_score_display() {
         let mut score = QualityScore {
@@ -805,6 +805,17 @@ pub enum Verbosity {
     Full,    // Complete with constraints
 }
 
+impl Verbosity {
+    /// Full on a task's first round, shorter as the loop goes on
+    pub fn for_iteration(iteration: usize) -> Self {
+        match iteration {
+            0 => Verbosity::Full,
+            1 | 2 => Verbosity::Normal,
+            _ => Verbosity::Minimal,
+        }
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════
 // PROMPTS FOR FREE LLM MAINTENANCE
 // ═══════════════════════════════════════════════════════════════
@@ -3,8 +3,10 @@
 //! This lib.rs exposes modules for integration testing.
 //! The main binary is in main.rs.
 
+pub mod artifacts;
 pub mod coggy_bridge;
 pub mod coggy_live;
 pub mod cognitive;
 pub mod config;
+pub mod decode;
 pub mod session;
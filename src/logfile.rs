@@ -0,0 +1,273 @@
+//! Structured log file for debugging after the fact
+//!
+//! Off unless `log_level` is set in config (or `HYLE_LOG`). Events at that
+//! level or more severe are appended as JSON lines to
+//! `~/.local/state/hyle/logs/hyle.log`, which rotates to `hyle.log.1`,
+//! `hyle.log.2`, ... once it passes `MAX_LOG_BYTES`. The TUI Log view is
+//! separate and unaffected.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::config::LogLevel;
+
+/// Size at which the log rotates
+pub const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Rotated files kept besides the live one
+pub const KEEP_LOGS: usize = 3;
+
+const LOG_FILE: &str = "hyle.log";
+
+// ═══════════════════════════════════════════════════════════════
+// EVENTS
+// ═══════════════════════════════════════════════════════════════
+
+/// One log line
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEvent {
+    pub ts: DateTime<Utc>,
+    pub level: LogLevel,
+    pub module: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl LogEvent {
+    /// `module` is usually `module_path!()`
+    pub fn new(level: LogLevel, module: &str, message: impl Into<String>) -> Self {
+        Self {
+            ts: Utc::now(),
+            level,
+            module: module.to_string(),
+            message: message.into(),
+            fields: serde_json::Map::new(),
+        }
+    }
+
+    pub fn error(module: &str, message: impl Into<String>) -> Self {
+        Self::new(LogLevel::Error, module, message)
+    }
+
+    pub fn warn(module: &str, message: impl Into<String>) -> Self {
+        Self::new(LogLevel::Warn, module, message)
+    }
+
+    pub fn field(mut self, key: &str, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).unwrap_or(serde_json::Value::Null);
+        self.fields.insert(key.to_string(), value);
+        self
+    }
+
+    pub fn to_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Write to the process log, if logging is on
+    pub fn emit(self) {
+        if let Some(log) = LOG.get().and_then(Option::as_ref) {
+            if let Ok(log) = log.lock() {
+                let _ = log.write(&self);
+            }
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ROTATING FILE
+// ═══════════════════════════════════════════════════════════════
+
+/// Appends events to `<dir>/hyle.log`, rotating at a size cap
+#[derive(Debug)]
+pub struct RotatingLog {
+    dir: PathBuf,
+    level: LogLevel,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingLog {
+    pub fn new(dir: &Path, level: LogLevel) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            level,
+            max_bytes: MAX_LOG_BYTES,
+            keep: KEEP_LOGS,
+        }
+    }
+
+    pub fn with_limits(mut self, max_bytes: u64, keep: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self.keep = keep;
+        self
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(LOG_FILE)
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        self.dir.join(format!("{}.{}", LOG_FILE, n))
+    }
+
+    /// Append `event` if it is at or above the level; rotates first when
+    /// the line would take the file past the cap
+    pub fn write(&self, event: &LogEvent) -> Result<()> {
+        if event.level > self.level {
+            return Ok(());
+        }
+        let line = event.to_line();
+        fs::create_dir_all(&self.dir)?;
+        let size = fs::metadata(self.path()).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 + 1 > self.max_bytes {
+            self.rotate()?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path())?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    /// hyle.log -> hyle.log.1 -> ... -> hyle.log.<keep>, dropping the oldest
+    fn rotate(&self) -> Result<()> {
+        if self.keep == 0 {
+            fs::remove_file(self.path())?;
+            return Ok(());
+        }
+        let _ = fs::remove_file(self.rotated(self.keep));
+        for n in (1..self.keep).rev() {
+            let from = self.rotated(n);
+            if from.exists() {
+                fs::rename(&from, self.rotated(n + 1))?;
+            }
+        }
+        fs::rename(self.path(), self.rotated(1))?;
+        Ok(())
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// PROCESS LOG
+// ═══════════════════════════════════════════════════════════════
+
+static LOG: OnceLock<Option<Mutex<RotatingLog>>> = OnceLock::new();
+
+/// Level in effect: `env` (HYLE_LOG), then `configured` (log_level); an env
+/// value of "off" turns a configured level off, an unknown one is ignored
+pub fn level(env: Option<&str>, configured: Option<LogLevel>) -> Option<LogLevel> {
+    match env.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) if v.eq_ignore_ascii_case("off") => None,
+        Some(v) => LogLevel::parse(v).ok().or(configured),
+        None => configured,
+    }
+}
+
+/// Directory logs are written to
+pub fn logs_dir() -> Result<PathBuf> {
+    Ok(crate::config::state_dir()?.join("logs"))
+}
+
+/// Turn on file logging for this process when a level is configured
+/// (first call wins)
+pub fn init() {
+    let configured = crate::config::Config::load().ok().and_then(|c| c.log_level);
+    let log = level(std::env::var("HYLE_LOG").ok().as_deref(), configured)
+        .and_then(|level| Some(Mutex::new(RotatingLog::new(&logs_dir().ok()?, level))));
+    let _ = LOG.set(log);
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TESTS
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("hyle_test_log_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_log_event_fields() {
+        let event = LogEvent::warn("hyle::ui", "rate limited, switching model")
+            .field("from", "a/model:free")
+            .field("to", "b/model:free")
+            .field("attempt", 2);
+        let json: serde_json::Value = serde_json::from_str(&event.to_line()).unwrap();
+        assert_eq!(json["level"], "warn");
+        assert_eq!(json["module"], "hyle::ui");
+        assert_eq!(json["message"], "rate limited, switching model");
+        assert_eq!(json["fields"]["from"], "a/model:free");
+        assert_eq!(json["fields"]["attempt"], 2);
+        assert!(json["ts"]
+            .as_str()
+            .unwrap()
+            .parse::<DateTime<Utc>>()
+            .is_ok());
+
+        // No fields, no empty object
+        let bare = LogEvent::error("hyle::client", "boom").to_line();
+        assert!(!bare.contains("fields"));
+    }
+
+    #[test]
+    fn test_rotation_at_size_cap() {
+        let dir = scratch("rotate");
+        let event = LogEvent::error("hyle::tools", "tool failed").field("tool", "bash");
+        let line = event.to_line().len() as u64 + 1;
+        // Two lines fit, the third rotates
+        let log = RotatingLog::new(&dir, LogLevel::Warn).with_limits(line * 2, 2);
+
+        log.write(&event).unwrap();
+        log.write(&event).unwrap();
+        assert_eq!(fs::metadata(log.path()).unwrap().len(), line * 2);
+        assert!(!log.rotated(1).exists());
+
+        log.write(&event).unwrap();
+        assert_eq!(fs::metadata(log.path()).unwrap().len(), line);
+        assert_eq!(fs::metadata(log.rotated(1)).unwrap().len(), line * 2);
+
+        // Older files shift along and the oldest past `keep` is dropped
+        for _ in 0..4 {
+            log.write(&event).unwrap();
+        }
+        assert!(log.rotated(2).exists());
+        assert!(!log.rotated(3).exists());
+
+        // Below the level: nothing written
+        let before = fs::metadata(log.path()).unwrap().len();
+        log.write(&LogEvent::new(LogLevel::Info, "hyle::ui", "noise"))
+            .unwrap();
+        assert_eq!(fs::metadata(log.path()).unwrap().len(), before);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_level_from_env_and_config() {
+        assert_eq!(level(None, None), None);
+        assert_eq!(level(None, Some(LogLevel::Warn)), Some(LogLevel::Warn));
+        assert_eq!(
+            level(Some("debug"), Some(LogLevel::Warn)),
+            Some(LogLevel::Debug)
+        );
+        assert_eq!(level(Some("off"), Some(LogLevel::Warn)), None);
+        assert_eq!(
+            level(Some("loud"), Some(LogLevel::Error)),
+            Some(LogLevel::Error)
+        );
+        assert!(LogLevel::Error < LogLevel::Debug);
+    }
+}
@@ -12,29 +12,46 @@
 //!   hyle --model <id> [PATHS...]  # specific model
 //!   hyle --task "..." [PATHS...]  # one-shot: produce diff, ask apply
 //!   hyle doctor                   # check config, key, network
+//!   hyle doctor --fix             # offer to repair what it finds
 //!   hyle models --refresh         # refresh models cache
 //!   hyle config set key <value>   # non-interactive config
+//!   hyle config validate          # check config.json without changing it
 
 mod agent;
+mod agent_loop;
+mod artifacts;
 mod backburner;
 mod benchmark;
 mod bootstrap;
 mod client;
 mod cognitive;
+mod compare;
+mod completion;
 mod config;
+mod decode;
 mod docs;
+mod doctor;
 mod environ;
 mod eval;
 mod git;
 mod github;
+mod guard;
 mod intake;
 mod intent;
+mod logfile;
+mod mentions;
+mod minimap;
 mod models;
 mod orchestrator;
 mod orchestrator_server;
+mod otel;
+mod overview;
 mod project;
 mod prompt;
 mod prompts;
+mod recipe;
+mod replay;
+mod response_cache;
 mod server;
 mod session;
 mod skills;
@@ -62,13 +79,26 @@ enum Command {
         paths: Vec<PathBuf>,
         resume: bool,
         handoff: bool,
+        auto: bool,
+        /// Skip the summary printed on exit
+        quiet: bool,
     },
     Benchmark {
-        model: Option<String>,
+        models: Vec<String>,
+        categories: Option<String>,
+        free: bool,
+        format: Option<String>,
+        baseline: Option<PathBuf>,
+        /// Prompts from this project instead of the synthetic samples
+        project: bool,
     },
     Task {
         task: String,
         paths: Vec<PathBuf>,
+        compare: Vec<String>,
+        verbosity: agent::Verbosity,
+        /// JSON-lines events on stdout, permission decisions on stdin
+        json: bool,
     },
     Backburner {
         paths: Vec<PathBuf>,
@@ -82,23 +112,55 @@ enum Command {
         projects_root: PathBuf,
         domain: String,
     },
-    Doctor,
+    Doctor {
+        fix: bool,
+    },
     Models {
         refresh: bool,
+        json: bool,
+        all: bool,
+        stats: bool,
     },
     ConfigSet {
         key: String,
         value: String,
     },
+    ConfigValidate,
     Sessions {
         list: bool,
         clean: bool,
+        export_all: Option<PathBuf>,
+        with_models: bool,
+        import: Option<PathBuf>,
+        /// Session id (or prefix) and optional output path
+        export_html: Option<(String, Option<PathBuf>)>,
+        since: Option<String>,
+        sort: Option<String>,
+        limit: Option<String>,
+        offset: Option<String>,
+    },
+    Recipe {
+        file: PathBuf,
+    },
+    Summarize,
+    Replay {
+        session: String,
+        model: Option<String>,
+        temperature: Option<String>,
+        seed: Option<String>,
+    },
+    Slash {
+        cmd: String,
     },
     Help,
 }
 
 fn parse_args() -> Command {
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // Global; read by resolve_work_dir
+    if let Some(i) = args.iter().position(|a| a == "--cwd") {
+        args.drain(i..(i + 2).min(args.len()));
+    }
 
     if args.is_empty() {
         return Command::Interactive {
@@ -108,6 +170,8 @@ fn parse_args() -> Command {
             paths: vec![],
             resume: true, // Default: resume last session
             handoff: false,
+            auto: false,
+            quiet: false,
         };
     }
 
@@ -116,30 +180,129 @@ fn parse_args() -> Command {
     }
 
     if args.first().map(|s| s.as_str()) == Some("doctor") {
-        return Command::Doctor;
+        return Command::Doctor {
+            fix: args.iter().any(|a| a == "--fix"),
+        };
     }
 
     if args.first().map(|s| s.as_str()) == Some("models") {
         return Command::Models {
             refresh: args.iter().any(|a| a == "--refresh"),
+            json: args.iter().any(|a| a == "--json"),
+            all: args.iter().any(|a| a == "--all"),
+            stats: args.iter().any(|a| a == "--stats"),
         };
     }
 
     if args.first().map(|s| s.as_str()) == Some("sessions") {
+        let value_after = |flag: &str| {
+            args.iter()
+                .position(|a| a == flag)
+                .and_then(|i| args.get(i + 1))
+                .map(PathBuf::from)
+        };
+        let string_after = |flag: &str| {
+            args.iter()
+                .position(|a| a == flag)
+                .and_then(|i| args.get(i + 1))
+                .cloned()
+        };
         return Command::Sessions {
             list: args.iter().any(|a| a == "--list" || a == "-l"),
             clean: args.iter().any(|a| a == "--clean"),
+            export_all: value_after("--export-all"),
+            with_models: args.iter().any(|a| a == "--with-models"),
+            import: value_after("--import"),
+            export_html: args
+                .iter()
+                .position(|a| a == "--export-html")
+                .and_then(|i| args.get(i + 1).map(|id| (i, id)))
+                .map(|(i, id)| {
+                    let path = args.get(i + 2).filter(|a| !a.starts_with("--"));
+                    (id.clone(), path.map(PathBuf::from))
+                }),
+            since: string_after("--since"),
+            sort: string_after("--sort"),
+            limit: string_after("--limit"),
+            offset: string_after("--offset"),
+        };
+    }
+
+    // One slash command from the shell: hyle /apply src/main.rs < fix.diff
+    if args.first().map(|s| s.starts_with('/')) == Some(true) {
+        return Command::Slash {
+            cmd: args.join(" "),
+        };
+    }
+
+    if args.first().map(|s| s.as_str()) == Some("summarize") {
+        return Command::Summarize;
+    }
+
+    if let Some(i) = args.iter().position(|a| a == "--replay") {
+        let value_after = |flags: &[&str]| {
+            args.iter()
+                .position(|a| flags.contains(&a.as_str()))
+                .and_then(|i| args.get(i + 1))
+                .cloned()
+        };
+        return Command::Replay {
+            session: args.get(i + 1).cloned().unwrap_or_default(),
+            model: value_after(&["--model", "-m"]),
+            temperature: value_after(&["--temperature", "--temp"]),
+            seed: value_after(&["--seed"]),
+        };
+    }
+
+    if args.first().map(|s| s.as_str()) == Some("recipe") {
+        return Command::Recipe {
+            file: args.get(1).map(PathBuf::from).unwrap_or_default(),
         };
     }
 
     // Check for benchmark command
-    if args.first().map(|s| s.as_str()) == Some("benchmark") {
-        let model = args
-            .iter()
-            .position(|a| a == "--model" || a == "-m")
-            .and_then(|i| args.get(i + 1))
-            .cloned();
-        return Command::Benchmark { model };
+    if let Some("benchmark" | "bench") = args.first().map(|s| s.as_str()) {
+        let mut models = Vec::new();
+        let mut categories = None;
+        let mut free = false;
+        let mut format = None;
+        let mut baseline = None;
+        let mut project = false;
+        let mut i = 1;
+        while i < args.len() {
+            match args[i].as_str() {
+                "self" => project = true,
+                "--models" | "--model" | "-m" => {
+                    i += 1;
+                    if let Some(list) = args.get(i) {
+                        models.extend(compare::parse_model_list(list));
+                    }
+                }
+                "--categories" | "-c" => {
+                    i += 1;
+                    categories = args.get(i).cloned();
+                }
+                "--free" | "-f" => free = true,
+                "--format" => {
+                    i += 1;
+                    format = args.get(i).cloned();
+                }
+                "--baseline" => {
+                    i += 1;
+                    baseline = args.get(i).map(PathBuf::from);
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        return Command::Benchmark {
+            models,
+            categories,
+            free,
+            format,
+            baseline,
+            project,
+        };
     }
 
     if args.first().map(|s| s.as_str()) == Some("config")
@@ -151,6 +314,12 @@ fn parse_args() -> Command {
         };
     }
 
+    if args.first().map(|s| s.as_str()) == Some("config")
+        && args.get(1).map(|s| s.as_str()) == Some("validate")
+    {
+        return Command::ConfigValidate;
+    }
+
     // Check for --backburner flag
     if args.iter().any(|a| a == "--backburner" || a == "-b") {
         let watch_docs = args.iter().any(|a| a == "--watch-docs");
@@ -212,11 +381,16 @@ fn parse_args() -> Command {
     let mut nonfree_only = false;
     let mut model = None;
     let mut task = None;
+    let mut compare = Vec::new();
     let mut paths = Vec::new();
     let mut resume = true;
     let mut trust_mode = false;
     let mut ask_mode = false;
     let mut handoff = false;
+    let mut auto = false;
+    let mut quiet = false;
+    let mut verbose = 0u8;
+    let mut json = false;
     let mut i = 0;
 
     while i < args.len() {
@@ -227,6 +401,11 @@ fn parse_args() -> Command {
             "--trust" | "-y" => trust_mode = true,
             "--ask" | "-a" => ask_mode = true,
             "--handoff" => handoff = true,
+            "--auto" => auto = true,
+            "--quiet" | "-q" => quiet = true,
+            "--verbose" | "-v" => verbose += 1,
+            "-vv" => verbose += 2,
+            "--json" => json = true,
             "--model" | "-m" => {
                 i += 1;
                 model = args.get(i).cloned();
@@ -235,6 +414,13 @@ fn parse_args() -> Command {
                 i += 1;
                 task = args.get(i).cloned();
             }
+            "--compare" => {
+                i += 1;
+                compare = args
+                    .get(i)
+                    .map(|s| compare::parse_model_list(s))
+                    .unwrap_or_default();
+            }
             s if !s.starts_with('-') => {
                 paths.push(PathBuf::from(s));
             }
@@ -261,6 +447,9 @@ fn parse_args() -> Command {
         Command::Task {
             task: task_str,
             paths,
+            compare,
+            verbosity: agent::Verbosity::from_flags(quiet, verbose),
+            json,
         }
     } else {
         Command::Interactive {
@@ -270,6 +459,8 @@ fn parse_args() -> Command {
             paths,
             resume,
             handoff,
+            auto,
+            quiet,
         }
     }
 }
@@ -285,16 +476,40 @@ USAGE:
     hyle --new                    # start fresh session
     hyle --handoff                # import Claude Code context
     hyle --model <id> [PATHS...]  # use specific model
+    hyle --auto                   # probe free models, use the best
     hyle --task "..." [PATHS...]  # autonomous agent mode (no TUI)
+    hyle --task "..." --compare m1,m2  # same prompt to several models
     hyle --backburner [PATHS...]  # background maintenance daemon
     hyle --serve [PORT]           # HTTP API server (default: 8420)
     hyle orchestrate              # project orchestrator (default: 8421)
-    hyle benchmark [--model <id>] # profile LLM for housekeeping tasks
+    hyle recipe <file.json>       # run a multi-step recipe of prompts/commands
+    hyle summarize                # write a repo overview to .hyle/overview.md
+    hyle --replay <session> [--model <id>] [--temperature <t>] [--seed <n>]
+                                  # re-send a session's prompts, diff the replies
+    hyle /<command> [ARGS]        # run one slash command (see /help)
+    echo "..." | hyle --task -    # read the task prompt from stdin
+    cat fix.diff | hyle /apply <file>  # apply a piped diff
+    hyle benchmark [--models m1,m2] [--categories security,docs] [--free]
+                   [--format text|json|markdown] [--baseline <result.json>]
+                                  # score models on housekeeping tasks
+    hyle benchmark self [...]     # same, on tasks drawn from this project's code
     hyle doctor                   # check config, key, network
+    hyle doctor --fix             # offer to repair what it finds
     hyle models --refresh         # refresh models cache
+    hyle models --all [--json]    # include paid models, sorted by cost
+    hyle models --stats [--json]  # models ranked by your observed quality
     hyle sessions --list          # list saved sessions
+    hyle sessions --since 7d --sort tokens --limit 20 --offset 20
+                                  # filter (s/m/h/d/w), sort (recent, tokens,
+                                  # messages) and page the list; --limit 0 for all
     hyle sessions --clean         # clean old sessions
+    hyle sessions --export-all <dir> [--with-models]
+                                  # bundle every session into one archive file
+    hyle sessions --import <file> # restore sessions from an archive
+    hyle sessions --export-html <id> [path]
+                                  # one session as a shareable HTML page
     hyle config set key <value>   # set config value
+    hyle config validate          # check config for errors (exit 1 if any)
 
 FLAGS:
     -f, --free              Only show free models in picker
@@ -302,7 +517,14 @@ FLAGS:
     -n, --new               Start new session (don't resume)
     --handoff               Import context from Claude Code session
     -m, --model <id>        Use specific model ID
+    --auto                  Pick the best free model by a quick probe (cached)
     -t, --task <text>       One-shot task mode
+    -v, -vv, --verbose      With --task: show tool arguments and full output (-vv: parsed calls)
+    -q, --quiet             With --task: print only errors and the final response;
+                            interactive: skip the summary printed on exit
+    --compare <m1,m2,...>   With --task: compare models side-by-side
+    --json                  With --task: JSON-lines events on stdout; calls needing
+                            confirmation read a permission_decision line from stdin
     -b, --backburner        Run background maintenance daemon
     -s, --serve [port]      HTTP API server mode
     benchmark               Profile LLM on housekeeping tasks
@@ -312,15 +534,28 @@ FLAGS:
         -d, --domain <dom>  Domain for subdomains (default: hyperstitious.org)
     -y, --trust             Trust mode: auto-approve all tool operations
     -a, --ask               Ask mode: confirm before write/execute/git ops
+    --no-cache              Skip the response cache (see response_cache in config)
+    --cwd <path>            Work in <path>: tools, project detection and git
     -h, --help              Show this help
 
 CONFIG:
     ~/.config/hyle/config.json    API key, preferences
     ~/.cache/hyle/models.json     Cached model list
-    ~/.local/state/hyle/sessions/ Session history
+    ~/.local/state/hyle/sessions/ Session history (sessions_dir in config)
+    ~/.local/state/hyle/logs/     JSON-lines log, when log_level is set
 
 ENVIRONMENT:
     OPENROUTER_API_KEY              Override API key from config
+    OTEL_EXPORTER_OTLP_ENDPOINT     Export request/tool spans via OTLP (otel_endpoint in config)
+    HYLE_SESSIONS_DIR               Store sessions here instead (overrides sessions_dir)
+    HYLE_IDLE_TIMEOUT=<secs>        Save and exit the TUI or --serve after this long idle
+                                    (overrides idle_timeout_secs; 0 turns it off)
+    HYLE_LOG=<level>                Log error|warn|info|debug to the log file (overrides
+                                    log_level; off turns it off)
+    HYLE_SERVER_TOKEN               Require this bearer token on every --serve endpoint
+                                    (the web UI takes it as /?token=...)
+    HYLE_RAW_OUTPUT=base64          Attach raw bytes of non-UTF-8 command output as base64
+    NO_COLOR                        Plain ASCII output for task, doctor and config (also when piped)
 
 CONTROLS (interactive mode):
     Enter      Send prompt
@@ -352,8 +587,13 @@ BACKBURNER MODE:
 #[tokio::main]
 async fn main() -> Result<()> {
     // Set up tmux integration
-    let work_dir = std::env::current_dir().unwrap_or_default();
+    let work_dir = resolve_work_dir()?;
     tmux::setup(&work_dir);
+    // A replay wants fresh replies, not the cached originals
+    client::init_response_cache(std::env::args().any(|a| a == "--no-cache" || a == "--replay"));
+    client::init_request_options();
+    otel::init();
+    logfile::init();
 
     // Ensure cleanup on exit
     let result = run_command().await;
@@ -370,13 +610,83 @@ async fn run_command() -> Result<()> {
             print_help();
             Ok(())
         }
-        Command::Doctor => run_doctor().await,
-        Command::Models { refresh } => run_models(refresh).await,
-        Command::Sessions { list, clean } => run_sessions(list, clean),
+        Command::Doctor { fix } => run_doctor(fix).await,
+        Command::Models {
+            refresh,
+            json,
+            all,
+            stats,
+        } => {
+            if stats {
+                run_model_stats(json)
+            } else {
+                run_models(refresh, json, all).await
+            }
+        }
+        Command::Sessions {
+            list,
+            clean,
+            export_all,
+            with_models,
+            import,
+            export_html,
+            since,
+            sort,
+            limit,
+            offset,
+        } => {
+            if let Some((id, path)) = export_html {
+                run_sessions_export_html(&id, path.as_deref())
+            } else if let Some(dir) = export_all {
+                run_sessions_export(&dir, with_models)
+            } else if let Some(archive) = import {
+                run_sessions_import(&archive)
+            } else {
+                let query = session::SessionQuery::from_flags(
+                    since.as_deref(),
+                    sort.as_deref(),
+                    limit.as_deref(),
+                    offset.as_deref(),
+                )?;
+                run_sessions(list, clean, &query)
+            }
+        }
+        Command::Recipe { file } => {
+            tmux::set_status("recipe");
+            let result = run_recipe(&file).await;
+            tmux::task_complete("Recipe", result.is_ok());
+            result
+        }
+        Command::Summarize => run_summarize().await,
+        Command::Replay {
+            session,
+            model,
+            temperature,
+            seed,
+        } => run_replay(&session, model, temperature.as_deref(), seed.as_deref()).await,
+        Command::Slash { cmd } => run_slash(&cmd),
         Command::ConfigSet { key, value } => run_config_set(&key, &value),
-        Command::Task { task, paths } => {
+        Command::ConfigValidate => run_config_validate(),
+        Command::Task {
+            task,
+            paths,
+            compare,
+            verbosity,
+            json,
+        } => {
             tmux::set_status("task");
-            let result = run_task(&task, &paths).await;
+            let task = {
+                use std::io::IsTerminal;
+                let stdin = std::io::stdin();
+                skills::task_from_arg(&task, stdin.is_terminal(), stdin.lock())?
+            };
+            let result = if json {
+                run_task_json(&task, &paths).await
+            } else if compare.is_empty() {
+                run_task(&task, &paths, verbosity).await
+            } else {
+                run_compare(&task, &paths, &compare).await
+            };
             tmux::task_complete("Task", result.is_ok());
             result
         }
@@ -386,7 +696,7 @@ async fn run_command() -> Result<()> {
         }
         Command::Server { port } => {
             tmux::set_status("serve");
-            server::run_server(port).await
+            server::run_server(port, resolve_work_dir()?).await
         }
         Command::Orchestrate {
             port,
@@ -396,9 +706,24 @@ async fn run_command() -> Result<()> {
             tmux::set_status("orch");
             orchestrator_server::run_orchestrator(port, projects_root, domain).await
         }
-        Command::Benchmark { model } => {
+        Command::Benchmark {
+            models,
+            categories,
+            free,
+            format,
+            baseline,
+            project,
+        } => {
             tmux::set_status("bench");
-            run_benchmark(model.as_deref()).await
+            run_benchmark(
+                &models,
+                categories.as_deref(),
+                free,
+                format.as_deref(),
+                baseline.as_deref(),
+                project,
+            )
+            .await
         }
         Command::Interactive {
             free_only,
@@ -407,7 +732,21 @@ async fn run_command() -> Result<()> {
             paths,
             resume,
             handoff,
-        } => run_interactive(free_only, nonfree_only, model, paths, resume, handoff).await,
+            auto,
+            quiet,
+        } => {
+            run_interactive(
+                free_only,
+                nonfree_only,
+                model,
+                paths,
+                resume,
+                handoff,
+                auto,
+                quiet,
+            )
+            .await
+        }
     }
 }
 
@@ -415,39 +754,60 @@ async fn run_command() -> Result<()> {
 // COMMANDS
 // ═══════════════════════════════════════════════════════════════
 
-async fn run_doctor() -> Result<()> {
+/// Directory to work in: `--cwd <path>`, else the process cwd
+///
+/// Passed explicitly to the agent, tools and project detection rather
+/// than applied with `set_current_dir`.
+fn resolve_work_dir() -> Result<PathBuf> {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(i) = args.iter().position(|a| a == "--cwd") else {
+        return Ok(std::env::current_dir()?);
+    };
+    let dir = args
+        .get(i + 1)
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow::anyhow!("--cwd needs a directory"))?;
+    if !dir.is_dir() {
+        anyhow::bail!("--cwd: {} is not a directory", dir.display());
+    }
+    Ok(std::fs::canonicalize(&dir)?)
+}
+
+async fn run_doctor(fix: bool) -> Result<()> {
+    let style = agent::OutputStyle::from_env();
+    let say = |line: String| println!("{}", style.apply(&line));
     println!("hyle doctor\n");
 
     // Check config
     let cfg = config::Config::load()?;
-    println!(
+    say(format!(
         "[{}] Config: {}",
         if cfg.api_key.is_some() { "✓" } else { "✗" },
         config::config_path()?.display()
-    );
+    ));
 
     // Check API key
     let has_key = cfg.api_key.is_some() || std::env::var("OPENROUTER_API_KEY").is_ok();
-    println!(
+    say(format!(
         "[{}] API key: {}",
         if has_key { "✓" } else { "✗" },
         if has_key { "configured" } else { "missing" }
-    );
+    ));
 
     // Check models cache
     let models_path = config::cache_dir()?.join("models.json");
     let models_ok = models_path.exists();
-    println!(
+    say(format!(
         "[{}] Models cache: {}",
         if models_ok { "✓" } else { "✗" },
         models_path.display()
-    );
+    ));
 
     // Check tmux
     let in_tmux = tmux::is_tmux();
     let width = tmux::term_width();
     let wide = tmux::is_wide();
-    println!(
+    say(format!(
         "[{}] Tmux: {} ({}cols, {})",
         if in_tmux { "✓" } else { "○" },
         if in_tmux { "detected" } else { "not in tmux" },
@@ -457,43 +817,143 @@ async fn run_doctor() -> Result<()> {
         } else {
             "narrow"
         }
-    );
+    ));
 
     // Check project
-    let cwd = std::env::current_dir()?;
+    let cwd = resolve_work_dir()?;
     if let Some(p) = project::Project::detect(&cwd) {
-        println!(
+        say(format!(
             "[✓] Project: {} ({:?}, {} files, {} lines)",
             p.name,
             p.project_type,
             p.files.len(),
             p.total_lines()
-        );
+        ));
     } else {
-        println!("[○] Project: not detected");
+        say("[○] Project: not detected".into());
     }
 
-    // Check network
-    print!("[?] Network: checking...");
-    match client::check_connectivity().await {
-        Ok(()) => println!("\r[✓] Network: connected         "),
-        Err(e) => println!("\r[✗] Network: {}", e),
+    // Check network; the progress line is overwritten, so only on a terminal
+    let fancy = style == agent::OutputStyle::Fancy;
+    if fancy {
+        print!("[?] Network: checking...");
+    }
+    let network = match client::check_connectivity().await {
+        Ok(()) => "[✓] Network: connected".to_string(),
+        Err(e) => format!("[✗] Network: {}", e),
+    };
+    if fancy {
+        say(format!("\r{:<40}", network));
+    } else {
+        say(network);
     }
 
+    if fix {
+        run_doctor_fix().await?;
+    }
     Ok(())
 }
 
-async fn run_models(refresh: bool) -> Result<()> {
+/// `doctor --fix`: offer each repair, asking on stdin before making it
+async fn run_doctor_fix() -> Result<()> {
+    let repairs = doctor::Findings::gather()?.repairs();
+    println!();
+    if repairs.is_empty() {
+        println!("Nothing to fix");
+        return Ok(());
+    }
+
+    let confirm = |question: &str| {
+        use std::io::Write;
+        print!("{} [y/N] ", question);
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer).is_ok()
+            && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+    };
+    let save_key = || {
+        println!("Get a free key at: https://openrouter.ai/keys");
+        let key = ui::prompt_api_key()?;
+        if key.trim().is_empty() {
+            anyhow::bail!("no key entered");
+        }
+        let mut cfg = config::Config::load()?;
+        cfg.api_key = Some(key.trim().to_string());
+        cfg.save()?;
+        config::config_path()
+    };
+    // The key may have been saved a moment ago
+    let fetch = || async { client::fetch_models(&config::get_api_key()?).await };
+
+    let report = doctor::fix(&repairs, confirm, save_key, fetch).await;
+    println!("\n{}", report.summary());
+    Ok(())
+}
+
+/// Leaderboard of models by the quality observed across sessions
+fn run_model_stats(json: bool) -> Result<()> {
+    let board = eval::Leaderboard::load();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&board)?);
+        return Ok(());
+    }
+    if board.models.is_empty() {
+        println!("No model stats yet: they're recorded as you use hyle interactively.");
+        return Ok(());
+    }
+    println!(
+        "\nModels by observed quality ({}):",
+        eval::Leaderboard::path()?.display()
+    );
+    println!("  {:<49} {:>5} {:>6}  LAST USED", "MODEL", "QUAL", "RESP");
+    for line in board.render() {
+        println!("  {}", line);
+    }
+    Ok(())
+}
+
+async fn run_models(refresh: bool, json: bool, all: bool) -> Result<()> {
     let api_key = config::get_api_key()?;
 
     if refresh {
-        println!("Fetching models from OpenRouter...");
+        // Keep stdout clean for --json consumers
+        eprintln!("Fetching models from OpenRouter...");
         let models = client::fetch_models(&api_key).await?;
         models::save_cache(&models)?;
-        println!("Cached {} models", models.len());
+        eprintln!("Cached {} models", models.len());
     }
 
     let models = models::load_or_fetch(&api_key).await?;
+
+    if json {
+        let selected: Vec<&models::Model> = if all {
+            models::sort_by_cost(&models)
+        } else {
+            models.iter().filter(|m| m.is_free()).collect()
+        };
+        println!("{}", serde_json::to_string_pretty(&selected)?);
+        return Ok(());
+    }
+
+    if all {
+        let sorted = models::sort_by_cost(&models);
+        println!("\nAll models ({}), cheapest first:", sorted.len());
+        println!(
+            "  {:<50} {:>8} {:>10} {:>10}",
+            "ID", "CTX", "PROMPT", "COMPL"
+        );
+        for m in sorted {
+            println!(
+                "  {:<50} {:>7}k {:>10} {:>10}",
+                m.id,
+                m.context_length / 1000,
+                format_price(m.pricing_prompt),
+                format_price(m.pricing_completion)
+            );
+        }
+        return Ok(());
+    }
+
     let free: Vec<_> = models.iter().filter(|m| m.is_free()).collect();
 
     println!("\nFree models ({}):", free.len());
@@ -507,7 +967,15 @@ async fn run_models(refresh: bool) -> Result<()> {
     Ok(())
 }
 
-fn run_sessions(_list: bool, clean: bool) -> Result<()> {
+fn format_price(price: f64) -> String {
+    if price == 0.0 {
+        "free".to_string()
+    } else {
+        format!("{:.6}", price)
+    }
+}
+
+fn run_sessions(_list: bool, clean: bool, query: &session::SessionQuery) -> Result<()> {
     if clean {
         let removed = session::cleanup_sessions(10)?;
         println!("Cleaned up {} old sessions", removed);
@@ -515,14 +983,14 @@ fn run_sessions(_list: bool, clean: bool) -> Result<()> {
     }
 
     // Default: list sessions
-    let sessions = session::list_sessions()?;
-    if sessions.is_empty() {
+    let (sessions, total) = query.apply(session::list_sessions()?, chrono::Utc::now());
+    if total == 0 {
         println!("No sessions found");
         return Ok(());
     }
 
-    println!("Sessions ({}):\n", sessions.len());
-    for s in sessions.iter().take(10) {
+    println!("Sessions ({}):\n", total);
+    for s in &sessions {
         let age = chrono::Utc::now() - s.updated_at;
         let age_str = if age.num_hours() < 1 {
             format!("{}m ago", age.num_minutes())
@@ -533,45 +1001,180 @@ fn run_sessions(_list: bool, clean: bool) -> Result<()> {
         };
 
         println!(
-            "  {} | {} | {} msgs | {} tokens | {}",
+            "  {} | {} | {} msgs | {} tokens | {}{}",
             s.id,
             s.model.split('/').next_back().unwrap_or(&s.model),
             s.message_count,
             s.total_tokens,
             age_str,
+            s.parent_id
+                .as_ref()
+                .map(|p| format!(" | fork of {}", p))
+                .unwrap_or_default(),
         );
     }
 
-    if sessions.len() > 10 {
-        println!("  ... and {} more", sessions.len() - 10);
+    let shown = query.offset + sessions.len();
+    if shown < total {
+        println!(
+            "  ... and {} more (--offset {} for the next page)",
+            total - shown,
+            shown
+        );
     }
 
     Ok(())
 }
 
+fn run_sessions_export(dir: &std::path::Path, with_models: bool) -> Result<()> {
+    let root = session::sessions_dir()?;
+    let mut archive = session::SessionArchive::collect_from(&root)?;
+    if with_models {
+        let models_path = config::cache_dir()?.join("models.json");
+        archive.models_cache = std::fs::read_to_string(&models_path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok());
+        if archive.models_cache.is_none() {
+            println!("No models cache to include ({})", models_path.display());
+        }
+    }
+    let path = archive.write_to_dir(dir)?;
+    println!(
+        "Exported {} sessions from {} to {}",
+        archive.sessions.len(),
+        root.display(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn run_sessions_export_html(query: &str, path: Option<&std::path::Path>) -> Result<()> {
+    let id = session::resolve_session_id(query)?;
+    let session = session::Session::load(&id)?;
+    let path = path
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("hyle-session-{}.html", id)));
+    std::fs::write(&path, session.export_html())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!(
+        "Exported session {} ({} messages) to {}",
+        id,
+        session.messages.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn run_sessions_import(path: &std::path::Path) -> Result<()> {
+    let archive = session::SessionArchive::load(path)?;
+    let root = session::sessions_dir()?;
+    let report = archive.import_into(&root)?;
+    println!(
+        "Imported {} sessions into {} ({} already present)",
+        report.imported.len(),
+        root.display(),
+        report.skipped.len()
+    );
+    for (old, new) in &report.renamed {
+        println!("  {} -> {} (id already in use)", old, new);
+    }
+
+    // Only fills a missing cache; a local one is as fresh or fresher
+    if let Some(cache) = &archive.models_cache {
+        let models_path = config::cache_dir()?.join("models.json");
+        if !models_path.exists() {
+            if let Some(parent) = models_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&models_path, serde_json::to_string_pretty(cache)?)?;
+            println!("Restored models cache to {}", models_path.display());
+        }
+    }
+    Ok(())
+}
+
 fn run_config_set(key: &str, value: &str) -> Result<()> {
     let mut cfg = config::Config::load()?;
+    cfg.set(key, value)?;
+    cfg.save()?;
 
     match key {
         "key" | "api_key" | "openrouter.key" => {
-            cfg.api_key = Some(value.to_string());
-            cfg.save()?;
             println!("API key saved to {}", config::config_path()?.display());
+            if let Some(config::Finding::Warning(w)) = config::check_api_key(Some(value)) {
+                println!("Warning: {}", w);
+            }
         }
-        "model" => {
-            cfg.default_model = Some(value.to_string());
-            cfg.save()?;
-            println!("Default model set to: {}", value);
+        "model" | "default_model" => println!("Default model set to: {}", value),
+        _ => println!("{} set to: {}", key, value),
+    }
+    Ok(())
+}
+
+/// Run one slash command outside the TUI, completing `/apply` from stdin
+fn run_slash(cmd: &str) -> Result<()> {
+    use std::io::IsTerminal;
+
+    let stdin = std::io::stdin();
+    let cmd = skills::with_piped_diff(cmd, stdin.is_terminal(), stdin.lock())?;
+    let cwd = std::env::current_dir()?;
+    let project_type =
+        project::Project::detect(&cwd).map(|p| project::format_project_type(&p.project_type));
+
+    match skills::execute_slash_command(&cmd, project_type) {
+        Some(r) if r.action.is_some() => {
+            anyhow::bail!("{} only works in the interactive TUI", cmd)
+        }
+        Some(r) => {
+            println!("{}", r.output);
+            if !r.success {
+                anyhow::bail!("Command failed");
+            }
+            Ok(())
+        }
+        None => anyhow::bail!("Unknown command: {}", cmd),
+    }
+}
+
+/// Check config.json and the models cache without changing anything
+fn run_config_validate() -> Result<()> {
+    let path = config::config_path()?;
+    let env_key = std::env::var("OPENROUTER_API_KEY").ok();
+
+    let mut findings = match std::fs::read_to_string(&path) {
+        Ok(content) => config::validate_config(&content, env_key.as_deref()),
+        Err(_) => {
+            println!("No config file at {} (using defaults)", path.display());
+            config::check_api_key(env_key.as_deref())
+                .into_iter()
+                .collect()
         }
-        _ => {
-            anyhow::bail!("Unknown config key: {}. Valid keys: key, model", key);
+    };
+    if !config::cache_dir()?.join("models.json").exists() {
+        findings.push(config::Finding::Warning(
+            "No models cache: run `hyle models --refresh`".into(),
+        ));
+    }
+
+    let style = agent::OutputStyle::from_env();
+    println!("Checked {}", path.display());
+    for finding in &findings {
+        match finding {
+            config::Finding::Error(e) => println!("{}", style.apply(&format!("[✗] {}", e))),
+            config::Finding::Warning(w) => println!("[!] {}", w),
         }
     }
+
+    let errors = findings.iter().filter(|f| f.is_error()).count();
+    if errors > 0 {
+        anyhow::bail!("{} error(s) in config", errors);
+    }
+    println!("{}", style.apply("[✓] Config OK"));
     Ok(())
 }
 
-async fn run_task(task: &str, paths: &[PathBuf]) -> Result<()> {
-    use agent::{AgentCore, AgentEvent};
+async fn run_task(task: &str, paths: &[PathBuf], verbosity: agent::Verbosity) -> Result<()> {
+    use agent::{AgentCore, AgentEvent, Verbosity};
     use std::io::Write;
 
     let api_key = config::get_api_key()?;
@@ -583,87 +1186,65 @@ async fn run_task(task: &str, paths: &[PathBuf]) -> Result<()> {
         .or(cfg.default_model.clone())
         .unwrap_or_else(|| "meta-llama/llama-3.2-3b-instruct:free".to_string());
 
-    let work_dir = std::env::current_dir()?;
+    let work_dir = resolve_work_dir()?;
 
-    println!("Task: {}", task);
-    println!("Model: {}", model);
-    println!("Mode: Agent (autonomous tool execution)");
-    if !paths.is_empty() {
-        println!("Paths: {:?}", paths);
-    }
-    println!();
-
-    // Read file contents if paths provided
-    let mut context = String::new();
-    for path in paths {
-        if path.exists() {
-            let content = std::fs::read_to_string(path)
-                .with_context(|| format!("Failed to read {}", path.display()))?;
-            context.push_str(&format!("\n--- {} ---\n{}\n", path.display(), content));
+    let quiet = verbosity == Verbosity::Quiet;
+    let style = agent::OutputStyle::from_env();
+    if !quiet {
+        println!("Task: {}", task);
+        println!("Model: {}", model);
+        println!("Mode: Agent (autonomous tool execution)");
+        if !paths.is_empty() {
+            println!("Paths: {:?}", paths);
         }
+        println!();
     }
 
-    // Build prompt
-    let prompt = if context.is_empty() {
-        task.to_string()
-    } else {
-        format!("Given these files:\n{}\n\nTask: {}", context, task)
-    };
+    // Leave half the window for the conversation
+    let budget = models::get_context_window(&model) as usize / 2;
+    let prompt = build_task_prompt(task, paths, budget)?;
 
     // Run agent with event printing
     let agent = AgentCore::new(&api_key, &model, &work_dir);
 
     let result = agent
         .run_with_callback(&prompt, |event| {
-            match event {
-                AgentEvent::Token(t) => {
-                    print!("{}", t);
+            match (event, agent::render_event(event, verbosity)) {
+                (AgentEvent::Error(_), Some(text)) => eprint!("{}", text),
+                // The model's own text is printed as it came
+                (AgentEvent::Token(_), Some(text)) => {
+                    print!("{}", text);
                     let _ = std::io::stdout().flush();
                 }
-                AgentEvent::Status(s) => {
-                    println!("\n[{}]", s);
-                }
-                AgentEvent::ToolExecuting { name, args: _ } => {
-                    println!("\n  → {}", name);
-                }
-                AgentEvent::ToolResult {
-                    name,
-                    success,
-                    output,
-                } => {
-                    let icon = if *success { "✓" } else { "✗" };
-                    println!("  {} {}", icon, name);
-                    // Show first few lines of output
-                    for line in output.lines().take(3) {
-                        println!("    {}", line);
-                    }
-                    if output.lines().count() > 3 {
-                        println!("    ...");
-                    }
-                }
-                AgentEvent::IterationComplete {
-                    iteration,
-                    tool_count,
-                } => {
-                    println!("\n─── Iteration {} ({} tools) ───\n", iteration, tool_count);
-                }
-                AgentEvent::Complete {
-                    iterations,
-                    success,
-                } => {
-                    let status = if *success { "completed" } else { "stopped" };
-                    println!("\n\n[Agent {} after {} iterations]", status, iterations);
-                }
-                AgentEvent::Error(e) => {
-                    eprintln!("\n[Error: {}]", e);
+                (_, Some(text)) => {
+                    print!("{}", style.apply(&text));
+                    let _ = std::io::stdout().flush();
                 }
-                AgentEvent::ToolCallsParsed(_) => {}
+                (_, None) => {}
             }
         })
         .await;
 
+    // Scripts get the final response alone on stdout
+    if quiet {
+        println!("{}", result.final_response.trim());
+        if let Some(question) = result.needs_input {
+            eprintln!("Task needs input: {}", question);
+        } else if let Some(err) = result.error {
+            eprintln!("Task failed: {}", err);
+        }
+        return Ok(());
+    }
+
     if result.success {
         println!("\nTask completed successfully.");
+    } else if let Some(question) = result.needs_input {
+        println!("\nTask needs input: {}", question);
+        if question.starts_with("Approve this plan") {
+            println!("Run with --trust to approve plans, or set plan_first to false");
+        } else {
+            println!("Answer it in the task, or set ask_user_default in config.json");
+        }
     } else if let Some(err) = result.error {
         println!("\nTask failed: {}", err);
     }
@@ -676,6 +1257,288 @@ async fn run_task(task: &str, paths: &[PathBuf]) -> Result<()> {
     Ok(())
 }
 
+/// `--task --json`: every agent event as a JSON line on stdout, ending with
+/// a `result` line. Calls that need confirmation are gated: a
+/// `permission_request` is emitted and the matching `permission_decision`
+/// line is read from stdin (`--trust` allows them all).
+async fn run_task_json(task: &str, paths: &[PathBuf]) -> Result<()> {
+    use agent::{AgentConfig, AgentCore, Approver, ToolGate};
+    use std::io::Write;
+
+    let api_key = config::get_api_key()?;
+    let cfg = config::Config::load()?;
+    let model = std::env::var("HYLE_MODEL")
+        .ok()
+        .or(cfg.default_model.clone())
+        .unwrap_or_else(|| "meta-llama/llama-3.2-3b-instruct:free".to_string());
+    let work_dir = resolve_work_dir()?;
+
+    let budget = models::get_context_window(&model) as usize / 2;
+    let prompt = build_task_prompt(task, paths, budget)?;
+
+    let gate =
+        ToolGate::new(&work_dir, cfg.clone(), cfg.trust_mode).with_approver(Approver::stdin());
+    let agent = AgentCore::new(&api_key, &model, &work_dir)
+        .with_config(AgentConfig::default().with_gate(gate));
+
+    let result = agent
+        .run_with_callback(&prompt, |event| {
+            println!("{}", agent::event_json(event));
+            let _ = std::io::stdout().flush();
+        })
+        .await;
+
+    println!(
+        "{}",
+        serde_json::json!({
+            "type": "result",
+            "model": model,
+            "success": result.success,
+            "response": result.final_response,
+            "iterations": result.iterations,
+            "tool_calls": result.tool_calls_executed,
+            "error": result.error,
+            "needs_input": result.needs_input,
+        })
+    );
+    Ok(())
+}
+
+/// Ask the model for a repo overview and cache it in .hyle/overview.md
+async fn run_summarize() -> Result<()> {
+    let api_key = config::get_api_key()?;
+    let cfg = config::Config::load()?;
+    let model = std::env::var("HYLE_MODEL")
+        .ok()
+        .or(cfg.default_model.clone())
+        .unwrap_or_else(|| "meta-llama/llama-3.2-3b-instruct:free".to_string());
+    let cwd = resolve_work_dir()?;
+    let root = project::Project::detect(&cwd)
+        .map(|p| p.root)
+        .unwrap_or(cwd);
+
+    eprintln!("Summarizing {} with {}...", root.display(), model);
+    let path = overview::summarize(&root, |prompt| async move {
+        client::chat_completion_simple(&api_key, &model, &prompt, 2048).await
+    })
+    .await?;
+
+    println!("{}", overview::load(&root).unwrap_or_default());
+    eprintln!(
+        "Saved to {} (used as context in future sessions)",
+        path.display()
+    );
+    Ok(())
+}
+
+/// Send a saved session's prompts again and diff the new replies
+async fn run_replay(
+    query: &str,
+    model: Option<String>,
+    temperature: Option<&str>,
+    seed: Option<&str>,
+) -> Result<()> {
+    if query.is_empty() || query.starts_with('-') {
+        anyhow::bail!(
+            "Usage: hyle --replay <session> [--model <id>] [--temperature <t>] [--seed <n>]"
+        );
+    }
+    let id = session::resolve_session_id(query)?;
+    let original = session::Session::load(&id)?;
+    let turns = replay::turns(&original.messages);
+    if turns.is_empty() {
+        anyhow::bail!("Session {} has no prompts to replay", id);
+    }
+
+    let api_key = config::get_api_key()?;
+    let cfg = config::Config::load()?;
+    let model = model
+        .or_else(|| std::env::var("HYLE_MODEL").ok())
+        .unwrap_or_else(|| original.meta.model.clone());
+    let mut params = cfg.sampling.clone();
+    if let Some(t) = temperature {
+        params.set("temperature", t)?;
+    }
+    if let Some(s) = seed {
+        params.set("seed", s)?;
+    }
+
+    eprintln!(
+        "Replaying {} prompt(s) from {} with {} ({})",
+        turns.len(),
+        id,
+        model,
+        params.display()
+    );
+    let preamble = replay::preamble(&original.messages);
+    let result = replay::run(preamble, &turns, |history, prompt| {
+        let (key, model, params) = (api_key.clone(), model.clone(), params.clone());
+        async move { client::complete_with_history(&key, &model, &prompt, &history, params).await }
+    })
+    .await;
+
+    let mut fresh = session::Session::new(&model)?;
+    fresh.meta.description = Some(format!("Replay of {}", id));
+    result.record(&mut fresh)?;
+    println!("{}", result.report());
+    eprintln!("Transcript saved as session {}", fresh.meta.id);
+    match result.error {
+        Some(e) => anyhow::bail!("Replay stopped at {}", e),
+        None => Ok(()),
+    }
+}
+
+/// Run a recipe's steps through the agent and slash commands
+async fn run_recipe(file: &std::path::Path) -> Result<()> {
+    use agent::AgentCore;
+    use recipe::{StepAction, StepOutcome, StepStatus};
+
+    if file.as_os_str().is_empty() {
+        anyhow::bail!("Usage: hyle recipe <file.json>");
+    }
+    let recipe = recipe::Recipe::load(file)?;
+
+    let api_key = config::get_api_key()?;
+    let cfg = config::Config::load()?;
+    let model = std::env::var("HYLE_MODEL")
+        .ok()
+        .or(cfg.default_model.clone())
+        .unwrap_or_else(|| "meta-llama/llama-3.2-3b-instruct:free".to_string());
+    let work_dir = resolve_work_dir()?;
+    let project_type =
+        project::Project::detect(&work_dir).map(|p| project::format_project_type(&p.project_type));
+
+    println!("Recipe: {} ({} steps)", recipe.name, recipe.steps.len());
+    println!("Model: {}", model);
+    println!();
+
+    let total = recipe.steps.len();
+    let report = recipe::run(&recipe, |i, step| {
+        println!("[{}/{}] {}", i + 1, total, step.label(i));
+        let action = match step.action() {
+            StepAction::Prompt(prompt) => Ok(prompt.to_string()),
+            StepAction::Command(cmd) => Err(cmd.to_string()),
+        };
+        let agent = AgentCore::new(&api_key, &model, &work_dir);
+        async move {
+            match action {
+                Ok(prompt) => {
+                    let result = agent.run_blocking(&prompt).await;
+                    let output = match result.error {
+                        Some(e) => format!("{}\n\nError: {}", result.final_response, e),
+                        None => result.final_response,
+                    };
+                    StepOutcome {
+                        success: result.success,
+                        output,
+                    }
+                }
+                Err(cmd) => match skills::execute_slash_command(&cmd, project_type) {
+                    Some(r) if r.action.is_some() => StepOutcome {
+                        success: false,
+                        output: format!("{} only works in the interactive TUI", cmd),
+                    },
+                    Some(r) => StepOutcome {
+                        success: r.success,
+                        output: r.output,
+                    },
+                    None => StepOutcome {
+                        success: false,
+                        output: format!("Unknown command: {}", cmd),
+                    },
+                },
+            }
+        }
+    })
+    .await;
+
+    println!();
+    for step in &report.steps {
+        let icon = match step.status {
+            StepStatus::Passed => "✓",
+            StepStatus::Failed => "✗",
+            StepStatus::Skipped => "-",
+        };
+        println!("  {} {} ({}ms)", icon, step.name, step.duration_ms);
+    }
+    match report.save() {
+        Ok(path) => println!("\nReport: {}", path.display()),
+        Err(e) => eprintln!("\nFailed to save report: {}", e),
+    }
+
+    if report.success {
+        Ok(())
+    } else {
+        anyhow::bail!("Recipe '{}' failed", recipe.name)
+    }
+}
+
+/// Build the task prompt, capping file context at `budget` tokens
+fn build_task_prompt(task: &str, paths: &[PathBuf], budget: usize) -> Result<String> {
+    let path_refs: Vec<&std::path::Path> = paths.iter().map(|p| p.as_path()).collect();
+    let budgeted = tools::read_files_context_budgeted(&path_refs, budget)?;
+    if !budgeted.trimmed.is_empty() {
+        eprintln!(
+            "Warning: context over budget (~{} tokens), trimmed: {}",
+            budget,
+            budgeted.trimmed.join(", ")
+        );
+    }
+
+    if budgeted.text.is_empty() {
+        Ok(task.to_string())
+    } else {
+        Ok(format!(
+            "Given these files:\n{}\n\nTask: {}",
+            budgeted.text, task
+        ))
+    }
+}
+
+async fn run_compare(task: &str, paths: &[PathBuf], models: &[String]) -> Result<()> {
+    let api_key = config::get_api_key()?;
+
+    // Budget for the smallest window so every model gets the same prompt
+    let budget = models
+        .iter()
+        .map(|m| models::get_context_window(m) as usize / 2)
+        .min()
+        .unwrap_or(4096);
+    let prompt = build_task_prompt(task, paths, budget)?;
+
+    println!("Task: {}", task);
+    println!("Comparing: {}", models.join(", "));
+    println!();
+
+    let results = compare::compare_models(
+        models,
+        &prompt,
+        &compare::CompareConfig::default(),
+        |model, prompt| {
+            let api_key = api_key.clone();
+            async move { client::chat_completion_with_usage(&api_key, &model, &prompt, 4096).await }
+        },
+    )
+    .await;
+
+    let style = agent::OutputStyle::from_env();
+    print!(
+        "{}",
+        style.apply(&compare::format_side_by_side(
+            &results,
+            tmux::term_width() as usize
+        ))
+    );
+    println!("{}", style.apply("\n─── Stats ───"));
+    print!("{}", style.apply(&compare::format_stats(&results)));
+
+    if results.iter().all(|r| !r.is_ok()) {
+        anyhow::bail!("All models failed");
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn run_interactive(
     free_only: bool,
     nonfree_only: bool,
@@ -683,6 +1546,8 @@ async fn run_interactive(
     paths: Vec<PathBuf>,
     resume: bool,
     handoff: bool,
+    auto: bool,
+    quiet: bool,
 ) -> Result<()> {
     // Ensure we have an API key
     let api_key = match config::get_api_key() {
@@ -700,7 +1565,7 @@ async fn run_interactive(
     };
 
     // Detect project context
-    let cwd = std::env::current_dir()?;
+    let cwd = resolve_work_dir()?;
     let cwd_str = cwd.display().to_string();
     let project = project::Project::detect(&cwd);
     if let Some(ref p) = project {
@@ -763,95 +1628,191 @@ async fn run_interactive(
     // Load or fetch models
     let models = models::load_or_fetch(&api_key).await?;
 
-    // Select model with filtering
-    let selected_model = if let Some(m) = model {
-        m
-    } else {
-        let available: Vec<_> = if free_only {
-            models.iter().filter(|m| m.is_free()).cloned().collect()
-        } else if nonfree_only {
-            models.iter().filter(|m| !m.is_free()).cloned().collect()
-        } else {
-            models.clone()
-        };
+    // An explicit --model wins, then a resumed session's model, then the
+    // configured default (unless a filter or --auto asks for a choice)
+    let mut requested = model;
+    if requested.is_none() && resume {
+        requested = session::resumable_session()?.map(|s| s.model);
+    }
+    if requested.is_none() && !(free_only || nonfree_only || auto) {
+        requested = config::Config::load().ok().and_then(|c| c.default_model);
+    }
 
-        if available.is_empty() {
-            if nonfree_only {
-                anyhow::bail!("No paid models available");
-            } else if free_only {
-                anyhow::bail!("No free models available");
+    // A model that stopped being offered falls back to the closest free one
+    let mut replaced_model = None;
+    let selected_model = match requested {
+        Some(id) => match models::check_availability(&id, &models) {
+            models::Availability::Offered => id,
+            models::Availability::Replaced(alt) => {
+                eprintln!("Model {} is no longer offered; using {} instead", id, alt);
+                replaced_model = Some(id);
+                alt
+            }
+            models::Availability::Gone => {
+                eprintln!("Model {} is no longer offered; choose another", id);
+                replaced_model = Some(id);
+                ui::pick_model(&filter_models(&models, free_only, nonfree_only)?)?
+            }
+        },
+        None => {
+            let available = filter_models(&models, free_only, nonfree_only)?;
+            if auto {
+                match auto_select_model(&api_key, &available).await {
+                    Some(m) => m,
+                    None => {
+                        println!("Auto-select failed, choose a model manually");
+                        ui::pick_model(&available)?
+                    }
+                }
             } else {
-                anyhow::bail!("No models available");
+                ui::pick_model(&available)?
             }
         }
-
-        ui::pick_model(&available)?
     };
 
     println!("Using model: {}", selected_model);
 
     // Run TUI with session, project context, and optional Claude import
-    ui::run_tui(
+    let summary = ui::run_tui(
         &api_key,
         &selected_model,
+        replaced_model,
         paths,
         resume,
+        cwd,
         project,
         claude_context,
     )
-    .await
+    .await?;
+    if !quiet {
+        for line in summary.render() {
+            println!("{}", line);
+        }
+    }
+    Ok(())
 }
 
-async fn run_benchmark(model: Option<&str>) -> Result<()> {
-    let api_key = config::get_api_key()?;
-    let work_dir = std::env::current_dir()?;
+/// Models allowed by --free / --nonfree, erroring when none are left
+fn filter_models(
+    models: &[models::Model],
+    free_only: bool,
+    nonfree_only: bool,
+) -> Result<Vec<models::Model>> {
+    let available: Vec<_> = if free_only {
+        models.iter().filter(|m| m.is_free()).cloned().collect()
+    } else if nonfree_only {
+        models.iter().filter(|m| !m.is_free()).cloned().collect()
+    } else {
+        models.to_vec()
+    };
 
-    // Use provided model or default to a capable free model
-    let model_id = model.unwrap_or("meta-llama/llama-3.2-3b-instruct:free");
+    if available.is_empty() {
+        if nonfree_only {
+            anyhow::bail!("No paid models available");
+        } else if free_only {
+            anyhow::bail!("No free models available");
+        } else {
+            anyhow::bail!("No models available");
+        }
+    }
+    Ok(available)
+}
 
-    println!("Benchmarking model: {}", model_id);
-    println!("Work directory: {}", work_dir.display());
-    println!();
+/// Reuse the cached `--auto` choice if still available, else probe free models
+async fn auto_select_model(api_key: &str, available: &[models::Model]) -> Option<String> {
+    let mut cfg = config::Config::load().unwrap_or_default();
+    if let Some(cached) = &cfg.auto_model {
+        if available.iter().any(|m| &m.id == cached) {
+            println!("Auto-selected (cached): {}", cached);
+            return Some(cached.clone());
+        }
+    }
+
+    let candidates = compare::probe_candidates(available, compare::AUTO_PROBE_MODELS);
+    println!("Probing {} free models...", candidates.len());
+    let probe_config = compare::CompareConfig {
+        timeout: std::time::Duration::from_secs(30),
+        ..Default::default()
+    };
+    let picked = compare::auto_select(&candidates, &probe_config, |model, prompt| async move {
+        client::chat_completion_with_usage(api_key, &model, &prompt, 256).await
+    })
+    .await?;
+
+    println!("Auto-selected: {}", picked);
+    cfg.auto_model = Some(picked.clone());
+    if let Err(e) = cfg.save() {
+        eprintln!("Could not cache auto-selected model: {}", e);
+    }
+    Some(picked)
+}
 
-    // Run benchmark suite
-    let mut runner = benchmark::BenchmarkRunner::new(&api_key, model_id, &work_dir);
-    let profile = runner.run_full_suite().await?;
+async fn run_benchmark(
+    models: &[String],
+    categories: Option<&str>,
+    free: bool,
+    format: Option<&str>,
+    baseline: Option<&std::path::Path>,
+    project: bool,
+) -> Result<()> {
+    let api_key = config::get_api_key()?;
+    let format = benchmark::ReportFormat::parse(format.unwrap_or("text"))?;
+    let baseline = baseline.map(benchmark::BenchmarkResult::load).transpose()?;
 
-    // Display results
-    println!("\n{}", "═".repeat(60));
-    println!("BENCHMARK RESULTS: {}", model_id);
-    println!("{}", "═".repeat(60));
-    println!();
-    println!(
-        "Overall Score: {:.1} (Grade: {})",
-        profile.total_score,
-        profile.grade()
+    let mut bench_config = benchmark::BenchmarkConfig {
+        free_only: free,
+        ..Default::default()
+    };
+    if let Some(spec) = categories {
+        bench_config.categories = benchmark::parse_categories(spec)?;
+    }
+
+    let catalog = models::load_or_fetch(&api_key).await?;
+    let selected = benchmark::select_models(models, bench_config.free_only, &catalog);
+    if selected.is_empty() {
+        anyhow::bail!("No models to benchmark (paid models are skipped with --free)");
+    }
+
+    // Progress goes to stderr so the report can be piped
+    eprintln!("Benchmarking: {}", selected.join(", "));
+    let mut runner = benchmark::BenchmarkRunner::new(bench_config, |model, prompt, max_tokens| {
+        let api_key = api_key.clone();
+        async move { client::chat_completion_simple(&api_key, &model, &prompt, max_tokens).await }
+    });
+    if project {
+        eprintln!("Building prompts from this project (cached until the source changes)...");
+        let root = resolve_work_dir()?;
+        let prompts =
+            tokio::task::spawn_blocking(move || benchmark::load_project_prompts(&root)).await??;
+        runner = runner.with_prompts(prompts);
+        if runner.prompts().is_empty() {
+            anyhow::bail!("No benchmark prompts in this project for the selected categories");
+        }
+    }
+    eprintln!(
+        "{} prompts per model, {} requests",
+        runner.prompts().len(),
+        runner.prompts().len() * selected.len()
     );
-    println!();
-    println!("Detailed Scores:");
-    for score in &profile.scores {
-        println!(
-            "  {:30} {:.2}  (rel:{:.0}% prec:{:.0}% comp:{:.0}% eff:{:.0}%)",
-            score.prompt_id,
-            score.weighted_score,
-            score.relevance * 100.0,
-            score.precision * 100.0,
-            score.completeness * 100.0,
-            score.efficiency * 100.0,
-        );
+    let result = runner.run(&selected).await;
+
+    println!("{}", result.render(format)?);
+    if let Err(e) = result.save_latest() {
+        eprintln!("Could not save results for the model picker: {}", e);
+    }
+    if let Some(baseline) = &baseline {
+        eprintln!("{}", result.compare_to(baseline));
     }
-    println!();
-    println!("Tokens used: {}", profile.total_tokens);
-    println!("Avg latency: {}ms", profile.avg_latency_ms);
-    println!("Cost estimate: ${:.4}", profile.cost_estimate);
-    println!();
 
+    if result.profiles.iter().all(|p| p.scores.is_empty()) {
+        anyhow::bail!("All models failed");
+    }
     Ok(())
 }
 
 async fn run_backburner(paths: &[PathBuf], watch_docs: bool) -> Result<()> {
     let work_dir = if paths.is_empty() {
-        std::env::current_dir()?
+        resolve_work_dir()?
     } else {
         paths[0].clone()
     };
@@ -0,0 +1,288 @@
+//! `@file` mentions in prompts
+//!
+//! `@src/main.rs` or `@src/main.rs:10-40` in a prompt sends that file (or
+//! those lines) with the request, as a system message just before the
+//! prompt. Whole files go through the budgeted reader, so large ones are cut
+//! to head and tail; mentions that can't be read are reported and skipped
+//! rather than failing the send.
+
+use std::path::Path;
+
+use crate::cognitive::estimate_tokens;
+use crate::tools;
+
+// ═══════════════════════════════════════════════════════════════
+// PARSING
+// ═══════════════════════════════════════════════════════════════
+
+/// A file referenced as `@path` or `@path:start-end`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mention {
+    pub path: String,
+    /// 1-based inclusive line range, when one was given
+    pub lines: Option<(usize, usize)>,
+}
+
+impl Mention {
+    /// As written, without the `@`
+    pub fn label(&self) -> String {
+        match self.lines {
+            Some((start, end)) if start == end => format!("{}:{}", self.path, start),
+            Some((start, end)) => format!("{}:{}-{}", self.path, start, end),
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// `start-end` or a single line number
+fn parse_lines(spec: &str) -> Option<(usize, usize)> {
+    match spec.split_once('-') {
+        Some((start, end)) => Some((start.parse().ok()?, end.parse().ok()?)),
+        None => spec.parse().ok().map(|n| (n, n)),
+    }
+}
+
+/// Mentions in `prompt`, in order and without repeats. A mention is a word
+/// starting with `@`; trailing punctuation is dropped, so `see @a.rs.` works
+/// and `me@example.com` is not a mention.
+pub fn extract(prompt: &str) -> Vec<Mention> {
+    let mut mentions: Vec<Mention> = Vec::new();
+    for word in prompt.split_whitespace() {
+        let Some(rest) = word
+            .trim_start_matches(['(', '"', '\'', '`'])
+            .strip_prefix('@')
+        else {
+            continue;
+        };
+        let rest = rest.trim_end_matches([',', '.', ';', ':', ')', '!', '?', '"', '\'', '`']);
+        let mention = match rest.rsplit_once(':') {
+            Some((path, spec)) => match parse_lines(spec) {
+                Some(lines) => Mention {
+                    path: path.to_string(),
+                    lines: Some(lines),
+                },
+                None => Mention {
+                    path: rest.to_string(),
+                    lines: None,
+                },
+            },
+            None => Mention {
+                path: rest.to_string(),
+                lines: None,
+            },
+        };
+        if !mention.path.is_empty() && !mentions.contains(&mention) {
+            mentions.push(mention);
+        }
+    }
+    mentions
+}
+
+// ═══════════════════════════════════════════════════════════════
+// READING
+// ═══════════════════════════════════════════════════════════════
+
+/// Mentioned files read for one request
+#[derive(Debug, Clone, Default)]
+pub struct MentionContext {
+    /// System message content; empty when nothing could be attached
+    pub text: String,
+    /// Labels of the mentions included
+    pub attached: Vec<String>,
+    /// Files cut to fit the budget
+    pub trimmed: Vec<String>,
+    /// Mentions skipped, with the reason
+    pub warnings: Vec<String>,
+}
+
+/// Read `mentions` within `budget_tokens` (~4 chars each). Line ranges are
+/// read exactly and go first; whole files share what's left.
+pub fn read(mentions: &[Mention], budget_tokens: usize) -> MentionContext {
+    let mut ctx = MentionContext::default();
+    let mut body = String::new();
+    let mut whole: Vec<&Mention> = Vec::new();
+
+    for mention in mentions {
+        let path = Path::new(&mention.path);
+        if !path.is_file() {
+            let reason = if path.is_dir() {
+                "is a directory"
+            } else {
+                "no such file"
+            };
+            ctx.warnings
+                .push(format!("@{}: {}", mention.label(), reason));
+            continue;
+        }
+        let Some((start, end)) = mention.lines else {
+            whole.push(mention);
+            continue;
+        };
+        match tools::read_file_range(path, start, end) {
+            Ok(range) => {
+                let section = format!(
+                    "\n--- {} (lines {}-{} of {}) ---\n{}",
+                    mention.path, range.start, range.end, range.total, range.text
+                );
+                if estimate_tokens(&body) + estimate_tokens(&section) > budget_tokens {
+                    ctx.warnings
+                        .push(format!("@{}: over budget", mention.label()));
+                    continue;
+                }
+                body.push_str(&section);
+                ctx.attached.push(mention.label());
+            }
+            Err(e) => ctx.warnings.push(format!("@{}: {}", mention.label(), e)),
+        }
+    }
+
+    if !whole.is_empty() {
+        let paths: Vec<&Path> = whole.iter().map(|m| Path::new(&m.path)).collect();
+        let remaining = budget_tokens.saturating_sub(estimate_tokens(&body));
+        match tools::read_files_context_budgeted(&paths, remaining) {
+            Ok(budgeted) => {
+                for mention in &whole {
+                    // Files with no room at all are left out of the text
+                    if budgeted
+                        .text
+                        .contains(&format!("\n--- {} ---\n", mention.path))
+                    {
+                        ctx.attached.push(mention.label());
+                    } else {
+                        ctx.warnings
+                            .push(format!("@{}: over budget", mention.label()));
+                    }
+                }
+                ctx.trimmed = budgeted
+                    .trimmed
+                    .into_iter()
+                    .filter(|p| ctx.attached.contains(p))
+                    .collect();
+                body.push_str(&budgeted.text);
+            }
+            Err(e) => ctx.warnings.push(format!("Reading mentioned files: {}", e)),
+        }
+    }
+
+    if !body.is_empty() {
+        ctx.text = format!("Files mentioned in the prompt:\n{}", body);
+    }
+    ctx
+}
+
+impl MentionContext {
+    /// Add the files to `history` as a system message at the end, right
+    /// before the prompt the request sends
+    pub fn apply(&self, mut history: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+        if !self.text.is_empty() {
+            history.push(serde_json::json!({ "role": "system", "content": self.text }));
+        }
+        history
+    }
+
+    /// Lines for the chat output: what was attached and what was skipped
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if !self.attached.is_empty() {
+            lines.push(format!("[@] Attached {}", self.attached.join(", ")));
+        }
+        if !self.trimmed.is_empty() {
+            lines.push(format!("[@] Trimmed to fit: {}", self.trimmed.join(", ")));
+        }
+        lines.extend(self.warnings.iter().map(|w| format!("[!] {}", w)));
+        lines
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TESTS
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn mention(path: &str, lines: Option<(usize, usize)>) -> Mention {
+        Mention {
+            path: path.to_string(),
+            lines,
+        }
+    }
+
+    #[test]
+    fn test_extract_mentions() {
+        assert_eq!(
+            extract("why does @src/main.rs call @src/ui.rs:10-20?"),
+            vec![
+                mention("src/main.rs", None),
+                mention("src/ui.rs", Some((10, 20)))
+            ]
+        );
+        // Trailing punctuation, quotes and repeats
+        assert_eq!(
+            extract("compare (@a.rs), `@b.rs` and @a.rs."),
+            vec![mention("a.rs", None), mention("b.rs", None)]
+        );
+        assert_eq!(
+            extract("line @lib.rs:7"),
+            vec![mention("lib.rs", Some((7, 7)))]
+        );
+        // Not a line spec: kept as part of the path
+        assert_eq!(extract("@notes:todo"), vec![mention("notes:todo", None)]);
+        // Emails and a bare @ are not mentions
+        assert!(extract("mail me@example.com or @ now").is_empty());
+        assert_eq!(mention("a.rs", Some((3, 3))).label(), "a.rs:3");
+    }
+
+    #[test]
+    fn test_read_includes_content_and_warns() {
+        let dir = std::env::temp_dir().join(format!("hyle_test_mentions_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let at = |name: &str| dir.join(name).display().to_string();
+        fs::write(dir.join("small.rs"), "fn small() {}\n").unwrap();
+        let numbered: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+        fs::write(dir.join("long.txt"), &numbered).unwrap();
+
+        let mentions = vec![
+            mention(&at("small.rs"), None),
+            mention(&at("long.txt"), Some((10, 12))),
+            mention(&at("missing.rs"), None),
+            mention(&at("long.txt"), Some((40, 50))),
+            mention(&dir.display().to_string(), None),
+        ];
+        let ctx = read(&mentions, 1000);
+        assert!(ctx.text.starts_with("Files mentioned in the prompt:"));
+        assert!(ctx.text.contains("fn small() {}"));
+        assert!(ctx.text.contains("(lines 10-12 of 30)"));
+        assert!(ctx.text.contains("line 11"));
+        assert!(!ctx.text.contains("line 13"));
+        assert_eq!(
+            ctx.attached,
+            vec![format!("{}:10-12", at("long.txt")), at("small.rs")]
+        );
+        assert_eq!(ctx.warnings.len(), 3);
+        assert!(ctx.warnings[0].ends_with("missing.rs: no such file"));
+        assert!(ctx.warnings[1].contains("has 30 lines"));
+        assert!(ctx.warnings[2].ends_with("is a directory"));
+
+        // Sent as the last thing before the prompt
+        let history = vec![serde_json::json!({"role": "user", "content": "earlier"})];
+        let applied = ctx.apply(history.clone());
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[1]["role"], "system");
+        assert_eq!(read(&[], 1000).apply(history.clone()), history);
+
+        let summary = ctx.summary();
+        assert!(summary[0].starts_with("[@] Attached "));
+        assert!(summary[1].starts_with("[!] @"));
+
+        // A tight budget trims whole files instead of dropping the send
+        fs::write(dir.join("big.txt"), "x".repeat(4000)).unwrap();
+        let ctx = read(&[mention(&at("big.txt"), None)], 100);
+        assert_eq!(ctx.trimmed, vec![at("big.txt")]);
+        assert!(estimate_tokens(&ctx.text) <= 120);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,129 @@
+//! Minimap - scrollbar gutter math for the chat view
+//!
+//! Maps output lines onto the rows of a scrollbar track so the chat view can
+//! show where search matches and the last response sit in a long conversation.
+//! Everything here is plain arithmetic on cached line counts; rendering lives
+//! in `ui::render_chat`.
+
+/// Clamp a scroll offset so the viewport never runs past the last line
+pub fn clamp_scroll(offset: u16, visible: u16, total: u16, auto_scroll: bool) -> u16 {
+    let max = total.saturating_sub(visible);
+    if auto_scroll {
+        max
+    } else {
+        offset.min(max)
+    }
+}
+
+/// Track row (0-based) that output line `line` falls on
+pub fn row_for_line(line: usize, total: usize, track: u16) -> u16 {
+    if total == 0 || track == 0 {
+        return 0;
+    }
+    let row = line.min(total - 1) * track as usize / total;
+    row as u16
+}
+
+/// What a gutter mark points at, in increasing priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Mark {
+    Response,
+    Search,
+    CurrentMatch,
+}
+
+/// Gutter marks as (row, kind), one per row, highest priority wins
+///
+/// `response_start` is the first line of the last response, which runs to
+/// the end of the output.
+pub fn marks(
+    search_matches: &[usize],
+    current_match: Option<usize>,
+    response_start: Option<usize>,
+    total: usize,
+    track: u16,
+) -> Vec<(u16, Mark)> {
+    let mut rows: Vec<Option<Mark>> = vec![None; track as usize];
+    let mut put = |row: u16, mark: Mark| {
+        if let Some(slot) = rows.get_mut(row as usize) {
+            if slot.map(|m| mark > m).unwrap_or(true) {
+                *slot = Some(mark);
+            }
+        }
+    };
+
+    if let Some(start) = response_start.filter(|s| *s < total) {
+        let first = row_for_line(start, total, track);
+        let last = row_for_line(total - 1, total, track);
+        for row in first..=last {
+            put(row, Mark::Response);
+        }
+    }
+    for &line in search_matches {
+        put(row_for_line(line, total, track), Mark::Search);
+    }
+    if let Some(&line) = current_match.and_then(|i| search_matches.get(i)) {
+        put(row_for_line(line, total, track), Mark::CurrentMatch);
+    }
+
+    rows.into_iter()
+        .enumerate()
+        .filter_map(|(row, mark)| mark.map(|m| (row as u16, m)))
+        .collect()
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TESTS
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamp_scroll() {
+        // Short output never scrolls
+        assert_eq!(clamp_scroll(5, 20, 10, false), 0);
+        assert_eq!(clamp_scroll(5, 20, 10, true), 0);
+        // Offset within range is kept
+        assert_eq!(clamp_scroll(30, 20, 100, false), 30);
+        // Past the end clamps to the last page
+        assert_eq!(clamp_scroll(95, 20, 100, false), 80);
+        // Auto-scroll pins to the bottom
+        assert_eq!(clamp_scroll(0, 20, 100, true), 80);
+    }
+
+    #[test]
+    fn test_row_for_line() {
+        assert_eq!(row_for_line(0, 100, 10), 0);
+        assert_eq!(row_for_line(55, 100, 10), 5);
+        assert_eq!(row_for_line(99, 100, 10), 9);
+        // Lines past the end stay on the last row
+        assert_eq!(row_for_line(500, 100, 10), 9);
+        // Fewer lines than rows spreads them out
+        assert_eq!(row_for_line(1, 4, 20), 5);
+        assert_eq!(row_for_line(3, 4, 20), 15);
+        // Degenerate sizes
+        assert_eq!(row_for_line(3, 0, 10), 0);
+        assert_eq!(row_for_line(3, 10, 0), 0);
+    }
+
+    #[test]
+    fn test_marks_priority_and_rows() {
+        // 100 lines on a 10-row track; last response is lines 80..100
+        let m = marks(&[5, 7, 85], Some(2), Some(80), 100, 10);
+        assert_eq!(
+            m,
+            vec![
+                (0, Mark::Search),
+                (8, Mark::CurrentMatch),
+                (9, Mark::Response),
+            ]
+        );
+
+        // No matches, no response: nothing to mark
+        assert!(marks(&[], None, None, 100, 10).is_empty());
+        // Response start past the end is ignored
+        assert!(marks(&[], None, Some(200), 100, 10).is_empty());
+    }
+}
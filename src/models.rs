@@ -6,8 +6,11 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 
+use crate::client::TokenUsage;
 use crate::config;
 
 /// Model information
@@ -18,6 +21,15 @@ pub struct Model {
     pub context_length: u32,
     pub pricing_prompt: f64,
     pub pricing_completion: f64,
+    /// Prompt-cache read/write prices, when the provider publishes them
+    #[serde(default)]
+    pub pricing_cache_read: Option<f64>,
+    #[serde(default)]
+    pub pricing_cache_write: Option<f64>,
+    /// Supported request parameters reported by OpenRouter (e.g. "tools"),
+    /// plus "vision" for models that accept image input
+    #[serde(default)]
+    pub capabilities: Vec<String>,
 }
 
 impl Model {
@@ -26,6 +38,32 @@ impl Model {
         self.pricing_prompt == 0.0 && self.pricing_completion == 0.0
     }
 
+    pub fn supports_tools(&self) -> bool {
+        self.capabilities.iter().any(|c| c == "tools")
+    }
+
+    pub fn supports_vision(&self) -> bool {
+        self.capabilities.iter().any(|c| c == "vision")
+    }
+
+    /// Combined prompt + completion price, for ordering by cost
+    pub fn total_price(&self) -> f64 {
+        self.pricing_prompt + self.pricing_completion
+    }
+
+    /// Cost of a request, pricing cache reads/writes at their own rates
+    /// (falling back to the prompt rate when none is published)
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        let cache_read = self.pricing_cache_read.unwrap_or(self.pricing_prompt);
+        let cache_write = self.pricing_cache_write.unwrap_or(self.pricing_prompt);
+        // Prices are typically per 1M tokens
+        (usage.uncached_prompt_tokens() as f64 * self.pricing_prompt
+            + usage.cache_read_tokens as f64 * cache_read
+            + usage.cache_write_tokens as f64 * cache_write
+            + usage.completion_tokens as f64 * self.pricing_completion)
+            / 1_000_000.0
+    }
+
     /// Get display name (shorter version for UI)
     pub fn display_name(&self) -> String {
         // Extract just the model name without provider prefix for display
@@ -37,69 +75,216 @@ impl Model {
     }
 }
 
+/// Current on-disk cache format. Bump when `ModelsCache` changes shape
+/// and teach `migrate_cache` how to read the previous version.
+pub const CACHE_VERSION: u32 = 1;
+
+/// Cache age after which models are re-fetched
+const CACHE_MAX_AGE_HOURS: i64 = 24;
+
 /// Cached models data
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ModelsCache {
+    /// Missing in caches written before versioning (treated as 0)
+    #[serde(default)]
+    pub version: u32,
     pub models: Vec<Model>,
     pub fetched_at: chrono::DateTime<chrono::Utc>,
 }
 
 /// Cache file path
-fn cache_path() -> Result<std::path::PathBuf> {
+pub fn cache_path() -> Result<std::path::PathBuf> {
     Ok(config::cache_dir()?.join("models.json"))
 }
 
 /// Load models from cache
 pub fn load_cache() -> Result<Option<ModelsCache>> {
-    let path = cache_path()?;
-    if !path.exists() {
-        return Ok(None);
-    }
+    read_cache_at(&cache_path()?)
+}
 
-    let content =
-        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+/// Read a fresh cache from `path`; None when missing or stale. An
+/// unreadable cache is logged and returned as an error: a corrupt one is
+/// removed, one from an unknown version is left for the hyle that wrote it.
+fn read_cache_at(path: &Path) -> Result<Option<ModelsCache>> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Ok(None);
+    };
 
-    let cache: ModelsCache = serde_json::from_str(&content)
-        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let cache = match serde_json::from_str(&content)
+        .map_err(anyhow::Error::from)
+        .and_then(migrate_cache)
+    {
+        Ok(cache) => cache,
+        Err(e) => {
+            let corrupt = e.is::<serde_json::Error>();
+            crate::logfile::LogEvent::warn(module_path!(), "ignoring unreadable model cache")
+                .field("path", path.display().to_string())
+                .field("error", e.to_string())
+                .field("removed", corrupt)
+                .emit();
+            if corrupt {
+                let _ = fs::remove_file(path);
+            }
+            return Err(e.context(format!("Unreadable model cache {}", path.display())));
+        }
+    };
 
-    // Check if cache is stale (older than 24 hours)
+    // Check if cache is stale
     let age = chrono::Utc::now() - cache.fetched_at;
-    if age > chrono::Duration::hours(24) {
+    if age > chrono::Duration::hours(CACHE_MAX_AGE_HOURS) {
         return Ok(None);
     }
 
     Ok(Some(cache))
 }
 
+/// Bring a cache of any known version up to `CACHE_VERSION`
+fn migrate_cache(raw: serde_json::Value) -> Result<ModelsCache> {
+    let version = raw.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    match version as u32 {
+        // v0 differs only by missing fields that default on read
+        0 | CACHE_VERSION => {
+            let mut cache: ModelsCache = serde_json::from_value(raw)?;
+            cache.version = CACHE_VERSION;
+            Ok(cache)
+        }
+        v => anyhow::bail!("unsupported cache version {}", v),
+    }
+}
+
 /// Save models to cache
 pub fn save_cache(models: &[Model]) -> Result<()> {
     config::ensure_dirs()?;
-    let path = cache_path()?;
+    write_cache_at(&cache_path()?, models)
+}
 
+fn write_cache_at(path: &Path, models: &[Model]) -> Result<()> {
     let cache = ModelsCache {
+        version: CACHE_VERSION,
         models: models.to_vec(),
         fetched_at: chrono::Utc::now(),
     };
 
     let content = serde_json::to_string_pretty(&cache)?;
-    fs::write(&path, &content).with_context(|| format!("Failed to write {}", path.display()))?;
+    fs::write(path, &content).with_context(|| format!("Failed to write {}", path.display()))?;
 
     Ok(())
 }
 
 /// Load models from cache or fetch from API
 pub async fn load_or_fetch(api_key: &str) -> Result<Vec<Model>> {
-    // Try cache first
-    if let Some(cache) = load_cache()? {
-        return Ok(cache.models);
-    }
+    config::ensure_dirs()?;
+    load_or_fetch_at(&cache_path()?, || crate::client::fetch_models(api_key)).await
+}
+
+/// Cache-or-fetch against an explicit path; `fetch` runs only on a miss
+async fn load_or_fetch_at<F, Fut>(path: &Path, fetch: F) -> Result<Vec<Model>>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<Model>>>,
+{
+    // Try cache first; only a cache from another version survives a failed read
+    let foreign = match read_cache_at(path) {
+        Ok(Some(cache)) => return Ok(cache.models),
+        Ok(None) => false,
+        Err(_) => path.exists(),
+    };
 
     // Fetch from API
-    let models = crate::client::fetch_models(api_key).await?;
-    save_cache(&models)?;
+    let models = fetch().await?;
+    if !foreign {
+        if let Err(e) = write_cache_at(path, &models) {
+            crate::logfile::LogEvent::warn(module_path!(), "could not write model cache")
+                .field("path", path.display().to_string())
+                .field("error", e.to_string())
+                .emit();
+        }
+    }
     Ok(models)
 }
 
+/// Fetch and write the cache at `path` whatever its current state,
+/// returning the number of models cached
+pub async fn refresh_cache_at<F, Fut>(path: &Path, fetch: F) -> Result<usize>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<Model>>>,
+{
+    let models = fetch().await?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_cache_at(path, &models)?;
+    Ok(models.len())
+}
+
+/// Catalog reloaded inside a running session (`/models refresh`)
+#[derive(Debug, Clone)]
+pub struct Reloaded {
+    pub models: Vec<Model>,
+    /// Rate-limit fallbacks from the new catalog, for the model picker
+    pub fallbacks: Vec<String>,
+    /// Fetched from the API rather than read from a fresh cache
+    pub fetched: bool,
+}
+
+impl Reloaded {
+    pub fn summary(&self) -> String {
+        format!(
+            "Loaded {} models from {} ({} free fallbacks)",
+            self.models.len(),
+            if self.fetched { "the API" } else { "cache" },
+            self.fallbacks.len()
+        )
+    }
+}
+
+/// Reload the model catalog without restarting
+pub async fn reload(api_key: &str, force: bool) -> Result<Reloaded> {
+    config::ensure_dirs()?;
+    reload_at(&cache_path()?, force, || {
+        crate::client::fetch_models(api_key)
+    })
+    .await
+}
+
+/// Reload against an explicit cache path. A fresh cache is reused unless
+/// `force`; otherwise `fetch` runs and the cache is rewritten. A failed
+/// fetch leaves the cache as it was.
+pub async fn reload_at<F, Fut>(path: &Path, force: bool, fetch: F) -> Result<Reloaded>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<Model>>>,
+{
+    let cached = if force {
+        None
+    } else {
+        read_cache_at(path).ok().flatten()
+    };
+    let (models, fetched) = match cached {
+        Some(cache) => (cache.models, false),
+        None => {
+            let models = fetch().await.context("Could not fetch models")?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            write_cache_at(path, &models)?;
+            (models, true)
+        }
+    };
+    Ok(Reloaded {
+        fallbacks: free_fallbacks(&models),
+        models,
+        fetched,
+    })
+}
+
+/// Whether the cache at `path` is readable and fresh; a corrupt one is
+/// removed, as on load
+pub fn cache_usable_at(path: &Path) -> bool {
+    matches!(read_cache_at(path), Ok(Some(_)))
+}
+
 /// Get just the free models, sorted by context length
 pub fn get_free_models(models: &[Model]) -> Vec<&Model> {
     let mut free: Vec<_> = models.iter().filter(|m| m.is_free()).collect();
@@ -107,6 +292,231 @@ pub fn get_free_models(models: &[Model]) -> Vec<&Model> {
     free
 }
 
+/// All models, cheapest first (free models lead), ties broken by id
+pub fn sort_by_cost(models: &[Model]) -> Vec<&Model> {
+    let mut sorted: Vec<_> = models.iter().collect();
+    sorted.sort_by(|a, b| {
+        a.total_price()
+            .partial_cmp(&b.total_price())
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    sorted
+}
+
+/// Resolve a user-typed model name against known ids (exact match, then substring)
+pub fn resolve_model(query: &str, known: &[String]) -> Option<String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return None;
+    }
+    known
+        .iter()
+        .find(|id| id.as_str() == query)
+        .or_else(|| known.iter().find(|id| id.contains(query)))
+        .cloned()
+}
+
+/// Average benchmark score per prompt that earns a "recommended" badge (grade A)
+pub const RECOMMENDED_SCORE: f64 = 2.0;
+
+/// Picker ordering, cycled with Tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PickerSort {
+    /// Largest context first
+    #[default]
+    Context,
+    /// Cheapest first
+    Price,
+    /// Best benchmark score first, unscored models last
+    Score,
+}
+
+impl PickerSort {
+    pub fn next(self) -> Self {
+        match self {
+            PickerSort::Context => PickerSort::Price,
+            PickerSort::Price => PickerSort::Score,
+            PickerSort::Score => PickerSort::Context,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PickerSort::Context => "context",
+            PickerSort::Price => "price",
+            PickerSort::Score => "score",
+        }
+    }
+
+    /// Order two models; ties fall back to context, then id
+    pub fn compare(
+        self,
+        a: &Model,
+        b: &Model,
+        scores: &HashMap<String, f64>,
+    ) -> std::cmp::Ordering {
+        let by_context = b
+            .context_length
+            .cmp(&a.context_length)
+            .then_with(|| a.id.cmp(&b.id));
+        match self {
+            PickerSort::Context => by_context,
+            PickerSort::Price => a
+                .total_price()
+                .partial_cmp(&b.total_price())
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(by_context),
+            PickerSort::Score => {
+                let score = |m: &Model| scores.get(&m.id).copied().unwrap_or(f64::NEG_INFINITY);
+                score(b)
+                    .partial_cmp(&score(a))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(by_context)
+            }
+        }
+    }
+}
+
+/// Per-token price as dollars per 1M tokens
+pub fn format_price_per_million(price: f64) -> String {
+    if price == 0.0 {
+        "free".to_string()
+    } else {
+        format!("${:.2}", price * 1_000_000.0)
+    }
+}
+
+/// One picker row: id, context, prompt/completion price per 1M, badges
+pub fn picker_row(model: &Model, score: Option<f64>) -> String {
+    let price = if model.is_free() {
+        "[FREE]".to_string()
+    } else {
+        format!(
+            "{}/{} per 1M",
+            format_price_per_million(model.pricing_prompt),
+            format_price_per_million(model.pricing_completion)
+        )
+    };
+    let mut row = format!("{} ({}k) {}", model.id, model.context_length / 1000, price);
+    if model.supports_tools() {
+        row.push_str(" [tools]");
+    }
+    if model.supports_vision() {
+        row.push_str(" [vision]");
+    }
+    if score.map(|s| s >= RECOMMENDED_SCORE).unwrap_or(false) {
+        row.push_str(" ★ recommended");
+    }
+    row
+}
+
+/// Whether a configured or resumed model is still offered
+#[derive(Debug, Clone, PartialEq)]
+pub enum Availability {
+    Offered,
+    /// Gone; the closest free model to fall back to
+    Replaced(String),
+    /// Gone, with no free model to fall back to
+    Gone,
+}
+
+/// Check `id` against the fetched catalog (an empty catalog can't rule anything out)
+pub fn check_availability(id: &str, catalog: &[Model]) -> Availability {
+    if catalog.is_empty() || catalog.iter().any(|m| m.id == id) {
+        return Availability::Offered;
+    }
+    match closest_free_model(id, catalog) {
+        Some(m) => Availability::Replaced(m.id.clone()),
+        None => Availability::Gone,
+    }
+}
+
+/// Free model most like `id`: same provider first, then most shared name
+/// parts ("llama", "3.1", "8b"...), then largest context
+pub fn closest_free_model<'a>(id: &str, catalog: &'a [Model]) -> Option<&'a Model> {
+    let parts = |id: &str| -> Vec<String> {
+        id.split(['/', '-', ':', '_'])
+            .filter(|p| !p.is_empty() && *p != "free")
+            .map(|p| p.to_lowercase())
+            .collect()
+    };
+    let wanted = parts(id);
+    let provider = id.split('/').next().unwrap_or_default();
+
+    let mut best: Option<(usize, &Model)> = None;
+    for model in get_free_models(catalog) {
+        let same_provider = model.id.split('/').next() == Some(provider);
+        let shared = parts(&model.id)
+            .iter()
+            .filter(|p| wanted.contains(p))
+            .count();
+        let score = shared + if same_provider { 10 } else { 0 };
+        // Ties keep the earlier (larger context) model
+        if best.map(|(s, _)| score > s).unwrap_or(true) {
+            best = Some((score, model));
+        }
+    }
+    best.map(|(_, m)| m)
+}
+
+/// Free models to prefer on a rate limit, best first, while still offered
+pub const FREE_MODEL_PREFERENCES: &[&str] = &[
+    "meta-llama/llama-3.2-3b-instruct:free",
+    "google/gemma-2-9b-it:free",
+    "qwen/qwen-2-7b-instruct:free",
+    "mistralai/mistral-7b-instruct:free",
+    "microsoft/phi-3-mini-128k-instruct:free",
+];
+
+/// Rate-limit fallbacks when there is no catalog to check (offline, no cache)
+pub const FREE_MODEL_LAST_RESORT: &[&str] = &[
+    "meta-llama/llama-3.2-3b-instruct:free",
+    "google/gemma-2-9b-it:free",
+    "mistralai/mistral-7b-instruct:free",
+];
+
+/// Longest rate-limit fallback list
+pub const MAX_FREE_FALLBACKS: usize = 8;
+
+/// Free models to fall back to on a rate limit: preferred ones the catalog
+/// still offers, then other free models by context length
+///
+/// Tool-capable models are used when the catalog reports any; older
+/// caches without capabilities fall back to every free model.
+pub fn free_fallbacks(catalog: &[Model]) -> Vec<String> {
+    let free = get_free_models(catalog);
+    let with_tools: Vec<&Model> = free
+        .iter()
+        .copied()
+        .filter(|m| m.supports_tools())
+        .collect();
+    let candidates = if with_tools.is_empty() {
+        free
+    } else {
+        with_tools
+    };
+    if candidates.is_empty() {
+        return FREE_MODEL_LAST_RESORT
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+    }
+
+    let mut fallbacks: Vec<String> = FREE_MODEL_PREFERENCES
+        .iter()
+        .filter(|id| candidates.iter().any(|m| m.id == **id))
+        .map(|id| id.to_string())
+        .collect();
+    for model in candidates {
+        if !fallbacks.contains(&model.id) {
+            fallbacks.push(model.id.clone());
+        }
+    }
+    fallbacks.truncate(MAX_FREE_FALLBACKS);
+    fallbacks
+}
+
 /// Get pricing for a model (prompt, completion) in $/1M tokens
 /// Returns (0.0, 0.0) for free models
 pub fn get_model_pricing(model_id: &str) -> (f64, f64) {
@@ -121,11 +531,14 @@ pub fn get_model_pricing(model_id: &str) -> (f64, f64) {
 }
 
 /// Calculate cost for a request
-pub fn calculate_cost(model_id: &str, prompt_tokens: u32, completion_tokens: u32) -> f64 {
-    let (prompt_price, completion_price) = get_model_pricing(model_id);
-    // Prices are typically per 1M tokens
-    (prompt_tokens as f64 * prompt_price / 1_000_000.0)
-        + (completion_tokens as f64 * completion_price / 1_000_000.0)
+/// Returns 0.0 for models missing from the cache
+pub fn calculate_cost(model_id: &str, usage: &TokenUsage) -> f64 {
+    if let Ok(Some(cache)) = load_cache() {
+        if let Some(model) = cache.models.iter().find(|m| m.id == model_id) {
+            return model.cost(usage);
+        }
+    }
+    0.0
 }
 
 /// Get context window for a model (from cache or default)
@@ -161,6 +574,9 @@ mod tests {
             context_length: 8192,
             pricing_prompt: 0.0,
             pricing_completion: 0.0,
+            pricing_cache_read: None,
+            pricing_cache_write: None,
+            capabilities: vec![],
         };
         assert!(free.is_free());
 
@@ -170,10 +586,285 @@ mod tests {
             context_length: 8192,
             pricing_prompt: 0.001,
             pricing_completion: 0.002,
+            pricing_cache_read: None,
+            pricing_cache_write: None,
+            capabilities: vec![],
         };
         assert!(!paid.is_free());
     }
 
+    #[test]
+    fn test_cost_prices_cache_tokens() {
+        let model = Model {
+            id: "anthropic/claude".to_string(),
+            name: "Claude".to_string(),
+            context_length: 200_000,
+            pricing_prompt: 3.0,
+            pricing_completion: 15.0,
+            pricing_cache_read: Some(0.3),
+            pricing_cache_write: Some(3.75),
+            capabilities: vec![],
+        };
+        let cached = TokenUsage {
+            prompt_tokens: 10_000,
+            completion_tokens: 500,
+            total_tokens: 10_500,
+            cache_read_tokens: 8_000,
+            cache_write_tokens: 1_000,
+        };
+        let full_price = TokenUsage {
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
+            ..cached.clone()
+        };
+
+        // 1k uncached + 8k read + 1k written + 500 completion
+        let expected = (1_000.0 * 3.0 + 8_000.0 * 0.3 + 1_000.0 * 3.75 + 500.0 * 15.0) / 1e6;
+        assert!((model.cost(&cached) - expected).abs() < 1e-12);
+        assert!(model.cost(&cached) < model.cost(&full_price));
+
+        // Without published cache prices, cache tokens cost the prompt rate
+        let unpriced = Model {
+            pricing_cache_read: None,
+            pricing_cache_write: None,
+            ..model
+        };
+        assert_eq!(unpriced.cost(&cached), unpriced.cost(&full_price));
+    }
+
+    #[test]
+    fn test_resolve_model() {
+        let known = vec![
+            "meta-llama/llama-3.2-3b-instruct:free".to_string(),
+            "meta-llama/llama-3.2-3b-instruct".to_string(),
+            "google/gemma-2-9b-it:free".to_string(),
+        ];
+        // Exact id wins over an earlier substring match
+        assert_eq!(
+            resolve_model("meta-llama/llama-3.2-3b-instruct", &known).as_deref(),
+            Some("meta-llama/llama-3.2-3b-instruct")
+        );
+        assert_eq!(
+            resolve_model("gemma", &known).as_deref(),
+            Some("google/gemma-2-9b-it:free")
+        );
+        assert_eq!(resolve_model("no-such-model", &known), None);
+        assert_eq!(resolve_model("  ", &known), None);
+    }
+
+    #[test]
+    fn test_stale_model_falls_back_to_closest_free() {
+        let model = |id: &str, ctx: u32, price: f64| Model {
+            id: id.into(),
+            name: id.into(),
+            context_length: ctx,
+            pricing_prompt: price,
+            pricing_completion: price,
+            pricing_cache_read: None,
+            pricing_cache_write: None,
+            capabilities: vec![],
+        };
+        let catalog = vec![
+            model("google/gemma-2-9b-it:free", 128_000, 0.0),
+            model("meta-llama/llama-3.1-70b-instruct", 128_000, 1.0),
+            model("meta-llama/llama-3.3-70b-instruct:free", 64_000, 0.0),
+            model("meta-llama/llama-3.1-8b-instruct:free", 16_000, 0.0),
+        ];
+
+        assert_eq!(
+            check_availability("meta-llama/llama-3.1-70b-instruct", &catalog),
+            Availability::Offered
+        );
+        // Same provider and most shared parts, even over a larger context
+        assert_eq!(
+            check_availability("meta-llama/llama-3.1-8b-instruct", &catalog),
+            Availability::Replaced("meta-llama/llama-3.1-8b-instruct:free".into())
+        );
+        assert_eq!(
+            check_availability("meta-llama/llama-3-70b-instruct:free", &catalog),
+            Availability::Replaced("meta-llama/llama-3.3-70b-instruct:free".into())
+        );
+        // Unknown provider: largest free model
+        assert_eq!(
+            check_availability("acme/retired", &catalog),
+            Availability::Replaced("google/gemma-2-9b-it:free".into())
+        );
+
+        let paid_only = vec![catalog[1].clone()];
+        assert_eq!(
+            check_availability("acme/retired", &paid_only),
+            Availability::Gone
+        );
+        // Offline (no catalog): trust the configured id
+        assert_eq!(
+            check_availability("acme/retired", &[]),
+            Availability::Offered
+        );
+    }
+
+    #[test]
+    fn test_free_fallbacks_follow_catalog() {
+        let catalog = vec![
+            // Preferred and still offered
+            picker_model("google/gemma-2-9b-it:free", 8_000, 0.0, &["tools"]),
+            picker_model("acme/new-large:free", 256_000, 0.0, &["tools"]),
+            picker_model("acme/new-small:free", 32_000, 0.0, &["tools"]),
+            picker_model("acme/chat-only:free", 512_000, 0.0, &[]),
+            picker_model("openai/gpt-4o", 128_000, 0.000_002_5, &["tools"]),
+        ];
+        // The stale static favourite is gone; the offered one leads
+        let fallbacks = free_fallbacks(&catalog);
+        assert_eq!(
+            fallbacks,
+            vec![
+                "google/gemma-2-9b-it:free",
+                "acme/new-large:free",
+                "acme/new-small:free"
+            ]
+        );
+        assert!(!fallbacks.contains(&FREE_MODEL_PREFERENCES[0].to_string()));
+
+        // No capability data: every free model, by context length
+        let untagged: Vec<Model> = catalog
+            .iter()
+            .cloned()
+            .map(|mut m| {
+                m.capabilities.clear();
+                m
+            })
+            .collect();
+        assert_eq!(free_fallbacks(&untagged)[1], "acme/chat-only:free");
+
+        // Offline, or nothing free: the last-resort set
+        assert_eq!(free_fallbacks(&[]), FREE_MODEL_LAST_RESORT);
+        assert_eq!(free_fallbacks(&catalog[4..]), FREE_MODEL_LAST_RESORT);
+
+        let many: Vec<Model> = (0..20)
+            .map(|i| picker_model(&format!("x/m{}:free", i), 1_000 * i, 0.0, &["tools"]))
+            .collect();
+        assert_eq!(free_fallbacks(&many).len(), MAX_FREE_FALLBACKS);
+        assert_eq!(free_fallbacks(&many)[0], "x/m19:free");
+    }
+
+    fn picker_model(id: &str, ctx: u32, price: f64, capabilities: &[&str]) -> Model {
+        Model {
+            id: id.into(),
+            name: id.into(),
+            context_length: ctx,
+            pricing_prompt: price,
+            pricing_completion: price * 4.0,
+            pricing_cache_read: None,
+            pricing_cache_write: None,
+            capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_picker_row() {
+        let paid = picker_model(
+            "openai/gpt-4o",
+            128_000,
+            0.000_002_5,
+            &["tools", "vision", "temperature"],
+        );
+        assert_eq!(
+            picker_row(&paid, Some(2.3)),
+            "openai/gpt-4o (128k) $2.50/$10.00 per 1M [tools] [vision] ★ recommended"
+        );
+        let free = picker_model("google/gemma-2-9b-it:free", 8_000, 0.0, &[]);
+        assert_eq!(
+            picker_row(&free, Some(1.2)),
+            "google/gemma-2-9b-it:free (8k) [FREE]"
+        );
+        assert_eq!(picker_row(&free, None), picker_row(&free, Some(1.2)));
+    }
+
+    #[test]
+    fn test_picker_sort() {
+        let models = [
+            picker_model("a/cheap-small", 8_000, 0.000_000_1, &[]),
+            picker_model("b/pricey-large", 200_000, 0.000_01, &["tools"]),
+            picker_model("c/free-mid", 32_000, 0.0, &[]),
+            picker_model("d/free-mid", 32_000, 0.0, &[]),
+        ];
+        let scores: HashMap<String, f64> = [
+            ("a/cheap-small".to_string(), 2.4),
+            ("c/free-mid".to_string(), 1.0),
+        ]
+        .into();
+        let order = |sort: PickerSort| {
+            let mut sorted: Vec<&Model> = models.iter().collect();
+            sorted.sort_by(|a, b| sort.compare(a, b, &scores));
+            sorted.iter().map(|m| m.id.as_str()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            order(PickerSort::Context),
+            vec![
+                "b/pricey-large",
+                "c/free-mid",
+                "d/free-mid",
+                "a/cheap-small"
+            ]
+        );
+        assert_eq!(
+            order(PickerSort::Price),
+            vec![
+                "c/free-mid",
+                "d/free-mid",
+                "a/cheap-small",
+                "b/pricey-large"
+            ]
+        );
+        // Unscored models trail, largest context first
+        assert_eq!(
+            order(PickerSort::Score),
+            vec![
+                "a/cheap-small",
+                "c/free-mid",
+                "b/pricey-large",
+                "d/free-mid"
+            ]
+        );
+        assert_eq!(PickerSort::Score.next(), PickerSort::Context);
+    }
+
+    #[test]
+    fn test_models_json_roundtrip_and_cost_order() {
+        let model = |id: &str, price: f64| Model {
+            id: id.to_string(),
+            name: id.to_string(),
+            context_length: 32768,
+            pricing_prompt: price,
+            pricing_completion: price * 2.0,
+            pricing_cache_read: None,
+            pricing_cache_write: None,
+            capabilities: vec!["tools".into()],
+        };
+        let models = vec![
+            model("b/pricey", 0.5),
+            model("a/free", 0.0),
+            model("c/cheap", 0.1),
+        ];
+
+        let sorted = sort_by_cost(&models);
+        let ids: Vec<_> = sorted.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["a/free", "c/cheap", "b/pricey"]);
+
+        let json = serde_json::to_string_pretty(&sorted).unwrap();
+        let parsed: Vec<Model> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[2].pricing_completion, 1.0);
+        assert_eq!(parsed[0].capabilities, vec!["tools".to_string()]);
+
+        // Caches written before capabilities existed still load
+        let old = r#"{"id":"x/y","name":"Y","context_length":1,"pricing_prompt":0.0,"pricing_completion":0.0}"#;
+        assert!(serde_json::from_str::<Model>(old)
+            .unwrap()
+            .capabilities
+            .is_empty());
+    }
+
     #[test]
     fn test_display_name() {
         let model = Model {
@@ -182,7 +873,128 @@ mod tests {
             context_length: 8192,
             pricing_prompt: 0.0,
             pricing_completion: 0.0,
+            pricing_cache_read: None,
+            pricing_cache_write: None,
+            capabilities: vec![],
         };
         assert_eq!(model.display_name(), "llama-3.2-3b-instruct:free");
     }
+
+    fn test_cache_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("hyle_test_{}_{}.json", name, std::process::id()))
+    }
+
+    fn sample_models() -> Vec<Model> {
+        vec![Model {
+            id: "fetched/model".into(),
+            name: "Fetched".into(),
+            context_length: 8192,
+            pricing_prompt: 0.0,
+            pricing_completion: 0.0,
+            pricing_cache_read: None,
+            pricing_cache_write: None,
+            capabilities: vec![],
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_cache_triggers_refetch() {
+        let path = test_cache_path("models_corrupt");
+        fs::write(&path, "{\"models\": [{\"id\": \"trunc").unwrap();
+
+        let models = load_or_fetch_at(&path, || async { Ok(sample_models()) })
+            .await
+            .unwrap();
+        assert_eq!(models[0].id, "fetched/model");
+
+        // The bad cache was replaced with a good one
+        let cache = read_cache_at(&path).unwrap().unwrap();
+        assert_eq!(cache.version, CACHE_VERSION);
+
+        // Second call is served from cache
+        let models = load_or_fetch_at(&path, || async { anyhow::bail!("should not fetch") })
+            .await
+            .unwrap();
+        assert_eq!(models.len(), 1);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_reload_updates_model_list() {
+        let path = test_cache_path("models_reload");
+        let _ = fs::remove_file(&path);
+        let free = |id: &str| Model {
+            id: id.into(),
+            pricing_prompt: 0.0,
+            capabilities: vec!["tools".into()],
+            ..sample_models()[0].clone()
+        };
+
+        // Nothing cached: fetched and written
+        let first = reload_at(&path, false, || async { Ok(vec![free("a/one:free")]) })
+            .await
+            .unwrap();
+        assert!(first.fetched);
+        assert_eq!(first.fallbacks, vec!["a/one:free".to_string()]);
+
+        // Fresh cache is reused unless forced
+        let cached = reload_at(&path, false, || async { anyhow::bail!("should not fetch") })
+            .await
+            .unwrap();
+        assert!(!cached.fetched);
+        assert_eq!(cached.models.len(), 1);
+
+        let forced = reload_at(&path, true, || async {
+            Ok(vec![free("a/one:free"), free("b/two:free")])
+        })
+        .await
+        .unwrap();
+        assert!(forced.fetched);
+        assert_eq!(forced.fallbacks.len(), 2);
+        assert!(forced.fallbacks.contains(&"b/two:free".to_string()));
+        assert!(forced.summary().starts_with("Loaded 2 models from the API"));
+
+        // A failed fetch reports and keeps the cache
+        let err = reload_at(&path, true, || async { anyhow::bail!("offline") })
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("offline"));
+        assert_eq!(read_cache_at(&path).unwrap().unwrap().models.len(), 2);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_cache_version_migration() {
+        let path = test_cache_path("models_v0");
+        let v0 = serde_json::json!({
+            "models": [{
+                "id": "old/model",
+                "name": "Old",
+                "context_length": 4096,
+                "pricing_prompt": 0.0,
+                "pricing_completion": 0.0
+            }],
+            "fetched_at": chrono::Utc::now()
+        });
+        fs::write(&path, v0.to_string()).unwrap();
+
+        let cache = read_cache_at(&path).unwrap().unwrap();
+        assert_eq!(cache.version, CACHE_VERSION);
+        assert_eq!(cache.models[0].id, "old/model");
+
+        // A cache from a newer hyle is reported rather than misread, and kept
+        let future = serde_json::json!({
+            "version": CACHE_VERSION + 1,
+            "models": [],
+            "fetched_at": chrono::Utc::now()
+        });
+        fs::write(&path, future.to_string()).unwrap();
+        let err = read_cache_at(&path).unwrap_err();
+        assert!(format!("{:#}", err).contains("unsupported cache version"));
+        assert!(path.exists());
+
+        let _ = fs::remove_file(&path);
+    }
 }
@@ -76,6 +76,12 @@ pub struct Project {
     pub log: Vec<ProjectEvent>,
     pub hyle_pid: Option<u32>,
     pub url: Option<String>,
+    /// Models dispatched for this project, oldest first
+    #[serde(default)]
+    pub models_tried: Vec<String>,
+    /// Model of the current (or last successful) attempt
+    #[serde(default)]
+    pub model_used: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -91,6 +97,16 @@ pub enum ProjectStatus {
     Completed,
 }
 
+impl ProjectStatus {
+    /// Still in progress, so a restart leaves it without a worker
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            Self::Pending | Self::Scaffolding | Self::Building | Self::Testing | Self::Deploying
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectEvent {
     pub timestamp: DateTime<Utc>,
@@ -102,6 +118,9 @@ pub struct ProjectEvent {
 // ORCHESTRATOR STATE
 // ═══════════════════════════════════════════════════════════════
 
+/// Project records saved on shutdown, relative to the projects root
+pub const STATE_FILE: &str = ".hyle-orchestrator.json";
+
 pub struct Orchestrator {
     pub projects: HashMap<String, Project>,
     pub projects_root: PathBuf,
@@ -139,6 +158,8 @@ impl Orchestrator {
             }],
             hyle_pid: None,
             url: None,
+            models_tried: Vec::new(),
+            model_used: None,
         };
 
         self.projects.insert(id.clone(), project);
@@ -176,6 +197,39 @@ impl Orchestrator {
             project.updated_at = Utc::now();
         }
     }
+
+    /// Persist project records under the projects root
+    pub fn save_state(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.projects)?;
+        fs::write(self.projects_root.join(STATE_FILE), json).context("Failed to save projects")
+    }
+
+    /// Restore records saved by `save_state`, returning how many were loaded.
+    /// Projects that were still in progress are marked failed.
+    pub fn load_state(&mut self) -> Result<usize> {
+        let path = self.projects_root.join(STATE_FILE);
+        if !path.exists() {
+            return Ok(0);
+        }
+        let mut projects: HashMap<String, Project> =
+            serde_json::from_str(&fs::read_to_string(&path)?)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+        for project in projects.values_mut() {
+            if project.status.is_active() {
+                project.status = ProjectStatus::Failed;
+                project.hyle_pid = None;
+                project.updated_at = Utc::now();
+                project.log.push(ProjectEvent {
+                    timestamp: Utc::now(),
+                    kind: "error".into(),
+                    message: "Interrupted by server shutdown".into(),
+                });
+            }
+        }
+        let count = projects.len();
+        self.projects.extend(projects);
+        Ok(count)
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════
@@ -746,25 +800,52 @@ Begin implementation now.
     )
 }
 
+/// Build output of the dispatched instance, relative to the project dir
+pub const BUILD_LOG: &str = ".hyle-build.log";
+
 /// Dispatch a hyle instance to build a project
+///
+/// stdout and stderr go to `BUILD_LOG` so progress can be tailed while it runs.
 pub fn dispatch_hyle(
     hyle_binary: &Path,
     project_dir: &Path,
     prompt: &str,
+    model: &str,
 ) -> Result<std::process::Child> {
+    let log = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(project_dir.join(BUILD_LOG))
+        .context("Failed to open build log")?;
+
     let child = Command::new(hyle_binary)
         .arg("--trust") // Auto-approve tool calls
         .arg(prompt)
         .current_dir(project_dir)
+        .env("HYLE_MODEL", model)
         .stdin(Stdio::null())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
+        .stdout(Stdio::from(log.try_clone()?))
+        .stderr(Stdio::from(log))
         .spawn()
         .context("Failed to spawn hyle instance")?;
 
     Ok(child)
 }
 
+/// Last `lines` lines of a project's build log (empty if nothing logged yet)
+pub fn tail_build_log(project_dir: &Path, lines: usize) -> Result<Vec<String>> {
+    let path = project_dir.join(BUILD_LOG);
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let text = String::from_utf8_lossy(&bytes);
+    let all: Vec<&str> = text.lines().collect();
+    let start = all.len().saturating_sub(lines);
+    Ok(all[start..].iter().map(|l| l.to_string()).collect())
+}
+
 // ═══════════════════════════════════════════════════════════════
 // UTILITIES
 // ═══════════════════════════════════════════════════════════════
@@ -884,4 +965,30 @@ fn main() {
         assert_eq!(extract_subdomain("subdomain = ../etc"), None);
         assert_eq!(extract_subdomain("subdomain = foo/bar"), None);
     }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let root =
+            std::env::temp_dir().join(format!("hyle_test_orch_state_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+
+        let mut orch = Orchestrator::new(root.clone(), PathBuf::from("hyle"), "test".into());
+        let id = orch
+            .submit_project("# Saved\nA project that outlives a restart")
+            .unwrap();
+        orch.set_status(&id, ProjectStatus::Building);
+        orch.save_state().unwrap();
+
+        let mut restored = Orchestrator::new(root.clone(), PathBuf::from("hyle"), "test".into());
+        assert_eq!(restored.load_state().unwrap(), 1);
+        // The build had no worker left after the restart
+        let project = restored.get_project(&id).unwrap();
+        assert_eq!(project.status, ProjectStatus::Failed);
+        assert_eq!(
+            project.log.last().unwrap().message,
+            "Interrupted by server shutdown"
+        );
+
+        fs::remove_dir_all(&root).ok();
+    }
 }
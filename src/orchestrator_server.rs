@@ -7,18 +7,27 @@ use anyhow::Result;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 use crate::intake::INTAKE_HTML;
 use crate::orchestrator::{
     build_dispatch_prompt, dispatch_hyle, generate_nginx_config, generate_systemd_service,
-    scaffold_project, Orchestrator, Project, ProjectStatus,
+    scaffold_project, tail_build_log, Orchestrator, Project, ProjectStatus,
 };
+use crate::server::{authorized, shutdown_signal, Drain, WithoutToken, DRAIN_TIMEOUT};
+
+/// Default number of build log lines returned by /log
+const DEFAULT_LOG_LINES: usize = 50;
 
 /// Shared orchestrator state
 pub struct OrchestratorState {
     pub orchestrator: Orchestrator,
     pub domain: String,
+    /// Bearer token for status and log endpoints (HYLE_ORCHESTRATOR_TOKEN)
+    pub auth_token: Option<String>,
+    /// Set once shutdown starts; new submissions are refused
+    pub draining: bool,
 }
 
 /// Run the orchestrator server
@@ -27,11 +36,22 @@ pub async fn run_orchestrator(port: u16, projects_root: PathBuf, domain: String)
     use tokio::net::TcpListener;
 
     let hyle_binary = std::env::current_exe()?;
-    let orchestrator = Orchestrator::new(projects_root.clone(), hyle_binary, domain.clone());
+    let mut orchestrator = Orchestrator::new(projects_root.clone(), hyle_binary, domain.clone());
+    match orchestrator.load_state() {
+        Ok(0) => {}
+        Ok(n) => println!("Restored {} project(s)", n),
+        Err(e) => eprintln!("Failed to restore projects: {}", e),
+    }
+
+    let auth_token = std::env::var("HYLE_ORCHESTRATOR_TOKEN")
+        .ok()
+        .filter(|t| !t.is_empty());
 
     let state = Arc::new(RwLock::new(OrchestratorState {
         orchestrator,
         domain: domain.clone(),
+        auth_token,
+        draining: false,
     }));
 
     // Bind to all interfaces for external access
@@ -52,18 +72,39 @@ pub async fn run_orchestrator(port: u16, projects_root: PathBuf, domain: String)
     println!("║    GET  /api/projects     - List all projects              ║");
     println!("║    POST /api/projects     - Submit new project             ║");
     println!("║    GET  /api/projects/:id - Get project details            ║");
+    println!("║    GET  /api/projects/:id/status - Build status (auth)     ║");
+    println!("║    GET  /api/projects/:id/log    - Tail build log (auth)   ║");
     println!("╚════════════════════════════════════════════════════════════╝");
     println!("\nPress Ctrl-C to stop\n");
 
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    let mut drain = Drain::default();
+
     loop {
-        let (mut socket, peer) = listener.accept().await?;
+        if drain.finished(false) {
+            break;
+        }
+        let (mut socket, peer) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = &mut shutdown, if !drain.is_draining() => {
+                println!("Shutting down: finishing in-flight requests...");
+                drain.start(DRAIN_TIMEOUT);
+                state.write().await.draining = true;
+                continue;
+            }
+            _ = tokio::time::sleep(Duration::from_millis(250)), if drain.is_draining() => continue,
+        };
+        let in_flight = drain.track();
         let state = state.clone();
 
         tokio::spawn(async move {
+            let _in_flight = in_flight;
             let (reader, mut writer) = socket.split();
             let mut reader = BufReader::new(reader);
             let mut request = String::new();
             let mut content_length = 0usize;
+            let mut authorization = None;
 
             // Read request line
             if reader.read_line(&mut request).await.is_err() {
@@ -91,6 +132,9 @@ pub async fn run_orchestrator(port: u16, projects_root: PathBuf, domain: String)
                         }
                     }
                 }
+                if line.to_lowercase().starts_with("authorization:") {
+                    authorization = line.split_once(':').map(|(_, v)| v.trim().to_string());
+                }
             }
 
             // Read body
@@ -122,14 +166,30 @@ pub async fn run_orchestrator(port: u16, projects_root: PathBuf, domain: String)
                 ("POST", "/api/projects") => handle_create_project(&state, &body).await,
                 ("OPTIONS", _) => cors_preflight(),
                 (_, p) if p.starts_with("/api/projects/") => {
-                    let id = p.trim_start_matches("/api/projects/");
+                    let (route, query) = p.split_once('?').unwrap_or((p, ""));
+                    let rest = route.trim_start_matches("/api/projects/");
+                    let (id, sub) = rest.split_once('/').unwrap_or((rest, ""));
+                    let token = state.read().await.auth_token.clone();
                     if !id
                         .chars()
                         .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
                     {
                         json_response(400, r#"{"error": "Invalid project ID"}"#)
-                    } else {
+                    } else if sub.is_empty() {
                         handle_get_project(&state, id).await
+                    } else if !authorized(
+                        token.as_deref(),
+                        authorization.as_deref(),
+                        "",
+                        WithoutToken::Closed,
+                    ) {
+                        json_response(401, r#"{"error": "Unauthorized"}"#)
+                    } else if sub == "status" {
+                        handle_project_status(&state, id).await
+                    } else if sub == "log" {
+                        handle_project_log(&state, id, log_lines(query)).await
+                    } else {
+                        json_response(404, r#"{"error": "Not found"}"#)
                     }
                 }
                 _ => json_response(404, r#"{"error": "Not found"}"#),
@@ -138,6 +198,13 @@ pub async fn run_orchestrator(port: u16, projects_root: PathBuf, domain: String)
             let _ = writer.write_all(response.as_bytes()).await;
         });
     }
+
+    // Dispatched hyle instances run on as separate processes; keep their records
+    if let Err(e) = state.read().await.orchestrator.save_state() {
+        eprintln!("Failed to save projects: {}", e);
+    }
+    println!("Orchestrator stopped");
+    Ok(())
 }
 
 fn html_response(body: &str) -> String {
@@ -158,8 +225,10 @@ fn json_response(status: u16, body: &str) -> String {
         200 => "OK",
         201 => "Created",
         400 => "Bad Request",
+        401 => "Unauthorized",
         404 => "Not Found",
         500 => "Internal Server Error",
+        503 => "Service Unavailable",
         _ => "Unknown",
     };
 
@@ -169,7 +238,7 @@ fn json_response(status: u16, body: &str) -> String {
         Content-Length: {}\r\n\
         Access-Control-Allow-Origin: *\r\n\
         Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
-        Access-Control-Allow-Headers: Content-Type\r\n\
+        Access-Control-Allow-Headers: Content-Type, Authorization\r\n\
         \r\n\
         {}",
         status,
@@ -183,7 +252,7 @@ fn cors_preflight() -> String {
     "HTTP/1.1 204 No Content\r\n\
     Access-Control-Allow-Origin: *\r\n\
     Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
-    Access-Control-Allow-Headers: Content-Type\r\n\
+    Access-Control-Allow-Headers: Content-Type, Authorization\r\n\
     \r\n"
         .to_string()
 }
@@ -208,6 +277,70 @@ async fn handle_get_project(state: &Arc<RwLock<OrchestratorState>>, id: &str) ->
     }
 }
 
+/// Parse `lines=N` from a query string
+fn log_lines(query: &str) -> usize {
+    query
+        .split('&')
+        .find_map(|kv| kv.strip_prefix("lines="))
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(DEFAULT_LOG_LINES)
+        .min(1000)
+}
+
+async fn handle_project_status(state: &Arc<RwLock<OrchestratorState>>, id: &str) -> String {
+    let state = state.read().await;
+    let project = match state.orchestrator.get_project(id) {
+        Some(project) => project,
+        None => return json_response(404, r#"{"error": "Project not found"}"#),
+    };
+
+    let recent = &project.log[project.log.len().saturating_sub(10)..];
+    let json = serde_json::json!({
+        "id": project.id,
+        "status": project.status,
+        "updated_at": project.updated_at,
+        "hyle_pid": project.hyle_pid,
+        "url": project.url,
+        "models_tried": project.models_tried,
+        "model_used": project.model_used,
+        "recent_events": recent,
+    });
+    json_response(200, &json.to_string())
+}
+
+async fn handle_project_log(
+    state: &Arc<RwLock<OrchestratorState>>,
+    id: &str,
+    lines: usize,
+) -> String {
+    let project_dir = {
+        let state = state.read().await;
+        match state.orchestrator.get_project(id) {
+            Some(project) => project.project_dir.clone(),
+            None => return json_response(404, r#"{"error": "Project not found"}"#),
+        }
+    };
+
+    match tail_build_log(&project_dir, lines) {
+        Ok(lines) => json_response(
+            200,
+            &serde_json::json!({ "id": id, "lines": lines }).to_string(),
+        ),
+        Err(e) => json_response(
+            500,
+            &serde_json::json!({ "error": e.to_string() }).to_string(),
+        ),
+    }
+}
+
+/// Model the dispatched instance will use, as it resolves it
+fn dispatch_model() -> String {
+    std::env::var("HYLE_MODEL")
+        .ok()
+        .or_else(|| crate::config::Config::load().ok()?.default_model)
+        .unwrap_or_else(|| "default".into())
+}
+
 async fn handle_create_project(state: &Arc<RwLock<OrchestratorState>>, body: &str) -> String {
     #[derive(serde::Deserialize)]
     struct CreateRequest {
@@ -224,6 +357,9 @@ async fn handle_create_project(state: &Arc<RwLock<OrchestratorState>>, body: &st
     }
 
     let mut state = state.write().await;
+    if state.draining {
+        return json_response(503, r#"{"error": "Server is shutting down"}"#);
+    }
 
     // Extract values we need before getting mutable project reference
     let hyle_binary = state.orchestrator.hyle_binary.clone();
@@ -290,10 +426,13 @@ async fn handle_create_project(state: &Arc<RwLock<OrchestratorState>>, body: &st
     let prompt = build_dispatch_prompt(&project_clone);
 
     // Spawn hyle instance in background
-    match dispatch_hyle(&hyle_binary, &project_dir, &prompt) {
+    let model = dispatch_model();
+    project.models_tried.push(model.clone());
+    match dispatch_hyle(&hyle_binary, &project_dir, &prompt, &model) {
         Ok(child) => {
             let pid = child.id();
             project.hyle_pid = Some(pid);
+            project.model_used = Some(model);
             project.log.push(crate::orchestrator::ProjectEvent {
                 timestamp: chrono::Utc::now(),
                 kind: "dispatch".into(),
@@ -305,7 +444,7 @@ async fn handle_create_project(state: &Arc<RwLock<OrchestratorState>>, body: &st
             project.log.push(crate::orchestrator::ProjectEvent {
                 timestamp: chrono::Utc::now(),
                 kind: "error".into(),
-                message: format!("Failed to dispatch hyle: {}", e),
+                message: format!("Failed to dispatch hyle with {}: {}", model, e),
             });
         }
     }
@@ -336,4 +475,79 @@ mod tests {
         assert!(resp.contains("200 OK"));
         assert!(resp.contains("text/html"));
     }
+
+    fn test_state(root: &std::path::Path, token: Option<&str>) -> Arc<RwLock<OrchestratorState>> {
+        Arc::new(RwLock::new(OrchestratorState {
+            orchestrator: Orchestrator::new(
+                root.to_path_buf(),
+                PathBuf::from("hyle"),
+                "test".into(),
+            ),
+            domain: "test".into(),
+            auth_token: token.map(String::from),
+            draining: false,
+        }))
+    }
+
+    #[test]
+    fn test_log_lines() {
+        assert_eq!(log_lines(""), DEFAULT_LOG_LINES);
+        assert_eq!(log_lines("lines=5"), 5);
+        assert_eq!(log_lines("x=1&lines=20"), 20);
+        assert_eq!(log_lines("lines=99999"), 1000);
+    }
+
+    #[tokio::test]
+    async fn test_project_status_and_log() {
+        let root =
+            std::env::temp_dir().join(format!("hyle_test_orch_status_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let state = test_state(&root, Some("tok"));
+
+        let id = {
+            let mut s = state.write().await;
+            let id = s
+                .orchestrator
+                .submit_project("# Counter\nA tiny counter web app with a button")
+                .unwrap();
+            let project = s.orchestrator.projects.get_mut(&id).unwrap();
+            project.status = ProjectStatus::Building;
+            project.models_tried.push("model/a".into());
+            project.models_tried.push("model/b".into());
+            project.model_used = Some("model/b".into());
+            std::fs::create_dir_all(&project.project_dir).unwrap();
+            let log: String = (1..=30).map(|i| format!("step {}\n", i)).collect();
+            std::fs::write(
+                project.project_dir.join(crate::orchestrator::BUILD_LOG),
+                log,
+            )
+            .unwrap();
+            id
+        };
+
+        let resp = handle_project_status(&state, &id).await;
+        let body: serde_json::Value =
+            serde_json::from_str(resp.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["status"], "building");
+        assert_eq!(
+            body["models_tried"],
+            serde_json::json!(["model/a", "model/b"])
+        );
+        assert_eq!(body["model_used"], "model/b");
+        assert_eq!(body["recent_events"][0]["kind"], "created");
+
+        let resp = handle_project_log(&state, &id, 3).await;
+        let body: serde_json::Value =
+            serde_json::from_str(resp.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(
+            body["lines"],
+            serde_json::json!(["step 28", "step 29", "step 30"])
+        );
+
+        assert!(handle_project_status(&state, "missing")
+            .await
+            .contains("404"));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
 }
@@ -0,0 +1,366 @@
+//! OpenTelemetry export - spans for API requests and tool calls
+//!
+//! Off unless an OTLP endpoint is configured (`OTEL_EXPORTER_OTLP_ENDPOINT`,
+//! or `otel_endpoint` in config). While off, `start` returns `None` and
+//! nothing is measured or allocated. When on, each finished span is posted
+//! as OTLP/HTTP JSON to `<endpoint>/v1/traces`.
+
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// ═══════════════════════════════════════════════════════════════
+// SPANS
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttrValue {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl From<&str> for AttrValue {
+    fn from(v: &str) -> Self {
+        AttrValue::Str(v.to_string())
+    }
+}
+
+impl From<String> for AttrValue {
+    fn from(v: String) -> Self {
+        AttrValue::Str(v)
+    }
+}
+
+impl From<i64> for AttrValue {
+    fn from(v: i64) -> Self {
+        AttrValue::Int(v)
+    }
+}
+
+impl From<u32> for AttrValue {
+    fn from(v: u32) -> Self {
+        AttrValue::Int(v as i64)
+    }
+}
+
+impl From<bool> for AttrValue {
+    fn from(v: bool) -> Self {
+        AttrValue::Bool(v)
+    }
+}
+
+/// A finished span, ready for export
+#[derive(Debug, Clone)]
+pub struct SpanData {
+    pub name: String,
+    pub trace_id: String,
+    pub span_id: String,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub attributes: Vec<(String, AttrValue)>,
+    pub error: Option<String>,
+}
+
+impl SpanData {
+    pub fn attr(&self, key: &str) -> Option<&AttrValue> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+}
+
+pub trait SpanExporter: Send + Sync {
+    fn export(&self, span: SpanData);
+}
+
+/// An open span; call `end` to record latency and export it
+pub struct Span {
+    data: SpanData,
+    started: Instant,
+    exporter: Arc<dyn SpanExporter>,
+}
+
+impl Span {
+    pub fn new(exporter: Arc<dyn SpanExporter>, name: &str) -> Self {
+        let trace_id = uuid::Uuid::new_v4().simple().to_string();
+        let span_id = uuid::Uuid::new_v4().simple().to_string()[..16].to_string();
+        Self {
+            data: SpanData {
+                name: name.to_string(),
+                trace_id,
+                span_id,
+                start: SystemTime::now(),
+                end: SystemTime::now(),
+                attributes: Vec::new(),
+                error: None,
+            },
+            started: Instant::now(),
+            exporter,
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<AttrValue>) {
+        self.data.attributes.push((key.to_string(), value.into()));
+    }
+
+    /// Close the span with its outcome and hand it to the exporter
+    pub fn end(mut self, error: Option<&str>) {
+        let elapsed = self.started.elapsed();
+        self.set("hyle.latency_ms", elapsed.as_millis() as i64);
+        self.set("hyle.success", error.is_none());
+        self.data.end = self.data.start + elapsed;
+        self.data.error = error.map(|e| e.to_string());
+        self.exporter.export(self.data);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// GLOBAL EXPORTER
+// ═══════════════════════════════════════════════════════════════
+
+static EXPORTER: OnceLock<Option<Arc<dyn SpanExporter>>> = OnceLock::new();
+
+/// Endpoint from the environment, falling back to config
+fn endpoint() -> Option<String> {
+    std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .ok()
+        .or_else(|| crate::config::Config::load().ok()?.otel_endpoint)
+        .filter(|e| !e.trim().is_empty())
+}
+
+/// Turn on export for this process when an endpoint is configured (first
+/// call wins)
+pub fn init() {
+    let exporter = endpoint().map(|e| Arc::new(OtlpExporter::new(&e)) as Arc<dyn SpanExporter>);
+    let _ = EXPORTER.set(exporter);
+}
+
+/// Start a span, or `None` when export is off
+pub fn start(name: &str) -> Option<Span> {
+    let exporter = EXPORTER.get()?.as_ref()?;
+    Some(Span::new(exporter.clone(), name))
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ATTRIBUTES
+// ═══════════════════════════════════════════════════════════════
+
+/// Attributes for a chat completion request
+pub fn record_request(span: &mut Span, model: &str, usage: &crate::client::TokenUsage) {
+    span.set("gen_ai.system", "openrouter");
+    span.set("gen_ai.request.model", model);
+    span.set("gen_ai.usage.input_tokens", usage.prompt_tokens);
+    span.set("gen_ai.usage.output_tokens", usage.completion_tokens);
+    if usage.cache_read_tokens > 0 {
+        span.set("gen_ai.usage.cache_read_tokens", usage.cache_read_tokens);
+    }
+}
+
+/// Attributes for a tool call
+pub fn record_tool(span: &mut Span, call: &crate::tools::ToolCall) {
+    span.set("hyle.tool.name", call.name.as_str());
+    span.set("hyle.tool.id", call.id.as_str());
+}
+
+// ═══════════════════════════════════════════════════════════════
+// OTLP/HTTP JSON
+// ═══════════════════════════════════════════════════════════════
+
+fn unix_nanos(t: SystemTime) -> String {
+    t.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+        .to_string()
+}
+
+fn attr_json(key: &str, value: &AttrValue) -> serde_json::Value {
+    let value = match value {
+        AttrValue::Str(s) => serde_json::json!({ "stringValue": s }),
+        // OTLP JSON encodes 64-bit integers as strings
+        AttrValue::Int(i) => serde_json::json!({ "intValue": i.to_string() }),
+        AttrValue::Bool(b) => serde_json::json!({ "boolValue": b }),
+    };
+    serde_json::json!({ "key": key, "value": value })
+}
+
+/// Encode spans as an OTLP `ExportTraceServiceRequest`
+pub fn to_otlp_json(spans: &[SpanData]) -> serde_json::Value {
+    let spans: Vec<_> = spans
+        .iter()
+        .map(|s| {
+            let status = match &s.error {
+                None => serde_json::json!({ "code": 1 }),
+                Some(e) => serde_json::json!({ "code": 2, "message": e }),
+            };
+            let attributes: Vec<_> = s.attributes.iter().map(|(k, v)| attr_json(k, v)).collect();
+            serde_json::json!({
+                "traceId": s.trace_id,
+                "spanId": s.span_id,
+                "name": s.name,
+                "kind": 3, // SPAN_KIND_CLIENT
+                "startTimeUnixNano": unix_nanos(s.start),
+                "endTimeUnixNano": unix_nanos(s.end),
+                "attributes": attributes,
+                "status": status,
+            })
+        })
+        .collect();
+
+    let resource = vec![
+        attr_json("service.name", &AttrValue::from("hyle")),
+        attr_json(
+            "service.version",
+            &AttrValue::from(env!("CARGO_PKG_VERSION")),
+        ),
+    ];
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": { "attributes": resource },
+            "scopeSpans": [{
+                "scope": { "name": "hyle" },
+                "spans": spans,
+            }]
+        }]
+    })
+}
+
+/// Posts each finished span to an OTLP/HTTP collector
+pub struct OtlpExporter {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl OtlpExporter {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            url: format!("{}/v1/traces", endpoint.trim_end_matches('/')),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl SpanExporter for OtlpExporter {
+    fn export(&self, span: SpanData) {
+        // Tool calls also end on blocking threads; any runtime will do
+        let handle = match tokio::runtime::Handle::try_current() {
+            Ok(handle) => handle,
+            Err(_) => return,
+        };
+        let request = self.client.post(&self.url).json(&to_otlp_json(&[span]));
+        handle.spawn(async move {
+            let _ = request.send().await;
+        });
+    }
+}
+
+/// Keeps spans in memory, for tests and debugging
+#[derive(Default)]
+pub struct InMemoryExporter {
+    spans: Mutex<Vec<SpanData>>,
+}
+
+impl InMemoryExporter {
+    pub fn spans(&self) -> Vec<SpanData> {
+        self.spans.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+impl SpanExporter for InMemoryExporter {
+    fn export(&self, span: SpanData) {
+        if let Ok(mut spans) = self.spans.lock() {
+            spans.push(span);
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TESTS
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::TokenUsage;
+
+    #[test]
+    fn test_request_span_attributes() {
+        let exporter = Arc::new(InMemoryExporter::default());
+        let mut span = Span::new(exporter.clone(), "chat");
+        let usage = TokenUsage {
+            prompt_tokens: 120,
+            completion_tokens: 45,
+            total_tokens: 165,
+            ..Default::default()
+        };
+        record_request(&mut span, "openai/gpt-4o", &usage);
+        span.end(None);
+
+        let spans = exporter.spans();
+        assert_eq!(spans.len(), 1);
+        let s = &spans[0];
+        assert_eq!(s.name, "chat");
+        assert_eq!(s.trace_id.len(), 32);
+        assert_eq!(s.span_id.len(), 16);
+        assert_eq!(
+            s.attr("gen_ai.request.model"),
+            Some(&AttrValue::from("openai/gpt-4o"))
+        );
+        assert_eq!(
+            s.attr("gen_ai.usage.input_tokens"),
+            Some(&AttrValue::Int(120))
+        );
+        assert_eq!(
+            s.attr("gen_ai.usage.output_tokens"),
+            Some(&AttrValue::Int(45))
+        );
+        assert_eq!(s.attr("hyle.success"), Some(&AttrValue::Bool(true)));
+        assert!(matches!(s.attr("hyle.latency_ms"), Some(AttrValue::Int(_))));
+        assert!(s.end >= s.start);
+    }
+
+    #[test]
+    fn test_tool_span_records_failure() {
+        let exporter = Arc::new(InMemoryExporter::default());
+        let call = crate::tools::ToolCall::new("bash", serde_json::json!({"command": "false"}));
+        let mut span = Span::new(exporter.clone(), "execute_tool");
+        record_tool(&mut span, &call);
+        span.end(Some("exit status 1"));
+
+        let s = &exporter.spans()[0];
+        assert_eq!(s.attr("hyle.tool.name"), Some(&AttrValue::from("bash")));
+        assert_eq!(s.attr("hyle.success"), Some(&AttrValue::Bool(false)));
+        assert_eq!(s.error.as_deref(), Some("exit status 1"));
+    }
+
+    #[test]
+    fn test_otlp_json_shape() {
+        let exporter = Arc::new(InMemoryExporter::default());
+        let mut span = Span::new(exporter.clone(), "chat");
+        span.set("gen_ai.usage.input_tokens", 7u32);
+        span.end(Some("boom"));
+
+        let json = to_otlp_json(&exporter.spans());
+        let resource = &json["resourceSpans"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "hyle"
+        );
+        let s = &resource["scopeSpans"][0]["spans"][0];
+        assert_eq!(s["name"], "chat");
+        assert_eq!(s["status"]["code"], 2);
+        assert_eq!(s["status"]["message"], "boom");
+        assert_eq!(s["attributes"][0]["value"]["intValue"], "7");
+        assert!(s["startTimeUnixNano"]
+            .as_str()
+            .unwrap()
+            .parse::<u128>()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_start_is_none_when_off() {
+        // Nothing initialized in tests: no span, no cost
+        assert!(start("chat").is_none());
+    }
+}
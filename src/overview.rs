@@ -0,0 +1,246 @@
+//! Repository overview for cold starts
+//!
+//! `hyle summarize` (and `/summarize`) gathers what hyle can learn about a
+//! repo without a model - project detection, module breakdown, the
+//! `tree` and `stats` tool output - and asks the model to turn it into a
+//! short structured summary. The result is cached in `.hyle/overview.md`
+//! and included as context in later sessions.
+
+use anyhow::{bail, Context, Result};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use crate::bootstrap::SelfAnalyzer;
+use crate::project::{repo_stats, Project};
+use crate::tools::{render_tree, TreeOptions};
+
+/// Cached overview, relative to the project root
+pub const OVERVIEW_FILE: &str = ".hyle/overview.md";
+
+/// Most of the cached overview sent as context with each request
+const MAX_CONTEXT_BYTES: usize = 6_000;
+
+/// Modules listed in the prompt, largest first
+const MAX_MODULES: usize = 30;
+
+/// Tree depth for the prompt; deeper levels rarely change the summary
+const TREE_DEPTH: usize = 3;
+
+/// Everything the summary is built from, gathered without the model
+#[derive(Debug, Clone, Default)]
+pub struct OverviewInputs {
+    /// `Project::context_for_llm`, empty when no project was detected
+    pub project: String,
+    /// One line per source module, largest first
+    pub modules: Vec<String>,
+    pub tree: String,
+    /// `stats` tool JSON
+    pub stats: String,
+}
+
+impl OverviewInputs {
+    pub fn gather(root: &Path) -> Result<Self> {
+        let project = Project::detect(root);
+        let modules = match &project {
+            Some(p) => SelfAnalyzer::for_project(p.clone())
+                .analyze_modules()?
+                .iter()
+                .take(MAX_MODULES)
+                .map(|m| {
+                    let path = m.path.strip_prefix(root).unwrap_or(&m.path);
+                    let mut line = format!(
+                        "{} ({}): {} lines, {} fns, {} tests",
+                        m.name,
+                        path.display(),
+                        m.lines,
+                        m.functions,
+                        m.tests
+                    );
+                    if !m.dependencies.is_empty() {
+                        line.push_str(&format!("; uses {}", m.dependencies.join(", ")));
+                    }
+                    line
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        let tree = render_tree(
+            root,
+            &TreeOptions {
+                max_depth: TREE_DEPTH,
+                ..TreeOptions::default()
+            },
+        )?;
+        let stats = serde_json::to_string_pretty(&repo_stats(root, None)?)?;
+
+        Ok(Self {
+            project: project.map(|p| p.context_for_llm()).unwrap_or_default(),
+            modules,
+            tree,
+            stats,
+        })
+    }
+
+    /// Prompt asking for the structured summary
+    pub fn prompt(&self) -> String {
+        let mut prompt = String::from(
+            "Summarize this repository for a developer opening it for the first time.\n\
+             Answer in Markdown with exactly these sections:\n\
+             ## Architecture\n## Entry points\n## Key modules\n## Build and test\n\
+             Be concise, name real file paths, and only describe what the material below shows.\n\n",
+        );
+        if !self.project.is_empty() {
+            prompt.push_str(&self.project);
+        }
+        if !self.modules.is_empty() {
+            prompt.push_str("<modules>\n");
+            for line in &self.modules {
+                prompt.push_str(line);
+                prompt.push('\n');
+            }
+            prompt.push_str("</modules>\n");
+        }
+        prompt.push_str(&format!("<tree>\n{}</tree>\n", self.tree));
+        prompt.push_str(&format!("<stats>\n{}\n</stats>\n", self.stats));
+        prompt
+    }
+}
+
+pub fn overview_path(root: &Path) -> PathBuf {
+    root.join(OVERVIEW_FILE)
+}
+
+/// Cached overview, if one has been generated
+pub fn load(root: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(overview_path(root)).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+pub fn save(root: &Path, overview: &str) -> Result<PathBuf> {
+    let path = overview_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, format!("{}\n", overview.trim()))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Gather inputs, ask `complete` for the summary and cache it
+pub async fn summarize<F, Fut>(root: &Path, complete: F) -> Result<PathBuf>
+where
+    F: FnOnce(String) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let inputs = OverviewInputs::gather(root)?;
+    let summary = complete(inputs.prompt()).await?;
+    if summary.trim().is_empty() {
+        bail!("Model returned an empty overview");
+    }
+    save(root, &summary)
+}
+
+/// System prompt section with the cached overview, trimmed to stay light
+pub fn context_section(root: &Path) -> Option<String> {
+    let mut text = load(root)?;
+    if text.len() > MAX_CONTEXT_BYTES {
+        let mut cut = MAX_CONTEXT_BYTES;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        text.truncate(cut);
+        text.push_str("\n[overview truncated]");
+    }
+    Some(format!("<repo_overview>\n{}\n</repo_overview>\n", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("hyle_test_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"hyle-test\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/main.rs"),
+            "mod store;\n\nfn main() {\n    store::open();\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/store.rs"),
+            "use crate::main;\n\npub fn open() {}\n\n#[test]\nfn test_open() {}\n",
+        )
+        .unwrap();
+        root
+    }
+
+    #[test]
+    fn test_overview_inputs() {
+        let root = fixture("overview_inputs");
+
+        let inputs = OverviewInputs::gather(&root).unwrap();
+        assert!(inputs
+            .project
+            .contains("<project name=\"hyle_test_overview_inputs"));
+        assert_eq!(inputs.modules.len(), 2);
+        assert!(inputs
+            .modules
+            .iter()
+            .any(|m| m.starts_with("store (src/store.rs): 6 lines, 2 fns, 1 tests")));
+        assert!(inputs.tree.contains("main.rs"));
+        assert!(inputs.stats.contains("\"lines\""));
+
+        let prompt = inputs.prompt();
+        for section in [
+            "## Architecture",
+            "## Entry points",
+            "## Key modules",
+            "## Build and test",
+        ] {
+            assert!(prompt.contains(section), "{}", section);
+        }
+        for tag in ["<modules>", "<tree>", "<stats>", "</project>"] {
+            assert!(prompt.contains(tag), "{}", tag);
+        }
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_caches_overview() {
+        let root = fixture("overview_cache");
+        assert!(context_section(&root).is_none());
+
+        let path = summarize(&root, |prompt| async move {
+            assert!(prompt.contains("<tree>"));
+            Ok("## Architecture\nOne binary.\n".to_string())
+        })
+        .await
+        .unwrap();
+        assert_eq!(path, root.join(OVERVIEW_FILE));
+        assert_eq!(load(&root).unwrap(), "## Architecture\nOne binary.");
+        let section = context_section(&root).unwrap();
+        assert!(section.starts_with("<repo_overview>\n## Architecture"));
+
+        // An empty answer leaves the cache alone
+        assert!(summarize(&root, |_| async { Ok("  ".to_string()) })
+            .await
+            .is_err());
+        assert!(load(&root).unwrap().contains("One binary."));
+
+        // Long overviews are cut for context
+        save(&root, &"x".repeat(MAX_CONTEXT_BYTES * 2)).unwrap();
+        let section = context_section(&root).unwrap();
+        assert!(section.len() < MAX_CONTEXT_BYTES + 100);
+        assert!(section.contains("[overview truncated]"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -8,11 +8,14 @@
 
 #![allow(dead_code)] // Forward-looking module for self-bootstrapping
 
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::bootstrap::{classify_todo, TodoPriority};
+
 // ═══════════════════════════════════════════════════════════════
 // PROJECT DETECTION
 // ═══════════════════════════════════════════════════════════════
@@ -201,7 +204,7 @@ impl Project {
 // ═══════════════════════════════════════════════════════════════
 
 /// Find project root by walking up looking for markers
-fn find_project_root(start: &Path) -> Option<PathBuf> {
+pub fn find_project_root(start: &Path) -> Option<PathBuf> {
     let markers = [
         "Cargo.toml",
         "package.json",
@@ -238,7 +241,7 @@ fn find_git_root(start: &Path) -> Option<PathBuf> {
 }
 
 /// Detect project type from root directory
-fn detect_project_type(root: &Path) -> ProjectType {
+pub fn detect_project_type(root: &Path) -> ProjectType {
     if root.join("Cargo.toml").exists() {
         ProjectType::Rust
     } else if root.join("package.json").exists() {
@@ -252,7 +255,7 @@ fn detect_project_type(root: &Path) -> ProjectType {
     }
 }
 
-fn format_project_type(pt: &ProjectType) -> &'static str {
+pub fn format_project_type(pt: &ProjectType) -> &'static str {
     match pt {
         ProjectType::Rust => "Rust",
         ProjectType::Node => "Node.js",
@@ -323,6 +326,111 @@ fn collect_files_recursive(
     }
 }
 
+// ═══════════════════════════════════════════════════════════════
+// REPO STATS
+// ═══════════════════════════════════════════════════════════════
+
+/// Extensions counted by `repo_stats`, regardless of project type
+const STATS_EXTENSIONS: &[&str] = &[
+    "rs", "py", "js", "ts", "jsx", "tsx", "go", "c", "h", "cpp", "java", "rb", "sh", "md", "toml",
+    "json", "yaml", "yml",
+];
+
+/// How many of the largest files to report
+const STATS_LARGEST: usize = 10;
+
+/// Quick repository metrics, serialized as JSON for the `stats` tool
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoStats {
+    pub scope: String,
+    pub files: usize,
+    pub lines: usize,
+    /// Keyed by file extension
+    pub languages: BTreeMap<String, LanguageStats>,
+    pub largest: Vec<FileLines>,
+    pub todo: usize,
+    /// FIXME/XXX/HACK markers
+    pub fixme: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LanguageStats {
+    pub files: usize,
+    pub lines: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileLines {
+    pub path: String,
+    pub lines: usize,
+}
+
+/// Collect metrics for `root`, optionally narrowed to `subpath`
+pub fn repo_stats(root: &Path, subpath: Option<&str>) -> Result<RepoStats> {
+    let scope = match subpath.filter(|s| !s.is_empty() && *s != ".") {
+        Some(sub) => root.join(sub),
+        None => root.to_path_buf(),
+    };
+    if !scope.exists() {
+        anyhow::bail!("Path not found: {}", scope.display());
+    }
+
+    let files = if scope.is_file() {
+        let lines = fs::read_to_string(&scope)
+            .map(|s| s.lines().count())
+            .unwrap_or(0);
+        vec![SourceFile {
+            relative: subpath.unwrap_or_default().to_string(),
+            language: scope
+                .extension()
+                .map(|e| e.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: scope.clone(),
+            lines,
+        }]
+    } else {
+        collect_source_files(&scope, STATS_EXTENSIONS)
+    };
+
+    let mut languages: BTreeMap<String, LanguageStats> = BTreeMap::new();
+    let (mut todo, mut fixme) = (0, 0);
+    for file in &files {
+        let lang = languages.entry(file.language.clone()).or_default();
+        lang.files += 1;
+        lang.lines += file.lines;
+
+        if let Ok(content) = fs::read_to_string(&file.path) {
+            for line in content.lines() {
+                match classify_todo(line) {
+                    Some(TodoPriority::High) => fixme += 1,
+                    Some(TodoPriority::Medium) => todo += 1,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let mut largest: Vec<FileLines> = files
+        .iter()
+        .map(|f| FileLines {
+            path: f.relative.clone(),
+            lines: f.lines,
+        })
+        .collect();
+    largest.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.path.cmp(&b.path)));
+    largest.truncate(STATS_LARGEST);
+
+    Ok(RepoStats {
+        scope: subpath.unwrap_or(".").to_string(),
+        files: files.len(),
+        lines: files.iter().map(|f| f.lines).sum(),
+        languages,
+        largest,
+        todo,
+        fixme,
+    })
+}
+
 // ═══════════════════════════════════════════════════════════════
 // SELF-AWARENESS (for hyle developing itself)
 // ═══════════════════════════════════════════════════════════════
@@ -486,4 +594,41 @@ mod tests {
 
         let _ = fs::remove_dir_all(&temp);
     }
+
+    #[test]
+    fn test_repo_stats() {
+        let temp = env::temp_dir().join(format!("test_repo_stats_{}", std::process::id()));
+        let _ = fs::create_dir_all(temp.join("src"));
+        let _ = fs::create_dir_all(temp.join("target"));
+        let _ = fs::write(temp.join("Cargo.toml"), "[package]\nname = \"x\"\n");
+        let _ = fs::write(
+            temp.join("src/main.rs"),
+            "// TODO: split this up\nfn main() {}\n// FIXME: handle errors\n",
+        );
+        let _ = fs::write(temp.join("src/lib.rs"), "pub fn a() {}\n");
+        let _ = fs::write(temp.join("README.md"), "# x\n\nhello\n// TODO later\n");
+        let _ = fs::write(temp.join("target/junk.rs"), "ignored\n");
+
+        let stats = repo_stats(&temp, None).unwrap();
+        assert_eq!(stats.files, 4);
+        assert_eq!(stats.lines, 2 + 3 + 1 + 4);
+        assert_eq!(stats.languages["rs"].files, 2);
+        assert_eq!(stats.languages["rs"].lines, 4);
+        assert_eq!(stats.languages["md"].lines, 4);
+        assert_eq!(stats.largest[0].path, "README.md");
+        assert_eq!(stats.todo, 2);
+        assert_eq!(stats.fixme, 1);
+
+        let scoped = repo_stats(&temp, Some("src")).unwrap();
+        assert_eq!(scoped.files, 2);
+        assert_eq!(scoped.scope, "src");
+        assert!(!scoped.languages.contains_key("md"));
+
+        let json = serde_json::to_value(&scoped).unwrap();
+        assert_eq!(json["lines"], 4);
+
+        assert!(repo_stats(&temp, Some("missing")).is_err());
+
+        let _ = fs::remove_dir_all(&temp);
+    }
 }
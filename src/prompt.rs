@@ -4,8 +4,12 @@
 
 #![allow(dead_code)] // Forward-looking module for LLM integration
 
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::environ::EnvSnapshot;
 use crate::intent::IntentStack;
-use crate::project::Project;
+use crate::project::{Project, ProjectType};
 
 // ═══════════════════════════════════════════════════════════════
 // SYSTEM PROMPT BUILDER
@@ -200,17 +204,31 @@ fn default_tools() -> Vec<String> {
         "bash".into(),
         "glob".into(),
         "grep".into(),
+        "tree".into(),
+        "stats".into(),
+        "docs".into(),
+        "environ".into(),
+        "move".into(),
+        "delete".into(),
+        "ask_user".into(),
     ]
 }
 
 fn tool_description(name: &str) -> &'static str {
     match name {
-        "read" => "Read file contents. Args: {path: string}",
+        "read" => "Read file contents. Args: {path: string, start_line?: int, end_line?: int, cursor?: string} (cursor: from a large file's previous chunk)",
         "write" => "Write file contents. Args: {path: string, content: string}",
         "edit" => "Edit file with search/replace. Args: {path: string, old: string, new: string}",
-        "bash" => "Execute shell command. Args: {command: string}",
-        "glob" => "Find files matching pattern. Args: {pattern: string}",
-        "grep" => "Search file contents. Args: {pattern: string, path?: string}",
+        "bash" => "Execute shell command. Args: {command: string} or {argv: [program, ...args]} to run without a shell",
+        "glob" => "Find files matching pattern. Args: {pattern: string, offset?: int, limit?: int}",
+        "grep" => "Search file contents. Args: {pattern: string, path?: string, offset?: int, limit?: int}",
+        "tree" => "Directory tree with file counts. Args: {path?: string, max_depth?: int, show_hidden?: bool}",
+        "stats" => "Repo metrics as JSON (lines by language, largest files, TODOs). Args: {path?: string}",
+        "docs" => "Search project docs and doc comments, with file:line citations. Args: {query: string, limit?: int}",
+        "environ" => "Execution environment as JSON: OS, shell, installed tools, git version",
+        "move" => "Rename or move a file (git mv in a repo). Args: {from: string, to: string, force?: bool}",
+        "delete" => "Move a file into .hyle/trash, restorable with /undo. Args: {path: string, recursive?: bool}",
+        "ask_user" => "Ask the user a clarifying question when the task is ambiguous. Args: {question: string}",
         "git_status" => "Get git status",
         "git_diff" => "Get git diff. Args: {staged?: bool}",
         "git_commit" => "Create commit. Args: {message: string}",
@@ -218,6 +236,232 @@ fn tool_description(name: &str) -> &'static str {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════
+// CUSTOM INSTRUCTIONS
+// ═══════════════════════════════════════════════════════════════
+
+/// Project instruction files, in priority order (first found wins)
+pub const PROJECT_INSTRUCTION_FILES: &[&str] = &[".hyle/system.md", "AGENTS.md"];
+
+/// Global instructions file name inside the config dir
+pub const GLOBAL_INSTRUCTION_FILE: &str = "system.md";
+
+/// Instructions loaded from disk, with where each part came from
+#[derive(Debug, Clone, Default)]
+pub struct CustomInstructions {
+    pub global: Option<(PathBuf, String)>,
+    pub project: Option<(PathBuf, String)>,
+}
+
+impl CustomInstructions {
+    /// Load global (`<config_dir>/system.md`) and project instructions.
+    /// Missing or blank files are skipped.
+    pub fn load(project_root: Option<&Path>, global_dir: Option<&Path>) -> Self {
+        let global =
+            global_dir.and_then(|dir| read_instructions(&dir.join(GLOBAL_INSTRUCTION_FILE)));
+        let project = project_root.and_then(|root| {
+            PROJECT_INSTRUCTION_FILES
+                .iter()
+                .find_map(|name| read_instructions(&root.join(name)))
+        });
+        Self { global, project }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.global.is_none() && self.project.is_none()
+    }
+
+    /// Render as a system prompt section; project comes last so it
+    /// takes precedence over global conventions.
+    pub fn to_section(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut section = String::from("<instructions>\n");
+        if let Some((_, text)) = &self.global {
+            section.push_str(text);
+            section.push('\n');
+        }
+        if let Some((_, text)) = &self.project {
+            if self.global.is_some() {
+                section.push_str("\nProject instructions (take precedence):\n");
+            }
+            section.push_str(text);
+            section.push('\n');
+        }
+        section.push_str("</instructions>\n");
+        Some(section)
+    }
+}
+
+fn read_instructions(path: &Path) -> Option<(PathBuf, String)> {
+    let text = fs::read_to_string(path).ok()?;
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some((path.to_path_buf(), text.to_string()))
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// PROJECT HINTS
+// ═══════════════════════════════════════════════════════════════
+
+/// How to build, test, format and lint one project
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProjectHints {
+    pub kind: &'static str,
+    pub build: Option<String>,
+    pub test: Option<String>,
+    pub format: Option<String>,
+    pub lint: Option<String>,
+    /// Where tests live, e.g. "inline #[cfg(test)] modules, tests/"
+    pub tests: Option<String>,
+    /// Commands the hints rely on that aren't on PATH
+    pub missing: Vec<&'static str>,
+}
+
+impl ProjectHints {
+    /// Hints for a `project_type` project at `root`, using only tools found
+    /// in `env`. None for an unknown project type.
+    pub fn detect(project_type: &ProjectType, root: &Path, env: &EnvSnapshot) -> Option<Self> {
+        let dirs = |names: &[&str]| {
+            names
+                .iter()
+                .filter(|d| root.join(d).is_dir())
+                .map(|d| format!("{}/", d))
+                .collect::<Vec<_>>()
+        };
+        let mut hints = match project_type {
+            ProjectType::Rust => Self {
+                kind: "Rust",
+                build: Some("cargo build".into()),
+                test: Some("cargo test".into()),
+                format: Some("cargo fmt".into()),
+                lint: Some("cargo clippy".into()),
+                tests: Some(
+                    std::iter::once("inline #[cfg(test)] modules".to_string())
+                        .chain(dirs(&["tests"]))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+                missing: vec!["cargo"],
+            },
+            ProjectType::Node => node_hints(root, env),
+            ProjectType::Python => {
+                let ruff = env.has("ruff");
+                Self {
+                    kind: "Python",
+                    test: Some(if env.has("pytest") {
+                        "pytest".into()
+                    } else {
+                        "python3 -m unittest".into()
+                    }),
+                    format: if ruff {
+                        Some("ruff format".into())
+                    } else if env.has("black") {
+                        Some("black .".into())
+                    } else {
+                        None
+                    },
+                    lint: ruff.then(|| "ruff check".into()),
+                    tests: Some(dirs(&["tests", "test"]).join(", ")),
+                    missing: vec!["python3"],
+                    ..Self::default()
+                }
+            }
+            ProjectType::Go => Self {
+                kind: "Go",
+                build: Some("go build ./...".into()),
+                test: Some("go test ./...".into()),
+                format: Some("gofmt -w .".into()),
+                lint: Some("go vet ./...".into()),
+                tests: Some("*_test.go next to the code".into()),
+                missing: vec!["go"],
+            },
+            ProjectType::Unknown => return None,
+        };
+        hints.tests = hints.tests.filter(|t| !t.is_empty());
+        hints.missing.retain(|tool| !env.has(tool));
+        Some(hints)
+    }
+
+    /// Render as a short system prompt section
+    pub fn to_section(&self) -> String {
+        let mut section = format!("<project_hints>\nThis is a {} project.\n", self.kind);
+        let commands = [
+            ("build", &self.build),
+            ("test", &self.test),
+            ("format", &self.format),
+            ("lint", &self.lint),
+        ];
+        for (name, command) in commands {
+            if let Some(command) = command {
+                section.push_str(&format!("- {}: `{}`\n", name, command));
+            }
+        }
+        if let Some(tests) = &self.tests {
+            section.push_str(&format!("- tests in: {}\n", tests));
+        }
+        if !self.missing.is_empty() {
+            section.push_str(&format!(
+                "- not on PATH: {} (these commands will fail)\n",
+                self.missing.join(", ")
+            ));
+        }
+        section.push_str("</project_hints>\n");
+        section
+    }
+}
+
+/// npm, pnpm or yarn, following the lockfile, and only the package.json
+/// scripts that exist
+fn node_hints(root: &Path, env: &EnvSnapshot) -> ProjectHints {
+    let pm = if root.join("pnpm-lock.yaml").exists() && env.has("pnpm") {
+        "pnpm"
+    } else if root.join("yarn.lock").exists() && env.has("yarn") {
+        "yarn"
+    } else {
+        "npm"
+    };
+    let scripts: serde_json::Map<String, serde_json::Value> =
+        fs::read_to_string(root.join("package.json"))
+            .ok()
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+            .and_then(|pkg| pkg.get("scripts")?.as_object().cloned())
+            .unwrap_or_default();
+    let script = |name: &str| {
+        scripts.contains_key(name).then(|| match name {
+            "test" => format!("{} test", pm),
+            _ => format!("{} run {}", pm, name),
+        })
+    };
+    let tests = ["test", "tests", "__tests__"]
+        .iter()
+        .filter(|d| root.join(d).is_dir())
+        .map(|d| format!("{}/", d))
+        .collect::<Vec<_>>()
+        .join(", ");
+    ProjectHints {
+        kind: "Node.js",
+        build: script("build"),
+        test: script("test"),
+        format: script("format"),
+        lint: script("lint"),
+        tests: Some(tests),
+        missing: vec![pm],
+    }
+}
+
+/// Hints section for the project containing `dir`, if its type is known
+pub fn project_hints_for(dir: &Path) -> Option<String> {
+    let root = crate::project::find_project_root(dir)?;
+    let project_type = crate::project::detect_project_type(&root);
+    ProjectHints::detect(&project_type, &root, crate::environ::snapshot()).map(|h| h.to_section())
+}
+
 // ═══════════════════════════════════════════════════════════════
 // QUICK BUILDERS
 // ═══════════════════════════════════════════════════════════════
@@ -295,6 +539,113 @@ mod tests {
         assert_eq!(tool_description("unknown"), "Unknown tool");
     }
 
+    #[test]
+    fn test_custom_instructions_included() {
+        let root = std::env::temp_dir().join(format!("hyle_test_instr_{}", std::process::id()));
+        let global = root.join("global");
+        let project = root.join("project");
+        fs::create_dir_all(&global).unwrap();
+        fs::create_dir_all(project.join(".hyle")).unwrap();
+        fs::write(
+            global.join(GLOBAL_INSTRUCTION_FILE),
+            "Prefer small commits.",
+        )
+        .unwrap();
+        fs::write(project.join("AGENTS.md"), "Use anyhow for errors.").unwrap();
+
+        let instr = CustomInstructions::load(Some(&project), Some(&global));
+        let section = instr.to_section().unwrap();
+        assert!(section.contains("Prefer small commits."));
+        assert!(section.contains("Use anyhow for errors."));
+        assert!(section.find("small commits") < section.find("anyhow"));
+
+        // .hyle/system.md takes priority over AGENTS.md
+        fs::write(project.join(".hyle/system.md"), "Tabs, not spaces.").unwrap();
+        let instr = CustomInstructions::load(Some(&project), None);
+        let prompt = SystemPrompt::new()
+            .add_instruction(&instr.to_section().unwrap())
+            .build();
+        assert!(prompt.contains("Tabs, not spaces."));
+        assert!(!prompt.contains("anyhow"));
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    fn env_with(tools: &[&str]) -> EnvSnapshot {
+        EnvSnapshot {
+            os: "linux".into(),
+            arch: "x86_64".into(),
+            shell: None,
+            tools: crate::environ::SNAPSHOT_TOOLS
+                .iter()
+                .map(|t| (t.to_string(), tools.contains(t)))
+                .collect(),
+            git_version: None,
+            terminal: None,
+            in_tmux: false,
+        }
+    }
+
+    #[test]
+    fn test_project_hints_follow_type_and_tools() {
+        let root = std::env::temp_dir().join(format!("hyle_test_hints_{}", std::process::id()));
+        fs::create_dir_all(root.join("tests")).unwrap();
+
+        let rust = ProjectHints::detect(&ProjectType::Rust, &root, &env_with(&["cargo"])).unwrap();
+        let section = rust.to_section();
+        assert!(section.contains("This is a Rust project."));
+        assert!(section.contains("- build: `cargo build`"));
+        assert!(section.contains("- format: `cargo fmt`"));
+        assert!(section.contains("inline #[cfg(test)] modules, tests/"));
+        assert!(!section.contains("not on PATH"));
+        // Short enough to send on every request
+        assert!(section.len() < 400);
+
+        let no_cargo = ProjectHints::detect(&ProjectType::Rust, &root, &env_with(&[])).unwrap();
+        assert_eq!(no_cargo.missing, vec!["cargo"]);
+        assert!(no_cargo.to_section().contains("not on PATH: cargo"));
+
+        // Python: formatter and test runner follow what's installed
+        let py = ProjectHints::detect(&ProjectType::Python, &root, &env_with(&["python3", "ruff"]))
+            .unwrap();
+        assert_eq!(py.test.as_deref(), Some("python3 -m unittest"));
+        assert_eq!(py.format.as_deref(), Some("ruff format"));
+        let py = ProjectHints::detect(&ProjectType::Python, &root, &env_with(&["pytest", "black"]))
+            .unwrap();
+        assert_eq!(py.test.as_deref(), Some("pytest"));
+        assert_eq!(py.format.as_deref(), Some("black ."));
+        assert_eq!(py.lint, None);
+
+        // Node: the lockfile picks the package manager, scripts pick commands
+        fs::write(
+            root.join("package.json"),
+            r#"{"scripts": {"test": "vitest", "lint": "eslint ."}}"#,
+        )
+        .unwrap();
+        fs::write(root.join("pnpm-lock.yaml"), "").unwrap();
+        let node =
+            ProjectHints::detect(&ProjectType::Node, &root, &env_with(&["node", "pnpm"])).unwrap();
+        assert_eq!(node.test.as_deref(), Some("pnpm test"));
+        assert_eq!(node.lint.as_deref(), Some("pnpm run lint"));
+        assert_eq!(node.build, None);
+        assert!(node.missing.is_empty());
+        let node = ProjectHints::detect(&ProjectType::Node, &root, &env_with(&["node"])).unwrap();
+        assert_eq!(node.test.as_deref(), Some("npm test"));
+        assert_eq!(node.missing, vec!["npm"]);
+
+        assert!(ProjectHints::detect(&ProjectType::Unknown, &root, &env_with(&[])).is_none());
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_custom_instructions_missing() {
+        let root = std::env::temp_dir().join(format!("hyle_test_noinstr_{}", std::process::id()));
+        let instr = CustomInstructions::load(Some(&root), Some(&root));
+        assert!(instr.is_empty());
+        assert!(instr.to_section().is_none());
+    }
+
     #[test]
     fn test_full_prompt() {
         let prompt = full_prompt(None, None);
@@ -0,0 +1,346 @@
+//! Recipes: repeatable multi-step workflows
+//!
+//! A recipe is a JSON file of ordered steps, each either an agent prompt or a
+//! slash command. Steps run in order and a failing step stops the recipe
+//! unless it is marked `continue_on_error`.
+//!
+//! ```json
+//! {
+//!   "name": "add-endpoint",
+//!   "steps": [
+//!     { "name": "scaffold", "prompt": "Add a GET /ping endpoint" },
+//!     { "name": "test", "command": "/test" },
+//!     { "name": "document", "prompt": "Document /ping in README.md", "continue_on_error": true }
+//!   ]
+//! }
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+// ═══════════════════════════════════════════════════════════════
+// RECIPE FORMAT
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recipe {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Step {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Agent prompt to run
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Slash command to run, e.g. "/test"
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Text the output must contain for the step to pass
+    #[serde(default)]
+    pub expect: Option<String>,
+    /// Keep going if this step fails
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+/// What a step does, once validated
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepAction<'a> {
+    Prompt(&'a str),
+    Command(&'a str),
+}
+
+impl Step {
+    pub fn action(&self) -> StepAction<'_> {
+        match (&self.prompt, &self.command) {
+            (Some(prompt), _) => StepAction::Prompt(prompt),
+            (None, Some(command)) => StepAction::Command(command),
+            (None, None) => unreachable!("validated in Recipe::parse"),
+        }
+    }
+
+    /// Whether `output` satisfies `expect` (always true without one)
+    pub fn expectation_met(&self, output: &str) -> bool {
+        match &self.expect {
+            Some(expected) => output.contains(expected.as_str()),
+            None => true,
+        }
+    }
+
+    /// Display name, falling back to the step number
+    pub fn label(&self, index: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("step {}", index + 1))
+    }
+}
+
+impl Recipe {
+    pub fn parse(json: &str) -> Result<Self> {
+        let recipe: Recipe = serde_json::from_str(json).context("Invalid recipe")?;
+        if recipe.steps.is_empty() {
+            bail!("Recipe '{}' has no steps", recipe.name);
+        }
+        for (i, step) in recipe.steps.iter().enumerate() {
+            match (&step.prompt, &step.command) {
+                (Some(_), Some(_)) => bail!("{}: has both prompt and command", step.label(i)),
+                (None, None) => bail!("{}: needs a prompt or a command", step.label(i)),
+                (None, Some(cmd)) if !cmd.starts_with('/') => {
+                    bail!("{}: command must start with '/'", step.label(i))
+                }
+                _ => {}
+            }
+        }
+        Ok(recipe)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&json)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// RUNNING
+// ═══════════════════════════════════════════════════════════════
+
+/// What running a single step produced
+#[derive(Debug, Clone)]
+pub struct StepOutcome {
+    pub success: bool,
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepReport {
+    pub name: String,
+    pub status: StepStatus,
+    pub output: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeReport {
+    pub recipe: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub success: bool,
+    pub steps: Vec<StepReport>,
+}
+
+/// Longest step output kept in a report
+const REPORT_OUTPUT_CHARS: usize = 4000;
+
+/// Run each step through `run_step`, stopping at the first failure that
+/// isn't marked `continue_on_error` (later steps are recorded as skipped)
+pub async fn run<F, Fut>(recipe: &Recipe, mut run_step: F) -> RecipeReport
+where
+    F: FnMut(usize, &Step) -> Fut,
+    Fut: Future<Output = StepOutcome>,
+{
+    let started_at = chrono::Utc::now();
+    let mut steps = Vec::new();
+    let mut success = true;
+    let mut stopped = false;
+
+    for (i, step) in recipe.steps.iter().enumerate() {
+        if stopped {
+            steps.push(StepReport {
+                name: step.label(i),
+                status: StepStatus::Skipped,
+                output: String::new(),
+                duration_ms: 0,
+            });
+            continue;
+        }
+
+        let start = Instant::now();
+        let outcome = run_step(i, step).await;
+        let passed = outcome.success && step.expectation_met(&outcome.output);
+
+        if !passed {
+            success = false;
+            stopped = !step.continue_on_error;
+        }
+        steps.push(StepReport {
+            name: step.label(i),
+            status: if passed {
+                StepStatus::Passed
+            } else {
+                StepStatus::Failed
+            },
+            output: truncate_output(&outcome.output),
+            duration_ms: start.elapsed().as_millis() as u64,
+        });
+    }
+
+    RecipeReport {
+        recipe: recipe.name.clone(),
+        started_at,
+        success,
+        steps,
+    }
+}
+
+fn truncate_output(output: &str) -> String {
+    match output.char_indices().nth(REPORT_OUTPUT_CHARS) {
+        Some((idx, _)) => format!("{}\n... (truncated)", &output[..idx]),
+        None => output.to_string(),
+    }
+}
+
+impl RecipeReport {
+    /// Save under `dir` as `<recipe>-<timestamp>.json`
+    pub fn save_in(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let slug: String = self
+            .recipe
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect();
+        let path = dir.join(format!(
+            "{}-{}.json",
+            slug,
+            self.started_at.format("%Y%m%d-%H%M%S")
+        ));
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    /// Save to the default reports dir (~/.local/state/hyle/recipes/)
+    pub fn save(&self) -> Result<PathBuf> {
+        self.save_in(&crate::config::state_dir()?.join("recipes"))
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TESTS
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPE: &str = r#"{
+        "name": "add-endpoint",
+        "steps": [
+            { "name": "scaffold", "prompt": "Add a GET /ping endpoint" },
+            { "name": "test", "command": "/test", "expect": "passed" },
+            { "prompt": "Document /ping", "continue_on_error": true }
+        ]
+    }"#;
+
+    /// Stub runner: steps whose index is in `fail` fail, the rest pass
+    async fn stub(recipe: &Recipe, fail: &[usize]) -> (RecipeReport, Vec<usize>) {
+        let mut ran = Vec::new();
+        let report = run(recipe, |i, _| {
+            ran.push(i);
+            let success = !fail.contains(&i);
+            std::future::ready(StepOutcome {
+                success,
+                output: "all tests passed".into(),
+            })
+        })
+        .await;
+        (report, ran)
+    }
+
+    #[test]
+    fn test_parse_recipe() {
+        let recipe = Recipe::parse(RECIPE).unwrap();
+        assert_eq!(recipe.name, "add-endpoint");
+        assert_eq!(recipe.steps.len(), 3);
+        assert_eq!(
+            recipe.steps[0].action(),
+            StepAction::Prompt("Add a GET /ping endpoint")
+        );
+        assert_eq!(recipe.steps[1].action(), StepAction::Command("/test"));
+        assert!(recipe.steps[2].continue_on_error);
+        assert_eq!(recipe.steps[2].label(2), "step 3");
+    }
+
+    #[test]
+    fn test_parse_recipe_rejects_bad_steps() {
+        let both = r#"{"name": "x", "steps": [{"prompt": "a", "command": "/b"}]}"#;
+        assert!(Recipe::parse(both).is_err());
+        let neither = r#"{"name": "x", "steps": [{"name": "empty"}]}"#;
+        assert!(Recipe::parse(neither).is_err());
+        let bare = r#"{"name": "x", "steps": [{"command": "test"}]}"#;
+        assert!(Recipe::parse(bare).is_err());
+        assert!(Recipe::parse(r#"{"name": "x", "steps": []}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stops_on_failure() {
+        let recipe = Recipe::parse(RECIPE).unwrap();
+        let (report, ran) = stub(&recipe, &[0]).await;
+        assert_eq!(ran, vec![0]);
+        assert!(!report.success);
+        let statuses: Vec<_> = report.steps.iter().map(|s| s.status).collect();
+        assert_eq!(
+            statuses,
+            vec![StepStatus::Failed, StepStatus::Skipped, StepStatus::Skipped]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_continue_on_error() {
+        let recipe = Recipe::parse(RECIPE).unwrap();
+        let (report, ran) = stub(&recipe, &[2]).await;
+        assert_eq!(ran, vec![0, 1, 2]);
+        assert!(!report.success);
+        assert_eq!(report.steps[2].status, StepStatus::Failed);
+
+        let mut lenient = recipe.clone();
+        lenient.steps[0].continue_on_error = true;
+        let (report, ran) = stub(&lenient, &[0]).await;
+        assert_eq!(ran, vec![0, 1, 2]);
+        assert_eq!(report.steps[1].status, StepStatus::Passed);
+    }
+
+    #[tokio::test]
+    async fn test_expect_gates_step() {
+        let mut recipe = Recipe::parse(RECIPE).unwrap();
+        recipe.steps[1].expect = Some("0 failures".into());
+        let (report, _) = stub(&recipe, &[]).await;
+        assert_eq!(report.steps[1].status, StepStatus::Failed);
+        assert_eq!(report.steps[2].status, StepStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_report_persisted() {
+        let recipe = Recipe::parse(RECIPE).unwrap();
+        let (report, _) = stub(&recipe, &[]).await;
+        assert!(report.success);
+
+        let dir = std::env::temp_dir().join(format!("hyle_test_recipe_{}", std::process::id()));
+        let path = report.save_in(&dir).unwrap();
+        let saved: RecipeReport =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.steps.len(), 3);
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("add-endpoint-"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,317 @@
+//! Replay a saved session against a model
+//!
+//! `hyle --replay <session>` sends the session's user prompts again, in
+//! order, each with the replayed conversation so far as history. The new
+//! replies are saved as a fresh session and diffed against the originals,
+//! to see how a model change behaves on a known workload. Tool calls in the
+//! replies are not run; replies are compared as text.
+
+use anyhow::Result;
+use similar::TextDiff;
+use std::future::Future;
+
+use crate::client::TokenUsage;
+use crate::session::{Message, Session};
+
+// ═══════════════════════════════════════════════════════════════
+// WORKLOAD
+// ═══════════════════════════════════════════════════════════════
+
+/// One user prompt and the reply it originally got
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turn {
+    pub prompt: String,
+    /// Last assistant message before the next prompt, if any
+    pub original: Option<String>,
+}
+
+/// The user prompts of `messages` in order, each paired with its reply
+pub fn turns(messages: &[Message]) -> Vec<Turn> {
+    let mut turns: Vec<Turn> = Vec::new();
+    for message in messages {
+        match message.role.as_str() {
+            "user" => turns.push(Turn {
+                prompt: message.content.clone(),
+                original: None,
+            }),
+            "assistant" => {
+                if let Some(turn) = turns.last_mut() {
+                    turn.original = Some(message.content.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    turns
+}
+
+/// Leading system messages, sent ahead of every replayed prompt
+pub fn preamble(messages: &[Message]) -> Vec<serde_json::Value> {
+    messages
+        .iter()
+        .take_while(|m| m.role == "system")
+        .map(|m| serde_json::json!({"role": m.role, "content": m.content}))
+        .collect()
+}
+
+// ═══════════════════════════════════════════════════════════════
+// RUNNING
+// ═══════════════════════════════════════════════════════════════
+
+/// A prompt sent again and the reply it got this time
+#[derive(Debug, Clone)]
+pub struct ReplayedTurn {
+    pub prompt: String,
+    pub original: Option<String>,
+    pub response: String,
+    pub completion_tokens: u32,
+}
+
+impl ReplayedTurn {
+    /// Whether the reply differs from the original (ignoring surrounding whitespace)
+    pub fn changed(&self) -> bool {
+        self.original.as_deref().map(str::trim) != Some(self.response.trim())
+    }
+
+    /// Unified diff from the original reply to the new one
+    pub fn diff(&self) -> String {
+        TextDiff::from_lines(self.original.as_deref().unwrap_or(""), &self.response)
+            .unified_diff()
+            .context_radius(3)
+            .header("original", "replay")
+            .to_string()
+    }
+}
+
+/// Result of a replay; stops at the first failed request
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    pub turns: Vec<ReplayedTurn>,
+    /// Prompts in the workload, replayed or not
+    pub total: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub error: Option<String>,
+}
+
+/// Send each prompt through `ask(history, prompt)`, where history is
+/// `preamble` plus the prompts and new replies before it
+pub async fn run<F, Fut>(preamble: Vec<serde_json::Value>, turns: &[Turn], mut ask: F) -> Replay
+where
+    F: FnMut(Vec<serde_json::Value>, String) -> Fut,
+    Fut: Future<Output = Result<(String, TokenUsage)>>,
+{
+    let mut replay = Replay {
+        total: turns.len(),
+        ..Replay::default()
+    };
+    let mut history = preamble;
+    for (i, turn) in turns.iter().enumerate() {
+        match ask(history.clone(), turn.prompt.clone()).await {
+            Ok((response, usage)) => {
+                replay.prompt_tokens += usage.prompt_tokens as u64;
+                replay.completion_tokens += usage.completion_tokens as u64;
+                history.push(serde_json::json!({"role": "user", "content": turn.prompt}));
+                history.push(serde_json::json!({"role": "assistant", "content": response}));
+                replay.turns.push(ReplayedTurn {
+                    prompt: turn.prompt.clone(),
+                    original: turn.original.clone(),
+                    response,
+                    completion_tokens: usage.completion_tokens,
+                });
+            }
+            Err(e) => {
+                replay.error = Some(format!("prompt {}: {}", i + 1, e));
+                break;
+            }
+        }
+    }
+    replay
+}
+
+impl Replay {
+    /// Save the prompts and new replies into `session`
+    pub fn record(&self, session: &mut Session) -> Result<()> {
+        for turn in &self.turns {
+            session.add_user_message(&turn.prompt)?;
+            session.add_assistant_message(&turn.response, Some(turn.completion_tokens))?;
+        }
+        session.save_meta()
+    }
+
+    pub fn changed(&self) -> usize {
+        self.turns.iter().filter(|t| t.changed()).count()
+    }
+
+    /// Per-prompt diffs followed by a one-line summary
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        for (i, turn) in self.turns.iter().enumerate() {
+            let first_line = turn.prompt.lines().next().unwrap_or("");
+            let title: String = first_line.chars().take(60).collect();
+            out.push_str(&format!(
+                "── Prompt {}/{}: {} ──\n",
+                i + 1,
+                self.total,
+                title
+            ));
+            if turn.original.is_none() {
+                out.push_str("(no original reply)\n");
+                out.push_str(turn.response.trim_end());
+                out.push('\n');
+            } else if turn.changed() {
+                out.push_str(&turn.diff());
+            } else {
+                out.push_str("(unchanged)\n");
+            }
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "Replayed {} of {} prompts: {} changed, {} unchanged ({} prompt + {} completion tokens)",
+            self.turns.len(),
+            self.total,
+            self.changed(),
+            self.turns.len() - self.changed(),
+            self.prompt_tokens,
+            self.completion_tokens
+        ));
+        if let Some(e) = &self.error {
+            out.push_str(&format!("\nStopped at {}", e));
+        }
+        out
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TESTS
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded_session(root: &std::path::Path) -> Session {
+        let mut session = Session::new_in(root, "test/model").unwrap();
+        session.add_user_message("What is 2+2?").unwrap();
+        session.add_assistant_message("4", Some(1)).unwrap();
+        session.add_user_message("List the files").unwrap();
+        session
+            .add_assistant_message("{\"tool\": \"glob\"}", Some(5))
+            .unwrap();
+        session.add_system_message("tool output").unwrap();
+        session
+            .add_assistant_message("src/main.rs\nsrc/lib.rs", Some(6))
+            .unwrap();
+        session.add_user_message("Thanks").unwrap();
+        session
+    }
+
+    #[test]
+    fn test_turns_pair_prompts_with_final_replies() {
+        let root = std::env::temp_dir().join(format!("hyle_test_replay_{}", std::process::id()));
+        let session = recorded_session(&root);
+
+        let turns = turns(&session.messages);
+        assert_eq!(
+            turns.iter().map(|t| t.prompt.as_str()).collect::<Vec<_>>(),
+            vec!["What is 2+2?", "List the files", "Thanks"]
+        );
+        assert_eq!(turns[0].original.as_deref(), Some("4"));
+        assert_eq!(
+            turns[1].original.as_deref(),
+            Some("src/main.rs\nsrc/lib.rs")
+        );
+        assert_eq!(turns[2].original, None);
+        assert_eq!(preamble(&session.messages).len(), 1);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_replay_records_new_responses() {
+        let root =
+            std::env::temp_dir().join(format!("hyle_test_replay_run_{}", std::process::id()));
+        let session = recorded_session(&root);
+        let turns = turns(&session.messages);
+
+        let mut seen = Vec::new();
+        let replay = run(preamble(&session.messages), &turns, |history, prompt| {
+            seen.push((history.len(), prompt.clone()));
+            let response = match prompt.as_str() {
+                "What is 2+2?" => "4".to_string(),
+                "List the files" => "src/main.rs\nsrc/app.rs".to_string(),
+                _ => "You're welcome".to_string(),
+            };
+            async move {
+                let usage = TokenUsage {
+                    prompt_tokens: 10,
+                    completion_tokens: 2,
+                    ..Default::default()
+                };
+                Ok((response, usage))
+            }
+        })
+        .await;
+
+        // Every prompt in order, each with the replayed turns before it
+        assert_eq!(
+            seen,
+            vec![
+                (1, "What is 2+2?".to_string()),
+                (3, "List the files".to_string()),
+                (5, "Thanks".to_string()),
+            ]
+        );
+        assert_eq!(replay.turns.len(), 3);
+        assert!(!replay.turns[0].changed());
+        assert!(replay.turns[1].changed());
+        let diff = replay.turns[1].diff();
+        assert!(diff.contains("-src/lib.rs"));
+        assert!(diff.contains("+src/app.rs"));
+        assert!(!diff.contains("-src/main.rs"));
+        assert_eq!(replay.completion_tokens, 6);
+        let report = replay.report();
+        assert!(report.contains("(unchanged)"));
+        assert!(report.contains("(no original reply)\nYou're welcome"));
+        assert!(report.ends_with(
+            "Replayed 3 of 3 prompts: 2 changed, 1 unchanged (30 prompt + 6 completion tokens)"
+        ));
+
+        // The fresh transcript holds the new replies
+        let mut fresh = Session::new_in(&root, "other/model").unwrap();
+        replay.record(&mut fresh).unwrap();
+        let reloaded = Session::load_in(&root, &fresh.meta.id).unwrap();
+        let replayed = super::turns(&reloaded.messages);
+        assert_eq!(replayed.len(), 3);
+        assert_eq!(
+            replayed[1].original.as_deref(),
+            Some("src/main.rs\nsrc/app.rs")
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_replay_stops_at_failed_request() {
+        let turns = vec![
+            Turn {
+                prompt: "one".into(),
+                original: Some("1".into()),
+            },
+            Turn {
+                prompt: "two".into(),
+                original: Some("2".into()),
+            },
+        ];
+        let replay = run(Vec::new(), &turns, |_, prompt| async move {
+            if prompt == "two" {
+                anyhow::bail!("rate limited");
+            }
+            Ok(("1".to_string(), TokenUsage::default()))
+        })
+        .await;
+        assert_eq!(replay.turns.len(), 1);
+        assert_eq!(replay.error.as_deref(), Some("prompt 2: rate limited"));
+        assert!(replay.report().contains("Replayed 1 of 2 prompts"));
+    }
+}
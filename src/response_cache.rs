@@ -0,0 +1,123 @@
+//! Response cache - replay identical requests from disk
+//!
+//! Entries are keyed on a hash of the full request body (model, system
+//! prompt, messages, sampling params), so any change to what would be sent
+//! is a miss. Hits are replayed as the original token chunks. Opt in with
+//! `response_cache: true` in config; `--no-cache` bypasses it for one run.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long entries stay valid unless configured
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    created_at: chrono::DateTime<chrono::Utc>,
+    tokens: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+/// Cache key for a serialized request body
+pub fn cache_key(request: &str) -> String {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    request.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+impl ResponseCache {
+    pub fn new(dir: &Path, ttl: Duration) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            ttl,
+        }
+    }
+
+    /// Cache under ~/.cache/hyle/responses/
+    pub fn open(ttl: Duration) -> Result<Self> {
+        Ok(Self::new(
+            &crate::config::cache_dir()?.join("responses"),
+            ttl,
+        ))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// Token chunks of a fresh entry; expired entries are removed
+    pub fn get(&self, key: &str) -> Option<Vec<String>> {
+        let path = self.path(key);
+        let entry: Entry = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+        let age = (chrono::Utc::now() - entry.created_at)
+            .to_std()
+            .unwrap_or_default();
+        if age > self.ttl {
+            std::fs::remove_file(&path).ok();
+            return None;
+        }
+        Some(entry.tokens)
+    }
+
+    pub fn put(&self, key: &str, tokens: &[String]) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let entry = Entry {
+            created_at: chrono::Utc::now(),
+            tokens: tokens.to_vec(),
+        };
+        std::fs::write(self.path(key), serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TESTS
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(tag: &str, ttl: Duration) -> ResponseCache {
+        let dir = std::env::temp_dir().join(format!(
+            "hyle_test_response_cache_{}_{}",
+            tag,
+            std::process::id()
+        ));
+        ResponseCache::new(&dir, ttl)
+    }
+
+    #[test]
+    fn test_hit_and_miss() {
+        let cache = temp_cache("hit", DEFAULT_TTL);
+        let key = cache_key(r#"{"model":"m","messages":[]}"#);
+        assert_eq!(cache.get(&key), None);
+
+        let tokens = vec!["Hello".to_string(), ", world".to_string()];
+        cache.put(&key, &tokens).unwrap();
+        assert_eq!(cache.get(&key), Some(tokens));
+        assert_eq!(cache.get(&cache_key(r#"{"model":"other"}"#)), None);
+
+        std::fs::remove_dir_all(&cache.dir).ok();
+    }
+
+    #[test]
+    fn test_expired_entries_miss() {
+        let cache = temp_cache("ttl", Duration::ZERO);
+        let key = cache_key("request");
+        cache.put(&key, &["stale".to_string()]).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.get(&key), None);
+        assert!(!cache.path(&key).exists());
+
+        std::fs::remove_dir_all(&cache.dir).ok();
+    }
+}
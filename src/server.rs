@@ -5,13 +5,18 @@
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, RwLock};
 
-use crate::agent::{AgentConfig, AgentCore, AgentEvent};
+use crate::agent::{AgentConfig, AgentCore, AgentEvent, AgentResult, ToolGate};
+use crate::client::TokenUsage;
 use crate::config;
+use crate::session::IdleTimeout;
 
 // ═══════════════════════════════════════════════════════════════
 // API TYPES
@@ -34,6 +39,9 @@ pub struct PromptResponse {
     pub tool_calls: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Question the agent stopped on because it needed input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub needs_input: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -58,6 +66,9 @@ pub struct RateLimitInfo {
     pub requests_used: u32,
     pub tokens_used: u64,
     pub context_window: u64,
+    /// Spent on `/stream` chats, from cached model pricing
+    #[serde(default)]
+    pub cost_usd: f64,
 }
 
 /// Session/conversation for web UI
@@ -109,6 +120,157 @@ pub struct StreamEvent {
     pub message: Option<String>,
 }
 
+impl StreamEvent {
+    fn new(event_type: &str) -> Self {
+        Self {
+            event_type: event_type.into(),
+            token: None,
+            tool: None,
+            iteration: None,
+            message: None,
+        }
+    }
+
+    /// Convert an agent event for the task log (tokens are left to the final response)
+    pub fn from_agent(event: &AgentEvent) -> Option<Self> {
+        Some(match event {
+            AgentEvent::Token(_) | AgentEvent::ToolCallsParsed(_) => return None,
+            AgentEvent::ToolExecuting { name, args } => {
+                let mut e = Self::new("tool_start");
+                e.message = Some(format!("{} {}", name, args));
+                e
+            }
+            AgentEvent::ToolResult {
+                name,
+                success,
+                output,
+            } => {
+                let mut e = Self::new("tool_result");
+                e.tool = Some(ToolCallInfo {
+                    name: name.clone(),
+                    success: *success,
+                    output: output.clone(),
+                });
+                e
+            }
+            AgentEvent::IterationComplete { iteration, .. } => {
+                let mut e = Self::new("iteration");
+                e.iteration = Some(*iteration);
+                e
+            }
+            AgentEvent::Complete {
+                iterations,
+                success,
+            } => {
+                let mut e = Self::new("complete");
+                e.iteration = Some(*iterations);
+                e.message = Some(if *success { "success" } else { "failed" }.into());
+                e
+            }
+            AgentEvent::Error(msg) => {
+                let mut e = Self::new("error");
+                e.message = Some(msg.clone());
+                e
+            }
+            AgentEvent::Status(msg) => {
+                let mut e = Self::new("status");
+                e.message = Some(msg.clone());
+                e
+            }
+            AgentEvent::PermissionRequest(request) => {
+                let mut e = Self::new("permission_request");
+                e.message = Some(format!(
+                    "{} {}: {}",
+                    request.id, request.tool, request.reason
+                ));
+                e
+            }
+            AgentEvent::PermissionDecision(decision) => {
+                let mut e = Self::new("permission_decision");
+                let verdict = if decision.allow { "allow" } else { "deny" };
+                e.message = Some(format!("{} {}", decision.id, verdict));
+                e
+            }
+        })
+    }
+}
+
+/// Request to run a full agent task in the background
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskRequest {
+    pub task: String,
+    /// Context files, relative to the server's work dir
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Override the server's default model
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Approve tools that would normally ask for confirmation
+    #[serde(default)]
+    pub trust: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Running,
+    Completed,
+    Failed,
+    NeedsInput,
+}
+
+/// A background agent task, polled via GET /api/task/:id
+#[derive(Debug, Serialize)]
+pub struct TaskJob {
+    pub id: String,
+    pub task: String,
+    pub model: String,
+    pub status: TaskStatus,
+    pub events: Vec<StreamEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<PromptResponse>,
+    pub created: String,
+    #[serde(skip)]
+    finished_at: Option<std::time::Instant>,
+}
+
+/// Finished task jobs kept for polling; older ones are dropped first
+const MAX_FINISHED_TASKS: usize = 64;
+
+impl TaskJob {
+    fn new(id: &str, task: &str, model: &str) -> Self {
+        Self {
+            id: id.into(),
+            task: task.into(),
+            model: model.into(),
+            status: TaskStatus::Running,
+            events: Vec::new(),
+            result: None,
+            created: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+        }
+    }
+
+    fn finish(&mut self, result: AgentResult) {
+        self.status = if result.success {
+            TaskStatus::Completed
+        } else if result.needs_input.is_some() {
+            TaskStatus::NeedsInput
+        } else {
+            TaskStatus::Failed
+        };
+        self.result = Some(PromptResponse {
+            success: result.success,
+            response: result.final_response,
+            iterations: result.iterations,
+            tool_calls: result.tool_calls_executed,
+            error: result.error,
+            needs_input: result.needs_input,
+        });
+        self.finished_at = Some(std::time::Instant::now());
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════
 // SERVER STATE
 // ═══════════════════════════════════════════════════════════════
@@ -118,8 +280,13 @@ pub struct ServerState {
     model: String,
     work_dir: PathBuf,
     busy: bool,
+    /// Set once shutdown starts; new agent runs are refused
+    draining: bool,
     rate_limits: RateLimitInfo,
     request_times: Vec<std::time::Instant>,
+    tasks: HashMap<String, TaskJob>,
+    /// Bearer token every endpoint requires when set (HYLE_SERVER_TOKEN)
+    auth_token: Option<String>,
 }
 
 impl ServerState {
@@ -129,16 +296,26 @@ impl ServerState {
             model,
             work_dir,
             busy: false,
+            draining: false,
             rate_limits: RateLimitInfo {
                 requests_per_minute: 20, // Conservative default
                 requests_used: 0,
                 tokens_used: 0,
                 context_window: 128000,
+                cost_usd: 0.0,
             },
             request_times: Vec::new(),
+            tasks: HashMap::new(),
+            auth_token: None,
         }
     }
 
+    /// Require `token` on every request
+    pub fn with_auth_token(mut self, token: Option<String>) -> Self {
+        self.auth_token = token.filter(|t| !t.is_empty());
+        self
+    }
+
     fn record_request(&mut self) {
         let now = std::time::Instant::now();
         // Clean up old requests (older than 1 minute)
@@ -151,6 +328,28 @@ impl ServerState {
     fn add_tokens(&mut self, tokens: u64) {
         self.rate_limits.tokens_used += tokens;
     }
+
+    fn add_usage(&mut self, usage: &TokenUsage, cost_usd: f64) {
+        self.add_tokens(usage.total_tokens as u64);
+        self.rate_limits.cost_usd += cost_usd;
+    }
+
+    /// Drop the oldest finished jobs beyond `MAX_FINISHED_TASKS`
+    fn prune_tasks(&mut self) {
+        let mut finished: Vec<(std::time::Instant, String)> = self
+            .tasks
+            .values()
+            .filter_map(|job| job.finished_at.map(|at| (at, job.id.clone())))
+            .collect();
+        if finished.len() <= MAX_FINISHED_TASKS {
+            return;
+        }
+        finished.sort();
+        let excess = finished.len() - MAX_FINISHED_TASKS;
+        for (_, id) in finished.into_iter().take(excess) {
+            self.tasks.remove(&id);
+        }
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════
@@ -158,7 +357,7 @@ impl ServerState {
 // ═══════════════════════════════════════════════════════════════
 
 /// Run the HTTP server
-pub async fn run_server(port: u16) -> Result<()> {
+pub async fn run_server(port: u16, work_dir: PathBuf) -> Result<()> {
     use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
     use tokio::net::TcpListener;
 
@@ -167,32 +366,85 @@ pub async fn run_server(port: u16) -> Result<()> {
     let model = cfg
         .default_model
         .unwrap_or_else(|| "meta-llama/llama-3.2-3b-instruct:free".into());
-    let work_dir = std::env::current_dir()?;
 
-    let state = Arc::new(RwLock::new(ServerState::new(api_key, model, work_dir)));
+    let auth_token = std::env::var("HYLE_SERVER_TOKEN").ok();
+    let state = Arc::new(RwLock::new(
+        ServerState::new(api_key, model, work_dir).with_auth_token(auth_token),
+    ));
 
     let addr: SocketAddr = ([127, 0, 0, 1], port).into();
     let listener = TcpListener::bind(addr).await?;
 
     println!("hyle server listening on http://{}", addr);
+    if state.read().await.auth_token.is_some() {
+        println!("Auth: HYLE_SERVER_TOKEN (Bearer header, or /?token=... for the web UI)");
+    }
     println!("Endpoints:");
+    println!("  GET  /            - Web UI");
     println!("  GET  /status      - Server status + rate limits");
     println!("  GET  /sessions    - List saved sessions");
     println!("  GET  /session/:id - Get session by ID");
     println!("  POST /prompt      - Run agent with prompt");
     println!("  POST /complete    - Simple completion (no tools)");
     println!("  POST /stream      - SSE streaming completion");
+    println!("  POST /api/task    - Run agent task in background");
+    println!("  GET  /api/task/:id - Poll a background task");
     println!("Press Ctrl-C to stop\n");
 
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    let mut drain = Drain::default();
+    let mut idle = IdleTimeout::from_config();
+    if let Some(idle) = &idle {
+        println!(
+            "Stopping after {}s without requests",
+            idle.limit().as_secs()
+        );
+    }
+
     loop {
-        let (mut socket, peer) = listener.accept().await?;
+        let busy = state.read().await.busy;
+        if drain.finished(busy) {
+            break;
+        }
+        let watching_idle = idle.is_some() && !drain.is_draining();
+        let (mut socket, peer) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = &mut shutdown, if !drain.is_draining() => {
+                println!("Shutting down: finishing in-flight requests...");
+                drain.start(DRAIN_TIMEOUT);
+                state.write().await.draining = true;
+                continue;
+            }
+            _ = tokio::time::sleep(Duration::from_millis(250)), if drain.is_draining() => continue,
+            _ = tokio::time::sleep(Duration::from_secs(1)), if watching_idle => {
+                if let Some(idle) = idle.as_mut() {
+                    let now = Instant::now();
+                    if busy {
+                        idle.touch(now);
+                    }
+                    if idle.fire_if_idle(now, || Ok(()))? {
+                        println!("Idle: finishing in-flight requests and stopping...");
+                        drain.start(DRAIN_TIMEOUT);
+                        state.write().await.draining = true;
+                    }
+                }
+                continue;
+            }
+        };
+        if let Some(idle) = idle.as_mut() {
+            idle.touch(Instant::now());
+        }
+        let in_flight = drain.track();
         let state = state.clone();
 
         tokio::spawn(async move {
+            let _in_flight = in_flight;
             let (reader, mut writer) = socket.split();
             let mut reader = BufReader::new(reader);
             let mut request = String::new();
             let mut headers = Vec::new();
+            let mut authorization = None;
             let mut content_length = 0usize;
 
             // Read request line
@@ -210,6 +462,9 @@ pub async fn run_server(port: u16) -> Result<()> {
                 if line.trim().is_empty() {
                     break;
                 }
+                if line.to_lowercase().starts_with("authorization:") {
+                    authorization = line.split_once(':').map(|(_, v)| v.trim().to_string());
+                }
                 if line.to_lowercase().starts_with("content-length:") {
                     if let Some(len) = line.split(':').nth(1) {
                         content_length = len.trim().parse().unwrap_or(0);
@@ -245,59 +500,203 @@ pub async fn run_server(port: u16) -> Result<()> {
                 }
             };
 
-            println!("[{}] {} {}", peer, method, path);
-
-            // Route request
-            let response = match (method, path) {
-                ("GET", "/status") => handle_status(&state).await,
-                ("GET", "/sessions") => handle_sessions().await,
-                ("POST", "/prompt") => handle_prompt(&state, &body).await,
-                ("POST", "/complete") => handle_complete(&state, &body).await,
-                ("OPTIONS", _) => Ok(cors_preflight()),
-                ("GET", "/") => Ok(html_response(WEB_UI_HTML)),
-                ("GET", "/api") => Ok(json_response(
-                    200,
-                    &serde_json::json!({
-                        "name": "hyle",
-                        "version": env!("CARGO_PKG_VERSION"),
-                        "endpoints": ["/status", "/sessions", "/prompt", "/complete"],
-                        "docs": "POST /prompt with {\"prompt\": \"...\", \"files\": [...]} for agent mode"
-                    }),
-                )),
-                (_, p) if p.starts_with("/session/") => {
-                    let id = p.trim_start_matches("/session/");
-                    // Validate session ID format to prevent path traversal
-                    if !id
-                        .chars()
-                        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
-                    {
-                        Ok(json_response(
-                            400,
-                            &serde_json::json!({"error": "Invalid session ID format"}),
-                        ))
-                    } else {
-                        handle_session(id).await
-                    }
+            // The query can carry the API token; keep it out of the log
+            let logged = path.split_once('?').map_or(path, |(route, _)| route);
+            println!("[{}] {} {}", peer, method, logged);
+
+            match respond(&state, method, path, authorization.as_deref(), &body).await {
+                Some(response) => {
+                    let _ = writer.write_all(response.as_bytes()).await;
                 }
-                _ => Ok(json_response(
-                    404,
-                    &serde_json::json!({"error": "Not found"}),
-                )),
-            };
+                None => {
+                    let _ = handle_stream(&state, &body, &mut writer).await;
+                }
+            }
+        });
+    }
 
-            let response = response.unwrap_or_else(|e| {
-                json_response(500, &serde_json::json!({"error": e.to_string()}))
-            });
+    println!("hyle server stopped");
+    Ok(())
+}
 
-            let _ = writer.write_all(response.as_bytes()).await;
-        });
+/// Route a request to its response; None for `POST /stream`, which writes
+/// its own
+async fn respond(
+    state: &Arc<RwLock<ServerState>>,
+    method: &str,
+    path: &str,
+    authorization: Option<&str>,
+    body: &str,
+) -> Option<String> {
+    let (route, query) = path.split_once('?').unwrap_or((path, ""));
+    let token = state.read().await.auth_token.clone();
+    if method != "OPTIONS"
+        && !authorized(token.as_deref(), authorization, query, WithoutToken::Open)
+    {
+        return Some(json_response(
+            401,
+            &serde_json::json!({"error": "Unauthorized"}),
+        ));
+    }
+
+    let response = match (method, route) {
+        ("POST", "/stream") => return None,
+        ("GET", "/status") => handle_status(state).await,
+        ("GET", "/sessions") => handle_sessions().await,
+        ("POST", "/prompt") => handle_prompt(state, body).await,
+        ("POST", "/complete") => handle_complete(state, body).await,
+        ("POST", "/api/task") => handle_task(state, body).await,
+        ("GET", p) if p.starts_with("/api/task/") => {
+            handle_task_status(state, p.trim_start_matches("/api/task/")).await
+        }
+        ("OPTIONS", _) => Ok(cors_preflight()),
+        ("GET", "/") => Ok(html_response(WEB_UI_HTML)),
+        ("GET", "/api") => Ok(json_response(
+            200,
+            &serde_json::json!({
+                "name": "hyle",
+                "version": env!("CARGO_PKG_VERSION"),
+                "endpoints": ["/status", "/sessions", "/prompt", "/complete", "/stream", "/api/task"],
+                "docs": "POST /prompt with {\"prompt\": \"...\", \"files\": [...]} for agent mode"
+            }),
+        )),
+        (_, p) if p.starts_with("/session/") => {
+            let id = p.trim_start_matches("/session/");
+            // Validate session ID format to prevent path traversal
+            if !id
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+            {
+                Ok(json_response(
+                    400,
+                    &serde_json::json!({"error": "Invalid session ID format"}),
+                ))
+            } else {
+                handle_session(id).await
+            }
+        }
+        _ => Ok(json_response(
+            404,
+            &serde_json::json!({"error": "Not found"}),
+        )),
+    };
+
+    let response = response
+        .unwrap_or_else(|e| json_response(500, &serde_json::json!({"error": e.to_string()})));
+    Some(response)
+}
+
+/// What a server does with a request when no API token is configured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WithoutToken {
+    /// Let it through: the hyle server is meant for local use
+    Open,
+    /// Refuse it: the orchestrator's guarded endpoints act on the host
+    Closed,
+}
+
+/// Whether a request carries the configured token, as a bearer header or
+/// `token=` in `query` (for the web UI). With no token configured,
+/// `without` decides, so each server states its rule at the call site.
+pub fn authorized(
+    expected: Option<&str>,
+    header: Option<&str>,
+    query: &str,
+    without: WithoutToken,
+) -> bool {
+    let Some(expected) = expected else {
+        return without == WithoutToken::Open;
+    };
+    let bearer = header
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(str::trim);
+    let param = query.split('&').find_map(|kv| kv.strip_prefix("token="));
+    bearer == Some(expected) || param == Some(expected)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// SHUTDOWN
+// ═══════════════════════════════════════════════════════════════
+
+/// How long the hand-rolled servers wait for in-flight work on shutdown
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Resolves on SIGINT or SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Shutdown bookkeeping for an accept loop
+///
+/// Connections hold an `InFlight` guard; once draining starts the loop keeps
+/// serving (new work gets 503) until nothing is in flight or the limit passes.
+#[derive(Default)]
+pub struct Drain {
+    in_flight: Arc<AtomicUsize>,
+    deadline: Option<Instant>,
+}
+
+/// Marks one connection as in flight until dropped
+pub struct InFlight(Arc<AtomicUsize>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Drain {
+    pub fn track(&self) -> InFlight {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlight(self.in_flight.clone())
+    }
+
+    pub fn start(&mut self, limit: Duration) {
+        self.deadline = Some(Instant::now() + limit);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    /// Whether the accept loop should stop (`busy` = background work still running)
+    pub fn finished(&self, busy: bool) -> bool {
+        match self.deadline {
+            None => false,
+            Some(deadline) if Instant::now() >= deadline => {
+                eprintln!("Drain timed out; stopping with work still in flight");
+                true
+            }
+            Some(_) => !busy && self.in_flight.load(Ordering::SeqCst) == 0,
+        }
     }
 }
 
 fn json_response(status: u16, body: &serde_json::Value) -> String {
     let status_text = match status {
         200 => "OK",
+        202 => "Accepted",
         400 => "Bad Request",
+        401 => "Unauthorized",
         404 => "Not Found",
         500 => "Internal Server Error",
         503 => "Service Unavailable",
@@ -322,243 +721,8 @@ fn html_response(html: &str) -> String {
 // WEB UI
 // ═══════════════════════════════════════════════════════════════
 
-const WEB_UI_HTML: &str = r##"<!DOCTYPE html>
-<html lang="en">
-<head>
-  <meta charset="UTF-8">
-  <meta name="viewport" content="width=device-width, initial-scale=1.0">
-  <title>hyle</title>
-  <style>
-    :root {
-      --bg: #1a1b26;
-      --fg: #c0caf5;
-      --dim: #565f89;
-      --accent: #7aa2f7;
-      --success: #9ece6a;
-      --error: #f7768e;
-      --surface: #24283b;
-      --border: #414868;
-    }
-    * { box-sizing: border-box; margin: 0; padding: 0; }
-    body {
-      font-family: 'SF Mono', 'Fira Code', 'Consolas', monospace;
-      font-size: 14px;
-      background: var(--bg);
-      color: var(--fg);
-      height: 100vh;
-      display: flex;
-      flex-direction: column;
-    }
-    header {
-      padding: 12px 20px;
-      border-bottom: 1px solid var(--border);
-      display: flex;
-      justify-content: space-between;
-      align-items: center;
-    }
-    header h1 { font-size: 18px; color: var(--accent); font-weight: 500; }
-    #status { color: var(--dim); font-size: 12px; }
-    #status.ready { color: var(--success); }
-    #status.busy { color: var(--error); }
-    main {
-      flex: 1;
-      overflow-y: auto;
-      padding: 20px;
-    }
-    .msg {
-      margin-bottom: 16px;
-      padding: 12px 16px;
-      border-radius: 8px;
-      max-width: 85%;
-      line-height: 1.5;
-      white-space: pre-wrap;
-      word-break: break-word;
-    }
-    .msg.user {
-      background: var(--surface);
-      margin-left: auto;
-      border: 1px solid var(--border);
-    }
-    .msg.assistant {
-      background: transparent;
-      border-left: 3px solid var(--accent);
-      padding-left: 16px;
-    }
-    .msg.error {
-      background: rgba(247, 118, 142, 0.1);
-      border-left-color: var(--error);
-      color: var(--error);
-    }
-    .msg .meta {
-      font-size: 11px;
-      color: var(--dim);
-      margin-top: 8px;
-    }
-    footer {
-      padding: 16px 20px;
-      border-top: 1px solid var(--border);
-      background: var(--surface);
-    }
-    #input-form {
-      display: flex;
-      gap: 12px;
-    }
-    #prompt {
-      flex: 1;
-      background: var(--bg);
-      border: 1px solid var(--border);
-      border-radius: 6px;
-      padding: 12px 16px;
-      color: var(--fg);
-      font-family: inherit;
-      font-size: 14px;
-      resize: none;
-      min-height: 44px;
-      max-height: 200px;
-    }
-    #prompt:focus {
-      outline: none;
-      border-color: var(--accent);
-    }
-    button {
-      background: var(--accent);
-      color: var(--bg);
-      border: none;
-      border-radius: 6px;
-      padding: 12px 24px;
-      font-family: inherit;
-      font-size: 14px;
-      font-weight: 500;
-      cursor: pointer;
-      transition: opacity 0.2s;
-    }
-    button:hover { opacity: 0.9; }
-    button:disabled {
-      opacity: 0.5;
-      cursor: not-allowed;
-    }
-    .tools {
-      margin-top: 8px;
-      font-size: 12px;
-      color: var(--dim);
-    }
-    code {
-      background: var(--surface);
-      padding: 2px 6px;
-      border-radius: 4px;
-      font-size: 13px;
-    }
-    pre {
-      background: var(--surface);
-      padding: 12px;
-      border-radius: 6px;
-      overflow-x: auto;
-      margin: 8px 0;
-    }
-  </style>
-</head>
-<body>
-  <header>
-    <h1>hyle</h1>
-    <span id="status">connecting...</span>
-  </header>
-  <main id="messages"></main>
-  <footer>
-    <form id="input-form">
-      <textarea id="prompt" placeholder="Ask anything..." rows="1"></textarea>
-      <button type="submit" id="send">Send</button>
-    </form>
-  </footer>
-  <script>
-    const messages = document.getElementById('messages');
-    const form = document.getElementById('input-form');
-    const prompt = document.getElementById('prompt');
-    const sendBtn = document.getElementById('send');
-    const status = document.getElementById('status');
-
-    // Auto-resize textarea
-    prompt.addEventListener('input', () => {
-      prompt.style.height = 'auto';
-      prompt.style.height = Math.min(prompt.scrollHeight, 200) + 'px';
-    });
-
-    // Submit on Enter (Shift+Enter for newline)
-    prompt.addEventListener('keydown', (e) => {
-      if (e.key === 'Enter' && !e.shiftKey) {
-        e.preventDefault();
-        form.dispatchEvent(new Event('submit'));
-      }
-    });
-
-    // Check status
-    async function checkStatus() {
-      try {
-        const res = await fetch('/status');
-        const data = await res.json();
-        status.textContent = data.ready ? `ready | ${data.model.split('/').pop()}` : 'busy';
-        status.className = data.ready ? 'ready' : 'busy';
-      } catch {
-        status.textContent = 'offline';
-        status.className = '';
-      }
-    }
-    checkStatus();
-    setInterval(checkStatus, 5000);
-
-    function addMessage(role, content, meta = null) {
-      const div = document.createElement('div');
-      div.className = `msg ${role}`;
-      div.textContent = content;
-      if (meta) {
-        const metaDiv = document.createElement('div');
-        metaDiv.className = 'meta';
-        metaDiv.textContent = meta;
-        div.appendChild(metaDiv);
-      }
-      messages.appendChild(div);
-      messages.scrollTop = messages.scrollHeight;
-    }
-
-    form.addEventListener('submit', async (e) => {
-      e.preventDefault();
-      const text = prompt.value.trim();
-      if (!text) return;
-
-      addMessage('user', text);
-      prompt.value = '';
-      prompt.style.height = 'auto';
-      sendBtn.disabled = true;
-      status.textContent = 'thinking...';
-      status.className = 'busy';
-
-      try {
-        const res = await fetch('/prompt', {
-          method: 'POST',
-          headers: { 'Content-Type': 'application/json' },
-          body: JSON.stringify({ prompt: text, files: [] })
-        });
-        const data = await res.json();
-
-        if (data.success) {
-          const meta = `${data.iterations} iterations, ${data.tool_calls} tool calls`;
-          addMessage('assistant', data.response, meta);
-        } else {
-          addMessage('error', data.error || 'Unknown error');
-        }
-      } catch (err) {
-        addMessage('error', 'Connection error: ' + err.message);
-      }
-
-      sendBtn.disabled = false;
-      checkStatus();
-    });
-
-    // Focus input
-    prompt.focus();
-  </script>
-</body>
-</html>
-"##;
+/// Chat page served at `/`, embedded so the binary needs no asset files
+const WEB_UI_HTML: &str = include_str!("../assets/ui.html");
 
 async fn handle_status(state: &Arc<RwLock<ServerState>>) -> Result<String> {
     let state = state.read().await;
@@ -576,7 +740,7 @@ fn cors_preflight() -> String {
     "HTTP/1.1 204 No Content\r\n\
      Access-Control-Allow-Origin: *\r\n\
      Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
-     Access-Control-Allow-Headers: Content-Type\r\n\
+     Access-Control-Allow-Headers: Content-Type, Authorization\r\n\
      Access-Control-Max-Age: 86400\r\n\r\n"
         .to_string()
 }
@@ -643,6 +807,12 @@ async fn handle_prompt(state: &Arc<RwLock<ServerState>>, body: &str) -> Result<S
     // Check if busy and record request
     {
         let mut state = state.write().await;
+        if state.draining {
+            return Ok(json_response(
+                503,
+                &serde_json::json!({"error": "Server is shutting down"}),
+            ));
+        }
         if state.busy {
             return Ok(json_response(
                 503,
@@ -699,6 +869,7 @@ async fn handle_prompt(state: &Arc<RwLock<ServerState>>, body: &str) -> Result<S
         iterations: result.iterations,
         tool_calls: result.tool_calls_executed,
         error: result.error,
+        needs_input: result.needs_input,
     };
 
     Ok(json_response(200, &serde_json::to_value(response)?))
@@ -709,6 +880,12 @@ async fn handle_complete(state: &Arc<RwLock<ServerState>>, body: &str) -> Result
 
     let (api_key, model) = {
         let state = state.read().await;
+        if state.draining {
+            return Ok(json_response(
+                503,
+                &serde_json::json!({"error": "Server is shutting down"}),
+            ));
+        }
         (state.api_key.clone(), state.model.clone())
     };
 
@@ -735,6 +912,254 @@ async fn handle_complete(state: &Arc<RwLock<ServerState>>, body: &str) -> Result
     ))
 }
 
+// ═══════════════════════════════════════════════════════════════
+// STREAMING CHAT
+// ═══════════════════════════════════════════════════════════════
+
+/// `POST /stream`: a plain completion (no tools) as server-sent events
+async fn handle_stream<W>(
+    state: &Arc<RwLock<ServerState>>,
+    body: &str,
+    writer: &mut W,
+) -> Result<()>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::AsyncWriteExt;
+
+    let request: PromptRequest = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(e) => {
+            let response = json_response(400, &serde_json::json!({"error": e.to_string()}));
+            writer.write_all(response.as_bytes()).await?;
+            return Ok(());
+        }
+    };
+    let (api_key, model) = {
+        let mut state = state.write().await;
+        if state.draining {
+            let response = json_response(
+                503,
+                &serde_json::json!({"error": "Server is shutting down"}),
+            );
+            writer.write_all(response.as_bytes()).await?;
+            return Ok(());
+        }
+        state.record_request();
+        (state.api_key.clone(), state.model.clone())
+    };
+
+    let events = match crate::client::stream_completion(&api_key, &model, &request.prompt).await {
+        Ok(events) => events,
+        Err(e) => {
+            let response = json_response(500, &serde_json::json!({"error": e.to_string()}));
+            writer.write_all(response.as_bytes()).await?;
+            return Ok(());
+        }
+    };
+    let price = |usage: &TokenUsage| crate::models::calculate_cost(&model, usage);
+    if let Some(usage) = write_sse(writer, events, price).await? {
+        state.write().await.add_usage(&usage, price(&usage));
+    }
+    Ok(())
+}
+
+/// Relay a completion stream as SSE: a `token` event per chunk, then `done`
+/// with usage and cost, or `error`. Returns the usage when it completed.
+async fn write_sse<W, P>(
+    writer: &mut W,
+    mut events: mpsc::Receiver<crate::client::StreamEvent>,
+    price: P,
+) -> Result<Option<TokenUsage>>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+    P: Fn(&TokenUsage) -> f64,
+{
+    use crate::client::StreamEvent as Chunk;
+    use tokio::io::AsyncWriteExt;
+
+    writer
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+              Connection: close\r\nAccess-Control-Allow-Origin: *\r\n\r\n",
+        )
+        .await?;
+
+    while let Some(event) = events.recv().await {
+        let (name, data, usage) = match event {
+            Chunk::Token(text) => ("token", serde_json::json!({"text": text}), None),
+            Chunk::Done(usage) => {
+                let data = serde_json::json!({
+                    "prompt_tokens": usage.prompt_tokens,
+                    "completion_tokens": usage.completion_tokens,
+                    "total_tokens": usage.total_tokens,
+                    "cost_usd": price(&usage),
+                });
+                ("done", data, Some(usage))
+            }
+            Chunk::Error(error) => ("error", serde_json::json!({"error": error}), None),
+        };
+        let frame = format!("event: {}\ndata: {}\n\n", name, data);
+        writer.write_all(frame.as_bytes()).await?;
+        writer.flush().await?;
+        if name != "token" {
+            return Ok(usage);
+        }
+    }
+    Ok(None)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// TASK JOBS
+// ═══════════════════════════════════════════════════════════════
+
+async fn handle_task(state: &Arc<RwLock<ServerState>>, body: &str) -> Result<String> {
+    submit_task(state, body, |agent, prompt| agent.run(prompt)).await
+}
+
+/// Validate a task request and start it as a background job
+///
+/// `run` starts the agent; tests swap it out to avoid the network.
+async fn submit_task<F>(state: &Arc<RwLock<ServerState>>, body: &str, run: F) -> Result<String>
+where
+    F: FnOnce(
+        &AgentCore,
+        &str,
+    ) -> (
+        mpsc::Receiver<AgentEvent>,
+        tokio::task::JoinHandle<AgentResult>,
+    ),
+{
+    let request: TaskRequest = match serde_json::from_str(body) {
+        Ok(r) => r,
+        Err(e) => {
+            return Ok(json_response(
+                400,
+                &serde_json::json!({"error": format!("Invalid task request: {}", e)}),
+            ))
+        }
+    };
+    if request.task.trim().is_empty() {
+        return Ok(json_response(
+            400,
+            &serde_json::json!({"error": "Task is empty"}),
+        ));
+    }
+
+    let (api_key, default_model, work_dir) = {
+        let state = state.read().await;
+        (
+            state.api_key.clone(),
+            state.model.clone(),
+            state.work_dir.clone(),
+        )
+    };
+
+    // Every tool call and context file stays under the server's work dir
+    let gate = ToolGate::new(
+        &work_dir,
+        config::Config::load().unwrap_or_default(),
+        request.trust,
+    );
+    let mut prompt = request.task.clone();
+    for path in &request.paths {
+        if !gate.contains(Path::new(path)) {
+            return Ok(json_response(
+                400,
+                &serde_json::json!({
+                    "error": format!("Path '{}' is outside the sandbox root", path)
+                }),
+            ));
+        }
+        if let Ok(content) = std::fs::read_to_string(work_dir.join(path)) {
+            prompt = format!("{}\n\n--- {} ---\n{}", prompt, path, content);
+        }
+    }
+
+    {
+        let mut state = state.write().await;
+        if state.draining {
+            return Ok(json_response(
+                503,
+                &serde_json::json!({"error": "Server is shutting down"}),
+            ));
+        }
+        if state.busy {
+            return Ok(json_response(
+                503,
+                &serde_json::json!({
+                    "error": "Server is busy processing another request"
+                }),
+            ));
+        }
+        state.busy = true;
+        state.record_request();
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let model = request.model.unwrap_or(default_model);
+    let agent = AgentCore::new(&api_key, &model, &work_dir)
+        .with_config(AgentConfig::conservative().with_gate(gate));
+
+    state
+        .write()
+        .await
+        .tasks
+        .insert(id.clone(), TaskJob::new(&id, &request.task, &model));
+    let (mut rx, handle) = run(&agent, &prompt);
+
+    let job_state = state.clone();
+    let job_id = id.clone();
+    tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            if let Some(event) = StreamEvent::from_agent(&event) {
+                let mut state = job_state.write().await;
+                if let Some(job) = state.tasks.get_mut(&job_id) {
+                    job.events.push(event);
+                }
+            }
+        }
+
+        let result = handle.await.unwrap_or_else(|e| AgentResult {
+            iterations: 0,
+            tool_calls_executed: 0,
+            final_response: String::new(),
+            success: false,
+            error: Some(e.to_string()),
+            tokens_used: 0,
+            needs_input: None,
+        });
+
+        let mut state = job_state.write().await;
+        state.busy = false;
+        state.add_tokens(result.tokens_used as u64);
+        if let Some(job) = state.tasks.get_mut(&job_id) {
+            job.finish(result);
+        }
+        state.prune_tasks();
+    });
+
+    Ok(json_response(
+        202,
+        &serde_json::json!({
+            "status": TaskStatus::Running,
+            "job_id": id,
+            "poll_url": format!("/api/task/{}", id),
+        }),
+    ))
+}
+
+async fn handle_task_status(state: &Arc<RwLock<ServerState>>, id: &str) -> Result<String> {
+    let state = state.read().await;
+    match state.tasks.get(id) {
+        Some(job) => Ok(json_response(200, &serde_json::to_value(job)?)),
+        None => Ok(json_response(
+            404,
+            &serde_json::json!({"error": format!("No task with id {}", id)}),
+        )),
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════
 // TESTS
 // ═══════════════════════════════════════════════════════════════
@@ -751,6 +1176,84 @@ mod tests {
         assert!(resp.contains("\"test\":true"));
     }
 
+    #[tokio::test]
+    async fn test_web_ui_served() {
+        let state = test_state(std::env::temp_dir());
+        let page = respond(&state, "GET", "/", None, "").await.unwrap();
+        assert!(page.starts_with("HTTP/1.1 200 OK"));
+        assert!(page.contains("text/html"));
+        assert!(page.contains("<!DOCTYPE html>"));
+        assert!(page.contains("'/stream'"));
+        // The chat endpoint is left to the streaming writer
+        assert!(respond(&state, "POST", "/stream", None, "{}")
+            .await
+            .is_none());
+
+        // Behind a token, everything but preflight needs it
+        state.write().await.auth_token = Some("s3cret".into());
+        let denied = respond(&state, "GET", "/", None, "").await.unwrap();
+        assert!(denied.contains("401 Unauthorized"));
+        let denied = respond(&state, "POST", "/stream", Some("Bearer nope"), "{}").await;
+        assert!(denied.unwrap().contains("401"));
+        let page = respond(&state, "GET", "/?token=s3cret", None, "").await;
+        assert!(page.unwrap().contains("text/html"));
+        let status = respond(&state, "GET", "/status", Some("Bearer s3cret"), "").await;
+        assert!(status.unwrap().contains("200 OK"));
+        assert!(
+            respond(&state, "POST", "/stream", Some("Bearer s3cret"), "{}")
+                .await
+                .is_none()
+        );
+        let preflight = respond(&state, "OPTIONS", "/stream", None, "").await;
+        assert!(preflight.unwrap().contains("Authorization"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_writes_sse() {
+        use crate::client::StreamEvent as Chunk;
+
+        let (tx, rx) = mpsc::channel(8);
+        tx.send(Chunk::Token("Hel".into())).await.unwrap();
+        tx.send(Chunk::Token("lo".into())).await.unwrap();
+        let usage = TokenUsage {
+            prompt_tokens: 12,
+            completion_tokens: 2,
+            total_tokens: 14,
+            ..Default::default()
+        };
+        tx.send(Chunk::Done(usage)).await.unwrap();
+        drop(tx);
+
+        let mut out = Vec::new();
+        let usage = write_sse(&mut out, rx, |u| u.total_tokens as f64 / 1000.0)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(usage.total_tokens, 14);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/event-stream"));
+        let frames: Vec<&str> = out
+            .split("\r\n\r\n")
+            .nth(1)
+            .unwrap()
+            .split("\n\n")
+            .collect();
+        assert_eq!(frames[0], "event: token\ndata: {\"text\":\"Hel\"}");
+        assert_eq!(frames[1], "event: token\ndata: {\"text\":\"lo\"}");
+        assert!(frames[2].starts_with("event: done\n"));
+        assert!(frames[2].contains("\"completion_tokens\":2"));
+        assert!(frames[2].contains("\"cost_usd\":0.014"));
+
+        // Errors end the stream without usage
+        let (tx, rx) = mpsc::channel(8);
+        tx.send(Chunk::Error("rate limited".into())).await.unwrap();
+        let mut out = Vec::new();
+        assert!(write_sse(&mut out, rx, |_| 0.0).await.unwrap().is_none());
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .contains("event: error\ndata: {\"error\":\"rate limited\"}"));
+    }
+
     #[test]
     fn test_prompt_request_parse() {
         let json = r#"{"prompt": "hello", "files": ["test.rs"]}"#;
@@ -758,4 +1261,208 @@ mod tests {
         assert_eq!(req.prompt, "hello");
         assert_eq!(req.files.len(), 1);
     }
+
+    fn test_state(work_dir: PathBuf) -> Arc<RwLock<ServerState>> {
+        Arc::new(RwLock::new(ServerState::new(
+            "test-key".into(),
+            "test/model".into(),
+            work_dir,
+        )))
+    }
+
+    /// Stand-in for the agent loop that finishes straight away
+    fn trivial_run(
+        agent: &AgentCore,
+        prompt: &str,
+    ) -> (
+        mpsc::Receiver<AgentEvent>,
+        tokio::task::JoinHandle<AgentResult>,
+    ) {
+        assert!(agent.config.gate.is_some());
+        let (tx, rx) = mpsc::channel(8);
+        let prompt = prompt.to_string();
+        let handle = tokio::spawn(async move {
+            let _ = tx.send(AgentEvent::Status("Iteration 1 of 1".into())).await;
+            let _ = tx
+                .send(AgentEvent::Complete {
+                    iterations: 1,
+                    success: true,
+                })
+                .await;
+            AgentResult {
+                iterations: 1,
+                tool_calls_executed: 0,
+                final_response: format!("done: {}", prompt),
+                success: true,
+                error: None,
+                tokens_used: 0,
+                needs_input: None,
+            }
+        });
+        (rx, handle)
+    }
+
+    #[test]
+    fn test_task_request_parse() {
+        let req: TaskRequest = serde_json::from_str(r#"{"task": "fix it"}"#).unwrap();
+        assert_eq!(req.task, "fix it");
+        assert!(req.paths.is_empty());
+        assert!(req.model.is_none());
+        assert!(!req.trust);
+    }
+
+    #[tokio::test]
+    async fn test_task_rejects_paths_outside_sandbox() {
+        let state = test_state(std::env::temp_dir());
+        let body = r#"{"task": "read it", "paths": ["../../etc/passwd"]}"#;
+        let resp = submit_task(&state, body, trivial_run).await.unwrap();
+        assert!(resp.contains("400 Bad Request"));
+        assert!(resp.contains("outside the sandbox root"));
+        assert!(state.read().await.tasks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_task_runs_to_completion() {
+        let state = test_state(std::env::temp_dir());
+        let resp = submit_task(&state, r#"{"task": "say hi"}"#, trivial_run)
+            .await
+            .unwrap();
+        assert!(resp.contains("202 Accepted"));
+
+        let body = resp.split("\r\n\r\n").nth(1).unwrap();
+        let submitted: serde_json::Value = serde_json::from_str(body).unwrap();
+        let id = submitted["job_id"].as_str().unwrap().to_string();
+        assert_eq!(submitted["poll_url"], format!("/api/task/{}", id));
+
+        let mut job = serde_json::Value::Null;
+        for _ in 0..100 {
+            let resp = handle_task_status(&state, &id).await.unwrap();
+            job = serde_json::from_str(resp.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+            if job["status"] != "running" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(job["status"], "completed");
+        assert_eq!(job["result"]["response"], "done: say hi");
+        assert_eq!(job["events"][1]["event_type"], "complete");
+        assert!(!state.read().await.busy);
+    }
+
+    #[test]
+    fn test_drain_waits_for_in_flight() {
+        let mut drain = Drain::default();
+        let conn = drain.track();
+        assert!(!drain.finished(false), "not draining yet");
+
+        drain.start(Duration::from_secs(60));
+        assert!(drain.is_draining());
+        assert!(!drain.finished(false), "connection still open");
+        drop(conn);
+        assert!(!drain.finished(true), "background task still running");
+        assert!(drain.finished(false));
+    }
+
+    #[test]
+    fn test_drain_gives_up_at_deadline() {
+        let mut drain = Drain::default();
+        let _conn = drain.track();
+        drain.start(Duration::ZERO);
+        assert!(drain.finished(true));
+    }
+
+    #[tokio::test]
+    async fn test_task_refused_while_draining() {
+        let state = test_state(std::env::temp_dir());
+        state.write().await.draining = true;
+        let resp = submit_task(&state, r#"{"task": "say hi"}"#, trivial_run)
+            .await
+            .unwrap();
+        assert!(resp.contains("503"));
+        assert!(resp.contains("shutting down"));
+
+        // Plain completions start upstream calls too
+        let resp = handle_complete(&state, r#"{"prompt": "say hi"}"#)
+            .await
+            .unwrap();
+        assert!(resp.starts_with("HTTP/1.1 503"));
+        assert!(resp.contains("shutting down"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_refused_while_draining() {
+        let state = test_state(std::env::temp_dir());
+        state.write().await.draining = true;
+        let mut out = Vec::new();
+        handle_stream(&state, r#"{"prompt": "hi"}"#, &mut out)
+            .await
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("HTTP/1.1 503"));
+        assert!(out.contains("shutting down"));
+    }
+
+    #[test]
+    fn test_authorized() {
+        use WithoutToken::{Closed, Open};
+        let token = Some("s3cret");
+        for without in [Open, Closed] {
+            assert!(authorized(token, Some("Bearer s3cret"), "", without));
+            assert!(authorized(token, None, "a=1&token=s3cret", without));
+            assert!(!authorized(token, Some("Bearer nope"), "", without));
+            assert!(!authorized(token, None, "", without));
+        }
+        // With no token configured, the caller's rule applies
+        assert!(authorized(None, None, "", Open));
+        assert!(!authorized(None, Some("Bearer anything"), "", Closed));
+    }
+
+    #[test]
+    fn test_finished_tasks_are_capped() {
+        let mut state = ServerState::new("k".into(), "m".into(), std::env::temp_dir());
+        let done = || AgentResult {
+            iterations: 1,
+            tool_calls_executed: 0,
+            final_response: "ok".into(),
+            success: true,
+            error: None,
+            tokens_used: 0,
+            needs_input: None,
+        };
+        state
+            .tasks
+            .insert("running".into(), TaskJob::new("running", "t", "m"));
+        for i in 0..MAX_FINISHED_TASKS + 3 {
+            let id = format!("job-{}", i);
+            let mut job = TaskJob::new(&id, "t", "m");
+            job.finish(done());
+            state.tasks.insert(id, job);
+        }
+        state.prune_tasks();
+
+        assert_eq!(state.tasks.len(), MAX_FINISHED_TASKS + 1);
+        assert!(state.tasks.contains_key("running"));
+        assert!(!state.tasks.contains_key("job-0"));
+        assert!(state
+            .tasks
+            .contains_key(&format!("job-{}", MAX_FINISHED_TASKS + 2)));
+    }
+
+    #[test]
+    fn test_task_needs_input_status() {
+        let mut job = TaskJob::new("id", "add a db layer", "m");
+        job.finish(AgentResult {
+            iterations: 1,
+            tool_calls_executed: 0,
+            final_response: String::new(),
+            success: false,
+            error: Some("Needs input: Which database?".into()),
+            tokens_used: 0,
+            needs_input: Some("Which database?".into()),
+        });
+        let json = serde_json::to_value(&job).unwrap();
+        assert_eq!(json["status"], "needs_input");
+        assert_eq!(json["result"]["needs_input"], "Which database?");
+    }
 }
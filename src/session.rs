@@ -1,17 +1,19 @@
 //! Session persistence - logs, history, context
 //!
-//! Sessions are stored in ~/.local/state/hyle/sessions/
+//! Sessions are stored in ~/.local/state/hyle/sessions/ unless
+//! `HYLE_SESSIONS_DIR` or `sessions_dir` in config.json says otherwise.
 //! Each session is a directory with:
 //! - meta.json: Session metadata (model, start time, etc.)
 //! - messages.jsonl: Conversation history (append-only)
 //! - log.jsonl: Event log (tool calls, errors, etc.)
+//! - partial.json: In-flight assistant response, autosaved while streaming
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::config;
 
@@ -107,6 +109,21 @@ pub struct SessionMeta {
     pub total_tokens: u64,
     pub working_dir: String,
     pub description: Option<String>,
+    /// Session this one was forked from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// Model this session used before it stopped being offered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_model: Option<String>,
+    /// Files whose current contents go with every request
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<String>,
+    /// Notes kept in every request, whatever gets trimmed or compacted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pins: Vec<String>,
+    /// Working set from `/focus`: edits elsewhere need the user's say-so
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub focus: Vec<String>,
 }
 
 /// A message in the conversation
@@ -135,11 +152,40 @@ pub struct Session {
     log_file: Option<File>,
 }
 
+/// Messages `/compact` keeps verbatim at the end of the conversation
+pub const COMPACT_KEEP_RECENT: usize = 4;
+
+/// Appended to a response recovered from an autosave after a crash
+pub const INCOMPLETE_MARKER: &str = "[incomplete: interrupted before the response finished]";
+
+/// A reply discarded by `Session::regenerate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regenerated {
+    /// The user prompt to send again
+    pub prompt: String,
+    /// Messages removed: the reply and any tool output after it
+    pub dropped: usize,
+    /// Completion tokens taken off the session total
+    pub tokens: u64,
+}
+
+/// In-flight assistant response, flushed periodically while streaming
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Partial {
+    content: String,
+    updated_at: DateTime<Utc>,
+}
+
 impl Session {
     /// Create a new session
     pub fn new(model: &str) -> Result<Self> {
+        Self::new_in(&sessions_dir()?, model)
+    }
+
+    /// Create a new session under a specific sessions root
+    pub fn new_in(root: &Path, model: &str) -> Result<Self> {
         let id = generate_session_id();
-        let session_dir = sessions_dir()?.join(&id);
+        let session_dir = root.join(&id);
         fs::create_dir_all(&session_dir)?;
 
         let meta = SessionMeta {
@@ -153,6 +199,11 @@ impl Session {
                 .map(|p| p.display().to_string())
                 .unwrap_or_else(|_| ".".to_string()),
             description: None,
+            parent_id: None,
+            replaced_model: None,
+            attachments: vec![],
+            pins: vec![],
+            focus: vec![],
         };
 
         let mut session = Self {
@@ -178,7 +229,12 @@ impl Session {
 
     /// Load an existing session
     pub fn load(id: &str) -> Result<Self> {
-        let session_dir = sessions_dir()?.join(id);
+        Self::load_in(&sessions_dir()?, id)
+    }
+
+    /// Load an existing session from a specific sessions root
+    pub fn load_in(root: &Path, id: &str) -> Result<Self> {
+        let session_dir = root.join(id);
         if !session_dir.exists() {
             anyhow::bail!("Session not found: {}", id);
         }
@@ -190,19 +246,7 @@ impl Session {
         )
         .context("Failed to parse meta.json")?;
 
-        // Load messages
-        let messages_path = session_dir.join("messages.jsonl");
-        let messages = if messages_path.exists() {
-            let file = File::open(&messages_path)?;
-            let reader = BufReader::new(file);
-            reader
-                .lines()
-                .map_while(|line| line.ok())
-                .filter_map(|line| serde_json::from_str(&line).ok())
-                .collect()
-        } else {
-            vec![]
-        };
+        let messages = read_messages(&session_dir.join("messages.jsonl"))?;
 
         let mut session = Self {
             meta,
@@ -211,20 +255,139 @@ impl Session {
             log_file: None,
         };
 
+        session.recover_partial()?;
         session.open_log()?;
         Ok(session)
     }
 
     /// Load the most recent session, or create new
-    pub fn load_or_create(model: &str) -> Result<Self> {
-        if let Some(recent) = most_recent_session()? {
-            // Only resume if same model and less than 1 hour old
-            let age = Utc::now() - recent.updated_at;
-            if recent.model == model && age.num_hours() < 1 {
-                return Session::load(&recent.id);
+    ///
+    /// `replacing` is a model that is no longer offered and `model` stands in
+    /// for; a recent session on it is resumed and switched over to `model`.
+    pub fn load_or_create(model: &str, replacing: Option<&str>) -> Result<Self> {
+        Self::load_or_create_in(&sessions_dir()?, model, replacing)
+    }
+
+    pub fn load_or_create_in(root: &Path, model: &str, replacing: Option<&str>) -> Result<Self> {
+        // Only resume if same model and less than 1 hour old
+        if let Some(recent) = resumable_session_in(root) {
+            if recent.model == model {
+                return Session::load_in(root, &recent.id);
+            }
+            if Some(recent.model.as_str()) == replacing {
+                let mut session = Session::load_in(root, &recent.id)?;
+                session.substitute_model(&recent.model, model)?;
+                return Ok(session);
             }
         }
-        Session::new(model)
+        Session::new_in(root, model)
+    }
+
+    /// Record that `from` was swapped for `to` because it disappeared
+    pub fn substitute_model(&mut self, from: &str, to: &str) -> Result<()> {
+        self.meta.model = to.to_string();
+        self.meta.replaced_model = Some(from.to_string());
+        self.save_meta()?;
+        self.log(
+            "model_substitution",
+            serde_json::json!({ "from": from, "to": to }),
+        )
+    }
+
+    /// Continue from a comparison: record `prompt` and the chosen
+    /// `response`, and carry on with the model that wrote it
+    pub fn adopt_response(
+        &mut self,
+        prompt: &str,
+        model: &str,
+        response: &str,
+        tokens: Option<u32>,
+    ) -> Result<()> {
+        self.add_user_message(prompt)?;
+        self.add_assistant_message(response, tokens)?;
+        self.meta.model = model.to_string();
+        self.save_meta()?;
+        self.log("compare_adopt", serde_json::json!({ "model": model }))
+    }
+
+    /// Copy this session into a new one (new id, same messages) that
+    /// records this session as its parent
+    pub fn fork(&self) -> Result<Self> {
+        let root = self
+            .session_dir
+            .parent()
+            .context("Session directory has no parent")?;
+        let mut id = generate_session_id();
+        while id == self.meta.id || root.join(&id).exists() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            id = generate_session_id();
+        }
+        let session_dir = root.join(&id);
+        fs::create_dir_all(&session_dir)?;
+
+        let now = Utc::now();
+        let mut fork = Self {
+            meta: SessionMeta {
+                id,
+                created_at: now,
+                updated_at: now,
+                parent_id: Some(self.meta.id.clone()),
+                ..self.meta.clone()
+            },
+            messages: self.messages.clone(),
+            session_dir,
+            log_file: None,
+        };
+        fork.rewrite_messages()?;
+        fork.save_meta()?;
+        fork.open_log()?;
+        Ok(fork)
+    }
+
+    /// Track a file as an attachment; false if it already was
+    pub fn attach(&mut self, path: &str) -> Result<bool> {
+        if self.meta.attachments.iter().any(|p| p == path) {
+            return Ok(false);
+        }
+        self.meta.attachments.push(path.to_string());
+        self.save_meta()?;
+        Ok(true)
+    }
+
+    /// Stop tracking `paths` (all attachments when empty), returning those removed
+    pub fn detach(&mut self, paths: &[String]) -> Result<Vec<String>> {
+        let (removed, kept): (Vec<String>, Vec<String>) = self
+            .meta
+            .attachments
+            .drain(..)
+            .partition(|p| paths.is_empty() || paths.contains(p));
+        self.meta.attachments = kept;
+        self.save_meta()?;
+        Ok(removed)
+    }
+
+    /// Replace the focus set (empty clears it)
+    pub fn set_focus(&mut self, files: &[String]) -> Result<()> {
+        self.meta.focus = files.to_vec();
+        self.save_meta()
+    }
+
+    /// Pin a note, returning its 1-based number
+    pub fn pin(&mut self, note: &str) -> Result<usize> {
+        self.meta.pins.push(note.trim().to_string());
+        self.save_meta()?;
+        Ok(self.meta.pins.len())
+    }
+
+    /// Remove pin `number` (1-based), or all pins when None
+    pub fn unpin(&mut self, number: Option<usize>) -> Result<Vec<String>> {
+        let removed = match number {
+            None => std::mem::take(&mut self.meta.pins),
+            Some(n) if n >= 1 && n <= self.meta.pins.len() => vec![self.meta.pins.remove(n - 1)],
+            Some(n) => anyhow::bail!("No pin #{} ({} pinned)", n, self.meta.pins.len()),
+        };
+        self.save_meta()?;
+        Ok(removed)
     }
 
     /// Add a message to the conversation
@@ -309,6 +472,165 @@ impl Session {
         Ok(())
     }
 
+    /// Autosave the response still being streamed, so a crash doesn't lose it
+    pub fn save_partial(&self, content: &str) -> Result<()> {
+        let partial = Partial {
+            content: content.to_string(),
+            updated_at: Utc::now(),
+        };
+        let tmp_path = self.session_dir.join(".partial.json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(&partial)?)?;
+        fs::rename(&tmp_path, self.session_dir.join("partial.json"))?;
+        self.save_meta()
+    }
+
+    /// Discard the autosaved partial once the response is complete (or failed)
+    pub fn clear_partial(&self) -> Result<()> {
+        let path = self.session_dir.join("partial.json");
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Turn a leftover partial into an assistant message marked incomplete
+    fn recover_partial(&mut self) -> Result<()> {
+        let path = self.session_dir.join("partial.json");
+        let partial: Option<Partial> = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok());
+        if let Some(partial) = partial {
+            if !partial.content.trim().is_empty() {
+                self.add_message(Message {
+                    role: "assistant".into(),
+                    content: format!("{}\n\n{}", partial.content, INCOMPLETE_MARKER),
+                    timestamp: partial.updated_at,
+                    tokens: None,
+                })?;
+                self.save_meta()?;
+            }
+        }
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Drop everything after the last user prompt (a failed reply and its tool output).
+    /// Returns the number of messages removed.
+    pub fn drop_last_response(&mut self) -> Result<usize> {
+        let Some(idx) = last_user_index(&self.messages) else {
+            return Ok(0);
+        };
+        let dropped = self.messages.len() - idx - 1;
+        if dropped > 0 {
+            self.messages.truncate(idx + 1);
+            self.rewrite_messages()?;
+        }
+        Ok(dropped)
+    }
+
+    /// Discard the reply to the last user prompt so it can be asked again.
+    /// None when that prompt has no assistant reply yet.
+    pub fn regenerate(&mut self) -> Result<Option<Regenerated>> {
+        let Some(idx) = last_user_index(&self.messages) else {
+            return Ok(None);
+        };
+        let reply = &self.messages[idx + 1..];
+        if !reply.iter().any(|m| m.role == "assistant") {
+            return Ok(None);
+        }
+        let tokens = reply.iter().filter_map(|m| m.tokens).map(u64::from).sum();
+        let regenerated = Regenerated {
+            prompt: self.messages[idx].content.clone(),
+            dropped: reply.len(),
+            tokens,
+        };
+        self.messages.truncate(idx + 1);
+        self.meta.total_tokens = self.meta.total_tokens.saturating_sub(tokens);
+        self.rewrite_messages()?;
+        self.save_meta()?;
+        Ok(Some(regenerated))
+    }
+
+    /// Reset to the initial system prompt. Returns the number of messages removed.
+    pub fn clear(&mut self) -> Result<usize> {
+        let keep = self.prompt_len();
+        let removed = self.messages.len() - keep;
+        self.messages.truncate(keep);
+        self.rewrite_messages()?;
+        self.save_meta()?;
+        Ok(removed)
+    }
+
+    /// Transcript of the messages `compact` would fold, leaving the last
+    /// `keep_recent` untouched. Returns the exclusive end index to pass to
+    /// `compact`, or None if there's too little history to be worth it.
+    pub fn compaction_transcript(&self, keep_recent: usize) -> Option<(usize, String)> {
+        let start = self.prompt_len();
+        let end = self.messages.len().saturating_sub(keep_recent);
+        if end < start + 2 {
+            return None;
+        }
+
+        let transcript = self.messages[start..end]
+            .iter()
+            .map(|m| format!("{}: {}", m.role, m.content))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        Some((end, transcript))
+    }
+
+    /// Replace messages between the system prompt and `upto` with a single
+    /// summary message. Returns the number of messages removed.
+    pub fn compact(&mut self, upto: usize, summary: &str) -> Result<usize> {
+        let start = self.prompt_len();
+        if upto > self.messages.len() || upto <= start {
+            anyhow::bail!("Nothing to compact");
+        }
+
+        let folded = upto - start;
+        self.messages.splice(
+            start..upto,
+            [Message {
+                role: "system".into(),
+                content: format!("[Summary of {} earlier messages]\n{}", folded, summary),
+                timestamp: Utc::now(),
+                tokens: None,
+            }],
+        );
+        self.rewrite_messages()?;
+        self.save_meta()?;
+        Ok(folded - 1)
+    }
+
+    /// Length of the leading system prompt (0 or 1 messages)
+    fn prompt_len(&self) -> usize {
+        match self.messages.first() {
+            Some(m) if m.role == "system" => 1,
+            _ => 0,
+        }
+    }
+
+    /// Rewrite messages.jsonl from memory (temp file + rename)
+    fn rewrite_messages(&mut self) -> Result<()> {
+        let messages_path = self.session_dir.join("messages.jsonl");
+        let tmp_path = self.session_dir.join(".messages.jsonl.tmp");
+
+        let mut content = String::new();
+        for msg in &self.messages {
+            content.push_str(&serde_json::to_string(msg)?);
+            content.push('\n');
+        }
+
+        fs::write(&tmp_path, &content)?;
+        fs::rename(&tmp_path, &messages_path)?;
+
+        self.meta.message_count = self.messages.len();
+        self.meta.updated_at = Utc::now();
+        Ok(())
+    }
+
     /// Open log file for appending
     fn open_log(&mut self) -> Result<()> {
         let log_path = self.session_dir.join("log.jsonl");
@@ -354,13 +676,233 @@ impl Session {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════
+// AUTOSAVE
+// ═══════════════════════════════════════════════════════════════
+
+/// How often a streaming response is flushed to disk
+pub const AUTOSAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3);
+/// Flush sooner once this many tokens have streamed in since the last save
+pub const AUTOSAVE_TOKENS: usize = 200;
+
+/// Debounces partial-response autosaves
+#[derive(Debug)]
+pub struct Autosave {
+    interval: std::time::Duration,
+    token_threshold: usize,
+    last_save: std::time::Instant,
+    pending_tokens: usize,
+}
+
+impl Autosave {
+    pub fn new(interval: std::time::Duration, token_threshold: usize) -> Self {
+        Self {
+            interval,
+            token_threshold,
+            last_save: std::time::Instant::now(),
+            pending_tokens: 0,
+        }
+    }
+
+    pub fn record_tokens(&mut self, n: usize) {
+        self.pending_tokens += n;
+    }
+
+    /// Whether there is unsaved output and enough time or tokens have passed
+    pub fn due(&self, now: std::time::Instant) -> bool {
+        self.pending_tokens > 0
+            && (self.pending_tokens >= self.token_threshold
+                || now.duration_since(self.last_save) >= self.interval)
+    }
+
+    pub fn saved(&mut self, now: std::time::Instant) {
+        self.last_save = now;
+        self.pending_tokens = 0;
+    }
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self::new(AUTOSAVE_INTERVAL, AUTOSAVE_TOKENS)
+    }
+}
+
+/// Closes a hosted session after a stretch without activity
+///
+/// Opt-in via `idle_timeout_secs` or `HYLE_IDLE_TIMEOUT`, for TUIs the
+/// orchestrator starts and `--serve` processes that may be abandoned.
+#[derive(Debug)]
+pub struct IdleTimeout {
+    limit: std::time::Duration,
+    last_activity: std::time::Instant,
+    fired: bool,
+}
+
+impl IdleTimeout {
+    pub fn new(limit: std::time::Duration, now: std::time::Instant) -> Self {
+        Self {
+            limit,
+            last_activity: now,
+            fired: false,
+        }
+    }
+
+    /// From config and environment; None unless a limit is set
+    pub fn from_config() -> Option<Self> {
+        let configured = config::Config::load()
+            .ok()
+            .and_then(|c| c.idle_timeout_secs);
+        let env = std::env::var("HYLE_IDLE_TIMEOUT").ok();
+        config::idle_timeout(env.as_deref(), configured)
+            .map(|limit| Self::new(limit, std::time::Instant::now()))
+    }
+
+    pub fn limit(&self) -> std::time::Duration {
+        self.limit
+    }
+
+    pub fn touch(&mut self, now: std::time::Instant) {
+        self.last_activity = now;
+    }
+
+    pub fn is_idle(&self, now: std::time::Instant) -> bool {
+        now.saturating_duration_since(self.last_activity) >= self.limit
+    }
+
+    /// Run `teardown` the first time the session is found idle; true from
+    /// then on, telling the caller to stop
+    pub fn fire_if_idle<F>(&mut self, now: std::time::Instant, teardown: F) -> Result<bool>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        if self.fired {
+            return Ok(true);
+        }
+        if !self.is_idle(now) {
+            return Ok(false);
+        }
+        self.fired = true;
+        teardown()?;
+        Ok(true)
+    }
+}
+
+/// Index of the most recent user message
+fn last_user_index(messages: &[Message]) -> Option<usize> {
+    messages.iter().rposition(|m| m.role == "user")
+}
+
+/// Put attached files' current contents into `history` as a system message
+/// after any leading system messages, within `budget_tokens` (~4 chars each).
+/// Files past the budget are truncated or listed as omitted.
+pub fn with_attachments(
+    mut history: Vec<serde_json::Value>,
+    paths: &[String],
+    budget_tokens: usize,
+) -> Vec<serde_json::Value> {
+    if paths.is_empty() {
+        return history;
+    }
+
+    let mut remaining = budget_tokens * 4;
+    let mut content = String::from("Attached files (current contents):\n");
+    for path in paths {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                content.push_str(&format!("\n--- {} (unreadable: {}) ---\n", path, e));
+                continue;
+            }
+        };
+        if remaining == 0 {
+            content.push_str(&format!("\n--- {} (omitted: over budget) ---\n", path));
+            continue;
+        }
+        match text.char_indices().nth(remaining) {
+            Some((idx, _)) => {
+                content.push_str(&format!(
+                    "\n--- {} (truncated) ---\n{}\n...\n",
+                    path,
+                    &text[..idx]
+                ));
+                remaining = 0;
+            }
+            None => {
+                content.push_str(&format!("\n--- {} ---\n{}\n", path, text));
+                remaining -= text.chars().count();
+            }
+        }
+    }
+
+    let at = history
+        .iter()
+        .position(|m| m.get("role").and_then(|r| r.as_str()) != Some("system"))
+        .unwrap_or(history.len());
+    history.insert(
+        at,
+        serde_json::json!({ "role": "system", "content": content }),
+    );
+    history
+}
+
+/// Put pinned notes into `history` as a system message after any leading
+/// system messages. Pins sit at the Focus salience tier, so they are never
+/// trimmed for budget.
+pub fn with_pins(mut history: Vec<serde_json::Value>, pins: &[String]) -> Vec<serde_json::Value> {
+    if pins.is_empty() {
+        return history;
+    }
+
+    let mut salience = crate::cognitive::SalienceContext::new(0);
+    for pin in pins {
+        salience.pin(format!("- {}", pin));
+    }
+    let content = format!("Pinned notes (always apply):\n{}", salience.build());
+
+    let at = history
+        .iter()
+        .position(|m| m.get("role").and_then(|r| r.as_str()) != Some("system"))
+        .unwrap_or(history.len());
+    history.insert(
+        at,
+        serde_json::json!({ "role": "system", "content": content }),
+    );
+    history
+}
+
+/// Parse messages.jsonl, skipping lines that don't parse
+fn read_messages(path: &Path) -> Result<Vec<Message>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let reader = BufReader::new(File::open(path)?);
+    Ok(reader
+        .lines()
+        .map_while(|line| line.ok())
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
 /// Get sessions directory
 pub fn sessions_dir() -> Result<PathBuf> {
-    let dir = config::state_dir()?.join("sessions");
+    let configured = config::Config::load().ok().and_then(|c| c.sessions_dir);
+    let dir = config::sessions_root(
+        std::env::var("HYLE_SESSIONS_DIR").ok().as_deref(),
+        configured.as_deref(),
+    )?;
     fs::create_dir_all(&dir)?;
     Ok(dir)
 }
 
+/// Whether `id` is safe as a session directory name: letters, digits,
+/// `-` and `_` only
+pub fn is_valid_session_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
 /// Generate a unique session ID with random suffix to prevent collisions
 fn generate_session_id() -> String {
     let now = Utc::now();
@@ -373,10 +915,14 @@ fn generate_session_id() -> String {
 
 /// List all sessions, sorted by updated_at (newest first)
 pub fn list_sessions() -> Result<Vec<SessionMeta>> {
-    let dir = sessions_dir()?;
+    Ok(list_sessions_in(&sessions_dir()?))
+}
+
+/// List sessions under a specific sessions root, newest first
+pub fn list_sessions_in(dir: &Path) -> Vec<SessionMeta> {
     let mut sessions = Vec::new();
 
-    if let Ok(entries) = fs::read_dir(&dir) {
+    if let Ok(entries) = fs::read_dir(dir) {
         for entry in entries.filter_map(|e| e.ok()) {
             let meta_path = entry.path().join("meta.json");
             if meta_path.exists() {
@@ -391,7 +937,32 @@ pub fn list_sessions() -> Result<Vec<SessionMeta>> {
 
     // Sort by updated_at, newest first
     sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
-    Ok(sessions)
+    sessions
+}
+
+/// Resolve a full session id or a unique prefix of one
+pub fn resolve_session_id(query: &str) -> Result<String> {
+    resolve_session_id_in(&sessions_dir()?, query)
+}
+
+pub fn resolve_session_id_in(root: &Path, query: &str) -> Result<String> {
+    let sessions = list_sessions_in(root);
+    if sessions.iter().any(|s| s.id == query) {
+        return Ok(query.to_string());
+    }
+    let matches: Vec<&SessionMeta> = sessions
+        .iter()
+        .filter(|s| s.id.starts_with(query))
+        .collect();
+    match matches.as_slice() {
+        [one] => Ok(one.id.clone()),
+        [] => anyhow::bail!("Session not found: {}", query),
+        many => anyhow::bail!(
+            "Ambiguous session id '{}' matches {} sessions",
+            query,
+            many.len()
+        ),
+    }
 }
 
 /// Get most recent session
@@ -400,19 +971,629 @@ pub fn most_recent_session() -> Result<Option<SessionMeta>> {
     Ok(sessions.into_iter().next())
 }
 
+/// The most recent session, if it is fresh enough (under an hour) to resume
+pub fn resumable_session() -> Result<Option<SessionMeta>> {
+    Ok(resumable_session_in(&sessions_dir()?))
+}
+
+fn resumable_session_in(root: &Path) -> Option<SessionMeta> {
+    list_sessions_in(root)
+        .into_iter()
+        .next()
+        .filter(|recent| (Utc::now() - recent.updated_at).num_hours() < 1)
+}
+
 /// Clean up old sessions (keep last N)
 pub fn cleanup_sessions(keep: usize) -> Result<usize> {
-    let sessions = list_sessions()?;
-    let mut removed = 0;
+    Ok(cleanup_sessions_in(&sessions_dir()?, keep))
+}
+
+/// `cleanup_sessions` under a specific sessions root
+pub fn cleanup_sessions_in(root: &Path, keep: usize) -> usize {
+    list_sessions_in(root)
+        .into_iter()
+        .skip(keep)
+        .filter(|session| fs::remove_dir_all(root.join(&session.id)).is_ok())
+        .count()
+}
+
+// ═══════════════════════════════════════════════════════════════
+// LISTING
+// ═══════════════════════════════════════════════════════════════
+
+/// Sessions shown per page by `hyle sessions`
+pub const DEFAULT_LIST_LIMIT: usize = 10;
+
+/// Order of the `hyle sessions` list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionSort {
+    /// Most recently updated first
+    #[default]
+    Recent,
+    /// Most tokens first
+    Tokens,
+    /// Most messages first
+    Messages,
+}
+
+impl SessionSort {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "recent" | "updated" => Ok(Self::Recent),
+            "tokens" => Ok(Self::Tokens),
+            "messages" | "msgs" => Ok(Self::Messages),
+            other => anyhow::bail!("Unknown sort: {} (recent, tokens, messages)", other),
+        }
+    }
+}
+
+/// Filter, order and page of `hyle sessions`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionQuery {
+    /// Only sessions updated within this long
+    pub since: Option<chrono::Duration>,
+    pub sort: SessionSort,
+    /// Page size; 0 shows everything
+    pub limit: usize,
+    pub offset: usize,
+}
+
+impl Default for SessionQuery {
+    fn default() -> Self {
+        Self {
+            since: None,
+            sort: SessionSort::default(),
+            limit: DEFAULT_LIST_LIMIT,
+            offset: 0,
+        }
+    }
+}
+
+impl SessionQuery {
+    /// From the `--since`, `--sort`, `--limit` and `--offset` values given
+    pub fn from_flags(
+        since: Option<&str>,
+        sort: Option<&str>,
+        limit: Option<&str>,
+        offset: Option<&str>,
+    ) -> Result<Self> {
+        let number = |flag: &str, value: Option<&str>, default: usize| -> Result<usize> {
+            match value {
+                Some(v) => v
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("{} needs a number, got: {}", flag, v)),
+                None => Ok(default),
+            }
+        };
+        Ok(Self {
+            since: since.map(parse_duration).transpose()?,
+            sort: sort
+                .map(SessionSort::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            limit: number("--limit", limit, DEFAULT_LIST_LIMIT)?,
+            offset: number("--offset", offset, 0)?,
+        })
+    }
+
+    /// The requested page of `sessions`, and how many matched the filter
+    pub fn apply(
+        &self,
+        sessions: Vec<SessionMeta>,
+        now: DateTime<Utc>,
+    ) -> (Vec<SessionMeta>, usize) {
+        let mut matched: Vec<SessionMeta> = sessions
+            .into_iter()
+            .filter(|s| !matches!(self.since, Some(since) if now - s.updated_at > since))
+            .collect();
+        // Stable sorts: ties keep the newest first
+        matched.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        match self.sort {
+            SessionSort::Recent => {}
+            SessionSort::Tokens => matched.sort_by(|a, b| b.total_tokens.cmp(&a.total_tokens)),
+            SessionSort::Messages => matched.sort_by(|a, b| b.message_count.cmp(&a.message_count)),
+        }
+        let total = matched.len();
+        let limit = if self.limit == 0 {
+            usize::MAX
+        } else {
+            self.limit
+        };
+        let page = matched.into_iter().skip(self.offset).take(limit).collect();
+        (page, total)
+    }
+}
+
+/// Parse a human duration such as "7d", "12h", "30m", "90s", "2w" or
+/// "1d12h"
+pub fn parse_duration(value: &str) -> Result<chrono::Duration> {
+    let value = value.trim().to_lowercase();
+    if value.is_empty() {
+        anyhow::bail!("Empty duration");
+    }
+    let mut total = chrono::Duration::zero();
+    let mut rest = value.as_str();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let n: i64 = rest[..digits]
+            .parse()
+            .with_context(|| format!("Invalid duration: {} (try 7d, 12h, 30m)", value))?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let step = match rest[..unit_len].trim() {
+            "s" | "sec" | "secs" => chrono::Duration::seconds(n),
+            "m" | "min" | "mins" => chrono::Duration::minutes(n),
+            "h" | "hr" | "hrs" => chrono::Duration::hours(n),
+            "d" | "day" | "days" => chrono::Duration::days(n),
+            "w" | "wk" | "wks" => chrono::Duration::weeks(n),
+            "" => anyhow::bail!("Duration needs a unit: {} (try 7d, 12h, 30m)", value),
+            unit => anyhow::bail!("Unknown duration unit '{}' in {}", unit, value),
+        };
+        total += step;
+        rest = &rest[unit_len..];
+    }
+    Ok(total)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// EXPORT / IMPORT
+// ═══════════════════════════════════════════════════════════════
+
+/// Format version written by `SessionArchive::collect_from`
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Every session in one portable JSON file (`hyle sessions --export-all`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionArchive {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub sessions: Vec<ArchivedSession>,
+    /// Contents of the models cache, when exported with `--with-models`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub models_cache: Option<serde_json::Value>,
+}
+
+/// One session's files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedSession {
+    pub meta: SessionMeta,
+    pub messages: Vec<Message>,
+    /// log.jsonl lines as written
+    #[serde(default)]
+    pub log: Vec<String>,
+}
+
+/// What an import did
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    /// (archived id, new id) for ids already taken by a different session
+    pub renamed: Vec<(String, String)>,
+    /// Already here with the same history
+    pub skipped: Vec<String>,
+}
+
+impl SessionArchive {
+    /// Read every session under `root`, oldest first
+    pub fn collect_from(root: &Path) -> Result<Self> {
+        let mut sessions = Vec::new();
+        for meta in list_sessions_in(root).into_iter().rev() {
+            let dir = root.join(&meta.id);
+            let messages = read_messages(&dir.join("messages.jsonl"))?;
+            let log = fs::read_to_string(dir.join("log.jsonl"))
+                .map(|s| s.lines().map(String::from).collect())
+                .unwrap_or_default();
+            sessions.push(ArchivedSession {
+                meta,
+                messages,
+                log,
+            });
+        }
+        Ok(Self {
+            version: ARCHIVE_VERSION,
+            exported_at: Utc::now(),
+            sessions,
+            models_cache: None,
+        })
+    }
+
+    /// Write into `dir` as hyle-sessions-<timestamp>.json
+    pub fn write_to_dir(&self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!(
+            "hyle-sessions-{}.json",
+            self.exported_at.format("%Y%m%d-%H%M%S")
+        ));
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
 
-    for session in sessions.into_iter().skip(keep) {
-        let session_dir = sessions_dir()?.join(&session.id);
-        if fs::remove_dir_all(&session_dir).is_ok() {
-            removed += 1;
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let archive: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Not a session archive: {}", path.display()))?;
+        if archive.version > ARCHIVE_VERSION {
+            anyhow::bail!(
+                "{} was written by a newer hyle (archive version {})",
+                path.display(),
+                archive.version
+            );
         }
+        Ok(archive)
     }
 
-    Ok(removed)
+    /// Restore into `root`
+    ///
+    /// A session whose id is taken by a different session, or isn't a valid
+    /// id, gets a fresh id, and forks of it follow; an identical one is
+    /// skipped, so importing the same archive twice is harmless.
+    pub fn import_into(&self, root: &Path) -> Result<ImportReport> {
+        fs::create_dir_all(root)?;
+        let mut report = ImportReport::default();
+        let mut reserved: Vec<String> = self.sessions.iter().map(|s| s.meta.id.clone()).collect();
+        let mut pending = Vec::new();
+
+        for archived in &self.sessions {
+            let id = archived.meta.id.clone();
+            // Ids come from the archive; one like "../x" must not reach a path
+            let valid = is_valid_session_id(&id);
+            let existing = if valid {
+                fs::read_to_string(root.join(&id).join("meta.json"))
+                    .ok()
+                    .and_then(|c| serde_json::from_str::<SessionMeta>(&c).ok())
+            } else {
+                None
+            };
+            match existing {
+                None if valid && !root.join(&id).exists() => pending.push((archived, id)),
+                Some(meta)
+                    if meta.created_at == archived.meta.created_at
+                        && meta.updated_at == archived.meta.updated_at
+                        && meta.message_count == archived.meta.message_count =>
+                {
+                    report.skipped.push(id)
+                }
+                _ => {
+                    let mut fresh = generate_session_id();
+                    while root.join(&fresh).exists() || reserved.contains(&fresh) {
+                        std::thread::sleep(std::time::Duration::from_millis(1));
+                        fresh = generate_session_id();
+                    }
+                    reserved.push(fresh.clone());
+                    report.renamed.push((id, fresh.clone()));
+                    pending.push((archived, fresh));
+                }
+            }
+        }
+
+        for (archived, id) in pending {
+            let mut meta = archived.meta.clone();
+            meta.id = id.clone();
+            if let Some((_, renamed)) = report
+                .renamed
+                .iter()
+                .find(|(old, _)| Some(old) == meta.parent_id.as_ref())
+            {
+                meta.parent_id = Some(renamed.clone());
+            }
+
+            let dir = root.join(&id);
+            fs::create_dir_all(&dir)?;
+            let mut messages = String::new();
+            for msg in &archived.messages {
+                messages.push_str(&serde_json::to_string(msg)?);
+                messages.push('\n');
+            }
+            fs::write(dir.join("messages.jsonl"), messages)?;
+            if !archived.log.is_empty() {
+                fs::write(dir.join("log.jsonl"), archived.log.join("\n") + "\n")?;
+            }
+            // Last, so a half-written session isn't listed
+            fs::write(dir.join("meta.json"), serde_json::to_string_pretty(&meta)?)?;
+            report.imported.push(id);
+        }
+        Ok(report)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// HTML EXPORT
+// ═══════════════════════════════════════════════════════════════
+
+const HTML_STYLE: &str = "\
+body{font:15px/1.5 system-ui,sans-serif;max-width:860px;margin:2em auto;padding:0 1em;color:#1f2328;background:#fff}
+header .meta{color:#656d76;font-size:13px}
+.msg{border:1px solid #d0d7de;border-radius:6px;margin:1em 0;padding:.5em 1em}
+.msg.user{background:#f6f8fa}.msg.system{background:#fff8c5}
+.role{font-weight:600;font-size:13px;color:#656d76;text-transform:uppercase}
+.role time{font-weight:400;text-transform:none;margin-left:.5em}
+.text{white-space:pre-wrap;margin:.5em 0}
+pre{background:#161b22;color:#e6edf3;padding:.75em;border-radius:6px;overflow-x:auto}
+code{font:13px ui-monospace,monospace}.text code{background:#eff1f3;padding:0 .25em;border-radius:4px}
+.kw{color:#ff7b72}.str{color:#a5d6ff}.com{color:#8b949e;font-style:italic}.num{color:#79c0ff}
+details{border-left:3px solid #8c959f;margin:.5em 0;padding-left:.75em}
+details.error,details.killed{border-color:#cf222e}
+summary{cursor:pointer;font:13px ui-monospace,monospace;color:#656d76}
+";
+
+/// Words highlighted as keywords in code blocks, across common languages
+const HIGHLIGHT_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "class", "const", "continue", "def", "else", "enum", "export",
+    "false", "fn", "for", "from", "func", "function", "if", "impl", "import", "in", "let", "match",
+    "mod", "mut", "None", "null", "pub", "return", "self", "Self", "static", "struct", "trait",
+    "true", "type", "use", "var", "where", "while",
+];
+
+/// A message split into what the HTML page shows differently
+#[derive(Debug, Clone, PartialEq)]
+enum HtmlBlock {
+    Text(String),
+    Code {
+        lang: String,
+        body: String,
+    },
+    /// A tool call the model wrote (JSON block or `<tool>` tag)
+    ToolCall {
+        name: String,
+        body: String,
+    },
+    /// A `<tool_result ...>` block fed back to the model
+    ToolResult {
+        summary: String,
+        status: String,
+        body: String,
+    },
+}
+
+impl Session {
+    /// Self-contained HTML page of the conversation, for sharing
+    ///
+    /// Code blocks are highlighted and tool calls and results fold away;
+    /// all message text is escaped.
+    pub fn export_html(&self) -> String {
+        render_html(&self.meta, &self.messages)
+    }
+}
+
+fn render_html(meta: &SessionMeta, messages: &[Message]) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>hyle session {}</title>\n<style>\n{}</style>\n</head>\n<body>\n",
+        escape_html(&meta.id),
+        HTML_STYLE
+    );
+    html.push_str(&format!(
+        "<header>\n<h1>{}</h1>\n<p class=\"meta\">{} &middot; {} &middot; {} &middot; {} messages</p>\n</header>\n<main>\n",
+        escape_html(meta.description.as_deref().unwrap_or("hyle session")),
+        escape_html(&meta.id),
+        escape_html(&meta.model),
+        meta.created_at.format("%Y-%m-%d %H:%M UTC"),
+        messages.len()
+    ));
+
+    for msg in messages {
+        let role = escape_html(&msg.role);
+        html.push_str(&format!(
+            "<section class=\"msg {}\">\n<div class=\"role\">{}<time>{}</time></div>\n",
+            role,
+            role,
+            msg.timestamp.format("%H:%M:%S")
+        ));
+        for block in html_blocks(&msg.content) {
+            html.push_str(&render_block(&block));
+        }
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</main>\n</body>\n</html>\n");
+    html
+}
+
+fn render_block(block: &HtmlBlock) -> String {
+    match block {
+        HtmlBlock::Text(text) => format!("<div class=\"text\">{}</div>\n", inline_code(text)),
+        HtmlBlock::Code { lang, body } => format!(
+            "<pre><code class=\"lang-{}\">{}</code></pre>\n",
+            escape_html(lang),
+            highlight_code(lang, body)
+        ),
+        HtmlBlock::ToolCall { name, body } => format!(
+            "<details class=\"tool-call\">\n<summary>tool call: {}</summary>\n<pre><code>{}</code></pre>\n</details>\n",
+            escape_html(name),
+            highlight_code("json", body)
+        ),
+        HtmlBlock::ToolResult {
+            summary,
+            status,
+            body,
+        } => format!(
+            "<details class=\"tool-result {}\">\n<summary>{}</summary>\n<pre><code>{}</code></pre>\n</details>\n",
+            escape_html(status),
+            escape_html(summary),
+            escape_html(body)
+        ),
+    }
+}
+
+/// Split message text into prose, fenced code, tool calls and tool results
+fn html_blocks(content: &str) -> Vec<HtmlBlock> {
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+    let mut lines = content.lines();
+
+    let flush = |text: &mut String, blocks: &mut Vec<HtmlBlock>| {
+        if !text.trim().is_empty() {
+            blocks.push(HtmlBlock::Text(text.trim_matches('\n').to_string()));
+        }
+        text.clear();
+    };
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        if let Some(lang) = trimmed.strip_prefix("```") {
+            flush(&mut text, &mut blocks);
+            let body = take_until(&mut lines, |l| l.trim_start().starts_with("```"));
+            let lang = lang.trim().to_string();
+            blocks.push(match tool_call_name(&body) {
+                Some(name) if matches!(lang.as_str(), "" | "json" | "tool") => {
+                    HtmlBlock::ToolCall { name, body }
+                }
+                _ => HtmlBlock::Code { lang, body },
+            });
+        } else if trimmed.starts_with("<tool_result") {
+            flush(&mut text, &mut blocks);
+            let body = take_until(&mut lines, |l| l.trim() == "</tool_result>");
+            let attr = |name: &str| {
+                let key = format!("{}=\"", name);
+                let start = trimmed.find(&key)? + key.len();
+                let len = trimmed[start..].find('"')?;
+                Some(trimmed[start..start + len].to_string())
+            };
+            let status = attr("status").unwrap_or_default();
+            let summary = [attr("tool"), attr("args"), Some(status.clone())]
+                .into_iter()
+                .flatten()
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>()
+                .join(" \u{b7} ");
+            blocks.push(HtmlBlock::ToolResult {
+                summary,
+                status,
+                body,
+            });
+        } else if trimmed == "<tool>" {
+            flush(&mut text, &mut blocks);
+            let body = take_until(&mut lines, |l| l.trim() == "</tool>");
+            let name = tool_call_name(&body).unwrap_or_else(|| "tool".into());
+            blocks.push(HtmlBlock::ToolCall { name, body });
+        } else {
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    flush(&mut text, &mut blocks);
+    blocks
+}
+
+/// Lines up to (not including) the first one matching `end`, which is consumed
+fn take_until<'a>(lines: &mut impl Iterator<Item = &'a str>, end: impl Fn(&str) -> bool) -> String {
+    let mut body = Vec::new();
+    for line in lines.by_ref() {
+        if end(line) {
+            break;
+        }
+        body.push(line);
+    }
+    body.join("\n")
+}
+
+/// Tool name when `body` is a JSON tool call (or a list of them)
+fn tool_call_name(body: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(body.trim()).ok()?;
+    let first = value.as_array().and_then(|a| a.first()).unwrap_or(&value);
+    let name = first.get("tool").or_else(|| first.get("name"))?.as_str()?;
+    Some(name.to_string())
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escaped prose with `inline code` spans
+fn inline_code(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let parts: Vec<&str> = line.split('`').collect();
+            if parts.len() % 2 == 0 {
+                // Unbalanced backtick: leave the line as written
+                return escape_html(line);
+            }
+            parts
+                .iter()
+                .enumerate()
+                .map(|(i, part)| match i % 2 {
+                    1 => format!("<code>{}</code>", escape_html(part)),
+                    _ => escape_html(part),
+                })
+                .collect()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Escaped code with keywords, strings, comments and numbers wrapped in
+/// spans; a rough lexer, good enough for reading
+fn highlight_code(lang: &str, code: &str) -> String {
+    let hash_comments = matches!(
+        lang,
+        "python" | "py" | "sh" | "bash" | "shell" | "zsh" | "toml" | "yaml" | "yml" | "ruby" | "rb"
+    );
+    let single_quote_strings = !matches!(lang, "rust" | "rs");
+    let span =
+        |class: &str, text: &str| format!("<span class=\"{}\">{}</span>", class, escape_html(text));
+
+    let chars: Vec<char> = code.chars().collect();
+    let mut out = String::with_capacity(code.len() * 2);
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let rest_of_line = |from: usize| {
+            chars[from..]
+                .iter()
+                .position(|&c| c == '\n')
+                .map_or(chars.len(), |n| from + n)
+        };
+        let start = i;
+        if (c == '/' && chars.get(i + 1) == Some(&'/')) || (c == '#' && hash_comments) {
+            i = rest_of_line(i);
+            out.push_str(&span("com", &chars[start..i].iter().collect::<String>()));
+        } else if c == '"' || (c == '\'' && single_quote_strings) {
+            i += 1;
+            while i < chars.len() && chars[i] != c && chars[i] != '\n' {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(chars.len());
+            out.push_str(&span("str", &chars[start..i].iter().collect::<String>()));
+        } else if c.is_ascii_digit() {
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric() || chars[i] == '.' || chars[i] == '_')
+            {
+                i += 1;
+            }
+            out.push_str(&span("num", &chars[start..i].iter().collect::<String>()));
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if HIGHLIGHT_KEYWORDS.contains(&word.as_str()) {
+                out.push_str(&span("kw", &word));
+            } else {
+                out.push_str(&escape_html(&word));
+            }
+        } else {
+            out.push_str(&escape_html(&c.to_string()));
+            i += 1;
+        }
+    }
+    out
 }
 
 // ═══════════════════════════════════════════════════════════════
@@ -578,6 +1759,631 @@ mod tests {
         assert!(id.contains('-'));
     }
 
+    #[test]
+    fn test_drop_last_response() {
+        let root = std::env::temp_dir().join(format!("hyle_test_sessions_{}", std::process::id()));
+        let mut session = Session::new_in(&root, "test/model").unwrap();
+        session.add_user_message("first").unwrap();
+        session.add_assistant_message("ok", None).unwrap();
+        session.add_user_message("second").unwrap();
+        session.add_assistant_message("bad answer", None).unwrap();
+        session.add_system_message("tool output").unwrap();
+
+        assert_eq!(session.drop_last_response().unwrap(), 2);
+        assert_eq!(session.messages.last().unwrap().content, "second");
+
+        // Persisted, not just in memory
+        let reloaded = Session::load_in(&root, &session.meta.id).unwrap();
+        assert_eq!(reloaded.messages.len(), session.messages.len());
+        assert_eq!(reloaded.messages.last().unwrap().content, "second");
+
+        // Nothing after the last prompt: no-op
+        assert_eq!(session.drop_last_response().unwrap(), 0);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_regenerate_drops_one_assistant_turn() {
+        let root = std::env::temp_dir().join(format!("hyle_test_regen_{}", std::process::id()));
+        let mut session = Session::new_in(&root, "test/model").unwrap();
+        session.add_user_message("first").unwrap();
+        session.add_assistant_message("ok", Some(10)).unwrap();
+        session.add_user_message("second").unwrap();
+        session
+            .add_assistant_message("calling a tool", Some(20))
+            .unwrap();
+        session.add_system_message("tool output").unwrap();
+        session
+            .add_assistant_message("meh answer", Some(5))
+            .unwrap();
+        let before = session.messages.len();
+        assert_eq!(session.meta.total_tokens, 35);
+
+        let regen = session.regenerate().unwrap().unwrap();
+        assert_eq!(regen.prompt, "second");
+        assert_eq!(regen.dropped, 3);
+        assert_eq!(regen.tokens, 25);
+        assert_eq!(session.messages.len(), before - 3);
+        assert_eq!(session.messages.last().unwrap().content, "second");
+        // The earlier turn is untouched and only its tokens remain
+        assert_eq!(session.messages[session.messages.len() - 2].content, "ok");
+        assert_eq!(session.meta.total_tokens, 10);
+
+        let reloaded = Session::load_in(&root, &session.meta.id).unwrap();
+        assert_eq!(reloaded.messages.len(), session.messages.len());
+        assert_eq!(reloaded.meta.total_tokens, 10);
+
+        // The prompt now has no reply: nothing more to regenerate
+        assert_eq!(session.regenerate().unwrap(), None);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_switch_sessions_keeps_messages_separate() {
+        let root = std::env::temp_dir().join(format!("hyle_test_switch_{}", std::process::id()));
+        let mut a = Session::new_in(&root, "test/model").unwrap();
+        a.add_user_message("question for a").unwrap();
+        let a_id = a.meta.id.clone();
+        a.save_meta().unwrap();
+
+        // Switch to a fresh session, talk there, then switch back
+        let mut b = Session::new_in(&root, "test/model").unwrap();
+        b.add_user_message("question for b").unwrap();
+        b.add_assistant_message("answer for b", None).unwrap();
+        b.save_meta().unwrap();
+
+        let mut current =
+            Session::load_in(&root, &resolve_session_id_in(&root, &a_id).unwrap()).unwrap();
+        assert_eq!(current.messages.len(), 2);
+        assert_eq!(current.messages[1].content, "question for a");
+        current.add_assistant_message("answer for a", None).unwrap();
+
+        let b = Session::load_in(&root, &b.meta.id).unwrap();
+        assert_eq!(b.messages.len(), 3);
+        assert!(b.messages.iter().all(|m| !m.content.contains("for a")));
+
+        let a = Session::load_in(&root, &a_id).unwrap();
+        assert_eq!(a.messages.last().unwrap().content, "answer for a");
+
+        assert!(resolve_session_id_in(&root, "no-such-session").is_err());
+        assert_eq!(list_sessions_in(&root).len(), 2);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_fork() {
+        let root = std::env::temp_dir().join(format!("hyle_test_fork_{}", std::process::id()));
+        let mut original = Session::new_in(&root, "test/model").unwrap();
+        original.add_user_message("explore this").unwrap();
+        original.add_assistant_message("sure", None).unwrap();
+
+        let mut fork = original.fork().unwrap();
+        assert_ne!(fork.meta.id, original.meta.id);
+        assert_eq!(
+            fork.meta.parent_id.as_deref(),
+            Some(original.meta.id.as_str())
+        );
+        assert_eq!(fork.messages.len(), original.messages.len());
+        assert!(fork
+            .messages
+            .iter()
+            .zip(&original.messages)
+            .all(|(a, b)| a.content == b.content && a.role == b.role));
+
+        // Diverging in the fork leaves the original untouched
+        fork.add_user_message("alternate direction").unwrap();
+        let reloaded = Session::load_in(&root, &original.meta.id).unwrap();
+        assert_eq!(reloaded.messages.len(), 3);
+        let reloaded_fork = Session::load_in(&root, &fork.meta.id).unwrap();
+        assert_eq!(reloaded_fork.messages.len(), 4);
+
+        let listed = list_sessions_in(&root);
+        let listed_fork = listed.iter().find(|s| s.id == fork.meta.id).unwrap();
+        assert_eq!(listed_fork.parent_id, Some(original.meta.id.clone()));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_resume_substitutes_stale_model() {
+        let root = std::env::temp_dir().join(format!("hyle_test_stale_{}", std::process::id()));
+        let mut old = Session::new_in(&root, "gone/model").unwrap();
+        old.add_user_message("before the model vanished").unwrap();
+        old.save_meta().unwrap();
+
+        // A stand-in for the vanished model picks the session up
+        let resumed = Session::load_or_create_in(&root, "free/alt", Some("gone/model")).unwrap();
+        assert_eq!(resumed.meta.id, old.meta.id);
+        assert_eq!(resumed.messages.len(), 2);
+        assert_eq!(resumed.meta.model, "free/alt");
+        assert_eq!(resumed.meta.replaced_model.as_deref(), Some("gone/model"));
+
+        let reloaded = Session::load_in(&root, &old.meta.id).unwrap();
+        assert_eq!(reloaded.meta.model, "free/alt");
+        assert_eq!(reloaded.meta.replaced_model.as_deref(), Some("gone/model"));
+        let log = fs::read_to_string(root.join(&old.meta.id).join("log.jsonl")).unwrap();
+        assert!(log.contains("model_substitution"));
+
+        // Now on the stand-in, it resumes like any other session
+        let again = Session::load_or_create_in(&root, "free/alt", None).unwrap();
+        assert_eq!(again.meta.id, old.meta.id);
+        let fresh = Session::load_or_create_in(&root, "other/model", None).unwrap();
+        assert_ne!(fresh.meta.id, old.meta.id);
+        assert_eq!(fresh.meta.replaced_model, None);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_adopt_response() {
+        let root = std::env::temp_dir().join(format!("hyle_test_adopt_{}", std::process::id()));
+        let mut session = Session::new_in(&root, "a/one").unwrap();
+        session.add_user_message("earlier").unwrap();
+        session.add_assistant_message("reply", None).unwrap();
+        let before = session.messages.len();
+
+        session
+            .adopt_response("which is faster?", "b/two", "b/two's answer", Some(42))
+            .unwrap();
+        assert_eq!(session.messages.len(), before + 2);
+        let user = &session.messages[before];
+        let assistant = &session.messages[before + 1];
+        assert_eq!(
+            (user.role.as_str(), user.content.as_str()),
+            ("user", "which is faster?")
+        );
+        assert_eq!(assistant.role, "assistant");
+        assert_eq!(assistant.content, "b/two's answer");
+        assert_eq!(assistant.tokens, Some(42));
+        assert_eq!(session.meta.model, "b/two");
+
+        // The next request carries the adopted exchange
+        let api = session.messages_for_api();
+        assert_eq!(api.last().unwrap()["content"], "b/two's answer");
+
+        let reloaded = Session::load_in(&root, &session.meta.id).unwrap();
+        assert_eq!(reloaded.meta.model, "b/two");
+        assert_eq!(reloaded.messages.len(), before + 2);
+        let log = fs::read_to_string(root.join(&session.meta.id).join("log.jsonl")).unwrap();
+        assert!(log.contains("compare_adopt"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_import_renames_traversal_ids() {
+        let base =
+            std::env::temp_dir().join(format!("hyle_test_import_ids_{}", std::process::id()));
+        let (from, to) = (base.join("from"), base.join("a/b/to"));
+        let mut session = Session::new_in(&from, "test/model").unwrap();
+        session.add_user_message("hello").unwrap();
+        session.save_meta().unwrap();
+
+        let mut archive = SessionArchive::collect_from(&from).unwrap();
+        archive.sessions[0].meta.id = "../../escaped".into();
+        let report = archive.import_into(&to).unwrap();
+
+        assert!(!base.join("a/escaped").exists());
+        assert!(!base.join("escaped").exists());
+        assert_eq!(report.renamed.len(), 1);
+        let (old, new) = &report.renamed[0];
+        assert_eq!(old, "../../escaped");
+        assert!(is_valid_session_id(new));
+        assert_eq!(Session::load_in(&to, new).unwrap().messages.len(), 1);
+
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let base = std::env::temp_dir().join(format!("hyle_test_export_{}", std::process::id()));
+        let (from, to, out) = (base.join("from"), base.join("to"), base.join("out"));
+
+        let mut original = Session::new_in(&from, "test/model").unwrap();
+        original
+            .add_user_message("explain the borrow checker")
+            .unwrap();
+        original
+            .add_assistant_message("It tracks ownership.", Some(12))
+            .unwrap();
+        original.pin("answer briefly").unwrap();
+        original
+            .log("tool", serde_json::json!({ "name": "read" }))
+            .unwrap();
+        original.save_meta().unwrap();
+        let mut fork = original.fork().unwrap();
+        fork.add_user_message("and lifetimes?").unwrap();
+        fork.save_meta().unwrap();
+
+        let written = SessionArchive::collect_from(&from)
+            .unwrap()
+            .write_to_dir(&out)
+            .unwrap();
+        let archive = SessionArchive::load(&written).unwrap();
+        assert_eq!(archive.sessions.len(), 2);
+
+        let report = archive.import_into(&to).unwrap();
+        assert_eq!(report.imported.len(), 2);
+        assert!(report.renamed.is_empty());
+        for id in [&original.meta.id, &fork.meta.id] {
+            let before = Session::load_in(&from, id).unwrap();
+            let after = Session::load_in(&to, id).unwrap();
+            let contents = |s: &Session| -> Vec<(String, String, Option<u32>)> {
+                s.messages
+                    .iter()
+                    .map(|m| (m.role.clone(), m.content.clone(), m.tokens))
+                    .collect()
+            };
+            assert_eq!(contents(&after), contents(&before));
+            assert_eq!(after.meta.pins, before.meta.pins);
+            assert_eq!(after.meta.parent_id, before.meta.parent_id);
+            assert_eq!(after.meta.total_tokens, before.meta.total_tokens);
+        }
+        let log = fs::read_to_string(to.join(&original.meta.id).join("log.jsonl")).unwrap();
+        assert!(log.contains("\"read\""));
+
+        // A second import changes nothing
+        let again = archive.import_into(&to).unwrap();
+        assert!(again.imported.is_empty());
+        assert_eq!(again.skipped.len(), 2);
+
+        // The original's id is taken by something else on this machine:
+        // it arrives under a new id, and the fork follows it
+        let elsewhere = base.join("elsewhere");
+        let mut other = Session::new_in(&elsewhere, "other/model").unwrap();
+        other.add_user_message("unrelated").unwrap();
+        fs::rename(
+            elsewhere.join(&other.meta.id),
+            elsewhere.join(&original.meta.id),
+        )
+        .unwrap();
+        let report = archive.import_into(&elsewhere).unwrap();
+        assert_eq!(report.renamed.len(), 1);
+        let (old, new) = &report.renamed[0];
+        assert_eq!(old, &original.meta.id);
+        let moved = Session::load_in(&elsewhere, new).unwrap();
+        assert_eq!(moved.meta.id, *new);
+        assert_eq!(moved.messages.len(), original.messages.len());
+        let forked = Session::load_in(&elsewhere, &fork.meta.id).unwrap();
+        assert_eq!(forked.meta.parent_id.as_ref(), Some(new));
+
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_export_html() {
+        let root = std::env::temp_dir().join(format!("hyle_test_html_{}", std::process::id()));
+        let mut session = Session::new_in(&root, "test/model").unwrap();
+        session
+            .add_user_message("why does <script>alert('x')</script> run?")
+            .unwrap();
+        session
+            .add_assistant_message(
+                "Let me look at `main.rs`.\n```json\n{\"tool\": \"read\", \"args\": {\"path\": \"src/main.rs\"}}\n```",
+                None,
+            )
+            .unwrap();
+        session
+            .add_user_message(
+                "<tool_result tool=\"read\" args=\"src/main.rs\" status=\"ok\" bytes=\"12\">\nfn main() {}\n</tool_result>",
+            )
+            .unwrap();
+        session
+            .add_assistant_message(
+                "Fixed:\n```rust\nfn main() { let s = \"<b>\"; } // done\n```",
+                None,
+            )
+            .unwrap();
+
+        let html = session.export_html();
+        assert!(html.starts_with("<!DOCTYPE html>\n<html"));
+        assert!(html.ends_with("</html>\n"));
+        for tag in [
+            "html", "header", "body", "main", "section", "details", "pre",
+        ] {
+            assert_eq!(
+                html.matches(&format!("<{}", tag)).count(),
+                html.matches(&format!("</{}>", tag)).count(),
+                "unbalanced <{}>",
+                tag
+            );
+        }
+        assert_eq!(html.matches("<section class=\"msg ").count(), 4);
+
+        // Message text can't inject markup
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"));
+
+        assert!(html.contains("<code>main.rs</code>"));
+        assert!(html.contains("<summary>tool call: read</summary>"));
+        assert!(html.contains("<details class=\"tool-result ok\">"));
+        assert!(html.contains("<summary>read \u{b7} src/main.rs \u{b7} ok</summary>"));
+        assert!(html.contains("<span class=\"kw\">fn</span> main"));
+        assert!(html.contains("<span class=\"str\">&quot;&lt;b&gt;&quot;</span>"));
+        assert!(html.contains("<span class=\"com\">// done</span>"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_attach_and_detach() {
+        let root = std::env::temp_dir().join(format!("hyle_test_attach_{}", std::process::id()));
+        let mut session = Session::new_in(&root, "test/model").unwrap();
+        assert!(session.attach("src/main.rs").unwrap());
+        assert!(session.attach("src/lib.rs").unwrap());
+        assert!(!session.attach("src/main.rs").unwrap());
+        assert!(session.attach("Cargo.toml").unwrap());
+
+        // Persisted with the session
+        let reloaded = Session::load_in(&root, &session.meta.id).unwrap();
+        assert_eq!(
+            reloaded.meta.attachments,
+            vec!["src/main.rs", "src/lib.rs", "Cargo.toml"]
+        );
+
+        let removed = session.detach(&["src/lib.rs".to_string()]).unwrap();
+        assert_eq!(removed, vec!["src/lib.rs"]);
+        assert_eq!(session.meta.attachments, vec!["src/main.rs", "Cargo.toml"]);
+        assert_eq!(session.detach(&[]).unwrap().len(), 2);
+        let reloaded = Session::load_in(&root, &session.meta.id).unwrap();
+        assert!(reloaded.meta.attachments.is_empty());
+
+        // The focus set persists the same way
+        session
+            .set_focus(&["src/auth.rs".into(), "tests/".into()])
+            .unwrap();
+        let reloaded = Session::load_in(&root, &session.meta.id).unwrap();
+        assert_eq!(reloaded.meta.focus, vec!["src/auth.rs", "tests/"]);
+        session.set_focus(&[]).unwrap();
+        let reloaded = Session::load_in(&root, &session.meta.id).unwrap();
+        assert!(reloaded.meta.focus.is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_attachments_in_history() {
+        let dir = std::env::temp_dir().join(format!("hyle_test_attached_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let small = dir.join("small.rs");
+        let big = dir.join("big.rs");
+        fs::write(&small, "fn small() {}").unwrap();
+        fs::write(&big, "x".repeat(100)).unwrap();
+        let paths = vec![
+            small.display().to_string(),
+            big.display().to_string(),
+            dir.join("missing.rs").display().to_string(),
+        ];
+        let history = vec![
+            serde_json::json!({"role": "system", "content": "be brief"}),
+            serde_json::json!({"role": "user", "content": "hi"}),
+        ];
+
+        // No attachments: untouched
+        assert_eq!(with_attachments(history.clone(), &[], 100), history);
+
+        // 20 tokens ≈ 80 chars: small fits, big is cut off
+        let assembled = with_attachments(history.clone(), &paths, 20);
+        assert_eq!(assembled.len(), 3);
+        assert_eq!(assembled[0]["content"], "be brief");
+        assert_eq!(assembled[2]["content"], "hi");
+        let attached = assembled[1]["content"].as_str().unwrap();
+        assert_eq!(assembled[1]["role"], "system");
+        assert!(attached.contains("fn small() {}"));
+        assert!(attached.contains("big.rs (truncated)"));
+        assert!(!attached.contains(&"x".repeat(100)));
+        assert!(attached.contains("missing.rs (unreadable"));
+
+        // Out of budget entirely: listed but omitted
+        let assembled = with_attachments(history, &paths[..2], 3);
+        let attached = assembled[1]["content"].as_str().unwrap();
+        assert!(attached.contains("big.rs (omitted: over budget)"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_pins_persist_and_survive_compact() {
+        let root = std::env::temp_dir().join(format!("hyle_test_pins_{}", std::process::id()));
+        let mut session = Session::new_in(&root, "test/model").unwrap();
+        session.add_system_message("be brief").unwrap();
+        for i in 0..4 {
+            session
+                .add_user_message(&format!("question {}", i))
+                .unwrap();
+            session
+                .add_assistant_message(&format!("answer {}", i), None)
+                .unwrap();
+        }
+        assert_eq!(session.pin("The API base is /v2").unwrap(), 1);
+        assert_eq!(session.pin("Don't touch src/legacy").unwrap(), 2);
+
+        let (upto, _) = session.compaction_transcript(2).unwrap();
+        session.compact(upto, "they asked questions").unwrap();
+
+        let reloaded = Session::load_in(&root, &session.meta.id).unwrap();
+        assert_eq!(
+            reloaded.meta.pins,
+            vec!["The API base is /v2", "Don't touch src/legacy"]
+        );
+
+        assert!(session.unpin(Some(3)).is_err());
+        assert_eq!(session.unpin(Some(1)).unwrap(), vec!["The API base is /v2"]);
+        assert_eq!(session.unpin(None).unwrap().len(), 1);
+        let reloaded = Session::load_in(&root, &session.meta.id).unwrap();
+        assert!(reloaded.meta.pins.is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_pins_in_history() {
+        let history = vec![
+            serde_json::json!({"role": "system", "content": "be brief"}),
+            serde_json::json!({"role": "system", "content": "x ".repeat(10_000)}),
+            serde_json::json!({"role": "user", "content": "hi"}),
+        ];
+        assert_eq!(with_pins(history.clone(), &[]), history);
+
+        // Way over any budget: pins still go in, right after the system prompt
+        let pins = vec!["The API base is /v2".to_string(), "Use tabs".to_string()];
+        let assembled = with_pins(history, &pins);
+        assert_eq!(assembled.len(), 4);
+        assert_eq!(assembled[2]["role"], "system");
+        assert_eq!(
+            assembled[2]["content"],
+            "Pinned notes (always apply):\n- The API base is /v2\n- Use tabs\n"
+        );
+        assert_eq!(assembled[3]["content"], "hi");
+    }
+
+    #[test]
+    fn test_clear() {
+        let root = std::env::temp_dir().join(format!("hyle_test_clear_{}", std::process::id()));
+        let mut session = Session::new_in(&root, "test/model").unwrap();
+        session.add_user_message("hello").unwrap();
+        session.add_assistant_message("hi", None).unwrap();
+
+        assert_eq!(session.clear().unwrap(), 2);
+        assert_eq!(session.messages.len(), 1);
+        assert_eq!(session.messages[0].role, "system");
+
+        let reloaded = Session::load_in(&root, &session.meta.id).unwrap();
+        assert_eq!(reloaded.messages.len(), 1);
+        assert_eq!(reloaded.meta.message_count, 1);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_compact() {
+        let root = std::env::temp_dir().join(format!("hyle_test_compact_{}", std::process::id()));
+        let mut session = Session::new_in(&root, "test/model").unwrap();
+        assert!(session.compaction_transcript(2).is_none());
+
+        for i in 0..3 {
+            session
+                .add_user_message(&format!("question {}", i))
+                .unwrap();
+            session
+                .add_assistant_message(&format!("answer {}", i), None)
+                .unwrap();
+        }
+        let before = session.messages.len();
+
+        let (upto, transcript) = session.compaction_transcript(2).unwrap();
+        assert!(transcript.contains("user: question 0"));
+        assert!(!transcript.contains("question 2"));
+
+        session.compact(upto, "asked two questions").unwrap();
+        assert!(session.messages.len() < before);
+        assert_eq!(session.messages.len(), 4); // prompt + summary + last exchange
+        assert!(session.messages[1].content.contains("asked two questions"));
+        assert_eq!(session.messages[3].content, "answer 2");
+
+        let reloaded = Session::load_in(&root, &session.meta.id).unwrap();
+        assert_eq!(reloaded.messages.len(), 4);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_partial_recovered_on_reload() {
+        let root = std::env::temp_dir().join(format!("hyle_test_partial_{}", std::process::id()));
+        let mut session = Session::new_in(&root, "test/model").unwrap();
+        session.add_user_message("write a parser").unwrap();
+        session.save_partial("Here is the first half").unwrap();
+        let id = session.meta.id.clone();
+        // Simulate a crash: the session is dropped without Done
+        drop(session);
+
+        let reloaded = Session::load_in(&root, &id).unwrap();
+        let last = reloaded.messages.last().unwrap();
+        assert_eq!(last.role, "assistant");
+        assert!(last.content.starts_with("Here is the first half"));
+        assert!(last.content.ends_with(INCOMPLETE_MARKER));
+
+        // Recovered once, persisted, and not duplicated on the next load
+        let again = Session::load_in(&root, &id).unwrap();
+        assert_eq!(again.messages.len(), reloaded.messages.len());
+
+        // A completed response clears the partial
+        let mut session = again;
+        session.save_partial("draft").unwrap();
+        session.add_assistant_message("final", None).unwrap();
+        session.clear_partial().unwrap();
+        let done = Session::load_in(&root, &id).unwrap();
+        assert_eq!(done.messages.last().unwrap().content, "final");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_autosave_debounce() {
+        let start = std::time::Instant::now();
+        let mut autosave = Autosave::new(std::time::Duration::from_secs(3), 100);
+        autosave.saved(start);
+
+        // Nothing streamed: never due
+        assert!(!autosave.due(start + std::time::Duration::from_secs(10)));
+
+        autosave.record_tokens(10);
+        assert!(!autosave.due(start + std::time::Duration::from_secs(1)));
+        assert!(autosave.due(start + std::time::Duration::from_secs(3)));
+
+        // Enough tokens flush early
+        autosave.record_tokens(90);
+        assert!(autosave.due(start));
+
+        autosave.saved(start);
+        assert!(!autosave.due(start + std::time::Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_idle_timeout_saves_and_tears_down() {
+        let root = std::env::temp_dir().join(format!("hyle_test_idle_{}", std::process::id()));
+        let mut session = Session::new_in(&root, "test/model").unwrap();
+        let mut model = Some("test/model".to_string());
+
+        let start = std::time::Instant::now();
+        let mut idle = IdleTimeout::new(std::time::Duration::from_secs(300), start);
+        let teardown = |session: &Session, model: &mut Option<String>| -> Result<()> {
+            session.save_meta()?;
+            *model = None;
+            Ok(())
+        };
+
+        // Activity keeps pushing the deadline out
+        let later = start + std::time::Duration::from_secs(200);
+        idle.touch(later);
+        session.add_user_message("still here").unwrap();
+        assert!(!idle
+            .fire_if_idle(start + std::time::Duration::from_secs(400), || {
+                teardown(&session, &mut model)
+            })
+            .unwrap());
+        assert!(model.is_some());
+
+        // Past the limit: saved once, resources dropped
+        let expired = later + std::time::Duration::from_secs(300);
+        assert!(idle.is_idle(expired));
+        assert!(idle
+            .fire_if_idle(expired, || teardown(&session, &mut model))
+            .unwrap());
+        assert!(model.is_none());
+        let saved = list_sessions_in(&root);
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].message_count, session.meta.message_count);
+        assert!(idle
+            .fire_if_idle(expired, || panic!("teardown runs once"))
+            .unwrap());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
     #[test]
     fn test_message_serialize() {
         let msg = Message {
@@ -670,4 +2476,121 @@ mod tests {
             "/home/user/project2/sub"
         ));
     }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("7d").unwrap(), chrono::Duration::days(7));
+        assert_eq!(
+            parse_duration(" 12H ").unwrap(),
+            chrono::Duration::hours(12)
+        );
+        assert_eq!(
+            parse_duration("30m").unwrap(),
+            chrono::Duration::minutes(30)
+        );
+        assert_eq!(parse_duration("2w").unwrap(), chrono::Duration::days(14));
+        assert_eq!(
+            parse_duration("1d12h").unwrap(),
+            chrono::Duration::hours(36)
+        );
+        assert_eq!(parse_duration("3 days").unwrap(), chrono::Duration::days(3));
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("5y").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn test_session_query() {
+        let now = Utc::now();
+        let meta = |id: &str, hours_ago: i64, messages: usize, tokens: u64| SessionMeta {
+            id: id.into(),
+            model: "test/model".into(),
+            created_at: now - chrono::Duration::hours(hours_ago),
+            updated_at: now - chrono::Duration::hours(hours_ago),
+            message_count: messages,
+            total_tokens: tokens,
+            working_dir: "/tmp".into(),
+            description: None,
+            parent_id: None,
+            replaced_model: None,
+            attachments: vec![],
+            pins: vec![],
+            focus: vec![],
+        };
+        let fixtures = vec![
+            meta("old", 24 * 30, 50, 90_000),
+            meta("week", 24 * 5, 4, 2_000),
+            meta("today", 2, 12, 500),
+            meta("now", 0, 2, 40_000),
+        ];
+        let ids = |(page, _): &(Vec<SessionMeta>, usize)| -> Vec<String> {
+            page.iter().map(|s| s.id.clone()).collect()
+        };
+
+        // Default: newest first, first page of 10
+        let all = SessionQuery::default().apply(fixtures.clone(), now);
+        assert_eq!(ids(&all), vec!["now", "today", "week", "old"]);
+        assert_eq!(all.1, 4);
+
+        let recent = SessionQuery {
+            since: Some(parse_duration("7d").unwrap()),
+            ..Default::default()
+        }
+        .apply(fixtures.clone(), now);
+        assert_eq!(ids(&recent), vec!["now", "today", "week"]);
+        assert_eq!(recent.1, 3);
+
+        let by_tokens = SessionQuery {
+            sort: SessionSort::Tokens,
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&by_tokens.apply(fixtures.clone(), now)),
+            vec!["old", "now", "week", "today"]
+        );
+        let by_messages = SessionQuery {
+            sort: SessionSort::parse("messages").unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&by_messages.apply(fixtures.clone(), now)),
+            vec!["old", "today", "week", "now"]
+        );
+
+        // Pages: total counts every match, not just the page
+        let page = SessionQuery {
+            limit: 2,
+            offset: 1,
+            ..Default::default()
+        }
+        .apply(fixtures.clone(), now);
+        assert_eq!(ids(&page), vec!["today", "week"]);
+        assert_eq!(page.1, 4);
+        let past_end = SessionQuery {
+            offset: 10,
+            ..Default::default()
+        }
+        .apply(fixtures.clone(), now);
+        assert!(past_end.0.is_empty());
+        let unlimited = SessionQuery {
+            limit: 0,
+            ..Default::default()
+        }
+        .apply(fixtures, now);
+        assert_eq!(unlimited.0.len(), 4);
+
+        assert!(SessionSort::parse("size").is_err());
+
+        let flags = SessionQuery::from_flags(Some("2w"), Some("tokens"), Some("5"), None).unwrap();
+        assert_eq!(flags.since, Some(chrono::Duration::days(14)));
+        assert_eq!(flags.sort, SessionSort::Tokens);
+        assert_eq!((flags.limit, flags.offset), (5, 0));
+        assert_eq!(
+            SessionQuery::from_flags(None, None, None, None).unwrap(),
+            SessionQuery::default()
+        );
+        assert!(SessionQuery::from_flags(None, None, Some("all"), None).is_err());
+        assert!(SessionQuery::from_flags(Some("soon"), None, None, None).is_err());
+    }
 }
@@ -8,11 +8,16 @@
 #![allow(dead_code)] // Forward-looking module
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::backburner::parse_test_output;
-use crate::bootstrap::SelfAnalyzer;
+use crate::bootstrap::{
+    apply_and_verify, apply_diff, parse_clippy_json, restore, ImproveOutcome, LintFinding,
+    LintSeverity, ModuleGraph, SelfAnalyzer,
+};
+use crate::prompt::{CustomInstructions, PROJECT_INSTRUCTION_FILES};
 use crate::prompts::{PromptLibrary, Toolbelt};
+use crate::tools::{read_file_range, truncate_head_tail, FileRange};
 
 // ═══════════════════════════════════════════════════════════════
 // TOOL DEFINITIONS
@@ -124,36 +129,132 @@ pub fn tool_glob(pattern: &str) -> ToolResult {
 
 /// Run a shell command
 pub fn tool_shell(command: &str, cwd: Option<&str>) -> ToolResult {
-    use std::process::Command;
+    tool_shell_with_code(command, cwd).0
+}
 
-    let mut cmd = Command::new("sh");
+/// `tool_shell`, plus the exit code (None when killed by a signal or the
+/// shell couldn't start)
+pub fn tool_shell_with_code(command: &str, cwd: Option<&str>) -> (ToolResult, Option<i32>) {
+    let mut cmd = std::process::Command::new("sh");
     cmd.arg("-c").arg(command);
+    run_process_with_code(cmd, cwd)
+}
+
+/// Run a program with an argument vector, bypassing the shell entirely
+pub fn tool_exec<S: AsRef<std::ffi::OsStr>>(
+    program: &str,
+    args: &[S],
+    cwd: Option<&str>,
+) -> ToolResult {
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(args);
+    run_process(cmd, cwd)
+}
 
+fn run_process(cmd: std::process::Command, cwd: Option<&str>) -> ToolResult {
+    run_process_with_code(cmd, cwd).0
+}
+
+fn run_process_with_code(
+    mut cmd: std::process::Command,
+    cwd: Option<&str>,
+) -> (ToolResult, Option<i32>) {
     if let Some(dir) = cwd {
         cmd.current_dir(dir);
     }
 
     match cmd.output() {
         Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            let stdout = crate::decode::decode_output(&output.stdout);
+            let stderr = crate::decode::decode_output(&output.stderr);
             let combined = if stderr.is_empty() {
                 stdout
             } else {
                 format!("{}\n--- stderr ---\n{}", stdout, stderr)
             };
 
-            ToolResult {
+            let result = ToolResult {
                 success: output.status.success(),
                 output: combined,
                 artifacts: vec![],
-            }
+            };
+            (result, output.status.code())
+        }
+        Err(e) => {
+            let result = ToolResult {
+                success: false,
+                output: format!("Command error: {}", e),
+                artifacts: vec![],
+            };
+            (result, None)
         }
-        Err(e) => ToolResult {
-            success: false,
-            output: format!("Command error: {}", e),
-            artifacts: vec![],
-        },
+    }
+}
+
+/// Most command output `/run` keeps as context; the middle is cut
+pub const RUN_CONTEXT_MAX_BYTES: usize = 8_000;
+
+/// A command run with `/run`, captured for the conversation
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandCapture {
+    pub command: String,
+    pub exit_code: Option<i32>,
+    /// stdout then stderr, truncated to RUN_CONTEXT_MAX_BYTES
+    pub output: String,
+    /// Size of the output before truncation
+    pub total_bytes: usize,
+}
+
+impl CommandCapture {
+    /// Run `command` through the shell, like `tool_shell`
+    pub fn run(command: &str, cwd: Option<&str>) -> Self {
+        let (result, exit_code) = tool_shell_with_code(command, cwd);
+        Self::from_output(command, exit_code, &result.output)
+    }
+
+    pub fn from_output(command: &str, exit_code: Option<i32>, output: &str) -> Self {
+        let output = output.trim_end();
+        Self {
+            command: command.to_string(),
+            exit_code,
+            output: truncate_head_tail(output, RUN_CONTEXT_MAX_BYTES),
+            total_bytes: output.len(),
+        }
+    }
+
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    pub fn truncated(&self) -> bool {
+        self.output.len() != self.total_bytes
+    }
+
+    /// "exit 0", or "no exit code" for a signal or a shell that never ran
+    pub fn status(&self) -> String {
+        match self.exit_code {
+            Some(code) => format!("exit {}", code),
+            None => "no exit code".into(),
+        }
+    }
+
+    /// Context message for the session: command, status and output
+    pub fn to_context(&self) -> String {
+        format!(
+            "[Ran `{}` - {}{}]\n```\n{}\n```",
+            self.command,
+            self.status(),
+            if self.truncated() {
+                format!(", output truncated from {} bytes", self.total_bytes)
+            } else {
+                String::new()
+            },
+            if self.output.is_empty() {
+                "(no output)"
+            } else {
+                &self.output
+            }
+        )
     }
 }
 
@@ -161,10 +262,67 @@ pub fn tool_shell(command: &str, cwd: Option<&str>) -> ToolResult {
 // GIT OPERATIONS
 // ═══════════════════════════════════════════════════════════════
 
+/// Split a command line into words, honoring quotes but never expanding
+/// anything (no globs, variables or command separators)
+pub fn split_args(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => match chars.next() {
+                Some(n) if n == '"' || n == '\\' => current.push(n),
+                Some(n) => {
+                    current.push('\\');
+                    current.push(n);
+                }
+                None => current.push('\\'),
+            },
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                if let Some(n) = chars.next() {
+                    current.push(n);
+                }
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
 /// Git repository operations
+///
+/// Everything here runs `git` with an argument vector, so user-supplied
+/// messages and paths are passed literally and never seen by a shell.
 pub mod git {
     use super::*;
 
+    /// Run git with the given arguments
+    pub fn run<S: AsRef<std::ffi::OsStr>>(args: &[S], cwd: Option<&str>) -> ToolResult {
+        tool_exec("git", args, cwd)
+    }
+
     /// Check if current directory is a git repo
     pub fn is_repo() -> bool {
         std::path::Path::new(".git").exists()
@@ -172,7 +330,7 @@ pub mod git {
 
     /// Get current branch
     pub fn current_branch() -> Option<String> {
-        let result = tool_shell("git branch --show-current", None);
+        let result = run(&["branch", "--show-current"], None);
         if result.success {
             Some(result.output.trim().to_string())
         } else {
@@ -182,41 +340,44 @@ pub mod git {
 
     /// Get git status
     pub fn status() -> ToolResult {
-        tool_shell("git status --short", None)
+        run(&["status", "--short"], None)
     }
 
     /// Get git diff
     pub fn diff(staged: bool) -> ToolResult {
-        let cmd = if staged {
-            "git diff --cached"
+        if staged {
+            run(&["diff", "--cached"], None)
         } else {
-            "git diff"
-        };
-        tool_shell(cmd, None)
+            run(&["diff"], None)
+        }
     }
 
     /// Stage files
     pub fn add(paths: &[&str]) -> ToolResult {
-        let files = paths.join(" ");
-        tool_shell(&format!("git add {}", files), None)
+        let mut args = vec!["add", "--"];
+        args.extend_from_slice(paths);
+        run(&args, None)
     }
 
     /// Commit with message
     pub fn commit(message: &str) -> ToolResult {
-        tool_shell(
-            &format!("git commit -m '{}'", message.replace('\'', "\\'")),
-            None,
-        )
+        commit_in(message, None)
+    }
+
+    /// Commit with message in a specific working directory
+    pub fn commit_in(message: &str, cwd: Option<&str>) -> ToolResult {
+        run(&["commit", "-m", message], cwd)
     }
 
     /// Get recent commits
     pub fn log(count: usize) -> ToolResult {
-        tool_shell(&format!("git log --oneline -n {}", count), None)
+        let count = count.to_string();
+        run(&["log", "--oneline", "-n", count.as_str()], None)
     }
 
     /// Get changed files
     pub fn changed_files() -> Vec<String> {
-        let result = tool_shell("git diff --name-only HEAD", None);
+        let result = run(&["diff", "--name-only", "HEAD"], None);
         if result.success {
             result.output.lines().map(|s| s.to_string()).collect()
         } else {
@@ -387,6 +548,50 @@ impl ToolRegistry {
             ],
         });
 
+        registry.register(ToolDef {
+            name: "move".into(),
+            description: "Rename or move a file or directory; git mv when tracked".into(),
+            parameters: vec![
+                ToolParam {
+                    name: "from".into(),
+                    param_type: "string".into(),
+                    description: "Current path".into(),
+                    required: true,
+                },
+                ToolParam {
+                    name: "to".into(),
+                    param_type: "string".into(),
+                    description: "New path; parent directories are created".into(),
+                    required: true,
+                },
+                ToolParam {
+                    name: "force".into(),
+                    param_type: "boolean".into(),
+                    description: "Overwrite an existing file at `to`".into(),
+                    required: false,
+                },
+            ],
+        });
+
+        registry.register(ToolDef {
+            name: "delete".into(),
+            description: "Move a file or directory into .hyle/trash; /undo restores it".into(),
+            parameters: vec![
+                ToolParam {
+                    name: "path".into(),
+                    param_type: "string".into(),
+                    description: "Path to delete".into(),
+                    required: true,
+                },
+                ToolParam {
+                    name: "recursive".into(),
+                    param_type: "boolean".into(),
+                    description: "Required to delete a directory".into(),
+                    required: false,
+                },
+            ],
+        });
+
         registry.register(ToolDef {
             name: "glob".into(),
             description: "List files matching a pattern".into(),
@@ -398,6 +603,69 @@ impl ToolRegistry {
             }],
         });
 
+        registry.register(ToolDef {
+            name: "tree".into(),
+            description: "Show a depth-limited directory tree with file counts".into(),
+            parameters: vec![
+                ToolParam {
+                    name: "path".into(),
+                    param_type: "string".into(),
+                    description: "Root directory (default: .)".into(),
+                    required: false,
+                },
+                ToolParam {
+                    name: "max_depth".into(),
+                    param_type: "integer".into(),
+                    description: "Levels to descend (default: 3)".into(),
+                    required: false,
+                },
+                ToolParam {
+                    name: "show_hidden".into(),
+                    param_type: "boolean".into(),
+                    description: "Include dotfiles".into(),
+                    required: false,
+                },
+            ],
+        });
+
+        registry.register(ToolDef {
+            name: "stats".into(),
+            description:
+                "Repo metrics as JSON: lines by language, largest files, TODO/FIXME counts".into(),
+            parameters: vec![ToolParam {
+                name: "path".into(),
+                param_type: "string".into(),
+                description: "Subpath to scope the stats to".into(),
+                required: false,
+            }],
+        });
+
+        registry.register(ToolDef {
+            name: "docs".into(),
+            description: "Search README, docs/ and doc comments; results cite file:line".into(),
+            parameters: vec![
+                ToolParam {
+                    name: "query".into(),
+                    param_type: "string".into(),
+                    description: "What to look for".into(),
+                    required: true,
+                },
+                ToolParam {
+                    name: "limit".into(),
+                    param_type: "integer".into(),
+                    description: "Maximum passages to return".into(),
+                    required: false,
+                },
+            ],
+        });
+
+        registry.register(ToolDef {
+            name: "environ".into(),
+            description: "OS, shell, installed tools (rg, fd, cargo, ...) and git version as JSON"
+                .into(),
+            parameters: vec![],
+        });
+
         registry.register(ToolDef {
             name: "shell".into(),
             description: "Run a shell command".into(),
@@ -417,6 +685,17 @@ impl ToolRegistry {
             ],
         });
 
+        registry.register(ToolDef {
+            name: "ask_user".into(),
+            description: "Ask the user a clarifying question and wait for the answer".into(),
+            parameters: vec![ToolParam {
+                name: "question".into(),
+                param_type: "string".into(),
+                description: "The question to ask".into(),
+                required: true,
+            }],
+        });
+
         registry.register(ToolDef {
             name: "git_status".into(),
             description: "Get git repository status".into(),
@@ -518,6 +797,89 @@ impl Default for ToolRegistry {
 pub struct SlashResult {
     pub output: String,
     pub success: bool,
+    /// Session-level effect for the UI to perform, if any
+    pub action: Option<SlashAction>,
+}
+
+/// Effects that need UI/session state, so skills.rs can't perform them itself
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlashAction {
+    ClearConversation,
+    CompactConversation,
+    /// Offer to compact above this percent of the context window; 0 turns
+    /// it off, None shows the setting
+    AutoCompact(Option<u32>),
+    ShowModelPicker,
+    SwitchModel(String),
+    ToggleAgent,
+    /// Re-run the last prompt, optionally on another model
+    RetryLast(Option<String>),
+    /// Replace the last reply with a fresh one, optionally at another temperature
+    Regenerate(Option<f32>),
+    /// Set a sampling parameter; empty value means show current
+    SetSampling {
+        key: String,
+        value: String,
+    },
+    /// Add a URL or file to the conversation as context
+    Ingest(String),
+    /// Switch the TUI to another saved session (id or unique prefix)
+    ResumeSession(String),
+    /// Copy the current session and continue in the copy
+    ForkSession,
+    /// Choose how history is assembled into requests; empty means show current
+    SetContextStrategy(String),
+    /// Ask the model to fix the top analyzer finding, then apply and verify it
+    SelfImprove,
+    /// Ask the model for a repo overview, cached in .hyle/overview.md
+    Summarize,
+    /// Send a prompt built by the command to the model as the next turn
+    SendPrompt(String),
+    /// Track files whose current contents go with every request
+    Attach(Vec<String>),
+    /// Stop tracking files; empty means all
+    Detach(Vec<String>),
+    /// Set the working set edits stay within; empty clears it, None shows it
+    Focus(Option<Vec<String>>),
+    /// Keep a note in every request, even after /compact
+    Pin(String),
+    /// Remove a pin by number; None means all
+    Unpin(Option<usize>),
+    /// List pinned notes
+    ListPins,
+    /// Send one prompt to several models side by side; no models reopens
+    /// the last comparison
+    Compare {
+        models: Vec<String>,
+        prompt: String,
+    },
+    /// Require an approved plan before mutating tools; None toggles
+    PlanFirst(Option<bool>),
+    /// Run a shell command and add its output to the conversation
+    RunCommand(String),
+    /// Reload the model list; `force` skips a fresh cache
+    RefreshModels {
+        force: bool,
+    },
+    /// Restore the last trashed path under the tools' working directory
+    Undo,
+}
+
+impl SlashResult {
+    pub fn action(action: SlashAction) -> Self {
+        SlashResult {
+            output: String::new(),
+            success: true,
+            action: Some(action),
+        }
+    }
+}
+
+fn set_sampling(key: &str, value: &str) -> SlashResult {
+    SlashResult::action(SlashAction::SetSampling {
+        key: key.to_string(),
+        value: value.to_string(),
+    })
 }
 
 impl From<ToolResult> for SlashResult {
@@ -525,6 +887,7 @@ impl From<ToolResult> for SlashResult {
         SlashResult {
             output: r.output,
             success: r.success,
+            action: None,
         }
     }
 }
@@ -560,16 +923,25 @@ pub fn execute_slash_command_with_context(
         "update" => Some(run_update(project_type)),
         "clean" => Some(run_clean(project_type)),
         "check" | "lint" => Some(run_check(project_type)),
+        "fixerror" | "fixerrors" => Some(run_fixerror(project_type)),
 
         // === Session Commands ===
-        "clear" => Some(SlashResult {
-            output: "CLEAR_CONVERSATION".into(),
-            success: true,
-        }),
-        "compact" => Some(SlashResult {
-            output: "COMPACT_CONVERSATION".into(),
-            success: true,
+        "clear" => Some(SlashResult::action(SlashAction::ClearConversation)),
+        "compact" => Some(run_compact(args)),
+        "summarize" => Some(SlashResult::action(SlashAction::Summarize)),
+        "resume" if args.is_empty() => Some(SlashResult {
+            output: "Usage: /resume <session-id> (see the Sessions view for ids)".into(),
+            success: false,
+            action: None,
         }),
+        "resume" => Some(SlashResult::action(SlashAction::ResumeSession(
+            args.to_string(),
+        ))),
+        "fork" => Some(SlashResult::action(SlashAction::ForkSession)),
+        // ui.rs parses and applies the strategy; empty shows the current one
+        "context" => Some(SlashResult::action(SlashAction::SetContextStrategy(
+            args.trim().to_string(),
+        ))),
         "cost" | "tokens" | "usage" => Some(run_cost(ctx)),
         "status" => {
             // /status git → git status, otherwise project status
@@ -602,34 +974,81 @@ pub fn execute_slash_command_with_context(
         "version" => Some(SlashResult {
             output: format!("hyle v{}", env!("CARGO_PKG_VERSION")),
             success: true,
+            action: None,
         }),
+        "model" | "models" if args.starts_with("refresh") => {
+            let force = args.split_whitespace().nth(1) == Some("force");
+            Some(SlashResult::action(SlashAction::RefreshModels { force }))
+        }
         "model" | "models" => Some(SlashResult {
             output: ctx
                 .map(|c| format!("Current model: {}", c.model))
                 .unwrap_or_else(|| "unknown".into()),
             success: true,
+            action: None,
         }),
-        "switch" => Some(SlashResult {
-            // Return the switch target - ui.rs will handle actual switching
-            output: if args.is_empty() {
-                "SWITCH_MODEL_PICKER".into() // Signal to show picker
-            } else {
-                format!("SWITCH_MODEL:{}", args) // Signal to switch to specific model
-            },
-            success: true,
+        // ui.rs performs the switch, validating the target
+        "switch" if args.is_empty() => Some(SlashResult::action(SlashAction::ShowModelPicker)),
+        "switch" => Some(SlashResult::action(SlashAction::SwitchModel(
+            args.to_string(),
+        ))),
+        // Re-send the last prompt - ui.rs validates the model and retries
+        "retry" => Some(SlashResult::action(SlashAction::RetryLast(
+            Some(args.to_string()).filter(|a| !a.is_empty()),
+        ))),
+        "regenerate" | "regen" => Some(run_regenerate(args)),
+        // Adjust sampling - ui.rs validates and applies; empty value shows current
+        "temp" | "temperature" => Some(set_sampling("temperature", args)),
+        "top_p" | "max_tokens" | "stop" => Some(set_sampling(command, args)),
+        "system" => Some(run_system(args)),
+        // Fetching is async - ui.rs ingests and adds the context message
+        "ingest" if args.is_empty() => Some(SlashResult {
+            output: "Usage: /ingest <url|path>".into(),
+            success: false,
+            action: None,
         }),
-        "agent" => Some(SlashResult {
-            // Toggle agent mode (autonomous tool chaining)
-            output: "TOGGLE_AGENT_MODE".into(),
-            success: true,
+        "ingest" => Some(SlashResult::action(SlashAction::Ingest(args.to_string()))),
+        // The session owns the attachment list - ui.rs applies these
+        "attach" => Some(run_attach(args)),
+        "detach" => Some(SlashResult::action(SlashAction::Detach(
+            args.split_whitespace().map(String::from).collect(),
+        ))),
+        "focus" => Some(run_focus(args)),
+        // Pins live in the session too
+        "pin" if args.is_empty() => Some(SlashResult {
+            output: "Usage: /pin <note>  (see /pins)".into(),
+            success: false,
+            action: None,
         }),
+        "pin" => Some(SlashResult::action(SlashAction::Pin(args.to_string()))),
+        "unpin" => Some(run_unpin(args)),
+        "pins" => Some(SlashResult::action(SlashAction::ListPins)),
+        // Streams every model at once - ui.rs runs it in the Compare view
+        "compare" => Some(run_compare(args)),
+        "docs" => Some(run_docs(args)),
+        // Toggle agent mode (autonomous tool chaining)
+        "agent" => Some(SlashResult::action(SlashAction::ToggleAgent)),
+        "planfirst" => Some(run_planfirst(args)),
+        // ui.rs runs it off the UI thread and adds the output as context
+        "run" if args.trim().is_empty() => Some(SlashResult {
+            output: "Usage: /run <command>  (e.g. /run cargo test 2>&1)".into(),
+            success: false,
+            action: None,
+        }),
+        "run" => Some(SlashResult::action(SlashAction::RunCommand(
+            args.trim().to_string(),
+        ))),
 
         // === Editor Integration ===
         "edit" | "open" => Some(run_edit(args)),
         "view" | "cat" | "read" => Some(tool_read_file(args).into()),
+        "explain" => Some(run_explain(args)),
 
         // === Self-Analysis ===
         "analyze" | "health" => Some(run_analyze()),
+        "improve" if matches!(args.trim(), "apply" | "fix") => {
+            Some(SlashResult::action(SlashAction::SelfImprove))
+        }
         "improve" => Some(run_improve()),
         "deps" | "graph" => Some(run_deps()),
         "selftest" => Some(run_selftest()),
@@ -640,6 +1059,8 @@ pub fn execute_slash_command_with_context(
         // === Patch Operations ===
         "apply" => Some(run_apply(args)),
         "revert" => Some(run_revert(args)),
+        // ui.rs restores into the tool executor's directory
+        "undo" => Some(SlashResult::action(SlashAction::Undo)),
 
         // === Prompt Library ===
         "toolbelt" => Some(run_toolbelt(args)),
@@ -655,6 +1076,7 @@ pub fn execute_slash_command_with_context(
                         command
                     ),
                     success: false,
+                    action: None,
                 })
             } else {
                 Some(SlashResult {
@@ -664,6 +1086,7 @@ pub fn execute_slash_command_with_context(
                         suggestions.join(", ")
                     ),
                     success: false,
+                    action: None,
                 })
             }
         }
@@ -673,12 +1096,85 @@ pub fn execute_slash_command_with_context(
 /// Suggest similar slash commands for typos
 fn suggest_slash_command(input: &str) -> Vec<String> {
     const COMMANDS: &[&str] = &[
-        "build", "test", "update", "clean", "check", "lint", "clear", "compact", "cost", "tokens",
-        "usage", "status", "git", "diff", "commit", "pr", "prs", "issue", "issues", "runs",
-        "actions", "cd", "ls", "files", "find", "glob", "grep", "search", "help", "doctor",
-        "version", "model", "models", "switch", "agent", "edit", "open", "view", "cat", "read",
-        "analyze", "health", "improve", "deps", "graph", "selftest", "map", "env", "apply",
-        "revert", "toolbelt", "prompts",
+        "build",
+        "test",
+        "update",
+        "clean",
+        "check",
+        "lint",
+        "fixerror",
+        "clear",
+        "compact",
+        "summarize",
+        "resume",
+        "fork",
+        "context",
+        "cost",
+        "tokens",
+        "usage",
+        "status",
+        "git",
+        "diff",
+        "commit",
+        "pr",
+        "prs",
+        "issue",
+        "issues",
+        "runs",
+        "actions",
+        "cd",
+        "ls",
+        "files",
+        "find",
+        "glob",
+        "grep",
+        "search",
+        "help",
+        "doctor",
+        "version",
+        "model",
+        "models",
+        "switch",
+        "retry",
+        "regenerate",
+        "regen",
+        "temp",
+        "temperature",
+        "top_p",
+        "max_tokens",
+        "stop",
+        "system",
+        "agent",
+        "planfirst",
+        "run",
+        "edit",
+        "open",
+        "view",
+        "explain",
+        "cat",
+        "read",
+        "analyze",
+        "health",
+        "improve",
+        "deps",
+        "graph",
+        "selftest",
+        "map",
+        "env",
+        "apply",
+        "revert",
+        "undo",
+        "toolbelt",
+        "prompts",
+        "ingest",
+        "attach",
+        "detach",
+        "focus",
+        "pin",
+        "unpin",
+        "compare",
+        "pins",
+        "docs",
     ];
 
     let mut matches: Vec<(&str, usize)> = COMMANDS
@@ -745,11 +1241,7 @@ fn run_build(project_type: Option<&str>) -> SlashResult {
         Some("Go") => "go build ./...",
         _ => "make build 2>/dev/null || cargo build 2>/dev/null || npm run build 2>/dev/null",
     };
-    let result = tool_shell(cmd, None);
-    SlashResult {
-        output: result.output,
-        success: result.success,
-    }
+    run_project_command("build", cmd)
 }
 
 fn run_test(project_type: Option<&str>) -> SlashResult {
@@ -760,13 +1252,35 @@ fn run_test(project_type: Option<&str>) -> SlashResult {
         Some("Go") => "go test ./...",
         _ => "make test 2>/dev/null || cargo test 2>/dev/null || npm test 2>/dev/null || pytest 2>/dev/null",
     };
-    let result = tool_shell(cmd, None);
+    run_project_command("test", cmd)
+}
+
+/// Run `commands.<name>` from `.hyle/config.json`, else `default`
+fn run_project_command(name: &str, default: &str) -> SlashResult {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let cmd = match project_command(&root, name, default) {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            return SlashResult {
+                output: format!("{:#}", e),
+                success: false,
+                action: None,
+            }
+        }
+    };
+    let result = tool_shell(&cmd, None);
     SlashResult {
         output: result.output,
         success: result.success,
+        action: None,
     }
 }
 
+/// The command /build, /test or /check runs in `root`
+pub fn project_command(root: &Path, name: &str, default: &str) -> anyhow::Result<String> {
+    Ok(crate::config::ProjectConfig::load(root)?.command(name, default))
+}
+
 fn run_update(project_type: Option<&str>) -> SlashResult {
     let cmd = match project_type {
         Some("Rust") => "cargo update",
@@ -779,6 +1293,7 @@ fn run_update(project_type: Option<&str>) -> SlashResult {
     SlashResult {
         output: result.output,
         success: result.success,
+        action: None,
     }
 }
 
@@ -794,6 +1309,7 @@ fn run_clean(project_type: Option<&str>) -> SlashResult {
     SlashResult {
         output: result.output,
         success: result.success,
+        action: None,
     }
 }
 
@@ -805,11 +1321,170 @@ fn run_check(project_type: Option<&str>) -> SlashResult {
         Some("Go") => "go vet ./...",
         _ => "cargo check 2>/dev/null || npm run lint 2>/dev/null",
     };
+    run_project_command("check", cmd)
+}
+
+/// Errors /fixerror puts in one prompt; the rest are only counted
+const FIX_MAX_ERRORS: usize = 10;
+
+/// Source lines shown either side of each error
+const FIX_CONTEXT_LINES: usize = 5;
+
+/// Most of a failed build's output shown when no errors could be parsed
+const FIX_RAW_OUTPUT_BYTES: usize = 4_000;
+
+/// Build, and if it fails send the model the errors with the offending
+/// source already read
+fn run_fixerror(project_type: Option<&str>) -> SlashResult {
+    let cmd = match project_type {
+        Some("Rust") => "cargo check --all-targets --message-format=json",
+        Some("Node.js") => "npm run build",
+        Some("Python") => "ruff check . || flake8",
+        Some("Go") => "go vet ./...",
+        _ => "make build 2>/dev/null || cargo check --message-format=json 2>/dev/null || npm run build 2>/dev/null",
+    };
     let result = tool_shell(cmd, None);
+    if result.success {
+        return SlashResult {
+            output: format!("`{}` passed; nothing to fix", cmd),
+            success: true,
+            action: None,
+        };
+    }
+
+    let errors = parse_build_errors(&result.output);
+    if errors.is_empty() {
+        return SlashResult {
+            output: format!(
+                "`{}` failed, but no file:line errors were found:\n{}",
+                cmd,
+                truncate_head_tail(&result.output, FIX_RAW_OUTPUT_BYTES)
+            ),
+            success: false,
+            action: None,
+        };
+    }
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
     SlashResult {
-        output: result.output,
-        success: result.success,
+        output: format!(
+            "{} error{} from `{}`; asking the model to fix {}",
+            errors.len(),
+            if errors.len() == 1 { "" } else { "s" },
+            cmd,
+            if errors.len() == 1 { "it" } else { "them" }
+        ),
+        success: true,
+        action: Some(SlashAction::SendPrompt(fix_errors_prompt(
+            &root, cmd, &errors,
+        ))),
+    }
+}
+
+/// Errors from build output: cargo's JSON diagnostics when present,
+/// otherwise `file:line[:col]: message` lines (go, gcc, ruff, mypy)
+pub fn parse_build_errors(output: &str) -> Vec<LintFinding> {
+    if output.lines().any(|l| l.starts_with("{\"reason\"")) {
+        return parse_clippy_json(output)
+            .into_iter()
+            .filter(|f| f.severity == LintSeverity::Error)
+            .collect();
+    }
+
+    let located =
+        regex::Regex::new(r"^\s*([^\s:][^:]*\.[A-Za-z0-9]+):(\d+)(?::\d+)?:\s*(.+)$").unwrap();
+    let mut errors: Vec<LintFinding> = Vec::new();
+    for line in output.lines() {
+        let Some(caps) = located.captures(line) else {
+            continue;
+        };
+        let message = caps[3].trim();
+        if message.starts_with("warning") || message.starts_with("note") {
+            continue;
+        }
+        let error = LintFinding {
+            file: caps[1].trim_start_matches("./").to_string(),
+            line: caps[2].parse().unwrap_or(0),
+            lint: "build".into(),
+            severity: LintSeverity::Error,
+            message: message.trim_start_matches("error:").trim().to_string(),
+        };
+        if !errors.contains(&error) {
+            errors.push(error);
+        }
+    }
+    errors
+}
+
+/// "Fix these errors" prompt, with the source around each error read from
+/// under `root`
+pub fn fix_errors_prompt(root: &Path, command: &str, errors: &[LintFinding]) -> String {
+    let shown = &errors[..errors.len().min(FIX_MAX_ERRORS)];
+    let mut prompt = format!(
+        "The build (`{}`) fails with {} error{}. Fix {} with minimal changes, \
+         then run the build again to confirm.\n\n## Errors\n",
+        command,
+        errors.len(),
+        if errors.len() == 1 { "" } else { "s" },
+        if errors.len() == 1 { "it" } else { "them" }
+    );
+    for (i, e) in shown.iter().enumerate() {
+        let code = if e.lint == "build" || e.lint == "rustc" {
+            String::new()
+        } else {
+            format!(" [{}]", e.lint)
+        };
+        prompt.push_str(&format!(
+            "{}. {}:{}{} {}\n",
+            i + 1,
+            e.file,
+            e.line,
+            code,
+            e.message
+        ));
+    }
+    if errors.len() > shown.len() {
+        prompt.push_str(&format!(
+            "(and {} more; fix these first)\n",
+            errors.len() - shown.len()
+        ));
+    }
+
+    // One merged window per file, so nearby errors share a snippet
+    let mut files: Vec<(&str, Vec<(usize, usize)>)> = Vec::new();
+    for e in shown.iter().filter(|e| e.line > 0) {
+        let window = (
+            e.line.saturating_sub(FIX_CONTEXT_LINES).max(1),
+            e.line + FIX_CONTEXT_LINES,
+        );
+        match files.iter_mut().find(|(f, _)| *f == e.file) {
+            Some((_, windows)) => windows.push(window),
+            None => files.push((&e.file, vec![window])),
+        }
+    }
+    let mut source = String::new();
+    for (file, mut windows) in files {
+        windows.sort();
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in windows {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        for (start, end) in merged {
+            if let Ok(range) = read_file_range(&root.join(file), start, end) {
+                source.push_str(&format!(
+                    "{} (lines {}-{} of {}):\n```\n{}```\n",
+                    file, range.start, range.end, range.total, range.text
+                ));
+            }
+        }
+    }
+    if !source.is_empty() {
+        prompt.push_str("\n## Source\n");
+        prompt.push_str(&source);
     }
+    prompt
 }
 
 fn slash_help_full() -> SlashResult {
@@ -818,17 +1493,55 @@ fn slash_help_full() -> SlashResult {
   /build          Build the project
   /test           Run tests
   /check, /lint   Run lints and checks
+  /fixerror       Build, then send the errors and their source to the model
   /update         Update dependencies
   /clean          Clean build artifacts
 
 ═══ Session ═══
   /clear          Clear conversation history
   /compact        Summarize and compact history
+  /compact auto [N|off]
+                  Offer to compact once a request would fill N% of the
+                  context window (auto_compact_percent in config)
+  /summarize      Write a repo overview to .hyle/overview.md; later
+                  sessions include it as context
+  /resume <id>    Switch to another saved session
+  /fork           Branch the session; continue in the copy
+  /context [s]    Show or set history strategy: full, window [n],
+                  salience [tokens], summarized
   /cost, /tokens  Show token usage
   /status         Show session status
   /model          Show current model
+  /models refresh Reload the model list (cached 24h; add force to re-fetch)
   /switch [name]  Switch to different model
+  /retry [model]  Re-run last prompt, optionally on another model
+  /regenerate [t] Replace the last reply, optionally at temperature t (Ctrl-R)
+  /temp [t|reset] Show or set sampling temperature (0-2)
+  /top_p [p]      Show or set nucleus sampling (0-1]
+  /max_tokens [n] Show or set response token limit
+  /stop [s|clear] Add a stop sequence (max 4)
+  /system         Show custom instructions (AGENTS.md, .hyle/system.md)
+  /system edit    Open project instructions in $EDITOR
+  /system add <t> Append a line to project instructions
+  /ingest <src>   Add a URL or file to the conversation as context
+  /attach <files> Send these files' current contents with every request
+  /detach [files] Stop attaching files (all when none given)
+  /focus [files]  Keep edits within these files or directories; edits
+                  elsewhere ask first (no args: show, clear: remove)
+  /pin <note>     Keep a note in every request, even after /compact
+  /unpin [n]      Remove pin n (all when none given)
+  /pins           List pinned notes
+  /compare <m1,m2[,m3]> <prompt>
+                  Stream a prompt to 2-3 models side by side, then
+                  press 1-3 to continue with that response (no args
+                  reopens the last comparison)
+  /docs <query>   Search README, docs/ and doc comments
+  /run <cmd>      Run a shell command and add its output to the
+                  conversation (exit code included)
   /agent          Toggle autonomous agent mode
+  /planfirst [on|off]
+                  Approve the model's plan before it writes or runs
+                  anything (plan_first in config; trust mode approves)
 
 ═══ Git ═══
   /git <cmd>      Run git command
@@ -851,6 +1564,7 @@ fn slash_help_full() -> SlashResult {
   /find <pattern> Find files (glob)
   /grep <pattern> Search in files
   /view <file>    View file contents
+  /explain <file>:<a>-<b>  Explain a line range
   /edit <file>    Open in $EDITOR
 
 ═══ Utility ═══
@@ -862,7 +1576,8 @@ fn slash_help_full() -> SlashResult {
 ═══ Self-Analysis ═══
   /analyze        Codebase health analysis
   /improve        Generate improvement suggestions
-  /deps           Show module dependency graph
+  /improve apply  Fix the top finding, test, revert on failure
+  /deps           Module dependency graph, cycles, coupling
   /selftest       Run cargo test and parse results
 
 ═══ Environment ═══
@@ -875,9 +1590,11 @@ fn slash_help_full() -> SlashResult {
 
 ═══ Patch Operations ═══
   /apply <file>   Apply unified diff to file
-  /revert <file>  Restore from .bak backup"#
+  /revert <file>  Restore from .bak backup
+  /undo           Restore the last path the delete tool trashed"#
             .into(),
         success: true,
+        action: None,
     }
 }
 
@@ -889,10 +1606,12 @@ fn run_cost(ctx: Option<&SlashContext>) -> SlashResult {
                 c.session_id, c.message_count, c.total_tokens, c.model
             ),
             success: true,
+            action: None,
         },
         None => SlashResult {
             output: "No session context available".into(),
             success: false,
+            action: None,
         },
     }
 }
@@ -924,6 +1643,28 @@ fn run_status(project_type: Option<&str>, ctx: Option<&SlashContext>) -> SlashRe
             lines.join("\n")
         },
         success: true,
+        action: None,
+    }
+}
+
+fn run_docs(query: &str) -> SlashResult {
+    if query.is_empty() {
+        return SlashResult {
+            output: "Usage: /docs <query>".into(),
+            success: false,
+            action: None,
+        };
+    }
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let index = crate::docs::DocsIndex::load_or_build(&root);
+    let hits = index.search(query, crate::docs::DOCS_DEFAULT_LIMIT);
+    let guard = crate::config::Config::load()
+        .map(|cfg| cfg.injection_guard)
+        .unwrap_or_default();
+    SlashResult {
+        output: crate::docs::format_hits(query, &hits, guard),
+        success: !hits.is_empty(),
+        action: None,
     }
 }
 
@@ -931,7 +1672,7 @@ fn run_git(args: &str) -> SlashResult {
     if args.is_empty() {
         git::status().into()
     } else {
-        tool_shell(&format!("git {}", args), None).into()
+        git::run(&split_args(args), None).into()
     }
 }
 
@@ -940,6 +1681,7 @@ fn run_commit(msg: &str) -> SlashResult {
         SlashResult {
             output: "Usage: /commit <message>".into(),
             success: false,
+            action: None,
         }
     } else {
         git::commit(msg).into()
@@ -955,6 +1697,7 @@ fn run_pr(args: &str) -> SlashResult {
         return SlashResult {
             output: "GitHub CLI (gh) not installed. Install from https://cli.github.com".into(),
             success: false,
+            action: None,
         };
     }
 
@@ -970,6 +1713,7 @@ fn run_pr(args: &str) -> SlashResult {
                         SlashResult {
                             output: "No open pull requests".into(),
                             success: true,
+                            action: None,
                         }
                     } else {
                         let output = prs
@@ -980,12 +1724,14 @@ fn run_pr(args: &str) -> SlashResult {
                         SlashResult {
                             output,
                             success: true,
+                            action: None,
                         }
                     }
                 }
                 Err(e) => SlashResult {
                     output: format!("Failed to list PRs: {}", e),
                     success: false,
+                    action: None,
                 },
             }
         }
@@ -996,16 +1742,19 @@ fn run_pr(args: &str) -> SlashResult {
                 SlashResult {
                     output: "Usage: /pr create <title>".into(),
                     success: false,
+                    action: None,
                 }
             } else {
                 match github::create_pr(&work_dir, title, "", None, false) {
                     Ok(url) => SlashResult {
                         output: format!("Created PR: {}", url),
                         success: true,
+                        action: None,
                     },
                     Err(e) => SlashResult {
                         output: format!("Failed to create PR: {}", e),
                         success: false,
+                        action: None,
                     },
                 }
             }
@@ -1018,15 +1767,18 @@ fn run_pr(args: &str) -> SlashResult {
                     Ok(diff) => SlashResult {
                         output: diff,
                         success: true,
+                        action: None,
                     },
                     Err(e) => SlashResult {
                         output: format!("Failed to get PR diff: {}", e),
                         success: false,
+                        action: None,
                     },
                 },
                 None => SlashResult {
                     output: "Usage: /pr diff <number>".into(),
                     success: false,
+                    action: None,
                 },
             }
         }
@@ -1038,15 +1790,18 @@ fn run_pr(args: &str) -> SlashResult {
                     Ok(()) => SlashResult {
                         output: format!("Checked out PR #{}", n),
                         success: true,
+                        action: None,
                     },
                     Err(e) => SlashResult {
                         output: format!("Failed to checkout PR: {}", e),
                         success: false,
+                        action: None,
                     },
                 },
                 None => SlashResult {
                     output: "Usage: /pr checkout <number>".into(),
                     success: false,
+                    action: None,
                 },
             }
         }
@@ -1057,16 +1812,19 @@ fn run_pr(args: &str) -> SlashResult {
                     Ok(info) => SlashResult {
                         output: info,
                         success: true,
+                        action: None,
                     },
                     Err(e) => SlashResult {
                         output: format!("Failed to view PR: {}", e),
                         success: false,
+                        action: None,
                     },
                 }
             } else {
                 SlashResult {
                     output: "Usage: /pr [list|create|diff|checkout|<number>]".into(),
                     success: false,
+                    action: None,
                 }
             }
         }
@@ -1080,6 +1838,7 @@ fn run_issue(args: &str) -> SlashResult {
         return SlashResult {
             output: "GitHub CLI (gh) not installed. Install from https://cli.github.com".into(),
             success: false,
+            action: None,
         };
     }
 
@@ -1095,6 +1854,7 @@ fn run_issue(args: &str) -> SlashResult {
                         SlashResult {
                             output: "No open issues".into(),
                             success: true,
+                            action: None,
                         }
                     } else {
                         let output = issues
@@ -1105,12 +1865,14 @@ fn run_issue(args: &str) -> SlashResult {
                         SlashResult {
                             output,
                             success: true,
+                            action: None,
                         }
                     }
                 }
                 Err(e) => SlashResult {
                     output: format!("Failed to list issues: {}", e),
                     success: false,
+                    action: None,
                 },
             }
         }
@@ -1121,16 +1883,19 @@ fn run_issue(args: &str) -> SlashResult {
                 SlashResult {
                     output: "Usage: /issue create <title>".into(),
                     success: false,
+                    action: None,
                 }
             } else {
                 match github::create_issue(&work_dir, title, "", &[]) {
                     Ok(url) => SlashResult {
                         output: format!("Created issue: {}", url),
                         success: true,
+                        action: None,
                     },
                     Err(e) => SlashResult {
                         output: format!("Failed to create issue: {}", e),
                         success: false,
+                        action: None,
                     },
                 }
             }
@@ -1142,16 +1907,19 @@ fn run_issue(args: &str) -> SlashResult {
                     Ok(info) => SlashResult {
                         output: info,
                         success: true,
+                        action: None,
                     },
                     Err(e) => SlashResult {
                         output: format!("Failed to view issue: {}", e),
                         success: false,
+                        action: None,
                     },
                 }
             } else {
                 SlashResult {
                     output: "Usage: /issue [list|create|<number>]".into(),
                     success: false,
+                    action: None,
                 }
             }
         }
@@ -1165,6 +1933,7 @@ fn run_actions(args: &str) -> SlashResult {
         return SlashResult {
             output: "GitHub CLI (gh) not installed. Install from https://cli.github.com".into(),
             success: false,
+            action: None,
         };
     }
 
@@ -1176,10 +1945,12 @@ fn run_actions(args: &str) -> SlashResult {
             Ok(runs) => SlashResult {
                 output: runs,
                 success: true,
+                action: None,
             },
             Err(e) => SlashResult {
                 output: format!("Failed to list runs: {}", e),
                 success: false,
+                action: None,
             },
         }
     } else if let Ok(run_id) = args.parse::<u64>() {
@@ -1188,16 +1959,19 @@ fn run_actions(args: &str) -> SlashResult {
             Ok(info) => SlashResult {
                 output: info,
                 success: true,
+                action: None,
             },
             Err(e) => SlashResult {
                 output: format!("Failed to view run: {}", e),
                 success: false,
+                action: None,
             },
         }
     } else {
         SlashResult {
             output: "Usage: /runs [<run_id>]".into(),
             success: false,
+            action: None,
         }
     }
 }
@@ -1209,16 +1983,19 @@ fn run_cd(path: &str) -> SlashResult {
                 .map(|p| p.display().to_string())
                 .unwrap_or_else(|_| "unknown".into()),
             success: true,
+            action: None,
         }
     } else {
         match std::env::set_current_dir(path) {
             Ok(()) => SlashResult {
                 output: format!("Changed to: {}", path),
                 success: true,
+                action: None,
             },
             Err(e) => SlashResult {
                 output: format!("cd failed: {}", e),
                 success: false,
+                action: None,
             },
         }
     }
@@ -1234,23 +2011,245 @@ fn run_grep(args: &str) -> SlashResult {
         SlashResult {
             output: "Usage: /grep <pattern> [path]".into(),
             success: false,
+            action: None,
         }
     } else {
         tool_shell(&format!("grep -rn --color=never {} .", args), None).into()
     }
 }
 
+/// Parse an `/explain` selection: `<file>:<start>-<end>` or `<file>:<line>`
+pub fn parse_selection(spec: &str) -> Result<(String, usize, usize), String> {
+    let spec = spec.trim();
+    let (path, range) = spec
+        .rsplit_once(':')
+        .ok_or_else(|| format!("Expected <file>:<start>-<end>, got '{}'", spec))?;
+    if path.is_empty() {
+        return Err("Missing file path".into());
+    }
+    let (start, end) = range.split_once('-').unwrap_or((range, range));
+    let line = |s: &str| {
+        s.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid line number '{}'", s))
+    };
+    let (start, end) = (line(start)?, line(end)?);
+    if start == 0 {
+        return Err("Line numbers start at 1".into());
+    }
+    if end < start {
+        return Err(format!("Range end {} is before start {}", end, start));
+    }
+    Ok((path.to_string(), start, end))
+}
+
+/// The explain skill's prompt filled with just the selected lines
+pub fn explain_selection_prompt(path: &str, range: &FileRange) -> String {
+    let template = builtin_skills()
+        .into_iter()
+        .find(|s| s.name == "explain")
+        .map(|s| s.prompt_template)
+        .unwrap_or_else(|| "Explain the following code:\n\n{code}".into());
+    let code = format!(
+        "{} (lines {}-{} of {}):\n```\n{}```",
+        path, range.start, range.end, range.total, range.text
+    );
+    template.replace("{code}", &code)
+}
+
+fn run_unpin(args: &str) -> SlashResult {
+    if args.is_empty() {
+        return SlashResult::action(SlashAction::Unpin(None));
+    }
+    match args.parse::<usize>() {
+        Ok(n) if n > 0 => SlashResult::action(SlashAction::Unpin(Some(n))),
+        _ => SlashResult {
+            output: "Usage: /unpin [n]  (numbers from /pins)".into(),
+            success: false,
+            action: None,
+        },
+    }
+}
+
+fn run_compact(args: &str) -> SlashResult {
+    let words: Vec<&str> = args.split_whitespace().collect();
+    let threshold = match words.as_slice() {
+        [] => return SlashResult::action(SlashAction::CompactConversation),
+        ["auto"] => Some(None),
+        ["auto", "off"] => Some(Some(0)),
+        ["auto", pct] => pct
+            .trim_end_matches('%')
+            .parse::<u32>()
+            .ok()
+            .filter(|pct| (1..=100).contains(pct))
+            .map(Some),
+        _ => None,
+    };
+    match threshold {
+        Some(threshold) => SlashResult::action(SlashAction::AutoCompact(threshold)),
+        None => SlashResult {
+            output: "Usage: /compact [auto [percent|off]]".into(),
+            success: false,
+            action: None,
+        },
+    }
+}
+
+fn run_planfirst(args: &str) -> SlashResult {
+    let enable = match args.trim() {
+        "" => None,
+        "on" | "true" => Some(true),
+        "off" | "false" => Some(false),
+        _ => {
+            return SlashResult {
+                output: "Usage: /planfirst [on|off]".into(),
+                success: false,
+                action: None,
+            }
+        }
+    };
+    SlashResult::action(SlashAction::PlanFirst(enable))
+}
+
+fn run_regenerate(args: &str) -> SlashResult {
+    let args = args.trim();
+    if args.is_empty() {
+        return SlashResult::action(SlashAction::Regenerate(None));
+    }
+    match args.parse::<f32>() {
+        Ok(t) if (0.0..=2.0).contains(&t) => SlashResult::action(SlashAction::Regenerate(Some(t))),
+        _ => SlashResult {
+            output: "Usage: /regenerate [temperature]  (0-2)".into(),
+            success: false,
+            action: None,
+        },
+    }
+}
+
+fn run_compare(args: &str) -> SlashResult {
+    let usage = |msg: &str| SlashResult {
+        output: format!("{}\nUsage: /compare <m1,m2[,m3]> <prompt>", msg),
+        success: false,
+        action: None,
+    };
+    let args = args.trim();
+    if args.is_empty() {
+        return SlashResult::action(SlashAction::Compare {
+            models: vec![],
+            prompt: String::new(),
+        });
+    }
+    let (spec, prompt) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+    let models = crate::compare::parse_model_list(spec);
+    if models.len() < 2 || models.len() > crate::compare::MAX_LIVE_MODELS {
+        return usage(&format!(
+            "Compare 2-{} models",
+            crate::compare::MAX_LIVE_MODELS
+        ));
+    }
+    if prompt.trim().is_empty() {
+        return usage("Missing prompt");
+    }
+    SlashResult::action(SlashAction::Compare {
+        models,
+        prompt: prompt.trim().to_string(),
+    })
+}
+
+fn run_attach(args: &str) -> SlashResult {
+    let paths: Vec<String> = args.split_whitespace().map(String::from).collect();
+    if paths.is_empty() {
+        return SlashResult {
+            output: "Usage: /attach <file> [file...]".into(),
+            success: false,
+            action: None,
+        };
+    }
+    let missing: Vec<&str> = paths
+        .iter()
+        .filter(|p| !Path::new(p).is_file())
+        .map(|p| p.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return SlashResult {
+            output: format!("Not a file: {}", missing.join(", ")),
+            success: false,
+            action: None,
+        };
+    }
+    SlashResult::action(SlashAction::Attach(paths))
+}
+
+fn run_focus(args: &str) -> SlashResult {
+    match args.trim() {
+        "" => return SlashResult::action(SlashAction::Focus(None)),
+        "clear" | "off" => return SlashResult::action(SlashAction::Focus(Some(vec![]))),
+        _ => {}
+    }
+    let mut files = Vec::new();
+    let mut missing = Vec::new();
+    for arg in args.split_whitespace() {
+        let path = Path::new(arg);
+        if path.is_dir() {
+            // Directories match everything beneath them
+            files.push(format!("{}/", arg.trim_end_matches('/')));
+        } else if path.is_file() {
+            files.push(arg.to_string());
+        } else {
+            missing.push(arg);
+        }
+    }
+    if !missing.is_empty() {
+        return SlashResult {
+            output: format!("No such file or directory: {}", missing.join(", ")),
+            success: false,
+            action: None,
+        };
+    }
+    SlashResult::action(SlashAction::Focus(Some(files)))
+}
+
+fn run_explain(args: &str) -> SlashResult {
+    let selection = parse_selection(args).and_then(|(path, start, end)| {
+        read_file_range(Path::new(&path), start, end)
+            .map(|range| (path, range))
+            .map_err(|e| e.to_string())
+    });
+    match selection {
+        Ok((path, range)) => SlashResult {
+            output: format!(
+                "Explaining {}:{}-{} ({} lines)",
+                path,
+                range.start,
+                range.end,
+                range.end - range.start + 1
+            ),
+            success: true,
+            action: Some(SlashAction::SendPrompt(explain_selection_prompt(
+                &path, &range,
+            ))),
+        },
+        Err(e) => SlashResult {
+            output: format!("{}\nUsage: /explain <file>:<start>-<end>", e),
+            success: false,
+            action: None,
+        },
+    }
+}
+
 fn run_edit(path: &str) -> SlashResult {
     if path.is_empty() {
         SlashResult {
             output: "Usage: /edit <file>".into(),
             success: false,
+            action: None,
         }
     } else {
         let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".into());
         SlashResult {
             output: format!("Open with: {} {}", editor, path),
             success: true,
+            action: None,
         }
     }
 }
@@ -1261,37 +2260,112 @@ fn run_improve() -> SlashResult {
             Ok(prompt) => SlashResult {
                 output: prompt,
                 success: true,
+                action: None,
             },
             Err(e) => SlashResult {
                 output: format!("Failed to generate improvement prompt: {}", e),
                 success: false,
+                action: None,
             },
         },
         Err(e) => SlashResult {
             output: format!("Not in hyle project: {}", e),
             success: false,
+            action: None,
         },
     }
 }
 
+/// Apply a proposed `/improve apply` diff to hyle's source, run the tests,
+/// and revert if they fail
+pub fn apply_improvement(diff: &str) -> SlashResult {
+    let analyzer = match SelfAnalyzer::new() {
+        Ok(analyzer) => analyzer,
+        Err(e) => {
+            return SlashResult {
+                output: format!("Not in hyle project: {}", e),
+                success: false,
+                action: None,
+            }
+        }
+    };
+    let root = analyzer.root();
+
+    let outcome = apply_and_verify(
+        diff,
+        |diff| apply_diff(root, diff),
+        || {
+            let tests = run_selftest_in(root);
+            if tests.success {
+                Ok(())
+            } else {
+                Err(tests.output)
+            }
+        },
+        restore,
+    );
+
+    match outcome {
+        Ok(ImproveOutcome::Applied { files }) => SlashResult {
+            output: format!(
+                "Applied and verified ({} file(s)):\n{}",
+                files.len(),
+                list_files(root, &files)
+            ),
+            success: true,
+            action: None,
+        },
+        Ok(ImproveOutcome::Reverted { files, reason }) => SlashResult {
+            output: format!(
+                "Tests failed, reverted {} file(s):\n{}\n{}",
+                files.len(),
+                list_files(root, &files),
+                reason
+            ),
+            success: false,
+            action: None,
+        },
+        Err(e) => SlashResult {
+            output: format!("Failed to apply fix: {}", e),
+            success: false,
+            action: None,
+        },
+    }
+}
+
+fn list_files(root: &Path, files: &[PathBuf]) -> String {
+    files
+        .iter()
+        .map(|f| format!("  {}", f.strip_prefix(root).unwrap_or(f).display()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn run_deps() -> SlashResult {
     match SelfAnalyzer::new() {
-        Ok(analyzer) => match analyzer.dependency_graph() {
-            Ok(graph) => SlashResult {
+        Ok(analyzer) => match analyzer
+            .dependency_graph()
+            .and_then(|graph| Ok((graph, analyzer.module_graph()?)))
+        {
+            Ok((graph, modules)) => SlashResult {
                 output: format!(
-                    "Module Dependencies (Mermaid):\n\n```mermaid\n{}\n```",
-                    graph
+                    "Module Dependencies (Mermaid):\n\n```mermaid\n{}\n```\n\n{}",
+                    graph,
+                    modules.report()
                 ),
                 success: true,
+                action: None,
             },
             Err(e) => SlashResult {
                 output: format!("Failed to generate dependency graph: {}", e),
                 success: false,
+                action: None,
             },
         },
         Err(e) => SlashResult {
             output: format!("Not in hyle project: {}", e),
             success: false,
+            action: None,
         },
     }
 }
@@ -1302,8 +2376,9 @@ fn run_apply(args: &str) -> SlashResult {
     let parts: Vec<&str> = args.splitn(2, ' ').collect();
     if parts.is_empty() || parts[0].is_empty() {
         return SlashResult {
-            output: "Usage: /apply <file> [diff]\n\nApplies a unified diff to a file.\nIf diff is not provided, reads from stdin or last clipboard.\n\nExamples:\n  /apply src/main.rs\n  /apply src/main.rs \"--- a/...\"".into(),
+            output: "Usage: /apply <file> [diff]\n\nApplies a unified diff to a file.\nFrom the shell, the diff can be piped in instead.\n\nExamples:\n  /apply src/main.rs \"--- a/...\"\n  cat patch.diff | hyle /apply src/main.rs".into(),
             success: false,
+            action: None,
         };
     }
 
@@ -1318,6 +2393,7 @@ fn run_apply(args: &str) -> SlashResult {
             return SlashResult {
                 output: format!("Failed to read {}: {}", path, e),
                 success: false,
+                action: None,
             }
         }
     };
@@ -1333,6 +2409,7 @@ fn run_apply(args: &str) -> SlashResult {
                 path, path
             ),
             success: false,
+            action: None,
         };
     };
 
@@ -1349,6 +2426,7 @@ fn run_apply(args: &str) -> SlashResult {
                     return SlashResult {
                         output: format!("Failed to backup: {}", e),
                         success: false,
+                        action: None,
                     };
                 }
             }
@@ -1361,25 +2439,180 @@ fn run_apply(args: &str) -> SlashResult {
                         preview, path
                     ),
                     success: true,
+                    action: None,
                 },
                 Err(e) => SlashResult {
                     output: format!("Failed to write: {}", e),
                     success: false,
+                    action: None,
                 },
             }
         }
         Err(e) => SlashResult {
             output: format!("Failed to apply patch: {}", e),
             success: false,
+            action: None,
         },
     }
 }
 
+/// Piped stdin, if any: None when stdin is a terminal or nothing was piped
+pub fn read_piped(
+    stdin_is_tty: bool,
+    mut reader: impl std::io::Read,
+) -> std::io::Result<Option<String>> {
+    if stdin_is_tty {
+        return Ok(None);
+    }
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    Ok(Some(input).filter(|s| !s.trim().is_empty()))
+}
+
+/// Complete `/apply <file>` from the shell with a diff piped on stdin;
+/// inline diffs and other commands are left as they are
+pub fn with_piped_diff(
+    cmd: &str,
+    stdin_is_tty: bool,
+    reader: impl std::io::Read,
+) -> std::io::Result<String> {
+    let (name, args) = cmd.trim().split_once(' ').unwrap_or((cmd.trim(), ""));
+    let path = args.trim();
+    if name != "/apply" || path.is_empty() || path.contains(' ') {
+        return Ok(cmd.to_string());
+    }
+    Ok(match read_piped(stdin_is_tty, reader)? {
+        Some(diff) => format!("/apply {} {}", path, diff),
+        None => cmd.to_string(),
+    })
+}
+
+/// `--task -` takes the prompt from stdin; any other value is the prompt
+pub fn task_from_arg(
+    arg: &str,
+    stdin_is_tty: bool,
+    reader: impl std::io::Read,
+) -> anyhow::Result<String> {
+    if arg != "-" {
+        return Ok(arg.to_string());
+    }
+    match read_piped(stdin_is_tty, reader)? {
+        Some(prompt) => Ok(prompt.trim().to_string()),
+        None => anyhow::bail!("--task - reads the prompt from stdin: echo \"...\" | hyle --task -"),
+    }
+}
+
+fn run_system(args: &str) -> SlashResult {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let global = crate::config::config_dir().ok();
+    run_system_in(&cwd, global.as_deref(), args)
+}
+
+/// `/system` against explicit roots (testable without touching cwd)
+fn run_system_in(root: &Path, global_dir: Option<&Path>, args: &str) -> SlashResult {
+    let (sub, rest) = args.trim().split_once(' ').unwrap_or((args.trim(), ""));
+
+    // Edit whichever project file is active, else create .hyle/system.md
+    let project_file = PROJECT_INSTRUCTION_FILES
+        .iter()
+        .map(|name| root.join(name))
+        .find(|p| p.exists())
+        .unwrap_or_else(|| root.join(PROJECT_INSTRUCTION_FILES[0]));
+
+    match sub {
+        "" | "show" => {
+            let instr = CustomInstructions::load(Some(root), global_dir);
+            if instr.is_empty() {
+                return SlashResult {
+                    output: format!(
+                        "No custom instructions.\nCreate {} or AGENTS.md (project), or system.md in the config dir (global).",
+                        PROJECT_INSTRUCTION_FILES[0]
+                    ),
+                    success: true,
+                    action: None,
+                };
+            }
+            let mut out = String::new();
+            for (label, part) in [("global", &instr.global), ("project", &instr.project)] {
+                if let Some((path, text)) = part {
+                    out.push_str(&format!(
+                        "── {} ({}) ──\n{}\n\n",
+                        label,
+                        path.display(),
+                        text
+                    ));
+                }
+            }
+            SlashResult {
+                output: out.trim_end().to_string(),
+                success: true,
+                action: None,
+            }
+        }
+        "edit" => {
+            if let Err(e) = ensure_file(&project_file) {
+                return SlashResult {
+                    output: format!("Failed to create {}: {}", project_file.display(), e),
+                    success: false,
+                    action: None,
+                };
+            }
+            run_edit(&project_file.display().to_string())
+        }
+        "add" if !rest.trim().is_empty() => {
+            let result = ensure_file(&project_file).and_then(|_| {
+                use std::io::Write;
+                // A hand-edited file may lack a final newline; don't glue onto its last line
+                let existing = std::fs::read_to_string(&project_file)?;
+                let mut f = std::fs::OpenOptions::new()
+                    .append(true)
+                    .open(&project_file)?;
+                if !existing.is_empty() && !existing.ends_with('\n') {
+                    writeln!(f)?;
+                }
+                writeln!(f, "{}", rest.trim())
+            });
+            match result {
+                Ok(()) => SlashResult {
+                    output: format!(
+                        "Added to {} (applies to the next request)",
+                        project_file.display()
+                    ),
+                    success: true,
+                    action: None,
+                },
+                Err(e) => SlashResult {
+                    output: format!("Failed to update {}: {}", project_file.display(), e),
+                    success: false,
+                    action: None,
+                },
+            }
+        }
+        _ => SlashResult {
+            output: "Usage: /system [show|edit|add <text>]".into(),
+            success: false,
+            action: None,
+        },
+    }
+}
+
+fn ensure_file(path: &Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    Ok(())
+}
+
 fn run_revert(args: &str) -> SlashResult {
     if args.is_empty() {
         return SlashResult {
             output: "Usage: /revert <file>\n\nRestores a file from its .bak backup.".into(),
             success: false,
+            action: None,
         };
     }
 
@@ -1390,6 +2623,7 @@ fn run_revert(args: &str) -> SlashResult {
         return SlashResult {
             output: format!("No backup found: {}", backup.display()),
             success: false,
+            action: None,
         };
     }
 
@@ -1400,23 +2634,50 @@ fn run_revert(args: &str) -> SlashResult {
             SlashResult {
                 output: format!("Reverted {} from backup", args),
                 success: true,
+                action: None,
             }
         }
         Err(e) => SlashResult {
             output: format!("Failed to revert: {}", e),
             success: false,
+            action: None,
+        },
+    }
+}
+
+/// Restore the latest entry in `work_dir`'s trash
+pub fn run_undo(work_dir: &Path) -> SlashResult {
+    match crate::tools::restore_latest(work_dir) {
+        Ok(Some(path)) => SlashResult {
+            output: format!("Restored {} from {}", path, crate::tools::TRASH_DIR),
+            success: true,
+            action: None,
+        },
+        Ok(None) => SlashResult {
+            output: format!("Nothing to undo: {} is empty", crate::tools::TRASH_DIR),
+            success: true,
+            action: None,
+        },
+        Err(e) => SlashResult {
+            output: format!("Failed to undo: {}", e),
+            success: false,
+            action: None,
         },
     }
 }
 
 fn run_selftest() -> SlashResult {
     let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    run_selftest_in(&cwd)
+}
 
+fn run_selftest_in(cwd: &Path) -> SlashResult {
     // Check if we're in a Rust project
     if !cwd.join("Cargo.toml").exists() {
         return SlashResult {
             output: "Not in a Rust project (no Cargo.toml)".into(),
             success: false,
+            action: None,
         };
     }
 
@@ -1424,7 +2685,7 @@ fn run_selftest() -> SlashResult {
     let start = std::time::Instant::now();
     let output = std::process::Command::new("cargo")
         .args(["test", "--", "--color=never"])
-        .current_dir(&cwd)
+        .current_dir(cwd)
         .output();
 
     let duration = start.elapsed();
@@ -1460,11 +2721,13 @@ fn run_selftest() -> SlashResult {
             SlashResult {
                 output: out,
                 success: results.success(),
+                action: None,
             }
         }
         Err(e) => SlashResult {
             output: format!("Failed to run tests: {}", e),
             success: false,
+            action: None,
         },
     }
 }
@@ -1474,6 +2737,7 @@ fn run_map() -> SlashResult {
     SlashResult {
         output: map.display(),
         success: true,
+        action: None,
     }
 }
 
@@ -1516,20 +2780,43 @@ fn run_analyze() -> SlashResult {
                         }
                     }
 
+                    // Add import cycles and coupling hotspots
+                    output.push('\n');
+                    output.push_str(&ModuleGraph::from_modules(&analysis.modules).report());
+
+                    // Add lint findings, errors first
+                    if !analysis.lint_findings.is_empty() {
+                        let mut findings = analysis.lint_findings.clone();
+                        findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+                        output.push_str("\nLint Findings:\n");
+                        for f in findings.iter().take(10) {
+                            output.push_str(&format!(
+                                "  {}:{}: [{}] {}\n",
+                                f.file,
+                                f.line,
+                                f.lint,
+                                f.message.chars().take(60).collect::<String>()
+                            ));
+                        }
+                    }
+
                     SlashResult {
                         output,
                         success: true,
+                        action: None,
                     }
                 }
                 Err(e) => SlashResult {
                     output: format!("Analysis failed: {}", e),
                     success: false,
+                    action: None,
                 },
             }
         }
         Err(e) => SlashResult {
             output: format!("Not in hyle project: {}", e),
             success: false,
+            action: None,
         },
     }
 }
@@ -1564,8 +2851,9 @@ fn run_doctor() -> SlashResult {
     }
 
     // Check tools
-    let has_rg = tool_shell("which rg", None).success;
-    let has_fd = tool_shell("which fd", None).success;
+    let env = crate::environ::snapshot();
+    let has_rg = env.has("rg");
+    let has_fd = env.has("fd");
     lines.push(format!(
         "[{}] ripgrep: {}",
         if has_rg { "✓" } else { "○" },
@@ -1577,9 +2865,14 @@ fn run_doctor() -> SlashResult {
         if has_fd { "available" } else { "not found" }
     ));
 
+    // Execution environment
+    lines.push(String::new());
+    lines.extend(env.report());
+
     SlashResult {
         output: lines.join("\n"),
         success: true,
+        action: None,
     }
 }
 
@@ -1601,11 +2894,13 @@ fn run_toolbelt(args: &str) -> SlashResult {
                     cmd.name, cmd.description, cmd.prompt
                 ),
                 success: true,
+                action: None,
             };
         }
         return SlashResult {
             output: format!("Unknown toolbelt command: {}", args),
             success: false,
+            action: None,
         };
     }
 
@@ -1625,6 +2920,7 @@ fn run_toolbelt(args: &str) -> SlashResult {
     SlashResult {
         output: lines.join("\n"),
         success: true,
+        action: None,
     }
 }
 
@@ -1660,6 +2956,7 @@ fn run_prompts() -> SlashResult {
     SlashResult {
         output: lines.join("\n"),
         success: true,
+        action: None,
     }
 }
 
@@ -1687,12 +2984,57 @@ mod tests {
         let _ = git::is_repo();
     }
 
+    #[test]
+    fn test_split_args_is_literal() {
+        assert_eq!(
+            split_args(r#"commit -m "fix: it's done; rm -rf ." --amend"#),
+            vec!["commit", "-m", "fix: it's done; rm -rf .", "--amend"]
+        );
+        assert_eq!(
+            split_args("log  'a b'\\ c $(x)"),
+            vec!["log", "a b c", "$(x)"]
+        );
+        assert!(split_args("   ").is_empty());
+    }
+
+    #[test]
+    fn test_git_commit_message_passed_literally() {
+        let dir = std::env::temp_dir().join(format!("hyle_test_git_commit_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let cwd = dir.to_str().unwrap();
+
+        if !git::run(&["init", "-q"], Some(cwd)).success {
+            return; // git not available
+        }
+        git::run(&["config", "user.email", "test@example.com"], Some(cwd));
+        git::run(&["config", "user.name", "Test"], Some(cwd));
+        std::fs::write(dir.join("a.txt"), "a").unwrap();
+        git::run(&["add", "a.txt"], Some(cwd));
+
+        let message = "it's a 'test'; touch pwned && echo \"$HOME\"";
+        let result = git::commit_in(message, Some(cwd));
+        assert!(result.success, "{}", result.output);
+
+        let logged = git::run(&["log", "-1", "--format=%B"], Some(cwd));
+        assert_eq!(logged.output.trim(), message);
+        assert!(!dir.join("pwned").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_tool_registry() {
         let registry = ToolRegistry::new();
         assert!(registry.get("read_file").is_some());
         assert!(registry.get("write_file").is_some());
         assert!(registry.get("shell").is_some());
+        assert!(registry.get("tree").is_some());
+        assert!(registry.get("move").is_some());
+        assert!(registry.get("delete").is_some());
+        assert!(registry.get("stats").is_some());
+        assert!(registry.get("docs").is_some());
+        assert!(registry.get("environ").is_some());
     }
 
     #[test]
@@ -1702,4 +3044,507 @@ mod tests {
         assert!(skills.iter().any(|s| s.name == "explain"));
         assert!(skills.iter().any(|s| s.name == "refactor"));
     }
+
+    fn slash_action(cmd: &str) -> Option<SlashAction> {
+        execute_slash_command(cmd, None).and_then(|r| r.action)
+    }
+
+    #[test]
+    fn test_command_capture() {
+        let capture = CommandCapture::run("echo out; echo err >&2; exit 3", None);
+        assert_eq!(capture.exit_code, Some(3));
+        assert!(!capture.success());
+        assert!(capture.output.starts_with("out\n"));
+        assert!(capture.output.ends_with("--- stderr ---\nerr"));
+        assert!(!capture.truncated());
+        assert_eq!(
+            CommandCapture::run("true", None).to_context(),
+            "[Ran `true` - exit 0]\n```\n(no output)\n```"
+        );
+
+        // Large output keeps its head and tail around a marker
+        let big: String = (0..2_000).map(|i| format!("line {}\n", i)).collect();
+        let capture = CommandCapture::from_output("cargo test", Some(101), &big);
+        assert!(capture.truncated());
+        assert!(capture.output.len() <= RUN_CONTEXT_MAX_BYTES);
+        assert!(capture.output.starts_with("line 0\n"));
+        assert!(capture.output.ends_with("line 1999"));
+        assert!(capture.output.contains("[truncated "));
+
+        // What goes into the session
+        let context = capture.to_context();
+        assert!(context.starts_with(&format!(
+            "[Ran `cargo test` - exit 101, output truncated from {} bytes]\n```\nline 0",
+            big.trim_end().len()
+        )));
+        assert!(context.ends_with("line 1999\n```"));
+        assert_eq!(
+            slash_action("/run cargo test 2>&1"),
+            Some(SlashAction::RunCommand("cargo test 2>&1".into()))
+        );
+        assert_eq!(slash_action("/run  "), None);
+    }
+
+    #[test]
+    fn test_slash_actions() {
+        assert_eq!(slash_action("/clear"), Some(SlashAction::ClearConversation));
+        assert_eq!(
+            slash_action("/compact"),
+            Some(SlashAction::CompactConversation)
+        );
+        assert_eq!(
+            slash_action("/compact auto"),
+            Some(SlashAction::AutoCompact(None))
+        );
+        assert_eq!(
+            slash_action("/compact auto 85%"),
+            Some(SlashAction::AutoCompact(Some(85)))
+        );
+        assert_eq!(
+            slash_action("/compact auto off"),
+            Some(SlashAction::AutoCompact(Some(0)))
+        );
+        assert_eq!(slash_action("/compact auto 150"), None);
+        assert_eq!(slash_action("/compact now"), None);
+        assert_eq!(slash_action("/summarize"), Some(SlashAction::Summarize));
+        assert_eq!(slash_action("/switch"), Some(SlashAction::ShowModelPicker));
+        assert_eq!(
+            slash_action("/switch gemma"),
+            Some(SlashAction::SwitchModel("gemma".into()))
+        );
+        assert_eq!(slash_action("/agent"), Some(SlashAction::ToggleAgent));
+        assert_eq!(
+            slash_action("/planfirst"),
+            Some(SlashAction::PlanFirst(None))
+        );
+        assert_eq!(
+            slash_action("/planfirst off"),
+            Some(SlashAction::PlanFirst(Some(false)))
+        );
+        assert_eq!(slash_action("/planfirst maybe"), None);
+        assert_eq!(
+            slash_action("/ingest https://example.com/doc"),
+            Some(SlashAction::Ingest("https://example.com/doc".into()))
+        );
+        assert_eq!(slash_action("/ingest"), None);
+        assert_eq!(slash_action("/fork"), Some(SlashAction::ForkSession));
+        assert_eq!(
+            slash_action("/models refresh"),
+            Some(SlashAction::RefreshModels { force: false })
+        );
+        assert_eq!(
+            slash_action("/models refresh force"),
+            Some(SlashAction::RefreshModels { force: true })
+        );
+        assert_eq!(slash_action("/models"), None);
+        assert_eq!(
+            slash_action("/resume 20260101"),
+            Some(SlashAction::ResumeSession("20260101".into()))
+        );
+        assert_eq!(
+            slash_action("/context window 4"),
+            Some(SlashAction::SetContextStrategy("window 4".into()))
+        );
+        assert_eq!(
+            slash_action("/context"),
+            Some(SlashAction::SetContextStrategy(String::new()))
+        );
+        assert_eq!(
+            slash_action("/improve apply"),
+            Some(SlashAction::SelfImprove)
+        );
+        assert_eq!(slash_action("/version"), None);
+    }
+
+    #[test]
+    fn test_attach_detach_commands() {
+        let attach = execute_slash_command("/attach Cargo.toml src/main.rs", None).unwrap();
+        assert_eq!(
+            attach.action,
+            Some(SlashAction::Attach(vec![
+                "Cargo.toml".into(),
+                "src/main.rs".into()
+            ]))
+        );
+
+        let missing = execute_slash_command("/attach Cargo.toml no_such_file.rs", None).unwrap();
+        assert!(!missing.success);
+        assert!(missing.output.contains("no_such_file.rs"));
+        assert!(missing.action.is_none());
+        assert!(!execute_slash_command("/attach", None).unwrap().success);
+
+        assert_eq!(
+            execute_slash_command("/detach src/main.rs", None)
+                .unwrap()
+                .action,
+            Some(SlashAction::Detach(vec!["src/main.rs".into()]))
+        );
+        assert_eq!(
+            execute_slash_command("/detach", None).unwrap().action,
+            Some(SlashAction::Detach(vec![]))
+        );
+    }
+
+    #[test]
+    fn test_focus_command() {
+        assert_eq!(
+            execute_slash_command("/focus Cargo.toml src", None)
+                .unwrap()
+                .action,
+            Some(SlashAction::Focus(Some(vec![
+                "Cargo.toml".into(),
+                "src/".into()
+            ])))
+        );
+        assert_eq!(
+            execute_slash_command("/focus", None).unwrap().action,
+            Some(SlashAction::Focus(None))
+        );
+        assert_eq!(
+            execute_slash_command("/focus clear", None).unwrap().action,
+            Some(SlashAction::Focus(Some(vec![])))
+        );
+        let missing = execute_slash_command("/focus src/nope.rs", None).unwrap();
+        assert!(!missing.success);
+        assert!(missing.output.contains("src/nope.rs"));
+    }
+
+    #[test]
+    fn test_pin_commands() {
+        assert_eq!(
+            execute_slash_command("/pin The API base is /v2", None)
+                .unwrap()
+                .action,
+            Some(SlashAction::Pin("The API base is /v2".into()))
+        );
+        assert!(!execute_slash_command("/pin", None).unwrap().success);
+        assert_eq!(
+            execute_slash_command("/unpin 2", None).unwrap().action,
+            Some(SlashAction::Unpin(Some(2)))
+        );
+        assert_eq!(
+            execute_slash_command("/unpin", None).unwrap().action,
+            Some(SlashAction::Unpin(None))
+        );
+        assert!(!execute_slash_command("/unpin 0", None).unwrap().success);
+        assert!(!execute_slash_command("/unpin two", None).unwrap().success);
+        assert_eq!(
+            execute_slash_command("/pins", None).unwrap().action,
+            Some(SlashAction::ListPins)
+        );
+    }
+
+    #[test]
+    fn test_undo_restores_in_work_dir() {
+        assert_eq!(slash_action("/undo"), Some(SlashAction::Undo));
+
+        let dir = std::env::temp_dir().join(format!("hyle_undo_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(run_undo(&dir).output.starts_with("Nothing to undo"));
+
+        std::fs::write(dir.join("gone.txt"), "x").unwrap();
+        crate::tools::trash_path(&dir, &dir.join("gone.txt"), false).unwrap();
+        let undo = run_undo(&dir);
+        assert!(undo.success);
+        assert!(dir.join("gone.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_compare_command() {
+        assert_eq!(
+            execute_slash_command("/compare a/one,b/two  Explain lifetimes ", None)
+                .unwrap()
+                .action,
+            Some(SlashAction::Compare {
+                models: vec!["a/one".into(), "b/two".into()],
+                prompt: "Explain lifetimes".into(),
+            })
+        );
+        assert_eq!(
+            execute_slash_command("/compare", None).unwrap().action,
+            Some(SlashAction::Compare {
+                models: vec![],
+                prompt: String::new(),
+            })
+        );
+        assert!(
+            !execute_slash_command("/compare a/one hi", None)
+                .unwrap()
+                .success
+        );
+        assert!(
+            !execute_slash_command("/compare a,b,c,d hi", None)
+                .unwrap()
+                .success
+        );
+        assert!(!execute_slash_command("/compare a,b", None).unwrap().success);
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(
+            parse_selection("src/ui.rs:10-20"),
+            Ok(("src/ui.rs".to_string(), 10, 20))
+        );
+        assert_eq!(
+            parse_selection(" src/ui.rs:7 "),
+            Ok(("src/ui.rs".to_string(), 7, 7))
+        );
+        // Only the last colon separates the range
+        assert_eq!(
+            parse_selection("C:/code/a.rs:1-2"),
+            Ok(("C:/code/a.rs".to_string(), 1, 2))
+        );
+        assert!(parse_selection("src/ui.rs").is_err());
+        assert!(parse_selection(":1-2").is_err());
+        assert!(parse_selection("src/ui.rs:0-5").is_err());
+        assert!(parse_selection("src/ui.rs:9-3").is_err());
+        assert!(parse_selection("src/ui.rs:a-b").is_err());
+        assert!(parse_selection("src/ui.rs:3-").is_err());
+    }
+
+    #[test]
+    fn test_explain_selection_prompt() {
+        let tmp = std::env::temp_dir().join(format!("hyle_test_explain_{}.rs", std::process::id()));
+        let source: String = (1..=10)
+            .map(|i| format!("let line{} = {};\n", i, i))
+            .collect();
+        std::fs::write(&tmp, source).unwrap();
+        let path = tmp.to_string_lossy().to_string();
+
+        let result = execute_slash_command(&format!("/explain {}:4-50", path), None).unwrap();
+        assert!(result.success);
+        assert!(result.output.contains(":4-10 (7 lines)"));
+        let prompt = match result.action {
+            Some(SlashAction::SendPrompt(prompt)) => prompt,
+            other => panic!("expected SendPrompt, got {:?}", other),
+        };
+        assert!(prompt.starts_with("Explain the following code"));
+        assert!(prompt.contains("lines 4-10 of 10"));
+        assert!(prompt.contains("let line4 = 4;"));
+        assert!(prompt.contains("let line10 = 10;"));
+        assert!(!prompt.contains("let line3 = 3;"));
+
+        // Out-of-bounds start is reported, not sent
+        let result = execute_slash_command(&format!("/explain {}:11-12", path), None).unwrap();
+        assert!(!result.success);
+        assert!(result.action.is_none());
+
+        std::fs::remove_file(&tmp).ok();
+    }
+
+    /// `cargo check --message-format=json` for a broken crate: one error
+    /// reported for both lib and bin targets, a warning, and the artifact and
+    /// build-finished records
+    const CARGO_ERROR_FIXTURE: &str = r#"{"reason":"compiler-artifact","package_id":"dep 0.1.0","target":{"name":"dep"}}
+{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","code":{"code":"E0308"},"spans":[{"file_name":"src/store.rs","line_start":4,"is_primary":true}]}}
+{"reason":"compiler-message","message":{"level":"warning","message":"unused variable: `x`","code":{"code":"unused_variables"},"spans":[{"file_name":"src/main.rs","line_start":2,"is_primary":true}]}}
+{"reason":"compiler-message","message":{"level":"error","message":"cannot find value `y` in this scope","code":{"code":"E0425"},"spans":[{"file_name":"src/store.rs","line_start":7,"is_primary":true}]}}
+{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","code":{"code":"E0308"},"spans":[{"file_name":"src/store.rs","line_start":4,"is_primary":true}]}}
+{"reason":"compiler-message","message":{"level":"error","message":"aborting due to 2 previous errors","code":null,"spans":[]}}
+{"reason":"build-finished","success":false}"#;
+
+    #[test]
+    fn test_parse_build_errors() {
+        let errors = parse_build_errors(CARGO_ERROR_FIXTURE);
+        let located: Vec<(&str, usize, &str)> = errors
+            .iter()
+            .map(|e| (e.file.as_str(), e.line, e.message.as_str()))
+            .collect();
+        assert_eq!(
+            located,
+            vec![
+                ("src/store.rs", 4, "mismatched types"),
+                ("src/store.rs", 7, "cannot find value `y` in this scope"),
+            ]
+        );
+        assert_eq!(errors[0].lint, "E0308");
+
+        // Plain file:line[:col] output from other toolchains
+        let go = "# example\n./main.go:12:5: undefined: thing\nmain.go:3: warning: unused\nFAIL\n";
+        let errors = parse_build_errors(go);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].file, "main.go");
+        assert_eq!(errors[0].line, 12);
+        assert_eq!(errors[0].message, "undefined: thing");
+
+        assert!(parse_build_errors("error: linker `cc` not found\n").is_empty());
+    }
+
+    #[test]
+    fn test_fix_errors_prompt() {
+        let root = std::env::temp_dir().join(format!("hyle_test_fixerror_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let source: String = (1..=30).map(|i| format!("let l{} = {};\n", i, i)).collect();
+        std::fs::write(root.join("src/store.rs"), source).unwrap();
+
+        let errors = parse_build_errors(CARGO_ERROR_FIXTURE);
+        let prompt = fix_errors_prompt(&root, "cargo check", &errors);
+        assert!(prompt.starts_with("The build (`cargo check`) fails with 2 errors."));
+        assert!(prompt.contains("1. src/store.rs:4 [E0308] mismatched types\n"));
+        assert!(prompt.contains("2. src/store.rs:7 [E0425] cannot find value `y`"));
+        // Nearby errors share one snippet covering both
+        assert_eq!(prompt.matches("src/store.rs (lines").count(), 1);
+        assert!(prompt.contains("src/store.rs (lines 1-12 of 30):"));
+        assert!(prompt.contains("let l12 = 12;"));
+        assert!(!prompt.contains("let l13 = 13;"));
+
+        // Unreadable files still list their errors, just without source
+        let missing = vec![LintFinding {
+            file: "src/gone.rs".into(),
+            line: 3,
+            lint: "build".into(),
+            severity: LintSeverity::Error,
+            message: "boom".into(),
+        }];
+        let prompt = fix_errors_prompt(&root, "make", &missing);
+        assert!(prompt.contains("1. src/gone.rs:3 boom"));
+        assert!(!prompt.contains("## Source"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_slash_retry() {
+        assert_eq!(slash_action("/retry"), Some(SlashAction::RetryLast(None)));
+        assert_eq!(
+            slash_action("/retry gemma"),
+            Some(SlashAction::RetryLast(Some("gemma".into())))
+        );
+    }
+
+    #[test]
+    fn test_slash_regenerate() {
+        assert_eq!(
+            slash_action("/regenerate"),
+            Some(SlashAction::Regenerate(None))
+        );
+        assert_eq!(
+            slash_action("/regen 1.2"),
+            Some(SlashAction::Regenerate(Some(1.2)))
+        );
+        assert_eq!(slash_action("/regenerate hot"), None);
+        assert_eq!(slash_action("/regenerate 3"), None);
+    }
+
+    #[test]
+    fn test_slash_system() {
+        let root = std::env::temp_dir().join(format!("hyle_test_system_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let empty = run_system_in(&root, None, "");
+        assert!(empty.output.contains("No custom instructions"));
+
+        let added = run_system_in(&root, None, "add Run cargo fmt before committing");
+        assert!(added.success);
+        assert!(root.join(".hyle/system.md").exists());
+
+        let shown = run_system_in(&root, None, "");
+        assert!(shown.output.contains("Run cargo fmt"));
+        assert!(shown.output.contains("project"));
+
+        assert!(!run_system_in(&root, None, "add").success);
+
+        // Appending after a hand edit without a final newline starts a new line
+        let file = root.join(".hyle/system.md");
+        std::fs::write(&file, "Prefer small commits").unwrap();
+        assert!(run_system_in(&root, None, "add Keep tests green").success);
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "Prefer small commits\nKeep tests green\n"
+        );
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_piped_input() {
+        let piped = |s: &'static str| std::io::Cursor::new(s.as_bytes());
+
+        // Inline arguments win; a terminal is never read
+        assert_eq!(
+            task_from_arg("fix it", false, piped("ignored")).unwrap(),
+            "fix it"
+        );
+        assert_eq!(
+            task_from_arg("-", false, piped("  from stdin\n")).unwrap(),
+            "from stdin"
+        );
+        assert!(task_from_arg("-", true, piped("unread")).is_err());
+        assert!(task_from_arg("-", false, piped("\n")).is_err());
+
+        let inline = "/apply src/a.rs --- a/src/a.rs";
+        assert_eq!(with_piped_diff(inline, false, piped("x")).unwrap(), inline);
+        assert_eq!(
+            with_piped_diff("/clear", false, piped("x")).unwrap(),
+            "/clear"
+        );
+        assert_eq!(
+            with_piped_diff("/apply f.rs", true, piped("x")).unwrap(),
+            "/apply f.rs"
+        );
+
+        // Piped diff reaches /apply
+        let root = std::env::temp_dir().join(format!("hyle_test_piped_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let file = root.join("notes.txt");
+        std::fs::write(&file, "line 1\nline 2\n").unwrap();
+        let diff =
+            "--- a/notes.txt\n+++ b/notes.txt\n@@ -1,2 +1,2 @@\n line 1\n-line 2\n+line two\n";
+        let cmd =
+            with_piped_diff(&format!("/apply {}", file.display()), false, piped(diff)).unwrap();
+        assert!(execute_slash_command(&cmd, None).unwrap().success);
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "line 1\nline two\n"
+        );
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_slash_sampling() {
+        let sampling = |key: &str, value: &str| {
+            Some(SlashAction::SetSampling {
+                key: key.into(),
+                value: value.into(),
+            })
+        };
+        assert_eq!(slash_action("/temp 0.2"), sampling("temperature", "0.2"));
+        assert_eq!(slash_action("/stop ###"), sampling("stop", "###"));
+        assert_eq!(slash_action("/top_p"), sampling("top_p", ""));
+    }
+
+    #[test]
+    fn test_project_command() {
+        let root =
+            std::env::temp_dir().join(format!("hyle_test_project_cmd_{}", std::process::id()));
+        std::fs::create_dir_all(root.join(".hyle")).unwrap();
+        assert_eq!(
+            project_command(&root, "test", "cargo test").unwrap(),
+            "cargo test"
+        );
+
+        std::fs::write(
+            root.join(".hyle/config.json"),
+            r#"{"commands": {"test": "cargo nextest run"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            project_command(&root, "test", "cargo test").unwrap(),
+            "cargo nextest run"
+        );
+        assert_eq!(
+            project_command(&root, "build", "cargo build").unwrap(),
+            "cargo build"
+        );
+
+        std::fs::write(root.join(".hyle/config.json"), "{").unwrap();
+        assert!(project_command(&root, "test", "cargo test").is_err());
+
+        std::fs::remove_dir_all(&root).ok();
+    }
 }
@@ -317,6 +317,152 @@ impl TelemetrySampler {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════
+// UI MESSAGE CHANNEL
+// ═══════════════════════════════════════════════════════════════
+
+/// Room in the TUI message channel; streaming tasks wait when it's full
+pub const UI_CHANNEL_CAPACITY: usize = 1024;
+
+/// Time each frame spends handling messages before drawing again
+pub const FRAME_DRAIN_BUDGET: Duration = Duration::from_millis(12);
+
+/// Messages pulled off the channel at once; bounds the work a single
+/// pull can add past the budget
+const DRAIN_BATCH: usize = 256;
+
+/// Minimum gap between lag reports in the Log view
+const LAG_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Counts for the channel between background tasks and the UI loop
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    pub received: u64,
+    /// Messages folded into the one before them
+    pub coalesced: u64,
+    /// Frames that used up their budget with messages still waiting
+    pub lagged_frames: u64,
+    /// Messages waiting when the last frame ended
+    pub backlog: usize,
+    pub max_backlog: usize,
+}
+
+/// Hands out the UI channel's messages one frame at a time
+///
+/// A frame handles messages until its budget runs out (always at least
+/// one), so a token flood can't hold off drawing and input. Messages are
+/// pulled in batches, and runs that `merge` can fold together - streamed
+/// tokens - become one message, which drains a backlog quickly.
+pub struct FrameDrain<T> {
+    budget: Duration,
+    merge: fn(&mut T, T) -> Option<T>,
+    /// Pulled but not yet handled, each with how many messages it stands for
+    pending: VecDeque<(T, usize)>,
+    deadline: Option<Instant>,
+    handled: usize,
+    last_report: Option<Instant>,
+    pub stats: ChannelStats,
+}
+
+impl<T> FrameDrain<T> {
+    /// `merge` folds the second message into the first and returns None,
+    /// or hands it back when the two don't combine
+    pub fn new(budget: Duration, merge: fn(&mut T, T) -> Option<T>) -> Self {
+        Self {
+            budget,
+            merge,
+            pending: VecDeque::new(),
+            deadline: None,
+            handled: 0,
+            last_report: None,
+            stats: ChannelStats::default(),
+        }
+    }
+
+    /// Start a frame
+    pub fn begin(&mut self) {
+        self.deadline = Some(Instant::now() + self.budget);
+        self.handled = 0;
+    }
+
+    /// Next message for this frame, with how many were merged into it;
+    /// None once the channel is empty or the budget is spent
+    pub fn next(&mut self, rx: &mut mpsc::Receiver<T>) -> Option<(T, usize)> {
+        let deadline = self.deadline?;
+        if self.handled > 0 && Instant::now() >= deadline {
+            self.end_frame(rx);
+            return None;
+        }
+        if self.pending.is_empty() {
+            self.pull(rx);
+        }
+        match self.pending.pop_front() {
+            Some(item) => {
+                self.handled += 1;
+                Some(item)
+            }
+            None => {
+                self.end_frame(rx);
+                None
+            }
+        }
+    }
+
+    fn pull(&mut self, rx: &mut mpsc::Receiver<T>) {
+        for _ in 0..DRAIN_BATCH {
+            let Ok(msg) = rx.try_recv() else {
+                break;
+            };
+            self.stats.received += 1;
+            let msg = match self.pending.back_mut() {
+                Some((last, count)) => match (self.merge)(last, msg) {
+                    None => {
+                        *count += 1;
+                        self.stats.coalesced += 1;
+                        continue;
+                    }
+                    Some(msg) => msg,
+                },
+                None => msg,
+            };
+            self.pending.push_back((msg, 1));
+        }
+    }
+
+    fn end_frame(&mut self, rx: &mpsc::Receiver<T>) {
+        self.deadline = None;
+        let backlog = self.pending.len() + rx.len();
+        self.stats.backlog = backlog;
+        self.stats.max_backlog = self.stats.max_backlog.max(backlog);
+        if backlog > 0 {
+            self.stats.lagged_frames += 1;
+        }
+    }
+
+    /// Note for the Log view when the last frame left messages waiting,
+    /// at most once per LAG_REPORT_INTERVAL
+    pub fn lag_report(&mut self) -> Option<String> {
+        if self.stats.backlog == 0 {
+            return None;
+        }
+        let now = Instant::now();
+        if self
+            .last_report
+            .is_some_and(|t| now.duration_since(t) < LAG_REPORT_INTERVAL)
+        {
+            return None;
+        }
+        self.last_report = Some(now);
+        Some(format!(
+            "UI channel lagging: {} messages waiting after a {}ms frame (peak {}, {} lagged frames)",
+            self.stats.backlog,
+            self.budget.as_millis(),
+            self.stats.max_backlog,
+            self.stats.lagged_frames
+        ))
+    }
+}
+
 /// Throttle mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ThrottleMode {
@@ -372,6 +518,104 @@ mod tests {
         assert!(sample.mem_percent >= 0.0);
     }
 
+    #[derive(Debug, PartialEq)]
+    enum Msg {
+        Token(String),
+        Done,
+    }
+
+    fn merge_tokens(last: &mut Msg, next: Msg) -> Option<Msg> {
+        match (last, next) {
+            (Msg::Token(text), Msg::Token(more)) => {
+                text.push_str(&more);
+                None
+            }
+            (_, next) => Some(next),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_frame_drain_coalesces_token_burst() {
+        const TOKENS: usize = 20_000;
+        let (tx, mut rx) = mpsc::channel::<Msg>(64);
+        let producer = tokio::spawn(async move {
+            for _ in 0..TOKENS {
+                tx.send(Msg::Token("t".into())).await.unwrap();
+            }
+            tx.send(Msg::Done).await.unwrap();
+        });
+
+        let mut drain = FrameDrain::new(Duration::from_millis(5), merge_tokens);
+        let (mut text, mut tokens, mut handled) = (String::new(), 0, 0);
+        let mut slowest = Duration::ZERO;
+        let started = Instant::now();
+        'frames: loop {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "burst never drained"
+            );
+            let frame = Instant::now();
+            drain.begin();
+            while let Some((msg, count)) = drain.next(&mut rx) {
+                handled += 1;
+                match msg {
+                    Msg::Token(t) => {
+                        text.push_str(&t);
+                        tokens += count;
+                    }
+                    Msg::Done => break 'frames,
+                }
+            }
+            slowest = slowest.max(frame.elapsed());
+            tokio::task::yield_now().await;
+        }
+        producer.await.unwrap();
+
+        // Nothing lost, far fewer messages handled than sent
+        assert_eq!(tokens, TOKENS);
+        assert_eq!(text.len(), TOKENS);
+        assert_eq!(drain.stats.received as usize, TOKENS + 1);
+        assert!(handled < TOKENS / 10, "{} messages handled", handled);
+        assert_eq!(drain.stats.coalesced as usize, TOKENS + 1 - handled);
+        // No frame waits on the whole burst
+        assert!(
+            slowest < Duration::from_millis(500),
+            "slowest frame {:?}",
+            slowest
+        );
+    }
+
+    #[test]
+    fn test_frame_drain_budget_leaves_backlog() {
+        let (tx, mut rx) = mpsc::channel::<Msg>(16);
+        for _ in 0..3 {
+            tx.try_send(Msg::Done).unwrap();
+        }
+        tx.try_send(Msg::Token("a".into())).unwrap();
+        tx.try_send(Msg::Token("b".into())).unwrap();
+
+        // A spent budget still lets one message through per frame
+        let mut drain = FrameDrain::new(Duration::ZERO, merge_tokens);
+        drain.begin();
+        assert_eq!(drain.next(&mut rx), Some((Msg::Done, 1)));
+        assert_eq!(drain.next(&mut rx), None);
+        assert_eq!(drain.stats.backlog, 3);
+        assert_eq!(drain.stats.lagged_frames, 1);
+        let report = drain.lag_report().unwrap();
+        assert!(report.contains("3 messages waiting"));
+        assert_eq!(drain.lag_report(), None);
+
+        drain.budget = Duration::from_secs(1);
+        drain.begin();
+        assert_eq!(drain.next(&mut rx), Some((Msg::Done, 1)));
+        assert_eq!(drain.next(&mut rx), Some((Msg::Done, 1)));
+        assert_eq!(drain.next(&mut rx), Some((Msg::Token("ab".into()), 2)));
+        assert_eq!(drain.next(&mut rx), None);
+        assert_eq!(drain.stats.backlog, 0);
+        assert_eq!(drain.stats.max_backlog, 3);
+        assert_eq!(drain.stats.lagged_frames, 1);
+    }
+
     #[test]
     fn test_throttle_mode() {
         assert_eq!(ThrottleMode::Normal.delay_multiplier(), 1.0);
@@ -2,8 +2,9 @@
 //!
 //! Provides detection, terminal info, and window management when running inside tmux.
 
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock};
 
 /// Store original window name for restoration on exit
 static ORIGINAL_WINDOW_NAME: OnceLock<String> = OnceLock::new();
@@ -292,6 +293,85 @@ pub fn popup_text(title: &str, text: &str) -> bool {
     popup(title, &cmd, 80, 80)
 }
 
+// ═══════════════════════════════════════════════════════════════
+// TOOL OUTPUT MIRROR
+// ═══════════════════════════════════════════════════════════════
+
+/// Pane tailing the tool-output log, once it has been split
+static MIRROR_PANE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Height of the mirror pane as a percentage of the window
+const MIRROR_PANE_PERCENT: u8 = 30;
+
+/// Log file that live tool output is appended to for the mirror pane
+pub fn mirror_log_path() -> PathBuf {
+    std::env::temp_dir().join(format!("hyle-tools-{}.log", std::process::id()))
+}
+
+/// tmux args that split a background pane tailing `log` and print its pane id
+pub fn mirror_pane_args(log: &Path, size_percent: u8) -> Vec<String> {
+    let quoted = log.display().to_string().replace('\'', "'\\''");
+    vec![
+        "split-window".into(),
+        "-d".into(),
+        "-v".into(),
+        "-p".into(),
+        size_percent.min(90).to_string(),
+        "-P".into(),
+        "-F".into(),
+        "#{pane_id}".into(),
+        format!("tail -n +1 -F '{}'", quoted),
+    ]
+}
+
+/// Pane showing live tool output, split on first use (or after the user
+/// closed it). None outside tmux.
+pub fn ensure_mirror_pane(log: &Path) -> Option<String> {
+    let mut pane = MIRROR_PANE.lock().ok()?;
+    ensure_pane(is_tmux(), &mut pane, log, tmux_output)
+}
+
+fn ensure_pane<F>(
+    in_tmux: bool,
+    pane: &mut Option<String>,
+    log: &Path,
+    mut tmux: F,
+) -> Option<String>
+where
+    F: FnMut(&[String]) -> Option<String>,
+{
+    if !in_tmux {
+        return None;
+    }
+    if let Some(id) = pane.as_ref() {
+        let probe = ["display-message", "-t", id, "-p", "#{pane_id}"].map(String::from);
+        if tmux(&probe).is_some() {
+            return Some(id.clone());
+        }
+    }
+    *pane = tmux(&mirror_pane_args(log, MIRROR_PANE_PERCENT)).filter(|id| !id.is_empty());
+    pane.clone()
+}
+
+/// Close the mirror pane and remove its log
+pub fn close_mirror_pane() {
+    let pane = MIRROR_PANE.lock().ok().and_then(|mut p| p.take());
+    if let Some(id) = pane {
+        let _ = Command::new("tmux").args(["kill-pane", "-t", &id]).output();
+    }
+    let _ = std::fs::remove_file(mirror_log_path());
+}
+
+/// Run tmux and return its trimmed stdout on success
+fn tmux_output(args: &[String]) -> Option<String> {
+    Command::new("tmux")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+}
+
 // ═══════════════════════════════════════════════════════════════
 // SESSION INFO
 // ═══════════════════════════════════════════════════════════════
@@ -428,6 +508,7 @@ pub fn cleanup() {
     }
 
     restore_window_name();
+    close_mirror_pane();
 
     // Clear hyle env vars
     let _ = Command::new("tmux")
@@ -485,4 +566,68 @@ mod tests {
         // Just test it doesn't panic
         let _ = rename_window("test");
     }
+
+    #[test]
+    fn test_mirror_pane_args() {
+        let args = mirror_pane_args(Path::new("/tmp/it's.log"), 95);
+        assert_eq!(
+            args,
+            vec![
+                "split-window",
+                "-d",
+                "-v",
+                "-p",
+                "90",
+                "-P",
+                "-F",
+                "#{pane_id}",
+                "tail -n +1 -F '/tmp/it'\\''s.log'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mirror_pane_split_once() {
+        let log = Path::new("/tmp/hyle-tools.log");
+        let mut pane = None;
+        let mut calls: Vec<String> = Vec::new();
+        let mut tmux = |args: &[String]| {
+            calls.push(args[0].clone());
+            Some("%7".to_string())
+        };
+
+        // First use splits; later uses only check the pane is still there
+        assert_eq!(
+            ensure_pane(true, &mut pane, log, &mut tmux),
+            Some("%7".into())
+        );
+        assert_eq!(
+            ensure_pane(true, &mut pane, log, &mut tmux),
+            Some("%7".into())
+        );
+        assert_eq!(calls, vec!["split-window", "display-message"]);
+
+        // A pane the user closed is re-split
+        let mut resplit = |args: &[String]| match args[0].as_str() {
+            "display-message" => None,
+            _ => Some("%9".to_string()),
+        };
+        assert_eq!(
+            ensure_pane(true, &mut pane, log, &mut resplit),
+            Some("%9".into())
+        );
+    }
+
+    #[test]
+    fn test_mirror_pane_noop_outside_tmux() {
+        let mut pane = None;
+        let mut called = false;
+        let result = ensure_pane(false, &mut pane, Path::new("/tmp/x.log"), |_| {
+            called = true;
+            Some("%1".to_string())
+        });
+        assert_eq!(result, None);
+        assert!(pane.is_none());
+        assert!(!called);
+    }
 }
@@ -11,13 +11,16 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use similar::TextDiff;
 use std::fs;
-use std::path::Path;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicUsize, Ordering},
     Arc, Mutex,
 };
 use std::time::{Duration, Instant};
 
+use crate::config::{EnvPolicy, InjectionPolicy};
+
 // ═══════════════════════════════════════════════════════════════
 // TOOL CALL INFRASTRUCTURE
 // ═══════════════════════════════════════════════════════════════
@@ -144,11 +147,12 @@ impl ToolCall {
         if let Some(path) = self.args.get("path").and_then(|v| v.as_str()) {
             return path.to_string();
         }
-        if let Some(cmd) = self.args.get("command").and_then(|v| v.as_str()) {
+        if let Ok(inv) = BashInvocation::from_args(&self.args) {
+            let cmd = inv.display();
             let truncated = if cmd.len() > 40 {
                 format!("{}...", &cmd[..40])
             } else {
-                cmd.to_string()
+                cmd
             };
             return truncated;
         }
@@ -270,6 +274,42 @@ impl<'a> ToolCallDisplay<'a> {
 }
 
 /// Track multiple concurrent tool calls
+/// Aggregate over one tool's finished calls
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolMetrics {
+    pub name: String,
+    pub calls: usize,
+    pub succeeded: usize,
+    pub total_time: Duration,
+}
+
+impl ToolMetrics {
+    pub fn success_rate(&self) -> f64 {
+        if self.calls == 0 {
+            return 0.0;
+        }
+        self.succeeded as f64 / self.calls as f64
+    }
+
+    pub fn avg_duration(&self) -> Duration {
+        if self.calls == 0 {
+            return Duration::ZERO;
+        }
+        self.total_time / self.calls as u32
+    }
+
+    /// e.g. "bash ×12, 83% ok, avg 1.4s"
+    pub fn render(&self) -> String {
+        format!(
+            "{} ×{}, {:.0}% ok, avg {:.1}s",
+            self.name,
+            self.calls,
+            self.success_rate() * 100.0,
+            self.avg_duration().as_secs_f64()
+        )
+    }
+}
+
 pub struct ToolCallTracker {
     calls: Vec<ToolCall>,
     max_concurrent: usize,
@@ -327,6 +367,34 @@ impl ToolCallTracker {
         self.calls.iter().filter(|c| c.is_running()).count()
     }
 
+    /// Per-tool counts, success rates and durations over finished calls,
+    /// most used first
+    pub fn metrics(&self) -> Vec<ToolMetrics> {
+        let mut metrics: Vec<ToolMetrics> = Vec::new();
+        for call in self.finished() {
+            let i = match metrics.iter().position(|m| m.name == call.name) {
+                Some(i) => i,
+                None => {
+                    metrics.push(ToolMetrics {
+                        name: call.name.clone(),
+                        calls: 0,
+                        succeeded: 0,
+                        total_time: Duration::ZERO,
+                    });
+                    metrics.len() - 1
+                }
+            };
+            let m = &mut metrics[i];
+            m.calls += 1;
+            if call.status == ToolCallStatus::Done {
+                m.succeeded += 1;
+            }
+            m.total_time += call.elapsed().unwrap_or_default();
+        }
+        metrics.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.name.cmp(&b.name)));
+        metrics
+    }
+
     /// Prune old finished calls
     fn prune(&mut self) {
         if self.calls.len() > self.max_history {
@@ -396,6 +464,20 @@ pub struct ToolExecutor {
     files_read: std::collections::HashSet<String>,
     /// File snapshots for rollback
     file_snapshots: std::collections::HashMap<String, Vec<u8>>,
+    /// Environment policy for bash subprocesses
+    env_policy: EnvPolicy,
+    /// Upper bound on any bash call's timeout
+    max_timeout_ms: u64,
+    /// Log that live bash output is copied to for the tmux mirror pane
+    mirror_log: Option<PathBuf>,
+    /// Lockfiles and build output that write/patch refuse to touch
+    protected_paths: crate::config::ProtectedPaths,
+    /// Directory relative paths and bash run in; None is the process cwd
+    work_dir: Option<PathBuf>,
+    /// Where chunked reads of large files continue from
+    read_cursors: ReadCursors,
+    /// Keeps each version write/patch produce under the work dir; None stores nothing
+    artifacts: Option<crate::artifacts::ArtifactStore>,
 }
 
 impl Default for ToolExecutor {
@@ -411,9 +493,124 @@ impl ToolExecutor {
             active_contract: None,
             files_read: std::collections::HashSet::new(),
             file_snapshots: std::collections::HashMap::new(),
+            env_policy: EnvPolicy::default(),
+            max_timeout_ms: crate::config::DEFAULT_MAX_COMMAND_TIMEOUT_MS,
+            mirror_log: None,
+            protected_paths: crate::config::ProtectedPaths::default(),
+            work_dir: None,
+            read_cursors: ReadCursors::default(),
+            artifacts: None,
+        }
+    }
+
+    /// Apply tool settings (environment policy, timeout cap, tmux mirror,
+    /// protected paths) from config
+    pub fn from_config(cfg: &crate::config::Config) -> Self {
+        let mirror = (cfg.tmux_mirror && crate::tmux::is_tmux()).then(crate::tmux::mirror_log_path);
+        Self::new()
+            .with_env_policy(cfg.env.clone())
+            .with_max_timeout(
+                cfg.max_command_timeout_ms
+                    .unwrap_or(crate::config::DEFAULT_MAX_COMMAND_TIMEOUT_MS),
+            )
+            .with_mirror(mirror)
+            .with_protected_paths(cfg.protected_paths.clone())
+    }
+
+    /// Resolve tool paths and run bash in `dir` instead of the process cwd
+    pub fn with_work_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.work_dir = Some(dir.into());
+        self
+    }
+
+    /// Point tools at `dir`, e.g. after `/cd` in the TUI
+    pub fn set_work_dir(&mut self, dir: PathBuf) {
+        self.work_dir = Some(dir);
+    }
+
+    /// Directory tools operate in
+    pub fn work_dir(&self) -> PathBuf {
+        self.work_dir
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    /// `path` as the tools see it: relative paths are under the work dir
+    fn resolve(&self, path: &str) -> PathBuf {
+        match &self.work_dir {
+            Some(dir) if path == "." => dir.clone(),
+            Some(dir) if Path::new(path).is_relative() => dir.join(path),
+            _ => PathBuf::from(path),
+        }
+    }
+
+    /// `path` shown relative to the work dir when it lies inside it
+    fn display_path(&self, path: &Path) -> String {
+        match &self.work_dir {
+            Some(dir) => path.strip_prefix(dir).unwrap_or(path).display().to_string(),
+            None => path.display().to_string(),
         }
     }
 
+    /// Store each file write/patch produce in `store`, so earlier versions
+    /// stay recoverable; only files under the work dir are kept
+    pub fn with_artifacts(mut self, store: crate::artifacts::ArtifactStore) -> Self {
+        self.artifacts = Some(store);
+        self
+    }
+
+    /// Where written files are versioned, if anywhere
+    pub fn artifacts(&self) -> Option<&crate::artifacts::ArtifactStore> {
+        self.artifacts.as_ref()
+    }
+
+    /// Refuse write/patch on paths matching these patterns
+    pub fn with_protected_paths(mut self, protected: crate::config::ProtectedPaths) -> Self {
+        self.protected_paths = protected;
+        self
+    }
+
+    /// Paths write/patch refuse
+    pub fn protected_paths(&self) -> &crate::config::ProtectedPaths {
+        &self.protected_paths
+    }
+
+    /// Continue chunked reads from `cursors`, e.g. the session's, shared
+    /// with executors made for earlier tool batches
+    pub fn with_read_cursors(mut self, cursors: ReadCursors) -> Self {
+        self.read_cursors = cursors;
+        self
+    }
+
+    /// Chunked-read positions of this executor
+    pub fn read_cursors(&self) -> &ReadCursors {
+        &self.read_cursors
+    }
+
+    /// Copy live bash output to `log`, shown in a tmux pane split on demand
+    pub fn with_mirror(mut self, log: Option<PathBuf>) -> Self {
+        self.mirror_log = log;
+        self
+    }
+
+    /// Log that bash output is mirrored to, if any
+    pub fn mirror_log(&self) -> Option<&Path> {
+        self.mirror_log.as_deref()
+    }
+
+    /// Cap every bash call's timeout at `ms`
+    pub fn with_max_timeout(mut self, ms: u64) -> Self {
+        self.max_timeout_ms = ms;
+        self
+    }
+
+    /// Use a specific environment policy for bash subprocesses
+    pub fn with_env_policy(mut self, policy: EnvPolicy) -> Self {
+        self.env_policy = policy;
+        self
+    }
+
     /// Set an active contract to govern tool execution
     pub fn with_contract(mut self, contract: Contract) -> Self {
         self.active_contract = Some(contract);
@@ -447,16 +644,44 @@ impl ToolExecutor {
 
     /// Snapshot a file for potential rollback
     fn snapshot_file(&mut self, path: &str) -> Result<()> {
-        if let Ok(content) = fs::read(path) {
+        if let Ok(content) = fs::read(self.resolve(path)) {
             self.file_snapshots.insert(path.to_string(), content);
         }
         Ok(())
     }
 
+    /// Keep the file a write/patch left behind as a new artifact version;
+    /// failing to store is logged, never fails the tool
+    fn store_artifact(&self, call: &ToolCall) {
+        let (Some(store), Some(root)) = (&self.artifacts, &self.work_dir) else {
+            return;
+        };
+        let Some(path) = call.args.get("path").and_then(|v| v.as_str()) else {
+            return;
+        };
+        let resolved = self.resolve(path);
+        let Ok(name) = resolved.strip_prefix(root) else {
+            return;
+        };
+        // Only plain names under the root, so a restore can't write elsewhere
+        if !name
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)))
+        {
+            return;
+        }
+        if let Err(e) = store.store_file(root, &name.to_string_lossy()) {
+            crate::logfile::LogEvent::warn(module_path!(), "could not store artifact")
+                .field("path", path)
+                .field("error", e.to_string())
+                .emit();
+        }
+    }
+
     /// Rollback a file to its snapshot
     pub fn rollback_file(&self, path: &str) -> Result<bool> {
         if let Some(content) = self.file_snapshots.get(path) {
-            fs::write(path, content)?;
+            fs::write(self.resolve(path), content)?;
             Ok(true)
         } else {
             Ok(false)
@@ -467,7 +692,7 @@ impl ToolExecutor {
     pub fn rollback_all(&self) -> Result<usize> {
         let mut count = 0;
         for (path, content) in &self.file_snapshots {
-            if fs::write(path, content).is_ok() {
+            if fs::write(self.resolve(path), content).is_ok() {
                 count += 1;
             }
         }
@@ -476,11 +701,42 @@ impl ToolExecutor {
 
     /// Check pre-conditions before tool execution
     fn check_preconditions(&mut self, call: &ToolCall) -> Result<()> {
+        // Generated files: lockfiles and build output
+        if matches!(
+            call.name.as_str(),
+            "write" | "patch" | "diff" | "edit" | "move" | "delete"
+        ) {
+            let forced = call
+                .args
+                .get("allow_protected")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let paths = ["path", "from", "to"]
+                .iter()
+                .filter_map(|key| call.args.get(*key).and_then(|v| v.as_str()));
+            for path in paths {
+                let pattern = self
+                    .protected_paths
+                    .matching_in(&self.work_dir(), path)
+                    .filter(|_| !forced);
+                if let Some(pattern) = pattern {
+                    return Err(anyhow::anyhow!(
+                        "{} is a generated file (matches protected pattern `{}`); \
+                         regenerate it with its tool (e.g. cargo update, npm install, a rebuild) \
+                         instead of editing it. If a hand edit is really intended, repeat the \
+                         call with \"allow_protected\": true",
+                        path,
+                        pattern
+                    ));
+                }
+            }
+        }
+
         // Check read-before-write for write/patch operations
         if call.name == "write" || call.name == "patch" {
             if let Some(path) = call.args.get("path").and_then(|v| v.as_str()) {
                 // Only enforce if file exists (new files are OK)
-                if Path::new(path).exists() && !self.was_file_read(path) {
+                if self.resolve(path).exists() && !self.was_file_read(path) {
                     // Check if contract has read-before-write obligation
                     if let Some(contract) = &self.active_contract {
                         for obligation in &contract.intent.preconditions {
@@ -511,7 +767,8 @@ impl ToolExecutor {
                 match &invariant.condition {
                     InvariantCondition::NoDestructiveCommands => {
                         if call.name == "bash" {
-                            if let Some(cmd) = call.args.get("command").and_then(|v| v.as_str()) {
+                            if let Ok(inv) = BashInvocation::from_args(&call.args) {
+                                let cmd = inv.display();
                                 // The BLOCKED_PATTERNS check already happened in exec_bash
                                 // This is a secondary check at contract level
                                 if cmd.contains("rm -rf") || cmd.contains("rm -r ") {
@@ -523,7 +780,7 @@ impl ToolExecutor {
                         }
                     }
                     InvariantCondition::FileExists(path) => {
-                        if !Path::new(path).exists() {
+                        if !self.resolve(path).exists() {
                             return Err(anyhow::anyhow!(
                                 "Invariant violation: required file {} was deleted",
                                 path
@@ -559,10 +816,12 @@ impl ToolExecutor {
         }
 
         // Track files touched for rollback
-        if call.name == "write" || call.name == "patch" {
-            if let Some(path) = call.args.get("path").and_then(|v| v.as_str()) {
-                if let Some(contract) = &mut self.active_contract {
-                    contract.touch_file(path);
+        if matches!(call.name.as_str(), "write" | "patch" | "move" | "delete") {
+            for key in ["path", "from", "to"] {
+                if let Some(path) = call.args.get(key).and_then(|v| v.as_str()) {
+                    if let Some(contract) = &mut self.active_contract {
+                        contract.touch_file(path);
+                    }
                 }
             }
         }
@@ -570,6 +829,23 @@ impl ToolExecutor {
 
     /// Execute a tool call with contract enforcement
     pub fn execute(&mut self, call: &mut ToolCall) -> Result<()> {
+        let span = crate::otel::start("execute_tool");
+        let result = self.execute_checked(call);
+        if let Some(mut span) = span {
+            crate::otel::record_tool(&mut span, call);
+            span.end(result.as_ref().err().map(|e| e.to_string()).as_deref());
+        }
+        if let Err(e) = &result {
+            crate::logfile::LogEvent::error(module_path!(), "tool failed")
+                .field("tool", &call.name)
+                .field("id", &call.id)
+                .field("error", e.to_string())
+                .emit();
+        }
+        result
+    }
+
+    fn execute_checked(&mut self, call: &mut ToolCall) -> Result<()> {
         let kill = Arc::new(AtomicBool::new(false));
         self.kill_signals.insert(call.id.clone(), kill.clone());
 
@@ -594,14 +870,26 @@ impl ToolExecutor {
             "write" => self.exec_write(call),
             "glob" => self.exec_glob(call),
             "grep" => self.exec_grep(call),
+            "tree" => self.exec_tree(call),
+            "stats" => self.exec_stats(call),
+            "docs" => self.exec_docs(call),
+            "environ" => self.exec_environ(call),
             "bash" => self.exec_bash(call, kill),
             "patch" | "diff" => self.exec_patch(call),
+            "move" => self.exec_move(call),
+            "delete" => self.exec_delete(call),
+            "ask_user" => Err(anyhow::anyhow!(
+                "ask_user needs someone to answer; it is handled by the agent loop"
+            )),
             _ => Err(anyhow::anyhow!("Unknown tool: {}", call.name)),
         };
 
         // Update contract state based on execution
         if result.is_ok() {
             self.update_contract_state(call);
+            if call.name == "write" || call.name == "patch" {
+                self.store_artifact(call);
+            }
         }
 
         // Post-execution: check invariants
@@ -635,8 +923,29 @@ impl ToolExecutor {
             .get("path")
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("read: missing 'path' argument"))?;
-
-        let content = read_file(Path::new(path))?;
+        let start = call.args.get("start_line").and_then(|v| v.as_u64());
+        let end = call.args.get("end_line").and_then(|v| v.as_u64());
+        // Accepted as a string or a number
+        let cursor = call.args.get("cursor").and_then(|v| match v {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        });
+
+        let path = self.resolve(path);
+        let content = match (start, end) {
+            (None, None) if cursor.is_some() || is_large_file(&path) => {
+                let cursors = &self.read_cursors;
+                let chunk = cursors.read_chunk(&path, cursor.as_deref(), READ_CHUNK_BYTES)?;
+                format!("{}{}", chunk.text, chunk.footer())
+            }
+            (None, None) => read_file(&path)?,
+            (start, end) => {
+                let start = start.unwrap_or(1) as usize;
+                let end = end.map(|e| e as usize).unwrap_or(usize::MAX);
+                read_file_range(&path, start, end)?.text
+            }
+        };
         call.append_output(&content);
         Ok(())
     }
@@ -657,10 +966,21 @@ impl ToolExecutor {
             .ok_or_else(|| anyhow::anyhow!("write: missing 'content' argument"))?
             .to_string();
 
-        let path = Path::new(&path_str);
+        let path = self.resolve(&path_str);
+        // Overwrites report a diff so the agent can check the edit it made
+        let original = fs::read_to_string(&path).ok();
 
         // Use atomic write for reliability
-        atomic_write_file(path, &content, call)?;
+        atomic_write_file(&path, &content, call)?;
+
+        match original {
+            Some(original) if original == content => call.append_output("No changes\n"),
+            Some(original) => {
+                let preview = preview_changes(&original, &content, &path_str);
+                call.append_output(&format!("Preview:\n{}\n", preview));
+            }
+            None => {}
+        }
 
         Ok(())
     }
@@ -672,12 +992,18 @@ impl ToolExecutor {
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("glob: missing 'pattern' argument"))?;
 
-        for entry in glob::glob(pattern)? {
-            match entry {
-                Ok(path) => call.append_output(&format!("{}\n", path.display())),
-                Err(e) => call.append_output(&format!("Error: {}\n", e)),
-            }
-        }
+        let pattern = self.resolve(pattern);
+        let mut results: Vec<String> = glob::glob(&pattern.to_string_lossy())?
+            .map(|entry| match entry {
+                Ok(path) => self.display_path(&path),
+                Err(e) => format!("Error: {}", e),
+            })
+            .collect();
+        // Sorted so offsets are stable between calls
+        results.sort();
+
+        let (offset, limit) = page_args(&call.args);
+        call.append_output(&paginate(&results, offset, limit));
         Ok(())
     }
 
@@ -692,16 +1018,149 @@ impl ToolExecutor {
             .args
             .get("path")
             .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("grep: missing 'path' argument"))?;
+            .unwrap_or(".");
+
+        // Path then line order, so offsets are stable between calls
+        let resolved = self.resolve(path);
+        let results: Vec<String> = if resolved.is_dir() {
+            grep_dir(pattern, &resolved)?
+                .into_iter()
+                .map(|mut m| {
+                    m.path = self.display_path(Path::new(&m.path));
+                    m.render()
+                })
+                .collect()
+        } else {
+            let content = fs::read_to_string(&resolved)?;
+            let regex = regex::Regex::new(pattern)?;
+            content
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| regex.is_match(line))
+                .map(|(i, line)| format!("{}:{}: {}", path, i + 1, line))
+                .collect()
+        };
+
+        let (offset, limit) = page_args(&call.args);
+        call.append_output(&paginate(&results, offset, limit));
+        Ok(())
+    }
+
+    fn exec_tree(&self, call: &mut ToolCall) -> Result<()> {
+        let path = call
+            .args
+            .get("path")
+            .and_then(|v| v.as_str())
+            .unwrap_or(".");
+
+        let defaults = TreeOptions::default();
+        let opts = TreeOptions {
+            max_depth: call
+                .args
+                .get("max_depth")
+                .and_then(|v| v.as_u64())
+                .map(|d| d as usize)
+                .unwrap_or(defaults.max_depth),
+            show_hidden: call
+                .args
+                .get("show_hidden")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(defaults.show_hidden),
+            ..defaults
+        };
+
+        let tree = render_tree(&self.resolve(path), &opts)?;
+        call.append_output(&tree);
+        Ok(())
+    }
+
+    fn exec_stats(&self, call: &mut ToolCall) -> Result<()> {
+        let subpath = call.args.get("path").and_then(|v| v.as_str());
+        let stats = crate::project::repo_stats(&self.work_dir(), subpath)?;
+        call.append_output(&serde_json::to_string_pretty(&stats)?);
+        Ok(())
+    }
+
+    fn exec_docs(&self, call: &mut ToolCall) -> Result<()> {
+        let query = call
+            .args
+            .get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("docs: missing 'query' argument"))?
+            .to_string();
+        let limit = call
+            .args
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(crate::docs::DOCS_DEFAULT_LIMIT);
+
+        let index = crate::docs::DocsIndex::load_or_build(&self.work_dir());
+        let hits = index.search(&query, limit);
+        // The result envelope guards tool output as a whole
+        call.append_output(&crate::docs::format_hits(
+            &query,
+            &hits,
+            InjectionPolicy::Off,
+        ));
+        Ok(())
+    }
 
-        let content = fs::read_to_string(path)?;
-        let regex = regex::Regex::new(pattern)?;
+    fn exec_environ(&self, call: &mut ToolCall) -> Result<()> {
+        let snapshot = crate::environ::snapshot();
+        call.append_output(&serde_json::to_string_pretty(snapshot)?);
+        Ok(())
+    }
 
-        for (i, line) in content.lines().enumerate() {
-            if regex.is_match(line) {
-                call.append_output(&format!("{}:{}: {}\n", path, i + 1, line));
+    fn exec_move(&self, call: &mut ToolCall) -> Result<()> {
+        let arg = |key: &str| {
+            call.args
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .ok_or_else(|| anyhow::anyhow!("move: missing '{}' argument", key))
+        };
+        let (from, to) = (arg("from")?, arg("to")?);
+        let force = call
+            .args
+            .get("force")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let root = self.work_dir();
+        for path in [&from, &to] {
+            if !resolves_within(&root, Path::new(path)) {
+                anyhow::bail!("move: {} is outside {}", path, root.display());
             }
         }
+        let method = move_path(&root, &self.resolve(&from), &self.resolve(&to), force)?;
+        call.append_output(&format!("Moved {} -> {} ({})\n", from, to, method));
+        Ok(())
+    }
+
+    fn exec_delete(&self, call: &mut ToolCall) -> Result<()> {
+        let path = call
+            .args
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("delete: missing 'path' argument"))?
+            .to_string();
+        let recursive = call
+            .args
+            .get("recursive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let root = self.work_dir();
+        if !resolves_within(&root, Path::new(&path)) {
+            anyhow::bail!("delete: {} is outside {}", path, root.display());
+        }
+        let entry = trash_path(&root, &self.resolve(&path), recursive)?;
+        call.append_output(&format!(
+            "Moved {} to {} (restore with /undo)\n",
+            path,
+            self.display_path(&entry)
+        ));
         Ok(())
     }
 
@@ -723,11 +1182,11 @@ impl ToolExecutor {
             .ok_or_else(|| anyhow::anyhow!("patch: missing 'diff' or 'patch' argument"))?
             .to_string();
 
-        let path = Path::new(&path_str);
+        let path = self.resolve(&path_str);
 
         // Read original content
         let original = if path.exists() {
-            fs::read_to_string(path)
+            fs::read_to_string(&path)
                 .with_context(|| format!("Failed to read {}", path.display()))?
         } else {
             // New file - start from empty
@@ -735,30 +1194,32 @@ impl ToolExecutor {
         };
 
         // Apply the patch
+        let format = DiffFormat::detect(&diff);
         let patched = apply_patch(&original, &diff)?;
 
         // Warn if diff was treated as full replacement (non-unified format)
-        let is_unified = diff.contains("@@");
-        if !is_unified && !original.is_empty() {
-            call.append_output("⚠ Warning: Input treated as full file replacement (not a unified diff)\n");
+        if format == DiffFormat::Replacement && !original.is_empty() {
+            call.append_output(
+                "⚠ Warning: Input treated as full file replacement (not a unified diff)\n",
+            );
+        }
+        if format == DiffFormat::Git {
+            call.append_output("Applied with git apply (git-style diff)\n");
         }
 
         // Preview the change
-        let preview = preview_changes(&original, &patched, &path.display().to_string());
+        let preview = preview_changes(&original, &patched, &path_str);
         call.append_output(&format!("Preview:\n{}\n", preview));
 
         // Use atomic write for reliability (handles backup, sync, verify)
-        atomic_write_file(path, &patched, call)?;
+        atomic_write_file(&path, &patched, call)?;
 
         Ok(())
     }
 
     fn exec_bash(&self, call: &mut ToolCall, kill: Arc<AtomicBool>) -> Result<()> {
-        let command = call
-            .args
-            .get("command")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("bash: missing 'command' argument"))?;
+        let invocation = BashInvocation::from_args(&call.args)?;
+        let command = invocation.display();
 
         // ═══════════════════════════════════════════════════════════════
         // SAFETY GUARD: Block destructive commands
@@ -800,37 +1261,54 @@ impl ToolExecutor {
             }
         }
 
-        let timeout_ms = call
-            .args
-            .get("timeout")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(60000);
+        let requested = call.args.get("timeout").and_then(|v| v.as_u64());
+        let (timeout_ms, limit) = clamp_timeout(requested, self.max_timeout_ms);
 
         let start = Instant::now();
-        let mut child = std::process::Command::new("bash")
-            .arg("-c")
-            .arg(command)
+        let mut child = invocation
+            .command()
+            .current_dir(self.work_dir())
+            .env_clear()
+            .envs(self.env_policy.child_env(std::env::vars()))
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
             .spawn()?;
 
+        // Drain pipes while the command runs so output reaches the mirror live
+        let mirror = self.open_mirror(&command);
+        let stdout_reader = child
+            .stdout
+            .take()
+            .map(|pipe| drain_pipe(pipe, mirror.as_ref().and_then(|m| m.try_clone().ok())));
+        let stderr_reader = child.stderr.take().map(|pipe| drain_pipe(pipe, mirror));
+
         // Poll for completion or kill signal
         loop {
+            let elapsed_ms = start.elapsed().as_millis() as u64;
             if kill.load(Ordering::SeqCst) {
-                child.kill()?;
-                return Err(anyhow::anyhow!("Killed by user"));
+                reap(&mut child);
+                return Err(anyhow::anyhow!("Killed by user after {}ms", elapsed_ms));
             }
 
-            if start.elapsed().as_millis() as u64 > timeout_ms {
-                child.kill()?;
-                return Err(anyhow::anyhow!("Timeout after {}ms", timeout_ms));
+            if elapsed_ms > timeout_ms {
+                reap(&mut child);
+                return Err(anyhow::anyhow!(
+                    "Timeout: {} of {}ms fired, process killed after running {}ms",
+                    limit.describe(),
+                    timeout_ms,
+                    elapsed_ms
+                ));
             }
 
             match child.try_wait()? {
                 Some(status) => {
-                    let output = child.wait_with_output()?;
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    let collect = |reader: Option<std::thread::JoinHandle<Vec<u8>>>| {
+                        reader.and_then(|r| r.join().ok()).unwrap_or_default()
+                    };
+                    let stdout = collect(stdout_reader);
+                    let stderr = collect(stderr_reader);
+                    let stdout = crate::decode::decode_output(&stdout);
+                    let stderr = crate::decode::decode_output(&stderr);
 
                     call.append_output(&stdout);
                     if !stderr.is_empty() {
@@ -850,6 +1328,187 @@ impl ToolExecutor {
     }
 }
 
+impl ToolExecutor {
+    /// Mirror log opened for appending, with the command as a header.
+    /// Splits the tmux pane on first use; None when not mirroring.
+    fn open_mirror(&self, command: &str) -> Option<fs::File> {
+        let log = self.mirror_log.as_ref()?;
+        crate::tmux::ensure_mirror_pane(log)?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log)
+            .ok()?;
+        let _ = writeln!(file, "\n$ {}", command);
+        Some(file)
+    }
+}
+
+/// Read a child pipe to EOF on a thread, copying chunks to `mirror` as they arrive
+fn drain_pipe<R: Read + Send + 'static>(
+    mut pipe: R,
+    mut mirror: Option<fs::File>,
+) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            match pipe.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    output.extend_from_slice(&buf[..n]);
+                    if let Some(file) = mirror.as_mut() {
+                        let _ = file.write_all(&buf[..n]);
+                    }
+                }
+            }
+        }
+        output
+    })
+}
+
+/// Default per-call bash timeout when the call doesn't set one
+pub const DEFAULT_BASH_TIMEOUT_MS: u64 = 60_000;
+
+/// Which limit bounds a bash call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutLimit {
+    /// The call's own `timeout` argument
+    PerCall,
+    /// No `timeout` argument, so the built-in default
+    Default,
+    /// The per-call value exceeded the configured global maximum
+    GlobalMax,
+}
+
+impl TimeoutLimit {
+    fn describe(self) -> &'static str {
+        match self {
+            Self::PerCall => "per-call timeout",
+            Self::Default => "default timeout",
+            Self::GlobalMax => "global max_command_timeout_ms",
+        }
+    }
+}
+
+/// Resolve the effective timeout for a call, clamped to the global maximum
+pub fn clamp_timeout(requested: Option<u64>, max_ms: u64) -> (u64, TimeoutLimit) {
+    let (ms, limit) = match requested {
+        Some(ms) => (ms, TimeoutLimit::PerCall),
+        None => (DEFAULT_BASH_TIMEOUT_MS, TimeoutLimit::Default),
+    };
+    if ms > max_ms {
+        (max_ms, TimeoutLimit::GlobalMax)
+    } else {
+        (ms, limit)
+    }
+}
+
+/// Kill a child and wait on it so it doesn't linger as a zombie
+fn reap(child: &mut std::process::Child) {
+    // kill() fails if the process already exited; wait() still reaps it
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// How a bash tool call runs: through `bash -c`, or as a program plus
+/// arguments with no shell in between (`argv`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum BashInvocation {
+    Shell(String),
+    Argv(Vec<String>),
+}
+
+impl BashInvocation {
+    /// Parse from tool args; `argv` takes precedence over `command`
+    pub fn from_args(args: &serde_json::Value) -> Result<Self> {
+        if let Some(argv) = args.get("argv") {
+            let argv: Vec<String> = argv
+                .as_array()
+                .ok_or_else(|| anyhow::anyhow!("bash: 'argv' must be an array of strings"))?
+                .iter()
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .ok_or_else(|| anyhow::anyhow!("bash: 'argv' must be an array of strings"))
+                })
+                .collect::<Result<_>>()?;
+            if argv.is_empty() {
+                anyhow::bail!("bash: 'argv' must name a program");
+            }
+            return Ok(Self::Argv(argv));
+        }
+
+        args.get("command")
+            .and_then(|v| v.as_str())
+            .map(|c| Self::Shell(c.to_string()))
+            .ok_or_else(|| anyhow::anyhow!("bash: missing 'command' or 'argv' argument"))
+    }
+
+    /// Command line as a single string, for safety checks and display
+    pub fn display(&self) -> String {
+        match self {
+            Self::Shell(cmd) => cmd.clone(),
+            Self::Argv(argv) => argv.join(" "),
+        }
+    }
+
+    fn command(&self) -> std::process::Command {
+        match self {
+            Self::Shell(cmd) => {
+                let mut c = std::process::Command::new("bash");
+                c.arg("-c").arg(cmd);
+                c
+            }
+            Self::Argv(argv) => {
+                let mut c = std::process::Command::new(&argv[0]);
+                c.args(&argv[1..]);
+                c
+            }
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// RESULT PAGINATION
+// ═══════════════════════════════════════════════════════════════
+
+/// Results glob/grep return per call unless `limit` says otherwise
+pub const DEFAULT_RESULT_LIMIT: usize = 200;
+
+/// `offset`/`limit` tool args, defaulting to the first page
+fn page_args(args: &serde_json::Value) -> (usize, usize) {
+    let get = |key: &str| args.get(key).and_then(|v| v.as_u64()).map(|n| n as usize);
+    let offset = get("offset").unwrap_or(0);
+    let limit = get("limit").unwrap_or(DEFAULT_RESULT_LIMIT).max(1);
+    (offset, limit)
+}
+
+/// One page of `results`, one per line, with a hint when more remain
+pub fn paginate(results: &[String], offset: usize, limit: usize) -> String {
+    let total = results.len();
+    if offset >= total && total > 0 {
+        return format!("[no results at offset={}; {} total]\n", offset, total);
+    }
+
+    let end = offset.saturating_add(limit).min(total);
+    let mut out = String::new();
+    for line in &results[offset..end] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    if end < total {
+        out.push_str(&format!(
+            "[showing {}-{} of {}; call again with offset={}]\n",
+            offset + 1,
+            end,
+            total,
+            end
+        ));
+    }
+    out
+}
+
 // ═══════════════════════════════════════════════════════════════
 // ATOMIC FILE OPERATIONS
 // ═══════════════════════════════════════════════════════════════
@@ -993,51 +1652,1082 @@ fn prune_old_backups(path: &Path, keep: usize) -> Result<()> {
     Ok(())
 }
 
-// ═══════════════════════════════════════════════════════════════
-// FILE OPERATIONS
-// ═══════════════════════════════════════════════════════════════
-
-/// Read a file with line numbers
-pub fn read_file(path: &Path) -> Result<String> {
-    let content =
-        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+/// Whether `path` (relative paths taken from `root`) stays inside `root`
+/// once symlinks and `..` are resolved; parts that don't exist yet count
+pub fn resolves_within(root: &Path, path: &Path) -> bool {
+    use std::path::Component;
+
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let joined = root.join(path);
+    // Resolve symlinks for the part that exists, then normalize the rest
+    let mut existing = joined.as_path();
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => break,
+        }
+    }
+    let base = existing
+        .canonicalize()
+        .unwrap_or_else(|_| existing.to_path_buf());
+
+    let mut resolved = PathBuf::new();
+    for component in base.components().chain(
+        rest.iter()
+            .rev()
+            .map(|name| Component::Normal(name.as_os_str())),
+    ) {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+    resolved.starts_with(&root)
+}
 
-    let mut output = String::new();
-    for (i, line) in content.lines().enumerate() {
-        output.push_str(&format!("{:4}│ {}\n", i + 1, line));
+/// Rename `from` to `to`, creating parent directories; `git mv` when `from`
+/// is tracked in the repo at `root`, so history follows the file. Refuses to
+/// replace an existing `to` unless `force`. Returns how it was moved.
+fn move_path(root: &Path, from: &Path, to: &Path, force: bool) -> Result<&'static str> {
+    if !from.exists() {
+        anyhow::bail!("move: {} does not exist", from.display());
+    }
+    if to.exists() && !force {
+        anyhow::bail!(
+            "move: {} already exists; pass \"force\": true to overwrite it",
+            to.display()
+        );
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+
+    let git = |args: &[&std::ffi::OsStr]| {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(root)
+            .env_remove("GIT_DIR")
+            .env_remove("GIT_WORK_TREE")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    };
+    let tracked = git(&[
+        "ls-files".as_ref(),
+        "--error-unmatch".as_ref(),
+        "--".as_ref(),
+        from.as_os_str(),
+    ]);
+    if tracked {
+        let force_flag = if force { "-f" } else { "-k" };
+        let moved = git(&[
+            "mv".as_ref(),
+            force_flag.as_ref(),
+            "--".as_ref(),
+            from.as_os_str(),
+            to.as_os_str(),
+        ]);
+        // -k skips silently when git can't move it; check it actually moved
+        if moved && !from.exists() {
+            return Ok("git mv");
+        }
     }
 
-    Ok(output)
+    // Same-filesystem renames are atomic; across devices this fails rather
+    // than leaving a half-copied file
+    fs::rename(from, to)
+        .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))?;
+    Ok("renamed")
 }
 
-/// Read multiple files into context string
-pub fn read_files_context(paths: &[&Path]) -> Result<String> {
-    let mut context = String::new();
+// ═══════════════════════════════════════════════════════════════
+// TRASH
+// ═══════════════════════════════════════════════════════════════
 
-    for path in paths {
-        if path.exists() {
-            let content = fs::read_to_string(path)
-                .with_context(|| format!("Failed to read {}", path.display()))?;
-            context.push_str(&format!("\n--- {} ---\n{}\n", path.display(), content));
-        }
+/// Where `delete` moves things, relative to the work dir
+pub const TRASH_DIR: &str = ".hyle/trash";
+
+/// File in each trash entry naming the path it came from
+const TRASH_ORIGIN: &str = ".hyle-origin";
+
+/// Move `target` into a new `<root>/.hyle/trash/<timestamp>/` entry instead
+/// of unlinking it, so `/undo` can put it back. Directories need
+/// `recursive`. Returns the entry directory.
+pub fn trash_path(root: &Path, target: &Path, recursive: bool) -> Result<PathBuf> {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let meta = fs::symlink_metadata(target)
+        .map_err(|_| anyhow::anyhow!("delete: {} does not exist", target.display()))?;
+    if meta.is_dir() && !recursive {
+        anyhow::bail!(
+            "delete: {} is a directory; pass \"recursive\": true to delete it",
+            target.display()
+        );
     }
+    // Where it sits under root, so /undo knows where it goes back
+    let parent = target
+        .parent()
+        .and_then(|p| p.canonicalize().ok())
+        .unwrap_or_default();
+    let name = target
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("delete: refusing to delete {}", target.display()))?;
+    let relative = parent
+        .join(name)
+        .strip_prefix(&root)
+        .map(Path::to_path_buf)
+        .map_err(|_| {
+            anyhow::anyhow!("delete: {} is outside {}", target.display(), root.display())
+        })?;
+    let first = relative
+        .components()
+        .next()
+        .map(|c| c.as_os_str().to_owned());
+    if relative.as_os_str().is_empty()
+        || first.as_deref() == Some(std::ffi::OsStr::new(".git"))
+        || relative.starts_with(TRASH_DIR)
+        || Path::new(TRASH_DIR).starts_with(&relative)
+    {
+        anyhow::bail!("delete: refusing to delete {}", relative.display());
+    }
+
+    let trash = root.join(TRASH_DIR);
+    fs::create_dir_all(&trash)?;
+    // Keeps the trash out of commits
+    let ignore = trash.join(".gitignore");
+    if !ignore.exists() {
+        fs::write(&ignore, "*\n")?;
+    }
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+    let mut entry = trash.join(&stamp);
+    let mut n = 1;
+    while entry.exists() {
+        entry = trash.join(format!("{}-{}", stamp, n));
+        n += 1;
+    }
+    fs::create_dir_all(&entry)?;
+    fs::write(
+        entry.join(TRASH_ORIGIN),
+        relative.to_string_lossy().as_bytes(),
+    )?;
+    if let Err(e) = fs::rename(target, entry.join(name)) {
+        let _ = fs::remove_dir_all(&entry);
+        return Err(e).with_context(|| format!("Failed to move {} to the trash", target.display()));
+    }
+    Ok(entry)
+}
 
-    Ok(context)
+/// Put the most recently trashed path back under `root`; None when the
+/// trash is empty. Refuses if something now exists at the original path.
+pub fn restore_latest(root: &Path) -> Result<Option<String>> {
+    let trash = root.join(TRASH_DIR);
+    let mut entries: Vec<PathBuf> = match fs::read_dir(&trash) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.join(TRASH_ORIGIN).is_file())
+            .collect(),
+        Err(_) => return Ok(None),
+    };
+    // Timestamped names sort by age
+    entries.sort();
+    let Some(entry) = entries.pop() else {
+        return Ok(None);
+    };
+
+    let relative = fs::read_to_string(entry.join(TRASH_ORIGIN))?;
+    let relative = relative.trim();
+    // An edited origin must not put files outside root
+    let origin = Path::new(relative);
+    if origin.as_os_str().is_empty()
+        || !origin
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)))
+    {
+        anyhow::bail!("Bad trash entry {}: origin {:?}", entry.display(), relative);
+    }
+    let dest = root.join(relative);
+    if fs::symlink_metadata(&dest).is_ok() {
+        anyhow::bail!(
+            "{} exists again; move it aside to restore the deleted copy from {}",
+            relative,
+            entry.display()
+        );
+    }
+    let name = dest
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Bad trash entry: {}", entry.display()))?;
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(entry.join(name), &dest)
+        .with_context(|| format!("Failed to restore {}", relative))?;
+    fs::remove_dir_all(&entry)?;
+    Ok(Some(relative.to_string()))
 }
 
-/// Generate a unified diff between two strings
-pub fn generate_diff(original: &str, modified: &str, filename: &str) -> String {
-    let diff = TextDiff::from_lines(original, modified);
+// ═══════════════════════════════════════════════════════════════
+// DIRECTORY TREE
+// ═══════════════════════════════════════════════════════════════
 
-    diff.unified_diff()
-        .context_radius(3)
-        .header(&format!("a/{}", filename), &format!("b/{}", filename))
-        .to_string()
-}
+/// Directories skipped regardless of .gitignore
+const TREE_ALWAYS_IGNORED: &[&str] = &[".git", "target", "node_modules", "__pycache__"];
 
-/// Parse a unified diff and extract hunks
+/// Entries visited while counting files before the counts stop growing
+const TREE_COUNT_CAP: usize = 20_000;
+
+/// Options for `render_tree`
 #[derive(Debug, Clone)]
-pub struct DiffHunk {
+pub struct TreeOptions {
+    pub max_depth: usize,
+    pub show_hidden: bool,
+    /// Stop listing after this many entries
+    pub max_nodes: usize,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            show_hidden: false,
+            max_nodes: 500,
+        }
+    }
+}
+
+/// Render a depth-limited directory tree like `tree -L n`.
+///
+/// Directories show the number of files beneath them (counted past
+/// `max_depth`), so collapsed subtrees still convey their size. Counting
+/// is one walk of at most `TREE_COUNT_CAP` entries; symlinked
+/// directories are listed but never followed.
+pub fn render_tree(root: &Path, opts: &TreeOptions) -> Result<String> {
+    if !root.is_dir() {
+        anyhow::bail!("Not a directory: {}", root.display());
+    }
+
+    let ignores = load_gitignore(root);
+    let mut counts = FileCounts {
+        budget: TREE_COUNT_CAP,
+        ..Default::default()
+    };
+    count_files(root, root, 0, &ignores, opts, &mut counts);
+    let mut out = format!("{}/ ({})\n", root.display(), counts.label(root));
+    let mut nodes = 0;
+    let mut level = TreeLevel {
+        root,
+        opts,
+        ignores: &ignores,
+        counts: &counts,
+        nodes: &mut nodes,
+        out: &mut out,
+    };
+    level.render(root, "", 1);
+
+    if nodes > opts.max_nodes {
+        out.push_str(&format!("[truncated at {} entries]\n", opts.max_nodes));
+    }
+    Ok(out)
+}
+
+/// What one `render_tree` pass shares across levels
+struct TreeLevel<'a> {
+    root: &'a Path,
+    opts: &'a TreeOptions,
+    ignores: &'a [glob::Pattern],
+    counts: &'a FileCounts,
+    nodes: &'a mut usize,
+    out: &'a mut String,
+}
+
+impl TreeLevel<'_> {
+    fn render(&mut self, dir: &Path, prefix: &str, depth: usize) {
+        if depth > self.opts.max_depth {
+            return;
+        }
+
+        let entries = tree_entries(self.root, dir, self.ignores, self.opts);
+        for (i, path) in entries.iter().enumerate() {
+            if *self.nodes >= self.opts.max_nodes {
+                // Overshoot by one so the caller knows entries were dropped
+                *self.nodes = self.opts.max_nodes + 1;
+                return;
+            }
+            *self.nodes += 1;
+
+            let last = i + 1 == entries.len();
+            let branch = if last { "└── " } else { "├── " };
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            if is_real_dir(path) {
+                self.out.push_str(&format!(
+                    "{}{}{}/ ({})\n",
+                    prefix,
+                    branch,
+                    name,
+                    self.counts.label(path)
+                ));
+                let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                self.render(path, &child_prefix, depth + 1);
+            } else if let Ok(target) = fs::read_link(path) {
+                self.out.push_str(&format!(
+                    "{}{}{} -> {}\n",
+                    prefix,
+                    branch,
+                    name,
+                    target.display()
+                ));
+            } else {
+                self.out
+                    .push_str(&format!("{}{}{}\n", prefix, branch, name));
+            }
+        }
+    }
+}
+
+/// A directory itself, not a symlink to one
+fn is_real_dir(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_dir())
+}
+
+/// Visible entries of `dir`: directories first, then files, each sorted by name
+fn tree_entries(
+    root: &Path,
+    dir: &Path,
+    ignores: &[glob::Pattern],
+    opts: &TreeOptions,
+) -> Vec<std::path::PathBuf> {
+    let Ok(read) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut entries: Vec<_> = read
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| !is_tree_ignored(root, p, ignores, opts.show_hidden))
+        .collect();
+    entries.sort_by(|a, b| {
+        b.is_dir()
+            .cmp(&a.is_dir())
+            .then_with(|| a.file_name().cmp(&b.file_name()))
+    });
+    entries
+}
+
+/// Files beneath each directory shown by `render_tree`
+#[derive(Debug, Default)]
+struct FileCounts {
+    by_dir: std::collections::HashMap<PathBuf, usize>,
+    /// Entries left to visit; counts are lower bounds once it runs out
+    budget: usize,
+}
+
+impl FileCounts {
+    fn label(&self, dir: &Path) -> String {
+        let count = self.by_dir.get(dir).copied().unwrap_or(0);
+        if self.budget == 0 {
+            format!("{}+ files", count)
+        } else {
+            format!("{} files", count)
+        }
+    }
+}
+
+/// Count files under `dir` in one walk, recording totals for the
+/// directories within `max_depth`
+fn count_files(
+    root: &Path,
+    dir: &Path,
+    depth: usize,
+    ignores: &[glob::Pattern],
+    opts: &TreeOptions,
+    counts: &mut FileCounts,
+) -> usize {
+    let mut total = 0;
+    for path in tree_entries(root, dir, ignores, opts) {
+        if counts.budget == 0 {
+            break;
+        }
+        counts.budget -= 1;
+        if is_real_dir(&path) {
+            total += count_files(root, &path, depth + 1, ignores, opts, counts);
+        } else {
+            total += 1;
+        }
+    }
+    if depth <= opts.max_depth {
+        counts.by_dir.insert(dir.to_path_buf(), total);
+    }
+    total
+}
+
+fn is_tree_ignored(root: &Path, path: &Path, ignores: &[glob::Pattern], show_hidden: bool) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    if TREE_ALWAYS_IGNORED.contains(&name.as_str()) {
+        return true;
+    }
+    if !show_hidden && name.starts_with('.') {
+        return true;
+    }
+
+    let relative = path
+        .strip_prefix(root)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    ignores
+        .iter()
+        .any(|p| p.matches(&name) || p.matches(&relative))
+}
+
+/// Parse the root `.gitignore` into glob patterns (negations unsupported)
+fn load_gitignore(root: &Path) -> Vec<glob::Pattern> {
+    let Ok(content) = fs::read_to_string(root.join(".gitignore")) else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with('!'))
+        .filter_map(|l| glob::Pattern::new(l.trim_start_matches('/').trim_end_matches('/')).ok())
+        .collect()
+}
+
+/// Paths under `root` that `tree` would show, relative and shallowest
+/// first, directories with a trailing `/`; stops after `limit`
+pub fn project_files(root: &Path, limit: usize) -> Vec<String> {
+    let ignores = load_gitignore(root);
+    let opts = TreeOptions::default();
+
+    let mut files = Vec::new();
+    let mut dirs = std::collections::VecDeque::from([root.to_path_buf()]);
+    while let Some(dir) = dirs.pop_front() {
+        for path in tree_entries(root, &dir, &ignores, &opts) {
+            if files.len() >= limit {
+                return files;
+            }
+            if path.is_symlink() {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().to_string();
+            if path.is_dir() {
+                files.push(format!("{}/", relative));
+                dirs.push_back(path);
+            } else {
+                files.push(relative);
+            }
+        }
+    }
+    files
+}
+
+// ═══════════════════════════════════════════════════════════════
+// DIRECTORY SEARCH
+// ═══════════════════════════════════════════════════════════════
+
+/// One line matched by `grep`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GrepMatch {
+    pub path: String,
+    /// 1-based
+    pub line: usize,
+    pub text: String,
+}
+
+impl GrepMatch {
+    /// `path:line: text`, the same for either backend
+    pub fn render(&self) -> String {
+        format!("{}:{}: {}", self.path, self.line, self.text)
+    }
+}
+
+/// Search the files under `root` that `tree` would show
+///
+/// Uses ripgrep when it's on PATH, falling back to the built-in walker if
+/// it's missing or fails. Results are sorted by path, then line.
+pub fn grep_dir(pattern: &str, root: &Path) -> Result<Vec<GrepMatch>> {
+    if crate::environ::snapshot().has("rg") {
+        if let Ok(matches) = grep_dir_rg(pattern, root) {
+            return Ok(matches);
+        }
+    }
+    grep_dir_native(pattern, root)
+}
+
+/// `rg --json`, skipping the same directories as `tree`
+pub fn grep_dir_rg(pattern: &str, root: &Path) -> Result<Vec<GrepMatch>> {
+    let mut cmd = std::process::Command::new("rg");
+    cmd.args(["--json", "--no-require-git", "--no-config"]);
+    for dir in TREE_ALWAYS_IGNORED {
+        cmd.arg("--glob").arg(format!("!{}", dir));
+    }
+    let output = cmd.arg("--regexp").arg(pattern).arg(root).output()?;
+
+    // 1 is "no matches"; 2 is an error, e.g. a bad pattern
+    if !matches!(output.status.code(), Some(0) | Some(1)) {
+        anyhow::bail!(
+            "rg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let mut matches: Vec<GrepMatch> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_rg_match)
+        .collect();
+    matches.sort();
+    Ok(matches)
+}
+
+/// A `match` message from `rg --json`; None for other messages and for
+/// lines that aren't UTF-8 (which the native walker skips too)
+fn parse_rg_match(line: &str) -> Option<GrepMatch> {
+    let msg: serde_json::Value = serde_json::from_str(line).ok()?;
+    if msg["type"] != "match" {
+        return None;
+    }
+    let data = &msg["data"];
+    Some(GrepMatch {
+        path: data["path"]["text"].as_str()?.to_string(),
+        line: data["line_number"].as_u64()? as usize,
+        text: data["lines"]["text"]
+            .as_str()?
+            .trim_end_matches('\n')
+            .trim_end_matches('\r')
+            .to_string(),
+    })
+}
+
+/// Walk `root` like `tree` (root .gitignore, no hidden files, no symlinks)
+/// and match each line with the regex crate
+pub fn grep_dir_native(pattern: &str, root: &Path) -> Result<Vec<GrepMatch>> {
+    let regex = regex::Regex::new(pattern)?;
+    let ignores = load_gitignore(root);
+    let opts = TreeOptions::default();
+
+    let mut matches = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for path in tree_entries(root, &dir, &ignores, &opts) {
+            if path.is_symlink() {
+                continue;
+            }
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            // Binary or unreadable
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let shown = path.display().to_string();
+            for (i, line) in content.lines().enumerate() {
+                if regex.is_match(line) {
+                    matches.push(GrepMatch {
+                        path: shown.clone(),
+                        line: i + 1,
+                        text: line.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// FILE OPERATIONS
+// ═══════════════════════════════════════════════════════════════
+
+/// Read a file with line numbers
+pub fn read_file(path: &Path) -> Result<String> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut output = String::new();
+    for (i, line) in content.lines().enumerate() {
+        output.push_str(&format!("{:4}│ {}\n", i + 1, line));
+    }
+
+    Ok(output)
+}
+
+/// A numbered slice of a file, as returned by `read_file_range`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileRange {
+    /// First line included (1-based)
+    pub start: usize,
+    /// Last line included, after clamping to the file
+    pub end: usize,
+    /// Lines in the whole file
+    pub total: usize,
+    /// The selected lines, numbered like `read_file`
+    pub text: String,
+}
+
+/// Read lines `start..=end` (1-based) with line numbers; `end` is clamped to
+/// the file's length
+pub fn read_file_range(path: &Path, start: usize, end: usize) -> Result<FileRange> {
+    if start == 0 || end < start {
+        anyhow::bail!("Invalid line range {}-{}", start, end);
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let total = content.lines().count();
+    if start > total {
+        anyhow::bail!(
+            "{} has {} lines; range starts at {}",
+            path.display(),
+            total,
+            start
+        );
+    }
+
+    let end = end.min(total);
+    let mut text = String::new();
+    for (i, line) in content.lines().enumerate().take(end).skip(start - 1) {
+        text.push_str(&format!("{:4}│ {}\n", i + 1, line));
+    }
+    Ok(FileRange {
+        start,
+        end,
+        total,
+        text,
+    })
+}
+
+/// Files larger than this are read a chunk at a time, continued with the
+/// `cursor` the `read` tool returns; a numbered chunk this size fits in one
+/// tool result
+pub const READ_CHUNK_BYTES: usize = 12 * 1024;
+
+fn is_large_file(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.len() > READ_CHUNK_BYTES as u64)
+}
+
+/// Whole lines from part of a large file, as returned by `read_file_chunk`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChunk {
+    /// First line included (1-based)
+    pub start_line: usize,
+    /// Last line included
+    pub end_line: usize,
+    /// Byte offset the chunk starts at
+    pub offset: u64,
+    /// Where the next chunk starts; None at end of file
+    pub next_offset: Option<u64>,
+    /// Line the next chunk starts on (the same as `end_line` when one line
+    /// was too long for a chunk)
+    pub next_line: usize,
+    /// Size of the whole file
+    pub size: u64,
+    /// The chunk's lines, numbered like `read_file`
+    pub text: String,
+}
+
+impl FileChunk {
+    /// Where the chunk sits in the file, and how to get the next one
+    pub fn footer(&self) -> String {
+        match self.next_offset {
+            Some(next) => format!(
+                "[lines {}-{}, bytes {}-{} of {}; call read again with \"cursor\": \"{}\" for the next chunk]\n",
+                self.start_line, self.end_line, self.offset, next, self.size, next
+            ),
+            None => format!(
+                "[end of file: lines {}-{}, {} bytes]\n",
+                self.start_line, self.end_line, self.size
+            ),
+        }
+    }
+}
+
+/// Read `path` from byte `offset`, which is the start of line `start_line`,
+/// numbering at most `max_bytes` of output. Only that part of the file is
+/// loaded; the chunk ends after its last whole line unless a single line
+/// is too long for a chunk, which is then split.
+pub fn read_file_chunk(
+    path: &Path,
+    offset: u64,
+    start_line: usize,
+    max_bytes: usize,
+) -> Result<FileChunk> {
+    use std::io::{Seek, SeekFrom};
+
+    let mut file =
+        fs::File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let size = file.metadata()?.len();
+    if offset > size {
+        anyhow::bail!(
+            "{} has {} bytes; chunk starts at {}",
+            path.display(),
+            size,
+            offset
+        );
+    }
+    file.seek(SeekFrom::Start(offset))?;
+    // Numbering only adds to a line, so this is all the chunk can hold
+    let mut buf = Vec::new();
+    file.take(max_bytes as u64).read_to_end(&mut buf)?;
+    let at_eof = offset + buf.len() as u64 >= size;
+
+    let mut text = String::new();
+    let mut used = 0;
+    let mut line = start_line;
+    for raw in buf.split_inclusive(|&b| b == b'\n') {
+        let content = String::from_utf8_lossy(raw);
+        let numbered = format!("{:4}│ {}\n", line, content.trim_end_matches(['\n', '\r']));
+        let whole = raw.ends_with(b"\n") || at_eof;
+        if !whole || text.len() + numbered.len() > max_bytes {
+            break;
+        }
+        text.push_str(&numbered);
+        used += raw.len();
+        line += 1;
+    }
+    let (end_line, next_line) = if used > 0 || buf.is_empty() {
+        (line.saturating_sub(1).max(start_line), line)
+    } else {
+        // One line too long for a chunk: send what fits beside its number,
+        // cut at a character boundary; the next chunk carries on with it
+        let prefix = format!("{:4}│ ", line);
+        let mut room = max_bytes
+            .saturating_sub(prefix.len() + 1)
+            .clamp(1, buf.len());
+        if let Err(e) = std::str::from_utf8(&buf[..room]) {
+            if e.error_len().is_none() && e.valid_up_to() > 0 {
+                room = e.valid_up_to();
+            }
+        }
+        text = format!("{}{}\n", prefix, String::from_utf8_lossy(&buf[..room]));
+        used = room;
+        (line, line)
+    };
+    let end = offset + used as u64;
+
+    Ok(FileChunk {
+        start_line,
+        end_line,
+        offset,
+        next_offset: (end < size).then_some(end),
+        next_line,
+        size,
+        text,
+    })
+}
+
+/// Where the next chunk of a file starts
+#[derive(Debug, Clone, PartialEq)]
+struct ReadCursor {
+    offset: u64,
+    line: usize,
+    /// The file as it was read, so a cursor into a changed file is refused
+    size: u64,
+    modified: Option<std::time::SystemTime>,
+}
+
+/// Chunked-read positions for one session, one per file. Clones share the
+/// positions, so executors made for each tool batch continue the reads of
+/// earlier ones.
+#[derive(Debug, Clone, Default)]
+pub struct ReadCursors(Arc<Mutex<std::collections::HashMap<PathBuf, ReadCursor>>>);
+
+impl ReadCursors {
+    /// Forget every position, e.g. when the conversation is cleared
+    pub fn clear(&self) {
+        if let Ok(mut cursors) = self.0.lock() {
+            cursors.clear();
+        }
+    }
+
+    /// The chunk of `path` that `cursor` points at, or its first chunk when
+    /// there's no cursor. A cursor is the byte offset from the previous
+    /// chunk's footer and only continues the latest read of that file.
+    pub fn read_chunk(
+        &self,
+        path: &Path,
+        cursor: Option<&str>,
+        max_bytes: usize,
+    ) -> Result<FileChunk> {
+        let meta =
+            fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let modified = meta.modified().ok();
+        let mut cursors = self
+            .0
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Read cursors unavailable"))?;
+
+        let (offset, line) = match cursor {
+            None => (0, 1),
+            Some(token) => {
+                let current = cursors
+                    .get(path)
+                    .filter(|c| c.offset.to_string() == token.trim())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "No read of {} continues at cursor {}; read it again without a cursor",
+                            path.display(),
+                            token
+                        )
+                    })?;
+                if current.size != meta.len() || current.modified != modified {
+                    cursors.remove(path);
+                    anyhow::bail!(
+                        "{} changed since the cursor was issued; read it again without a cursor",
+                        path.display()
+                    );
+                }
+                (current.offset, current.line)
+            }
+        };
+
+        let chunk = read_file_chunk(path, offset, line, max_bytes)?;
+        match chunk.next_offset {
+            Some(next) => {
+                cursors.insert(
+                    path.to_path_buf(),
+                    ReadCursor {
+                        offset: next,
+                        line: chunk.next_line,
+                        size: meta.len(),
+                        modified,
+                    },
+                );
+            }
+            None => {
+                cursors.remove(path);
+            }
+        }
+        Ok(chunk)
+    }
+}
+
+/// Read multiple files into context string
+pub fn read_files_context(paths: &[&Path]) -> Result<String> {
+    let mut context = String::new();
+
+    for path in paths {
+        if path.exists() {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            context.push_str(&format!("\n--- {} ---\n{}\n", path.display(), content));
+        }
+    }
+
+    Ok(context)
+}
+
+/// Files larger than this are read head/tail only, never loaded whole
+const MAX_FULL_READ_BYTES: u64 = 1024 * 1024;
+
+/// Context assembled under a token budget
+#[derive(Debug, Clone, Default)]
+pub struct BudgetedContext {
+    pub text: String,
+    /// Files that were truncated to fit (path display strings)
+    pub trimmed: Vec<String>,
+}
+
+/// Read multiple files into a context string capped at `budget_tokens`.
+///
+/// Smaller files are admitted whole first; whatever budget remains is split
+/// evenly among larger files, which are cut to head + tail with a marker.
+pub fn read_files_context_budgeted(
+    paths: &[&Path],
+    budget_tokens: usize,
+) -> Result<BudgetedContext> {
+    use crate::cognitive::estimate_tokens;
+
+    let budget_chars = budget_tokens.saturating_mul(4);
+
+    // (path, header, size on disk)
+    let mut entries: Vec<(&Path, String, u64)> = Vec::new();
+    for path in paths {
+        if path.is_file() {
+            let size = fs::metadata(path)
+                .with_context(|| format!("Failed to stat {}", path.display()))?
+                .len();
+            entries.push((path, format!("\n--- {} ---\n", path.display()), size));
+        }
+    }
+
+    // Admit smallest files first so one huge file can't starve the rest
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    order.sort_by_key(|&i| entries[i].2);
+
+    let mut bodies: Vec<Option<String>> = vec![None; entries.len()];
+    let mut trimmed = vec![false; entries.len()];
+    let mut remaining = budget_chars;
+
+    for (n, &i) in order.iter().enumerate() {
+        let (path, header, size) = &entries[i];
+        let share = remaining / (order.len() - n);
+        // Header + trailing newline are charged against the share too
+        let overhead = header.len() + 1;
+        if share <= overhead {
+            // No room even for the header; drop the file entirely
+            trimmed[i] = true;
+            continue;
+        }
+        let allowed = share - overhead;
+
+        let body = if *size as usize <= allowed {
+            let content =
+                fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            String::from_utf8_lossy(&content).into_owned()
+        } else {
+            trimmed[i] = true;
+            read_head_tail(path, *size, allowed)?
+        };
+
+        // Lossy decoding can grow the text slightly; clamp to what we allowed
+        let body = if body.len() > allowed {
+            trimmed[i] = true;
+            truncate_head_tail(&body, allowed)
+        } else {
+            body
+        };
+
+        remaining = remaining.saturating_sub(overhead + body.len());
+        bodies[i] = Some(body);
+    }
+
+    let mut text = String::new();
+    let mut trimmed_paths = Vec::new();
+    for (i, (path, header, _)) in entries.iter().enumerate() {
+        if let Some(body) = &bodies[i] {
+            text.push_str(header);
+            text.push_str(body);
+            text.push('\n');
+        }
+        if trimmed[i] {
+            trimmed_paths.push(path.display().to_string());
+        }
+    }
+
+    debug_assert!(estimate_tokens(&text) <= budget_tokens);
+
+    Ok(BudgetedContext {
+        text,
+        trimmed: trimmed_paths,
+    })
+}
+
+/// Read at most `max_chars` of a file as head + marker + tail
+fn read_head_tail(path: &Path, size: u64, max_chars: usize) -> Result<String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    if size <= MAX_FULL_READ_BYTES {
+        let content =
+            fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        return Ok(truncate_head_tail(
+            &String::from_utf8_lossy(&content),
+            max_chars,
+        ));
+    }
+
+    // Very large file: only pull the bytes we could possibly keep
+    let half = (max_chars / 2) as u64;
+    let mut file =
+        fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+    let mut head = vec![0u8; half as usize];
+    let n = file.read(&mut head)?;
+    head.truncate(n);
+
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(size.saturating_sub(half)))?;
+    file.read_to_end(&mut tail)?;
+
+    let joined = format!(
+        "{}{}",
+        String::from_utf8_lossy(&head),
+        String::from_utf8_lossy(&tail)
+    );
+    Ok(truncate_head_tail_with_omitted(
+        &joined,
+        max_chars,
+        size as usize,
+    ))
+}
+
+/// Keep the head and tail of `text` within `max_chars`, marking the cut
+pub(crate) fn truncate_head_tail(text: &str, max_chars: usize) -> String {
+    truncate_head_tail_with_omitted(text, max_chars, text.len())
+}
+
+fn truncate_head_tail_with_omitted(text: &str, max_chars: usize, original_len: usize) -> String {
+    if text.len() <= max_chars && text.len() == original_len {
+        return text.to_string();
+    }
+
+    let marker_for = |omitted: usize| format!("\n... [truncated {} bytes] ...\n", omitted);
+    // Reserve room for the widest marker we could produce
+    let marker_len = marker_for(original_len).len();
+    if max_chars <= marker_len {
+        return String::new();
+    }
+
+    let keep = max_chars - marker_len;
+    let head_len = floor_char_boundary(text, keep / 2);
+    let tail_start = ceil_char_boundary(text, text.len().saturating_sub(keep - keep / 2));
+    let tail_start = tail_start.max(head_len);
+    let omitted = original_len.saturating_sub(head_len + (text.len() - tail_start));
+
+    format!(
+        "{}{}{}",
+        &text[..head_len],
+        marker_for(omitted),
+        &text[tail_start..]
+    )
+}
+
+fn floor_char_boundary(s: &str, mut idx: usize) -> usize {
+    idx = idx.min(s.len());
+    while !s.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
+
+fn ceil_char_boundary(s: &str, mut idx: usize) -> usize {
+    idx = idx.min(s.len());
+    while !s.is_char_boundary(idx) {
+        idx += 1;
+    }
+    idx
+}
+
+/// Generate a unified diff between two strings
+pub fn generate_diff(original: &str, modified: &str, filename: &str) -> String {
+    let diff = TextDiff::from_lines(original, modified);
+
+    diff.unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", filename), &format!("b/{}", filename))
+        .to_string()
+}
+
+/// Parse a unified diff and extract hunks
+#[derive(Debug, Clone)]
+pub struct DiffHunk {
     pub old_start: usize,
     pub old_count: usize,
     pub new_start: usize,
@@ -1115,13 +2805,60 @@ fn parse_range(s: &str) -> (usize, usize) {
     }
 }
 
-/// Apply a unified diff patch to original text
+/// How a patch should be applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffFormat {
+    /// Not a diff: the input replaces the file
+    Replacement,
+    /// Plain unified diff, applied in-process
+    Unified,
+    /// Uses git extensions the in-process applier can't handle; applied
+    /// with `git apply`
+    Git,
+}
+
+/// Lines only git-style diffs contain
+const GIT_DIFF_MARKERS: &[&str] = &[
+    "diff --git ",
+    "rename from ",
+    "rename to ",
+    "copy from ",
+    "copy to ",
+    "similarity index ",
+    "old mode ",
+    "new mode ",
+    "new file mode ",
+    "deleted file mode ",
+    "GIT binary patch",
+    "\\ No newline at end of file",
+];
+
+impl DiffFormat {
+    pub fn detect(patch: &str) -> Self {
+        if patch
+            .lines()
+            .any(|l| GIT_DIFF_MARKERS.iter().any(|m| l.starts_with(m)))
+        {
+            Self::Git
+        } else if patch.contains("@@") {
+            Self::Unified
+        } else {
+            Self::Replacement
+        }
+    }
+}
+
+/// Apply a patch to original text, choosing the applier by `DiffFormat`
 pub fn apply_patch(original: &str, patch: &str) -> Result<String> {
-    // If patch doesn't look like a unified diff, treat as replacement
-    if !patch.contains("@@") {
-        return Ok(patch.to_string());
+    match DiffFormat::detect(patch) {
+        DiffFormat::Replacement => Ok(patch.to_string()),
+        DiffFormat::Unified => apply_unified_patch(original, patch),
+        DiffFormat::Git => apply_git_patch(original, patch),
     }
+}
 
+/// Apply a plain unified diff in-process
+fn apply_unified_patch(original: &str, patch: &str) -> Result<String> {
     let hunks = parse_unified_diff(patch);
     if hunks.is_empty() {
         return Ok(original.to_string());
@@ -1178,6 +2915,190 @@ pub fn apply_patch(original: &str, patch: &str) -> Result<String> {
     Ok(result)
 }
 
+/// Makes scratch directories unique within the process
+static PATCH_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+/// Apply a git-style diff to `original` in a scratch directory, with
+/// `git apply --3way` or, where git is missing, `patch -p1`
+pub fn apply_git_patch(original: &str, patch: &str) -> Result<String> {
+    let (old, new) = git_patch_paths(patch)?;
+    let scratch = std::env::temp_dir().join(format!(
+        "hyle_patch_{}_{}",
+        std::process::id(),
+        PATCH_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = apply_in_scratch(&scratch, original, patch, old.as_deref(), new.as_deref());
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
+
+fn apply_in_scratch(
+    dir: &Path,
+    original: &str,
+    patch: &str,
+    old: Option<&str>,
+    new: Option<&str>,
+) -> Result<String> {
+    fs::create_dir_all(dir)?;
+    if let Some(old) = old {
+        let path = dir.join(old);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, original)?;
+    }
+    // Both tools reject a patch whose last line is unterminated
+    let mut patch = patch.to_string();
+    if !patch.ends_with('\n') {
+        patch.push('\n');
+    }
+
+    // --3way needs the preimage in an index, so make the scratch dir a repo
+    let git = |args: &[&str], input: &str| run_piped(dir, "git", args, input);
+    let applied = git(&["init", "-q"], "")
+        .and_then(|out| match out.status.success() {
+            true => git(&["add", "-A"], ""),
+            false => Ok(out),
+        })
+        .and_then(|out| match out.status.success() {
+            true => git(
+                &["apply", "--3way", "--recount", "--whitespace=nowarn", "-"],
+                &patch,
+            ),
+            false => Ok(out),
+        });
+    let output = match applied {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => run_piped(
+            dir,
+            "patch",
+            &["-p1", "-s", "-f", "--no-backup-if-mismatch"],
+            &patch,
+        ),
+        other => other,
+    }
+    .context("Failed to run git apply or patch")?;
+    if !output.status.success() {
+        let mut reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if reason.is_empty() {
+            reason = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        }
+        anyhow::bail!("git-style diff did not apply: {}", reason);
+    }
+
+    match new {
+        Some(new) => fs::read_to_string(dir.join(new))
+            .with_context(|| format!("Patch did not produce {}", new)),
+        // Deleted
+        None => Ok(String::new()),
+    }
+}
+
+/// Run `program` in `dir` with `input` on stdin
+fn run_piped(
+    dir: &Path,
+    program: &str,
+    args: &[&str],
+    input: &str,
+) -> std::io::Result<std::process::Output> {
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .env_remove("GIT_DIR")
+        .env_remove("GIT_WORK_TREE")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    child.wait_with_output()
+}
+
+/// Git headers for changes other than content, which only the content is
+/// written back for
+const GIT_METADATA_HEADERS: &[&str] = &[
+    "rename from ",
+    "rename to ",
+    "copy from ",
+    "copy to ",
+    "old mode ",
+    "new mode ",
+];
+
+/// Paths before and after a git-style diff, None for /dev/null. Only the
+/// patched content is written back, so diffs touching more than one file,
+/// renaming, copying or changing modes are refused.
+fn git_patch_paths(patch: &str) -> Result<(Option<String>, Option<String>)> {
+    let strip = |p: &str| {
+        let p = p.split('\t').next().unwrap_or(p).trim();
+        p.strip_prefix("a/")
+            .or_else(|| p.strip_prefix("b/"))
+            .unwrap_or(p)
+            .to_string()
+    };
+    let (mut old, mut new) = (None, None);
+    let (mut git_headers, mut file_headers) = (0, 0);
+    let lines: Vec<&str> = patch.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            git_headers += 1;
+            if let Some((a, b)) = rest.split_once(" b/") {
+                old = Some(strip(a));
+                new = Some(b.trim().to_string());
+            }
+        } else if let Some(header) = GIT_METADATA_HEADERS.iter().find(|h| line.starts_with(*h)) {
+            anyhow::bail!(
+                "git-style diff has a '{}' header; only content changes are applied",
+                header.trim()
+            );
+        } else if let Some(mode) = line.strip_prefix("new file mode ") {
+            if mode.trim() != "100644" {
+                anyhow::bail!("git-style diff creates a file with mode {}", mode.trim());
+            }
+        } else if let Some(p) = line.strip_prefix("--- ") {
+            // A file header, not a removed "-- " line inside a hunk
+            if lines.get(i + 1).is_some_and(|l| l.starts_with("+++ ")) {
+                file_headers += 1;
+                old = Some(strip(p));
+            }
+        } else if let Some(p) = line.strip_prefix("+++ ") {
+            if i > 0 && lines[i - 1].starts_with("--- ") {
+                new = Some(strip(p));
+            }
+        }
+    }
+    if git_headers > 1 || file_headers > 1 {
+        anyhow::bail!("git-style diff touches more than one file; send one diff per file");
+    }
+    if let (Some(a), Some(b)) = (&old, &new) {
+        if a != b && a != "/dev/null" && b != "/dev/null" {
+            anyhow::bail!("git-style diff moves {} to {}; use the move tool", a, b);
+        }
+    }
+
+    let check = |p: Option<String>| -> Result<Option<String>> {
+        match p {
+            Some(p) if p == "/dev/null" => Ok(None),
+            Some(p) => {
+                let safe = Path::new(&p)
+                    .components()
+                    .all(|c| matches!(c, std::path::Component::Normal(_)));
+                if !safe {
+                    anyhow::bail!("Refusing diff path outside the file tree: {}", p);
+                }
+                Ok(Some(p))
+            }
+            None => Ok(None),
+        }
+    };
+    let (old, new) = (check(old)?, check(new)?);
+    if old.is_none() && new.is_none() {
+        anyhow::bail!("git-style diff names no file");
+    }
+    Ok((old, new))
+}
+
 /// Apply multiple patches to a file, with validation
 ///
 /// Uses atomic writes for reliability: temp file, sync, rename, verify.
@@ -1385,6 +3306,77 @@ mod tests {
         assert_eq!(result, "completely new content");
     }
 
+    const GIT_NO_NEWLINE_PATCH: &str = "diff --git a/src/lib.rs b/src/lib.rs
+index 3b18e51..a042389 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn a() {}
+ fn b() {}
+-fn c() {}
+\\ No newline at end of file
++fn c() -> u8 { 0 }
+";
+
+    #[test]
+    fn test_detect_diff_format() {
+        assert_eq!(DiffFormat::detect(GIT_NO_NEWLINE_PATCH), DiffFormat::Git);
+        assert_eq!(
+            DiffFormat::detect("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b\n"),
+            DiffFormat::Unified
+        );
+        assert_eq!(
+            DiffFormat::detect("rename from a.rs\nrename to b.rs\n"),
+            DiffFormat::Git
+        );
+        assert_eq!(
+            DiffFormat::detect("fn main() {}\n"),
+            DiffFormat::Replacement
+        );
+    }
+
+    #[test]
+    fn test_apply_git_patch_fallback() {
+        // Adds the missing trailing newline, which the in-process applier can't express
+        let original = "fn a() {}\nfn b() {}\nfn c() {}";
+        let result = apply_patch(original, GIT_NO_NEWLINE_PATCH).unwrap();
+        assert_eq!(result, "fn a() {}\nfn b() {}\nfn c() -> u8 { 0 }\n");
+
+        // Renames and mode changes would be lost when only content is written
+        let rename = "diff --git a/old.sh b/bin/new.sh
+old mode 100644
+new mode 100755
+similarity index 80%
+rename from old.sh
+rename to bin/new.sh
+--- a/old.sh
++++ b/bin/new.sh
+@@ -1,2 +1,2 @@
+ #!/bin/sh
+-echo old
++echo new
+";
+        let err = apply_patch("#!/bin/sh\necho old\n", rename).unwrap_err();
+        assert!(err.to_string().contains("only content changes"), "{}", err);
+
+        // So would every file after the first
+        let two_files = format!(
+            "{}{}",
+            GIT_NO_NEWLINE_PATCH,
+            GIT_NO_NEWLINE_PATCH.replace("src/lib.rs", "src/other.rs")
+        );
+        let err = apply_patch(original, &two_files).unwrap_err();
+        assert!(err.to_string().contains("more than one file"), "{}", err);
+
+        // A patch that doesn't match surfaces git's reason
+        let err = apply_patch("something else\n", GIT_NO_NEWLINE_PATCH).unwrap_err();
+        assert!(err.to_string().contains("did not apply"), "{}", err);
+
+        // Paths can't escape the scratch directory
+        let escape = GIT_NO_NEWLINE_PATCH.replace("a/src/lib.rs", "a/../../etc/passwd");
+        assert!(apply_patch("x", &escape).is_err());
+    }
+
     #[test]
     fn test_extract_diff_target() {
         let patch = r#"--- a/src/main.rs
@@ -1514,6 +3506,141 @@ mod tests {
         std::fs::remove_file(&tmp).ok();
     }
 
+    #[test]
+    fn test_read_file_range() {
+        let tmp = std::env::temp_dir().join(format!("hyle_test_range_{}.txt", std::process::id()));
+        std::fs::write(&tmp, "one\ntwo\nthree\nfour\n").unwrap();
+
+        let range = read_file_range(&tmp, 2, 3).unwrap();
+        assert_eq!(range.text, "   2│ two\n   3│ three\n");
+        assert_eq!((range.start, range.end, range.total), (2, 3, 4));
+
+        // End past the file is clamped; start past it is an error
+        let range = read_file_range(&tmp, 3, 99).unwrap();
+        assert_eq!(range.end, 4);
+        assert_eq!(range.text, "   3│ three\n   4│ four\n");
+        assert!(read_file_range(&tmp, 5, 9).is_err());
+        assert!(read_file_range(&tmp, 0, 2).is_err());
+        assert!(read_file_range(&tmp, 3, 2).is_err());
+
+        // The read tool takes the same range
+        let mut executor = ToolExecutor::new();
+        let mut call = ToolCall::new(
+            "read",
+            serde_json::json!({"path": tmp.to_string_lossy(), "start_line": 4}),
+        );
+        executor.execute(&mut call).unwrap();
+        assert_eq!(call.get_output(), "   4│ four\n");
+
+        std::fs::remove_file(&tmp).ok();
+    }
+
+    #[test]
+    fn test_read_file_chunk_sequential() {
+        let tmp = std::env::temp_dir().join(format!("hyle_test_chunk_{}.txt", std::process::id()));
+        let content: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&tmp, &content).unwrap();
+
+        // Chunks of whole lines, each starting where the last one stopped
+        let (mut offset, mut line) = (0, 1);
+        let mut text = String::new();
+        let mut chunks = 0;
+        loop {
+            let chunk = read_file_chunk(&tmp, offset, line, 100).unwrap();
+            assert_eq!(chunk.offset, offset);
+            assert_eq!(chunk.start_line, line);
+            assert!(chunk.text.ends_with('\n'));
+            assert!(chunk.text.len() <= 100);
+            for numbered in chunk.text.lines() {
+                text.push_str(numbered.split_once("│ ").unwrap().1);
+                text.push('\n');
+            }
+            chunks += 1;
+            match chunk.next_offset {
+                Some(next) => {
+                    assert!(next - offset <= 100);
+                    assert_eq!(chunk.next_line, chunk.end_line + 1);
+                    (offset, line) = (next, chunk.next_line);
+                }
+                None => {
+                    assert_eq!(chunk.end_line, 100);
+                    assert!(chunk.footer().starts_with("[end of file: lines"));
+                    break;
+                }
+            }
+        }
+        assert_eq!(text, content);
+        assert!(chunks > 5);
+
+        // A line longer than a chunk is split, keeping its number
+        std::fs::write(&tmp, format!("{}\nshort\n", "x".repeat(50))).unwrap();
+        let first = read_file_chunk(&tmp, 0, 1, 20).unwrap();
+        assert_eq!(first.text, format!("   1│ {}\n", "x".repeat(11)));
+        assert_eq!((first.end_line, first.next_line), (1, 1));
+        assert_eq!(first.next_offset, Some(11));
+        assert!(first.footer().contains("\"cursor\": \"11\""));
+        let second = read_file_chunk(&tmp, 11, 1, 20).unwrap();
+        assert!(second.text.starts_with("   1│ xxx"));
+        assert!(read_file_chunk(&tmp, 999, 1, 20).is_err());
+
+        std::fs::remove_file(&tmp).ok();
+    }
+
+    #[test]
+    fn test_read_tool_cursor_to_end_of_file() {
+        let tmp = std::env::temp_dir().join(format!("hyle_test_cursor_{}.txt", std::process::id()));
+        let content: String = (1..=400)
+            .map(|i| format!("line {:05} {}\n", i, "-".repeat(40)))
+            .collect();
+        std::fs::write(&tmp, &content).unwrap();
+        let path = tmp.to_string_lossy().to_string();
+        let read = |executor: &mut ToolExecutor, args: serde_json::Value| {
+            let mut call = ToolCall::new("read", args);
+            executor.execute(&mut call).map(|_| call.get_output())
+        };
+        let cursor_in = |output: &str| {
+            let rest = output.rsplit("\"cursor\": \"").next().unwrap();
+            rest.split('"').next().unwrap().to_string()
+        };
+
+        // A large file comes back one chunk at a time
+        let mut executor = ToolExecutor::new();
+        let first = read(&mut executor, serde_json::json!({"path": path})).unwrap();
+        assert!(first.starts_with("   1│ line 00001"));
+        assert!(!first.contains("line 00400"));
+        let cursor = cursor_in(&first);
+
+        // The next batch's executor continues the session's read
+        let mut next_batch = ToolExecutor::new().with_read_cursors(executor.read_cursors().clone());
+        let cursor_num: u64 = cursor.parse().unwrap();
+        let second = read(
+            &mut next_batch,
+            serde_json::json!({"path": path, "cursor": cursor_num}),
+        )
+        .unwrap();
+        assert!(!second.contains("line 00001"));
+        assert!(second.contains(" 400│ line 00400"));
+        assert!(second.ends_with(&format!("-400, {} bytes]\n", content.len())));
+
+        // Finished and stale cursors are refused
+        let finished = serde_json::json!({"path": path, "cursor": cursor});
+        assert!(read(&mut next_batch, finished).is_err());
+        let fresh = read(&mut executor, serde_json::json!({"path": path})).unwrap();
+        let unknown = serde_json::json!({"path": path, "cursor": "12"});
+        assert!(read(&mut executor, unknown).is_err());
+        executor.read_cursors().clear();
+        let args = serde_json::json!({"path": path, "cursor": cursor_in(&fresh)});
+        assert!(read(&mut executor, args.clone()).is_err());
+
+        // So is a cursor into a file that changed since
+        read(&mut executor, serde_json::json!({"path": path})).unwrap();
+        std::fs::write(&tmp, format!("{}more\n", content)).unwrap();
+        let err = read(&mut executor, args).unwrap_err();
+        assert!(err.to_string().contains("changed"));
+
+        std::fs::remove_file(&tmp).ok();
+    }
+
     #[test]
     fn test_executor_bash() {
         let mut executor = ToolExecutor::new();
@@ -1530,21 +3657,133 @@ mod tests {
         assert!(call.get_output().contains("hello"));
     }
 
+    #[test]
+    fn test_executor_bash_invalid_utf8() {
+        let mut executor = ToolExecutor::new();
+        let mut call = ToolCall::new(
+            "bash",
+            serde_json::json!({"command": "printf 'ok\\377\\376\\n'; printf '\\377' >&2"}),
+        );
+
+        executor.execute(&mut call).unwrap();
+        let output = call.get_output();
+        assert!(output.starts_with("ok\u{fffd}\u{fffd}\n"));
+        assert!(output.contains("[output contained 2 invalid bytes, shown lossily]"));
+        assert!(output.contains("[stderr]\n\u{fffd}\n[output contained 1 invalid byte"));
+    }
+
+    #[test]
+    fn test_executor_bash_shell_vs_argv() {
+        let run = |args: serde_json::Value| {
+            let mut executor = ToolExecutor::new();
+            let mut call = ToolCall::new("bash", args);
+            executor.execute(&mut call).unwrap();
+            call.get_output().to_string()
+        };
+
+        // Same logical command, same result
+        let shell = run(serde_json::json!({"command": "echo hello world"}));
+        let argv = run(serde_json::json!({"argv": ["echo", "hello", "world"]}));
+        assert_eq!(shell, argv);
+
+        // argv never goes through a shell: metacharacters stay literal
+        let argv = run(serde_json::json!({"argv": ["echo", "a; echo b", "$HOME"]}));
+        assert_eq!(argv.trim(), "a; echo b $HOME");
+        let shell = run(serde_json::json!({"command": "echo a; echo b"}));
+        assert_eq!(shell.lines().count(), 2);
+    }
+
+    #[test]
+    fn test_executor_bash_env_policy() {
+        std::env::set_var("HYLE_TEST_SECRET_TOKEN", "leaked");
+        let mut policy = EnvPolicy::default();
+        policy
+            .set
+            .insert("HYLE_TEST_INJECTED".into(), "injected".into());
+        let mut executor = ToolExecutor::new().with_env_policy(policy);
+
+        let mut call = ToolCall::new("bash", serde_json::json!({"argv": ["env"]}));
+        executor.execute(&mut call).unwrap();
+        let output = call.get_output();
+        assert!(!output.contains("HYLE_TEST_SECRET_TOKEN"));
+        assert!(output.contains("HYLE_TEST_INJECTED=injected"));
+        assert!(output.contains("PATH="));
+    }
+
+    #[test]
+    fn test_bash_invocation_from_args() {
+        assert_eq!(
+            BashInvocation::from_args(&serde_json::json!({"command": "ls"})).unwrap(),
+            BashInvocation::Shell("ls".into())
+        );
+        let argv = serde_json::json!({"argv": ["rm", "-rf", "x"], "command": "ls"});
+        let inv = BashInvocation::from_args(&argv).unwrap();
+        assert_eq!(inv.display(), "rm -rf x");
+        assert!(BashInvocation::from_args(&serde_json::json!({"argv": []})).is_err());
+        assert!(BashInvocation::from_args(&serde_json::json!({"argv": [1]})).is_err());
+        assert!(BashInvocation::from_args(&serde_json::json!({})).is_err());
+
+        // Blocked patterns apply to argv too
+        let mut executor = ToolExecutor::new();
+        let mut call = ToolCall::new("bash", argv);
+        assert!(executor.execute(&mut call).is_err());
+        assert!(call.error.as_ref().unwrap().contains("BLOCKED"));
+    }
+
     #[test]
     fn test_executor_bash_timeout() {
         let mut executor = ToolExecutor::new();
         let mut call = ToolCall::new(
             "bash",
-            serde_json::json!({
-                "command": "sleep 10",
-                "timeout": 100  // 100ms timeout
-            }),
+            serde_json::json!({
+                "command": "sleep 10",
+                "timeout": 100  // 100ms timeout
+            }),
+        );
+
+        let result = executor.execute(&mut call);
+        assert!(result.is_err());
+        assert_eq!(call.status, ToolCallStatus::Failed);
+        assert!(call.error.as_ref().unwrap().contains("Timeout"));
+    }
+
+    #[test]
+    fn test_clamp_timeout() {
+        assert_eq!(clamp_timeout(Some(500), 1000), (500, TimeoutLimit::PerCall));
+        assert_eq!(
+            clamp_timeout(Some(5000), 1000),
+            (1000, TimeoutLimit::GlobalMax)
+        );
+        assert_eq!(
+            clamp_timeout(None, 600_000),
+            (DEFAULT_BASH_TIMEOUT_MS, TimeoutLimit::Default)
+        );
+        assert_eq!(clamp_timeout(None, 10), (10, TimeoutLimit::GlobalMax));
+    }
+
+    #[test]
+    fn test_executor_bash_timeout_reports_limit() {
+        let mut executor = ToolExecutor::new().with_max_timeout(100);
+        let mut call = ToolCall::new(
+            "bash",
+            serde_json::json!({"command": "sleep 10", "timeout": 60000}),
+        );
+
+        let start = Instant::now();
+        assert!(executor.execute(&mut call).is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let error = call.error.as_ref().unwrap();
+        assert!(error.contains("global max_command_timeout_ms"), "{}", error);
+        assert!(error.contains("of 100ms"), "{}", error);
+        assert!(error.contains("after running"), "{}", error);
+
+        let mut call = ToolCall::new(
+            "bash",
+            serde_json::json!({"command": "sleep 10", "timeout": 100}),
         );
-
-        let result = executor.execute(&mut call);
-        assert!(result.is_err());
-        assert_eq!(call.status, ToolCallStatus::Failed);
-        assert!(call.error.as_ref().unwrap().contains("Timeout"));
+        assert!(executor.execute(&mut call).is_err());
+        assert!(call.error.as_ref().unwrap().contains("per-call timeout"));
     }
 
     #[test]
@@ -1650,6 +3889,71 @@ mod tests {
         assert!(output.contains("main.rs") || output.is_empty()); // May be empty in temp dir
     }
 
+    fn numbered(n: usize) -> Vec<String> {
+        (1..=n).map(|i| format!("r{:03}", i)).collect()
+    }
+
+    #[test]
+    fn test_paginate_limit() {
+        let out = paginate(&numbered(5), 0, 2);
+        assert_eq!(
+            out,
+            "r001\nr002\n[showing 1-2 of 5; call again with offset=2]\n"
+        );
+
+        // Everything fits: no hint
+        assert_eq!(paginate(&numbered(2), 0, 10), "r001\nr002\n");
+        assert_eq!(paginate(&[], 0, 10), "");
+    }
+
+    #[test]
+    fn test_paginate_offset() {
+        let results = numbered(5);
+        let out = paginate(&results, 2, 2);
+        assert_eq!(
+            out,
+            "r003\nr004\n[showing 3-4 of 5; call again with offset=4]\n"
+        );
+
+        // Last page has no continuation hint
+        assert_eq!(paginate(&results, 4, 2), "r005\n");
+        assert_eq!(
+            paginate(&results, 9, 2),
+            "[no results at offset=9; 5 total]\n"
+        );
+    }
+
+    #[test]
+    fn test_grep_pagination_hint() {
+        let dir = std::env::temp_dir().join(format!("hyle_test_grep_page_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("many.txt");
+        let content: String = (1..=500).map(|i| format!("match {}\n", i)).collect();
+        fs::write(&file, content).unwrap();
+
+        let mut executor = ToolExecutor::new();
+        let path = file.display().to_string();
+        let mut call = ToolCall::new(
+            "grep",
+            serde_json::json!({"pattern": "match", "path": path}),
+        );
+        executor.execute(&mut call).unwrap();
+        let output = call.get_output();
+        assert_eq!(output.lines().count(), DEFAULT_RESULT_LIMIT + 1);
+        assert!(output.contains("[showing 1-200 of 500; call again with offset=200]"));
+
+        let mut call = ToolCall::new(
+            "grep",
+            serde_json::json!({"pattern": "match", "path": path, "offset": 490, "limit": 50}),
+        );
+        executor.execute(&mut call).unwrap();
+        let output = call.get_output();
+        assert!(output.starts_with(&format!("{}:491: match 491", path)));
+        assert!(!output.contains("call again"));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
     // ═══════════════════════════════════════════════════════════════
     // OBSERVABLE EXECUTION TESTS
     // ═══════════════════════════════════════════════════════════════
@@ -1934,6 +4238,42 @@ mod tests {
         assert!(rendered.contains("hi"));
     }
 
+    #[test]
+    fn test_tracker_metrics() {
+        let mut tracker = ToolCallTracker::new();
+        let t0 = Instant::now();
+        let mut finished = |name: &str, ms: u64, status: ToolCallStatus| {
+            let mut call = ToolCall::new(name, serde_json::json!({}));
+            call.status = status;
+            call.started_at = Some(t0);
+            call.finished_at = Some(t0 + Duration::from_millis(ms));
+            tracker.add(call);
+        };
+        finished("bash", 1000, ToolCallStatus::Done);
+        finished("bash", 2000, ToolCallStatus::Done);
+        finished("bash", 1200, ToolCallStatus::Failed);
+        finished("bash", 1400, ToolCallStatus::Killed);
+        finished("read", 10, ToolCallStatus::Done);
+        finished("grep", 30, ToolCallStatus::Done);
+        // Still running: not counted
+        let mut running = ToolCall::new("read", serde_json::json!({}));
+        running.start();
+        tracker.add(running);
+
+        let metrics = tracker.metrics();
+        assert_eq!(
+            metrics.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(),
+            vec!["bash", "grep", "read"]
+        );
+        let bash = &metrics[0];
+        assert_eq!((bash.calls, bash.succeeded), (4, 2));
+        assert_eq!(bash.avg_duration(), Duration::from_millis(1400));
+        assert_eq!(bash.render(), "bash ×4, 50% ok, avg 1.4s");
+        assert_eq!(metrics[2].render(), "read ×1, 100% ok, avg 0.0s");
+
+        assert!(ToolCallTracker::new().metrics().is_empty());
+    }
+
     #[test]
     fn test_elapsed_formatting() {
         let mut call = ToolCall::new("test", serde_json::json!({}));
@@ -2009,6 +4349,173 @@ mod tests {
         let _ = std::fs::remove_file(&test_file);
     }
 
+    #[test]
+    fn test_writes_are_stored_as_artifacts() {
+        let dir = std::env::temp_dir().join(format!("hyle_test_artifacts_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = crate::artifacts::ArtifactStore::open(&dir);
+        let mut executor = ToolExecutor::new()
+            .with_work_dir(&dir)
+            .with_artifacts(store.clone());
+        for content in ["<h1>v1</h1>\n", "<h1>v2</h1>\n", "<h1>v2</h1>\n"] {
+            let mut call = ToolCall::new(
+                "write",
+                serde_json::json!({"path": "index.html", "content": content}),
+            );
+            executor.execute(&mut call).unwrap();
+        }
+
+        // The unchanged rewrite adds no version; the first is still recoverable
+        let versions = store.versions("index.html").unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(store.read(&versions[0].hash).unwrap(), b"<h1>v1</h1>\n");
+
+        // Files outside the work dir are not kept
+        let outside =
+            std::env::temp_dir().join(format!("hyle_test_outside_{}", std::process::id()));
+        let mut call = ToolCall::new(
+            "write",
+            serde_json::json!({"path": outside.to_string_lossy(), "content": "x"}),
+        );
+        executor.execute(&mut call).unwrap();
+        let relative = format!("../{}", outside.file_name().unwrap().to_string_lossy());
+        let mut call = ToolCall::new(
+            "write",
+            serde_json::json!({"path": relative, "content": "y"}),
+        );
+        executor.execute(&mut call).unwrap();
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        let _ = std::fs::remove_file(&outside);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_write_refuses_protected_paths() {
+        let dir = std::env::temp_dir().join(format!("hyle_test_protected_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lockfile = dir.join("Cargo.lock");
+        let _ = std::fs::remove_file(&lockfile);
+        let write = |extra: serde_json::Value| {
+            let mut args = serde_json::json!({
+                "path": lockfile.to_string_lossy(),
+                "content": "# edited\n"
+            });
+            args.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            let mut call = ToolCall::new("write", args);
+            let result = ToolExecutor::new().execute(&mut call);
+            (result, call)
+        };
+
+        let (result, call) = write(serde_json::json!({}));
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("protected pattern `Cargo.lock`"));
+        assert!(err.contains("allow_protected"));
+        assert_eq!(call.status, ToolCallStatus::Failed);
+        assert!(!lockfile.exists());
+
+        let (result, _) = write(serde_json::json!({"allow_protected": true}));
+        assert!(result.is_ok());
+        assert_eq!(std::fs::read_to_string(&lockfile).unwrap(), "# edited\n");
+
+        // Config can lift the protection for a whole pattern
+        let mut protected = crate::config::ProtectedPaths::default();
+        protected.allow.push("Cargo.lock".into());
+        let mut call = ToolCall::new(
+            "write",
+            serde_json::json!({"path": lockfile.to_string_lossy(), "content": "x\n"}),
+        );
+        ToolExecutor::new()
+            .with_protected_paths(protected)
+            .execute(&mut call)
+            .unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_tools_use_injected_work_dir() {
+        let dir = std::env::temp_dir().join(format!("hyle_test_work_dir_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "pub fn marker() {}\n").unwrap();
+        let cwd = std::env::current_dir().unwrap();
+
+        let mut executor = ToolExecutor::new().with_work_dir(&dir);
+        let mut run = |name: &str, args: serde_json::Value| {
+            let mut call = ToolCall::new(name, args);
+            executor.execute(&mut call).unwrap();
+            call.get_output()
+        };
+
+        assert!(run("read", serde_json::json!({"path": "src/lib.rs"})).contains("marker"));
+        run(
+            "write",
+            serde_json::json!({"path": "notes.txt", "content": "hello\n"}),
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("notes.txt")).unwrap(),
+            "hello\n"
+        );
+        assert_eq!(
+            run("glob", serde_json::json!({"pattern": "src/*.rs"})).trim(),
+            "src/lib.rs"
+        );
+        assert!(run("grep", serde_json::json!({"pattern": "marker"})).starts_with("src/lib.rs:1:"));
+        assert!(run("tree", serde_json::json!({})).contains("lib.rs"));
+        let pwd = run("bash", serde_json::json!({"command": "pwd"}));
+        assert_eq!(
+            std::fs::canonicalize(pwd.trim()).unwrap(),
+            std::fs::canonicalize(&dir).unwrap()
+        );
+
+        // The process working directory is never touched
+        assert_eq!(std::env::current_dir().unwrap(), cwd);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_write_reports_diff_on_overwrite() {
+        let test_file =
+            std::env::temp_dir().join(format!("hyle_test_write_diff_{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&test_file);
+        let write = |content: &str| {
+            let mut call = ToolCall::new(
+                "write",
+                serde_json::json!({
+                    "path": test_file.to_string_lossy(),
+                    "content": content
+                }),
+            );
+            ToolExecutor::new().execute(&mut call).unwrap();
+            call.get_output()
+        };
+
+        // New file: nothing to compare against
+        let created = write("one\ntwo\n");
+        assert!(!created.contains("Preview:"));
+        assert!(!created.contains("@@"));
+
+        let overwritten = write("one\n2\n");
+        assert!(overwritten.contains("Preview:"));
+        assert!(overwritten.contains("-two"));
+        assert!(overwritten.contains("+2"));
+
+        assert!(write("one\n2\n").contains("No changes"));
+
+        let _ = std::fs::remove_file(&test_file);
+        for backup in glob::glob(&format!("{}.*", test_file.display()))
+            .unwrap()
+            .flatten()
+        {
+            let _ = std::fs::remove_file(backup);
+        }
+    }
+
     #[test]
     fn test_atomic_write_creates_backup() {
         let tmp_dir = std::env::temp_dir();
@@ -2373,4 +4880,437 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_budgeted_context_trims_to_budget() {
+        let dir = std::env::temp_dir().join(format!("hyle_test_budget_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let small = dir.join("small.txt");
+        let big = dir.join("big.txt");
+        std::fs::write(&small, "fn main() {}\n").unwrap();
+        let big_content: String = (0..5000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&big, &big_content).unwrap();
+
+        let budget = 500;
+        let ctx = read_files_context_budgeted(&[big.as_path(), small.as_path()], budget).unwrap();
+
+        assert!(crate::cognitive::estimate_tokens(&ctx.text) <= budget);
+        // Small file survives whole, big one is cut head/tail
+        assert!(ctx.text.contains("fn main() {}"));
+        assert!(ctx.text.contains("line 0\n"));
+        assert!(ctx.text.contains("line 4999\n"));
+        assert!(ctx.text.contains("[truncated"));
+        assert_eq!(ctx.trimmed, vec![big.display().to_string()]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_budgeted_context_under_budget_is_untouched() {
+        let dir = std::env::temp_dir().join(format!("hyle_test_budget_ok_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a.txt");
+        std::fs::write(&file, "hello\n").unwrap();
+
+        let ctx = read_files_context_budgeted(&[file.as_path()], 10_000).unwrap();
+        assert!(ctx.trimmed.is_empty());
+        assert_eq!(ctx.text, read_files_context(&[file.as_path()]).unwrap());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_truncate_head_tail_respects_char_boundaries() {
+        let text = "é".repeat(200);
+        let out = truncate_head_tail(&text, 100);
+        assert!(out.len() <= 100);
+        assert!(out.contains("[truncated"));
+    }
+
+    fn tree_fixture(name: &str) -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("hyle_test_{}_{}", name, std::process::id()));
+        fs::create_dir_all(root.join("a/b/c")).unwrap();
+        fs::create_dir_all(root.join("build")).unwrap();
+        fs::create_dir_all(root.join(".hidden")).unwrap();
+        fs::write(root.join(".gitignore"), "build/\n*.log\n").unwrap();
+        fs::write(root.join("a/b/c/deep.rs"), "").unwrap();
+        fs::write(root.join("a/one.rs"), "").unwrap();
+        fs::write(root.join("build/out.o"), "").unwrap();
+        fs::write(root.join(".hidden/secret"), "").unwrap();
+        fs::write(root.join("debug.log"), "").unwrap();
+        fs::write(root.join("main.rs"), "").unwrap();
+        root
+    }
+
+    #[test]
+    fn test_grep_backends_agree() {
+        let root = tree_fixture("grep_backends");
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("main.rs"), "fn main() {\n    todo!()\n}\n").unwrap();
+        fs::write(root.join("a/one.rs"), "// TODO: one\r\nfn one() {}\n").unwrap();
+        fs::write(root.join("a/b/c/deep.rs"), "fn deep() { /* TODO */ }\n").unwrap();
+        // Matches the ignored files must not produce
+        fs::write(root.join("build/out.o"), "TODO build\n").unwrap();
+        fs::write(root.join("debug.log"), "TODO log\n").unwrap();
+        fs::write(root.join(".hidden/secret"), "TODO hidden\n").unwrap();
+        fs::write(root.join("target/gen.rs"), "// TODO generated\n").unwrap();
+
+        let native = grep_dir_native("TODO|todo", &root).unwrap();
+        let at = |file: &str| root.join(file).display().to_string();
+        let expected = {
+            let mut m = vec![
+                GrepMatch {
+                    path: at("a/b/c/deep.rs"),
+                    line: 1,
+                    text: "fn deep() { /* TODO */ }".into(),
+                },
+                GrepMatch {
+                    path: at("a/one.rs"),
+                    line: 1,
+                    text: "// TODO: one".into(),
+                },
+                GrepMatch {
+                    path: at("main.rs"),
+                    line: 2,
+                    text: "    todo!()".into(),
+                },
+            ];
+            m.sort();
+            m
+        };
+        assert_eq!(native, expected);
+
+        // What rg --json reports for the same tree parses to the same matches
+        let rg_line = |m: &GrepMatch, raw: &str| {
+            serde_json::json!({
+                "type": "match",
+                "data": {
+                    "path": {"text": m.path},
+                    "lines": {"text": raw},
+                    "line_number": m.line,
+                    "absolute_offset": 0,
+                    "submatches": []
+                }
+            })
+            .to_string()
+        };
+        let stream = [
+            r#"{"type":"begin","data":{"path":{"text":"x"}}}"#.to_string(),
+            rg_line(&expected[2], "    todo!()\n"),
+            rg_line(&expected[0], "fn deep() { /* TODO */ }\n"),
+            rg_line(&expected[1], "// TODO: one\r\n"),
+            r#"{"type":"summary","data":{}}"#.to_string(),
+        ];
+        let mut parsed: Vec<GrepMatch> = stream.iter().filter_map(|l| parse_rg_match(l)).collect();
+        parsed.sort();
+        assert_eq!(parsed, native);
+
+        // And the real thing, where it's installed
+        if crate::environ::snapshot().has("rg") {
+            assert_eq!(grep_dir_rg("TODO|todo", &root).unwrap(), native);
+        }
+
+        // The tool renders directory hits like single-file ones
+        let mut executor = ToolExecutor::new();
+        let mut call = ToolCall::new(
+            "grep",
+            serde_json::json!({"pattern": "todo!", "path": root.to_string_lossy()}),
+        );
+        executor.execute(&mut call).unwrap();
+        assert_eq!(
+            call.get_output(),
+            format!("{}:2:     todo!()\n", at("main.rs"))
+        );
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_tree_depth_limit() {
+        let root = tree_fixture("tree_depth");
+
+        let shallow = TreeOptions {
+            max_depth: 1,
+            ..Default::default()
+        };
+        let out = render_tree(&root, &shallow).unwrap();
+        assert!(out.contains("a/ (2 files)"));
+        assert!(out.contains("main.rs"));
+        assert!(!out.contains("one.rs"));
+
+        let out = render_tree(&root, &TreeOptions::default()).unwrap();
+        assert!(out.contains("one.rs"));
+        assert!(out.contains("c/ (1 files)"));
+        assert!(!out.contains("deep.rs"));
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_tree_ignores_and_hidden() {
+        let root = tree_fixture("tree_ignore");
+
+        let out = render_tree(&root, &TreeOptions::default()).unwrap();
+        assert!(!out.contains("build"));
+        assert!(!out.contains("debug.log"));
+        assert!(!out.contains(".hidden"));
+
+        let hidden = TreeOptions {
+            show_hidden: true,
+            ..Default::default()
+        };
+        let out = render_tree(&root, &hidden).unwrap();
+        assert!(out.contains(".hidden/"));
+        assert!(!out.contains("build"));
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_project_files_respect_ignores() {
+        let root = tree_fixture("project_files");
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("target/app"), "").unwrap();
+
+        let files = project_files(&root, 100);
+        assert_eq!(
+            files,
+            vec![
+                "a/",
+                "main.rs",
+                "a/b/",
+                "a/one.rs",
+                "a/b/c/",
+                "a/b/c/deep.rs"
+            ]
+        );
+        assert_eq!(project_files(&root, 2), vec!["a/", "main.rs"]);
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_tree_node_cap_and_tool() {
+        let root = tree_fixture("tree_cap");
+
+        let capped = TreeOptions {
+            max_nodes: 2,
+            ..Default::default()
+        };
+        assert!(render_tree(&root, &capped)
+            .unwrap()
+            .contains("[truncated at 2 entries]"));
+
+        let mut executor = ToolExecutor::new();
+        let mut call = ToolCall::new(
+            "tree",
+            serde_json::json!({"path": root.to_string_lossy(), "max_depth": 1}),
+        );
+        assert!(executor.execute(&mut call).is_ok());
+        assert!(call.get_output().contains("main.rs"));
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tree_does_not_follow_symlinked_dirs() {
+        let root = tree_fixture("tree_symlink");
+        // A link back to the root would recurse forever if followed
+        std::os::unix::fs::symlink(&root, root.join("a/loop")).unwrap();
+
+        let out = render_tree(&root, &TreeOptions::default()).unwrap();
+        assert!(out.contains("a/ (3 files)"));
+        assert!(out.contains(&format!("loop -> {}", root.display())));
+        assert!(!out.contains("loop/"));
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_executor_stats() {
+        let mut executor = ToolExecutor::new();
+        let mut call = ToolCall::new("stats", serde_json::json!({"path": "src"}));
+
+        assert!(executor.execute(&mut call).is_ok());
+        let json: serde_json::Value = serde_json::from_str(&call.get_output()).unwrap();
+        assert!(json["languages"]["rs"]["files"].as_u64().unwrap() > 0);
+        assert_eq!(json["scope"], "src");
+    }
+
+    #[test]
+    fn test_executor_docs_requires_query() {
+        let mut executor = ToolExecutor::new();
+        let mut call = ToolCall::new("docs", serde_json::json!({}));
+
+        assert!(executor.execute(&mut call).is_err());
+        assert!(call.error.as_ref().unwrap().contains("query"));
+    }
+
+    fn move_fixture(name: &str) -> (std::path::PathBuf, ToolExecutor) {
+        let root =
+            std::env::temp_dir().join(format!("hyle_test_move_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/old.rs"), "fn old() {}\n").unwrap();
+        fs::write(root.join("src/taken.rs"), "fn taken() {}\n").unwrap();
+        let executor = ToolExecutor::new().with_work_dir(&root);
+        (root, executor)
+    }
+
+    fn run_move(executor: &mut ToolExecutor, args: serde_json::Value) -> (Result<()>, ToolCall) {
+        let mut call = ToolCall::new("move", args);
+        let result = executor.execute(&mut call);
+        (result, call)
+    }
+
+    #[test]
+    fn test_move_tool_renames() {
+        let (root, mut executor) = move_fixture("basic");
+        let (result, call) = run_move(
+            &mut executor,
+            serde_json::json!({"from": "src/old.rs", "to": "src/new.rs"}),
+        );
+        assert!(result.is_ok(), "{:?}", call.error);
+        assert!(call
+            .get_output()
+            .contains("Moved src/old.rs -> src/new.rs (renamed)"));
+        assert!(!root.join("src/old.rs").exists());
+        assert_eq!(
+            fs::read_to_string(root.join("src/new.rs")).unwrap(),
+            "fn old() {}\n"
+        );
+
+        // Missing source
+        let (result, _) = run_move(
+            &mut executor,
+            serde_json::json!({"from": "src/old.rs", "to": "src/other.rs"}),
+        );
+        assert!(result.unwrap_err().to_string().contains("does not exist"));
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_move_tool_across_dirs() {
+        let (root, mut executor) = move_fixture("cross_dir");
+        let (result, call) = run_move(
+            &mut executor,
+            serde_json::json!({"from": "src/old.rs", "to": "lib/nested/moved.rs"}),
+        );
+        assert!(result.is_ok(), "{:?}", call.error);
+        assert!(root.join("lib/nested/moved.rs").exists());
+        assert!(!root.join("src/old.rs").exists());
+
+        // Nothing leaves the work dir
+        let (result, _) = run_move(
+            &mut executor,
+            serde_json::json!({"from": "src/taken.rs", "to": "../escaped.rs"}),
+        );
+        assert!(result.unwrap_err().to_string().contains("outside"));
+        assert!(root.join("src/taken.rs").exists());
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_delete_tool_trashes_and_restores() {
+        let (root, mut executor) = move_fixture("delete");
+        let mut call = ToolCall::new("delete", serde_json::json!({"path": "src/old.rs"}));
+        assert!(executor.execute(&mut call).is_ok(), "{:?}", call.error);
+        assert!(call.get_output().contains("restore with /undo"));
+        assert!(!root.join("src/old.rs").exists());
+
+        // It sits in a timestamped trash entry, not unlinked
+        let entries: Vec<_> = fs::read_dir(root.join(TRASH_DIR))
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            fs::read_to_string(entries[0].path().join("old.rs")).unwrap(),
+            "fn old() {}\n"
+        );
+
+        // Directories need recursive
+        let mut call = ToolCall::new("delete", serde_json::json!({"path": "src"}));
+        assert!(executor.execute(&mut call).is_err());
+        assert!(call.error.unwrap().contains("recursive"));
+        let mut call = ToolCall::new(
+            "delete",
+            serde_json::json!({"path": "src", "recursive": true}),
+        );
+        assert!(executor.execute(&mut call).is_ok());
+        assert!(!root.join("src").exists());
+
+        // Newest first: the directory, then the file inside it
+        assert_eq!(restore_latest(&root).unwrap().as_deref(), Some("src"));
+        assert!(root.join("src/taken.rs").exists());
+        assert_eq!(
+            restore_latest(&root).unwrap().as_deref(),
+            Some("src/old.rs")
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("src/old.rs")).unwrap(),
+            "fn old() {}\n"
+        );
+        assert_eq!(restore_latest(&root).unwrap(), None);
+
+        // The trash itself and paths outside the work dir are off limits
+        for path in [".hyle/trash", "../elsewhere.rs", "."] {
+            let mut call = ToolCall::new("delete", serde_json::json!({"path": path}));
+            assert!(executor.execute(&mut call).is_err(), "{}", path);
+        }
+
+        // An origin pointing out of root is refused, not followed
+        fs::write(root.join("kept.rs"), "").unwrap();
+        let entry = trash_path(&root, &root.join("kept.rs"), false).unwrap();
+        fs::write(entry.join(TRASH_ORIGIN), "../escaped.rs").unwrap();
+        assert!(restore_latest(&root).is_err());
+        assert!(!root.parent().unwrap().join("escaped.rs").exists());
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_delete_tool_honors_protected_paths() {
+        let (root, executor) = move_fixture("delete_protected");
+        let mut executor = executor.with_protected_paths(crate::config::ProtectedPaths::default());
+        fs::write(root.join("Cargo.lock"), "# generated\n").unwrap();
+        let mut call = ToolCall::new("delete", serde_json::json!({"path": "Cargo.lock"}));
+        assert!(executor.execute(&mut call).is_err());
+        assert!(call.error.unwrap().contains("generated file"));
+        assert!(root.join("Cargo.lock").exists());
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_move_tool_refuses_overwrite() {
+        let (root, mut executor) = move_fixture("overwrite");
+        let (result, _) = run_move(
+            &mut executor,
+            serde_json::json!({"from": "src/old.rs", "to": "src/taken.rs"}),
+        );
+        assert!(result.unwrap_err().to_string().contains("already exists"));
+        assert_eq!(
+            fs::read_to_string(root.join("src/taken.rs")).unwrap(),
+            "fn taken() {}\n"
+        );
+        assert!(root.join("src/old.rs").exists());
+
+        let (result, _) = run_move(
+            &mut executor,
+            serde_json::json!({"from": "src/old.rs", "to": "src/taken.rs", "force": true}),
+        );
+        assert!(result.is_ok());
+        assert_eq!(
+            fs::read_to_string(root.join("src/taken.rs")).unwrap(),
+            "fn old() {}\n"
+        );
+
+        fs::remove_dir_all(&root).ok();
+    }
 }
@@ -9,6 +9,9 @@
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
+use crate::session::Session;
+use crate::tools::ToolCallTracker;
+
 /// A single trace sample
 #[derive(Debug, Clone)]
 pub struct TraceSample {
@@ -121,6 +124,11 @@ pub struct TokenTrace {
     pub tokens_per_sec: TraceBuffer,
     pub total_prompt: u64,
     pub total_completion: u64,
+    /// Prompt tokens served from / written to the provider's cache
+    pub total_cache_read: u64,
+    pub total_cache_write: u64,
+    /// Completion tokens of replies thrown away by /regenerate
+    pub total_discarded: u64,
 }
 
 impl TokenTrace {
@@ -131,6 +139,9 @@ impl TokenTrace {
             tokens_per_sec: TraceBuffer::new("Rate", "tok/s", max_samples),
             total_prompt: 0,
             total_completion: 0,
+            total_cache_read: 0,
+            total_cache_write: 0,
+            total_discarded: 0,
         }
     }
 
@@ -145,9 +156,40 @@ impl TokenTrace {
         }
     }
 
+    pub fn record_cache(&mut self, cache_read: u32, cache_write: u32) {
+        self.total_cache_read += cache_read as u64;
+        self.total_cache_write += cache_write as u64;
+    }
+
+    /// Move a discarded reply's tokens out of the completion total, so the
+    /// reply that replaces it isn't counted on top
+    pub fn discard(&mut self, completion: u64) {
+        let completion = completion.min(self.total_completion);
+        self.total_completion -= completion;
+        self.total_discarded += completion;
+    }
+
     pub fn total(&self) -> u64 {
         self.total_prompt + self.total_completion
     }
+
+    /// Share of prompt tokens served from cache (0.0 - 1.0)
+    pub fn cache_hit_ratio(&self) -> f64 {
+        if self.total_prompt == 0 {
+            return 0.0;
+        }
+        self.total_cache_read as f64 / self.total_prompt as f64
+    }
+}
+
+/// How a request fits the context window, checked before sending
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextBudget {
+    Ok,
+    /// Above the warning threshold but still fits
+    Warning,
+    /// Exceeds the window; the request would be truncated or rejected
+    Over,
 }
 
 /// Context window trace
@@ -182,6 +224,154 @@ impl ContextTrace {
     pub fn is_full(&self) -> bool {
         self.usage.last().map(|v| v >= 100.0).unwrap_or(false)
     }
+
+    /// Classify a request of `tokens` against the window
+    pub fn budget(&self, tokens: u32) -> ContextBudget {
+        let ratio = tokens as f64 / self.context_window as f64;
+        if ratio > 1.0 {
+            ContextBudget::Over
+        } else if ratio > self.warn_threshold {
+            ContextBudget::Warning
+        } else {
+            ContextBudget::Ok
+        }
+    }
+
+    /// Record the estimated size of a request about to be sent, so the
+    /// indicator reflects it before the response arrives
+    pub fn record_request(&mut self, tokens: u32) -> ContextBudget {
+        self.record(tokens);
+        self.budget(tokens)
+    }
+
+    /// Gauge for a request of `tokens`
+    pub fn gauge(&self, tokens: u32) -> ContextGauge {
+        ContextGauge {
+            tokens,
+            window: self.context_window,
+            budget: self.budget(tokens),
+        }
+    }
+}
+
+/// Estimated size of the next request against the context window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextGauge {
+    pub tokens: u32,
+    pub window: u32,
+    pub budget: ContextBudget,
+}
+
+impl ContextGauge {
+    pub fn percent(&self) -> u32 {
+        if self.window == 0 {
+            return 0;
+        }
+        (self.tokens as u64 * 100 / self.window as u64) as u32
+    }
+
+    /// e.g. "~12.3K/128.0K (9%)"
+    pub fn label(&self) -> String {
+        format!(
+            "~{}/{} ({}%)",
+            format_count(self.tokens as u64),
+            format_count(self.window as u64),
+            self.percent()
+        )
+    }
+}
+
+/// Running estimate of the next request while the user types
+///
+/// Estimating the assembled history means shaping it with the context
+/// strategy, so that part is cached until `key` changes; the draft is
+/// added on top each frame.
+#[derive(Debug, Default)]
+pub struct ContextMeter {
+    key: Option<u64>,
+    base: u32,
+}
+
+impl ContextMeter {
+    /// Tokens of everything but the draft; `estimate` only runs when `key`
+    /// differs from the last call
+    pub fn base(&mut self, key: u64, estimate: impl FnOnce() -> u32) -> u32 {
+        if self.key != Some(key) {
+            self.base = estimate();
+            self.key = Some(key);
+        }
+        self.base
+    }
+
+    /// Gauge for sending `draft` next; slash commands aren't sent, so they
+    /// don't count
+    pub fn gauge(&self, context: &ContextTrace, draft: &str) -> ContextGauge {
+        let draft = if draft.trim_start().starts_with('/') {
+            0
+        } else {
+            crate::cognitive::estimate_tokens(draft) as u32
+        };
+        context.gauge(self.base + draft)
+    }
+}
+
+/// What the auto-compact policy wants done about the next request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompactDecision {
+    /// Off, or under the threshold
+    Idle,
+    /// Over the threshold mid-reply; ask once it finishes
+    Deferred,
+    /// Just crossed the threshold: ask now
+    Prompt,
+    /// Already asked since crossing; asks again after usage drops below
+    Asked,
+}
+
+/// Offers `/compact` once the next request would fill `threshold` percent
+/// of the context window, never while a reply is being generated
+#[derive(Debug, Clone, Default)]
+pub struct AutoCompact {
+    pub threshold: Option<u32>,
+    asked: bool,
+}
+
+impl AutoCompact {
+    pub fn new(threshold: Option<u32>) -> Self {
+        Self {
+            threshold,
+            asked: false,
+        }
+    }
+
+    /// Decide for a next request at `percent` of the window
+    pub fn decide(&mut self, percent: u32, generating: bool) -> CompactDecision {
+        let Some(threshold) = self.threshold else {
+            return CompactDecision::Idle;
+        };
+        if percent < threshold {
+            self.asked = false;
+            return CompactDecision::Idle;
+        }
+        if self.asked {
+            CompactDecision::Asked
+        } else if generating {
+            CompactDecision::Deferred
+        } else {
+            self.asked = true;
+            CompactDecision::Prompt
+        }
+    }
+
+    /// Status bar note for `decision`, e.g. "compact at 85%"
+    pub fn status(&self, decision: CompactDecision) -> Option<String> {
+        let threshold = self.threshold?;
+        Some(match decision {
+            CompactDecision::Idle => format!("compact at {}%", threshold),
+            CompactDecision::Deferred => "compact after reply".into(),
+            CompactDecision::Prompt | CompactDecision::Asked => "compact? y/n".into(),
+        })
+    }
 }
 
 /// Memory trace (RSS)
@@ -326,6 +516,92 @@ impl Traces {
     }
 }
 
+/// Accounting printed when the TUI exits
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionSummary {
+    pub session_id: String,
+    /// User and assistant messages
+    pub messages: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_write_tokens: u64,
+    /// Estimated from model pricing, in $
+    pub cost: f64,
+    pub tools_run: usize,
+    pub tools_succeeded: usize,
+    pub duration: Duration,
+}
+
+impl SessionSummary {
+    pub fn new(
+        session: &Session,
+        traces: &Traces,
+        tools: &ToolCallTracker,
+        cost: f64,
+        duration: Duration,
+    ) -> Self {
+        let metrics = tools.metrics();
+        Self {
+            session_id: session.meta.id.clone(),
+            messages: session
+                .messages
+                .iter()
+                .filter(|m| m.role == "user" || m.role == "assistant")
+                .count(),
+            prompt_tokens: traces.tokens.total_prompt,
+            completion_tokens: traces.tokens.total_completion,
+            cache_read_tokens: traces.tokens.total_cache_read,
+            cache_write_tokens: traces.tokens.total_cache_write,
+            cost,
+            tools_run: metrics.iter().map(|m| m.calls).sum(),
+            tools_succeeded: metrics.iter().map(|m| m.succeeded).sum(),
+            duration,
+        }
+    }
+
+    /// Share of tool calls that succeeded (0.0 - 1.0)
+    pub fn tool_success_rate(&self) -> f64 {
+        if self.tools_run == 0 {
+            return 0.0;
+        }
+        self.tools_succeeded as f64 / self.tools_run as f64
+    }
+
+    pub fn render(&self) -> Vec<String> {
+        let secs = self.duration.as_secs();
+        let mut tokens = format!(
+            "Tokens:   {} prompt, {} completion",
+            format_count(self.prompt_tokens),
+            format_count(self.completion_tokens)
+        );
+        if self.cache_read_tokens + self.cache_write_tokens > 0 {
+            tokens.push_str(&format!(
+                ", {} cache read, {} cache write",
+                format_count(self.cache_read_tokens),
+                format_count(self.cache_write_tokens)
+            ));
+        }
+        let tools = if self.tools_run == 0 {
+            "Tools:    none run".to_string()
+        } else {
+            format!(
+                "Tools:    {} run, {:.0}% ok",
+                self.tools_run,
+                self.tool_success_rate() * 100.0
+            )
+        };
+        vec![
+            format!("Session {}", self.session_id),
+            format!("Messages: {}", self.messages),
+            tokens,
+            format!("Cost:     ~${:.4}", self.cost),
+            tools,
+            format!("Duration: {}m {:02}s", secs / 60, secs % 60),
+        ]
+    }
+}
+
 fn format_count(n: u64) -> String {
     if n >= 1_000_000 {
         format!("{:.1}M", n as f64 / 1_000_000.0)
@@ -376,4 +652,192 @@ mod tests {
         assert_eq!(trace.total(), 150);
         assert_eq!(trace.tokens_per_sec.last(), Some(50.0));
     }
+
+    #[test]
+    fn test_token_trace_discard() {
+        let mut trace = TokenTrace::new(10);
+        trace.record(100, 50, 1.0);
+        trace.record(120, 40, 1.0);
+        trace.discard(40);
+
+        assert_eq!(trace.total_completion, 50);
+        assert_eq!(trace.total_discarded, 40);
+        assert_eq!(trace.total(), 270);
+
+        // Never more than was recorded
+        trace.discard(500);
+        assert_eq!(trace.total_completion, 0);
+        assert_eq!(trace.total_discarded, 90);
+    }
+
+    #[test]
+    fn test_token_trace_cache_ratio() {
+        let mut trace = TokenTrace::new(10);
+        assert_eq!(trace.cache_hit_ratio(), 0.0);
+
+        trace.record(1000, 50, 1.0);
+        trace.record_cache(600, 100);
+        trace.record(1000, 50, 1.0);
+        trace.record_cache(900, 0);
+
+        assert_eq!(trace.total_cache_read, 1500);
+        assert_eq!(trace.total_cache_write, 100);
+        assert!((trace.cache_hit_ratio() - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_context_budget() {
+        let ctx = ContextTrace::new(1000, 10);
+        assert_eq!(ctx.budget(500), ContextBudget::Ok);
+        assert_eq!(ctx.budget(900), ContextBudget::Warning);
+        assert_eq!(ctx.budget(1000), ContextBudget::Warning);
+        assert_eq!(ctx.budget(1001), ContextBudget::Over);
+    }
+
+    #[test]
+    fn test_session_summary() {
+        let root = std::env::temp_dir().join(format!("hyle_test_summary_{}", std::process::id()));
+        let mut session = Session::new_in(&root, "test/model").unwrap();
+        session.add_system_message("context").unwrap();
+        session.add_user_message("fix the build").unwrap();
+        session.add_assistant_message("done", Some(12)).unwrap();
+
+        let mut traces = Traces::new(8192);
+        traces.tokens.record(1000, 200, 2.0);
+        traces.tokens.record(1500, 300, 2.0);
+        traces.tokens.record_cache(800, 100);
+
+        let mut tools = ToolCallTracker::new();
+        for ok in [true, true, true, false] {
+            let mut call = crate::tools::ToolCall::new("bash", serde_json::json!({}));
+            call.start();
+            if ok {
+                call.complete();
+            } else {
+                call.fail("exit 1");
+            }
+            tools.add(call);
+        }
+        // Still running: not counted
+        let mut running = crate::tools::ToolCall::new("read", serde_json::json!({}));
+        running.start();
+        tools.add(running);
+
+        let summary =
+            SessionSummary::new(&session, &traces, &tools, 0.0123, Duration::from_secs(125));
+        assert_eq!(summary.session_id, session.meta.id);
+        assert_eq!(summary.messages, 2);
+        assert_eq!(summary.prompt_tokens, 2500);
+        assert_eq!(summary.completion_tokens, 500);
+        assert_eq!(summary.cache_read_tokens, 800);
+        assert_eq!(summary.cache_write_tokens, 100);
+        assert_eq!(summary.tools_run, 4);
+        assert_eq!(summary.tools_succeeded, 3);
+        assert!((summary.tool_success_rate() - 0.75).abs() < 1e-9);
+
+        let lines = summary.render();
+        assert_eq!(lines[1], "Messages: 2");
+        assert_eq!(
+            lines[2],
+            "Tokens:   2.5K prompt, 500 completion, 800 cache read, 100 cache write"
+        );
+        assert_eq!(lines[3], "Cost:     ~$0.0123");
+        assert_eq!(lines[4], "Tools:    4 run, 75% ok");
+        assert_eq!(lines[5], "Duration: 2m 05s");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_over_budget_request_flagged_before_send() {
+        // Size the window relative to the system prompt, which varies by environment
+        let small = crate::client::estimate_prompt_tokens("hi", None, &[]);
+        let mut ctx = ContextTrace::new(small * 2, 10);
+
+        let long = "x".repeat(small as usize * 8);
+        let history = vec![serde_json::json!({"role": "user", "content": long})];
+        let tokens = crate::client::estimate_prompt_tokens("and now this", None, &history);
+
+        assert_eq!(ctx.record_request(tokens), ContextBudget::Over);
+        // The indicator updates from the estimate, with no response yet
+        assert!(ctx.is_full());
+
+        assert_eq!(ctx.record_request(small), ContextBudget::Ok);
+        assert!(!ctx.is_full());
+    }
+
+    #[test]
+    fn test_auto_compact_prompts_once_over_threshold() {
+        let mut auto = AutoCompact::new(Some(85));
+        assert_eq!(auto.decide(40, false), CompactDecision::Idle);
+        assert_eq!(
+            auto.status(CompactDecision::Idle).as_deref(),
+            Some("compact at 85%")
+        );
+        assert_eq!(auto.decide(85, false), CompactDecision::Prompt);
+        // Declined: not asked again until usage drops and crosses again
+        assert_eq!(auto.decide(90, false), CompactDecision::Asked);
+        assert_eq!(auto.decide(60, false), CompactDecision::Idle);
+        assert_eq!(auto.decide(86, false), CompactDecision::Prompt);
+
+        let mut off = AutoCompact::new(None);
+        assert_eq!(off.decide(99, false), CompactDecision::Idle);
+        assert_eq!(off.status(CompactDecision::Idle), None);
+    }
+
+    #[test]
+    fn test_auto_compact_defers_while_generating() {
+        let mut auto = AutoCompact::new(Some(80));
+        assert_eq!(auto.decide(95, true), CompactDecision::Deferred);
+        assert_eq!(
+            auto.status(CompactDecision::Deferred).as_deref(),
+            Some("compact after reply")
+        );
+        assert_eq!(auto.decide(97, true), CompactDecision::Deferred);
+        // Asked as soon as the reply is done
+        assert_eq!(auto.decide(97, false), CompactDecision::Prompt);
+        assert_eq!(auto.decide(97, true), CompactDecision::Asked);
+    }
+
+    #[test]
+    fn test_context_meter_gauge() {
+        let history: Vec<serde_json::Value> = (0..20)
+            .map(|i| {
+                let role = if i % 2 == 0 { "user" } else { "assistant" };
+                serde_json::json!({"role": role, "content": "y".repeat(400)})
+            })
+            .collect();
+        let base = crate::client::estimate_prompt_tokens("", None, &history);
+        let ctx = ContextTrace::new(base * 2, 10);
+
+        let mut meter = ContextMeter::default();
+        assert_eq!(meter.base(1, || base), base);
+        // Unchanged history: the estimate is reused
+        assert_eq!(meter.base(1, || panic!("history unchanged")), base);
+
+        // The draft adds ~4 chars per token
+        let gauge = meter.gauge(&ctx, &"d".repeat(400));
+        assert_eq!(gauge.tokens, base + 100);
+        assert_eq!(gauge.budget, ContextBudget::Ok);
+        assert_eq!(meter.gauge(&ctx, "/clear everything").tokens, base);
+
+        // Approaching and passing the window
+        let near = meter.gauge(&ctx, &"d".repeat(base as usize * 4 * 7 / 10));
+        assert_eq!(near.budget, ContextBudget::Warning);
+        assert!(near.percent() > 80);
+        let over = meter.gauge(&ctx, &"d".repeat(base as usize * 5));
+        assert_eq!(over.budget, ContextBudget::Over);
+
+        // A narrower strategy shrinks the base once the key changes
+        let window = crate::config::ContextStrategy::SlidingWindow { turns: 2 }
+            .assemble(&history, |_, _| String::new());
+        let narrowed = meter.base(2, || {
+            crate::client::estimate_prompt_tokens("", None, &window)
+        });
+        assert!(narrowed < base);
+        assert_eq!(meter.gauge(&ctx, "").tokens, narrowed);
+
+        let gauge = ContextTrace::new(128_000, 10).gauge(12_345);
+        assert_eq!(gauge.label(), "~12.3K/128.0K (9%)");
+    }
 }
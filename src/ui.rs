@@ -19,28 +19,50 @@ use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Tabs, Wrap},
+    widgets::{
+        block::Title, Block, Borders, List, ListItem, ListState, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Tabs, Wrap,
+    },
 };
 use std::io::{self, Write};
 use std::path::PathBuf;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
-use crate::agent::{execute_tool_calls, format_tool_results, parse_tool_calls};
+use crate::agent::{
+    ask_user_question, execute_tool_calls, format_tool_results, parse_tool_calls, unmatched,
+    ParsedToolCall, ResultFormat, StreamingToolParser, ASK_USER_TOOL, PLAN_TOOL,
+};
+use crate::agent_loop::{
+    AgentLoop, FocusGate, LoopEvent, LoopStep, PendingQuestion, PlanGate, FOCUS_TOOL,
+};
 use crate::client::{self, StreamEvent};
 use crate::cognitive::{
-    extract_keywords, CognitiveConfig, ContextCategory, LoopDecision, Momentum, SalienceContext,
-    SalienceTier, StuckDetector,
+    extract_keywords, CognitiveConfig, ContextCategory, LoopDecision, Momentum, Recovery,
+    SalienceContext, SalienceTier, StuckDetector, StuckReason, StuckRecovery, MAX_STUCK_RECOVERIES,
 };
-use crate::eval::ModelTracker;
+use crate::compare::{within_cost_ceiling, CompareConfig, LiveCompare, COMPARE_COST_CEILING};
+use crate::completion::PathCompletion;
+use crate::config::{ContextStrategy, EnvPolicy, InjectionPolicy, SamplingParams};
+use crate::eval::{Leaderboard, ModelTracker, SwitchReason};
+use crate::intake::Ingested;
 use crate::intent::{IntentStack, IntentView, Verbosity};
+use crate::mentions::MentionContext;
+use crate::minimap::{self, Mark};
 use crate::models::Model;
 use crate::project::{Project, ProjectType};
-use crate::session::Session;
-use crate::skills::{execute_slash_command_with_context, is_slash_command, SlashContext};
-use crate::telemetry::{PressureLevel, Telemetry, TelemetryMsg, TelemetrySampler, ThrottleMode};
-use crate::tools::{ToolCallDisplay, ToolCallTracker, ToolExecutor};
-use crate::traces::Traces;
+use crate::session::{Autosave, IdleTimeout, Session, COMPACT_KEEP_RECENT};
+use crate::skills::{
+    execute_slash_command_with_context, is_slash_command, CommandCapture, SlashAction, SlashContext,
+};
+use crate::telemetry::{
+    ChannelStats, FrameDrain, PressureLevel, Telemetry, TelemetryMsg, TelemetrySampler,
+    ThrottleMode, FRAME_DRAIN_BUDGET, UI_CHANNEL_CAPACITY,
+};
+use crate::tools::{ToolCall, ToolCallDisplay, ToolCallTracker, ToolExecutor};
+use crate::traces::{
+    AutoCompact, CompactDecision, ContextBudget, ContextMeter, SessionSummary, Traces,
+};
 
 // ═══════════════════════════════════════════════════════════════
 // API KEY PROMPT
@@ -111,17 +133,25 @@ fn run_picker(
     models: &[Model],
 ) -> Result<String> {
     let matcher = SkimMatcherV2::default();
+    let scores = crate::benchmark::latest_scores();
     let mut filter = String::new();
+    let mut sort = crate::models::PickerSort::default();
+    let mut tools_only = false;
     let mut list_state = ListState::default();
     list_state.select(Some(0));
 
     loop {
-        // Filter models
+        // Sort, then filter; fuzzy rank wins over the sort order while typing
+        let mut sorted: Vec<_> = models
+            .iter()
+            .filter(|m| !tools_only || m.supports_tools())
+            .collect();
+        sorted.sort_by(|a, b| sort.compare(a, b, &scores));
         let filtered: Vec<_> = if filter.is_empty() {
-            models.iter().collect()
+            sorted
         } else {
-            let mut scored: Vec<_> = models
-                .iter()
+            let mut scored: Vec<_> = sorted
+                .into_iter()
                 .filter_map(|m| matcher.fuzzy_match(&m.id, &filter).map(|score| (m, score)))
                 .collect();
             scored.sort_by(|a, b| b.1.cmp(&a.1));
@@ -157,18 +187,16 @@ fn run_picker(
             // Model list
             let items: Vec<ListItem> = filtered
                 .iter()
-                .map(|m| {
-                    let ctx = format!("{}k", m.context_length / 1000);
-                    let free = if m.is_free() { " [FREE]" } else { "" };
-                    ListItem::new(format!("{} ({}){}", m.id, ctx, free))
-                })
+                .map(|m| ListItem::new(crate::models::picker_row(m, scores.get(&m.id).copied())))
                 .collect();
 
             let list = List::new(items)
                 .block(Block::default().borders(Borders::ALL).title(format!(
-                    "Models ({}/{})",
+                    "Models ({}/{}) by {}{}",
                     filtered.len(),
-                    models.len()
+                    models.len(),
+                    sort.label(),
+                    if tools_only { ", tools only" } else { "" }
                 )))
                 .highlight_style(
                     Style::default()
@@ -179,8 +207,10 @@ fn run_picker(
             f.render_stateful_widget(list, chunks[1], &mut list_state);
 
             // Help
-            let help = Paragraph::new("Enter: select | Esc: cancel | Type to filter")
-                .style(Style::default().fg(Color::DarkGray));
+            let help = Paragraph::new(
+                "Enter: select | Esc: cancel | Type to filter | Tab: sort | Ctrl+T: tools only",
+            )
+            .style(Style::default().fg(Color::DarkGray));
             f.render_widget(help, chunks[2]);
         })?;
 
@@ -208,6 +238,18 @@ fn run_picker(
                         let i = list_state.selected().unwrap_or(0);
                         list_state.select(Some((i + 1).min(filtered.len().saturating_sub(1))));
                     }
+                    KeyCode::Tab => {
+                        sort = sort.next();
+                        list_state.select(Some(0));
+                    }
+                    KeyCode::Char('t')
+                        if key
+                            .modifiers
+                            .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                    {
+                        tools_only = !tools_only;
+                        list_state.select(Some(0));
+                    }
                     KeyCode::Char(c) => {
                         filter.push(c);
                         list_state.select(Some(0));
@@ -238,6 +280,7 @@ enum View {
     Git,       // Git navigation
     Artifacts, // Generated files, diffs
     Plans,     // Task plans
+    Compare,   // One prompt, several models side by side
 }
 
 impl View {
@@ -251,6 +294,7 @@ impl View {
             View::Git,
             View::Artifacts,
             View::Plans,
+            View::Compare,
         ]
     }
 
@@ -268,13 +312,14 @@ impl View {
             View::Git => "Git",
             View::Artifacts => "Artifacts",
             View::Plans => "Plans",
+            View::Compare => "Compare",
         }
     }
 
     fn is_overlay(&self) -> bool {
         matches!(
             self,
-            View::Prompts | View::Git | View::Artifacts | View::Plans
+            View::Prompts | View::Git | View::Artifacts | View::Plans | View::Compare
         )
     }
 }
@@ -293,6 +338,8 @@ type Tab = View;
 /// TUI messages from background tasks
 enum TuiMsg {
     Token(String),
+    /// Finished calls from a background tool batch, for tool metrics
+    ToolCallsFinished(Vec<ToolCall>),
     Done(client::TokenUsage),
     Error(String),
     /// Continue agentic loop with tool results
@@ -322,6 +369,61 @@ enum TuiMsg {
     ToolsComplete {
         feedback: String,
     },
+    /// Summary for /compact; `upto` is the end of the summarized range,
+    /// `generation` the conversation it was started on
+    CompactDone {
+        generation: u64,
+        upto: usize,
+        summary: Result<String, String>,
+    },
+    /// Command finished for /run
+    RunDone(CommandCapture),
+    /// Overview written by /summarize
+    SummarizeDone(Result<PathBuf, String>),
+    /// Catalog reloaded by /models refresh
+    ModelsReloaded(Result<crate::models::Reloaded, String>),
+    /// Document fetched/read for /ingest
+    IngestDone {
+        source: String,
+        result: Result<Ingested, String>,
+    },
+    /// Diff proposed by the model for `/improve apply`
+    ImproveProposal {
+        target: String,
+        diff: Result<String, String>,
+    },
+    /// Proposed fix applied and tested (or reverted)
+    ImproveDone {
+        output: String,
+        success: bool,
+    },
+    /// Chunk from one model in the comparison numbered `run`
+    CompareToken {
+        run: u64,
+        slot: usize,
+        text: String,
+    },
+    CompareDone {
+        run: u64,
+        slot: usize,
+        usage: client::TokenUsage,
+    },
+    CompareFailed {
+        run: u64,
+        slot: usize,
+        error: String,
+    },
+}
+
+/// Folds consecutive streamed tokens into one message for the frame drain
+fn merge_tokens(last: &mut TuiMsg, next: TuiMsg) -> Option<TuiMsg> {
+    match (last, next) {
+        (TuiMsg::Token(text), TuiMsg::Token(more)) => {
+            text.push_str(&more);
+            None
+        }
+        (_, next) => Some(next),
+    }
 }
 
 /// Main TUI state
@@ -329,9 +431,12 @@ struct TuiState {
     tab: Tab,
     input: String,
     cursor_pos: usize, // Cursor position within input
+    completion: Option<PathCompletion>,
     output: Vec<String>,
     log: Vec<String>,
     telemetry: Telemetry,
+    /// Background task -> UI channel load, copied from the frame drain
+    channel_stats: ChannelStats,
     traces: Traces,
     throttle: ThrottleMode,
     is_generating: bool,
@@ -348,6 +453,9 @@ struct TuiState {
     // Current response for session saving
     current_response: String,
 
+    // First line after the last "> " prompt echo, for the minimap
+    last_response_line: Option<usize>,
+
     // Scroll state for long conversations
     scroll_offset: u16,
     auto_scroll: bool,
@@ -393,14 +501,14 @@ struct TuiState {
 
     // Model quality tracking
     model_tracker: ModelTracker,
+    leaderboard: Leaderboard, // Previous sessions; this one merges in on exit
     last_prompt: String,
 
     // Project context for LLM
     project: Option<Project>,
 
     // Agentic loop state
-    loop_iteration: u8,
-    max_iterations: u8,
+    agent_loop: AgentLoop,
 
     // Multi-granularity intent tracking
     intent_stack: IntentStack,
@@ -410,6 +518,8 @@ struct TuiState {
     cognitive_config: CognitiveConfig,
     momentum: Momentum,
     stuck_detector: StuckDetector,
+    /// Recoveries tried for the current task when the loop got stuck
+    stuck_recovery: StuckRecovery,
 
     // Salience-aware context
     salience_keywords: Vec<String>,
@@ -418,10 +528,73 @@ struct TuiState {
     // Model management for auto-switch on rate limit
     current_model: String,
     rate_limited_models: Vec<String>,
+    // Free models to switch to on a rate limit, from the model catalog
+    free_fallbacks: Vec<String>,
     api_key: String,
     rate_limit_pending: bool, // True when we hit rate limit - ESC should offer model switch
     pending_retry: bool,      // True when we should retry last prompt with new model
+    confirm_clear: bool,      // /clear asked for y/n; next input answers it
     session_cost: f64,        // Running cost for this session (in $)
+    session_start: std::time::Instant,
+
+    // `/improve apply`: diff awaiting y/n, auto-applied in trust mode
+    pending_improvement: Option<String>,
+    trust_mode: bool,
+
+    // `ask_user`: the model's question; the next input answers it
+    pending_question: Option<PendingQuestion>,
+    // Plan-first mode and the current plan's progress
+    plan_gate: PlanGate,
+    // Mirror of the session's /focus set; refuses edits outside it
+    focus_gate: FocusGate,
+
+    // Read-only tool calls started while the response is still streaming
+    tool_parser: StreamingToolParser,
+    early_tools: Vec<(ParsedToolCall, tokio::task::JoinHandle<String>)>,
+
+    // Latest /compare run; stale messages from earlier runs are dropped
+    compare: Option<LiveCompare>,
+    compare_run: u64,
+    compare_start: std::time::Instant,
+    compare_tasks: Vec<tokio::task::JoinHandle<()>>,
+
+    // Mirror of the session's attachments, for the header and requests
+    attachments: Vec<String>,
+    // Mirror of the session's pinned notes
+    pins: Vec<String>,
+
+    // Quality-driven upgrades (distinct from rate-limit fallback)
+    upgrade_models: Vec<String>,
+    auto_upgrade: bool,
+    upgrade_offered: Option<String>, // Last suggestion, so we don't repeat it every response
+    last_switch_reason: Option<SwitchReason>,
+
+    // Sampling overrides sent with every request
+    sampling: SamplingParams,
+    // One-off override for the next retry (/regenerate <temperature>),
+    // merged over `sampling` when the request is built
+    retry_sampling: Option<SamplingParams>,
+
+    // How history is assembled into each request (/context)
+    context_strategy: ContextStrategy,
+    /// Estimate of the next request, for the input gauge
+    context_meter: ContextMeter,
+    /// When to offer /compact, and what it decided for the next request
+    auto_compact: AutoCompact,
+    compact_decision: CompactDecision,
+    /// Auto-compact asked; y/n on an empty input answers
+    confirm_compact: bool,
+    /// A /compact summary is being generated
+    compacting: bool,
+    /// Bumped when the conversation is replaced, so a summary of the old
+    /// one is dropped
+    compact_generation: u64,
+    /// Environment policy for bash tool subprocesses
+    env_policy: EnvPolicy,
+    /// Cap on bash tool timeouts
+    max_command_timeout_ms: u64,
+    /// Guard applied to tool output and ingested text
+    injection_guard: InjectionPolicy,
 
     // Agent mode - autonomous tool chaining like Claude Code
     agent_mode: bool,
@@ -458,6 +631,8 @@ struct DetectedSession {
     tokens: u64,
     messages: usize,
     integration: Integration,
+    /// Session this was forked from
+    parent: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -475,14 +650,403 @@ enum Integration {
     ReadOnly, // Can view but not control
 }
 
-/// Free models to fall back to on rate limit
-const FREE_MODEL_FALLBACKS: &[&str] = &[
-    "meta-llama/llama-3.2-3b-instruct:free",
-    "google/gemma-2-9b-it:free",
-    "qwen/qwen-2-7b-instruct:free",
-    "mistralai/mistral-7b-instruct:free",
-    "microsoft/phi-3-mini-128k-instruct:free",
-];
+/// Save the current session and continue in another one
+fn switch_session(state: &mut TuiState, session: &mut Session, query: &str) -> Result<()> {
+    if state.is_generating || state.executing_tools {
+        anyhow::bail!("Wait for the current response to finish before switching sessions");
+    }
+    let id = crate::session::resolve_session_id(query)?;
+    if id == session.meta.id {
+        anyhow::bail!("Already in session {}", id);
+    }
+
+    let loaded = Session::load(&id)?;
+    session.save_meta()?;
+    *session = loaded;
+
+    state.reset_conversation();
+    state.confirm_clear = false;
+    state.confirm_compact = false;
+    state.pending_improvement = None;
+    state.attachments = session.meta.attachments.clone();
+    state.pins = session.meta.pins.clone();
+    state.focus_gate = FocusGate::new(&session.meta.focus);
+    state.output.push(format!(
+        "─── Session {} ({} messages) ───",
+        id,
+        session.messages.len()
+    ));
+    state.show_history(&session.messages);
+    if session.meta.model != state.current_model {
+        state.log(format!(
+            "Session was started with {}; still using {}",
+            session.meta.model, state.current_model
+        ));
+    }
+    state.log(format!("Switched to session {}", id));
+    Ok(())
+}
+
+/// Throw away the last reply and ask the same prompt again
+fn regenerate_last(
+    state: &mut TuiState,
+    session: &mut Session,
+    temperature: Option<f32>,
+) -> Result<()> {
+    if state.is_generating || state.executing_tools {
+        anyhow::bail!("Wait for the current response to finish before regenerating");
+    }
+    // The summary in flight covers the reply that would be replaced
+    if state.compacting {
+        anyhow::bail!("Wait for /compact to finish before regenerating");
+    }
+    let Some(regen) = session.regenerate()? else {
+        anyhow::bail!("No reply to regenerate yet");
+    };
+
+    // Back out the discarded turn so the replacement isn't counted twice
+    state.traces.tokens.discard(regen.tokens);
+    if let Some(start) = state.output.iter().rposition(|l| l.starts_with("> ")) {
+        state.output.truncate(start + 1);
+    }
+    state.log(format!(
+        "Regenerating: dropped {} message(s), {} tokens",
+        regen.dropped, regen.tokens
+    ));
+
+    state.retry_sampling = temperature.map(|t| SamplingParams {
+        temperature: Some(t),
+        ..Default::default()
+    });
+    state.last_prompt = regen.prompt;
+    state.current_response.clear();
+    state.advance_loop(LoopEvent::Prompt);
+    state.is_generating = true;
+    state.ttft = None;
+    state.request_start = std::time::Instant::now();
+    state.last_token_time = std::time::Instant::now();
+    state.pending_retry = true;
+    state.mark_dirty();
+    Ok(())
+}
+
+/// Pick the top analyzer finding and ask the model for a diff that fixes it
+async fn propose_improvement(api_key: &str, model: &str) -> TuiMsg {
+    let task =
+        tokio::task::spawn_blocking(|| crate::bootstrap::SelfAnalyzer::new()?.improvement_task())
+            .await;
+    let (target, prompt) = match task {
+        Ok(Ok(Some(task))) => task,
+        Ok(Ok(None)) => {
+            return TuiMsg::ImproveProposal {
+                target: String::new(),
+                diff: Err("Nothing to fix: no lint findings, TODOs or doc gaps".into()),
+            }
+        }
+        Ok(Err(e)) => {
+            return TuiMsg::ImproveProposal {
+                target: String::new(),
+                diff: Err(e.to_string()),
+            }
+        }
+        Err(e) => {
+            return TuiMsg::ImproveProposal {
+                target: String::new(),
+                diff: Err(e.to_string()),
+            }
+        }
+    };
+
+    let diff = client::chat_completion_simple(api_key, model, &prompt, 4096)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|reply| {
+            crate::bootstrap::extract_diff(&reply)
+                .ok_or_else(|| "Model reply contained no unified diff".to_string())
+        });
+    TuiMsg::ImproveProposal {
+        target: target.describe(),
+        diff,
+    }
+}
+
+/// Apply a confirmed `/improve apply` diff and run the tests off the UI thread
+fn spawn_improvement_apply(state: &mut TuiState, tx: &mpsc::Sender<TuiMsg>, diff: String) {
+    state
+        .output
+        .push("[Applying fix and running tests...]".into());
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let result =
+            tokio::task::spawn_blocking(move || crate::skills::apply_improvement(&diff)).await;
+        let (output, success) = match result {
+            Ok(result) => (result.output, result.success),
+            Err(e) => (e.to_string(), false),
+        };
+        let _ = tx.send(TuiMsg::ImproveDone { output, success }).await;
+    });
+}
+
+/// Summarize all but the last few messages off the UI thread; the summary
+/// comes back as CompactDone
+fn spawn_compact(state: &mut TuiState, session: &Session, tx: &mpsc::Sender<TuiMsg>) {
+    let Some((upto, transcript)) = session.compaction_transcript(COMPACT_KEEP_RECENT) else {
+        state.output.push("[Nothing to compact yet]".into());
+        return;
+    };
+    state
+        .output
+        .push(format!("[Compacting {} messages...]", upto - 1));
+    state.compacting = true;
+    let generation = state.compact_generation;
+    let tx = tx.clone();
+    let api_key = state.api_key.clone();
+    let model = state.current_model.clone();
+    tokio::spawn(async move {
+        let prompt = crate::cognitive::summarizer_prompt(&transcript);
+        let summary = client::chat_completion_simple(&api_key, &model, &prompt, 1024)
+            .await
+            .map_err(|e| e.to_string());
+        let _ = tx
+            .send(TuiMsg::CompactDone {
+                generation,
+                upto,
+                summary,
+            })
+            .await;
+    });
+}
+
+/// Run a batch of tool calls on the blocking pool; resolves to their feedback
+fn spawn_tool_batch(
+    state: &TuiState,
+    tx: &mpsc::Sender<TuiMsg>,
+    calls: Vec<ParsedToolCall>,
+) -> tokio::task::JoinHandle<String> {
+    let run = tool_batch(state, tx);
+    tokio::task::spawn_blocking(move || run(calls))
+}
+
+/// A blocking runner for a batch of tool calls, set up from the current
+/// executor settings so it can be started later
+fn tool_batch(
+    state: &TuiState,
+    tx: &mpsc::Sender<TuiMsg>,
+) -> impl FnOnce(Vec<ParsedToolCall>) -> String + Send + 'static {
+    let tx = tx.clone();
+    let env_policy = state.env_policy.clone();
+    let max_timeout = state.max_command_timeout_ms;
+    let guard = state.injection_guard;
+    let format = state.result_format();
+    let mirror_log = state.tool_executor.mirror_log().map(|p| p.to_path_buf());
+    let work_dir = state.tool_executor.work_dir();
+    let read_cursors = state.tool_executor.read_cursors().clone();
+    let protected = state.tool_executor.protected_paths().clone();
+    let artifacts = crate::artifacts::ArtifactStore::open(&work_dir);
+    move |calls| {
+        // Create temporary executor and tracker for this batch
+        let mut executor = ToolExecutor::new()
+            .with_work_dir(work_dir)
+            .with_read_cursors(read_cursors)
+            .with_env_policy(env_policy)
+            .with_max_timeout(max_timeout)
+            .with_mirror(mirror_log)
+            .with_protected_paths(protected)
+            .with_artifacts(artifacts);
+        let mut tracker = ToolCallTracker::new();
+
+        let results = execute_tool_calls(&calls, &mut executor, &mut tracker);
+        let indices: Vec<usize> = results.iter().map(|(idx, _)| *idx).collect();
+        let finished = tracker.finished().into_iter().cloned().collect();
+        let _ = tx.blocking_send(TuiMsg::ToolCallsFinished(finished));
+        format_tool_results(&tracker, &indices, guard, format)
+    }
+}
+
+/// Stream `prompt` to every model at once, filling the Compare view
+///
+/// Models whose worst-case cost would take the run over
+/// `COMPARE_COST_CEILING` are skipped before anything is sent.
+fn spawn_comparison(
+    state: &mut TuiState,
+    tx: &mpsc::Sender<TuiMsg>,
+    history: Vec<serde_json::Value>,
+    models: Vec<String>,
+    prompt: String,
+) {
+    for task in state.compare_tasks.drain(..) {
+        task.abort();
+    }
+
+    let prompt_tokens = client::estimate_prompt_tokens(&prompt, state.project.as_ref(), &history);
+    let max_tokens = state
+        .sampling
+        .max_tokens
+        .unwrap_or(client::DEFAULT_MAX_TOKENS);
+    let worst_case = |model: &str| {
+        let usage = client::TokenUsage {
+            prompt_tokens,
+            completion_tokens: max_tokens,
+            total_tokens: prompt_tokens + max_tokens,
+            ..Default::default()
+        };
+        crate::models::calculate_cost(model, &usage)
+    };
+    let (models, skipped) = within_cost_ceiling(&models, COMPARE_COST_CEILING, worst_case);
+    for (model, cost) in &skipped {
+        state.output.push(format!(
+            "[Compare: skipped {} - up to ${:.4} would pass the ${:.2} ceiling]",
+            model, cost, COMPARE_COST_CEILING
+        ));
+    }
+    if models.len() < 2 {
+        state
+            .output
+            .push("[✗] Compare: fewer than two models fit under the cost ceiling".into());
+        state.mark_dirty();
+        return;
+    }
+
+    state.compare_run += 1;
+    state.compare = Some(LiveCompare::new(&prompt, &models));
+    state.compare_start = std::time::Instant::now();
+    if state.tab != View::Compare {
+        state.push_view(View::Compare);
+    }
+    state.log(format!("Comparing {}", models.join(", ")));
+
+    let run = state.compare_run;
+    let timeout = CompareConfig::default().timeout;
+    for (slot, model) in models.into_iter().enumerate() {
+        let tx = tx.clone();
+        let api_key = state.api_key.clone();
+        let project = state.project.clone();
+        let history = history.clone();
+        let prompt = prompt.clone();
+        let params = state.sampling.clone();
+        state.compare_tasks.push(tokio::spawn(async move {
+            let stream = stream_comparison(
+                &tx,
+                run,
+                slot,
+                &api_key,
+                &model,
+                &prompt,
+                project.as_ref(),
+                &history,
+                params,
+            );
+            let msg = match tokio::time::timeout(timeout, stream).await {
+                Ok(Ok(usage)) => TuiMsg::CompareDone { run, slot, usage },
+                Ok(Err(e)) => TuiMsg::CompareFailed {
+                    run,
+                    slot,
+                    error: e.to_string(),
+                },
+                Err(_) => TuiMsg::CompareFailed {
+                    run,
+                    slot,
+                    error: format!("Timeout after {}s", timeout.as_secs()),
+                },
+            };
+            let _ = tx.send(msg).await;
+        }));
+    }
+}
+
+/// Forward one comparison model's tokens; resolves to its usage
+#[allow(clippy::too_many_arguments)]
+async fn stream_comparison(
+    tx: &mpsc::Sender<TuiMsg>,
+    run: u64,
+    slot: usize,
+    api_key: &str,
+    model: &str,
+    prompt: &str,
+    project: Option<&Project>,
+    history: &[serde_json::Value],
+    params: SamplingParams,
+) -> Result<client::TokenUsage> {
+    let mut stream =
+        client::stream_completion_with_params(api_key, model, prompt, project, history, params)
+            .await?;
+    while let Some(event) = stream.recv().await {
+        match event {
+            StreamEvent::Token(text) => {
+                let _ = tx.send(TuiMsg::CompareToken { run, slot, text }).await;
+            }
+            StreamEvent::Done(usage) => return Ok(usage),
+            StreamEvent::Error(e) => anyhow::bail!(e),
+        }
+    }
+    anyhow::bail!("stream ended without a response")
+}
+
+/// Continue the session from comparison column `slot` (0-based): record
+/// the exchange and switch to the model that wrote it
+fn adopt_comparison(state: &mut TuiState, session: &mut Session, slot: usize) {
+    let Some(live) = state.compare.as_ref() else {
+        return;
+    };
+    let Some(result) = live.adoptable(slot).cloned() else {
+        state.log(format!(
+            "Compare: column {} has no response to adopt",
+            slot + 1
+        ));
+        return;
+    };
+    let prompt = live.prompt.clone();
+    let response = result.response.unwrap_or_default();
+    if let Err(e) = session.adopt_response(
+        &prompt,
+        &result.model,
+        &response,
+        Some(result.usage.completion_tokens),
+    ) {
+        state.log(format!("Session save error: {}", e));
+    }
+
+    for task in state.compare_tasks.drain(..) {
+        task.abort();
+    }
+    state.compare = None;
+    state.pop_view();
+    state.switch_model(&result.model, SwitchReason::Compare);
+    state.last_prompt = prompt.clone();
+    state.output.push(format!("> {}", prompt));
+    state.output.extend(response.lines().map(String::from));
+    state.output.push(format!(
+        "[✓] Adopted {} (quality {:.0}%); continuing with it",
+        result.model,
+        result.quality * 100.0
+    ));
+    state.auto_scroll = true;
+    state.mark_dirty();
+}
+
+/// AGENTIC LOOP: continue with tool results unless max iterations is hit
+fn continue_after_tools(state: &mut TuiState, tx: &mpsc::Sender<TuiMsg>, feedback: String) {
+    match state.advance_loop(LoopEvent::ToolsComplete) {
+        LoopStep::ScheduleContinue(iteration) => {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let _ = tx
+                    .send(TuiMsg::ContinueLoop {
+                        results: feedback,
+                        iteration,
+                    })
+                    .await;
+            });
+        }
+        LoopStep::MaxIterations => {
+            state
+                .output
+                .push("[Max iterations reached - pausing for input]".into());
+            state.mark_dirty();
+        }
+        _ => {}
+    }
+}
 
 impl TuiState {
     fn new(context_window: u32, project: Option<Project>, model: &str, api_key: &str) -> Self {
@@ -518,9 +1082,11 @@ impl TuiState {
             tab: Tab::Chat,
             input: String::new(),
             cursor_pos: 0,
+            completion: None,
             output: welcome_lines,
             log: Vec::new(),
             telemetry: Telemetry::new(60, 4), // 60 second window, 4Hz
+            channel_stats: ChannelStats::default(),
             traces: Traces::new(context_window),
             throttle: ThrottleMode::Normal,
             is_generating: false,
@@ -537,6 +1103,7 @@ impl TuiState {
             output_line_count: 1,
             output_cache: String::new(),
             output_dirty: true,
+            last_response_line: None,
             search_mode: false,
             search_query: String::new(),
             search_matches: Vec::new(),
@@ -560,10 +1127,10 @@ impl TuiState {
             tool_executor: ToolExecutor::new(),
             executing_tools: false,
             model_tracker: ModelTracker::new(),
+            leaderboard: Leaderboard::load(),
             last_prompt: String::new(),
             project,
-            loop_iteration: 0,
-            max_iterations: 10, // Prevent runaway loops
+            agent_loop: AgentLoop::new(10), // Prevent runaway loops
             // Multi-granularity intent tracking
             intent_stack: IntentStack::new(),
             intent_view: IntentView::default(),
@@ -571,16 +1138,49 @@ impl TuiState {
             cognitive_config: CognitiveConfig::default(),
             momentum: Momentum::default(),
             stuck_detector: StuckDetector::default(),
+            stuck_recovery: StuckRecovery::default(),
             // Salience tracking
             salience_keywords: Vec::new(),
             focus_files: Vec::new(),
             // Model management
             current_model: model.to_string(),
             rate_limited_models: Vec::new(),
+            free_fallbacks: crate::models::free_fallbacks(&[]),
             api_key: api_key.to_string(),
             rate_limit_pending: false,
             pending_retry: false,
+            confirm_clear: false,
             session_cost: 0.0,
+            session_start: std::time::Instant::now(),
+            pending_improvement: None,
+            pending_question: None,
+            plan_gate: PlanGate::new(false),
+            focus_gate: FocusGate::default(),
+            tool_parser: StreamingToolParser::default(),
+            early_tools: Vec::new(),
+            compare: None,
+            compare_run: 0,
+            compare_start: std::time::Instant::now(),
+            compare_tasks: Vec::new(),
+            trust_mode: false,
+            attachments: Vec::new(),
+            pins: Vec::new(),
+            upgrade_models: Vec::new(),
+            auto_upgrade: false,
+            upgrade_offered: None,
+            last_switch_reason: None,
+            sampling: SamplingParams::default(),
+            retry_sampling: None,
+            context_strategy: ContextStrategy::default(),
+            context_meter: ContextMeter::default(),
+            auto_compact: AutoCompact::default(),
+            compact_decision: CompactDecision::Idle,
+            confirm_compact: false,
+            compacting: false,
+            compact_generation: 0,
+            env_policy: EnvPolicy::default(),
+            max_command_timeout_ms: crate::config::DEFAULT_MAX_COMMAND_TIMEOUT_MS,
+            injection_guard: InjectionPolicy::default(),
             // Agent mode
             agent_mode: true, // Enable by default - this is what makes hyle like Claude Code
             agent_running: false,
@@ -597,16 +1197,54 @@ impl TuiState {
         }
 
         // Find next available model
-        for model in FREE_MODEL_FALLBACKS {
-            if !self.rate_limited_models.contains(&model.to_string()) {
-                let old = self.current_model.clone();
-                self.current_model = model.to_string();
-                self.log(format!("Rate limited on {}. Switching to {}", old, model));
-                return Some(model.to_string());
-            }
+        let next = self
+            .free_fallbacks
+            .iter()
+            .find(|m| !self.rate_limited_models.contains(m))
+            .cloned()?; // All models exhausted
+        self.switch_model(&next, SwitchReason::RateLimit);
+        Some(next)
+    }
+
+    /// Change the active model, recording why
+    fn switch_model(&mut self, model: &str, reason: SwitchReason) {
+        let old = std::mem::replace(&mut self.current_model, model.to_string());
+        self.model_tracker.set_model(model);
+        self.last_switch_reason = Some(reason);
+        self.upgrade_offered = None;
+        self.log(format!(
+            "Model switched {} -> {} ({})",
+            old,
+            model,
+            reason.label()
+        ));
+    }
+
+    /// After a response, offer or perform a quality upgrade if the free model keeps underperforming
+    fn check_quality_upgrade(&mut self) {
+        if crate::models::get_model_pricing(&self.current_model) != (0.0, 0.0) {
+            return; // Only upgrade away from free models
         }
+        let Some(target) = self.model_tracker.upgrade_target(&self.upgrade_models) else {
+            return;
+        };
 
-        None // All models exhausted
+        if self.auto_upgrade {
+            let old = self.current_model.clone();
+            self.switch_model(&target, SwitchReason::Quality);
+            self.output.push(format!(
+                "[Quality: repeated low scores on {}, switched to {}]",
+                old, target
+            ));
+            self.mark_dirty();
+        } else if self.upgrade_offered.as_deref() != Some(target.as_str()) {
+            self.output.push(format!(
+                "[Quality: repeated low scores on {}. Upgrade with /switch {}]",
+                self.current_model, target
+            ));
+            self.upgrade_offered = Some(target);
+            self.mark_dirty();
+        }
     }
 
     /// Format an error message to be more user-friendly
@@ -668,6 +1306,11 @@ impl TuiState {
             self.rate_limit_pending = true;
 
             if let Some(new_model) = self.switch_to_next_model() {
+                crate::logfile::LogEvent::warn(module_path!(), "rate limited, switching model")
+                    .field("from", self.rate_limited_models.last())
+                    .field("to", &new_model)
+                    .field("error", error)
+                    .emit();
                 self.output.push(format!(
                     "\n[Rate limited on {}. Auto-switching to {}]",
                     self.rate_limited_models.last().unwrap_or(&"?".to_string()),
@@ -678,6 +1321,10 @@ impl TuiState {
                 self.rate_limit_pending = false; // Switched, no longer pending
                 return (true, true); // Handled, should retry
             } else {
+                crate::logfile::LogEvent::error(module_path!(), "all free models rate limited")
+                    .field("model", &self.current_model)
+                    .field("error", error)
+                    .emit();
                 self.output.push(
                     "\n[All free models rate limited. Press ESC to pick a different model.]".into(),
                 );
@@ -692,6 +1339,28 @@ impl TuiState {
         self.rate_limit_pending = false;
     }
 
+    /// Complete the path under the cursor, or move to the next candidate
+    /// if Tab was just pressed; false when the input holds no path to complete
+    fn complete_path(&mut self) -> bool {
+        let continuing = self
+            .completion
+            .as_ref()
+            .is_some_and(|c| c.is_current(&self.input));
+        if !continuing {
+            let files = std::env::current_dir()
+                .map(|dir| crate::tools::project_files(&dir, 5000))
+                .unwrap_or_default();
+            self.completion = PathCompletion::start(&self.input, self.cursor_pos, &files);
+        }
+        let Some(completion) = self.completion.as_mut() else {
+            return false;
+        };
+        let (input, cursor) = completion.apply(&self.input, self.cursor_pos);
+        self.input = input;
+        self.cursor_pos = cursor;
+        true
+    }
+
     /// Scan for sessions (hyle and foreign)
     fn refresh_sessions(&mut self) {
         self.detected_sessions.clear();
@@ -724,6 +1393,7 @@ impl TuiState {
                     tokens: s.total_tokens,
                     messages: s.message_count,
                     integration: Integration::Full,
+                    parent: s.parent_id.clone(),
                 });
             }
         }
@@ -748,6 +1418,7 @@ impl TuiState {
                                 tokens: 0,
                                 messages: 0,
                                 integration: Integration::ReadOnly,
+                                parent: None,
                             });
                         }
                     }
@@ -765,6 +1436,7 @@ impl TuiState {
                     tokens: 0,
                     messages: 0,
                     integration: Integration::ReadOnly,
+                    parent: None,
                 });
             }
         }
@@ -823,7 +1495,23 @@ impl TuiState {
         self.executing_tools = false;
 
         // Format results for LLM feedback
-        Some(format_tool_results(&self.tool_tracker, &indices))
+        Some(format_tool_results(
+            &self.tool_tracker,
+            &indices,
+            self.injection_guard,
+            self.result_format(),
+        ))
+    }
+
+    /// Accounting printed after the TUI exits
+    fn exit_summary(&self, session: &Session) -> SessionSummary {
+        SessionSummary::new(
+            session,
+            &self.traces,
+            &self.tool_tracker,
+            self.session_cost,
+            self.session_start.elapsed(),
+        )
     }
 
     /// Get tool status for status bar
@@ -952,9 +1640,17 @@ impl TuiState {
         }
     }
 
-    /// Update line count cache
+    /// Update line count cache (and where the last response starts)
     fn update_line_count(&mut self) {
-        self.output_line_count = self.output.iter().map(|s| s.lines().count().max(1)).sum();
+        let mut count = 0;
+        self.last_response_line = None;
+        for entry in &self.output {
+            count += entry.lines().count().max(1);
+            if entry.starts_with("> ") {
+                self.last_response_line = Some(count);
+            }
+        }
+        self.output_line_count = count;
     }
 
     /// Mark output as dirty (needs cache rebuild)
@@ -1071,6 +1767,103 @@ impl TuiState {
         self.log.push(format!("[{}] {}", now, msg.into()));
     }
 
+    /// Estimate the assembled request and record it in the context trace
+    /// before sending. Returns false (and stops generating) if it can't fit.
+    fn preflight_context(&mut self, prompt: &str, history: &[serde_json::Value]) -> bool {
+        let tokens = client::estimate_prompt_tokens(prompt, self.project.as_ref(), history);
+        match self.traces.context.record_request(tokens) {
+            ContextBudget::Ok => true,
+            ContextBudget::Warning => {
+                self.log(format!(
+                    "Context warning: request ~{} of {} tokens",
+                    tokens, self.traces.context.context_window
+                ));
+                true
+            }
+            ContextBudget::Over => {
+                self.output.push(format!(
+                    "[Not sent: request is ~{} tokens, over the {}k context window. Try /compact or /clear]",
+                    tokens,
+                    self.traces.context.context_window / 1000
+                ));
+                self.log(format!("Blocked over-budget request (~{} tokens)", tokens));
+                self.is_generating = false;
+                self.mark_dirty();
+                false
+            }
+        }
+    }
+
+    /// Mirror the current plan into the Plans view; `proposed` starts a
+    /// new entry
+    fn sync_plan(&mut self, proposed: bool) {
+        let gate = &self.plan_gate;
+        let status = if gate.steps().is_empty() {
+            "rejected"
+        } else if gate.is_finished() {
+            "done"
+        } else if gate.is_approved() {
+            "in_progress"
+        } else {
+            "pending"
+        };
+        if proposed {
+            self.plans.push(Plan {
+                name: gate.steps().first().cloned().unwrap_or_default(),
+                status: status.into(),
+                steps: gate.render(),
+            });
+        } else if let Some(plan) = self.plans.last_mut() {
+            plan.status = status.into();
+            if !gate.steps().is_empty() {
+                plan.steps = gate.render();
+            }
+        }
+    }
+
+    /// Forget in-memory conversation state after /clear
+    fn reset_conversation(&mut self) {
+        self.output.clear();
+        self.current_response.clear();
+        self.last_prompt.clear();
+        self.intent_stack = IntentStack::new();
+        self.advance_loop(LoopEvent::Reset);
+        self.pending_question = None;
+        self.plan_gate.clear();
+        self.tool_parser = StreamingToolParser::default();
+        self.early_tools.clear();
+        self.stuck_detector.clear();
+        self.stuck_recovery.reset();
+        self.momentum = Momentum::default();
+        self.salience_keywords.clear();
+        self.focus_files.clear();
+        self.tool_executor.read_cursors().clear();
+        self.scroll_offset = 0;
+        self.abandon_compaction();
+    }
+
+    /// Drop any /compact still running; its summary is of another conversation
+    fn abandon_compaction(&mut self) {
+        self.compact_generation += 1;
+        self.compacting = false;
+    }
+
+    /// Replay a session's conversation into the output pane (abbreviated)
+    fn show_history(&mut self, messages: &[crate::session::Message]) {
+        for msg in messages {
+            if msg.role == "user" {
+                self.output.push(format!("> {}", msg.content));
+            } else if msg.role == "assistant" {
+                self.output
+                    .push(format!("  {}", msg.content.lines().next().unwrap_or("")));
+                if msg.content.lines().count() > 1 {
+                    self.output.push("  ...".into());
+                }
+            }
+        }
+        self.mark_dirty();
+    }
+
     // === COGNITIVE ARCHITECTURE METHODS ===
 
     /// Update intent from user prompt
@@ -1079,6 +1872,8 @@ impl TuiState {
 
         // Extract keywords for salience tracking
         self.salience_keywords = extract_keywords(prompt);
+        // A new task gets its own recovery attempts
+        self.stuck_recovery.reset();
 
         // Extract file references for focus tracking
         self.focus_files = prompt
@@ -1124,19 +1919,35 @@ impl TuiState {
         self.intent_view = IntentView::from_stack(&self.intent_stack);
     }
 
+    /// Advance the agentic loop; the only place iterations are counted
+    fn advance_loop(&mut self, event: LoopEvent) -> LoopStep {
+        let step = self.agent_loop.advance(event);
+        // A new prompt is a new task, with a plan of its own
+        if event == LoopEvent::Prompt {
+            self.plan_gate.clear();
+        }
+        match step {
+            LoopStep::ScheduleContinue(i) => self.log(format!(
+                "Agentic loop iteration {}/{}",
+                i,
+                self.agent_loop.max_iterations()
+            )),
+            LoopStep::Ignore if matches!(event, LoopEvent::ContinueLoop { .. }) => {
+                self.log(format!("Ignoring stale loop message: {:?}", event))
+            }
+            _ => {}
+        }
+        step
+    }
+
     /// Assess whether to continue the agentic loop
     fn should_continue_loop(&self, tool_results: &str) -> LoopDecision {
         use crate::cognitive::LoopDecision;
 
-        // Check iteration limit
-        if self.loop_iteration >= self.max_iterations {
-            return LoopDecision::MaxIterations;
-        }
-
         // Check if stuck
-        if self.stuck_detector.is_stuck() {
+        if let Some(reason) = self.stuck_detector.reason() {
             return LoopDecision::Stuck {
-                reason: "Repeated actions or errors detected".into(),
+                reason: reason.describe().into(),
                 suggestions: vec![
                     "Try a different approach".into(),
                     "Break down the task into smaller steps".into(),
@@ -1164,6 +1975,67 @@ impl TuiState {
         LoopDecision::Continue
     }
 
+    /// Try the next recovery for a stuck loop, returning the instruction
+    /// for the continuation prompt; None once the attempts are used up
+    fn recover_from_stuck(&mut self, session: &mut Session, reason: StuckReason) -> Option<String> {
+        const CONTEXT_FILES: usize = 5;
+        let upgrade = self
+            .upgrade_models
+            .iter()
+            .find(|m| **m != self.current_model)
+            .cloned();
+        let root = self.tool_executor.work_dir();
+        let mut known = self.focus_files.clone();
+        known.extend(self.attachments.iter().cloned());
+        let related = crate::cognitive::related_files(
+            &crate::tools::project_files(&root, 5000),
+            &self.salience_keywords,
+            &known,
+            CONTEXT_FILES,
+        );
+        let recovery = self.stuck_recovery.next(reason, |r| match r {
+            Recovery::BroadenContext => !related.is_empty(),
+            Recovery::StrongerModel => upgrade.is_some(),
+            Recovery::Decompose => true,
+        })?;
+        self.stuck_detector.clear();
+
+        let detail = match (recovery, upgrade) {
+            (Recovery::BroadenContext, _) => {
+                let paths: Vec<PathBuf> = related.iter().map(|f| root.join(f)).collect();
+                let paths: Vec<&std::path::Path> = paths.iter().map(PathBuf::as_path).collect();
+                let budget = self.traces.context.context_window as usize / 8;
+                match crate::tools::read_files_context_budgeted(&paths, budget) {
+                    Ok(files) => {
+                        let msg =
+                            format!("More project files for the current task:\n{}", files.text);
+                        if let Err(e) = session.add_system_message(&msg) {
+                            self.log(format!("Session save error: {}", e));
+                        }
+                        format!("added {}", related.join(", "))
+                    }
+                    Err(e) => format!("could not read related files: {}", e),
+                }
+            }
+            (Recovery::StrongerModel, Some(target)) => {
+                self.switch_model(&target, SwitchReason::Stuck);
+                format!("switched to {}", target)
+            }
+            (Recovery::StrongerModel, None) => String::new(),
+            (Recovery::Decompose, _) => "asked for smaller subtasks".into(),
+        };
+        self.output.push(format!(
+            "[Stuck: {}. Recovery {}/{}: {}, {}]",
+            reason.describe(),
+            self.stuck_recovery.attempts(),
+            MAX_STUCK_RECOVERIES,
+            recovery.label(),
+            detail
+        ));
+        self.log(format!("Stuck recovery: {} ({})", recovery.label(), detail));
+        Some(recovery.instruction().to_string())
+    }
+
     /// Record tool execution outcome for momentum tracking
     fn record_tool_outcome(&mut self, tool_name: &str, success: bool, was_useful: bool) {
         use crate::cognitive::ToolOutcome;
@@ -1198,18 +2070,21 @@ impl TuiState {
         let mut ctx = String::new();
 
         // Add intent view at appropriate verbosity based on loop iteration
-        let verbosity = if self.loop_iteration == 0 {
-            Verbosity::Full // First iteration: full context
-        } else if self.loop_iteration < 3 {
-            Verbosity::Normal
-        } else {
-            Verbosity::Minimal // Later iterations: minimal
-        };
+        let verbosity = Verbosity::for_iteration(usize::from(self.agent_loop.iteration()));
 
         ctx.push_str(&self.intent_view.for_llm(verbosity));
         ctx
     }
 
+    /// Layout for tool results: follows the intent verbosity, rich again
+    /// while the loop looks stuck
+    fn result_format(&self) -> ResultFormat {
+        ResultFormat::select(
+            Verbosity::for_iteration(usize::from(self.agent_loop.iteration())),
+            self.stuck_detector.is_stuck(),
+        )
+    }
+
     /// Build salience-aware context from conversation history
     /// Returns context string with most salient items in full detail
     fn build_salient_context(
@@ -1220,6 +2095,7 @@ impl TuiState {
         let mut salience = SalienceContext::new(budget_tokens);
         salience.set_keywords(self.salience_keywords.clone());
         salience.set_focus_files(self.focus_files.clone());
+        salience.set_locked_files(self.focus_gate.files().to_vec());
 
         // Process messages from oldest to newest, assigning age
         let total = messages.len();
@@ -1271,6 +2147,87 @@ impl TuiState {
         salience.build()
     }
 
+    /// Apply the active context strategy to history before a request, then
+    /// add pins, the focus set and attached files (up to a quarter of the
+    /// context window)
+    fn assemble_history(&self, history: &[serde_json::Value]) -> Vec<serde_json::Value> {
+        let assembled = self.context_strategy.assemble(history, |older, budget| {
+            self.build_salient_context(older, budget)
+        });
+        // The focus set rides along as a pin; the gate enforces it
+        let mut pins = self.pins.clone();
+        if self.focus_gate.is_active() {
+            pins.push(format!(
+                "Focus: edit only {}; edits to other files need the user's approval",
+                self.focus_gate.files().join(", ")
+            ));
+        }
+        crate::session::with_attachments(
+            crate::session::with_pins(assembled, &pins),
+            &self.attachments,
+            self.traces.context.context_window as usize / 4,
+        )
+    }
+
+    /// Read the files `prompt` mentions as `@path` (up to a quarter of the
+    /// context window), noting in the output what was attached or skipped
+    fn read_mentions(&mut self, prompt: &str) -> MentionContext {
+        let mentioned = crate::mentions::read(
+            &crate::mentions::extract(prompt),
+            self.traces.context.context_window as usize / 4,
+        );
+        for line in mentioned.summary() {
+            self.log(line.clone());
+            self.output.push(line);
+        }
+        mentioned
+    }
+
+    /// Re-estimate the history part of the next request if the session,
+    /// strategy, pins or attachments changed since the last frame
+    fn update_context_meter(&mut self, session: &Session) {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        session.messages.len().hash(&mut hasher);
+        session
+            .messages
+            .iter()
+            .map(|m| m.content.len())
+            .sum::<usize>()
+            .hash(&mut hasher);
+        self.context_strategy.label().hash(&mut hasher);
+        self.pins.hash(&mut hasher);
+        self.focus_gate.files().hash(&mut hasher);
+        self.attachments.hash(&mut hasher);
+        self.traces.context.context_window.hash(&mut hasher);
+
+        let mut meter = std::mem::take(&mut self.context_meter);
+        meter.base(hasher.finish(), || {
+            let history = self.assemble_history(&session.messages_for_api());
+            client::estimate_prompt_tokens("", self.project.as_ref(), &history)
+        });
+        self.context_meter = meter;
+    }
+
+    /// Check the next request against the auto-compact threshold; asks
+    /// once when it's crossed, and never while a reply is in progress
+    fn check_auto_compact(&mut self) {
+        let percent = self.context_meter.gauge(&self.traces.context, "").percent();
+        let busy =
+            self.is_generating || self.executing_tools || self.agent_running || self.compacting;
+        self.compact_decision = self.auto_compact.decide(percent, busy);
+        if self.compact_decision == CompactDecision::Prompt {
+            self.confirm_compact = true;
+            self.output.push(format!(
+                "[Context at {}% of the window: compact now? (y/n)]",
+                percent
+            ));
+            self.mark_dirty();
+        }
+    }
+
     /// Get salience stats for display
     fn salience_stats(&self, messages: &[serde_json::Value]) -> String {
         let mut salience = SalienceContext::new(4000);
@@ -1299,21 +2256,29 @@ impl TuiState {
 }
 
 /// Run the main TUI
+///
+/// `replaced_model` is the configured or resumed model that is no longer
+/// offered and `model` stands in for.
+#[allow(clippy::too_many_arguments)]
 pub async fn run_tui(
     api_key: &str,
     model: &str,
+    replaced_model: Option<String>,
     paths: Vec<PathBuf>,
     resume: bool,
+    work_dir: PathBuf,
     project: Option<Project>,
     claude_context: Option<Vec<crate::session::Message>>,
-) -> Result<()> {
+) -> Result<SessionSummary> {
     let mut terminal = setup_terminal()?;
     let result = run_tui_loop(
         &mut terminal,
         api_key,
         model,
+        replaced_model,
         paths,
         resume,
+        work_dir,
         project,
         claude_context,
     )
@@ -1322,22 +2287,26 @@ pub async fn run_tui(
     result
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_tui_loop(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     api_key: &str,
     model: &str,
+    replaced_model: Option<String>,
     _paths: Vec<PathBuf>,
     resume: bool,
+    work_dir: PathBuf,
     project: Option<Project>,
     claude_context: Option<Vec<crate::session::Message>>,
-) -> Result<()> {
+) -> Result<SessionSummary> {
     // Get context window for this model
     let context_window = crate::models::get_context_window(model);
     let mut state = TuiState::new(context_window, project, model, api_key);
+    state.tool_executor.set_work_dir(work_dir.clone());
 
     // Load or create session
     let mut session = if resume {
-        match Session::load_or_create(model) {
+        match Session::load_or_create(model, replaced_model.as_deref()) {
             Ok(s) => {
                 if s.messages.len() > 1 {
                     state.log(format!(
@@ -1345,20 +2314,7 @@ async fn run_tui_loop(
                         s.meta.id,
                         s.messages.len()
                     ));
-                    // Restore conversation to output
-                    for msg in &s.messages {
-                        if msg.role == "user" {
-                            state.output.push(format!("> {}", msg.content));
-                        } else if msg.role == "assistant" {
-                            state
-                                .output
-                                .push(format!("  {}", msg.content.lines().next().unwrap_or("")));
-                            if msg.content.lines().count() > 1 {
-                                state.output.push("  ...".into());
-                            }
-                        }
-                    }
-                    state.mark_dirty();
+                    state.show_history(&s.messages);
                 }
                 s
             }
@@ -1370,6 +2326,20 @@ async fn run_tui_loop(
     } else {
         Session::new(model)?
     };
+    if let Some(gone) = &replaced_model {
+        if session.meta.replaced_model.as_deref() != Some(gone.as_str()) {
+            session.substitute_model(gone, model)?;
+        }
+        state.log(format!("{} is no longer offered; using {}", gone, model));
+    }
+    state.attachments = session.meta.attachments.clone();
+    if !state.attachments.is_empty() {
+        state.log(format!("Attached: {}", state.attachments.join(", ")));
+    }
+    state.pins = session.meta.pins.clone();
+    state.focus_gate = FocusGate::new(&session.meta.focus);
+    let mut autosave = Autosave::default();
+    let mut idle = IdleTimeout::from_config();
 
     // Inject Claude Code context if available
     if let Some(claude_msgs) = claude_context {
@@ -1397,11 +2367,34 @@ async fn run_tui_loop(
 
     state.log(format!("Model: {} ({}k ctx)", model, context_window / 1000));
     state.model_tracker.set_model(model);
+    // Fallbacks from what's offered now; main refreshed the cache on startup
+    let catalog = crate::models::load_cache()
+        .ok()
+        .flatten()
+        .map(|c| c.models)
+        .unwrap_or_default();
+    state.free_fallbacks = crate::models::free_fallbacks(&catalog);
+    if let Ok(cfg) = crate::config::Config::load() {
+        state.tool_executor = ToolExecutor::from_config(&cfg).with_work_dir(&work_dir);
+        state.max_command_timeout_ms = cfg
+            .max_command_timeout_ms
+            .unwrap_or(crate::config::DEFAULT_MAX_COMMAND_TIMEOUT_MS);
+        state.upgrade_models = cfg.upgrade_models;
+        state.auto_upgrade = cfg.auto_upgrade;
+        state.sampling = cfg.sampling;
+        state.context_strategy = cfg.context_strategy;
+        state.trust_mode = cfg.trust_mode;
+        state.plan_gate = PlanGate::new(cfg.plan_first);
+        state.auto_compact = AutoCompact::new(cfg.auto_compact_percent);
+        state.env_policy = cfg.env;
+        state.injection_guard = cfg.injection_guard;
+    }
 
     // Load existing sessions on startup
     state.refresh_sessions();
 
-    let (tx, mut rx) = mpsc::channel::<TuiMsg>(256);
+    let (tx, mut rx) = mpsc::channel::<TuiMsg>(UI_CHANNEL_CAPACITY);
+    let mut drain = FrameDrain::new(FRAME_DRAIN_BUDGET, merge_tokens);
 
     // Spawn background telemetry sampler (non-blocking sysinfo)
     let (_telemetry_handle, mut telemetry_rx) = TelemetrySampler::spawn(4);
@@ -1440,16 +2433,34 @@ async fn run_tui_loop(
             let api_key = state.api_key.clone();
             let model = state.current_model.clone();
             let project_clone = state.project.clone();
-            let history = session.messages_for_api();
             let prompt = state.last_prompt.clone();
+            let mut history = session.messages_for_api();
+            // The prompt is re-sent below; don't include it twice
+            if session
+                .messages
+                .last()
+                .map(|m| m.role == "user" && m.content == prompt)
+                .unwrap_or(false)
+            {
+                history.pop();
+            }
+            let history = state.assemble_history(&history);
+            if !state.preflight_context(&prompt, &history) {
+                continue;
+            }
 
+            let params = match state.retry_sampling.take() {
+                Some(overrides) => state.sampling.merged(&overrides),
+                None => state.sampling.clone(),
+            };
             tokio::spawn(async move {
-                match client::stream_completion_full(
+                match client::stream_completion_with_params(
                     &api_key,
                     &model,
                     &prompt,
                     project_clone.as_ref(),
                     &history,
+                    params,
                 )
                 .await
                 {
@@ -1475,10 +2486,13 @@ async fn run_tui_loop(
             });
         }
 
-        // Check for API responses
-        while let Ok(msg) = rx.try_recv() {
+        // Check for API responses, up to this frame's time budget
+        drain.begin();
+        while let Some((msg, merged)) = drain.next(&mut rx) {
             match msg {
                 TuiMsg::Token(t) => {
+                    autosave.record_tokens(merged);
+
                     // Record time to first token
                     if state.ttft.is_none() {
                         let ttft = state.request_start.elapsed();
@@ -1489,10 +2503,21 @@ async fn run_tui_loop(
                     // Update tokens/sec estimate
                     let elapsed = state.last_token_time.elapsed().as_secs_f32();
                     if elapsed > 0.0 {
-                        state.tokens_per_sec = 1.0 / elapsed;
+                        state.tokens_per_sec = merged as f32 / elapsed;
                     }
                     state.last_token_time = std::time::Instant::now();
 
+                    // Start read-only tool calls as soon as their block closes
+                    if state.current_response.is_empty() {
+                        state.tool_parser = StreamingToolParser::default();
+                        state.early_tools.clear();
+                    }
+                    for call in state.tool_parser.push_early(&t) {
+                        state.log(format!("Started {} while streaming", call.name));
+                        let handle = spawn_tool_batch(&state, &tx, vec![call.clone()]);
+                        state.early_tools.push((call, handle));
+                    }
+
                     // Append to output and accumulate response
                     state.current_response.push_str(&t);
                     // Use incremental update to avoid full cache rebuild per token
@@ -1504,11 +2529,7 @@ async fn run_tui_loop(
                     state.completion_tokens = usage.completion_tokens;
 
                     // Calculate and accumulate cost
-                    let request_cost = crate::models::calculate_cost(
-                        &state.current_model,
-                        usage.prompt_tokens,
-                        usage.completion_tokens,
-                    );
+                    let request_cost = crate::models::calculate_cost(&state.current_model, &usage);
                     state.session_cost += request_cost;
 
                     // Record traces
@@ -1519,6 +2540,10 @@ async fn run_tui_loop(
                         usage.completion_tokens,
                         duration.as_secs_f64(),
                     );
+                    state
+                        .traces
+                        .tokens
+                        .record_cache(usage.cache_read_tokens, usage.cache_write_tokens);
                     state.traces.context.record(usage.prompt_tokens);
 
                     // Evaluate response quality
@@ -1537,6 +2562,7 @@ async fn run_tui_loop(
                                 ));
                             }
                         }
+                        state.check_quality_upgrade();
                     }
 
                     // Save assistant message to session
@@ -1551,36 +2577,65 @@ async fn run_tui_loop(
                         // Check for tool calls - spawn execution in background to avoid blocking
                         let response_copy = state.current_response.clone();
                         let calls = parse_tool_calls(&response_copy);
-                        if !calls.is_empty() {
+                        state.tool_parser = StreamingToolParser::default();
+                        let early = std::mem::take(&mut state.early_tools);
+                        let step = state.advance_loop(LoopEvent::Done {
+                            tool_calls: !calls.is_empty(),
+                        });
+                        if step == LoopStep::RunTools {
+                            // Plan-first: record any plan, refuse what may not run yet
+                            let proposed = calls.iter().any(|c| c.name == PLAN_TOOL);
+                            let round = state.plan_gate.admit(calls, state.trust_mode);
+                            let focused = state.focus_gate.admit(round.calls);
+                            if proposed && !state.plan_gate.steps().is_empty() {
+                                state.sync_plan(true);
+                                state.output.push("─── Plan ───".into());
+                                for line in state.plan_gate.render() {
+                                    state.output.push(format!("  {}", line));
+                                }
+                            }
+
+                            // The question waits for an answer while the rest run
+                            let (questions, calls): (Vec<_>, Vec<_>) = focused
+                                .calls
+                                .into_iter()
+                                .partition(|c| c.name == ASK_USER_TOOL);
+                            if round.awaiting_approval {
+                                state.pending_question = Some(PendingQuestion::plan_approval());
+                            } else if !focused.outside.is_empty() {
+                                state.pending_question =
+                                    Some(PendingQuestion::focus_approval(&focused.outside));
+                            } else if let Some(q) = questions.first() {
+                                state.pending_question =
+                                    Some(PendingQuestion::new(&ask_user_question(&q.args)));
+                            }
                             state.executing_tools = true;
-                            state
-                                .output
-                                .push(format!("[Executing {} tool(s)...]", calls.len()));
+                            if !calls.is_empty() {
+                                state
+                                    .output
+                                    .push(format!("[Executing {} tool(s)...]", calls.len()));
+                            }
                             state.mark_dirty();
 
-                            // Spawn tool execution in blocking thread pool
+                            // Calls already running from the stream aren't run twice
+                            let started: Vec<ParsedToolCall> =
+                                early.iter().map(|(call, _)| call.clone()).collect();
+                            let rest = unmatched(calls, &started);
+                            let run_rest = tool_batch(&state, &tx);
+
+                            // Early calls come before any other in the response, so
+                            // the rest start once they finish, keeping call order
                             let tx = tx.clone();
-                            tokio::task::spawn_blocking(move || {
-                                // Create temporary executor and tracker for this batch
-                                let mut executor = ToolExecutor::new();
-                                let mut tracker = ToolCallTracker::new();
-
-                                let results =
-                                    execute_tool_calls(&calls, &mut executor, &mut tracker);
-                                let indices: Vec<usize> =
-                                    results.iter().map(|(idx, _)| *idx).collect();
-                                let feedback = format_tool_results(&tracker, &indices);
-
-                                // Send results back to main loop
-                                let rt = tokio::runtime::Handle::current();
-                                rt.block_on(async {
-                                    let _ = tx.send(TuiMsg::ToolsComplete { feedback }).await;
-                                });
+                            let mut feedback = round.feedback + &focused.feedback;
+                            tokio::spawn(async move {
+                                for (_, handle) in early {
+                                    feedback.push_str(&handle.await.unwrap_or_default());
+                                }
+                                let rest = tokio::task::spawn_blocking(move || run_rest(rest));
+                                feedback.push_str(&rest.await.unwrap_or_default());
+                                let _ = tx.send(TuiMsg::ToolsComplete { feedback }).await;
                             });
-                        } else {
-                            // No tool calls - reset loop counter
-                            state.loop_iteration = 0;
-
+                        } else if step == LoopStep::Finished {
                             // Process queued prompts if any
                             if let Some(queued) = state.pending_prompts.pop_front() {
                                 state.output.push(format!("> {} [from queue]", queued));
@@ -1612,19 +2667,28 @@ async fn run_tui_loop(
                                         })
                                     })
                                     .collect();
+                                let history = state
+                                    .read_mentions(&queued)
+                                    .apply(state.assemble_history(&history));
+                                if !state.preflight_context(&queued, &history) {
+                                    continue;
+                                }
 
                                 state.last_prompt = queued.clone();
+                                state.advance_loop(LoopEvent::Prompt);
                                 state.is_generating = true;
                                 state.request_start = std::time::Instant::now();
 
                                 let tx = tx.clone();
+                                let params = state.sampling.clone();
                                 tokio::spawn(async move {
-                                    match client::stream_completion_full(
+                                    match client::stream_completion_with_params(
                                         &api_key,
                                         &model,
                                         &queued,
                                         project_clone.as_ref(),
                                         &history,
+                                        params,
                                     )
                                     .await
                                     {
@@ -1654,10 +2718,14 @@ async fn run_tui_loop(
                         state.current_response.clear();
                     }
 
-                    // Save session metadata
+                    // Save session metadata; the response is complete now
                     if let Err(e) = session.save_meta() {
                         state.log(format!("Session meta save error: {}", e));
                     }
+                    if let Err(e) = session.clear_partial() {
+                        state.log(format!("Session autosave error: {}", e));
+                    }
+                    autosave.saved(std::time::Instant::now());
 
                     state.output.push(format!(
                         "\n[{} + {} = {} tokens, {:.1}s]",
@@ -1675,7 +2743,13 @@ async fn run_tui_loop(
                 }
                 TuiMsg::Error(e) => {
                     state.is_generating = false;
-                    state.loop_iteration = 0; // Reset on error
+                    state.advance_loop(LoopEvent::Reset);
+                    state.tool_parser = StreamingToolParser::default();
+                    state.early_tools.clear();
+                    if let Err(err) = session.clear_partial() {
+                        state.log(format!("Session autosave error: {}", err));
+                    }
+                    autosave.saved(std::time::Instant::now());
 
                     // Check for rate limit and auto-switch
                     let (handled, should_retry) = state.handle_rate_limit_error(&e);
@@ -1744,43 +2818,236 @@ async fn run_tui_loop(
                     ));
                     state.mark_dirty();
                 }
-                TuiMsg::ToolsComplete { feedback } => {
-                    // Tools finished executing in background
-                    state.executing_tools = false;
+                TuiMsg::CompactDone {
+                    generation,
+                    upto,
+                    summary,
+                } => {
+                    if generation != state.compact_generation {
+                        state.log("Dropped a /compact summary of a replaced conversation");
+                        continue;
+                    }
+                    state.compacting = false;
+                    let result = summary
+                        .map_err(anyhow::Error::msg)
+                        .and_then(|s| session.compact(upto, s.trim()));
+                    match result {
+                        Ok(removed) => {
+                            state.output.push(format!(
+                                "[✓] Compacted conversation ({} messages removed, {} left)",
+                                removed,
+                                session.messages.len()
+                            ));
+                            state.log(format!("Compacted {} messages", removed));
+                        }
+                        Err(e) => state.output.push(format!("[✗] Compact failed: {}", e)),
+                    }
+                    state.mark_dirty();
+                }
+                TuiMsg::SummarizeDone(result) => {
+                    match result {
+                        Ok(path) => {
+                            let text = std::fs::read_to_string(&path).unwrap_or_default();
+                            for line in text.lines() {
+                                state.output.push(format!("  {}", line));
+                            }
+                            state.output.push(format!(
+                                "[✓] Overview saved to {} - included as context from now on",
+                                path.display()
+                            ));
+                            state.log("Wrote repo overview");
+                        }
+                        Err(e) => state.output.push(format!("[✗] Summarize failed: {}", e)),
+                    }
+                    state.mark_dirty();
+                }
+                TuiMsg::ModelsReloaded(result) => {
+                    match result {
+                        Ok(reloaded) => {
+                            state.free_fallbacks = reloaded.fallbacks.clone();
+                            state.output.push(format!("[✓] {}", reloaded.summary()));
+                            if !reloaded.models.iter().any(|m| m.id == state.current_model) {
+                                state.output.push(format!(
+                                    "[!] {} is no longer listed; /switch to pick another",
+                                    state.current_model
+                                ));
+                            }
+                            state.log(reloaded.summary());
+                        }
+                        Err(e) => state.output.push(format!(
+                            "[✗] Model refresh failed, keeping the current list: {}",
+                            e
+                        )),
+                    }
+                    state.mark_dirty();
+                }
+                TuiMsg::RunDone(capture) => {
+                    for line in capture.output.lines().take(40) {
+                        state.output.push(format!("  {}", line));
+                    }
+                    if capture.output.lines().count() > 40 {
+                        state.output.push("  ... (full output in context)".into());
+                    }
+                    let status = if capture.success() { "✓" } else { "✗" };
+                    let note = if capture.truncated() {
+                        ", truncated"
+                    } else {
+                        ""
+                    };
+                    match session.add_system_message(&capture.to_context()) {
+                        Ok(()) => state.output.push(format!(
+                            "[{}] {} ({}) - output added to context{}",
+                            status,
+                            capture.command,
+                            capture.status(),
+                            note
+                        )),
+                        Err(e) => state.output.push(format!("[✗] Session save error: {}", e)),
+                    }
+                    state.log(format!("Ran {} ({})", capture.command, capture.status()));
+                    state.mark_dirty();
+                }
+                TuiMsg::IngestDone { source, result } => {
+                    let result = result.map_err(anyhow::Error::msg).and_then(|doc| {
+                        session
+                            .add_system_message(&doc.to_context(state.injection_guard))
+                            .map(|_| doc)
+                    });
+                    match result {
+                        Ok(doc) => {
+                            state.output.push(format!(
+                                "[✓] Ingested {} ({} chars in {} part(s){})",
+                                doc.source,
+                                doc.kept_chars(),
+                                doc.chunks.len(),
+                                if doc.truncated { ", truncated" } else { "" }
+                            ));
+                            state.log(format!("Ingested {}", doc.source));
+                        }
+                        Err(e) => state
+                            .output
+                            .push(format!("[✗] Ingest of {} failed: {}", source, e)),
+                    }
+                    state.mark_dirty();
+                }
+                TuiMsg::ImproveProposal { target, diff } => {
+                    match diff {
+                        Ok(diff) => {
+                            state
+                                .output
+                                .push(format!("─── Proposed fix: {} ───", target));
+                            for line in diff.lines().take(60) {
+                                state.output.push(format!("  {}", line));
+                            }
+                            if diff.lines().count() > 60 {
+                                state.output.push("  ... (truncated)".into());
+                            }
+                            if state.trust_mode {
+                                spawn_improvement_apply(&mut state, &tx, diff);
+                            } else {
+                                state
+                                    .output
+                                    .push("Apply this fix and run the tests? (y/n)".into());
+                                state.pending_improvement = Some(diff);
+                            }
+                        }
+                        Err(e) => state.output.push(format!("[✗] Improve: {}", e)),
+                    }
+                    state.mark_dirty();
+                }
+                TuiMsg::CompareToken { run, slot, text } if run == state.compare_run => {
+                    let elapsed = state.compare_start.elapsed().as_millis() as u64;
+                    if let Some(live) = state.compare.as_mut() {
+                        live.token(slot, &text, elapsed);
+                    }
+                    state.mark_dirty();
+                }
+                TuiMsg::CompareDone { run, slot, usage } if run == state.compare_run => {
+                    let elapsed = state.compare_start.elapsed().as_millis() as u64;
+                    if let Some(live) = state.compare.as_mut() {
+                        live.finish(slot, usage, elapsed);
+                    }
+                    state.mark_dirty();
+                }
+                TuiMsg::CompareFailed { run, slot, error } if run == state.compare_run => {
+                    let elapsed = state.compare_start.elapsed().as_millis() as u64;
+                    if let Some(live) = state.compare.as_mut() {
+                        live.fail(slot, &error, elapsed);
+                    }
+                    state.log(format!("Compare: {}", error));
+                    state.mark_dirty();
+                }
+                // From a comparison that has since been replaced
+                TuiMsg::CompareToken { .. }
+                | TuiMsg::CompareDone { .. }
+                | TuiMsg::CompareFailed { .. } => {}
+                TuiMsg::ToolCallsFinished(calls) => {
+                    for call in calls {
+                        state.tool_tracker.add(call);
+                    }
+                }
+                TuiMsg::ImproveDone { output, success } => {
+                    let status = if success { "✓" } else { "✗" };
+                    state.output.push(format!("[{}] /improve apply", status));
+                    for line in output.lines().take(50) {
+                        state.output.push(format!("  {}", line));
+                    }
+                    state.log(format!(
+                        "Self-improvement {}",
+                        if success { "applied" } else { "failed" }
+                    ));
+                    state.mark_dirty();
+                }
+                TuiMsg::ToolsComplete { feedback } => {
+                    // Tools finished executing in background
+                    state.executing_tools = false;
+                    if !state.plan_gate.steps().is_empty() {
+                        let before = state.plan_gate.done();
+                        state.plan_gate.round_complete();
+                        if state.plan_gate.done() > before {
+                            state.sync_plan(false);
+                            state.log(format!(
+                                "Plan step {}/{} done",
+                                state.plan_gate.done(),
+                                state.plan_gate.steps().len()
+                            ));
+                        }
+                    }
 
                     // Show tool execution results
-                    state.output.push(String::new());
-                    state.output.push("─── Tool Results ───".to_string());
-                    for line in feedback.lines().take(20) {
-                        state.output.push(format!("  {}", line));
+                    if !feedback.is_empty() {
+                        state.output.push(String::new());
+                        state.output.push("─── Tool Results ───".to_string());
+                        for line in feedback.lines().take(20) {
+                            state.output.push(format!("  {}", line));
+                        }
                     }
                     state.mark_dirty();
 
-                    // AGENTIC LOOP: Continue if we have tool results and haven't hit max iterations
-                    if state.loop_iteration < state.max_iterations {
-                        state.loop_iteration += 1;
-                        state.log(format!(
-                            "Agentic loop iteration {}/{}",
-                            state.loop_iteration, state.max_iterations
-                        ));
-
-                        // Send continuation message
-                        let tx = tx.clone();
-                        let tool_results = feedback.clone();
-                        let iteration = state.loop_iteration;
-                        tokio::spawn(async move {
-                            let _ = tx
-                                .send(TuiMsg::ContinueLoop {
-                                    results: tool_results,
-                                    iteration,
-                                })
-                                .await;
-                        });
-                    } else {
-                        state.loop_iteration = 0;
+                    // Hold the loop until the model's question is answered
+                    if let Some(mut question) = state.pending_question.take() {
+                        match question.tools_done(feedback) {
+                            Some(feedback) => continue_after_tools(&mut state, &tx, feedback),
+                            None => {
+                                state.output.push(format!("[? {}]", question.question));
+                                state
+                                    .output
+                                    .push("  Type your answer and press Enter".into());
+                                state.pending_question = Some(question);
+                                state.mark_dirty();
+                            }
+                        }
+                        continue;
                     }
+                    continue_after_tools(&mut state, &tx, feedback);
                 }
                 TuiMsg::ContinueLoop { results, iteration } => {
+                    if state.advance_loop(LoopEvent::ContinueLoop { iteration })
+                        != LoopStep::Continue(iteration)
+                    {
+                        continue;
+                    }
+
                     // AGENTIC LOOP: Continue with tool results
                     state.output.push(String::new());
                     state
@@ -1796,26 +3063,29 @@ async fn run_tui_loop(
 
                     // Use cognitive architecture for loop decision
                     let decision = state.should_continue_loop(&results);
-                    match decision {
-                        LoopDecision::MaxIterations => {
-                            state
-                                .output
-                                .push("[Max iterations reached - pausing for input]".into());
-                            state.is_generating = false;
-                            state.loop_iteration = 0;
-                            state.mark_dirty();
-                            continue;
+                    // Escalate before giving up on a stuck loop
+                    let recovery = match (&decision, state.stuck_detector.reason()) {
+                        (LoopDecision::Stuck { .. }, Some(reason)) => {
+                            state.recover_from_stuck(&mut session, reason)
                         }
+                        _ => None,
+                    };
+                    match decision {
+                        LoopDecision::Stuck { .. } if recovery.is_some() => {}
                         LoopDecision::Stuck {
                             reason,
                             suggestions,
                         } => {
-                            state.output.push(format!("[Stuck: {}]", reason));
+                            state.output.push(format!(
+                                "[Stuck: {} ({} recoveries tried)]",
+                                reason,
+                                state.stuck_recovery.attempts()
+                            ));
                             for s in suggestions {
                                 state.output.push(format!("  - {}", s));
                             }
                             state.is_generating = false;
-                            state.loop_iteration = 0;
+                            state.advance_loop(LoopEvent::Reset);
                             state.stuck_detector.clear();
                             state.mark_dirty();
                             continue;
@@ -1823,13 +3093,14 @@ async fn run_tui_loop(
                         LoopDecision::PauseConcern { reason } => {
                             state.output.push(format!("[Pausing: {}]", reason));
                             state.is_generating = false;
+                            state.advance_loop(LoopEvent::Reset);
                             state.mark_dirty();
                             continue;
                         }
                         LoopDecision::Complete { summary } => {
                             state.output.push(format!("[Complete: {}]", summary));
                             state.is_generating = false;
-                            state.loop_iteration = 0;
+                            state.advance_loop(LoopEvent::Reset);
                             // Mark active intent as completed
                             state.intent_stack.pop();
                             state.intent_view = IntentView::from_stack(&state.intent_stack);
@@ -1846,7 +3117,7 @@ async fn run_tui_loop(
 
                     // Build dynamic continuation prompt with intent context
                     let intent_ctx = state.get_llm_context();
-                    let continuation = if intent_ctx.is_empty() {
+                    let mut continuation = if intent_ctx.is_empty() {
                         "Continue based on the tool results above. If the task is complete, summarize what was done. If more steps are needed, proceed with the next step.".to_string()
                     } else {
                         format!(
@@ -1854,6 +3125,9 @@ async fn run_tui_loop(
                             intent_ctx
                         )
                     };
+                    if let Some(instruction) = recovery {
+                        continuation = format!("{}\n\n{}", instruction, continuation);
+                    }
 
                     state.output.push(format!(
                         "> {}",
@@ -1874,16 +3148,21 @@ async fn run_tui_loop(
                     let api_key = state.api_key.clone();
                     let model = state.current_model.clone(); // Use state model, can switch on rate limit
                     let project_clone = state.project.clone();
-                    let history = session.messages_for_api();
+                    let history = state.assemble_history(&session.messages_for_api());
                     let cont_prompt = continuation;
+                    if !state.preflight_context(&cont_prompt, &history) {
+                        continue;
+                    }
 
+                    let params = state.sampling.clone();
                     tokio::spawn(async move {
-                        match client::stream_completion_full(
+                        match client::stream_completion_with_params(
                             &api_key,
                             &model,
                             &cont_prompt,
                             project_clone.as_ref(),
                             &history,
+                            params,
                         )
                         .await
                         {
@@ -1911,6 +3190,41 @@ async fn run_tui_loop(
             }
         }
 
+        state.channel_stats = drain.stats.clone();
+        if let Some(report) = drain.lag_report() {
+            state.log(report);
+        }
+
+        // Periodically flush the in-flight response so a crash doesn't lose it
+        let now = std::time::Instant::now();
+        if state.is_generating && autosave.due(now) {
+            if let Err(e) = session.save_partial(&state.current_response) {
+                state.log(format!("Session autosave error: {}", e));
+            }
+            autosave.saved(now);
+        }
+
+        // Opt-in: save and close a session nobody is using
+        if let Some(idle) = idle.as_mut() {
+            if state.is_generating || state.executing_tools {
+                idle.touch(now);
+            }
+            let closing = idle.fire_if_idle(now, || {
+                for task in state.compare_tasks.drain(..) {
+                    task.abort();
+                }
+                session.save_meta()
+            });
+            match closing {
+                Ok(false) => {}
+                Ok(true) => break,
+                Err(e) => {
+                    state.log(format!("Session save error: {}", e));
+                    break;
+                }
+            }
+        }
+
         // Update cache before render (avoids allocation during draw)
         if state.output_dirty {
             state.output_cache = state.output.join("\n");
@@ -1918,11 +3232,17 @@ async fn run_tui_loop(
             state.output_dirty = false;
         }
 
+        state.update_context_meter(&session);
+        state.check_auto_compact();
+
         // Render
         terminal.draw(|f| render_tui(f, &state))?;
 
         // Handle input
         if event::poll(Duration::from_millis(50))? {
+            if let Some(idle) = idle.as_mut() {
+                idle.touch(std::time::Instant::now());
+            }
             match event::read()? {
                 // Handle paste events (multiline text) - works during generation
                 Event::Paste(pasted) => {
@@ -1972,22 +3292,22 @@ async fn run_tui_loop(
                                 state
                                     .output
                                     .push("─── Available Models (use /switch <model>) ───".into());
-                                for (i, m) in FREE_MODEL_FALLBACKS.iter().enumerate() {
-                                    let marker =
-                                        if state.rate_limited_models.contains(&m.to_string()) {
-                                            "✗" // Rate limited
-                                        } else if *m == state.current_model {
-                                            "●" // Current
-                                        } else {
-                                            " "
-                                        };
+                                for (i, m) in state.free_fallbacks.iter().enumerate() {
+                                    let marker = if state.rate_limited_models.contains(m) {
+                                        "✗" // Rate limited
+                                    } else if *m == state.current_model {
+                                        "●" // Current
+                                    } else {
+                                        " "
+                                    };
                                     state
                                         .output
                                         .push(format!("  [{}] {}: {}", marker, i + 1, m));
                                 }
-                                state
-                                    .output
-                                    .push("─── Type /switch <name> or /switch 1-5 ───".into());
+                                state.output.push(format!(
+                                    "─── Type /switch <name> or /switch 1-{} ───",
+                                    state.free_fallbacks.len()
+                                ));
                                 state.rate_limit_pending = false;
                                 state.mark_dirty();
                             } else if state.input.is_empty() {
@@ -2001,6 +3321,8 @@ async fn run_tui_loop(
                                 state.history_index = None;
                             }
                         }
+                        // Tab: complete a path in the input
+                        KeyCode::Tab if state.tab == View::Chat && state.complete_path() => {}
                         // Tab: cycle through main views
                         KeyCode::Tab => {
                             let views = View::main_views();
@@ -2080,6 +3402,13 @@ async fn run_tui_loop(
                         _ => {}
                     }
 
+                    // Compare view - pick the response to continue with
+                    if state.tab == View::Compare {
+                        if let KeyCode::Char(c @ '1'..='9') = key.code {
+                            adopt_comparison(&mut state, &mut session, c as usize - '1' as usize);
+                        }
+                    }
+
                     // Chat view - scrolling and search
                     if state.tab == Tab::Chat {
                         // Search mode input handling
@@ -2156,7 +3485,7 @@ async fn run_tui_loop(
                                         state.pending_prompts.len()
                                     ));
                                 } else if !state.input.is_empty() {
-                                    let prompt = state.input.clone();
+                                    let mut prompt = state.input.clone();
                                     state.add_to_history(&prompt);
                                     state.input.clear();
                                     state.cursor_pos = 0;
@@ -2164,6 +3493,78 @@ async fn run_tui_loop(
                                     state.output.push(format!("> {}", prompt));
                                     state.mark_dirty();
                                     state.auto_scroll = true;
+                                    // Sending something else passes on auto-compact
+                                    state.confirm_compact = false;
+
+                                    // Answer to a pending /clear confirmation
+                                    if std::mem::take(&mut state.confirm_clear) {
+                                        let answer = prompt.trim().to_lowercase();
+                                        if answer == "y" || answer == "yes" {
+                                            match session.clear() {
+                                                Ok(removed) => {
+                                                    state.reset_conversation();
+                                                    state.output.push(format!(
+                                                        "[✓] Conversation cleared ({} messages)",
+                                                        removed
+                                                    ));
+                                                    state.log("Conversation cleared");
+                                                }
+                                                Err(e) => state
+                                                    .output
+                                                    .push(format!("[✗] Clear failed: {}", e)),
+                                            }
+                                        } else {
+                                            state.output.push("[Clear cancelled]".into());
+                                        }
+                                        continue;
+                                    }
+
+                                    // Answer to a pending /improve apply confirmation
+                                    if let Some(diff) = state.pending_improvement.take() {
+                                        let answer = prompt.trim().to_lowercase();
+                                        if answer == "y" || answer == "yes" {
+                                            spawn_improvement_apply(&mut state, &tx, diff);
+                                        } else {
+                                            state.output.push("[Fix discarded]".into());
+                                        }
+                                        continue;
+                                    }
+
+                                    // Answer to the model's ask_user question or plan
+                                    if let Some(mut question) = state.pending_question.take() {
+                                        if !is_slash_command(&prompt) {
+                                            state.log(format!("Answered: {}", question.question));
+                                            let mut answer = prompt.clone();
+                                            if question.tool == PLAN_TOOL {
+                                                answer = state.plan_gate.decide(&prompt);
+                                                state.sync_plan(false);
+                                                state.output.push(
+                                                    if state.plan_gate.is_approved() {
+                                                        "[Plan approved]".into()
+                                                    } else {
+                                                        "[Plan rejected]".into()
+                                                    },
+                                                );
+                                            } else if question.tool == FOCUS_TOOL {
+                                                answer = state.focus_gate.decide(&prompt);
+                                                let files = state.focus_gate.files().to_vec();
+                                                if let Err(e) = session.set_focus(&files) {
+                                                    state.log(format!("Session save error: {}", e));
+                                                }
+                                                state
+                                                    .output
+                                                    .push(format!("[Focus: {}]", files.join(", ")));
+                                            }
+                                            match question.answer(&answer) {
+                                                Some(feedback) => {
+                                                    continue_after_tools(&mut state, &tx, feedback)
+                                                }
+                                                None => state.pending_question = Some(question),
+                                            }
+                                            continue;
+                                        }
+                                        state.pending_question = Some(question);
+                                    }
 
                                     // Check for slash commands first
                                     if is_slash_command(&prompt) {
@@ -2180,24 +3581,37 @@ async fn run_tui_loop(
                                             project_type,
                                             Some(&ctx),
                                         ) {
-                                            // Handle special SWITCH_MODEL signals
-                                            if result.output == "SWITCH_MODEL_PICKER" {
+                                            // /cd moves the process; tools follow it
+                                            if result.success && prompt.starts_with("/cd ") {
+                                                if let Ok(dir) = std::env::current_dir() {
+                                                    state.tool_executor.set_work_dir(dir);
+                                                }
+                                            }
+                                            // /undo restores where the tools delete
+                                            let result = if result.action == Some(SlashAction::Undo)
+                                            {
+                                                crate::skills::run_undo(
+                                                    &state.tool_executor.work_dir(),
+                                                )
+                                            } else {
+                                                result
+                                            };
+                                            // Actions that need UI/session state
+                                            if result.action == Some(SlashAction::ShowModelPicker) {
                                                 state
                                                     .output
                                                     .push("─── Available Models ───".into());
                                                 for (i, m) in
-                                                    FREE_MODEL_FALLBACKS.iter().enumerate()
+                                                    state.free_fallbacks.iter().enumerate()
                                                 {
-                                                    let marker = if state
-                                                        .rate_limited_models
-                                                        .contains(&m.to_string())
-                                                    {
-                                                        "✗"
-                                                    } else if *m == state.current_model {
-                                                        "●"
-                                                    } else {
-                                                        " "
-                                                    };
+                                                    let marker =
+                                                        if state.rate_limited_models.contains(m) {
+                                                            "✗"
+                                                        } else if *m == state.current_model {
+                                                            "●"
+                                                        } else {
+                                                            " "
+                                                        };
                                                     state.output.push(format!(
                                                         "  [{}] {}: {}",
                                                         marker,
@@ -2205,50 +3619,559 @@ async fn run_tui_loop(
                                                         m
                                                     ));
                                                 }
-                                                state.output.push(
-                                                    "Use /switch <name> or /switch 1-5".into(),
-                                                );
+                                                state.output.push(format!(
+                                                    "Use /switch <name> or /switch 1-{}",
+                                                    state.free_fallbacks.len()
+                                                ));
                                                 state.mark_dirty();
                                                 continue;
-                                            } else if result.output.starts_with("SWITCH_MODEL:") {
-                                                let target = result
-                                                    .output
-                                                    .trim_start_matches("SWITCH_MODEL:");
+                                            } else if let Some(SlashAction::SwitchModel(target)) =
+                                                &result.action
+                                            {
+                                                let target = target.as_str();
                                                 // Try to parse as number first
                                                 let new_model =
                                                     if let Ok(n) = target.parse::<usize>() {
-                                                        FREE_MODEL_FALLBACKS
+                                                        state
+                                                            .free_fallbacks
                                                             .get(n.saturating_sub(1))
-                                                            .map(|s| s.to_string())
+                                                            .cloned()
                                                     } else {
-                                                        // Find by partial match
-                                                        FREE_MODEL_FALLBACKS
+                                                        // Find by partial match, then configured upgrades
+                                                        state
+                                                            .free_fallbacks
                                                             .iter()
                                                             .find(|m| m.contains(target))
-                                                            .map(|s| s.to_string())
+                                                            .cloned()
+                                                            .or_else(|| {
+                                                                state
+                                                                    .upgrade_models
+                                                                    .iter()
+                                                                    .find(|m| m.contains(target))
+                                                                    .cloned()
+                                                            })
                                                     };
 
                                                 if let Some(model) = new_model {
-                                                    state.current_model = model.clone();
+                                                    let reason = if state.upgrade_offered.as_deref()
+                                                        == Some(model.as_str())
+                                                    {
+                                                        SwitchReason::Quality
+                                                    } else {
+                                                        SwitchReason::Manual
+                                                    };
+                                                    state.switch_model(&model, reason);
                                                     state.rate_limited_models.clear(); // Clear rate limits when manually switching
                                                     state.rate_limit_pending = false;
                                                     state.output.push(format!(
                                                         "[✓] Switched to: {}",
                                                         model
                                                     ));
-                                                    state.log(format!(
-                                                        "Model switched to: {}",
-                                                        model
+                                                } else {
+                                                    state.output.push(format!(
+                                                        "[✗] Unknown model: {}",
+                                                        target
                                                     ));
+                                                }
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::RetryLast(target)) =
+                                                &result.action
+                                            {
+                                                let target = target.as_deref().unwrap_or("");
+                                                if state.last_prompt.is_empty() {
+                                                    state
+                                                        .output
+                                                        .push("[✗] Nothing to retry yet".into());
+                                                    state.mark_dirty();
+                                                    continue;
+                                                }
+
+                                                let model = if target.is_empty() {
+                                                    Some(state.current_model.clone())
                                                 } else {
+                                                    let mut known = state.free_fallbacks.clone();
+                                                    known.extend(
+                                                        state.upgrade_models.iter().cloned(),
+                                                    );
+                                                    if let Ok(Some(cache)) =
+                                                        crate::models::load_cache()
+                                                    {
+                                                        known.extend(
+                                                            cache.models.into_iter().map(|m| m.id),
+                                                        );
+                                                    }
+                                                    crate::models::resolve_model(target, &known)
+                                                };
+
+                                                let Some(model) = model else {
                                                     state.output.push(format!(
                                                         "[✗] Unknown model: {}",
                                                         target
                                                     ));
+                                                    state.mark_dirty();
+                                                    continue;
+                                                };
+
+                                                if model != state.current_model {
+                                                    state
+                                                        .switch_model(&model, SwitchReason::Manual);
+                                                }
+                                                // Forget the failed reply so the retry starts clean
+                                                match session.drop_last_response() {
+                                                    Ok(n) if n > 0 => state.log(format!(
+                                                        "Dropped {} message(s) before retry",
+                                                        n
+                                                    )),
+                                                    Ok(_) => {}
+                                                    Err(e) => state
+                                                        .log(format!("Session save error: {}", e)),
                                                 }
+
+                                                state.current_response.clear();
+                                                state.advance_loop(LoopEvent::Prompt);
+                                                state.is_generating = true;
+                                                state.ttft = None;
+                                                state.request_start = std::time::Instant::now();
+                                                state.last_token_time = std::time::Instant::now();
+                                                state.pending_retry = true;
                                                 state.mark_dirty();
                                                 continue;
-                                            } else if result.output == "TOGGLE_AGENT_MODE" {
+                                            } else if let Some(SlashAction::Regenerate(
+                                                temperature,
+                                            )) = &result.action
+                                            {
+                                                if let Err(e) = regenerate_last(
+                                                    &mut state,
+                                                    &mut session,
+                                                    *temperature,
+                                                ) {
+                                                    state.output.push(format!("[✗] {}", e));
+                                                    state.mark_dirty();
+                                                }
+                                                continue;
+                                            } else if let Some(SlashAction::SetSampling {
+                                                key,
+                                                value,
+                                            }) = &result.action
+                                            {
+                                                if value.trim().is_empty() {
+                                                    state.output.push(format!(
+                                                        "[Sampling: {}]",
+                                                        state.sampling.display()
+                                                    ));
+                                                } else {
+                                                    match state.sampling.set(key, value) {
+                                                        Ok(()) => {
+                                                            state.output.push(format!(
+                                                                "[✓] Sampling: {}",
+                                                                state.sampling.display()
+                                                            ));
+                                                            state.log(format!(
+                                                                "Sampling {} = {}",
+                                                                key,
+                                                                value.trim()
+                                                            ));
+                                                        }
+                                                        Err(e) => {
+                                                            state.output.push(format!("[✗] {}", e))
+                                                        }
+                                                    }
+                                                }
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::SetContextStrategy(
+                                                spec,
+                                            )) = &result.action
+                                            {
+                                                if spec.is_empty() {
+                                                    state.output.push(format!(
+                                                        "[Context: {}]",
+                                                        state.context_strategy.label()
+                                                    ));
+                                                } else {
+                                                    match ContextStrategy::parse(spec) {
+                                                        Ok(strategy) => {
+                                                            state.context_strategy = strategy;
+                                                            state.output.push(format!(
+                                                                "[✓] Context: {}",
+                                                                strategy.label()
+                                                            ));
+                                                            state.log(format!(
+                                                                "Context strategy = {}",
+                                                                strategy.label()
+                                                            ));
+                                                        }
+                                                        Err(e) => {
+                                                            state.output.push(format!("[✗] {}", e))
+                                                        }
+                                                    }
+                                                }
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::ResumeSession(id)) =
+                                                &result.action
+                                            {
+                                                if let Err(e) =
+                                                    switch_session(&mut state, &mut session, id)
+                                                {
+                                                    state.output.push(format!("[✗] {}", e));
+                                                }
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if result.action
+                                                == Some(SlashAction::ForkSession)
+                                            {
+                                                if state.is_generating || state.executing_tools {
+                                                    state.output.push(
+                                                        "[✗] Wait for the current response to finish before forking".into(),
+                                                    );
+                                                    state.mark_dirty();
+                                                    continue;
+                                                }
+                                                match session.fork() {
+                                                    Ok(fork) => {
+                                                        let parent = session.meta.id.clone();
+                                                        if let Err(e) = session.save_meta() {
+                                                            state.log(format!(
+                                                                "Session save error: {}",
+                                                                e
+                                                            ));
+                                                        }
+                                                        session = fork;
+                                                        state.abandon_compaction();
+                                                        state.output.push(format!(
+                                                            "[✓] Forked {} → {} (original unchanged; /resume {} to go back)",
+                                                            parent, session.meta.id, parent
+                                                        ));
+                                                        state.refresh_sessions();
+                                                    }
+                                                    Err(e) => state
+                                                        .output
+                                                        .push(format!("[✗] Fork failed: {}", e)),
+                                                }
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::Attach(paths)) =
+                                                &result.action
+                                            {
+                                                for path in paths {
+                                                    match session.attach(path) {
+                                                        Ok(true) => state
+                                                            .output
+                                                            .push(format!("[✓] Attached {}", path)),
+                                                        Ok(false) => state.output.push(format!(
+                                                            "[Already attached: {}]",
+                                                            path
+                                                        )),
+                                                        Err(e) => state.output.push(format!(
+                                                            "[✗] Attach {}: {}",
+                                                            path, e
+                                                        )),
+                                                    }
+                                                }
+                                                state.attachments =
+                                                    session.meta.attachments.clone();
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::Focus(files)) =
+                                                &result.action
+                                            {
+                                                match files {
+                                                    None if state.focus_gate.is_active() => {
+                                                        state.output.push(format!(
+                                                            "[Focus: {}]",
+                                                            state.focus_gate.files().join(", ")
+                                                        ))
+                                                    }
+                                                    None => state.output.push(
+                                                        "[No focus set; /focus <files> to set one]"
+                                                            .into(),
+                                                    ),
+                                                    Some(files) => match session.set_focus(files) {
+                                                        Ok(()) if files.is_empty() => {
+                                                            state.focus_gate = FocusGate::default();
+                                                            state
+                                                                .output
+                                                                .push("[✓] Focus cleared".into());
+                                                        }
+                                                        Ok(()) => {
+                                                            state.focus_gate =
+                                                                FocusGate::new(files);
+                                                            state.output.push(format!(
+                                                                "[✓] Focus: {}",
+                                                                files.join(", ")
+                                                            ));
+                                                        }
+                                                        Err(e) => state.output.push(format!(
+                                                            "[✗] Focus failed: {}",
+                                                            e
+                                                        )),
+                                                    },
+                                                }
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::Detach(paths)) =
+                                                &result.action
+                                            {
+                                                match session.detach(paths) {
+                                                    Ok(removed) if removed.is_empty() => state
+                                                        .output
+                                                        .push("[Nothing to detach]".into()),
+                                                    Ok(removed) => state.output.push(format!(
+                                                        "[✓] Detached {}",
+                                                        removed.join(", ")
+                                                    )),
+                                                    Err(e) => state
+                                                        .output
+                                                        .push(format!("[✗] Detach failed: {}", e)),
+                                                }
+                                                state.attachments =
+                                                    session.meta.attachments.clone();
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::Pin(note)) =
+                                                &result.action
+                                            {
+                                                match session.pin(note) {
+                                                    Ok(n) => state
+                                                        .output
+                                                        .push(format!("[📌 {}] {}", n, note)),
+                                                    Err(e) => state
+                                                        .output
+                                                        .push(format!("[✗] Pin failed: {}", e)),
+                                                }
+                                                state.pins = session.meta.pins.clone();
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::Unpin(number)) =
+                                                &result.action
+                                            {
+                                                match session.unpin(*number) {
+                                                    Ok(removed) if removed.is_empty() => {
+                                                        state.output.push("[No pins]".into())
+                                                    }
+                                                    Ok(removed) => {
+                                                        for note in removed {
+                                                            state.output.push(format!(
+                                                                "[✓] Unpinned {}",
+                                                                note
+                                                            ));
+                                                        }
+                                                    }
+                                                    Err(e) => {
+                                                        state.output.push(format!("[✗] {}", e))
+                                                    }
+                                                }
+                                                state.pins = session.meta.pins.clone();
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::PlanFirst(enable)) =
+                                                &result.action
+                                            {
+                                                let gate = &mut state.plan_gate;
+                                                gate.enabled = enable.unwrap_or(!gate.enabled);
+                                                let mode = if gate.enabled { "ON" } else { "OFF" };
+                                                state
+                                                    .output
+                                                    .push(format!("[Plan-first: {}]", mode));
+                                                if state.plan_gate.enabled {
+                                                    state.output.push(if state.trust_mode {
+                                                        "  Plans are approved automatically (trust mode)".into()
+                                                    } else {
+                                                        "  Writes, shell and git wait for a plan you approve".into()
+                                                    });
+                                                }
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if result.action == Some(SlashAction::ListPins) {
+                                                if state.pins.is_empty() {
+                                                    state.output.push(
+                                                        "[No pins - add one with /pin <note>]"
+                                                            .into(),
+                                                    );
+                                                }
+                                                for (i, note) in state.pins.iter().enumerate() {
+                                                    state.output.push(format!(
+                                                        "  📌 {}. {}",
+                                                        i + 1,
+                                                        note
+                                                    ));
+                                                }
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::Compare {
+                                                models,
+                                                prompt,
+                                            }) = &result.action
+                                            {
+                                                if !models.is_empty() {
+                                                    let history = state.assemble_history(
+                                                        &session.messages_for_api(),
+                                                    );
+                                                    spawn_comparison(
+                                                        &mut state,
+                                                        &tx,
+                                                        history,
+                                                        models.clone(),
+                                                        prompt.clone(),
+                                                    );
+                                                } else if state.compare.is_some() {
+                                                    state.push_view(View::Compare);
+                                                } else {
+                                                    state.output.push(
+                                                        "[No comparison yet - /compare <m1,m2> <prompt>]"
+                                                            .into(),
+                                                    );
+                                                }
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::RefreshModels {
+                                                force,
+                                            }) = result.action
+                                            {
+                                                state.output.push("[Reloading models...]".into());
+                                                let tx = tx.clone();
+                                                let api_key = state.api_key.clone();
+                                                tokio::spawn(async move {
+                                                    let result =
+                                                        crate::models::reload(&api_key, force)
+                                                            .await
+                                                            .map_err(|e| format!("{:#}", e));
+                                                    let _ = tx
+                                                        .send(TuiMsg::ModelsReloaded(result))
+                                                        .await;
+                                                });
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::RunCommand(command)) =
+                                                &result.action
+                                            {
+                                                state
+                                                    .output
+                                                    .push(format!("[Running {}...]", command));
+                                                let tx = tx.clone();
+                                                let command = command.clone();
+                                                tokio::spawn(async move {
+                                                    let capture =
+                                                        tokio::task::spawn_blocking(move || {
+                                                            CommandCapture::run(&command, None)
+                                                        })
+                                                        .await;
+                                                    if let Ok(capture) = capture {
+                                                        let _ =
+                                                            tx.send(TuiMsg::RunDone(capture)).await;
+                                                    }
+                                                });
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::Ingest(source)) =
+                                                &result.action
+                                            {
+                                                state
+                                                    .output
+                                                    .push(format!("[Ingesting {}...]", source));
+                                                let tx = tx.clone();
+                                                let source = source.clone();
+                                                tokio::spawn(async move {
+                                                    let result = crate::intake::ingest(&source)
+                                                        .await
+                                                        .map_err(|e| e.to_string());
+                                                    let _ = tx
+                                                        .send(TuiMsg::IngestDone { source, result })
+                                                        .await;
+                                                });
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if result.action
+                                                == Some(SlashAction::ClearConversation)
+                                            {
+                                                state.confirm_clear = true;
+                                                state.output.push(format!(
+                                                    "Clear {} messages from this session? (y/n)",
+                                                    session.messages.len().saturating_sub(1)
+                                                ));
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if result.action
+                                                == Some(SlashAction::CompactConversation)
+                                            {
+                                                spawn_compact(&mut state, &session, &tx);
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if let Some(SlashAction::AutoCompact(
+                                                threshold,
+                                            )) = &result.action
+                                            {
+                                                if let Some(threshold) = *threshold {
+                                                    state.auto_compact = AutoCompact::new(
+                                                        Some(threshold).filter(|&t| t > 0),
+                                                    );
+                                                }
+                                                state.output.push(
+                                                    match state.auto_compact.threshold {
+                                                        Some(t) => format!(
+                                                            "[Auto-compact: offered above {}% of the context window]",
+                                                            t
+                                                        ),
+                                                        None => "[Auto-compact: OFF]".into(),
+                                                    },
+                                                );
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if result.action == Some(SlashAction::Summarize)
+                                            {
+                                                let root = state
+                                                    .project
+                                                    .as_ref()
+                                                    .map(|p| p.root.clone())
+                                                    .or_else(|| std::env::current_dir().ok())
+                                                    .unwrap_or_default();
+                                                state.output.push(format!(
+                                                    "[Summarizing {}...]",
+                                                    root.display()
+                                                ));
+                                                let tx = tx.clone();
+                                                let api_key = state.api_key.clone();
+                                                let model = state.current_model.clone();
+                                                tokio::spawn(async move {
+                                                    let result = crate::overview::summarize(
+                                                        &root,
+                                                        |prompt| async move {
+                                                            client::chat_completion_simple(
+                                                                &api_key, &model, &prompt, 2048,
+                                                            )
+                                                            .await
+                                                        },
+                                                    )
+                                                    .await
+                                                    .map_err(|e| e.to_string());
+                                                    let _ = tx
+                                                        .send(TuiMsg::SummarizeDone(result))
+                                                        .await;
+                                                });
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if result.action
+                                                == Some(SlashAction::SelfImprove)
+                                            {
+                                                state.output.push(
+                                                    "[Analyzing codebase for the top fix...]"
+                                                        .into(),
+                                                );
+                                                let tx = tx.clone();
+                                                let api_key = state.api_key.clone();
+                                                let model = state.current_model.clone();
+                                                tokio::spawn(async move {
+                                                    let msg =
+                                                        propose_improvement(&api_key, &model).await;
+                                                    let _ = tx.send(msg).await;
+                                                });
+                                                state.mark_dirty();
+                                                continue;
+                                            } else if result.action
+                                                == Some(SlashAction::ToggleAgent)
+                                            {
                                                 state.agent_mode = !state.agent_mode;
                                                 let mode =
                                                     if state.agent_mode { "ON" } else { "OFF" };
@@ -2262,27 +4185,38 @@ async fn run_tui_loop(
                                                 }
                                                 state.mark_dirty();
                                                 continue;
+                                            } else if let Some(SlashAction::SendPrompt(text)) =
+                                                &result.action
+                                            {
+                                                // Expands into a model prompt: fall through
+                                                // to the send path below
+                                                state.output.push(format!("[{}]", result.output));
+                                                prompt = text.clone();
+                                            } else {
+                                                let status =
+                                                    if result.success { "✓" } else { "✗" };
+                                                state
+                                                    .output
+                                                    .push(format!("[{}] {}", status, prompt));
+                                                for line in result.output.lines().take(50) {
+                                                    state.output.push(format!("  {}", line));
+                                                }
+                                                if result.output.lines().count() > 50 {
+                                                    state.output.push("  ... (truncated)".into());
+                                                }
+                                                state.mark_dirty();
+                                                state.log(format!(
+                                                    "Slash: {} -> {}",
+                                                    prompt,
+                                                    if result.success { "ok" } else { "failed" }
+                                                ));
+                                                continue;
                                             }
-
-                                            let status = if result.success { "✓" } else { "✗" };
-                                            state.output.push(format!("[{}] {}", status, prompt));
-                                            for line in result.output.lines().take(50) {
-                                                state.output.push(format!("  {}", line));
-                                            }
-                                            if result.output.lines().count() > 50 {
-                                                state.output.push("  ... (truncated)".into());
-                                            }
-                                            state.mark_dirty();
-                                            state.log(format!(
-                                                "Slash: {} -> {}",
-                                                prompt,
-                                                if result.success { "ok" } else { "failed" }
-                                            ));
-                                            continue;
                                         }
                                         // Unknown slash command falls through to LLM
                                     }
 
+                                    let mentioned = state.read_mentions(&prompt);
                                     state.output.push(String::new()); // For response
                                     state.is_generating = true;
                                     state.ttft = None;
@@ -2301,7 +4235,7 @@ async fn run_tui_loop(
 
                                     // Update intent tracking
                                     state.update_intent_from_prompt(&prompt);
-                                    state.loop_iteration = 0; // New prompt resets loop counter
+                                    state.advance_loop(LoopEvent::Prompt); // New prompt resets loop counter
                                     state.stuck_detector.clear(); // Clear stuck detection for new task
 
                                     // Spawn API call with session history
@@ -2309,15 +4243,21 @@ async fn run_tui_loop(
                                     let api_key = state.api_key.clone();
                                     let model = state.current_model.clone(); // Use state model, can switch on rate limit
                                     let project_clone = state.project.clone();
-                                    let history = session.messages_for_api();
+                                    let history = mentioned
+                                        .apply(state.assemble_history(&session.messages_for_api()));
+                                    if !state.preflight_context(&prompt, &history) {
+                                        continue;
+                                    }
 
+                                    let params = state.sampling.clone();
                                     tokio::spawn(async move {
-                                        match client::stream_completion_full(
+                                        match client::stream_completion_with_params(
                                             &api_key,
                                             &model,
                                             &prompt,
                                             project_clone.as_ref(),
                                             &history,
+                                            params,
                                         )
                                         .await
                                         {
@@ -2351,6 +4291,17 @@ async fn run_tui_loop(
                             {
                                 state.cursor_pos = 0;
                             }
+                            // Ctrl-R: regenerate the last reply
+                            KeyCode::Char('r')
+                                if key
+                                    .modifiers
+                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                            {
+                                if let Err(e) = regenerate_last(&mut state, &mut session, None) {
+                                    state.output.push(format!("[✗] {}", e));
+                                    state.mark_dirty();
+                                }
+                            }
                             // Readline: Ctrl-E = jump to end
                             KeyCode::Char('e')
                                 if key
@@ -2388,6 +4339,22 @@ async fn run_tui_loop(
                             KeyCode::Home => {
                                 state.cursor_pos = 0;
                             }
+                            // One-key answer to the auto-compact prompt
+                            KeyCode::Char(c @ ('y' | 'n'))
+                                if state.confirm_compact
+                                    && state.input.is_empty()
+                                    && !state.is_generating =>
+                            {
+                                state.confirm_compact = false;
+                                if c == 'y' {
+                                    spawn_compact(&mut state, &session, &tx);
+                                } else {
+                                    state
+                                        .output
+                                        .push("[Not compacting; /compact any time]".into());
+                                }
+                                state.mark_dirty();
+                            }
                             // Insert character at cursor position
                             KeyCode::Char(c) => {
                                 state.input.insert(state.cursor_pos, c);
@@ -2456,48 +4423,24 @@ async fn run_tui_loop(
                             }
                             KeyCode::Enter => {
                                 // Restore selected session
-                                if let Some(session) =
+                                if let Some(detected) =
                                     state.detected_sessions.get(state.session_selected).cloned()
                                 {
-                                    match session.tool.as_str() {
+                                    match detected.tool.as_str() {
                                         "hyle" => {
-                                            // Restore hyle session by loading and displaying its contents
-                                            match crate::session::Session::load(&session.id) {
-                                                Ok(loaded) => {
-                                                    // Add session messages to output display
-                                                    state.output.push(format!(
-                                                        "\n─── Restored session {} ───",
-                                                        session.id
-                                                    ));
-                                                    for msg in &loaded.messages {
-                                                        let prefix = if msg.role == "user" {
-                                                            "You"
-                                                        } else {
-                                                            "AI"
-                                                        };
-                                                        state.output.push(format!(
-                                                            "[{}] {}",
-                                                            prefix, msg.content
-                                                        ));
-                                                    }
-                                                    state.output.push(
-                                                        "─── End of restored session ───\n".into(),
-                                                    );
-                                                    state.output_dirty = true;
-                                                    state.log(format!(
-                                                        "Restored {} messages from session {}",
-                                                        loaded.messages.len(),
-                                                        session.id
-                                                    ));
-                                                    state.tab = View::Chat;
-                                                }
-                                                Err(e) => {
-                                                    state.log(format!(
-                                                        "Failed to load session: {}",
-                                                        e
-                                                    ));
-                                                }
+                                            // Continue in the selected session
+                                            match switch_session(
+                                                &mut state,
+                                                &mut session,
+                                                &detected.id,
+                                            ) {
+                                                Ok(()) => state.tab = View::Chat,
+                                                Err(e) => state.log(format!(
+                                                    "Failed to switch session: {}",
+                                                    e
+                                                )),
                                             }
+                                            state.refresh_sessions();
                                         }
                                         "claude-code" => {
                                             // Import Claude Code context from current directory
@@ -2536,7 +4479,7 @@ async fn run_tui_loop(
                                         _ => {
                                             state.log(format!(
                                                 "Cannot restore {} session (read-only)",
-                                                session.tool
+                                                detected.tool
                                             ));
                                         }
                                     }
@@ -2562,7 +4505,12 @@ async fn run_tui_loop(
         }
     }
 
-    Ok(())
+    // Fold this session's response quality into the model leaderboard
+    if let Ok(path) = Leaderboard::path() {
+        let _ = Leaderboard::persist_session(&state.model_tracker, &path);
+    }
+
+    Ok(state.exit_summary(&session))
 }
 
 fn render_tui(f: &mut Frame, state: &TuiState) {
@@ -2603,12 +4551,13 @@ fn render_tui(f: &mut Frame, state: &TuiState) {
 
     // Context usage indicator
     let context_pct = state.traces.context.usage.last().unwrap_or(0.0);
+    let strategy = state.context_strategy.label();
     let context_indicator = if state.traces.context.is_full() {
-        " | CTX:FULL".to_string()
+        format!(" | CTX:FULL {}", strategy)
     } else if context_pct > 0.0 {
-        format!(" | CTX:{:.0}%", context_pct)
+        format!(" | CTX:{:.0}% {}", context_pct, strategy)
     } else {
-        String::new()
+        format!(" | CTX {}", strategy)
     };
 
     // Agent mode indicator
@@ -2620,6 +4569,40 @@ fn render_tui(f: &mut Frame, state: &TuiState) {
         ""
     };
 
+    // Attached files: names when few, a count otherwise
+    let attach_indicator = match state.attachments.len() {
+        0 => String::new(),
+        1..=3 => {
+            let names: Vec<&str> = state
+                .attachments
+                .iter()
+                .map(|p| p.rsplit('/').next().unwrap_or(p))
+                .collect();
+            format!(" | 📎 {}", names.join(", "))
+        }
+        n => format!(" | 📎 {} files", n),
+    };
+    let pin_indicator = match state.pins.len() {
+        0 => String::new(),
+        n => format!(" | 📌 {}", n),
+    };
+    let focus_indicator = match state.focus_gate.files().len() {
+        0 => String::new(),
+        1..=3 => {
+            let names: Vec<String> = state
+                .focus_gate
+                .files()
+                .iter()
+                .map(|p| match p.strip_suffix('/') {
+                    Some(dir) => format!("{}/", dir.rsplit('/').next().unwrap_or(dir)),
+                    None => p.rsplit('/').next().unwrap_or(p).to_string(),
+                })
+                .collect();
+            format!(" | 🎯 {}", names.join(", "))
+        }
+        n => format!(" | 🎯 {} paths", n),
+    };
+
     let header_title = if exit_warning {
         format!(
             "hyle | {} | ⚠ Press Ctrl-C again to quit{}",
@@ -2642,8 +4625,14 @@ fn render_tui(f: &mut Frame, state: &TuiState) {
         )
     } else {
         format!(
-            "hyle | {}{}{}{}",
-            model_display, context_indicator, agent_indicator, nav_hint
+            "hyle | {}{}{}{}{}{}{}",
+            model_display,
+            context_indicator,
+            agent_indicator,
+            attach_indicator,
+            pin_indicator,
+            focus_indicator,
+            nav_hint
         )
     };
 
@@ -2692,6 +4681,7 @@ fn render_tui(f: &mut Frame, state: &TuiState) {
         View::Git => render_git(f, state, chunks[1]),
         View::Artifacts => render_artifacts(f, state, chunks[1]),
         View::Plans => render_plans(f, state, chunks[1]),
+        View::Compare => render_compare(f, state, chunks[1]),
     }
 
     // Input
@@ -2712,12 +4702,33 @@ fn render_tui(f: &mut Frame, state: &TuiState) {
         } else {
             "Waiting for first token...".into()
         }
+    } else if let Some(q) = state.pending_question.as_ref().filter(|q| !q.is_answered()) {
+        format!("Answer: {} (Enter send)", q.question)
     } else {
         "Input (↑↓ history, Enter send)".into()
     };
+    // Context budget of the request the draft would send
+    let gauge = state
+        .context_meter
+        .gauge(&state.traces.context, &state.input);
+    let gauge_style = match gauge.budget {
+        ContextBudget::Ok => Style::default().fg(Color::DarkGray),
+        ContextBudget::Warning => Style::default().fg(Color::Yellow),
+        ContextBudget::Over => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+    };
+    let input_block = Block::default()
+        .borders(Borders::ALL)
+        .title(input_title)
+        .title(
+            Title::from(Span::styled(
+                format!(" ctx {} ", gauge.label()),
+                gauge_style,
+            ))
+            .alignment(Alignment::Right),
+        );
     let input = Paragraph::new(state.input.as_str())
         .style(input_style)
-        .block(Block::default().borders(Borders::ALL).title(input_title));
+        .block(input_block);
     f.render_widget(input, chunks[2]);
 
     // Position cursor in input field (account for border)
@@ -2769,13 +4780,21 @@ fn render_tui(f: &mut Frame, state: &TuiState) {
         " ".to_string()
     };
 
+    // Auto-compact: the threshold, or what happens once it's crossed
+    let compact_str = state
+        .auto_compact
+        .status(state.compact_decision)
+        .map(|s| format!(" | {}", s))
+        .unwrap_or_default();
+
     let status = format!(
-        " {} | {} {} | {}{} | {}",
+        " {} | {} {} | {}{}{} | {}",
         gen_status,
         sparkline,
         pressure.symbol(),
         state.throttle.name(),
         cost_str,
+        compact_str,
         help,
     );
 
@@ -2801,13 +4820,12 @@ fn render_chat(f: &mut Frame, state: &TuiState, area: Rect) {
     let line_count = state.output_line_count as u16;
 
     // Calculate scroll position
-    let scroll = if state.auto_scroll {
-        line_count.saturating_sub(visible_height)
-    } else {
-        state
-            .scroll_offset
-            .min(line_count.saturating_sub(visible_height))
-    };
+    let scroll = minimap::clamp_scroll(
+        state.scroll_offset,
+        visible_height,
+        line_count,
+        state.auto_scroll,
+    );
 
     // Build title with scroll indicator
     let scroll_indicator = if line_count > visible_height {
@@ -2857,6 +4875,50 @@ fn render_chat(f: &mut Frame, state: &TuiState, area: Rect) {
         .scroll((scroll, 0))
         .block(Block::default().borders(Borders::ALL).title(title));
     f.render_widget(para, area);
+
+    if line_count > visible_height {
+        render_chat_minimap(f, state, area, scroll, visible_height);
+    }
+}
+
+/// Scrollbar over the chat's right border, with search matches and the
+/// last response marked in the gutter
+fn render_chat_minimap(f: &mut Frame, state: &TuiState, area: Rect, scroll: u16, visible: u16) {
+    let track = Rect::new(area.x, area.y + 1, area.width, visible);
+    let total = state.output_line_count;
+
+    let mut scrollbar_state = ScrollbarState::new(total.saturating_sub(visible as usize))
+        .position(scroll as usize)
+        .viewport_content_length(visible as usize);
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None);
+    f.render_stateful_widget(scrollbar, track, &mut scrollbar_state);
+
+    let current = (!state.search_matches.is_empty()).then_some(state.search_current);
+    let x = area.x + area.width.saturating_sub(1);
+    for (row, mark) in minimap::marks(
+        &state.search_matches,
+        current,
+        state.last_response_line,
+        total,
+        visible,
+    ) {
+        let (symbol, style) = match mark {
+            Mark::Response => ("┃", Style::default().fg(Color::Cyan)),
+            Mark::Search => ("•", Style::default().fg(Color::Yellow)),
+            Mark::CurrentMatch => (
+                "◆",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        };
+        f.buffer_mut()
+            .get_mut(x, track.y + row)
+            .set_symbol(symbol)
+            .set_style(style);
+    }
 }
 
 fn format_bytes(n: u64) -> String {
@@ -2945,6 +5007,20 @@ fn render_telemetry(f: &mut Frame, state: &TuiState, area: Rect) {
         state.traces.tokens.total_completion,
         state.traces.tokens.total()
     ));
+    if state.traces.tokens.total_cache_read + state.traces.tokens.total_cache_write > 0 {
+        lines.push(format!(
+            "Prompt cache: {} read, {} written ({:.0}% hit)",
+            state.traces.tokens.total_cache_read,
+            state.traces.tokens.total_cache_write,
+            state.traces.tokens.cache_hit_ratio() * 100.0
+        ));
+    }
+    if state.traces.tokens.total_discarded > 0 {
+        lines.push(format!(
+            "Discarded by /regenerate: {} completion tokens",
+            state.traces.tokens.total_discarded
+        ));
+    }
 
     if let Some(ttft) = state.ttft {
         lines.push(format!("Last TTFT: {}ms", ttft.as_millis()));
@@ -2967,6 +5043,37 @@ fn render_telemetry(f: &mut Frame, state: &TuiState, area: Rect) {
         ));
     }
 
+    // Tool usage this session, from the live tracker
+    let tool_metrics = state.tool_tracker.metrics();
+    if !tool_metrics.is_empty() {
+        lines.push(String::new());
+        lines.push("── Tools ──".into());
+        lines.extend(tool_metrics.iter().map(|m| m.render()));
+    }
+
+    let channel = &state.channel_stats;
+    if channel.received > 0 {
+        lines.push(String::new());
+        lines.push("── UI channel ──".into());
+        lines.push(format!(
+            "{} messages, {} merged, backlog {} (peak {}), {} lagged frames",
+            channel.received,
+            channel.coalesced,
+            channel.backlog,
+            channel.max_backlog,
+            channel.lagged_frames
+        ));
+    }
+
+    // Your own quality leaderboard, this session included
+    let mut board = state.leaderboard.clone();
+    board.merge(&Leaderboard::from_tracker(&state.model_tracker));
+    if !board.models.is_empty() {
+        lines.push(String::new());
+        lines.push("── Models (observed quality) ──".into());
+        lines.extend(board.render().into_iter().take(10));
+    }
+
     let para = Paragraph::new(lines.join("\n"))
         .block(Block::default().borders(Borders::ALL).title("Telemetry"));
     f.render_widget(para, area);
@@ -2985,6 +5092,13 @@ fn render_log(f: &mut Frame, state: &TuiState, area: Rect) {
     f.render_widget(para, area);
 }
 
+fn fork_suffix(s: &DetectedSession) -> String {
+    s.parent
+        .as_ref()
+        .map(|p| format!(" ↳ fork of {}", p))
+        .unwrap_or_default()
+}
+
 fn render_sessions(f: &mut Frame, state: &TuiState, area: Rect) {
     let mut lines = vec![
         "Sessions (↑↓:select Enter:restore r:refresh v:details)".into(),
@@ -3040,8 +5154,16 @@ fn render_sessions(f: &mut Frame, state: &TuiState, area: Rect) {
                     Integration::ReadOnly => "○",
                 };
                 lines.push(format!(
-                    "{} {} {} {} | {}msg {}tok | {} {}",
-                    marker, status_icon, s.tool, s.id, s.messages, s.tokens, s.age, int_icon
+                    "{} {} {} {} | {}msg {}tok | {} {}{}",
+                    marker,
+                    status_icon,
+                    s.tool,
+                    s.id,
+                    s.messages,
+                    s.tokens,
+                    s.age,
+                    int_icon,
+                    fork_suffix(s)
                 ));
             }
             lines.push("".into());
@@ -3057,8 +5179,14 @@ fn render_sessions(f: &mut Frame, state: &TuiState, area: Rect) {
                     " "
                 };
                 lines.push(format!(
-                    "{} ○ {} {} | {}msg {}tok | {}",
-                    marker, s.tool, s.id, s.messages, s.tokens, s.age
+                    "{} ○ {} {} | {}msg {}tok | {}{}",
+                    marker,
+                    s.tool,
+                    s.id,
+                    s.messages,
+                    s.tokens,
+                    s.age,
+                    fork_suffix(s)
                 ));
             }
             lines.push("".into());
@@ -3190,6 +5318,72 @@ fn render_plans(f: &mut Frame, state: &TuiState, area: Rect) {
     f.render_widget(para, area);
 }
 
+fn render_compare(f: &mut Frame, state: &TuiState, area: Rect) {
+    let Some(live) = state.compare.as_ref() else {
+        let para = Paragraph::new("No comparison yet.\n\n/compare <m1,m2[,m3]> <prompt>").block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Compare (Esc to close)"),
+        );
+        f.render_widget(para, area);
+        return;
+    };
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(4)])
+        .split(area);
+    let hint = if live.is_done() {
+        "1-3 to continue with a response, Esc to close"
+    } else {
+        "streaming..., Esc to close"
+    };
+    let prompt = Paragraph::new(live.prompt.as_str())
+        .style(Style::default().fg(Color::DarkGray))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Compare ({})", hint)),
+        );
+    f.render_widget(prompt, rows[0]);
+
+    let views = live.layout(rows[1].width as usize);
+    let cols = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            views
+                .iter()
+                .map(|_| Constraint::Ratio(1, views.len() as u32))
+                .collect::<Vec<_>>(),
+        )
+        .split(rows[1]);
+    // Borders and the status line
+    let body_height = rows[1].height.saturating_sub(3) as usize;
+    for (view, col_area) in views.iter().zip(cols.iter()) {
+        let status_style = if view.status.starts_with('✗') {
+            Style::default().fg(Color::Red)
+        } else if view.status.starts_with('✓') {
+            Style::default().fg(Color::Green)
+        } else {
+            Style::default().fg(Color::Yellow)
+        };
+        // Follow the tail while streaming
+        let skip = if live.is_done() {
+            0
+        } else {
+            view.lines.len().saturating_sub(body_height)
+        };
+        let mut lines = vec![Line::from(Span::styled(view.status.clone(), status_style))];
+        lines.extend(view.lines.iter().skip(skip).map(|l| Line::from(l.as_str())));
+        let para = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(view.title.as_str()),
+        );
+        f.render_widget(para, *col_area);
+    }
+}
+
 fn spinner_char(tick: usize) -> char {
     const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
     SPINNER[tick % SPINNER.len()]